@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use crate::language::Language;
+
+// --- Abbreviations ---
+
+// Short text replaced as soon as a terminator is typed after it: "@@" by an
+// email address, "addr" by a postal address. Unlike snippets there is no
+// placeholder to fill, the expansion is inserted as is.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Abbreviation {
+    pub abbreviation: String,
+    pub expansion: String,
+    // Only in documents of this language; every document when unset
+    pub language: Option<Language>,
+}
+
+impl Abbreviation {
+    // Text inserted: typed on one line in the settings, with \n for the line breaks
+    pub fn text(&self) -> String {
+        self.expansion.replace("\\n", "\n")
+    }
+}
+
+// Characters ending an abbreviation: spaces, line breaks and punctuation
+pub fn is_terminator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | ')' | ']' | '}')
+}
+
+// Abbreviation ending `before` (the text left of the cursor) in a document of
+// `language`. It must start a word, so "maddr" is left alone, and the entries
+// of the language win over the ones of every document.
+pub fn find<'a>(list: &'a [Abbreviation], language: Language, before: &str) -> Option<&'a Abbreviation> {
+    list.iter()
+        .filter(|a| !a.abbreviation.is_empty() && a.language.is_none_or(|l| l == language))
+        .filter(|a| {
+            before.strip_suffix(a.abbreviation.as_str()).is_some_and(|rest| {
+                rest.chars().next_back().is_none_or(|c| !c.is_alphanumeric())
+            })
+        })
+        .max_by_key(|a| (a.language.is_some(), a.abbreviation.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abbreviation(abbreviation: &str, expansion: &str, language: Option<Language>) -> Abbreviation {
+        Abbreviation {
+            abbreviation: abbreviation.to_string(),
+            expansion: expansion.to_string(),
+            language,
+        }
+    }
+
+    #[test]
+    fn abbreviation_must_start_a_word() {
+        let list = [
+            abbreviation("@@", "lea@example.org", None),
+            abbreviation("addr", "1 rue de la Paix", None),
+            abbreviation("addr", "## Adresse", Some(Language::Markdown)),
+        ];
+        let found = |language, before| find(&list, language, before).map(|a| a.expansion.as_str());
+        assert_eq!(found(Language::PlainText, "écrire à @@"), Some("lea@example.org"));
+        assert_eq!(found(Language::PlainText, "(addr"), Some("1 rue de la Paix"));
+        assert_eq!(found(Language::Markdown, "addr"), Some("## Adresse"));
+        assert_eq!(found(Language::PlainText, "maddr"), None);
+        assert_eq!(found(Language::PlainText, "add"), None);
+        assert_eq!(abbreviation("addr", "1 rue\\n75000 Paris", None).text(), "1 rue\n75000 Paris");
+        assert!(!is_terminator('a') && is_terminator(' ') && is_terminator('.'));
+    }
+}
@@ -2,9 +2,45 @@ use iced::widget::{text_editor, Id};
 use iced::{Event, Subscription, Task, Theme};
 use std::collections::VecDeque;
 use std::path::PathBuf;
-use std::time::{Duration, Instant};
-
-use crate::preferences::{SessionData, UserPreferences};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::abbreviations::Abbreviation;
+use crate::archive::{self, Archive};
+use crate::buffer::TextMirror;
+use crate::color::{ColorLiteral, Rgb};
+use crate::completion::PathEntry;
+use crate::dictionary::Entry;
+use crate::datetime;
+use crate::dpi;
+use crate::editorconfig::DocSettings;
+use crate::export::ExportFormat;
+use crate::hibernate::{self, HibernatedTab, Hibernation, MemoryUsage, SavedSnapshot};
+use crate::jobs::{self, JobId, Jobs};
+use crate::http::{ProxyMode, ProxySettings};
+use crate::language::Language;
+use crate::lock::{DocLock, LockState};
+use crate::markdown::{self, Heading};
+use crate::merge::{HunkChoice, Merge};
+use crate::metadata::MetadataStore;
+use crate::changes::{self, LineChange, CHANGE_MARKS_MAX_BYTES};
+use crate::navigation::{Bookmarks, EditSpots, NavHistory, NavPoint};
+use crate::notes::{NoteHit, NoteIndex};
+use crate::opacity::{self, MIN_WINDOW_OPACITY};
+use crate::paths;
+use crate::toast::Toasts;
+use crate::updates::{Release, UpdateCheck};
+use crate::preferences::{EscapeOrder, NamedSessions, RecentFiles, SessionData, UrlPaste, UserPreferences};
+use crate::print::{PageSetup, Pagination};
+use crate::qr::Qr;
+use crate::reflow;
+use crate::ink;
+use crate::search_index::SearchIndex;
+use crate::shortcuts::{Action, Keymap};
+use crate::snippets::Snippet;
+use crate::spelling::Speller;
+use crate::syntax::{self, SyntaxError, SYNTAX_CHECK_IDLE_MS};
+use crate::terminal::Terminal;
 use crate::{
     DEFAULT_FONT_SIZE, DEFAULT_WINDOW_HEIGHT, DEFAULT_WINDOW_WIDTH, MAX_FONT_SIZE, MIN_FONT_SIZE,
 };
@@ -14,14 +50,101 @@ pub const LARGE_FILE_UNDO_HISTORY: usize = 20;
 pub const UNDO_BATCH_TIMEOUT_MS: u128 = 300;
 pub const FILE_SIZE_WARN_MB: u64 = 50;
 pub const FILE_SIZE_LIMIT_MB: u64 = 500;
+// Files at least this big are read / written by a background job
+pub const BACKGROUND_IO_THRESHOLD_BYTES: u64 = 4 * 1024 * 1024;
 pub const MENU_BAR_HEIGHT: f32 = 30.0;
 pub const TAB_BAR_HEIGHT: f32 = 32.0;
+pub const TOOLBAR_HEIGHT: f32 = 30.0;
+pub const TERMINAL_PANEL_HEIGHT: f32 = 200.0;
+pub const BREADCRUMB_HEIGHT: f32 = 24.0;
+pub const TAB_FONT_SIZE: f32 = 11.0;
+pub const TAB_STRIP_RESERVED_WIDTH: f32 = 110.0;
 pub const MENU_ITEM_WIDTH: f32 = 220.0;
+pub const DEFAULT_INDENT_SIZE: usize = 4;
+pub const INDENT_DETECT_MAX_LINES: usize = 1000;
+pub const INDENT_SIZE_CHOICES: &[usize] = &[2, 4, 8];
+pub const AUTOSAVE_INTERVAL_SECS: u64 = 30;
+pub const SESSION_AUTOSAVE_SECS: u64 = 60;
+// Without input for this long, the session is no longer written on a timer
+// and the files on shares are checked once a minute instead of every 5 seconds
+pub const IDLE_AFTER_SECS: u64 = 10 * 60;
+pub const NETWORK_CHECK_SECS: u64 = 5;
+pub const IDLE_NETWORK_CHECK_SECS: u64 = 60;
+// Low power mode: stats, toasts and the syntax check refreshed at most once a second
+pub const LOW_POWER_REFRESH_MS: u64 = 1000;
+pub const NETWORK_AUTOSAVE_CHOICES: &[u64] = &[1, 5, 15];
+// Saves to a share slower than this (in milliseconds) switch it to queued
+// background saves; 0 turns the protection off
+pub const SLOW_SAVE_CHOICES_MS: &[u64] = &[0, 500, 1000, 2000];
+// Characters listed by the Save As warning about an encoding that lacks them
+pub const UNMAPPABLE_SHOWN: usize = 8;
 
 pub fn find_input_id() -> Id {
     Id::new("find_input")
 }
 
+pub fn editor_id() -> Id {
+    Id::new("editor")
+}
+
+// Editor of the split pane without focus
+pub fn split_editor_id() -> Id {
+    Id::new("split-editor")
+}
+
+pub fn terminal_input_id() -> Id {
+    Id::new("terminal_input")
+}
+
+pub fn snapshot_name_input_id() -> Id {
+    Id::new("snapshot_name_input")
+}
+
+pub fn shortcut_input_id() -> Id {
+    Id::new("shortcut_input")
+}
+
+pub fn tags_input_id() -> Id {
+    Id::new("tags_input")
+}
+
+pub fn note_search_input_id() -> Id {
+    Id::new("note_search_input")
+}
+
+pub fn filter_command_input_id() -> Id {
+    Id::new("filter_command_input")
+}
+
+pub fn wrap_width_input_id() -> Id {
+    Id::new("wrap_width_input")
+}
+
+pub fn macro_count_input_id() -> Id {
+    Id::new("macro_count_input")
+}
+
+pub fn char_search_input_id() -> Id {
+    Id::new("char_search_input")
+}
+
+pub fn folder_filter_input_id() -> Id {
+    Id::new("folder_filter_input")
+}
+
+pub fn session_name_input_id() -> Id {
+    Id::new("session_name_input")
+}
+
+pub fn template_name_input_id() -> Id {
+    Id::new("template_name_input")
+}
+
+// First variable of the template being instantiated
+pub fn template_value_input_id() -> Id {
+    Id::new("template_value_input")
+}
+
 pub fn replace_input_id() -> Id {
     Id::new("replace_input")
 }
@@ -30,8 +153,60 @@ pub fn goto_input_id() -> Id {
     Id::new("goto_input")
 }
 
+// Approximate rendered width of a tab (label + paddings + close button)
+pub fn tab_label_width(label: &str) -> f32 {
+    label.chars().count() as f32 * TAB_FONT_SIZE * 0.6 + 31.0
+}
+
+// "gz" / "zip" shown after the name of compressed files
+pub const TAB_BADGE_FONT_SIZE: f32 = TAB_FONT_SIZE - 2.0;
+
+pub fn tab_badge_width(badge: &str) -> f32 {
+    badge.chars().count() as f32 * TAB_BADGE_FONT_SIZE * 0.6 + 10.0
+}
+
+// Smallest change to `start` that keeps `active` inside the visible strip
+pub fn visible_tab_start(widths: &[f32], start: usize, active: usize, available: f32) -> usize {
+    if widths.iter().sum::<f32>() <= available {
+        return 0;
+    }
+    let mut start = start.min(widths.len().saturating_sub(1));
+    if active < start {
+        return active;
+    }
+    while start < active && widths[start..=active].iter().sum::<f32>() > available {
+        start += 1;
+    }
+    start
+}
+
+// Stable identity of a document, independent of its tab index
+fn next_document_id() -> u64 {
+    static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+    NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+// Cursor (column in characters) and scroll, restored once the editor is laid out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewState {
+    pub line: usize,
+    pub column: usize,
+    pub scroll: f32,
+}
+
 pub struct TextSnapshot {
-    pub text: String,
+    pub text: Arc<str>,
+    pub cursor_line: usize,
+    pub cursor_col: usize,
+}
+
+// Checkpoint named by the user, kept in the app (not in the file) until the tab closes
+pub struct NamedSnapshot {
+    pub name: String,
+    // Local time it was taken, as displayed
+    pub taken: String,
+    pub text: Arc<str>,
+    pub lines: usize,
     pub cursor_line: usize,
     pub cursor_col: usize,
 }
@@ -39,25 +214,96 @@ pub struct TextSnapshot {
 // --- Per-document state ---
 
 pub struct Document {
+    pub id: u64,
     pub content: text_editor::Content,
+    // Rope copy of `content`: edits go through `perform` / `set_text` to keep it in sync
+    pub mirror: TextMirror,
     pub file_path: Option<PathBuf>,
     pub is_modified: bool,
     pub undo_stack: VecDeque<TextSnapshot>,
     pub redo_stack: Vec<TextSnapshot>,
+    pub snapshots: Vec<NamedSnapshot>,
     pub last_edit_time: Option<Instant>,
     pub line_ending: LineEnding,
     pub encoding: &'static encoding_rs::Encoding,
+    // UTF-8 file starting with a byte order mark (UTF-16 always has one)
+    pub bom: bool,
     pub scroll_offset: f32,
     pub status_message: Option<String>,
     pub max_undo: usize,
 
-    // Cached stats (updated on edit, not every frame)
+    // Indentation (Tab key behavior)
+    pub indent_style: IndentStyle,
+    pub indent_size: usize,
+
+    // EditorConfig / modeline overrides
+    pub settings: DocSettings,
+
+    // Cached stats (updated on edit, not every frame), and the text version
+    // they were computed from
     pub cached_word_count: usize,
     pub cached_char_count: usize,
+    pub cached_headings: Vec<Heading>,
+    pub stats_version: u64,
+
+    // Activation stamp for most-recently-used tab order
+    pub last_active: u64,
 
     // File watching
     pub last_file_modified: Option<std::time::SystemTime>,
     pub externally_modified: bool,
+
+    // Background load / save in progress for this tab
+    pub job: Option<JobId>,
+
+    // Cursor / scroll to restore after a reload or session restore
+    pub pending_view: Option<ViewState>,
+
+    // Advisory lock on `file_path`, when document locking is enabled
+    pub lock: Option<DocLock>,
+
+    // Set when the file is a .gz / single-file .zip, recompressed on save
+    pub archive: Option<Archive>,
+
+    // Set while the text and history are unloaded to a temporary file
+    pub hibernation: Option<Hibernation>,
+
+    // Set when `file_path` is on a network share
+    pub network: bool,
+    // Set once a save to the share took longer than the slow save delay; its
+    // saves then run on a worker without locking the tab, Ctrl+S pressed
+    // meanwhile being queued into one more write
+    pub slow_share: bool,
+    pub save_in_flight: Option<JobId>,
+    pub save_queued: bool,
+    // Text handed to a save running on a worker, with the mirror version it
+    // was taken at: the tab is only clean once it reached the disk
+    pub pending_save: Option<(u64, Arc<str>)>,
+
+    // Language chosen from the status bar instead of the extension's
+    pub language_override: Option<Language>,
+
+    // Language guessed from the content of a file without extension
+    pub sniffed_language: Option<Language>,
+
+    // Parse error of a JSON / TOML / YAML document, for the text version
+    // `syntax_checked` (the last one parsed or being parsed)
+    pub syntax_error: Option<SyntaxError>,
+    pub syntax_checked: u64,
+
+    // Where the text was recently edited, for "Dernière modification"
+    pub edit_spots: EditSpots,
+    // Lines marked with Ctrl+F2, dotted in the gutter
+    pub bookmarks: Bookmarks,
+
+    // Text of the last load or save, None for a document never saved, and
+    // the lines changed since, marked in the gutter
+    pub saved_text: Option<Arc<str>>,
+    pub line_changes: Vec<(std::ops::Range<usize>, LineChange)>,
+
+    // Handwritten annotations, kept in a sidecar file, and the stroke being drawn
+    pub ink: Vec<ink::Stroke>,
+    pub ink_stroke: Option<ink::Stroke>,
 }
 
 impl Default for Document {
@@ -65,21 +311,51 @@ impl Default for Document {
         let mut content = text_editor::Content::new();
         content.perform(text_editor::Action::Click(iced::Point::new(0.0, 0.0)));
         Self {
+            id: next_document_id(),
             content,
+            mirror: TextMirror::default(),
             file_path: None,
             is_modified: false,
             undo_stack: VecDeque::new(),
             redo_stack: Vec::new(),
+            snapshots: Vec::new(),
             last_edit_time: None,
             line_ending: LineEnding::Lf,
             encoding: encoding_rs::UTF_8,
+            bom: false,
             scroll_offset: 0.0,
             max_undo: MAX_UNDO_HISTORY,
             status_message: None,
+            indent_style: IndentStyle::Tab,
+            indent_size: DEFAULT_INDENT_SIZE,
+            settings: DocSettings::default(),
             cached_word_count: 0,
             cached_char_count: 0,
+            cached_headings: Vec::new(),
+            stats_version: 0,
+            last_active: 0,
             last_file_modified: None,
             externally_modified: false,
+            job: None,
+            pending_view: None,
+            lock: None,
+            archive: None,
+            hibernation: None,
+            network: false,
+            slow_share: false,
+            save_in_flight: None,
+            save_queued: false,
+            pending_save: None,
+            language_override: None,
+            sniffed_language: None,
+            syntax_error: None,
+            syntax_checked: 0,
+            edit_spots: EditSpots::default(),
+            bookmarks: Bookmarks::default(),
+            saved_text: None,
+            line_changes: Vec::new(),
+            ink: Vec::new(),
+            ink_stroke: None,
         }
     }
 }
@@ -99,24 +375,515 @@ impl Document {
         }
     }
 
-    pub fn encode_content(&self) -> Vec<u8> {
-        let content = self.content.text();
-        if self.encoding != encoding_rs::UTF_8 {
+    pub fn encode_content(&self) -> std::io::Result<Vec<u8>> {
+        let content = self.text();
+        // encoding_rs follows the web standard and encodes UTF-16 as UTF-8, so
+        // it is written here, with a BOM so the next opening recognizes it
+        let utf16 = |bom: [u8; 2], unit: fn(u16) -> [u8; 2]| -> Vec<u8> {
+            bom.into_iter()
+                .chain(content.encode_utf16().flat_map(unit))
+                .collect()
+        };
+        let bytes = if self.encoding == encoding_rs::UTF_16LE {
+            utf16([0xFF, 0xFE], u16::to_le_bytes)
+        } else if self.encoding == encoding_rs::UTF_16BE {
+            utf16([0xFE, 0xFF], u16::to_be_bytes)
+        } else if self.encoding == encoding_rs::UTF_8 && self.bom {
+            [0xEF, 0xBB, 0xBF].into_iter().chain(content.bytes()).collect()
+        } else if self.encoding != encoding_rs::UTF_8 {
             let (encoded, _, _) = self.encoding.encode(&content);
             encoded.into_owned()
         } else {
-            content.into_bytes()
+            content.as_bytes().to_vec()
+        };
+        match &self.archive {
+            Some(archive) => archive.pack(&bytes),
+            None => Ok(bytes),
         }
     }
 
     pub fn update_stats_cache(&mut self) {
-        let text = self.content.text();
-        self.cached_char_count = text.len();
-        self.cached_word_count = text.split_whitespace().count();
+        self.cached_char_count = self.mirror.len_bytes();
+        self.cached_word_count = self.mirror.word_count();
+        self.cached_headings = if self.is_markdown() {
+            let lines: Vec<String> = self.mirror.lines().collect();
+            markdown::headings(lines.iter().map(String::as_str))
+        } else {
+            Vec::new()
+        };
+        self.refresh_line_changes();
+        self.stats_version = self.mirror.version();
+    }
+
+    // Typed since the stats were computed; only happens in low power mode,
+    // where they are refreshed on a timer rather than at each keystroke
+    pub fn stats_stale(&self) -> bool {
+        self.stats_version != self.mirror.version() && self.hibernation.is_none()
+    }
+
+    // The current text becomes the reference of the gutter's change marks
+    pub fn mark_saved(&mut self) {
+        self.saved_text = Some(self.text());
+        self.line_changes.clear();
+    }
+
+    // Keeps the text about to be written on a worker
+    pub fn start_save(&mut self) {
+        self.pending_save = Some((self.mirror.version(), self.text()));
+    }
+
+    // The worker wrote the text kept by `start_save`: clean again unless it
+    // was typed in since
+    pub fn finish_save(&mut self) {
+        if let Some((version, text)) = self.pending_save.take() {
+            if version == self.mirror.version() {
+                self.is_modified = false;
+            }
+            self.saved_text = Some(text);
+            self.refresh_line_changes();
+        }
+    }
+
+    fn refresh_line_changes(&mut self) {
+        self.line_changes = match &self.saved_text {
+            Some(saved)
+                if saved.len() <= CHANGE_MARKS_MAX_BYTES
+                    && self.mirror.len_bytes() <= CHANGE_MARKS_MAX_BYTES =>
+            {
+                changes::line_changes(saved, &self.mirror.text())
+            }
+            _ => Vec::new(),
+        };
+    }
+
+    // Shared copy of the text, reused until the next edit (read back from
+    // disk for a hibernated tab)
+    pub fn text(&self) -> Arc<str> {
+        match &self.hibernation {
+            Some(hibernation) => match self.hibernated_text(hibernation) {
+                Ok(text) => text.into(),
+                Err(e) => {
+                    tracing::warn!("Onglet en sommeil illisible : {e}");
+                    Arc::from("")
+                }
+            },
+            None if !self.mirror.is_exact() => Arc::from(self.content.text()),
+            None => self.mirror.text(),
+        }
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.hibernation = None;
+        self.content = text_editor::Content::with_text(text);
+        self.mirror = TextMirror::from_content(&self.content);
+    }
+
+    // --- Hibernation ---
+
+    pub fn memory_usage(&self) -> MemoryUsage {
+        if self.hibernation.is_some() {
+            return MemoryUsage::default();
+        }
+        let editor = self.mirror.len_bytes() + self.content.line_count() * hibernate::EDITOR_LINE_BYTES;
+        // Snapshots often share their text with each other or with the cached copy
+        let mut seen: Vec<&Arc<str>> = self.mirror.cached_text().into_iter().collect();
+        let mut history = 0;
+        let texts = self.undo_stack.iter().chain(&self.redo_stack).map(|s| &s.text);
+        let texts = texts.chain(self.snapshots.iter().map(|s| &s.text)).chain(&self.saved_text);
+        for text in texts {
+            if !seen.iter().any(|seen| Arc::ptr_eq(seen, text)) {
+                history += text.len();
+                seen.push(text);
+            }
+        }
+        MemoryUsage {
+            text: editor + self.mirror.memory_usage(),
+            history,
+        }
+    }
+
+    // Moves the text and undo history to a temporary file and frees them
+    pub fn hibernate(&mut self) -> std::io::Result<()> {
+        if self.hibernation.is_some() {
+            return Ok(());
+        }
+        let saved = |s: &TextSnapshot| SavedSnapshot {
+            text: s.text.to_string(),
+            cursor_line: s.cursor_line,
+            cursor_col: s.cursor_col,
+        };
+        let tab = HibernatedTab {
+            text: self.text().to_string(),
+            undo: self.undo_stack.iter().map(saved).collect(),
+            redo: self.redo_stack.iter().map(saved).collect(),
+        };
+        let hibernation = Hibernation::write(self.id, self.view_state(), &tab)?;
+        self.content = text_editor::Content::new();
+        self.mirror = TextMirror::default();
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.hibernation = Some(hibernation);
+        Ok(())
+    }
+
+    // Reloads a hibernated tab; the cursor and scroll come back through
+    // `pending_view`. The text comes back even when the history cannot; the
+    // tab stays hibernated when neither the file nor the copy can be read
+    pub fn wake(&mut self) -> std::io::Result<()> {
+        let Some(hibernation) = &self.hibernation else {
+            return Ok(());
+        };
+        let text = self.hibernated_text(hibernation)?;
+        let history = hibernation.read();
+        let view = hibernation.view;
+        self.set_text(&text);
+        self.pending_view = Some(view);
+        let tab = history?;
+        let restored = |s: SavedSnapshot| TextSnapshot {
+            text: s.text.into(),
+            cursor_line: s.cursor_line,
+            cursor_col: s.cursor_col,
+        };
+        self.undo_stack = tab.undo.into_iter().map(restored).collect();
+        self.redo_stack = tab.redo.into_iter().map(restored).collect();
+        Ok(())
+    }
+
+    // Only saved files hibernate: the file itself while it is still the
+    // version loaded or saved, else the copy in the hibernation file
+    fn hibernated_text(&self, hibernation: &Hibernation) -> std::io::Result<String> {
+        match self.unchanged_file_text() {
+            Some(text) => Ok(text),
+            None => hibernation.read().map(|tab| tab.text),
+        }
+    }
+
+    fn unchanged_file_text(&self) -> Option<String> {
+        let path = self.file_path.as_ref()?;
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified != self.last_file_modified {
+            return None;
+        }
+        let bytes = std::fs::read(path).ok()?;
+        let bytes = match self.archive {
+            Some(_) => archive::unpack(&bytes).ok()??.1,
+            None => bytes,
+        };
+        let bom = encoding_rs::Encoding::for_bom(&bytes).map_or(0, |(_, len)| len);
+        let (text, _) = self.encoding.decode_without_bom_handling(&bytes[bom..]);
+        Some(text.into_owned())
+    }
+
+    // Runs an editor action, replaying edits on the mirror for the lines they can touch
+    pub fn perform(&mut self, action: text_editor::Action) {
+        if !matches!(action, text_editor::Action::Edit(_)) {
+            self.content.perform(action);
+            return;
+        }
+        let cursor = self.content.cursor();
+        let anchor = cursor.selection.unwrap_or(cursor.position).line;
+        // One extra line on each side: Backspace / Delete can join lines
+        let start = anchor.min(cursor.position.line).saturating_sub(1);
+        let end = anchor.max(cursor.position.line) + 1;
+        let lines = self.content.line_count();
+        self.content.perform(action);
+        self.mirror.sync_lines(&self.content, start, end);
+        let delta = self.content.line_count() as isize - lines as isize;
+        let at = self.content.cursor().position;
+        let from = anchor.min(cursor.position.line);
+        self.edit_spots.record(from, delta, (at.line, at.column));
+        self.bookmarks.shift(from, delta);
+    }
+
+    pub fn language(&self) -> Language {
+        self.language_override.unwrap_or_else(|| self.detected_language())
+    }
+
+    // Language without the status bar override: the extension's, else the
+    // guess from the content
+    pub fn detected_language(&self) -> Language {
+        self.sniffed_language
+            .unwrap_or_else(|| Language::from_path(self.file_path.as_deref()))
+    }
+
+    pub fn is_markdown(&self) -> bool {
+        self.language() == Language::Markdown
+    }
+
+    // JSON / TOML / YAML text not parsed since its last change
+    pub fn needs_syntax_check(&self) -> bool {
+        syntax::is_checked(self.language())
+            && self.syntax_checked != self.mirror.version()
+            && self.hibernation.is_none()
+            && self.job.is_none()
+    }
+
+    // Error to show, if the document is still of a checked language
+    pub fn current_syntax_error(&self) -> Option<&SyntaxError> {
+        self.syntax_error.as_ref().filter(|_| syntax::is_checked(self.language()))
+    }
+
+    // Edits are refused while a background job runs or another instance holds the lock
+    pub fn is_read_only(&self) -> bool {
+        self.job.is_some()
+            || matches!(&self.lock, Some(DocLock { state: LockState::ReadOnly(_), .. }))
+    }
+
+    // Cursor position with the column counted in characters (as navigate_to expects)
+    pub fn nav_point(&self) -> NavPoint {
+        let position = self.content.cursor().position;
+        let column = self
+            .content
+            .line(position.line)
+            .map_or(0, |l| l.text.get(..position.column).map_or(0, |s| s.chars().count()));
+        NavPoint {
+            doc: self.id,
+            line: position.line,
+            column,
+        }
+    }
+
+    // Untitled copy with the same text, cursor and language; the history stays behind
+    pub fn duplicate(&self) -> Document {
+        let mut copy = Document {
+            is_modified: true,
+            line_ending: self.line_ending,
+            encoding: self.encoding,
+            bom: self.bom,
+            indent_style: self.indent_style,
+            indent_size: self.indent_size,
+            max_undo: self.max_undo,
+            language_override: Some(self.language()),
+            pending_view: Some(self.view_state()),
+            ..Document::default()
+        };
+        copy.set_text(&self.text());
+        copy.update_stats_cache();
+        copy
+    }
+
+    pub fn view_state(&self) -> ViewState {
+        if let Some(hibernation) = &self.hibernation {
+            return hibernation.view;
+        }
+        let point = self.nav_point();
+        ViewState {
+            line: point.line,
+            column: point.column,
+            scroll: self.scroll_offset,
+        }
+    }
+
+    // Places the cursor at a character column, clamped to the document
+    pub fn move_cursor(&mut self, line: usize, column: usize) {
+        let line = line.min(self.content.line_count().saturating_sub(1));
+        let byte_column = self.content.line(line).map_or(0, |l| {
+            l.text
+                .char_indices()
+                .nth(column)
+                .map_or(l.text.len(), |(i, _)| i)
+        });
+        self.place_cursor(
+            text_editor::Position {
+                line,
+                column: byte_column,
+            },
+            None,
+        );
+    }
+
+    // Selects the text between two byte offsets, leaving the cursor at `end`
+    pub fn select_bytes(&mut self, start: usize, end: usize) {
+        let position = |(line, column)| text_editor::Position { line, column };
+        let anchor = position(self.byte_to_position(start));
+        let cursor = position(self.byte_to_position(end));
+        self.place_cursor(cursor, (start != end).then_some(anchor));
+    }
+
+    // Line and byte column of an offset into `text()`. An inexact mirror has
+    // offsets of its own, so the editor's lines are walked instead
+    pub fn byte_to_position(&self, byte: usize) -> (usize, usize) {
+        if self.mirror.is_exact() {
+            return self.mirror.byte_to_position(byte);
+        }
+        let mut start = 0;
+        let last = self.content.line_count().saturating_sub(1);
+        for index in 0..=last {
+            let Some(line) = self.content.line(index) else {
+                break;
+            };
+            let end = start + line.text.len() + line.ending.as_str().len();
+            if byte < end || index == last {
+                return (index, byte.saturating_sub(start).min(line.text.len()));
+            }
+            start = end;
+        }
+        (0, 0)
+    }
+
+    // Offset into `text()` of a line and byte column, clamped to the text
+    pub fn position_to_byte(&self, line: usize, column: usize) -> usize {
+        if self.mirror.is_exact() {
+            return self.mirror.position_to_byte(line, column);
+        }
+        let line = line.min(self.content.line_count().saturating_sub(1));
+        let before: usize = (0..line)
+            .filter_map(|index| self.content.line(index))
+            .map(|l| l.text.len() + l.ending.as_str().len())
+            .sum();
+        let length = self.content.line(line).map_or(0, |l| l.text.len());
+        before + column.min(length)
+    }
+
+    // Drops the selection, the caret stays where it is
+    pub fn deselect(&mut self) {
+        let position = self.content.cursor().position;
+        self.place_cursor(position, None);
+    }
+
+    fn place_cursor(
+        &mut self,
+        position: text_editor::Position,
+        selection: Option<text_editor::Position>,
+    ) {
+        // `move_to` keeps the current selection anchor; any move drops it
+        if selection.is_none() && self.content.cursor().selection.is_some() {
+            self.content
+                .perform(text_editor::Action::Move(text_editor::Motion::Home));
+        }
+        self.content.move_to(text_editor::Cursor { position, selection });
+    }
+
+    // Scrolls then moves the cursor; the scroll only works once the editor has metrics
+    pub fn apply_pending_view(&mut self) {
+        let Some(view) = self.pending_view.take() else {
+            return;
+        };
+        let max_offset = self.content.line_count().saturating_sub(1) as f32;
+        let scroll = view.scroll.clamp(0.0, max_offset).trunc();
+        self.content.perform(text_editor::Action::Scroll {
+            lines: (scroll - self.scroll_offset) as i32,
+        });
+        self.scroll_offset = scroll;
+        self.move_cursor(view.line, view.column);
+    }
+
+    pub fn indent_label(&self) -> String {
+        match self.indent_style {
+            IndentStyle::Tab => "Tabulations".to_string(),
+            IndentStyle::Space => format!("Espaces: {}", self.indent_size),
+        }
+    }
+
+    pub fn indent_unit(&self) -> String {
+        match self.indent_style {
+            IndentStyle::Tab => "\t".to_string(),
+            IndentStyle::Space => " ".repeat(self.indent_size),
+        }
+    }
+
+    pub fn detect_indent(&mut self, text: &str) {
+        if let Some((style, size)) = IndentStyle::detect(text) {
+            self.indent_style = style;
+            self.indent_size = size;
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: DocSettings) {
+        if let Some(style) = settings.indent_style {
+            self.indent_style = style;
+        }
+        if let Some(size) = settings.indent_size {
+            self.indent_size = size;
+        }
+        if let Some(eol) = settings.end_of_line {
+            self.line_ending = eol;
+        }
+        if let Some(charset) = settings.charset {
+            self.encoding = charset.encoding();
+            self.bom = charset.bom();
+        }
+        self.settings = settings;
+    }
+
+    // Rewrites the buffer according to the save-time overrides, keeping the cursor in place.
+    // `trim_whitespace` is the preference; the file's own settings win over it.
+    pub fn prepare_for_save(&mut self, trim_whitespace: bool) {
+        let text = self.text();
+        let mut settings = self.settings.clone();
+        if trim_whitespace {
+            settings.trim_trailing_whitespace.get_or_insert(true);
+        }
+        let mut new_text = settings.apply_on_save(&text);
+        if self.is_markdown() {
+            new_text = markdown::align_tables(&new_text);
+        }
+        if *new_text != *text {
+            self.set_text_keeping_cursor(&new_text);
+        }
+    }
+
+    // Text without the spaces and tabs ending its lines, None when there are none
+    pub fn trimmed_text(&self) -> Option<String> {
+        let text = self.text();
+        let trim = DocSettings {
+            trim_trailing_whitespace: Some(true),
+            ..DocSettings::default()
+        };
+        let new_text = trim.apply_on_save(&text);
+        (*new_text != *text).then_some(new_text)
+    }
+
+    // Text with every line ending replaced by `eol`, None when it already has only those
+    pub fn text_with_line_endings(&self, eol: LineEnding) -> Option<String> {
+        let text = self.text();
+        let convert = DocSettings {
+            end_of_line: Some(eol),
+            ..DocSettings::default()
+        };
+        let new_text = convert.apply_on_save(&text);
+        (*new_text != *text).then_some(new_text)
+    }
+
+    // The cursor stays on its line, pulled back to the line end when it was past it
+    pub fn set_text_keeping_cursor(&mut self, new_text: &str) {
+        let position = self.content.cursor().position;
+        self.set_text(new_text);
+        let line = position.line.min(self.content.line_count().saturating_sub(1));
+        let line_len = self.content.line(line).map_or(0, |l| l.text.len());
+        let mut column = position.column.min(line_len);
+        if let Some(l) = self.content.line(line) {
+            while !l.text.is_char_boundary(column) {
+                column -= 1;
+            }
+        }
+        self.content.move_to(text_editor::Cursor {
+            position: text_editor::Position { line, column },
+            selection: None,
+        });
+        self.update_stats_cache();
     }
 }
 
 
+// Position of the highlighted match among all matches of the find query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchCount {
+    pub current: Option<usize>,
+    pub total: usize,
+}
+
+impl MatchCount {
+    pub fn label(&self) -> String {
+        match (self.total, self.current) {
+            (0, _) => "Aucun résultat".to_string(),
+            (total, Some(index)) => format!("{}/{total}", index + 1),
+            (1, None) => "1 résultat".to_string(),
+            (total, None) => format!("{total} résultats"),
+        }
+    }
+}
+
 // --- Enums ---
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -126,25 +893,398 @@ pub enum Menu {
     Search,
     View,
     Format,
+    Window,
+    Help,
 }
 
 #[derive(Debug, Clone)]
 pub enum FileMsg {
     NewTab,
+    DuplicateTab,
     CloseTab(usize),
+    ScrollTabs(i32),
     ConfirmCloseTabResult(bool, usize),
     SwitchTab(usize),
+    CloseUnmodifiedTabs,
     Save,
     SaveAs,
     Open,
     SaveFileSelected(Option<PathBuf>),
+    // Encoding and line ending step of Save As, once the file name is chosen
+    SetSaveEncoding(SaveEncoding),
+    SetSaveLineEnding(LineEnding),
+    ConfirmSaveAs,
+    CancelSaveAs,
     OpenFileSelected(Option<PathBuf>),
     CloseRequested(iced::window::Id),
     ConfirmCloseResult(bool, iced::window::Id),
     AutoSave,
+    // Autosave of the tabs on network shares, at their own interval
+    AutoSaveNetwork,
+    AutoSaveSession,
     CheckExternalChanges,
+    // Reported by the file watcher for anything touched in a watched folder
+    ChangedOnDisk(PathBuf),
     ReloadFile(usize),
+    StealLock(usize),
     IgnoreExternalChange(usize),
+    // Answer to the warning about a large file, for the tab it opens in
+    ConfirmLargeFile(bool, u64, PathBuf),
+    Loaded(JobId, Result<Arc<LoadedFile>, String>),
+    Saved(JobId, Result<(), String>),
+    ToggleProperties,
+    // Sends the active tab to the printer; the document name comes back with the result
+    Print,
+    Printed(String, Result<(), String>),
+    // "Fichiers récents" submenu of the File menu
+    ToggleRecentFiles,
+    OpenRecent(PathBuf),
+    ClearRecentFiles,
+    // "Rouvrir avec l'encodage" submenu: decodes the file again, for when
+    // detection guessed wrong
+    ToggleReopenEncodings,
+    ReopenWithEncoding(&'static encoding_rs::Encoding),
+    // Unsaved edits of the tab dropped for the reopening, or kept
+    ConfirmReopenWithEncoding(bool, u64, &'static encoding_rs::Encoding),
+}
+
+// Details of the active file shown by File → Propriétés, read when opened
+pub struct FileProperties {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    // Real file when `path` is a symbolic link
+    pub target: Option<PathBuf>,
+    pub network: bool,
+    pub size: Option<u64>,
+    pub modified: Option<String>,
+    pub encoding: &'static str,
+    pub line_ending: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub enum JobMsg {
+    Progress(JobId, f32),
+    Cancel(JobId),
+    TogglePanel,
+}
+
+#[derive(Debug, Clone)]
+pub enum ToastMsg {
+    Dismiss(u64),
+    Tick,
+}
+
+#[derive(Debug, Clone)]
+pub enum MergeMsg {
+    Open(usize),
+    // Hunk index, counting conflicts only
+    Choose(usize, HunkChoice),
+    ChooseAll(HunkChoice),
+    Apply,
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub enum ColorMsg {
+    // Line and byte start of the literal whose swatch was clicked
+    Open(usize, usize),
+    // Channel index (0 = red, 1 = green, 2 = blue) and value
+    SetChannel(usize, u8),
+    HexChanged(String),
+    Apply,
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub enum TableMsg {
+    Insert,
+    InsertRow,
+    RemoveRow,
+    InsertColumn,
+    RemoveColumn,
+}
+
+#[derive(Debug, Clone)]
+pub enum TerminalMsg {
+    TogglePanel,
+    InputChanged(String),
+    Submit,
+    // Session id, so output from a restarted shell is ignored
+    Output(u64, String),
+    Closed(u64),
+    Clear,
+    Restart,
+}
+
+#[derive(Debug, Clone)]
+pub enum SnapshotMsg {
+    // Opens the panel with a name ready for a new snapshot
+    Create,
+    TogglePanel,
+    NameChanged(String),
+    Save,
+    Restore(usize),
+    Delete(usize),
+}
+
+#[derive(Debug, Clone)]
+pub enum NotesMsg {
+    EditTags,
+    TagsInputChanged(String),
+    ToggleFavorite,
+    SaveTags,
+    CloseTags,
+    OpenSearch,
+    CloseSearch,
+    QueryChanged(String),
+    // Search generation, so results of an outdated query are dropped
+    Results(u64, Vec<NoteHit>),
+    Open(PathBuf),
+    // Export of the notes folder: pick the destination, then run it as a job
+    Export(ExportFormat),
+    ExportTo(ExportFormat, PathBuf),
+    Exported(JobId, PathBuf, Result<usize, String>),
+}
+
+// Tags and favorite flag being edited for the active file
+pub struct TagEditor {
+    pub path: PathBuf,
+    pub input: String,
+    pub favorite: bool,
+}
+
+// Search across the tagged, favorite and recently opened files
+#[derive(Default)]
+pub struct NoteSearch {
+    pub query: String,
+    pub generation: u64,
+    pub searching: bool,
+    pub results: Vec<NoteHit>,
+}
+
+#[derive(Debug, Clone)]
+pub enum FolderMsg {
+    Choose,
+    // Dropped or chosen folder: its text files are listed in the background
+    Show(PathBuf),
+    Listed(PathBuf, Vec<PathBuf>),
+    QueryChanged(String),
+    // Path relative to the folder
+    Open(PathBuf),
+    OpenFirst,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub enum SessionMsg {
+    Show,
+    Close,
+    NameChanged(String),
+    // Saves the open tabs under the typed name
+    Save,
+    Open(String),
+    // Answer to the prompt about the modified tabs of an unnamed session
+    OpenConfirmed(bool, String),
+    // Content of the session's files, read in the background
+    Loaded(String, Vec<Option<LoadedFile>>),
+    Delete(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum TemplateMsg {
+    Show,
+    Close,
+    NameChanged(String),
+    // Saves the document as a template under the typed name
+    Save,
+    // Opens a template, asking for its variables if it has some
+    Use(String),
+    ValueChanged(usize, String),
+    // New tab from the template and the values typed
+    Create,
+    Delete(String),
+}
+
+// File → Modèles: the name typed to save the document as a template, the
+// templates of the folder, and the one being instantiated
+pub struct TemplateManager {
+    pub name: String,
+    pub templates: Vec<String>,
+    pub filling: Option<TemplateFill>,
+}
+
+// Template whose {{variables}} are being asked for, with the values typed
+pub struct TemplateFill {
+    pub file_name: String,
+    pub text: String,
+    pub values: Vec<(String, String)>,
+}
+
+// Edit → Définition: the word looked up and what the dictionaries say, None
+// while they are read
+pub struct DefinitionPopup {
+    pub word: String,
+    pub result: Option<Result<Vec<Entry>, String>>,
+}
+
+// Unknown word under the cursor when the context menu opened, with the
+// replacements it offers
+pub struct SpellSuggestions {
+    pub line: usize,
+    // Bytes of the word in the line
+    pub range: std::ops::Range<usize>,
+    pub suggestions: Vec<String>,
+}
+
+// Text files of a folder, filtered to pick one to open
+pub struct FolderPicker {
+    pub folder: PathBuf,
+    pub query: String,
+    // None while the folder is being listed
+    pub files: Option<Vec<PathBuf>>,
+}
+
+// Edit → Code QR: the code and the text it holds
+pub struct QrPopup {
+    pub text: String,
+    pub code: Qr,
+}
+
+// Edit → Insérer un caractère: the search typed and the Unicode block shown
+// (an index in charmap::BLOCKS)
+pub struct CharPicker {
+    pub query: String,
+    pub block: usize,
+}
+
+// Edit → Exécuter un script: the scripts of the scripts folder
+pub struct ScriptPicker {
+    // None while the folder is being listed
+    pub scripts: Option<Vec<PathBuf>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum HelpMsg {
+    ShowLogs,
+    ToggleShortcuts,
+    ShortcutQueryChanged(String),
+    ToggleAbout,
+    CheckUpdates,
+    UpdateChecked(Result<Release, String>),
+    OpenUrl(String),
+}
+
+// Color being edited from a gutter swatch
+pub struct ColorPicker {
+    pub doc: u64,
+    pub line: usize,
+    pub literal: ColorLiteral,
+    pub color: Rgb,
+    pub hex_input: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum CompletionMsg {
+    Next,
+    Previous,
+    Accept,
+    Pick(usize),
+    Dismiss,
+}
+
+// Popup listing files that complete the path typed before the cursor,
+// or notes that complete a [[wiki link]]
+pub struct PathCompletion {
+    // Characters of the last path component already typed, replaced on accept
+    pub prefix_len: usize,
+    pub separator: char,
+    // Typed after a completed file, "]]" for wiki links
+    pub suffix: &'static str,
+    pub entries: Vec<PathEntry>,
+    pub selected: usize,
+}
+
+// View → Annotations manuscrites: points in text units (column, line)
+#[derive(Debug, Clone)]
+pub enum InkMsg {
+    Toggle,
+    Begin([f32; 2]),
+    Extend([f32; 2]),
+    End,
+    UndoStroke,
+    Clear,
+}
+
+#[derive(Debug, Clone)]
+pub enum PrintMsg {
+    OpenPreview,
+    ClosePreview,
+    PreviousPage,
+    NextPage,
+    SetMargin(f32),
+    ToggleHeaderFooter,
+    ToggleFitToWidth,
+    // Only the selection of the active tab, under the file name
+    PrintSelection,
+    OpenQueue,
+    ToggleQueued(u64),
+    CloseQueue,
+    PrintQueued,
+}
+
+// Print preview of the active tab, paginated when opened or when a setting changes
+pub struct PrintPreview {
+    pub setup: PageSetup,
+    pub pagination: Pagination,
+    pub page: usize,
+}
+
+// Encoding and line ending chosen for the file picked in Save As
+pub struct SaveAsDialog {
+    pub path: PathBuf,
+    pub choice: SaveEncoding,
+    pub options: Vec<SaveEncoding>,
+    pub line_ending: LineEnding,
+    // First characters of the document the choice cannot represent
+    pub unmappable: Vec<char>,
+}
+
+// Tabs chosen to be printed together in one job, by document id
+pub struct PrintQueue {
+    pub docs: Vec<u64>,
+}
+
+// Local edits being reconciled with the version of the file on disk
+pub struct MergeSession {
+    pub doc: u64,
+    pub name: String,
+    pub merge: Merge,
+    pub disk_modified: Option<SystemTime>,
+}
+
+// Everything read from disk at startup, off the UI thread
+#[derive(Debug, Clone, Default)]
+pub struct StartupData {
+    pub session: Option<SessionData>,
+    // Decoded content of the session tabs with a file, by tab index
+    pub files: Vec<Option<LoadedFile>>,
+    pub metadata: MetadataStore,
+    pub notes: NoteIndex,
+    pub named_sessions: NamedSessions,
+    pub recent_files: RecentFiles,
+    pub keymap: Keymap,
+    pub snippets: Vec<Snippet>,
+}
+
+// File decoded by a background load job
+#[derive(Debug, Clone)]
+pub struct LoadedFile {
+    pub text: String,
+    pub encoding: &'static encoding_rs::Encoding,
+    pub bom: bool,
+    pub settings: DocSettings,
+    pub size: u64,
+    pub archive: Option<Archive>,
 }
 
 #[derive(Debug, Clone)]
@@ -152,10 +1292,86 @@ pub enum EditMsg {
     Copy,
     Cut,
     Paste,
+    // Clipboard text without control characters and byte order marks
+    PastePlain,
     SelectAll,
     Undo,
     Redo,
     InsertDateTime,
+    InsertTab,
+    // Swaps the paragraph under the cursor with the one above or below
+    MoveParagraphUp,
+    MoveParagraphDown,
+    TrimTrailingWhitespace,
+    TabsToSpaces,
+    SpacesToTabs,
+    // Selected lines (the cursor line and the next without a selection) made one
+    JoinLines,
+    // Comments the selected lines with the tokens of the document's language,
+    // or uncomments them
+    ToggleComment,
+    // Selected lines broken at a column typed in a prompt
+    HardWrap,
+    HardWrapWidthChanged(String),
+    RunHardWrap,
+    CloseHardWrap,
+    // Keyboard macro: recording turned on or off, replayed once or the number
+    // of times typed in a prompt
+    ToggleMacroRecording,
+    PlayMacro,
+    PlayMacroTimes,
+    MacroCountChanged(String),
+    RunMacroTimes,
+    CloseMacroPrompt,
+    // Appends the selection to a file picked on disk, without opening it
+    AppendSelection,
+    AppendSelectionTo(PathBuf),
+    SelectionAppended(PathBuf, Result<(), String>),
+    // Selection piped through a shell command and replaced by its output
+    FilterSelection,
+    FilterCommandChanged(String),
+    RunFilter,
+    CloseFilter,
+    // Document id and the selection sent, to check it is still there
+    Filtered(JobId, u64, String, Result<String, String>),
+    // Rhai script run on the selection, or the whole document without one
+    ShowScripts,
+    ScriptsListed(Vec<PathBuf>),
+    RunScript(PathBuf),
+    CloseScripts,
+    // Character picker: search, block shown, and the character to insert
+    ShowCharPicker,
+    CharQueryChanged(String),
+    ChooseCharBlock(usize),
+    InsertChar(char),
+    CloseCharPicker,
+    // Presets of the F5 format, unfolded beside the Edit menu
+    ToggleDateFormats,
+    ChooseDateFormat(&'static str),
+    // Document id, where the pasted URL starts, the URL and the page's title
+    LinkTitleFetched(u64, usize, String, Result<String, String>),
+    // Document id, text version and the selection sent, to check they are still there
+    ScriptRan(JobId, u64, u64, Option<String>, Result<String, String>),
+    // QR code of the selection, or of the link under the cursor
+    ShowQrCode,
+    CloseQrCode,
+    CopyQrCode,
+    SaveQrCode,
+    QrCodeFileSelected(Option<PathBuf>),
+    // Stats left behind by typing in low power mode
+    RefreshStats,
+    // Parse the active document once typing pauses
+    CheckSyntax,
+    // Document id, text version, first error
+    SyntaxChecked(u64, u64, Option<SyntaxError>),
+    GoToSyntaxError,
+    // Looks up the selection, or the word under the cursor
+    Define,
+    DefineWord(String),
+    Defined(String, Result<Vec<Entry>, String>),
+    CloseDefinition,
+    // Replaces the misspelled word of the context menu
+    ReplaceMisspelled(String),
 }
 
 #[derive(Debug, Clone)]
@@ -175,6 +1391,48 @@ pub enum SearchMsg {
     GoToLineSubmit,
     ToggleCaseSensitive,
     ToggleRegex,
+    ToggleExtended,
+    // Find and Replace All limited to the text selected when it is turned on
+    ToggleInSelection,
+    NavigateBack,
+    NavigateForward,
+    // Recently edited places: the newest first, or the nearest in the document
+    LastEdit,
+    NextEdit,
+    PreviousEdit,
+    NextHeading,
+    PreviousHeading,
+    // Blocks of lines separated by blank lines
+    NextParagraph,
+    PreviousParagraph,
+    // Line of a heading picked in the breadcrumb or the outline
+    GoToHeading(usize),
+    // Bookmarks of the active document; the submenu of the Search menu lists them
+    ToggleBookmark,
+    NextBookmark,
+    PreviousBookmark,
+    GoToBookmark(usize),
+    ClearBookmarks,
+    ToggleBookmarksMenu,
+    // Search index built in the background: document id, mirror version
+    Indexed(u64, u64, Arc<SearchIndex>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    // Left, or top
+    First,
+    Second,
+}
+
+// The focused pane shows the active tab, so the menus and shortcuts act on it;
+// focusing the other pane swaps its document with the active one
+pub struct Split {
+    // Side by side; one above the other when false
+    pub vertical: bool,
+    // Document id of the pane without focus, the active one's for two views
+    pub other: u64,
+    pub focus: Pane,
 }
 
 #[derive(Debug, Clone)]
@@ -184,6 +1442,19 @@ pub enum ViewMsg {
     ZoomReset,
     ToggleDarkMode,
     ToggleWordWrap,
+    RestoreView,
+    ToggleOutline,
+    // Deepest heading level listed in the outline
+    SetOutlineLevel(u8),
+    ToggleMemory,
+    HibernateBackgroundTabs,
+    SplitVertical,
+    SplitHorizontal,
+    CloseSplit,
+    // Editor action of the pane without focus, which takes the focus first
+    PaneAction(Pane, text_editor::Action),
+    // Display scaling of the window's monitor
+    SystemScale(f32),
 }
 
 #[derive(Debug, Clone)]
@@ -193,12 +1464,66 @@ pub enum SettingsMsg {
     SetDarkMode(bool),
     SetFontSize(f32),
     SetWordWrap(bool),
+    SetProseLigatures(bool),
+    SetCodeLigatures(bool),
     SetRestoreSession(bool),
+    SetShowToolbar(bool),
+    SetMruTabSwitching(bool),
+    SetAutoHideFind(bool),
+    SetEscapeOrder(EscapeOrder),
+    SetClickClosesBars(bool),
+    SetRememberCursorPosition(bool),
+    SetLockDocuments(bool),
+    SetHibernateTabs(bool),
+    ChooseNotesFolder,
+    SetNotesFolder(Option<PathBuf>),
+    SetNetworkSafeSave(bool),
+    SetNetworkAutosaveMinutes(u64),
+    SetSlowSaveMs(u64),
+    SetTrimTrailingWhitespace(bool),
+    SetSaveOnFocusLoss(bool),
+    SetLowPower(bool),
+    SetTabWidth(usize),
+    SetInsertSpaces(bool),
+    // Applied while the slider moves, saved when it is released
+    SetWindowOpacity(u8),
+    SetUiScale(u16),
+    SaveWindowOpacity,
+    SetProxyMode(ProxyMode),
+    ProxyAddressChanged(String),
+    ProxyUserChanged(String),
+    DateFormatChanged(String),
+    SetUrlPaste(UrlPaste),
+    ProxyPasswordChanged(String),
+    SaveProxyPassword,
+    ProxyPasswordSaved(Result<(), String>),
+    ToggleKeybindings,
+    // Waits for the next key press to bind it to the action
+    Rebind(Action),
+    ResetBinding(Action),
+    ResetAllBindings,
+    ToggleAbbreviations,
+    AddAbbreviation,
+    RemoveAbbreviation(usize),
+    AbbreviationChanged(usize, String),
+    ExpansionChanged(usize, String),
+    SetAbbreviationLanguage(usize, Option<Language>),
+    ToggleSnippets,
+    AddSnippet,
+    RemoveSnippet(usize),
+    SnippetTriggerChanged(usize, String),
+    SnippetBodyChanged(usize, String),
+    SetSnippetLanguage(usize, Option<Language>),
+    // None turns spell checking off
+    SetSpellDictionary(Option<String>),
+    SpellDictionaryLoaded(String, Result<Arc<Speller>, String>),
 }
 
 #[derive(Debug, Clone)]
 pub enum FormatMsg {
     SetFontFamily(String),
+    SetIndent(IndentStyle, usize),
+    ChooseLanguage(Language),
 }
 
 #[derive(Debug, Clone)]
@@ -207,6 +1532,9 @@ pub enum MenuMsg {
     Hover(Menu),
     CloseAll,
     ShowContext,
+    ShowIndent,
+    ShowLanguage,
+    ToggleTabList,
 }
 
 #[derive(Debug, Clone)]
@@ -220,6 +1548,23 @@ pub enum Message {
     Settings(SettingsMsg),
     Format(FormatMsg),
     Menu(MenuMsg),
+    Job(JobMsg),
+    Toast(ToastMsg),
+    Merge(MergeMsg),
+    Print(PrintMsg),
+    Ink(InkMsg),
+    Completion(CompletionMsg),
+    Color(ColorMsg),
+    Table(TableMsg),
+    Terminal(TerminalMsg),
+    Help(HelpMsg),
+    Snapshot(SnapshotMsg),
+    Notes(NotesMsg),
+    Folder(FolderMsg),
+    Session(SessionMsg),
+    Template(TemplateMsg),
+    Shortcut(Action),
+    StartupLoaded(Box<StartupData>),
     ScrollbarClick(f32),
 }
 
@@ -229,12 +1574,18 @@ pub enum Message {
 pub enum LineEnding {
     Lf,
     CrLf,
+    // Classic Mac OS
+    Cr,
 }
 
 impl LineEnding {
+    pub const ALL: [Self; 3] = [Self::CrLf, Self::Lf, Self::Cr];
+
     pub fn detect(text: &str) -> Self {
         if text.contains("\r\n") {
             Self::CrLf
+        } else if text.contains('\r') && !text.contains('\n') {
+            Self::Cr
         } else {
             Self::Lf
         }
@@ -244,26 +1595,302 @@ impl LineEnding {
         match self {
             Self::Lf => "LF",
             Self::CrLf => "CRLF",
+            Self::Cr => "CR",
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+            Self::Cr => "\r",
         }
     }
 }
 
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lf => f.write_str("LF (Unix, macOS)"),
+            Self::CrLf => f.write_str("CRLF (Windows)"),
+            Self::Cr => f.write_str("CR (Mac OS classique)"),
+        }
+    }
+}
+
+// --- Encodings ---
+
+// Offered by "Rouvrir avec l'encodage", with their menu labels
+pub fn reopen_encodings() -> [(&'static str, &'static encoding_rs::Encoding); 12] {
+    [
+        ("UTF-8", encoding_rs::UTF_8),
+        ("UTF-16 LE", encoding_rs::UTF_16LE),
+        ("UTF-16 BE", encoding_rs::UTF_16BE),
+        ("Windows-1252 (occidental)", encoding_rs::WINDOWS_1252),
+        ("ISO-8859-15 (Latin-9)", encoding_rs::ISO_8859_15),
+        ("Windows-1250 (Europe centrale)", encoding_rs::WINDOWS_1250),
+        ("Windows-1251 (cyrillique)", encoding_rs::WINDOWS_1251),
+        ("KOI8-R", encoding_rs::KOI8_R),
+        ("Mac Roman", encoding_rs::MACINTOSH),
+        ("Shift_JIS", encoding_rs::SHIFT_JIS),
+        ("GBK", encoding_rs::GBK),
+        ("Big5", encoding_rs::BIG5),
+    ]
+}
+
+// Choices of the Save As dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveEncoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+    // The document's own encoding when it is none of the above
+    Other(&'static encoding_rs::Encoding),
+}
+
+impl SaveEncoding {
+    pub const ALL: [Self; 5] = [Self::Utf8, Self::Utf8Bom, Self::Utf16Le, Self::Utf16Be, Self::Windows1252];
+
+    pub fn of(encoding: &'static encoding_rs::Encoding, bom: bool) -> Self {
+        match encoding {
+            e if e == encoding_rs::UTF_8 && bom => Self::Utf8Bom,
+            e if e == encoding_rs::UTF_8 => Self::Utf8,
+            e if e == encoding_rs::UTF_16LE => Self::Utf16Le,
+            e if e == encoding_rs::UTF_16BE => Self::Utf16Be,
+            e if e == encoding_rs::WINDOWS_1252 => Self::Windows1252,
+            e => Self::Other(e),
+        }
+    }
+
+    pub fn encoding(self) -> &'static encoding_rs::Encoding {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => encoding_rs::UTF_8,
+            Self::Utf16Le => encoding_rs::UTF_16LE,
+            Self::Utf16Be => encoding_rs::UTF_16BE,
+            Self::Windows1252 => encoding_rs::WINDOWS_1252,
+            Self::Other(encoding) => encoding,
+        }
+    }
+
+    // UTF-16 always gets one, or the file would not be recognized when opened
+    pub fn bom(self) -> bool {
+        matches!(self, Self::Utf8Bom | Self::Utf16Le | Self::Utf16Be)
+    }
+}
+
+impl std::fmt::Display for SaveEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Utf8 => f.write_str("UTF-8"),
+            Self::Utf8Bom => f.write_str("UTF-8 avec BOM"),
+            Self::Utf16Le => f.write_str("UTF-16 LE"),
+            Self::Utf16Be => f.write_str("UTF-16 BE"),
+            Self::Windows1252 => f.write_str("Windows-1252"),
+            Self::Other(encoding) => f.write_str(encoding.name()),
+        }
+    }
+}
+
+// Distinct characters of `text` that `encoding` cannot represent, at most `limit`
+pub fn unmappable_chars(text: &str, encoding: &'static encoding_rs::Encoding, limit: usize) -> Vec<char> {
+    // UTF-16 encodes through UTF-8 in encoding_rs, so both cover everything
+    if encoding.output_encoding() == encoding_rs::UTF_8 {
+        return Vec::new();
+    }
+    let mut found = Vec::new();
+    let mut buf = [0; 4];
+    for c in text.chars() {
+        if found.len() == limit {
+            break;
+        }
+        if !found.contains(&c) && encoding.encode(c.encode_utf8(&mut buf)).2 {
+            found.push(c);
+        }
+    }
+    found
+}
+
+// --- Indentation ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+impl IndentStyle {
+    // Guesses (style, width) from leading whitespace; None if nothing is indented
+    pub fn detect(text: &str) -> Option<(Self, usize)> {
+        let mut tab_lines = 0;
+        let mut space_lines = 0;
+        let mut deltas = [0usize; 9];
+        let mut prev_spaces = 0;
+
+        for line in text.lines().take(INDENT_DETECT_MAX_LINES) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with('\t') {
+                tab_lines += 1;
+                prev_spaces = 0;
+                continue;
+            }
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            // A single space is usually alignment (e.g. " * " in block comments)
+            if spaces > 1 {
+                space_lines += 1;
+            }
+            let delta = spaces.abs_diff(prev_spaces);
+            if (2..deltas.len()).contains(&delta) {
+                deltas[delta] += 1;
+            }
+            prev_spaces = spaces;
+        }
+
+        if tab_lines == 0 && space_lines == 0 {
+            return None;
+        }
+        if tab_lines >= space_lines {
+            return Some((Self::Tab, DEFAULT_INDENT_SIZE));
+        }
+        let width = (2..deltas.len())
+            .max_by_key(|&w| (deltas[w], std::cmp::Reverse(w)))
+            .filter(|&w| deltas[w] > 0)
+            .unwrap_or(DEFAULT_INDENT_SIZE);
+        Some((Self::Space, width))
+    }
+}
+
 // --- Application state ---
 
 pub struct Notepad {
     // Tabs
     pub tabs: Vec<Document>,
     pub active_tab: usize,
+    pub tab_scroll: usize,
 
     // App-wide
+    // Created on first use: initializing it can stall the first frame on some systems
     pub clipboard: Option<arboard::Clipboard>,
+    // Process start, to report how long the startup took
+    pub launched: Instant,
+    // Last message from the user rather than a timer or a background job
+    pub last_activity: Instant,
+    // Files named on the command line, opened once the session is restored
+    pub cli_files: Vec<PathBuf>,
     pub font_size: f32,
     pub font_family: String,
     pub dark_mode: bool,
     pub word_wrap: bool,
+    pub prose_ligatures: bool,
+    pub code_ligatures: bool,
+    // Annotations shown over the text, and drawn with the pointer
+    pub show_ink: bool,
     pub window_width: f32,
     pub window_height: f32,
     pub restore_session: bool,
+    // Fingerprint of the session last written; None until the startup session
+    // is restored, so the first empty tab never overwrites it
+    pub session_saved: Option<u64>,
+    pub show_toolbar: bool,
+    pub mru_tab_switching: bool,
+    pub auto_hide_find: bool,
+    pub escape_order: EscapeOrder,
+    pub click_closes_bars: bool,
+    pub remember_cursor_position: bool,
+    // Caret, language, bookmarks and folds of each file, kept after it is closed
+    pub metadata: MetadataStore,
+    pub lock_documents: bool,
+    pub hibernate_tabs: bool,
+    pub notes_folder: Option<PathBuf>,
+    // Last file the selection was appended to; the next dialog opens in its folder
+    pub append_target: Option<PathBuf>,
+    // Command typed in the filter prompt, kept for the next time
+    pub filter_prompt: Option<String>,
+    pub script_picker: Option<ScriptPicker>,
+    pub qr_popup: Option<QrPopup>,
+    // Definitions and synonyms of a word
+    pub definition: Option<DefinitionPopup>,
+    // Dictionary chosen in the settings, once loaded, and the installed ones
+    pub spell_dictionary: Option<String>,
+    pub speller: Option<Arc<Speller>>,
+    pub spell_dictionaries: Vec<String>,
+    pub spell_suggestions: Option<SpellSuggestions>,
+    pub last_filter_command: String,
+    // Column typed in the hard wrap prompt while it is open, and the last one used
+    pub wrap_prompt: Option<String>,
+    pub last_wrap_width: usize,
+    // Macro being recorded, the last one recorded, and the repeat count typed
+    // in its prompt while it is open
+    pub macro_recording: Option<Vec<Message>>,
+    pub last_macro: Vec<Message>,
+    pub macro_prompt: Option<String>,
+    // Character picker while it is open, and the characters last inserted
+    // from it, most recent first
+    pub char_picker: Option<CharPicker>,
+    pub recent_characters: String,
+    // Format of the F5 timestamp, and whether its presets are unfolded in
+    // the Edit menu
+    pub date_format: String,
+    pub show_date_formats: bool,
+    pub url_paste: UrlPaste,
+    pub network_safe_save: bool,
+    pub network_autosave_minutes: u64,
+    pub slow_save_ms: u64,
+    // Modified documents with a file are written when the window loses focus
+    pub save_on_focus_loss: bool,
+    // Fewer timers and no background indexing, for laptops on battery
+    pub low_power: bool,
+    pub trim_trailing_whitespace: bool,
+    // Indentation given to new documents, until one is detected or configured
+    pub tab_width: usize,
+    pub insert_spaces: bool,
+    // Percent, applied through the window backend where supported
+    pub window_opacity: u8,
+    // UI scale in percent, and the display scaling of the monitor the window is on
+    pub ui_scale: u16,
+    pub system_scale: f32,
+    // Proxy of the network features, and the password typed in the settings
+    // until it is stored in the keychain
+    pub proxy: ProxySettings,
+    pub proxy_password: String,
+    // Text expansions typed in the editor, and whether their editor is open
+    pub abbreviations: Vec<Abbreviation>,
+    pub show_abbreviations: bool,
+    // Expanded by Tab, kept in their own file, and whether their editor is open
+    pub snippets: Vec<Snippet>,
+    pub show_snippets: bool,
+
+    // Ctrl+Tab switcher (MRU mode): tab indices in MRU order + highlighted entry
+    pub tab_switcher: Option<(Vec<usize>, usize)>,
+    pub activation_counter: u64,
+
+    // Background jobs (large loads / saves)
+    pub jobs: Jobs,
+    pub show_jobs: bool,
+
+    // Non-blocking notifications
+    pub toasts: Toasts,
+
+    // Merge view opened from the external modification banner
+    pub merge: Option<MergeSession>,
+
+    // Margins / header kept between previews; font size and wrap follow the editor
+    pub page_setup: PageSetup,
+    pub print_preview: Option<PrintPreview>,
+    pub print_queue: Option<PrintQueue>,
+    pub save_as: Option<SaveAsDialog>,
+
+    pub path_completion: Option<PathCompletion>,
+
+    pub color_picker: Option<ColorPicker>,
+
+    // Shell panel at the bottom of the window; the shell keeps running while hidden
+    pub show_terminal: bool,
+    pub terminal: Option<Terminal>,
+    pub terminal_sessions: u64,
 
     // Find & Replace (shared across tabs)
     pub show_find: bool,
@@ -273,6 +1900,17 @@ pub struct Notepad {
     pub find_cursor: usize,
     pub case_sensitive: bool,
     pub use_regex: bool,
+    // "Étendu" mode: \n, \t, \r, \0, \xNN are interpreted in both fields
+    pub extended_search: bool,
+    // Byte range searched by "Dans la sélection", None for the whole document
+    pub find_in_selection: Option<std::ops::Range<usize>>,
+    // Caret (byte offset) before search-as-you-type moved it; Escape goes back there
+    pub find_origin: Option<usize>,
+    pub find_matches: Option<MatchCount>,
+
+    // Alt+Left / Alt+Right history; `nav_jumping` marks a tab change made by it
+    pub nav_history: NavHistory,
+    pub nav_jumping: bool,
 
     // Go to line
     pub show_goto: bool,
@@ -284,9 +1922,58 @@ pub struct Notepad {
     // Settings modal
     pub show_settings: bool,
 
+    // Memory usage dialog
+    pub show_memory: bool,
+
+    // File properties dialog
+    pub properties: Option<FileProperties>,
+
+    // Named snapshots panel and the name typed for the next one
+    pub show_snapshots: bool,
+    pub snapshot_name: String,
+
+    // Document tags, favorites and recent files, with their editor and search
+    pub notes: NoteIndex,
+    pub tag_editor: Option<TagEditor>,
+    pub note_search: Option<NoteSearch>,
+
+    // Files of a dropped folder
+    pub folder_picker: Option<FolderPicker>,
+
+    // Second editor beside (or below) the first one
+    pub split: Option<Split>,
+    // Named sessions, and the name typed in the dialog while it is open
+    pub named_sessions: NamedSessions,
+    pub session_manager: Option<String>,
+    // Templates dialog, while it is open
+    pub template_manager: Option<TemplateManager>,
+
+    // Files last opened or saved, and whether their submenu is unfolded
+    pub recent_files: RecentFiles,
+    pub show_recent_files: bool,
+    pub show_reopen_encodings: bool,
+    pub show_bookmarks_menu: bool,
+
+    // Keyboard shortcut cheat sheet (F1) and its search query
+    pub shortcut_sheet: Option<String>,
+    // Bindings in use, their editor in the settings and the action waiting
+    // for its new keys
+    pub keymap: Keymap,
+    pub show_keybindings: bool,
+    pub rebinding: Option<Action>,
+
+    // About dialog and the last update check
+    pub show_about: bool,
+    pub update_check: UpdateCheck,
+
     // Menu state
     pub active_menu: Option<Menu>,
     pub show_context_menu: bool,
+    pub show_indent_menu: bool,
+    pub show_language_menu: bool,
+    pub show_tab_list: bool,
+    pub show_outline: bool,
+    pub outline_level: u8,
     pub mouse_position: iced::Point,
     pub context_menu_position: iced::Point,
 }
@@ -296,14 +1983,84 @@ impl Default for Notepad {
         Self {
             tabs: vec![Document::default()],
             active_tab: 0,
-            clipboard: arboard::Clipboard::new().ok(),
+            tab_scroll: 0,
+            clipboard: None,
+            launched: Instant::now(),
+            last_activity: Instant::now(),
+            cli_files: Vec::new(),
             font_size: DEFAULT_FONT_SIZE,
             font_family: crate::DEFAULT_FONT_FAMILY.to_string(),
             dark_mode: false,
             word_wrap: true,
+            prose_ligatures: true,
+            code_ligatures: true,
+            show_ink: false,
             window_width: DEFAULT_WINDOW_WIDTH,
             window_height: DEFAULT_WINDOW_HEIGHT,
             restore_session: true,
+            session_saved: None,
+            show_toolbar: false,
+            mru_tab_switching: false,
+            auto_hide_find: false,
+            escape_order: EscapeOrder::PanelsOnly,
+            click_closes_bars: true,
+            remember_cursor_position: false,
+            metadata: MetadataStore::default(),
+            lock_documents: false,
+            hibernate_tabs: false,
+            notes_folder: None,
+            append_target: None,
+            filter_prompt: None,
+            script_picker: None,
+            qr_popup: None,
+            definition: None,
+            spell_dictionary: None,
+            speller: None,
+            spell_dictionaries: Vec::new(),
+            spell_suggestions: None,
+            last_filter_command: String::new(),
+            wrap_prompt: None,
+            last_wrap_width: reflow::DEFAULT_WRAP_WIDTH,
+            macro_recording: None,
+            last_macro: Vec::new(),
+            macro_prompt: None,
+            char_picker: None,
+            recent_characters: String::new(),
+            date_format: datetime::DEFAULT_FORMAT.to_string(),
+            show_date_formats: false,
+            url_paste: UrlPaste::Selection,
+            network_safe_save: true,
+            network_autosave_minutes: 5,
+            slow_save_ms: 1000,
+            save_on_focus_loss: false,
+            low_power: false,
+            trim_trailing_whitespace: false,
+            tab_width: DEFAULT_INDENT_SIZE,
+            insert_spaces: false,
+            window_opacity: 100,
+            ui_scale: 100,
+            system_scale: 1.0,
+            proxy: ProxySettings::default(),
+            proxy_password: String::new(),
+            abbreviations: Vec::new(),
+            show_abbreviations: false,
+            snippets: Vec::new(),
+            show_snippets: false,
+            tab_switcher: None,
+            activation_counter: 0,
+            jobs: Jobs::default(),
+            show_jobs: false,
+            toasts: Toasts::default(),
+            merge: None,
+            page_setup: PageSetup::default(),
+            print_preview: None,
+            print_queue: None,
+            save_as: None,
+            path_completion: None,
+            color_picker: None,
+            show_terminal: false,
+            terminal: None,
+            terminal_sessions: 0,
             show_find: false,
             show_replace: false,
             find_query: String::new(),
@@ -311,12 +2068,45 @@ impl Default for Notepad {
             find_cursor: 0,
             case_sensitive: true,
             use_regex: false,
+            extended_search: false,
+            find_in_selection: None,
+            find_origin: None,
+            find_matches: None,
+            nav_history: NavHistory::default(),
+            nav_jumping: false,
             show_goto: false,
             goto_input: String::new(),
             ctrl_pressed: false,
             show_settings: false,
+            show_memory: false,
+            properties: None,
+            show_about: false,
+            shortcut_sheet: None,
+            keymap: Keymap::default(),
+            show_keybindings: false,
+            rebinding: None,
+            show_snapshots: false,
+            snapshot_name: String::new(),
+            notes: NoteIndex::default(),
+            tag_editor: None,
+            note_search: None,
+            folder_picker: None,
+            split: None,
+            named_sessions: NamedSessions::default(),
+            session_manager: None,
+            template_manager: None,
+            recent_files: RecentFiles::default(),
+            show_recent_files: false,
+            show_reopen_encodings: false,
+            show_bookmarks_menu: false,
+            update_check: UpdateCheck::Idle,
             active_menu: None,
             show_context_menu: false,
+            show_indent_menu: false,
+            show_language_menu: false,
+            show_tab_list: false,
+            show_outline: false,
+            outline_level: 6,
             mouse_position: iced::Point::ORIGIN,
             context_menu_position: iced::Point::ORIGIN,
         }
@@ -329,65 +2119,125 @@ impl Notepad {
         Self::default()
     }
 
-    pub fn new() -> (Self, Task<Message>) {
-        let prefs = UserPreferences::load();
+    // Preferences are read once by main (the window size depends on them); the
+    // session and the files it lists are loaded by a startup task
+    pub fn new(prefs: UserPreferences, launched: Instant, cli_files: Vec<PathBuf>) -> (Self, Task<Message>) {
+        let restore_session = prefs.restore_session;
         let mut notepad = Self {
             font_size: prefs.font_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE),
             font_family: prefs.font_family,
             dark_mode: prefs.dark_mode,
             word_wrap: prefs.word_wrap,
+            prose_ligatures: prefs.prose_ligatures,
+            code_ligatures: prefs.code_ligatures,
             window_width: prefs.window_width,
             window_height: prefs.window_height,
             restore_session: prefs.restore_session,
+            show_toolbar: prefs.show_toolbar,
+            mru_tab_switching: prefs.mru_tab_switching,
+            auto_hide_find: prefs.auto_hide_find,
+            escape_order: prefs.escape_order,
+            click_closes_bars: prefs.click_closes_bars,
+            remember_cursor_position: prefs.remember_cursor_position,
+            lock_documents: prefs.lock_documents,
+            hibernate_tabs: prefs.hibernate_tabs,
+            notes_folder: prefs.notes_folder,
+            network_safe_save: prefs.network_safe_save,
+            network_autosave_minutes: prefs.network_autosave_minutes,
+            slow_save_ms: prefs.slow_save_ms,
+            save_on_focus_loss: prefs.save_on_focus_loss,
+            low_power: prefs.low_power,
+            trim_trailing_whitespace: prefs.trim_trailing_whitespace,
+            tab_width: prefs.tab_width.max(1),
+            insert_spaces: prefs.insert_spaces,
+            window_opacity: prefs.window_opacity.clamp(MIN_WINDOW_OPACITY, 100),
+            ui_scale: prefs.ui_scale,
+            proxy: prefs.proxy,
+            abbreviations: prefs.abbreviations,
+            spell_dictionary: prefs.spell_dictionary,
+            recent_characters: prefs.recent_characters,
+            date_format: prefs.date_format,
+            url_paste: prefs.url_paste,
+            launched,
+            cli_files,
             ..Self::default()
         };
-
-        if prefs.restore_session {
-            let session = SessionData::load();
-            if !session.tabs.is_empty() {
-                notepad.restore_session_data(&session);
-                SessionData::clear();
-            }
+        notepad.tabs[0] = notepad.new_document();
+        let task = Task::perform(
+            jobs::spawn_blocking(move || Self::load_startup(restore_session)),
+            |data| Message::StartupLoaded(Box::new(data.unwrap_or_default())),
+        );
+        // Scale changes arrive as events; the starting one is asked for
+        let scale = iced::window::latest()
+            .and_then(iced::window::scale_factor)
+            .map(|scale| Message::View(ViewMsg::SystemScale(scale)));
+        let task = Task::batch([task, notepad.load_speller(), scale]);
+        if notepad.window_opacity < 100 {
+            let opacity = opacity::apply(notepad.window_opacity);
+            return (notepad, Task::batch([task, opacity]));
         }
-
-        (notepad, Task::none())
+        (notepad, task)
     }
 
-    fn restore_session_data(&mut self, session: &SessionData) {
+    // `files` holds the decoded content of each session tab with a file, by tab index
+    pub fn restore_session_data(&mut self, session: &SessionData, mut files: Vec<Option<LoadedFile>>) {
         let mut restored = Vec::new();
-
-        for tab in &session.tabs {
+        // Text typed while the session was loading is kept
+        let initial = &self.tabs[0];
+        let keep_initial =
+            self.tabs.len() > 1 || initial.is_modified || initial.file_path.is_some();
+
+        for (index, tab) in session.tabs.iter().enumerate() {
+            let view = ViewState {
+                line: tab.cursor_line,
+                column: tab.cursor_column,
+                scroll: tab.scroll_offset,
+            };
             if let Some(ref path) = tab.file_path {
-                if path.exists() {
-                    // File tab — load from disk
-                    self.tabs.push(Document::default());
-                    self.active_tab = self.tabs.len() - 1;
-                    self.load_from_file_silent(path.clone());
-                    // If saved session had unsaved changes, overlay the content
-                    if tab.is_modified {
-                        if let Some(ref content) = tab.unsaved_content {
-                            let doc = self.active_doc_mut();
-                            doc.content = text_editor::Content::with_text(content);
-                            doc.is_modified = true;
-                            doc.update_stats_cache();
-                        }
+                // The files were read off the UI thread: nothing touches the disk
+                // here, so an unreachable network share cannot stall the startup
+                let loaded = files.get_mut(index).and_then(Option::take);
+                let unsaved = tab.unsaved_content.as_ref().filter(|_| tab.is_modified);
+                if loaded.is_none() && unsaved.is_none() {
+                    continue;
+                }
+                self.tabs.push(self.new_document());
+                self.active_tab = self.tabs.len() - 1;
+                self.active_doc_mut().pending_view = Some(view);
+                match loaded {
+                    Some(loaded) => self.apply_loaded_silent(path.clone(), loaded),
+                    // Unsaved changes to a file out of reach stay attached to it
+                    None => {
+                        let language = self.metadata.language(path);
+                        let doc = self.active_doc_mut();
+                        doc.file_path = Some(path.clone());
+                        doc.network = paths::is_network(path);
+                        doc.language_override = language;
                     }
-                    restored.push(self.tabs.len() - 1);
                 }
+                // If saved session had unsaved changes, overlay the content
+                if let Some(content) = unsaved {
+                    let doc = self.active_doc_mut();
+                    doc.set_text(content);
+                    doc.is_modified = true;
+                    doc.update_stats_cache();
+                }
+                restored.push(self.tabs.len() - 1);
             } else if let Some(ref content) = tab.unsaved_content {
                 // "Sans titre" tab with unsaved content
                 let mut doc = Document {
-                    content: text_editor::Content::with_text(content),
                     is_modified: true,
-                    ..Document::default()
+                    pending_view: Some(view),
+                    ..self.new_document()
                 };
+                doc.set_text(content);
                 doc.update_stats_cache();
                 self.tabs.push(doc);
                 restored.push(self.tabs.len() - 1);
             }
         }
 
-        if !restored.is_empty() {
+        if !restored.is_empty() && !keep_initial {
             // Remove the initial empty default tab
             self.tabs.remove(0);
             self.active_tab = session
@@ -396,12 +2246,64 @@ impl Notepad {
         }
     }
 
+    pub fn tab_widths(&self) -> Vec<f32> {
+        self.tabs
+            .iter()
+            .map(|doc| {
+                tab_label_width(&doc.title_label())
+                    + doc.archive.as_ref().map_or(0.0, |a| tab_badge_width(a.badge()))
+            })
+            .collect()
+    }
+
+    pub fn tab_strip_width(&self) -> f32 {
+        self.window_width - TAB_STRIP_RESERVED_WIDTH
+    }
+
+    pub fn tabs_overflow(&self) -> bool {
+        self.tab_widths().iter().sum::<f32>() > self.tab_strip_width()
+    }
+
+    // Tab indices, most recently activated first
+    pub fn mru_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.tabs.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.tabs[i].last_active));
+        order
+    }
+
+    // Tab indices sorted by file name, then folder, for the Window menu
+    pub fn name_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.tabs.len()).collect();
+        order.sort_by_cached_key(|&i| {
+            let doc = &self.tabs[i];
+            (doc.title_label().to_lowercase(), doc.file_path.clone())
+        });
+        order
+    }
+
     pub fn active_doc(&self) -> &Document {
         &self.tabs[self.active_tab]
     }
 
+    // Empty document indented as the settings say
+    pub fn new_document(&self) -> Document {
+        Document {
+            indent_style: if self.insert_spaces { IndentStyle::Space } else { IndentStyle::Tab },
+            indent_size: self.tab_width,
+            ..Document::default()
+        }
+    }
+
+    // Wakes the tab first if it was hibernated
     pub fn active_doc_mut(&mut self) -> &mut Document {
-        &mut self.tabs[self.active_tab]
+        let doc = &mut self.tabs[self.active_tab];
+        if let Err(e) = doc.wake() {
+            doc.status_message = Some(match doc.hibernation {
+                Some(_) => format!("Impossible de restaurer l'onglet : {e}"),
+                None => format!("Historique d'annulation perdu : {e}"),
+            });
+        }
+        doc
     }
 
     pub fn title(&self) -> String {
@@ -413,7 +2315,24 @@ impl Notepad {
             .and_then(|n| n.to_str())
             .unwrap_or("Sans titre");
         let modified = if doc.is_modified { " *" } else { "" };
-        format!("{name}{modified} - Notepad")
+        let session = self
+            .named_sessions
+            .current
+            .as_ref()
+            .map_or(String::new(), |s| format!(" [{s}]"));
+        match self.jobs.overall_progress() {
+            Some(p) => format!("[{} %] {name}{modified} - Notepad{session}", (p * 100.0) as u32),
+            None => format!("{name}{modified} - Notepad{session}"),
+        }
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        dpi::ui_scale_factor(self.ui_scale)
+    }
+
+    // Device pixels per layout unit
+    pub fn pixel_scale(&self) -> f32 {
+        self.system_scale * self.scale_factor()
     }
 
     pub fn theme(&self) -> Theme {
@@ -424,29 +2343,90 @@ impl Notepad {
         }
     }
 
+    // The subscriptions are rebuilt after each message, so the first tick past
+    // the idle delay slows the timers down and the next input brings them back
+    pub fn is_idle(&self) -> bool {
+        self.last_activity.elapsed() >= Duration::from_secs(IDLE_AFTER_SECS)
+    }
+
     pub fn subscription(&self) -> Subscription<Message> {
+        let idle = self.is_idle();
+        let refresh_ms = if self.low_power { LOW_POWER_REFRESH_MS } else { 500 };
         let mut subs = vec![
             iced::event::listen().map(Message::EventOccurred),
             iced::window::close_requests()
                 .map(|id| Message::File(FileMsg::CloseRequested(id))),
         ];
-        // Auto-save if any tab is modified and has a file path
-        let any_modified = self
-            .tabs
-            .iter()
-            .any(|doc| doc.is_modified && doc.file_path.is_some());
-        if any_modified {
+        // Auto-save if any tab is modified and has a file path; files on
+        // network shares are saved less often
+        let modified = |network: bool| {
+            self.tabs
+                .iter()
+                .any(|doc| doc.is_modified && doc.file_path.is_some() && doc.network == network)
+        };
+        if modified(false) {
             subs.push(
-                iced::time::every(Duration::from_secs(30))
+                iced::time::every(Duration::from_secs(AUTOSAVE_INTERVAL_SECS))
                     .map(|_| Message::File(FileMsg::AutoSave)),
             );
         }
-        // File watching: poll every 5 seconds if any tab has a file
-        let any_file = self.tabs.iter().any(|doc| doc.file_path.is_some());
-        if any_file {
+        if modified(true) {
             subs.push(
-                iced::time::every(Duration::from_secs(5))
-                    .map(|_| Message::File(FileMsg::CheckExternalChanges)),
+                iced::time::every(Duration::from_secs(self.network_autosave_minutes.max(1) * 60))
+                    .map(|_| Message::File(FileMsg::AutoSaveNetwork)),
+            );
+        }
+        // Session written periodically too, so a crash keeps the tab list; it
+        // does not change while nobody uses the app
+        if self.restore_session && self.session_saved.is_some() && !idle {
+            subs.push(
+                iced::time::every(Duration::from_secs(SESSION_AUTOSAVE_SECS))
+                    .map(|_| Message::File(FileMsg::AutoSaveSession)),
+            );
+        }
+        // File watching: the folders of local files are watched; shares send
+        // no reliable notifications, so their files are polled every 5 seconds,
+        // once a minute when idle or in low power mode
+        let folders = crate::watch::Folders::of(
+            self.tabs
+                .iter()
+                .filter(|doc| !doc.network)
+                .filter_map(|doc| doc.file_path.as_deref()),
+        );
+        if !folders.is_empty() {
+            subs.push(
+                Subscription::run_with(folders, crate::watch::changes)
+                    .map(|path| Message::File(FileMsg::ChangedOnDisk(path))),
+            );
+        }
+        if self.tabs.iter().any(|doc| doc.network && doc.file_path.is_some()) {
+            subs.push(
+                iced::time::every(Duration::from_secs(if idle || self.low_power {
+                    IDLE_NETWORK_CHECK_SECS
+                } else {
+                    NETWORK_CHECK_SECS
+                }))
+                .map(|_| Message::File(FileMsg::CheckExternalChanges)),
+            );
+        }
+        // Syntax check of the active configuration file, once typing pauses
+        if self.active_doc().needs_syntax_check() {
+            subs.push(
+                iced::time::every(Duration::from_millis(refresh_ms.max(SYNTAX_CHECK_IDLE_MS)))
+                    .map(|_| Message::Edit(EditMsg::CheckSyntax)),
+            );
+        }
+        if self.low_power && self.tabs.iter().any(Document::stats_stale) {
+            subs.push(
+                iced::time::every(Duration::from_millis(LOW_POWER_REFRESH_MS))
+                    .map(|_| Message::Edit(EditMsg::RefreshStats)),
+            );
+        }
+        // Toast expiry
+        if !self.toasts.is_empty() {
+            subs.push(
+                iced::time::every(Duration::from_millis(refresh_ms))
+                    .map(|_| Message::Toast(ToastMsg::Tick)),
             );
         }
         Subscription::batch(subs)
@@ -480,6 +2460,60 @@ mod tests {
         assert_eq!(LineEnding::detect("a\nb\r\nc"), LineEnding::CrLf);
     }
 
+    #[test]
+    fn detect_cr_only() {
+        assert_eq!(LineEnding::detect("a\rb\rc"), LineEnding::Cr);
+        assert_eq!(LineEnding::detect("a\rb\nc"), LineEnding::Lf);
+    }
+
+    // --- Document::encode_content ---
+
+    #[test]
+    fn utf16_is_written_with_its_bom() {
+        let mut doc = Document::default();
+        doc.set_text("é");
+        doc.encoding = encoding_rs::UTF_16LE;
+        assert_eq!(doc.encode_content().unwrap(), vec![0xFF, 0xFE, 0xE9, 0x00]);
+        doc.encoding = encoding_rs::UTF_16BE;
+        assert_eq!(doc.encode_content().unwrap(), vec![0xFE, 0xFF, 0x00, 0xE9]);
+    }
+
+    #[test]
+    fn utf8_bom_is_kept_when_asked() {
+        let mut doc = Document::default();
+        doc.set_text("a");
+        assert_eq!(doc.encode_content().unwrap(), b"a");
+        doc.bom = true;
+        assert_eq!(doc.encode_content().unwrap(), b"\xEF\xBB\xBFa");
+    }
+
+    #[test]
+    fn lf_cr_breaks_are_written_back() {
+        let mut doc = Document::default();
+        doc.set_text("a\n\rb\r\nc");
+        assert_eq!(doc.encode_content().unwrap(), b"a\n\rb\r\nc");
+    }
+
+    // --- SaveEncoding ---
+
+    #[test]
+    fn save_encodings_match_the_document() {
+        for choice in SaveEncoding::ALL {
+            assert_eq!(SaveEncoding::of(choice.encoding(), choice.bom()), choice);
+        }
+        assert_eq!(
+            SaveEncoding::of(encoding_rs::SHIFT_JIS, false),
+            SaveEncoding::Other(encoding_rs::SHIFT_JIS)
+        );
+    }
+
+    #[test]
+    fn unmappable_chars_are_listed_once() {
+        let text = "café € → ★ → 日本";
+        assert_eq!(unmappable_chars(text, encoding_rs::WINDOWS_1252, 3), vec!['→', '★', '日']);
+        assert!(unmappable_chars(text, encoding_rs::UTF_16LE, 3).is_empty());
+    }
+
     // --- LineEnding::label ---
 
     #[test]
@@ -492,6 +2526,82 @@ mod tests {
         assert_eq!(LineEnding::CrLf.label(), "CRLF");
     }
 
+    // --- IndentStyle::detect ---
+
+    #[test]
+    fn detect_indent_none_when_flat() {
+        assert_eq!(IndentStyle::detect("a\nb\n\nc"), None);
+    }
+
+    #[test]
+    fn detect_indent_tabs() {
+        assert_eq!(
+            IndentStyle::detect("fn a() {\n\tx;\n\tif y {\n\t\tz;\n\t}\n}"),
+            Some((IndentStyle::Tab, DEFAULT_INDENT_SIZE))
+        );
+    }
+
+    #[test]
+    fn detect_indent_two_spaces() {
+        let text = "a:\n  b:\n    c: 1\n    d: 2\n  e: 3\n";
+        assert_eq!(IndentStyle::detect(text), Some((IndentStyle::Space, 2)));
+    }
+
+    #[test]
+    fn detect_indent_four_spaces_ignores_comment_alignment() {
+        let text = "/**\n * doc\n */\nfn a() {\n    if b {\n        c();\n    }\n}\n";
+        assert_eq!(IndentStyle::detect(text), Some((IndentStyle::Space, 4)));
+    }
+
+    // --- Document::indent_label ---
+
+    #[test]
+    fn indent_label_tabs_and_spaces() {
+        let mut doc = Document::default();
+        assert_eq!(doc.indent_label(), "Tabulations");
+        doc.indent_style = IndentStyle::Space;
+        doc.indent_size = 2;
+        assert_eq!(doc.indent_label(), "Espaces: 2");
+    }
+
+    // --- Tab strip ---
+
+    #[test]
+    fn visible_tab_start_zero_when_everything_fits() {
+        assert_eq!(visible_tab_start(&[50.0, 50.0], 1, 1, 200.0), 0);
+    }
+
+    #[test]
+    fn visible_tab_start_moves_left_to_active() {
+        let widths = [100.0; 10];
+        assert_eq!(visible_tab_start(&widths, 5, 2, 300.0), 2);
+    }
+
+    #[test]
+    fn visible_tab_start_moves_right_until_active_fits() {
+        let widths = [100.0; 10];
+        assert_eq!(visible_tab_start(&widths, 0, 7, 300.0), 5);
+    }
+
+    #[test]
+    fn visible_tab_start_keeps_offset_when_active_visible() {
+        let widths = [100.0; 10];
+        assert_eq!(visible_tab_start(&widths, 3, 4, 300.0), 3);
+    }
+
+    // --- MRU order ---
+
+    #[test]
+    fn mru_order_sorts_by_last_activation() {
+        let mut n = Notepad::test_default();
+        n.tabs.push(Document::default());
+        n.tabs.push(Document::default());
+        n.tabs[0].last_active = 1;
+        n.tabs[1].last_active = 3;
+        n.tabs[2].last_active = 2;
+        assert_eq!(n.mru_order(), vec![1, 2, 0]);
+    }
+
     // --- Document::title_label ---
 
     #[test]
@@ -502,15 +2612,19 @@ mod tests {
 
     #[test]
     fn doc_title_with_file() {
-        let mut doc = Document::default();
-        doc.file_path = Some(PathBuf::from("/tmp/test.txt"));
+        let doc = Document {
+            file_path: Some(PathBuf::from("/tmp/test.txt")),
+            ..Document::default()
+        };
         assert_eq!(doc.title_label(), "test.txt");
     }
 
     #[test]
     fn doc_title_modified() {
-        let mut doc = Document::default();
-        doc.is_modified = true;
+        let doc = Document {
+            is_modified: true,
+            ..Document::default()
+        };
         assert_eq!(doc.title_label(), "Sans titre *");
     }
 
@@ -544,4 +2658,23 @@ mod tests {
         doc.is_modified = true;
         assert_eq!(n.title(), "test.txt * - Notepad");
     }
+
+    #[test]
+    fn match_count_labels() {
+        let count = |current, total| MatchCount { current, total }.label();
+        assert_eq!(count(Some(2), 17), "3/17");
+        assert_eq!(count(None, 0), "Aucun résultat");
+        assert_eq!(count(None, 1), "1 résultat");
+        assert_eq!(count(None, 5), "5 résultats");
+    }
+
+    #[test]
+    fn title_shows_job_progress() {
+        let mut n = Notepad::test_default();
+        let (id, _) = n.jobs.spawn("Chargement", true, |_| Task::<Message>::none());
+        n.jobs.set_progress(id, 0.42);
+        assert_eq!(n.title(), "[42 %] Sans titre - Notepad");
+        n.jobs.finish(id);
+        assert_eq!(n.title(), "Sans titre - Notepad");
+    }
 }
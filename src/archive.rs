@@ -0,0 +1,238 @@
+use flate2::read::GzDecoder;
+use flate2::{Compression, GzBuilder};
+use std::io::{self, Cursor, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+// --- Compressed files edited transparently: unpacked on load, packed again on save ---
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZIP_MAGIC: [u8; 4] = *b"PK\x03\x04";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Archive {
+    Gzip {
+        level: u32,
+        filename: Option<Vec<u8>>,
+        mtime: u32,
+    },
+    // Single-file zip: the entry is rewritten with the same name and compression
+    Zip {
+        entry: String,
+        method: CompressionMethod,
+        level: Option<i64>,
+        modified: Option<DateTime>,
+        unix_mode: Option<u32>,
+    },
+}
+
+impl Archive {
+    pub fn badge(&self) -> &'static str {
+        match self {
+            Archive::Gzip { .. } => "gz",
+            Archive::Zip { .. } => "zip",
+        }
+    }
+
+    // Save As to another extension writes plain text
+    pub fn matches_path(&self, path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(self.badge()))
+    }
+
+    pub fn pack(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Archive::Gzip {
+                level,
+                filename,
+                mtime,
+            } => {
+                let mut builder = GzBuilder::new().mtime(*mtime);
+                if let Some(name) = filename {
+                    builder = builder.filename(name.clone());
+                }
+                let mut encoder = builder.write(Vec::new(), Compression::new(*level));
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Archive::Zip {
+                entry,
+                method,
+                level,
+                modified,
+                unix_mode,
+            } => {
+                let mut options = SimpleFileOptions::default()
+                    .compression_method(*method)
+                    .compression_level(*level);
+                if let Some(modified) = modified {
+                    options = options.last_modified_time(*modified);
+                }
+                if let Some(mode) = unix_mode {
+                    options = options.unix_permissions(*mode);
+                }
+                let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+                writer.start_file(entry.as_str(), options).map_err(io::Error::other)?;
+                writer.write_all(data)?;
+                Ok(writer.finish().map_err(io::Error::other)?.into_inner())
+            }
+        }
+    }
+}
+
+// Returns the inner bytes, or None when `bytes` is not a supported archive
+pub fn unpack(bytes: &[u8]) -> Result<Option<(Archive, Vec<u8>)>, String> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        return unpack_gzip(bytes).map(Some);
+    }
+    if bytes.starts_with(&ZIP_MAGIC) {
+        return unpack_zip(bytes).map(Some);
+    }
+    Ok(None)
+}
+
+fn unpack_gzip(bytes: &[u8]) -> Result<(Archive, Vec<u8>), String> {
+    let mut decoder = GzDecoder::new(bytes);
+    let mut data = Vec::new();
+    decoder
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Archive gzip invalide : {e}"))?;
+    let header = decoder.header().cloned().unwrap_or_default();
+    // XFL byte: 2 = best compression, 4 = fastest
+    let level = match bytes.get(8) {
+        Some(2) => 9,
+        Some(4) => 1,
+        _ => Compression::default().level(),
+    };
+    let archive = Archive::Gzip {
+        level,
+        filename: header.filename().map(<[u8]>::to_vec),
+        mtime: header.mtime(),
+    };
+    Ok((archive, data))
+}
+
+fn unpack_zip(bytes: &[u8]) -> Result<(Archive, Vec<u8>), String> {
+    let invalid = |e: zip::result::ZipError| format!("Archive zip invalide : {e}");
+    let mut zip = ZipArchive::new(Cursor::new(bytes)).map_err(invalid)?;
+    let files: Vec<usize> = (0..zip.len())
+        .filter(|&i| zip.by_index_raw(i).is_ok_and(|f| !f.is_dir()))
+        .collect();
+    let [index] = files[..] else {
+        return Err(format!(
+            "L'archive contient {} fichiers : seules les archives d'un seul fichier peuvent être ouvertes",
+            files.len()
+        ));
+    };
+    let mut file = zip.by_index(index).map_err(invalid)?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)
+        .map_err(|e| format!("Archive zip invalide : {e}"))?;
+    let method = file.compression();
+    let archive = Archive::Zip {
+        entry: file.name().to_string(),
+        method,
+        level: zip_deflate_level(bytes, method),
+        modified: file.last_modified(),
+        unix_mode: file.unix_mode(),
+    };
+    Ok((archive, data))
+}
+
+// Deflate option bits of the first local header's flags (single-file archives start with it)
+fn zip_deflate_level(bytes: &[u8], method: CompressionMethod) -> Option<i64> {
+    if method != CompressionMethod::Deflated {
+        return None;
+    }
+    let flags = u16::from_le_bytes([*bytes.get(6)?, *bytes.get(7)?]);
+    match (flags >> 1) & 0b11 {
+        0b01 => Some(9),
+        0b10 | 0b11 => Some(1),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &[u8] = b"bonjour\nle monde\n";
+
+    fn gzip(level: u32) -> Vec<u8> {
+        let mut encoder = GzBuilder::new()
+            .filename("notes.txt")
+            .mtime(1_700_000_000)
+            .write(Vec::new(), Compression::new(level));
+        encoder.write_all(TEXT).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn zip_with(entries: &[&str], method: CompressionMethod) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(method);
+        for name in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(TEXT).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn plain_text_is_not_an_archive() {
+        assert_eq!(unpack(TEXT).unwrap(), None);
+    }
+
+    #[test]
+    fn gzip_round_trip_keeps_header() {
+        let (archive, data) = unpack(&gzip(9)).unwrap().unwrap();
+        assert_eq!(data, TEXT);
+        assert_eq!(
+            archive,
+            Archive::Gzip {
+                level: 9,
+                filename: Some(b"notes.txt".to_vec()),
+                mtime: 1_700_000_000,
+            }
+        );
+        let packed = archive.pack(b"modifie\n").unwrap();
+        let (again, data) = unpack(&packed).unwrap().unwrap();
+        assert_eq!(data, b"modifie\n");
+        assert_eq!(again, archive);
+    }
+
+    #[test]
+    fn fast_gzip_level_is_detected() {
+        let (archive, _) = unpack(&gzip(1)).unwrap().unwrap();
+        assert!(matches!(archive, Archive::Gzip { level: 1, .. }));
+    }
+
+    #[test]
+    fn zip_round_trip_keeps_entry_and_method() {
+        let bytes = zip_with(&["docs/readme.txt"], CompressionMethod::Stored);
+        let (archive, data) = unpack(&bytes).unwrap().unwrap();
+        assert_eq!(data, TEXT);
+        let packed = archive.pack(b"nouveau\n").unwrap();
+        let (again, data) = unpack(&packed).unwrap().unwrap();
+        assert_eq!(data, b"nouveau\n");
+        let Archive::Zip { entry, method, .. } = again else {
+            panic!("expected a zip archive");
+        };
+        assert_eq!(entry, "docs/readme.txt");
+        assert_eq!(method, CompressionMethod::Stored);
+    }
+
+    #[test]
+    fn multi_file_zip_is_rejected() {
+        let bytes = zip_with(&["a.txt", "b.txt"], CompressionMethod::Deflated);
+        let err = unpack(&bytes).unwrap_err();
+        assert!(err.contains("2 fichiers"));
+    }
+
+    #[test]
+    fn archive_matches_its_extension() {
+        let (archive, _) = unpack(&gzip(6)).unwrap().unwrap();
+        assert!(archive.matches_path(Path::new("/tmp/notes.txt.GZ")));
+        assert!(!archive.matches_path(Path::new("/tmp/notes.txt")));
+    }
+}
@@ -0,0 +1,322 @@
+use iced::widget::text_editor::{self, Content};
+use ropey::Rope;
+use std::cell::OnceCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::search_index::SearchIndex;
+
+// Unique across mirrors, so an index built for a replaced text is never taken for current
+fn next_version() -> u64 {
+    static VERSION: AtomicU64 = AtomicU64::new(1);
+    VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
+// Rope copy of an editor buffer, kept in sync edit by edit so that search,
+// position mapping and counts don't have to copy the whole text out of the editor.
+// Lines are split where the editor splits them: on LF, CRLF and a lone CR.
+#[derive(Debug, Clone, Default)]
+pub struct TextMirror {
+    rope: Rope,
+    words: usize,
+    // Bumped on every change, to tell whether a background index or a copy
+    // of the text is still current
+    version: u64,
+    // Whole text, copied out of the rope on first use after a change
+    text: OnceCell<Arc<str>>,
+    index: Option<SearchIndex>,
+    // Version an index is being built for
+    indexing: Option<u64>,
+    // The rope could not hold the editor's line breaks as they are: LF CR,
+    // held as LF, or a CR line followed by an empty LF line, read as CRLF.
+    // The exact text then has to come from the editor
+    inexact: bool,
+}
+
+impl TextMirror {
+    pub fn new(text: &str) -> Self {
+        Self {
+            rope: Rope::from_str(text),
+            words: count_words(text.chars()),
+            version: next_version(),
+            ..Self::default()
+        }
+    }
+
+    // Rebuilds the mirror from the editor, when it cannot be kept in sync
+    pub fn from_content(content: &Content) -> Self {
+        let (text, lf_cr) = content_lines(content, 0, content.line_count());
+        let mirror = Self::new(&text);
+        Self {
+            inexact: lf_cr || mirror.line_count() != content.line_count(),
+            ..mirror
+        }
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        self.rope.len_bytes()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.rope.len_lines()
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.words
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    // Whether `text` is the editor's text, until the mirror is rebuilt
+    pub fn is_exact(&self) -> bool {
+        !self.inexact
+    }
+
+    // Rope, cached copy and search index
+    pub fn memory_usage(&self) -> usize {
+        self.rope.capacity()
+            + self.text.get().map_or(0, |text| text.len())
+            + self.index.as_ref().map_or(0, SearchIndex::memory_usage)
+    }
+
+    pub fn cached_text(&self) -> Option<&Arc<str>> {
+        self.text.get()
+    }
+
+    // Shared copy of the whole text, made once per version
+    pub fn text(&self) -> Arc<str> {
+        self.text
+            .get_or_init(|| Arc::from(self.rope.to_string()))
+            .clone()
+    }
+
+    // Line text without its line ending
+    pub fn line(&self, index: usize) -> String {
+        let line = self.rope.line(index).to_string();
+        let len = line.trim_end_matches(['\r', '\n']).len();
+        line[..len].to_string()
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.line_count()).map(|i| self.line(i))
+    }
+
+    pub fn slice_bytes(&self, start: usize, end: usize) -> String {
+        self.rope.byte_slice(start..end).to_string()
+    }
+
+    // Cheap copy sharing the rope's nodes
+    pub fn rope(&self) -> Rope {
+        self.rope.clone()
+    }
+
+    pub fn search_index(&self) -> Option<&SearchIndex> {
+        self.index.as_ref()
+    }
+
+    // Snapshot to index in the background, when the text has at least
+    // `min_bytes` and no index is ready or being built
+    pub fn index_job(&mut self, min_bytes: usize) -> Option<(u64, Rope)> {
+        if self.len_bytes() < min_bytes || self.index.is_some() || self.indexing.is_some() {
+            return None;
+        }
+        self.indexing = Some(self.version);
+        Some((self.version, self.rope.clone()))
+    }
+
+    // Installs an index built by `index_job`, unless the text changed since
+    // (the next `index_job` then starts over)
+    pub fn set_index(&mut self, version: u64, index: SearchIndex) {
+        if self.indexing == Some(version) {
+            self.indexing = None;
+        }
+        if version == self.version {
+            self.index = Some(index);
+        }
+    }
+
+    // Line and column (in bytes, like the editor cursor) of a byte offset
+    pub fn byte_to_position(&self, byte: usize) -> (usize, usize) {
+        let byte = byte.min(self.rope.len_bytes());
+        let line = self.rope.byte_to_line(byte);
+        (line, byte - self.rope.line_to_byte(line))
+    }
+
+    // Byte offset of a line and byte column, clamped to the text
+    pub fn position_to_byte(&self, line: usize, column: usize) -> usize {
+        let line = line.min(self.line_count().saturating_sub(1));
+        (self.rope.line_to_byte(line) + column).min(self.rope.len_bytes())
+    }
+
+    // Copies lines `start..=end` (as they were before an edit) from the edited
+    // content, where they became `start..=end + added` lines
+    pub fn sync_lines(&mut self, content: &Content, start: usize, end: usize) {
+        let old_count = self.line_count();
+        let new_count = content.line_count();
+        let end = end.min(old_count - 1);
+        let new_end = (end + new_count).checked_sub(old_count);
+        let Some(new_end) = new_end.filter(|&e| e >= start && start <= end) else {
+            *self = Self::from_content(content);
+            return;
+        };
+        self.version = next_version();
+        self.text = OnceCell::new();
+        let from = self.rope.line_to_char(start);
+        let to = if end + 1 < old_count {
+            self.rope.line_to_char(end + 1)
+        } else {
+            self.rope.len_chars()
+        };
+        let (replacement, lf_cr) = content_lines(content, start, new_end + 1);
+        self.inexact |= lf_cr;
+        self.words = self.words - count_words(self.rope.slice(from..to).chars())
+            + count_words(replacement.chars());
+        self.rope.remove(from..to);
+        self.rope.insert(from, &replacement);
+        if self.line_count() != new_count {
+            *self = Self::from_content(content);
+        } else if let Some(index) = &mut self.index {
+            index.update(&self.rope, start, end, new_end);
+        }
+    }
+}
+
+// Text of the content lines `start..end`, with the line endings the editor
+// reports, and whether one of them was LF CR
+fn content_lines(content: &Content, start: usize, end: usize) -> (String, bool) {
+    let last = content.line_count().saturating_sub(1);
+    let mut text = String::new();
+    let mut lf_cr = false;
+    for index in start..end {
+        let Some(line) = content.line(index) else {
+            break;
+        };
+        text.push_str(&line.text);
+        if index < last {
+            // The rope would count the two breaks of LF CR, read as one by the editor
+            lf_cr |= line.ending == text_editor::LineEnding::LfCr;
+            text.push_str(match line.ending {
+                text_editor::LineEnding::None | text_editor::LineEnding::LfCr => "\n",
+                ending => ending.as_str(),
+            });
+        }
+    }
+    (text, lf_cr)
+}
+
+fn count_words(chars: impl Iterator<Item = char>) -> usize {
+    let mut words = 0;
+    let mut in_word = false;
+    for c in chars {
+        let word_char = !c.is_whitespace();
+        if word_char && !in_word {
+            words += 1;
+        }
+        in_word = word_char;
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::widget::text_editor::{Action, Cursor, Edit, Motion, Position};
+    use std::sync::Arc;
+
+    // Applies an edit the way Document does and checks the mirror against the editor
+    fn edit(content: &mut Content, mirror: &mut TextMirror, action: Action) {
+        let cursor = content.cursor();
+        let anchor = cursor.selection.unwrap_or(cursor.position).line;
+        let start = anchor.min(cursor.position.line).saturating_sub(1);
+        let end = anchor.max(cursor.position.line) + 1;
+        content.perform(action);
+        mirror.sync_lines(content, start, end);
+        assert_eq!(*mirror.text(), content.text());
+        assert_eq!(mirror.word_count(), content.text().split_whitespace().count());
+    }
+
+    fn at(content: &mut Content, line: usize, column: usize) {
+        content.move_to(Cursor {
+            position: Position { line, column },
+            selection: None,
+        });
+    }
+
+    #[test]
+    fn mirror_follows_typing_and_deletions() {
+        let mut content = Content::with_text("one two\nthree\n");
+        let mut mirror = TextMirror::from_content(&content);
+        assert_eq!(mirror.word_count(), 3);
+        at(&mut content, 0, 3);
+        edit(&mut content, &mut mirror, Action::Edit(Edit::Insert('!')));
+        edit(&mut content, &mut mirror, Action::Edit(Edit::Enter));
+        at(&mut content, 1, 0);
+        edit(&mut content, &mut mirror, Action::Edit(Edit::Backspace));
+        at(&mut content, 1, 5);
+        edit(&mut content, &mut mirror, Action::Edit(Edit::Delete));
+        assert_eq!(&*mirror.text(), "one! two\nthree");
+    }
+
+    #[test]
+    fn mirror_follows_multiline_paste_over_selection() {
+        let mut content = Content::with_text("a\r\nb\r\nc\r\nd");
+        let mut mirror = TextMirror::from_content(&content);
+        content.move_to(Cursor {
+            position: Position { line: 2, column: 1 },
+            selection: Some(Position { line: 0, column: 1 }),
+        });
+        edit(&mut content, &mut mirror, Action::Edit(Edit::Paste(Arc::new("x\r\ny z".into()))));
+        assert_eq!(mirror.line_count(), 3);
+        content.perform(Action::Move(Motion::DocumentEnd));
+        edit(&mut content, &mut mirror, Action::Edit(Edit::Paste(Arc::new("\n\nend".into()))));
+    }
+
+    #[test]
+    fn mirror_maps_bytes_to_lines() {
+        let mirror = TextMirror::new("café\r\nbar\n");
+        assert_eq!(mirror.byte_to_position(0), (0, 0));
+        assert_eq!(mirror.byte_to_position(5), (0, 5));
+        assert_eq!(mirror.byte_to_position(7), (1, 0));
+        assert_eq!(mirror.byte_to_position(100), (2, 0));
+        assert_eq!(mirror.line(0), "café");
+        assert_eq!(mirror.lines().count(), 3);
+    }
+
+    #[test]
+    fn mirror_splits_lines_like_the_editor() {
+        // Classic Mac endings and a NUL byte
+        let mut content = Content::with_text("a\rb\0\rc");
+        let mut mirror = TextMirror::from_content(&content);
+        assert_eq!(mirror.line_count(), content.line_count());
+        assert_eq!(mirror.byte_to_position(5), (2, 0));
+        assert_eq!(mirror.line(1), "b\0");
+        at(&mut content, 1, 2);
+        edit(&mut content, &mut mirror, Action::Edit(Edit::Enter));
+        assert_eq!(mirror.line_count(), 4);
+        // LF CR is one break for the editor
+        let content = Content::with_text("a\n\rb\nc");
+        assert_eq!(TextMirror::from_content(&content).line_count(), content.line_count());
+    }
+
+    #[test]
+    fn text_copy_is_shared_until_an_edit() {
+        let mut content = Content::with_text("one\ntwo");
+        let mut mirror = TextMirror::from_content(&content);
+        let first = mirror.text();
+        assert!(Arc::ptr_eq(&first, &mirror.text()));
+        at(&mut content, 1, 3);
+        edit(&mut content, &mut mirror, Action::Edit(Edit::Insert('!')));
+        assert!(!Arc::ptr_eq(&first, &mirror.text()));
+        assert_eq!(&*mirror.text(), "one\ntwo!");
+    }
+
+    #[test]
+    fn mirror_rebuilds_when_lines_disagree() {
+        let content = Content::with_text("a");
+        let mut mirror = TextMirror::new("x\ny\nz");
+        mirror.sync_lines(&content, 2, 2);
+        assert_eq!(&*mirror.text(), "a");
+    }
+}
@@ -0,0 +1,85 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use similar::{DiffOp, TextDiff};
+
+// --- Lines changed since the last save, marked in the gutter ---
+
+// Larger documents get no marks: they are diffed again on every edit
+pub const CHANGE_MARKS_MAX_BYTES: usize = 1024 * 1024;
+// A diff taking longer falls back to coarser hunks instead of stalling typing
+const DIFF_TIMEOUT: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    // Lines deleted just above this one
+    Removed,
+}
+
+// Lines of `current` that differ from `saved`. Removed lines have nothing left
+// to mark, so they give an empty range at the line that now follows them.
+pub fn line_changes(saved: &str, current: &str) -> Vec<(Range<usize>, LineChange)> {
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(saved, current);
+    let last_line = diff.new_slices().len().saturating_sub(1);
+    diff.ops()
+        .iter()
+        .filter_map(|op| match *op {
+            DiffOp::Equal { .. } => None,
+            DiffOp::Insert { new_index, new_len, .. } => {
+                Some((new_index..new_index + new_len, LineChange::Added))
+            }
+            DiffOp::Replace { new_index, new_len, .. } => {
+                Some((new_index..new_index + new_len, LineChange::Modified))
+            }
+            DiffOp::Delete { new_index, .. } => {
+                let line = new_index.min(last_line);
+                Some((line..line, LineChange::Removed))
+            }
+        })
+        .collect()
+}
+
+// Mark shown beside `line`
+pub fn at(changes: &[(Range<usize>, LineChange)], line: usize) -> Option<LineChange> {
+    changes.iter().find_map(|(range, change)| {
+        let hit = if range.is_empty() {
+            range.start == line
+        } else {
+            range.contains(&line)
+        };
+        hit.then_some(*change)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_text_has_no_changes() {
+        assert!(line_changes("a\nb\nc", "a\nb\nc").is_empty());
+    }
+
+    #[test]
+    fn added_modified_and_removed_lines() {
+        let changes = line_changes("a\nb\nc\nd\n", "a\nnew\nb\nC\n");
+        assert_eq!(at(&changes, 0), None);
+        assert_eq!(at(&changes, 1), Some(LineChange::Added));
+        assert_eq!(at(&changes, 2), None);
+        assert_eq!(at(&changes, 3), Some(LineChange::Modified));
+    }
+
+    #[test]
+    fn removed_lines_mark_the_next_one() {
+        let changes = line_changes("a\nb\nc\n", "a\nc\n");
+        assert_eq!(changes, vec![(1..1, LineChange::Removed)]);
+        assert_eq!(at(&changes, 1), Some(LineChange::Removed));
+        // Deleted at the end: the last line carries the mark
+        let changes = line_changes("a\nb\n", "a\n");
+        assert_eq!(at(&changes, 0), Some(LineChange::Removed));
+    }
+}
@@ -0,0 +1,143 @@
+use std::ops::RangeInclusive;
+
+// --- Character picker ---
+
+// Characters kept in the recently used row, most recent first
+pub const MAX_RECENT: usize = 24;
+
+// Unicode block offered by Édition → Insérer un caractère, with words it can
+// be found by besides its name
+pub struct Block {
+    pub name: &'static str,
+    pub keywords: &'static str,
+    pub range: RangeInclusive<u32>,
+}
+
+pub const BLOCKS: &[Block] = &[
+    Block { name: "Latin-1 (supplément)", keywords: "accents ± × ÷ ° © ® « » ¼ ½ ¾ µ ¶ § ¿ ¡", range: 0x00A0..=0x00FF },
+    Block { name: "Latin étendu A", keywords: "accents œ Œ ł č š ž", range: 0x0100..=0x017F },
+    Block { name: "Grec", keywords: "alpha bêta pi sigma oméga λ", range: 0x0370..=0x03FF },
+    Block { name: "Cyrillique", keywords: "russe", range: 0x0400..=0x04FF },
+    Block { name: "Ponctuation", keywords: "tiret guillemets points de suspension … — – • ‰ espace insécable", range: 0x2000..=0x206F },
+    Block { name: "Exposants et indices", keywords: "puissance ⁿ ₂", range: 0x2070..=0x209F },
+    Block { name: "Symboles monétaires", keywords: "monnaie euro € dollar livre yen roupie bitcoin", range: 0x20A0..=0x20CF },
+    Block { name: "Symboles de type lettre", keywords: "™ ℃ ℉ № ℓ marque", range: 0x2100..=0x214F },
+    Block { name: "Formes numérales", keywords: "fractions chiffres romains ⅓ ⅔ Ⅳ", range: 0x2150..=0x218F },
+    Block { name: "Flèches", keywords: "→ ← ↑ ↓ ⇒ ⇔ direction", range: 0x2190..=0x21FF },
+    Block { name: "Opérateurs mathématiques", keywords: "maths ≠ ≤ ≥ ≈ ∞ √ ∑ ∫ ∈ ∀ ∃ somme racine infini", range: 0x2200..=0x22FF },
+    Block { name: "Signes techniques divers", keywords: "⌘ ⌫ ⏎ clavier touches ⌚ ⏰", range: 0x2300..=0x23FF },
+    Block { name: "Filets", keywords: "dessin de boîtes tableau cadre ─ │ ┌", range: 0x2500..=0x257F },
+    Block { name: "Pavés", keywords: "blocs █ ░ ▒ ▓", range: 0x2580..=0x259F },
+    Block { name: "Formes géométriques", keywords: "carré cercle triangle ■ ● ▲ ◆", range: 0x25A0..=0x25FF },
+    Block { name: "Symboles divers", keywords: "météo soleil ☀ ☎ ☑ ☐ ♥ ♪ ⚠ échecs cartes", range: 0x2600..=0x26FF },
+    Block { name: "Casseau (dingbats)", keywords: "✓ ✔ ✗ ✘ ✂ ✉ ✎ ❤ étoile coche", range: 0x2700..=0x27BF },
+    Block { name: "Pictogrammes divers", keywords: "emoji météo nourriture animaux objets 🌍 🎉 🔥 💡", range: 0x1F300..=0x1F5FF },
+    Block { name: "Émoticônes", keywords: "emoji smiley visages 😀 😂 😉 🙏", range: 0x1F600..=0x1F64F },
+    Block { name: "Transports et cartes", keywords: "emoji voiture avion fusée 🚀 🚗", range: 0x1F680..=0x1F6FF },
+    Block { name: "Pictogrammes complémentaires", keywords: "emoji gestes 🤔 🥳 🧠", range: 0x1F900..=0x1F9FF },
+];
+
+impl Block {
+    // Characters of the block, control and format characters aside
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.range.clone().filter_map(char::from_u32).filter(|c| !c.is_control() && !is_invisible(*c))
+    }
+}
+
+// Characters showing nothing on their own in the grid
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{00AD}' | '\u{200B}'..='\u{200F}' | '\u{2028}'..='\u{202E}' | '\u{2060}'..='\u{206F}')
+}
+
+// Blocks whose name or keywords hold `query`, or containing the character it
+// designates; all of them for an empty query
+pub fn search(query: &str) -> Vec<usize> {
+    let query = query.trim().to_lowercase();
+    let designated = designated_char(&query);
+    BLOCKS
+        .iter()
+        .enumerate()
+        .filter(|(_, block)| {
+            query.is_empty()
+                || block.name.to_lowercase().contains(&query)
+                || block.keywords.to_lowercase().contains(&query)
+                || designated.is_some_and(|c| block.range.contains(&u32::from(c)))
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// Character typed in the search field: the character itself, or its code
+// point written "U+B1" or, so that short words are not read as one, "20ac"
+pub fn designated_char(query: &str) -> Option<char> {
+    let query = query.trim();
+    let mut chars = query.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+    let (hex, min_len) = match query.strip_prefix("U+").or_else(|| query.strip_prefix("u+")) {
+        Some(hex) => (hex, 1),
+        None => (query, 4),
+    };
+    if hex.len() < min_len || hex.len() > 6 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).filter(|c| !c.is_control())
+}
+
+// Code point as shown under the grid: "U+20AC"
+pub fn code_point(c: char) -> String {
+    format!("U+{:04X}", u32::from(c))
+}
+
+// `recent` with `c` moved to the front, within MAX_RECENT characters
+pub fn remember(recent: &str, c: char) -> String {
+    std::iter::once(c).chain(recent.chars().filter(|&r| r != c)).take(MAX_RECENT).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(indices: Vec<usize>) -> Vec<&'static str> {
+        indices.into_iter().map(|i| BLOCKS[i].name).collect()
+    }
+
+    #[test]
+    fn search_by_name_keyword_or_character() {
+        assert_eq!(names(search("flèch")), ["Flèches"]);
+        assert_eq!(names(search("EURO")), ["Symboles monétaires"]);
+        assert_eq!(names(search("±")), ["Latin-1 (supplément)"]);
+        assert_eq!(names(search("u+2192")), ["Flèches"]);
+        assert_eq!(search("").len(), BLOCKS.len());
+        assert!(search("introuvable").is_empty());
+    }
+
+    #[test]
+    fn code_points_are_read_and_written() {
+        assert_eq!(designated_char("U+20AC"), Some('€'));
+        assert_eq!(designated_char("1f600"), Some('😀'));
+        assert_eq!(designated_char("é"), Some('é'));
+        assert_eq!(designated_char("u+b1"), Some('±'));
+        assert_eq!(designated_char("ab"), None);
+        assert_eq!(designated_char("flèche"), None);
+        assert_eq!(code_point('→'), "U+2192");
+        assert_eq!(code_point('😀'), "U+1F600");
+    }
+
+    #[test]
+    fn blocks_skip_invisible_characters() {
+        let punctuation = &BLOCKS[4];
+        assert_eq!(punctuation.name, "Ponctuation");
+        assert!(punctuation.chars().all(|c| c != '\u{200B}'));
+        assert!(punctuation.chars().any(|c| c == '…'));
+    }
+
+    #[test]
+    fn recent_characters_move_to_the_front() {
+        assert_eq!(remember("€→±", '±'), "±€→");
+        assert_eq!(remember("", '€'), "€");
+        let full: String = std::iter::repeat_n('a', MAX_RECENT).collect();
+        assert_eq!(remember(&full, 'b').chars().count(), MAX_RECENT);
+    }
+}
@@ -0,0 +1,159 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+// --- #RRGGBB / rgb() literals: gutter swatches and in-place editing ---
+
+static HEX_COLOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"#([0-9a-fA-F]{2})([0-9a-fA-F]{2})([0-9a-fA-F]{2})\b").unwrap());
+static RGB_COLOR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"rgb\(\s*(\d{1,3})\s*,\s*(\d{1,3})\s*,\s*(\d{1,3})\s*\)").unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub fn to_iced(self) -> iced::Color {
+        iced::Color::from_rgb8(self.r, self.g, self.b)
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    // Accepts "#rrggbb" or "rrggbb"
+    pub fn parse_hex(s: &str) -> Option<Self> {
+        let hex = s.trim().trim_start_matches('#');
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Self {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSyntax {
+    Hex { uppercase: bool },
+    Rgb,
+}
+
+// Byte range of a literal within its line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorLiteral {
+    pub start: usize,
+    pub end: usize,
+    pub color: Rgb,
+    pub syntax: ColorSyntax,
+}
+
+impl ColorLiteral {
+    // New text for the literal, in the syntax it was written with
+    pub fn format(&self, color: Rgb) -> String {
+        match self.syntax {
+            ColorSyntax::Hex { uppercase: true } => {
+                format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b)
+            }
+            ColorSyntax::Hex { uppercase: false } => {
+                format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+            }
+            ColorSyntax::Rgb => format!("rgb({}, {}, {})", color.r, color.g, color.b),
+        }
+    }
+}
+
+// Literal shown as the line's swatch, looked for in the line's first bytes only:
+// the gutter is drawn every frame and a line can be megabytes long
+pub fn swatch(line: &str) -> Option<ColorLiteral> {
+    const SCAN_BYTES: usize = 4096;
+    find_colors(&line[..line.floor_char_boundary(SCAN_BYTES)]).into_iter().next()
+}
+
+pub fn find_colors(line: &str) -> Vec<ColorLiteral> {
+    let mut found: Vec<ColorLiteral> = HEX_COLOR
+        .captures_iter(line)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            let color = Rgb::parse_hex(whole.as_str())?;
+            let digits = &whole.as_str()[1..];
+            Some(ColorLiteral {
+                start: whole.start(),
+                end: whole.end(),
+                color,
+                syntax: ColorSyntax::Hex {
+                    uppercase: digits.chars().any(|c| c.is_ascii_uppercase()),
+                },
+            })
+        })
+        .collect();
+    found.extend(RGB_COLOR.captures_iter(line).filter_map(|caps| {
+        let whole = caps.get(0)?;
+        let channel = |i: usize| caps[i].parse::<u8>().ok();
+        Some(ColorLiteral {
+            start: whole.start(),
+            end: whole.end(),
+            color: Rgb {
+                r: channel(1)?,
+                g: channel(2)?,
+                b: channel(3)?,
+            },
+            syntax: ColorSyntax::Rgb,
+        })
+    }));
+    found.sort_by_key(|c| c.start);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_hex_and_rgb_literals() {
+        let line = "color: #1e90FF; background: rgb(255, 0, 10);";
+        let found = find_colors(line);
+        assert_eq!(found.len(), 2);
+        assert_eq!(&line[found[0].start..found[0].end], "#1e90FF");
+        assert_eq!(found[0].color, Rgb { r: 0x1e, g: 0x90, b: 0xff });
+        assert_eq!(found[1].color, Rgb { r: 255, g: 0, b: 10 });
+        assert_eq!(found[1].syntax, ColorSyntax::Rgb);
+        assert_eq!(swatch(line), Some(found[0]));
+        // Too far right for a swatch
+        assert_eq!(swatch(&format!("{}#aabbcc", "é".repeat(4000))), None);
+    }
+
+    #[test]
+    fn ignores_non_colors() {
+        assert!(find_colors("issue #123456789").is_empty());
+        assert!(find_colors("#12345").is_empty());
+        assert!(find_colors("rgb(300, 0, 0)").is_empty());
+        assert!(find_colors("#abcdeg").is_empty());
+    }
+
+    #[test]
+    fn format_keeps_the_written_syntax() {
+        let color = Rgb { r: 1, g: 171, b: 255 };
+        let upper = find_colors("#AABBCC")[0];
+        let lower = find_colors("#aabbcc")[0];
+        let rgb = find_colors("rgb(1,2,3)")[0];
+        assert_eq!(upper.format(color), "#01ABFF");
+        assert_eq!(lower.format(color), "#01abff");
+        assert_eq!(rgb.format(color), "rgb(1, 171, 255)");
+    }
+
+    #[test]
+    fn parse_hex_input() {
+        assert_eq!(Rgb::parse_hex(" #00ff7f "), Some(Rgb { r: 0, g: 255, b: 127 }));
+        assert_eq!(Rgb::parse_hex("00FF7F"), Some(Rgb { r: 0, g: 255, b: 127 }));
+        assert_eq!(Rgb::parse_hex("#0f7"), None);
+        assert_eq!(Rgb { r: 0, g: 255, b: 127 }.to_hex(), "#00ff7f");
+    }
+}
@@ -0,0 +1,84 @@
+use crate::language::Language;
+
+// --- Line comments ---
+
+// Opening and closing tokens of a comment on one line; the closing one is
+// empty for languages with line comments
+pub fn tokens(language: Language) -> Option<(&'static str, &'static str)> {
+    match language {
+        Language::Rust => Some(("//", "")),
+        Language::Python | Language::Shell | Language::Toml | Language::Yaml => Some(("#", "")),
+        Language::Xml | Language::Markdown => Some(("<!--", "-->")),
+        Language::PlainText | Language::Log | Language::Json | Language::Csv => None,
+    }
+}
+
+// The lines commented, or uncommented when all of them already are. Blank
+// lines are left as they are, and the tokens go at the smallest indentation
+// so a block stays aligned.
+pub fn toggle(lines: &[&str], (open, close): (&str, &str)) -> Vec<String> {
+    let filled = || lines.iter().filter(|l| !l.trim().is_empty());
+    if filled().all(|l| is_commented(l, open, close)) {
+        return lines.iter().map(|l| uncomment(l, open, close)).collect();
+    }
+    let column = filled().map(|l| l.len() - l.trim_start().len()).min().unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| {
+            if l.trim().is_empty() {
+                return l.to_string();
+            }
+            let (indent, body) = l.split_at(column);
+            if close.is_empty() {
+                format!("{indent}{open} {body}")
+            } else {
+                format!("{indent}{open} {body} {close}")
+            }
+        })
+        .collect()
+}
+
+fn is_commented(line: &str, open: &str, close: &str) -> bool {
+    let body = line.trim();
+    body.starts_with(open) && body.ends_with(close) && body.len() >= open.len() + close.len()
+}
+
+fn uncomment(line: &str, open: &str, close: &str) -> String {
+    if line.trim().is_empty() {
+        return line.to_string();
+    }
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let body = &line.trim()[open.len()..];
+    let body = body.strip_prefix(' ').unwrap_or(body);
+    let body = body.strip_suffix(close).unwrap_or(body);
+    let body = if close.is_empty() { body } else { body.strip_suffix(' ').unwrap_or(body) };
+    format!("{indent}{body}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_commented_at_the_block_indentation() {
+        let lines = ["    if a {", "", "        b();", "    }"];
+        let commented = toggle(&lines, ("//", ""));
+        assert_eq!(commented, ["    // if a {", "", "    //     b();", "    // }"]);
+        let refs: Vec<&str> = commented.iter().map(String::as_str).collect();
+        assert_eq!(toggle(&refs, ("//", "")), lines);
+    }
+
+    #[test]
+    fn a_partly_commented_block_is_commented_again() {
+        assert_eq!(toggle(&["# a", "b"], ("#", "")), ["# # a", "# b"]);
+        assert_eq!(toggle(&["#a", "  # b"], ("#", "")), ["a", "  b"]);
+    }
+
+    #[test]
+    fn block_tokens_wrap_each_line() {
+        assert_eq!(toggle(&["<p>un</p>"], ("<!--", "-->")), ["<!-- <p>un</p> -->"]);
+        assert_eq!(toggle(&["  <!-- x -->"], ("<!--", "-->")), ["  x"]);
+        assert_eq!(toggle(&["<!---->"], ("<!--", "-->")), [""]);
+        assert!(tokens(Language::Json).is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use crate::paths;
+
+// --- File path completion while typing ./, ../ or C:\ in the document ---
+
+const MAX_PATH_COMPLETIONS: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+// Characters that end a path token when scanning back from the cursor
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '(' | '[' | '{' | '<' | '=' | ',' | ';')
+}
+
+fn looks_like_path(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    let drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/');
+    // ~/notes, $HOME/notes, %USERPROFILE%\notes once a separator follows
+    let expanded = token.starts_with(['~', '$', '%'])
+        && token.contains(['/', '\\'])
+        && paths::expand(token).is_absolute();
+    drive || expanded || ["./", "../", ".\\", "..\\"].iter().any(|p| token.starts_with(p))
+}
+
+// Path-looking word right before the cursor, if any
+pub fn path_token(before_cursor: &str) -> Option<&str> {
+    let start = before_cursor
+        .char_indices()
+        .rev()
+        .find(|(_, c)| is_delimiter(*c))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let token = &before_cursor[start..];
+    looks_like_path(token).then_some(token)
+}
+
+// Splits "../src/ma" into ("../src/", "ma")
+pub fn split_token(token: &str) -> (&str, &str) {
+    let cut = token.rfind(['/', '\\']).map_or(0, |i| i + 1);
+    token.split_at(cut)
+}
+
+// Separator the user typed last, reused after completed directories
+pub fn separator(token: &str) -> char {
+    token.chars().rev().find(|c| matches!(c, '/' | '\\')).unwrap_or('/')
+}
+
+// Entries of the token's directory whose name starts with its last component,
+// directories first; relative tokens are resolved against `base`
+pub fn complete(base: &Path, token: &str) -> Vec<PathEntry> {
+    let (dir, prefix) = split_token(token);
+    let dir = paths::expand(dir);
+    let dir_path = if dir.is_absolute() || dir.as_os_str().as_encoded_bytes().get(1) == Some(&b':') {
+        dir
+    } else {
+        base.join(dir)
+    };
+    let Ok(read) = std::fs::read_dir(dir_path) else {
+        return Vec::new();
+    };
+    let prefix = prefix.to_lowercase();
+    let mut entries: Vec<PathEntry> = read
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            name.to_lowercase().starts_with(&prefix).then(|| PathEntry {
+                is_dir: e.file_type().is_ok_and(|t| t.is_dir()),
+                name,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    entries.truncate(MAX_PATH_COMPLETIONS);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_path_tokens() {
+        assert_eq!(path_token("cp ./sr"), Some("./sr"));
+        assert_eq!(path_token("include \"../lib/"), Some("../lib/"));
+        assert_eq!(path_token("path=C:\\Win"), Some("C:\\Win"));
+        assert_eq!(path_token("..\\x"), Some("..\\x"));
+        assert_eq!(path_token("hello world"), None);
+        assert_eq!(path_token("a.b/c"), None);
+        assert_eq!(path_token("voir ~/no"), Some("~/no"));
+        assert_eq!(path_token("prix ~5 €"), None);
+        assert_eq!(path_token(""), None);
+    }
+
+    #[test]
+    fn splits_directory_and_prefix() {
+        assert_eq!(split_token("../src/ma"), ("../src/", "ma"));
+        assert_eq!(split_token("C:\\Win"), ("C:\\", "Win"));
+        assert_eq!(split_token("./"), ("./", ""));
+        assert_eq!(separator("..\\src\\"), '\\');
+        assert_eq!(separator("./a"), '/');
+    }
+
+    #[test]
+    fn lists_matching_entries_directories_first() {
+        let root = std::env::temp_dir().join(format!("notepad_complete_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src/build")).unwrap();
+        std::fs::write(root.join("src/Base.rs"), "").unwrap();
+        std::fs::write(root.join("src/bin.rs"), "").unwrap();
+        std::fs::write(root.join("src/main.rs"), "").unwrap();
+
+        let entries = complete(&root, "./src/b");
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["build", "Base.rs", "bin.rs"]);
+        assert!(entries[0].is_dir);
+        assert_eq!(complete(&root, "./src/").len(), 4);
+        assert!(complete(&root, "./missing/").is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
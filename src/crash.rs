@@ -0,0 +1,272 @@
+use ropey::Rope;
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::preferences;
+
+// Recent notifications kept for the crash report
+const LOG_LINES: usize = 50;
+
+// --- State seen by the panic hook ---
+
+// Unsaved text of a tab, as of the last update
+pub struct DirtyBuffer {
+    pub name: String,
+    // Rope clones share their nodes, so tracking costs no copy
+    pub text: Rope,
+}
+
+static DIRTY: Mutex<Vec<DirtyBuffer>> = Mutex::new(Vec::new());
+static LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+pub fn track(buffers: Vec<DirtyBuffer>) {
+    if let Ok(mut dirty) = DIRTY.lock() {
+        *dirty = buffers;
+    }
+}
+
+pub fn log(line: impl Into<String>) {
+    if let Ok(mut log) = LOG.lock() {
+        push_bounded(&mut log, line.into());
+    }
+}
+
+fn push_bounded(log: &mut VecDeque<String>, line: String) {
+    log.push_back(line);
+    while log.len() > LOG_LINES {
+        log.pop_front();
+    }
+}
+
+// --- Panic hook ---
+
+// A panic of the UI thread saves the unsaved tabs, writes a report and offers
+// to restart; background jobs keep the default behavior since they survive it
+pub fn install() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().name() != Some("main") {
+            default_hook(info);
+            return;
+        }
+        let crash = on_panic(info);
+        let restart = rfd::MessageDialog::new()
+            .set_title("Notepad")
+            .set_description(crash.summary())
+            .set_level(rfd::MessageLevel::Error)
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show();
+        if restart == rfd::MessageDialogResult::Yes {
+            if let Ok(exe) = std::env::current_exe() {
+                let _ = std::process::Command::new(exe).spawn();
+            }
+        }
+    }));
+}
+
+struct Crash {
+    message: String,
+    dir: PathBuf,
+    report: io::Result<PathBuf>,
+    recovered: Vec<(String, io::Result<PathBuf>)>,
+}
+
+impl Crash {
+    fn summary(&self) -> String {
+        let mut text = format!(
+            "Notepad a rencontré une erreur inattendue et doit se fermer.\n\n{}\n\n",
+            self.message
+        );
+        let saved = self.recovered.iter().filter(|(_, r)| r.is_ok()).count();
+        if saved > 0 {
+            text.push_str(&format!(
+                "{saved} document(s) non enregistré(s) copié(s) dans :\n{}\n\n",
+                self.dir.display()
+            ));
+        }
+        match &self.report {
+            Ok(path) => text.push_str(&format!("Rapport d'erreur :\n{}\n\n", path.display())),
+            Err(e) => text.push_str(&format!("Impossible d'écrire le rapport d'erreur : {e}\n\n")),
+        }
+        text.push_str("Redémarrer Notepad ?");
+        text
+    }
+}
+
+fn on_panic(info: &PanicHookInfo) -> Crash {
+    let message = panic_message(info);
+    tracing::error!("plantage : {message}");
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let dir = preferences::dir()
+        .join("recovery")
+        .join(stamp.to_string());
+    // try_lock: the panic may come from a thread holding the lock
+    let buffers = match DIRTY.try_lock() {
+        Ok(mut dirty) => std::mem::take(&mut *dirty),
+        Err(_) => Vec::new(),
+    };
+    let recovered = write_recovery(&dir, &buffers);
+    let log: Vec<String> = match LOG.try_lock() {
+        Ok(log) => log.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    };
+    let text = report_text(
+        &message,
+        &recovered,
+        &log,
+        &Backtrace::force_capture().to_string(),
+    );
+    let path = dir.join("rapport.txt");
+    let report = std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&path, text))
+        .map(|_| path);
+    Crash {
+        message,
+        dir,
+        report,
+        recovered,
+    }
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let text = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panique sans message".to_string());
+    match info.location() {
+        Some(location) => format!("{text} ({location})"),
+        None => text,
+    }
+}
+
+// --- Recovery files and report ---
+
+// Writes each buffer to `dir`, under a numbered copy of its name
+fn write_recovery(dir: &Path, buffers: &[DirtyBuffer]) -> Vec<(String, io::Result<PathBuf>)> {
+    buffers
+        .iter()
+        .enumerate()
+        .map(|(i, buffer)| {
+            let path = dir.join(recovery_name(i, &buffer.name));
+            let result = std::fs::create_dir_all(dir)
+                .and_then(|_| write_buffer(&path, &buffer.text))
+                .map(|_| path);
+            (buffer.name.clone(), result)
+        })
+        .collect()
+}
+
+fn write_buffer(path: &Path, text: &Rope) -> io::Result<()> {
+    text.write_to(std::io::BufWriter::new(std::fs::File::create(path)?))
+}
+
+fn recovery_name(index: usize, name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect();
+    if Path::new(&name).extension().is_some() {
+        format!("{}-{name}", index + 1)
+    } else {
+        format!("{}-{name}.txt", index + 1)
+    }
+}
+
+fn report_text(
+    message: &str,
+    recovered: &[(String, io::Result<PathBuf>)],
+    log: &[String],
+    backtrace: &str,
+) -> String {
+    let mut text = format!(
+        "Notepad {} ({} {})\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    if let Ok(exe) = std::env::current_exe() {
+        text.push_str(&format!("Exécutable : {}\n", exe.display()));
+    }
+    text.push_str(&format!("\nErreur : {message}\n\nDocuments non enregistrés :\n"));
+    if recovered.is_empty() {
+        text.push_str("  (aucun)\n");
+    }
+    for (name, result) in recovered {
+        match result {
+            Ok(path) => text.push_str(&format!("  {name} -> {}\n", path.display())),
+            Err(e) => text.push_str(&format!("  {name} : échec de la copie ({e})\n")),
+        }
+    }
+    text.push_str("\nJournal :\n");
+    for line in log {
+        text.push_str(&format!("  {line}\n"));
+    }
+    text.push_str(&format!("\nPile d'appels :\n{backtrace}\n"));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovery_writes_the_buffers() {
+        let dir = std::env::temp_dir().join(format!("notepad-recovery-{}", std::process::id()));
+        let buffers = vec![
+            DirtyBuffer {
+                name: "notes.md".into(),
+                text: Rope::from_str("brouillon\n"),
+            },
+            DirtyBuffer {
+                name: "Sans titre".into(),
+                text: Rope::from_str("un\n\rdeux"),
+            },
+        ];
+        let recovered = write_recovery(&dir, &buffers);
+        let paths: Vec<PathBuf> = recovered.into_iter().map(|(_, r)| r.unwrap()).collect();
+        assert_eq!(paths[0], dir.join("1-notes.md"));
+        assert_eq!(paths[1], dir.join("2-Sans titre.txt"));
+        assert_eq!(std::fs::read_to_string(&paths[0]).unwrap(), "brouillon\n");
+        assert_eq!(std::fs::read_to_string(&paths[1]).unwrap(), "un\n\rdeux");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn recovery_names_are_safe_file_names() {
+        assert_eq!(recovery_name(0, "a:b?.txt"), "1-a_b_.txt");
+    }
+
+    #[test]
+    fn report_lists_recovered_files_and_log() {
+        let recovered = vec![
+            ("notes.md".to_string(), Ok(PathBuf::from("1-notes.md"))),
+            ("b.txt".to_string(), Err(io::Error::other("disque plein"))),
+        ];
+        let log = vec!["[Erreur] enregistrement impossible".to_string()];
+        let text = report_text("index out of bounds (src/update.rs:1:1)", &recovered, &log, "frames");
+        assert!(text.contains("Erreur : index out of bounds"));
+        assert!(text.contains("notes.md -> 1-notes.md"));
+        assert!(text.contains("b.txt : échec de la copie (disque plein)"));
+        assert!(text.contains("enregistrement impossible"));
+        assert!(text.ends_with("frames\n"));
+    }
+
+    #[test]
+    fn log_keeps_recent_lines() {
+        let mut log = VecDeque::new();
+        for i in 0..LOG_LINES + 5 {
+            push_bounded(&mut log, format!("l{i}"));
+        }
+        assert_eq!(log.len(), LOG_LINES);
+        assert_eq!(log[0], "l5");
+    }
+}
@@ -0,0 +1,205 @@
+// --- Date and time ---
+
+// Stamp inserted by F5 until another format is chosen: "14:05 17/10/2026"
+pub const DEFAULT_FORMAT: &str = "%H:%M %d/%m/%Y";
+
+// Formats offered by Édition → Format de la date and the settings
+pub const PRESETS: [(&str, &str); 4] = [
+    ("Par défaut", DEFAULT_FORMAT),
+    ("ISO 8601", "%Y-%m-%dT%H:%M:%S"),
+    ("Date longue", "%A %-d %B %Y"),
+    ("Nom de fichier", "%Y-%m-%d_%H-%M-%S"),
+];
+
+const WEEKDAYS: [&str; 7] = ["dimanche", "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi"];
+const WEEKDAYS_SHORT: [&str; 7] = ["dim.", "lun.", "mar.", "mer.", "jeu.", "ven.", "sam."];
+const MONTHS: [&str; 12] = [
+    "janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre", "novembre",
+    "décembre",
+];
+const MONTHS_SHORT: [&str; 12] = [
+    "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.", "déc.",
+];
+
+// UTC offset for local time — use platform-specific API
+#[cfg(target_os = "windows")]
+fn utc_offset_secs() -> i64 {
+    #[repr(C)]
+    struct TimeZoneInformation {
+        bias: i32,
+        _rest: [u8; 168],
+    }
+    extern "system" {
+        fn GetTimeZoneInformation(lp_time_zone_information: *mut TimeZoneInformation) -> u32;
+    }
+    let mut tzi = TimeZoneInformation {
+        bias: 0,
+        _rest: [0; 168],
+    };
+    unsafe {
+        GetTimeZoneInformation(&mut tzi);
+    }
+    // Bias is in minutes, west-positive → negate for east-positive
+    -(tzi.bias as i64) * 60
+}
+
+#[cfg(not(target_os = "windows"))]
+fn utc_offset_secs() -> i64 {
+    0 // Fallback to UTC on non-Windows
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalTime {
+    pub year: i64,
+    // 1 to 12
+    pub month: i64,
+    pub day: i64,
+    pub hour: i64,
+    pub minute: i64,
+    pub second: i64,
+    // 0 for Sunday
+    pub weekday: i64,
+    // 1 to 366
+    pub day_of_year: i64,
+}
+
+impl LocalTime {
+    pub fn now() -> Self {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self::from_unix(secs)
+    }
+
+    pub fn from_unix(unix_secs: u64) -> Self {
+        Self::from_local_secs(unix_secs as i64 + utc_offset_secs())
+    }
+
+    // Seconds since 1970-01-01 00:00 local time
+    fn from_local_secs(local_secs: i64) -> Self {
+        // Days since epoch → date
+        let mut days = local_secs.div_euclid(86400);
+        let day_secs = local_secs.rem_euclid(86400);
+        // 1970-01-01 was a Thursday
+        let weekday = (days + 4).rem_euclid(7);
+
+        // Civil date from days since 1970-01-01 (Algorithm from Howard Hinnant)
+        days += 719_468;
+        let era = days.div_euclid(146_097);
+        let doe = days.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let y = yoe + era * 400;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let d = doy - (153 * mp + 2) / 5 + 1;
+        let m = if mp < 10 { mp + 3 } else { mp - 9 };
+        let y = if m <= 2 { y + 1 } else { y };
+
+        // doy counts from March 1st
+        let leap = (y % 4 == 0 && y % 100 != 0) || y % 400 == 0;
+        let day_of_year = if m <= 2 { doy - 305 } else { doy + 60 + i64::from(leap) };
+
+        Self {
+            year: y,
+            month: m,
+            day: d,
+            hour: day_secs / 3600,
+            minute: (day_secs % 3600) / 60,
+            second: day_secs % 60,
+            weekday,
+            day_of_year,
+        }
+    }
+
+    // strftime-like: %Y %y %m %d %H %M %S %j for the numbers, %A %a %B %b for
+    // the French day and month names, %% for a percent sign; "%-d" drops the
+    // leading zero. Anything else is written as it is.
+    pub fn format(&self, pattern: &str) -> String {
+        let mut out = String::with_capacity(pattern.len() + 16);
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            let mut spec = chars.next();
+            let padded = spec != Some('-');
+            if !padded {
+                spec = chars.next();
+            }
+            let number = |value: i64, width: usize| {
+                if padded {
+                    format!("{value:0width$}")
+                } else {
+                    value.to_string()
+                }
+            };
+            match spec {
+                Some('Y') => out.push_str(&number(self.year, 4)),
+                Some('y') => out.push_str(&number(self.year.rem_euclid(100), 2)),
+                Some('m') => out.push_str(&number(self.month, 2)),
+                Some('d') => out.push_str(&number(self.day, 2)),
+                Some('H') => out.push_str(&number(self.hour, 2)),
+                Some('M') => out.push_str(&number(self.minute, 2)),
+                Some('S') => out.push_str(&number(self.second, 2)),
+                Some('j') => out.push_str(&number(self.day_of_year, 3)),
+                Some('A') => out.push_str(WEEKDAYS[self.weekday as usize]),
+                Some('a') => out.push_str(WEEKDAYS_SHORT[self.weekday as usize]),
+                Some('B') => out.push_str(MONTHS[self.month as usize - 1]),
+                Some('b') => out.push_str(MONTHS_SHORT[self.month as usize - 1]),
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    if !padded {
+                        out.push('-');
+                    }
+                    out.push(other);
+                }
+                None => {
+                    out.push('%');
+                    if !padded {
+                        out.push('-');
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2001-09-09 01:46:40, a Sunday
+    const BILLENNIUM: i64 = 1_000_000_000;
+
+    #[test]
+    fn civil_date_and_weekday() {
+        let t = LocalTime::from_local_secs(BILLENNIUM);
+        assert_eq!((t.year, t.month, t.day), (2001, 9, 9));
+        assert_eq!((t.hour, t.minute, t.second), (1, 46, 40));
+        assert_eq!((t.weekday, t.day_of_year), (0, 252));
+        let leap_day = LocalTime::from_local_secs(951_782_400);
+        assert_eq!((leap_day.year, leap_day.month, leap_day.day), (2000, 2, 29));
+        assert_eq!((leap_day.weekday, leap_day.day_of_year), (2, 60));
+        assert_eq!(LocalTime::from_local_secs(978_220_800).day_of_year, 366);
+    }
+
+    #[test]
+    fn presets_format_the_same_moment() {
+        let t = LocalTime::from_local_secs(BILLENNIUM);
+        let formatted: Vec<String> = PRESETS.iter().map(|(_, pattern)| t.format(pattern)).collect();
+        assert_eq!(
+            formatted,
+            ["01:46 09/09/2001", "2001-09-09T01:46:40", "dimanche 9 septembre 2001", "2001-09-09_01-46-40"]
+        );
+    }
+
+    #[test]
+    fn unknown_specifiers_are_kept() {
+        let t = LocalTime::from_local_secs(BILLENNIUM);
+        assert_eq!(t.format("%a %-d %b %y, %j%% %Q %-Q %"), "dim. 9 sept. 01, 252% %Q %-Q %");
+    }
+}
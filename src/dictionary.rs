@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+
+use crate::preferences;
+
+// --- Dictionary and thesaurus lookup ---
+
+// Word lists installed next to the executable:
+// - dictionary.tsv: "word<TAB>definition", one line per sense
+// - *.dat: MyThes thesaurus, as shipped with LibreOffice (th_fr_FR_v2.dat)
+pub fn folder() -> PathBuf {
+    preferences::dir().join("dictionaries")
+}
+
+// What one source knows about a word
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub source: String,
+    pub definitions: Vec<String>,
+    // Grouped by meaning, with the part of speech when the source gives it
+    pub synonyms: Vec<(Option<String>, Vec<String>)>,
+}
+
+// A source of definitions or synonyms; an online one can be added beside the
+// offline files without touching the popup
+pub trait Provider: Send {
+    fn name(&self) -> String;
+    // Blocking. None when the word is unknown.
+    fn lookup(&self, word: &str) -> Result<Option<Entry>, String>;
+}
+
+// Tab-separated word list
+pub struct TsvDictionary {
+    path: PathBuf,
+}
+
+impl Provider for TsvDictionary {
+    fn name(&self) -> String {
+        file_name(&self.path)
+    }
+
+    fn lookup(&self, word: &str) -> Result<Option<Entry>, String> {
+        let text = std::fs::read_to_string(&self.path).map_err(|e| format!("{} : {e}", self.name()))?;
+        let definitions: Vec<String> = text
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .filter(|(w, _)| same_word(w, word))
+            .map(|(_, definition)| definition.trim().to_string())
+            .filter(|definition| !definition.is_empty())
+            .collect();
+        Ok((!definitions.is_empty()).then(|| Entry {
+            source: self.name(),
+            definitions,
+            synonyms: Vec::new(),
+        }))
+    }
+}
+
+// MyThes thesaurus: the encoding on the first line, then "word|count" followed
+// by `count` lines of "(part of speech)|synonym|synonym..."
+pub struct MyThes {
+    path: PathBuf,
+}
+
+impl Provider for MyThes {
+    fn name(&self) -> String {
+        file_name(&self.path)
+    }
+
+    fn lookup(&self, word: &str) -> Result<Option<Entry>, String> {
+        let bytes = std::fs::read(&self.path).map_err(|e| format!("{} : {e}", self.name()))?;
+        let label = bytes.split(|&b| b == b'\n').next().unwrap_or_default();
+        let encoding = encoding_rs::Encoding::for_label(label.trim_ascii()).unwrap_or(encoding_rs::UTF_8);
+        let (text, _, _) = encoding.decode(&bytes);
+        Ok(parse_mythes(&text, word).map(|synonyms| Entry {
+            source: self.name(),
+            definitions: Vec::new(),
+            synonyms,
+        }))
+    }
+}
+
+fn parse_mythes(text: &str, word: &str) -> Option<Vec<(Option<String>, Vec<String>)>> {
+    let mut lines = text.lines().skip(1);
+    while let Some(line) = lines.next() {
+        let Some((head, count)) = line.rsplit_once('|') else {
+            continue;
+        };
+        let Ok(count) = count.trim().parse::<usize>() else {
+            continue;
+        };
+        if !same_word(head, word) {
+            // Meanings of other words are skipped without being parsed
+            lines.by_ref().take(count).for_each(drop);
+            continue;
+        }
+        let meanings = lines
+            .take(count)
+            .map(|meaning| {
+                let mut parts = meaning.split('|');
+                let kind = parts
+                    .next()
+                    .map(|k| k.trim().trim_start_matches('(').trim_end_matches(')').to_string())
+                    .filter(|k| !k.is_empty());
+                (kind, parts.map(str::to_string).filter(|s| !s.is_empty()).collect())
+            })
+            .collect();
+        return Some(meanings);
+    }
+    None
+}
+
+fn same_word(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+// Providers for the files of `folder`, dictionaries first
+pub fn providers(folder: &Path) -> Vec<Box<dyn Provider>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(folder)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.sort();
+    let mut providers: Vec<Box<dyn Provider>> = Vec::new();
+    for path in &files {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("tsv") => {
+                providers.push(Box::new(TsvDictionary { path: path.clone() }))
+            }
+            _ => {}
+        }
+    }
+    for path in &files {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("dat") => providers.push(Box::new(MyThes { path: path.clone() })),
+            _ => {}
+        }
+    }
+    providers
+}
+
+// Blocking: asks every provider. Err when none is installed.
+pub fn lookup(providers: &[Box<dyn Provider>], word: &str) -> Result<Vec<Entry>, String> {
+    if providers.is_empty() {
+        return Err(format!(
+            "aucun dictionnaire installé dans {}",
+            folder().display()
+        ));
+    }
+    let mut entries = Vec::new();
+    for provider in providers {
+        match provider.lookup(word) {
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("dictionnaire {} illisible : {e}", provider.name()),
+        }
+    }
+    Ok(entries)
+}
+
+// Word around a column (in characters): letters and digits, with the inner
+// apostrophes and hyphens of "aujourd'hui" or "porte-monnaie"
+pub fn word_at(line: &str, column: usize) -> Option<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let is_word = |i: usize| {
+        chars.get(i).is_some_and(|c| c.is_alphanumeric())
+            || (chars.get(i).is_some_and(|c| matches!(c, '\'' | '’' | '-'))
+                && i > 0
+                && chars[i - 1].is_alphanumeric()
+                && chars.get(i + 1).is_some_and(|c| c.is_alphanumeric()))
+    };
+    // The cursor may sit just after the word
+    let at = if is_word(column) { column } else { column.checked_sub(1).filter(|&i| is_word(i))? };
+    let start = (0..at).rev().take_while(|&i| is_word(i)).last().unwrap_or(at);
+    let end = (at..chars.len()).take_while(|&i| is_word(i)).last().unwrap_or(at) + 1;
+    Some(chars[start..end].iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thesaurus_entries_are_found() {
+        let text = "UTF-8\nmaison|2\n(nom)|demeure|logis\n(nom)|famille\nmot|1\n(nom)|terme|vocable\n";
+        let meanings = parse_mythes(text, "Mot").unwrap();
+        assert_eq!(meanings, vec![(Some("nom".to_string()), vec!["terme".to_string(), "vocable".to_string()])]);
+        assert_eq!(parse_mythes(text, "maison").unwrap().len(), 2);
+        // "famille" is a synonym line, not a headword
+        assert_eq!(parse_mythes(text, "famille"), None);
+    }
+
+    #[test]
+    fn files_of_the_folder_are_providers() {
+        let dir = std::env::temp_dir().join(format!("notepad_dictionary_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("fr.tsv"), "logis\tLieu où l'on habite.\nlogis\tAuberge.\nmot\tx\n").unwrap();
+        let (latin1, _, _) = encoding_rs::WINDOWS_1252.encode("ISO8859-1\nlogis|1\n(nom)|demeure|résidence\n");
+        std::fs::write(dir.join("th_fr.dat"), latin1).unwrap();
+
+        let providers = providers(&dir);
+        let entries = lookup(&providers, "Logis").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].definitions, ["Lieu où l'on habite.", "Auberge."]);
+        assert_eq!(entries[1].source, "th_fr.dat");
+        assert_eq!(entries[1].synonyms[0].1, ["demeure", "résidence"]);
+        assert!(lookup(&providers, "inconnu").unwrap().is_empty());
+        assert!(lookup(&[], "logis").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn word_under_the_cursor() {
+        assert_eq!(word_at("il fait beau", 4).as_deref(), Some("fait"));
+        assert_eq!(word_at("il fait beau", 7).as_deref(), Some("fait"));
+        assert_eq!(word_at("c'est aujourd'hui", 10).as_deref(), Some("aujourd'hui"));
+        assert_eq!(word_at("un porte-monnaie.", 16).as_deref(), Some("porte-monnaie"));
+        assert_eq!(word_at("a  b", 2), None);
+        assert_eq!(word_at("", 0), None);
+    }
+}
@@ -0,0 +1,32 @@
+// --- Display scaling ---
+
+// UI scales offered in the settings, in percent; 100 follows the system alone
+pub const UI_SCALE_CHOICES: &[u16] = &[75, 90, 100, 110, 125, 150, 175, 200];
+
+pub fn ui_scale_factor(percent: u16) -> f32 {
+    f32::from(percent.clamp(UI_SCALE_CHOICES[0], UI_SCALE_CHOICES[UI_SCALE_CHOICES.len() - 1])) / 100.0
+}
+
+// Logical length rounded to whole device pixels at `scale` (system scale times
+// the UI scale), so borders and popup edges stay sharp at 125 % or 150 %
+pub fn snap(length: f32, scale: f32) -> f32 {
+    if scale <= 0.0 {
+        return length;
+    }
+    (length * scale).round() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lengths_land_on_device_pixels() {
+        assert_eq!(snap(10.3, 1.0), 10.0);
+        // 10.3 logical pixels are 15.45 device pixels at 150 %
+        assert!((snap(10.3, 1.5) * 1.5 - 15.0).abs() < 1e-4);
+        assert_eq!(snap(7.0, 0.0), 7.0);
+        assert_eq!(ui_scale_factor(125), 1.25);
+        assert_eq!(ui_scale_factor(500), 2.0);
+    }
+}
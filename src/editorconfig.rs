@@ -0,0 +1,505 @@
+use std::path::{Path, PathBuf};
+
+use crate::app::{IndentStyle, LineEnding, SaveEncoding};
+
+const MODELINE_SCAN_LINES: usize = 5;
+const MODELINE_SCAN_BYTES: usize = 4096;
+
+// --- Per-document overrides ---
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocSettings {
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<usize>,
+    pub end_of_line: Option<LineEnding>,
+    pub charset: Option<SaveEncoding>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+}
+
+impl DocSettings {
+    // EditorConfig first, then the modeline (more specific) on top
+    pub fn resolve(path: &Path, bytes: &[u8]) -> Self {
+        let mut settings = Self::from_editorconfig(path);
+        let head = &bytes[..bytes.len().min(MODELINE_SCAN_BYTES)];
+        settings.merge(Self::from_modeline(&String::from_utf8_lossy(head)));
+        settings
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.indent_style = other.indent_style.or(self.indent_style);
+        self.indent_size = other.indent_size.or(self.indent_size);
+        self.end_of_line = other.end_of_line.or(self.end_of_line);
+        self.charset = other.charset.or(self.charset);
+        self.trim_trailing_whitespace = other
+            .trim_trailing_whitespace
+            .or(self.trim_trailing_whitespace);
+        self.insert_final_newline = other.insert_final_newline.or(self.insert_final_newline);
+    }
+
+    pub fn apply_on_save(&self, text: &str) -> String {
+        let trim = self.trim_trailing_whitespace == Some(true);
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while !rest.is_empty() {
+            // Lines end with CRLF, LF or a lone CR
+            let (body, ending) = match rest.find(['\r', '\n']) {
+                Some(i) if rest[i..].starts_with("\r\n") => (&rest[..i], &rest[i..i + 2]),
+                Some(i) => (&rest[..i], &rest[i..i + 1]),
+                None => (rest, ""),
+            };
+            rest = &rest[body.len() + ending.len()..];
+            out.push_str(if trim {
+                body.trim_end_matches([' ', '\t'])
+            } else {
+                body
+            });
+            match self.end_of_line {
+                Some(eol) if !ending.is_empty() => out.push_str(eol.as_str()),
+                _ => out.push_str(ending),
+            }
+        }
+
+        match self.insert_final_newline {
+            Some(true) if !out.is_empty() && !out.ends_with(['\r', '\n']) => {
+                let eol = self.end_of_line.unwrap_or_else(|| LineEnding::detect(text));
+                out.push_str(eol.as_str());
+            }
+            Some(false) => {
+                let len = out.trim_end_matches(['\r', '\n']).len();
+                out.truncate(len);
+            }
+            _ => {}
+        }
+        out
+    }
+
+    // --- EditorConfig ---
+
+    pub fn from_editorconfig(path: &Path) -> Self {
+        let mut configs: Vec<(PathBuf, Vec<Section>)> = Vec::new();
+        let mut dir = path.parent();
+        while let Some(d) = dir {
+            if let Ok(src) = std::fs::read_to_string(d.join(".editorconfig")) {
+                let (root, sections) = parse_editorconfig(&src);
+                configs.push((d.to_path_buf(), sections));
+                if root {
+                    break;
+                }
+            }
+            dir = d.parent();
+        }
+
+        // Farthest file first so that nearer files win
+        let mut settings = Self::default();
+        for (d, sections) in configs.iter().rev() {
+            let Ok(relative) = path.strip_prefix(d) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            for section in sections {
+                if section_matches(&section.glob, &relative) {
+                    for (key, value) in &section.properties {
+                        settings.set_property(key, value);
+                    }
+                }
+            }
+        }
+        settings
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) {
+        match key {
+            "indent_style" => {
+                self.indent_style = match value {
+                    "tab" => Some(IndentStyle::Tab),
+                    "space" => Some(IndentStyle::Space),
+                    _ => None,
+                };
+            }
+            "indent_size" => self.indent_size = value.parse().ok().filter(|&n| n > 0),
+            "tab_width" if self.indent_size.is_none() => {
+                self.indent_size = value.parse().ok().filter(|&n| n > 0);
+            }
+            "end_of_line" => {
+                self.end_of_line = match value {
+                    "lf" => Some(LineEnding::Lf),
+                    "crlf" => Some(LineEnding::CrLf),
+                    "cr" => Some(LineEnding::Cr),
+                    _ => None,
+                };
+            }
+            "charset" => {
+                self.charset = match value {
+                    "utf-8-bom" => Some(SaveEncoding::Utf8Bom),
+                    _ => charset(value),
+                };
+            }
+            "trim_trailing_whitespace" => self.trim_trailing_whitespace = parse_bool(value),
+            "insert_final_newline" => self.insert_final_newline = parse_bool(value),
+            _ => {}
+        }
+    }
+
+    // --- Vim modelines ---
+
+    pub fn from_modeline(text: &str) -> Self {
+        text.lines()
+            .take(MODELINE_SCAN_LINES)
+            .find_map(parse_modeline)
+            .unwrap_or_default()
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+// A plain charset name means no BOM, except for UTF-16 which always has one
+fn charset(value: &str) -> Option<SaveEncoding> {
+    encoding_rs::Encoding::for_label(value.as_bytes()).map(|e| SaveEncoding::of(e, false))
+}
+
+struct Section {
+    glob: String,
+    properties: Vec<(String, String)>,
+}
+
+fn parse_editorconfig(src: &str) -> (bool, Vec<Section>) {
+    let mut root = false;
+    let mut sections: Vec<Section> = Vec::new();
+    for line in src.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push(Section {
+                glob: glob.to_string(),
+                properties: Vec::new(),
+            });
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_lowercase();
+        match sections.last_mut() {
+            Some(section) => section.properties.push((key, value)),
+            None if key == "root" => root = value == "true",
+            None => {}
+        }
+    }
+    (root, sections)
+}
+
+fn section_matches(glob: &str, relative: &str) -> bool {
+    let pattern = if let Some(anchored) = glob.strip_prefix('/') {
+        format!("^{}$", glob_to_regex(anchored))
+    } else if glob.contains('/') {
+        format!("^{}$", glob_to_regex(glob))
+    } else {
+        format!("^(?:.*/)?{}$", glob_to_regex(glob))
+    };
+    regex::Regex::new(&pattern).is_ok_and(|re| re.is_match(relative))
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut out = String::new();
+    let mut brace_depth = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                out.push_str(".*");
+                i += 1;
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            '[' => match chars[i..].iter().position(|&c| c == ']') {
+                Some(end) => {
+                    let class: String = chars[i + 1..i + end].iter().collect();
+                    match class.strip_prefix('!') {
+                        Some(negated) => out.push_str(&format!("[^{negated}]")),
+                        None => out.push_str(&format!("[{class}]")),
+                    }
+                    i += end;
+                }
+                None => out.push_str("\\["),
+            },
+            '{' => {
+                let end = chars[i..].iter().position(|&c| c == '}');
+                let range = end.and_then(|end| {
+                    let inner: String = chars[i + 1..i + end].iter().collect();
+                    let (lo, hi) = inner.split_once("..")?;
+                    Some((lo.parse::<i64>().ok()?, hi.parse::<i64>().ok()?, end))
+                });
+                if let Some((lo, hi, end)) = range {
+                    let (lo, hi) = (lo.min(hi), lo.max(hi));
+                    let alternatives: Vec<String> = (lo..=hi).map(|n| n.to_string()).collect();
+                    out.push_str(&format!("(?:{})", alternatives.join("|")));
+                    i += end;
+                } else {
+                    brace_depth += 1;
+                    out.push_str("(?:");
+                }
+            }
+            '}' if brace_depth > 0 => {
+                brace_depth -= 1;
+                out.push(')');
+            }
+            ',' if brace_depth > 0 => out.push('|'),
+            '\\' if i + 1 < chars.len() => {
+                out.push_str(&regex::escape(&chars[i + 1].to_string()));
+                i += 1;
+            }
+            _ => out.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    for _ in 0..brace_depth {
+        out.push(')');
+    }
+    out
+}
+
+fn parse_modeline(line: &str) -> Option<DocSettings> {
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|marker| {
+        line.match_indices(marker)
+            .find(|&(pos, _)| {
+                line[..pos]
+                    .chars()
+                    .next_back()
+                    .is_none_or(char::is_whitespace)
+            })
+            .map(|(pos, _)| pos + marker.len())
+    })?;
+    let rest = line[start..].trim_start();
+
+    // "vim: set ts=4 sw=4 et :" ends at the next colon; "vim: ts=4:sw=4" uses colons as separators
+    let options: Vec<&str> = if let Some(set) = rest
+        .strip_prefix("set ")
+        .or_else(|| rest.strip_prefix("se "))
+    {
+        set.split(':').next().unwrap_or("").split_whitespace().collect()
+    } else {
+        rest.split(|c: char| c == ':' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .collect()
+    };
+
+    let mut settings = DocSettings::default();
+    let mut tabstop = None;
+    for option in options {
+        let (name, value) = option.split_once('=').unwrap_or((option, ""));
+        match name {
+            "et" | "expandtab" => settings.indent_style = Some(IndentStyle::Space),
+            "noet" | "noexpandtab" => settings.indent_style = Some(IndentStyle::Tab),
+            "ts" | "tabstop" => tabstop = value.parse().ok().filter(|&n: &usize| n > 0),
+            "sw" | "shiftwidth" => {
+                settings.indent_size = value.parse().ok().filter(|&n: &usize| n > 0)
+            }
+            "ff" | "fileformat" => {
+                settings.end_of_line = match value {
+                    "unix" => Some(LineEnding::Lf),
+                    "dos" => Some(LineEnding::CrLf),
+                    "mac" => Some(LineEnding::Cr),
+                    _ => None,
+                };
+            }
+            "fenc" | "fileencoding" => {
+                settings.charset = charset(value);
+            }
+            _ => {}
+        }
+    }
+    settings.indent_size = settings.indent_size.or(tabstop);
+
+    (settings != DocSettings::default()).then_some(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ============================
+    // glob matching
+    // ============================
+
+    #[test]
+    fn glob_star_matches_basename_anywhere() {
+        assert!(section_matches("*.rs", "main.rs"));
+        assert!(section_matches("*.rs", "src/main.rs"));
+        assert!(!section_matches("*.rs", "main.txt"));
+    }
+
+    #[test]
+    fn glob_with_slash_is_anchored() {
+        assert!(section_matches("src/*.rs", "src/main.rs"));
+        assert!(!section_matches("src/*.rs", "lib/src/main.rs"));
+        assert!(section_matches("/docs/**", "docs/a/b.md"));
+    }
+
+    #[test]
+    fn glob_braces_and_ranges() {
+        assert!(section_matches("*.{js,ts}", "app.ts"));
+        assert!(!section_matches("*.{js,ts}", "app.rs"));
+        assert!(section_matches("file{1..3}.txt", "file2.txt"));
+        assert!(!section_matches("file{1..3}.txt", "file4.txt"));
+    }
+
+    #[test]
+    fn glob_character_classes() {
+        assert!(section_matches("[ab].txt", "a.txt"));
+        assert!(!section_matches("[!ab].txt", "a.txt"));
+        assert!(section_matches("[!ab].txt", "c.txt"));
+    }
+
+    // ============================
+    // .editorconfig parsing
+    // ============================
+
+    #[test]
+    fn parse_root_and_sections() {
+        let src = "root = true\n\n[*]\nindent_style = space\nindent_size = 2\n\n# comment\n[*.md]\ntrim_trailing_whitespace = false\n";
+        let (root, sections) = parse_editorconfig(src);
+        assert!(root);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].glob, "*");
+        assert_eq!(sections[0].properties.len(), 2);
+        assert_eq!(sections[1].properties[0].1, "false");
+    }
+
+    #[test]
+    fn editorconfig_discovery_applies_nearest_last() {
+        let base = std::env::temp_dir().join("notepad_editorconfig_test");
+        let sub = base.join("sub");
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::write(
+            base.join(".editorconfig"),
+            "root = true\n[*]\nindent_style = tab\nend_of_line = crlf\n",
+        )
+        .unwrap();
+        std::fs::write(
+            sub.join(".editorconfig"),
+            "[*.txt]\nindent_style = space\nindent_size = 2\ncharset = latin1\n",
+        )
+        .unwrap();
+
+        let settings = DocSettings::from_editorconfig(&sub.join("notes.txt"));
+        assert_eq!(settings.indent_style, Some(IndentStyle::Space));
+        assert_eq!(settings.indent_size, Some(2));
+        assert_eq!(settings.end_of_line, Some(LineEnding::CrLf));
+        assert_eq!(settings.charset, Some(SaveEncoding::Windows1252));
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn charset_utf8_bom_keeps_the_bom() {
+        let mut settings = DocSettings::default();
+        settings.set_property("charset", "utf-8-bom");
+        assert_eq!(settings.charset, Some(SaveEncoding::Utf8Bom));
+        settings.set_property("charset", "utf-8");
+        assert_eq!(settings.charset, Some(SaveEncoding::Utf8));
+    }
+
+    // ============================
+    // modelines
+    // ============================
+
+    #[test]
+    fn modeline_set_form() {
+        let settings = DocSettings::from_modeline("# vim: set ts=8 sw=4 et ff=dos :\nbody");
+        assert_eq!(settings.indent_style, Some(IndentStyle::Space));
+        assert_eq!(settings.indent_size, Some(4));
+        assert_eq!(settings.end_of_line, Some(LineEnding::CrLf));
+    }
+
+    #[test]
+    fn modeline_colon_form() {
+        let settings = DocSettings::from_modeline("// vi:noet:ts=3");
+        assert_eq!(settings.indent_style, Some(IndentStyle::Tab));
+        assert_eq!(settings.indent_size, Some(3));
+    }
+
+    #[test]
+    fn modeline_requires_word_boundary() {
+        assert_eq!(DocSettings::from_modeline("navi:et"), DocSettings::default());
+    }
+
+    #[test]
+    fn modeline_ignored_after_first_lines() {
+        let text = "a\nb\nc\nd\ne\nf\n# vim: et\n";
+        assert_eq!(DocSettings::from_modeline(text), DocSettings::default());
+    }
+
+    #[test]
+    fn modeline_overrides_editorconfig() {
+        let mut settings = DocSettings {
+            indent_style: Some(IndentStyle::Tab),
+            indent_size: Some(8),
+            ..DocSettings::default()
+        };
+        settings.merge(DocSettings::from_modeline("vim: set sw=2 et:"));
+        assert_eq!(settings.indent_style, Some(IndentStyle::Space));
+        assert_eq!(settings.indent_size, Some(2));
+    }
+
+    // ============================
+    // apply_on_save
+    // ============================
+
+    #[test]
+    fn save_unchanged_without_overrides() {
+        let text = "a  \r\nb\t\nc";
+        assert_eq!(DocSettings::default().apply_on_save(text), text);
+    }
+
+    #[test]
+    fn save_trims_trailing_whitespace() {
+        let settings = DocSettings {
+            trim_trailing_whitespace: Some(true),
+            ..DocSettings::default()
+        };
+        assert_eq!(settings.apply_on_save("a  \nb\t\r\nc "), "a\nb\r\nc");
+        assert_eq!(settings.apply_on_save("a \rb\t\r"), "a\rb\r");
+    }
+
+    #[test]
+    fn save_normalizes_line_endings() {
+        let settings = DocSettings {
+            end_of_line: Some(LineEnding::CrLf),
+            ..DocSettings::default()
+        };
+        assert_eq!(settings.apply_on_save("a\nb\r\nc"), "a\r\nb\r\nc");
+        assert_eq!(settings.apply_on_save("a\rb\r\n\rc"), "a\r\nb\r\n\r\nc");
+    }
+
+    #[test]
+    fn save_inserts_final_newline() {
+        let settings = DocSettings {
+            insert_final_newline: Some(true),
+            ..DocSettings::default()
+        };
+        assert_eq!(settings.apply_on_save("a\r\nb"), "a\r\nb\r\n");
+        assert_eq!(settings.apply_on_save("a\n"), "a\n");
+        assert_eq!(settings.apply_on_save(""), "");
+    }
+
+    #[test]
+    fn save_removes_final_newline_when_false() {
+        let settings = DocSettings {
+            insert_final_newline: Some(false),
+            ..DocSettings::default()
+        };
+        assert_eq!(settings.apply_on_save("a\nb\n\n"), "a\nb");
+    }
+}
@@ -0,0 +1,434 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag};
+use std::path::{Path, PathBuf};
+
+use crate::ink;
+use crate::markdown;
+use crate::print::{self, mm_to_pt, win_ansi, PageSetup, PAGE_HEIGHT_PT, PAGE_WIDTH_PT};
+
+// --- Export of a notes folder to a static site or a combined PDF ---
+
+// Width of the table of contents lines in the PDF, page number included
+const TOC_WIDTH: usize = 72;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Site,
+    Pdf,
+}
+
+impl ExportFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Site => "site HTML",
+            ExportFormat::Pdf => "PDF",
+        }
+    }
+}
+
+// Markdown file of the folder, by path relative to it
+#[derive(Debug, Clone, PartialEq)]
+struct Note {
+    rel: PathBuf,
+    title: String,
+}
+
+// Markdown files of `folder` and its subfolders, hidden ones and `skip` left out
+fn collect_notes(folder: &Path, skip: &Path) -> Vec<Note> {
+    let mut notes = Vec::new();
+    let mut dirs = vec![folder.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(read) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in read.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let hidden = path
+                .file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+            if hidden || path == skip {
+                continue;
+            }
+            if path.is_dir() {
+                dirs.push(path);
+            } else if markdown::is_markdown(Some(&path)) {
+                let Ok(rel) = path.strip_prefix(folder) else {
+                    continue;
+                };
+                let title = rel.with_extension("").to_string_lossy().replace('\\', "/");
+                notes.push(Note {
+                    rel: rel.to_path_buf(),
+                    title,
+                });
+            }
+        }
+    }
+    notes.sort_by_key(|n| n.title.to_lowercase());
+    notes
+}
+
+fn read_note(folder: &Path, note: &Note) -> Result<String, String> {
+    std::fs::read(folder.join(&note.rel))
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        .map_err(|e| format!("{} : {e}", note.rel.display()))
+}
+
+// Runs on a worker thread; returns the number of exported notes
+pub fn export(
+    format: ExportFormat,
+    folder: &Path,
+    target: &Path,
+    report: &dyn Fn(f32) -> bool,
+) -> Result<usize, String> {
+    let notes = collect_notes(folder, target);
+    if notes.is_empty() {
+        return Err(format!("aucun fichier Markdown dans {}", folder.display()));
+    }
+    match format {
+        ExportFormat::Site => export_site(folder, &notes, target, report)?,
+        ExportFormat::Pdf => export_pdf(folder, &notes, target, report)?,
+    }
+    Ok(notes.len())
+}
+
+// --- Static site ---
+
+fn export_site(
+    folder: &Path,
+    notes: &[Note],
+    out: &Path,
+    report: &dyn Fn(f32) -> bool,
+) -> Result<(), String> {
+    let site_name = folder
+        .file_name()
+        .map_or("Notes".to_string(), |n| n.to_string_lossy().into_owned());
+    let write = |path: &Path, html: String| {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, html)
+    };
+    for (i, note) in notes.iter().enumerate() {
+        let text = read_note(folder, note)?;
+        let root = "../".repeat(note.rel.components().count() - 1);
+        let body = note_html(&text, &root, notes);
+        let nav = format!("<nav><a href=\"{root}index.html\">← {}</a></nav>\n", escape(&site_name));
+        let html = page_html(&note.title, &format!("{nav}{body}"));
+        write(&out.join(note.rel.with_extension("html")), html)
+            .map_err(|e| format!("{} : {e}", out.display()))?;
+        if !report((i + 1) as f32 / (notes.len() + 1) as f32) {
+            return Err("Exportation annulée".to_string());
+        }
+    }
+    let mut toc = format!("<h1>{}</h1>\n<ul>\n", escape(&site_name));
+    for note in notes {
+        toc.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            href(&note.rel.with_extension("html")),
+            escape(&note.title)
+        ));
+    }
+    toc.push_str("</ul>\n");
+    write(&out.join("index.html"), page_html(&site_name, &toc))
+        .map_err(|e| format!("{} : {e}", out.display()))?;
+    report(1.0);
+    Ok(())
+}
+
+// [[wiki links]] and links to other .md notes point to the exported pages
+fn note_html(text: &str, root: &str, notes: &[Note]) -> String {
+    let text = link_wiki_notes(text, root, notes);
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(&text, options).map(|event| match event {
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !dest_url.contains("://") && markdown::is_markdown(Some(Path::new(&*dest_url))) => {
+            let dest_url = CowStr::from(Path::new(&*dest_url).with_extension("html").to_string_lossy().into_owned());
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            })
+        }
+        event => event,
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+fn link_wiki_notes(text: &str, root: &str, notes: &[Note]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        let Some(len) = rest[start + 2..].find("]]") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + len];
+        let target = inner.split(['|', '#']).next().unwrap_or_default().trim();
+        let label = inner.split_once('|').map_or(target, |(_, label)| label.trim());
+        out.push_str(&rest[..start]);
+        let note = notes.iter().find(|n| {
+            n.rel
+                .file_stem()
+                .is_some_and(|s| s.to_string_lossy().to_lowercase() == target.to_lowercase())
+        });
+        match note {
+            Some(note) => out.push_str(&format!(
+                "[{label}](<{root}{}>)",
+                href(&note.rel.with_extension("html"))
+            )),
+            None => out.push_str(label),
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn href(rel: &Path) -> String {
+    rel.to_string_lossy().replace('\\', "/")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn page_html(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"fr\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>\n\
+         body {{ max-width: 46em; margin: 2em auto; padding: 0 1em; font-family: sans-serif; line-height: 1.5; }}\n\
+         pre, code {{ background: #f4f4f4; }} pre {{ padding: .5em; overflow-x: auto; }}\n\
+         table {{ border-collapse: collapse; }} td, th {{ border: 1px solid #ccc; padding: .2em .5em; }}\n\
+         nav {{ margin-bottom: 1em; }}\n\
+         </style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        escape(title)
+    )
+}
+
+// --- Combined PDF ---
+
+struct PdfPage {
+    header: String,
+    lines: Vec<String>,
+    ink: Vec<Vec<[f32; 2]>>,
+}
+
+fn export_pdf(
+    folder: &Path,
+    notes: &[Note],
+    out: &Path,
+    report: &dyn Fn(f32) -> bool,
+) -> Result<(), String> {
+    let setup = PageSetup::default();
+    let mut pages: Vec<PdfPage> = Vec::new();
+    // First page of each note, counted without the table of contents
+    let mut starts = Vec::new();
+    for (i, note) in notes.iter().enumerate() {
+        let text = read_note(folder, note)?;
+        starts.push(pages.len());
+        // Handwritten annotations of the note come along
+        let ink = ink::load(&folder.join(&note.rel));
+        let pagination = print::paginate_annotated(&text, &note.title, &setup, &ink);
+        pages.extend(pagination.pages.into_iter().map(|page| PdfPage {
+            header: note.title.clone(),
+            lines: page.lines,
+            ink: page.ink,
+        }));
+        if !report((i + 1) as f32 / (notes.len() + 1) as f32) {
+            return Err("Exportation annulée".to_string());
+        }
+    }
+
+    // The table of contents never wraps, so its page count does not depend
+    // on the page numbers it shows
+    let toc_text = |offset: usize| {
+        let mut text = String::from("Sommaire\n\n");
+        for (note, start) in notes.iter().zip(&starts) {
+            text.push_str(&toc_line(&note.title, start + offset + 1));
+            text.push('\n');
+        }
+        text
+    };
+    let toc_pages = print::paginate(&toc_text(0), "", &setup).pages.len();
+    let toc = print::paginate(&toc_text(toc_pages), "", &setup);
+    let toc: Vec<PdfPage> = toc
+        .pages
+        .into_iter()
+        .map(|page| PdfPage {
+            header: "Sommaire".to_string(),
+            lines: page.lines,
+            ink: Vec::new(),
+        })
+        .collect();
+    let starts: Vec<usize> = starts.iter().map(|s| s + toc_pages).collect();
+    let bytes = pdf_bytes(&toc.into_iter().chain(pages).collect::<Vec<_>>(), notes, &starts, &setup);
+    std::fs::write(out, bytes).map_err(|e| format!("{} : {e}", out.display()))?;
+    report(1.0);
+    Ok(())
+}
+
+fn toc_line(title: &str, page: usize) -> String {
+    let number = page.to_string();
+    let room = TOC_WIDTH.saturating_sub(number.len() + 2);
+    let title: String = title.chars().take(room).collect();
+    let dots = TOC_WIDTH.saturating_sub(title.chars().count() + number.len() + 2);
+    format!("{title} {} {number}", ".".repeat(dots))
+}
+
+fn pdf_bytes(pages: &[PdfPage], notes: &[Note], starts: &[usize], setup: &PageSetup) -> Vec<u8> {
+    let catalog_id = Ref::new(1);
+    let tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let bold_id = Ref::new(4);
+    let outline_id = Ref::new(5);
+    let first = 6;
+    let page_ids: Vec<Ref> = (0..pages.len()).map(|i| Ref::new(first + 2 * i as i32)).collect();
+    let item_first = first + 2 * pages.len() as i32;
+    let item_ids: Vec<Ref> = (0..notes.len()).map(|i| Ref::new(item_first + i as i32)).collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(tree_id).outlines(outline_id);
+    pdf.pages(tree_id)
+        .kids(page_ids.iter().copied())
+        .count(pages.len() as i32);
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Courier"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold_id)
+        .base_font(Name(b"Courier-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    let margin = mm_to_pt(setup.margin_mm);
+    let size = setup.font_size;
+    let line_height = size * print::LINE_HEIGHT_RATIO;
+    let top = PAGE_HEIGHT_PT - margin - size;
+    for (i, page) in pages.iter().enumerate() {
+        let content_id = Ref::new(page_ids[i].get() + 1);
+        let mut content = Content::new();
+        content.begin_text();
+        content.set_font(Name(b"F2"), size);
+        content.next_line(margin, top);
+        content.show(Str(&win_ansi(&page.header)));
+        content.next_line(0.0, -2.0 * line_height);
+        for line in &page.lines {
+            let font = if line.starts_with('#') { b"F2" } else { b"F1" };
+            content.set_font(Name(font), size);
+            content.show(Str(&win_ansi(line)));
+            content.next_line(0.0, -line_height);
+        }
+        content.end_text();
+        print::draw_ink(&mut content, &page.ink, margin, top + size - 2.0 * line_height, size);
+        let footer = format!("Page {} / {}", i + 1, pages.len());
+        content.begin_text();
+        content.set_font(Name(b"F1"), size);
+        content.next_line(margin, margin);
+        content.show(Str(&win_ansi(&footer)));
+        content.end_text();
+
+        let mut pdf_page = pdf.page(page_ids[i]);
+        pdf_page
+            .parent(tree_id)
+            .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH_PT, PAGE_HEIGHT_PT))
+            .contents(content_id);
+        pdf_page
+            .resources()
+            .fonts()
+            .pair(Name(b"F1"), font_id)
+            .pair(Name(b"F2"), bold_id);
+        pdf_page.finish();
+        pdf.stream(content_id, &content.finish());
+    }
+
+    // Bookmarks to the first page of each note
+    let mut outline = pdf.outline(outline_id);
+    if let (Some(first), Some(last)) = (item_ids.first(), item_ids.last()) {
+        outline.first(*first).last(*last);
+    }
+    outline.count(item_ids.len() as i32);
+    outline.finish();
+    for (i, note) in notes.iter().enumerate() {
+        let mut item = pdf.outline_item(item_ids[i]);
+        item.title(TextStr(&note.title)).parent(outline_id);
+        if i > 0 {
+            item.prev(item_ids[i - 1]);
+        }
+        if let Some(next) = item_ids.get(i + 1) {
+            item.next(*next);
+        }
+        item.dest().page(page_ids[starts[i]]).xyz(0.0, PAGE_HEIGHT_PT, None);
+    }
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes_folder(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("notepad_export_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("projets")).unwrap();
+        std::fs::create_dir_all(root.join(".cache")).unwrap();
+        std::fs::write(root.join("Accueil.md"), "# Accueil\n\nVoir [[Projet A|le projet]] et [[Absent]].\n").unwrap();
+        std::fs::write(root.join("projets/Projet A.md"), "Retour à [l'accueil](../Accueil.md)\n").unwrap();
+        std::fs::write(root.join(".cache/cache.md"), "").unwrap();
+        std::fs::write(root.join("image.png"), "").unwrap();
+        root
+    }
+
+    #[test]
+    fn collects_markdown_notes_recursively() {
+        let root = notes_folder("collect");
+        let titles: Vec<String> = collect_notes(&root, &root.join("site")).into_iter().map(|n| n.title).collect();
+        assert_eq!(titles, ["Accueil", "projets/Projet A"]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn site_links_notes_together() {
+        let root = notes_folder("site");
+        let out = root.join("site");
+        assert_eq!(export(ExportFormat::Site, &root, &out, &|_| true), Ok(2));
+        let home = std::fs::read_to_string(out.join("Accueil.html")).unwrap();
+        assert!(home.contains("<h1>Accueil</h1>"));
+        assert!(home.contains("<a href=\"projets/Projet%20A.html\">le projet</a>"));
+        assert!(home.contains("et Absent."));
+        let projet = std::fs::read_to_string(out.join("projets/Projet A.html")).unwrap();
+        assert!(projet.contains("href=\"../Accueil.html\""));
+        assert!(projet.contains("<a href=\"../index.html\">"));
+        let index = std::fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(index.contains("<a href=\"projets/Projet A.html\">projets/Projet A</a>"));
+        // A second export skips the output folder
+        assert_eq!(export(ExportFormat::Site, &root, &out, &|_| true), Ok(2));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn pdf_has_a_table_of_contents() {
+        let root = notes_folder("pdf");
+        let out = root.join("notes.pdf");
+        assert_eq!(export(ExportFormat::Pdf, &root, &out, &|_| true), Ok(2));
+        let bytes = std::fs::read(&out).unwrap();
+        assert!(bytes.starts_with(b"%PDF-"));
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/Count 3"));
+        assert!(text.contains("Sommaire"));
+        assert!(export(ExportFormat::Pdf, &root, &out, &|_| false).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn toc_lines_align_page_numbers() {
+        let line = toc_line("Accueil", 12);
+        assert_eq!(line.chars().count(), TOC_WIDTH);
+        assert!(line.starts_with("Accueil ...") && line.ends_with(" 12"));
+    }
+}
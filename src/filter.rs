@@ -0,0 +1,113 @@
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// --- Selection filtered through an external command ---
+
+// How often a running command is checked for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[cfg(target_os = "windows")]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut shell = Command::new("cmd.exe");
+    shell.arg("/C").raw_arg(command).creation_flags(CREATE_NO_WINDOW);
+    shell
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("/bin/sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+fn read_all(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+// Blocking: pipes `input` to the command through the shell and returns what it
+// printed. `keep_going` is polled while it runs; false kills the command.
+pub fn run(command: &str, input: &str, keep_going: &dyn Fn() -> bool) -> Result<String, String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("impossible de lancer la commande : {e}"))?;
+
+    // Each pipe has its own thread so a command writing before it has read
+    // all of its input never blocks on a full pipe
+    let (Some(mut stdin), Some(stdout), Some(stderr)) =
+        (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+        return Err("entrées / sorties de la commande indisponibles".to_string());
+    };
+    let trailing_newline = input.ends_with('\n');
+    let input = input.to_owned();
+    let writer = std::thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let (stdout, stderr) = (read_all(stdout), read_all(stderr));
+
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if !keep_going() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("commande interrompue".to_string());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    let _ = writer.join();
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        let message = String::from_utf8_lossy(&stderr).trim().to_string();
+        return Err(if message.is_empty() {
+            format!("la commande a échoué ({status})")
+        } else {
+            message
+        });
+    }
+    Ok(fit_output(trailing_newline, &String::from_utf8_lossy(&stdout)))
+}
+
+// Line endings become \n like in the editor; a selection without a final
+// newline does not get the one most commands print
+fn fit_output(input_ends_with_newline: bool, output: &str) -> String {
+    let mut output = output.replace("\r\n", "\n");
+    if !input_ends_with_newline && output.ends_with('\n') {
+        output.pop();
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_keeps_the_shape_of_the_selection() {
+        assert_eq!(fit_output(false, "a\r\nb\r\n"), "a\nb");
+        assert_eq!(fit_output(true, "a\nb\n"), "a\nb\n");
+        assert_eq!(fit_output(false, "a"), "a");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_filter_their_input() {
+        assert_eq!(run("sort", "poire\npomme\nabricot", &|| true).unwrap(), "abricot\npoire\npomme");
+        let error = run("echo 'mauvais format' >&2; exit 3", "x", &|| true).unwrap_err();
+        assert_eq!(error, "mauvais format");
+        assert_eq!(run("sleep 5", "", &|| false).unwrap_err(), "commande interrompue");
+    }
+}
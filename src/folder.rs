@@ -0,0 +1,89 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+// --- Text files of an opened folder ---
+
+// Files listed from one folder; larger trees are truncated
+pub const MAX_FOLDER_FILES: usize = 2000;
+
+// Bytes read to tell a text file from a binary one
+const SNIFF_BYTES: u64 = 8 * 1024;
+
+// Build and dependency folders that would bury the documents
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "__pycache__"];
+
+// A NUL byte in the first kilobytes means binary
+pub fn is_text_file(path: &Path) -> bool {
+    let mut head = Vec::new();
+    let read = std::fs::File::open(path).and_then(|f| f.take(SNIFF_BYTES).read_to_end(&mut head));
+    read.is_ok() && !head.contains(&0)
+}
+
+// Blocking: text files under `folder`, relative to it and sorted; hidden
+// entries are skipped
+pub fn text_files(folder: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![folder.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(read) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                if !SKIPPED_DIRS.contains(&name.as_str()) {
+                    dirs.push(path);
+                }
+            } else if files.len() < MAX_FOLDER_FILES && is_text_file(&path) {
+                if let Ok(rel) = path.strip_prefix(folder) {
+                    files.push(rel.to_path_buf());
+                }
+            }
+        }
+    }
+    files.sort_by_key(|p| p.to_string_lossy().to_lowercase());
+    files
+}
+
+// Every word of the query must appear in the relative path
+pub fn filter<'a>(files: &'a [PathBuf], query: &str) -> impl Iterator<Item = &'a PathBuf> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    files.iter().filter(move |path| {
+        let path = path.to_string_lossy().to_lowercase();
+        words.iter().all(|w| path.contains(w.as_str()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_text_files_and_skips_binaries() {
+        let root = std::env::temp_dir().join(format!("notepad_folder_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules")).unwrap();
+        std::fs::write(root.join("Lisez-moi.txt"), "bonjour").unwrap();
+        std::fs::write(root.join("docs").join("plan.md"), "# Plan").unwrap();
+        std::fs::write(root.join("image.png"), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+        std::fs::write(root.join(".git").join("HEAD"), "ref").unwrap();
+        std::fs::write(root.join("node_modules").join("a.js"), "x").unwrap();
+
+        let files = text_files(&root);
+        assert_eq!(files, [Path::new("docs").join("plan.md"), PathBuf::from("Lisez-moi.txt")]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn filter_matches_every_word() {
+        let files = [PathBuf::from("docs/Plan.md"), PathBuf::from("notes/plan.txt"), PathBuf::from("a.txt")];
+        let hits: Vec<_> = filter(&files, "PLAN docs").collect();
+        assert_eq!(hits, [&files[0]]);
+        assert_eq!(filter(&files, "").count(), 3);
+    }
+}
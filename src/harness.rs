@@ -0,0 +1,210 @@
+use std::path::{Path, PathBuf};
+
+use iced::keyboard::{self, Key, Modifiers};
+use iced::widget::text_editor;
+use iced::Event;
+
+use crate::app::{Document, EditMsg, FileMsg, Message, Notepad, SearchMsg};
+
+// --- Scripted sessions for tests: messages in, state out, no window ---
+
+// Drives `Notepad::update` the way the runtime would, minus the runtime: the
+// tasks it returns are dropped, so dialogs and background jobs never answer.
+// Their results are sent as messages by the test when a flow needs them.
+// Small files open and save synchronously, which covers most flows.
+pub struct Harness {
+    pub app: Notepad,
+    dir: PathBuf,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("notepad_harness_{}_{n}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self { app: Notepad::test_default(), dir }
+    }
+
+    // File in the harness' own folder, removed with it
+    pub fn file(&self, name: &str, contents: &str) -> PathBuf {
+        let path = self.dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    pub fn send(&mut self, message: Message) -> &mut Self {
+        let _ = self.app.update(message);
+        self
+    }
+
+    pub fn event(&mut self, event: Event) -> &mut Self {
+        self.send(Message::EventOccurred(event))
+    }
+
+    // Opened as if dropped on the window
+    pub fn open(&mut self, path: &Path) -> &mut Self {
+        self.event(Event::Window(iced::window::Event::FileDropped(path.to_path_buf())))
+    }
+
+    pub fn edit(&mut self, edit: text_editor::Edit) -> &mut Self {
+        self.send(Message::EditorAction(text_editor::Action::Edit(edit)))
+    }
+
+    // One editor action per character, as typed on the keyboard
+    pub fn type_text(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            self.edit(if c == '\n' { text_editor::Edit::Enter } else { text_editor::Edit::Insert(c) });
+        }
+        self
+    }
+
+    // Key press outside the text editor: shortcuts, Escape
+    pub fn press(&mut self, key: Key, modifiers: Modifiers) -> &mut Self {
+        self.event(Event::Keyboard(keyboard::Event::KeyPressed {
+            key: key.clone(),
+            modified_key: key,
+            physical_key: keyboard::key::Physical::Unidentified(keyboard::key::NativeCode::Unidentified),
+            location: keyboard::Location::Standard,
+            modifiers,
+            text: None,
+            repeat: false,
+        }))
+    }
+
+    pub fn find(&mut self, query: &str) -> &mut Self {
+        self.send(Message::Search(SearchMsg::OpenFind))
+            .send(Message::Search(SearchMsg::FindQueryChanged(query.to_string())))
+    }
+
+    pub fn replace_all(&mut self, query: &str, replacement: &str) -> &mut Self {
+        self.send(Message::Search(SearchMsg::OpenReplace))
+            .send(Message::Search(SearchMsg::FindQueryChanged(query.to_string())))
+            .send(Message::Search(SearchMsg::ReplaceQueryChanged(replacement.to_string())))
+            .send(Message::Search(SearchMsg::ReplaceAll))
+    }
+
+    pub fn undo(&mut self) -> &mut Self {
+        self.send(Message::Edit(EditMsg::Undo))
+    }
+
+    pub fn save(&mut self) -> &mut Self {
+        self.send(Message::File(FileMsg::Save))
+    }
+
+    pub fn doc(&self) -> &Document {
+        self.app.active_doc()
+    }
+
+    pub fn text(&self) -> String {
+        self.doc().content.text()
+    }
+
+    pub fn selection(&self) -> Option<String> {
+        self.doc().content.selection()
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        let position = self.doc().content.cursor().position;
+        (position.line, position.column)
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::keyboard::key::Named;
+
+    #[test]
+    fn open_edit_find_replace_undo_save() {
+        let mut h = Harness::new();
+        let path = h.file("liste.txt", "pommes\npoires\npommes\n");
+        h.open(&path);
+        assert_eq!(h.text(), "pommes\npoires\npommes\n");
+        assert!(!h.doc().is_modified);
+
+        h.type_text("fruits :\n");
+        assert!(h.doc().is_modified);
+        assert_eq!(h.cursor(), (1, 0));
+
+        // Search as you type selects the first match after the caret
+        h.find("pommes");
+        assert_eq!(h.selection().as_deref(), Some("pommes"));
+        assert_eq!(h.cursor().0, 1);
+
+        h.replace_all("pommes", "prunes");
+        assert_eq!(h.text(), "fruits :\nprunes\npoires\nprunes\n");
+        h.undo();
+        assert_eq!(h.text(), "fruits :\npommes\npoires\npommes\n");
+
+        h.press(Key::Named(Named::Escape), Modifiers::empty());
+        assert!(!h.app.show_find && !h.app.show_replace);
+
+        h.save();
+        assert!(!h.doc().is_modified);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fruits :\npommes\npoires\npommes\n");
+    }
+
+    #[test]
+    fn tabs_keep_their_own_history() {
+        let mut h = Harness::new();
+        let first = h.file("a.txt", "un");
+        let second = h.file("b.txt", "deux");
+        h.open(&first).type_text("!").open(&second);
+        assert_eq!(h.app.tabs.len(), 2);
+        assert_eq!(h.text(), "deux");
+
+        // Ctrl+Tab through the keyboard handler, as the shortcut
+        h.press(Key::Named(Named::Tab), Modifiers::CTRL);
+        assert_eq!(h.text(), "!un");
+        h.undo();
+        assert_eq!(h.text(), "un");
+        h.send(Message::File(FileMsg::SwitchTab(1))).undo();
+        assert_eq!(h.text(), "deux");
+    }
+
+    #[test]
+    fn recorded_macro_replays_down_the_lines() {
+        let mut h = Harness::new();
+        let path = h.file("courses.txt", "pain\nlait\nœufs\nbeurre\n");
+        let record = Key::Character("r".into());
+        h.open(&path).press(record.clone(), Modifiers::CTRL | Modifiers::SHIFT);
+        assert!(h.app.macro_recording.is_some());
+        h.type_text("- ")
+            .send(Message::EditorAction(text_editor::Action::Move(text_editor::Motion::Down)))
+            .send(Message::EditorAction(text_editor::Action::Move(text_editor::Motion::Home)))
+            .press(record, Modifiers::CTRL | Modifiers::SHIFT);
+        assert_eq!(h.app.last_macro.len(), 4);
+
+        h.press(Key::Character("p".into()), Modifiers::CTRL | Modifiers::SHIFT);
+        assert_eq!(h.text(), "- pain\n- lait\nœufs\nbeurre\n");
+        // Far more times than there are lines: the end of the document stops it
+        h.send(Message::Edit(EditMsg::PlayMacroTimes))
+            .send(Message::Edit(EditMsg::MacroCountChanged("50".into())))
+            .send(Message::Edit(EditMsg::RunMacroTimes));
+        assert_eq!(h.text(), "- pain\n- lait\n- œufs\n- beurre\n");
+        assert_eq!(h.cursor(), (4, 0));
+        h.undo();
+        assert_eq!(h.text(), "- pain\n- lait\nœufs\nbeurre\n");
+    }
+
+    #[test]
+    fn leaving_the_window_saves_when_asked() {
+        let mut h = Harness::new();
+        let path = h.file("main.rs", "fn main() {}\n");
+        let unfocused = Event::Window(iced::window::Event::Unfocused);
+        h.open(&path).type_text("// ").event(unfocused.clone());
+        assert!(h.doc().is_modified);
+
+        h.send(Message::Settings(crate::app::SettingsMsg::SetSaveOnFocusLoss(true)));
+        h.event(unfocused);
+        assert!(!h.doc().is_modified);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "// fn main() {}\n");
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+
+use crate::app::ViewState;
+
+// Estimated memory in use above which background tabs get hibernated
+pub const HIBERNATE_ABOVE_BYTES: usize = 512 * 1024 * 1024;
+
+// Rough cost of one editor line besides its text (layout, attributes, shaping cache)
+pub const EDITOR_LINE_BYTES: usize = 256;
+
+// --- Hibernated tabs ---
+
+// Content and history of a background tab, moved to a temporary file
+#[derive(Serialize, Deserialize)]
+pub struct HibernatedTab {
+    pub text: String,
+    pub undo: Vec<SavedSnapshot>,
+    pub redo: Vec<SavedSnapshot>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedSnapshot {
+    pub text: String,
+    pub cursor_line: usize,
+    pub cursor_col: usize,
+}
+
+// Where a hibernated tab lives; the file is removed with it
+#[derive(Debug)]
+pub struct Hibernation {
+    pub path: PathBuf,
+    pub view: ViewState,
+}
+
+impl Hibernation {
+    pub fn write(doc_id: u64, view: ViewState, tab: &HibernatedTab) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "notepad-{}-{doc_id}.hibernate",
+            std::process::id()
+        ));
+        let json = serde_json::to_vec(tab).map_err(io::Error::other)?;
+        std::fs::write(&path, json)?;
+        Ok(Self { path, view })
+    }
+
+    pub fn read(&self) -> io::Result<HibernatedTab> {
+        let json = std::fs::read(&self.path)?;
+        serde_json::from_slice(&json).map_err(io::Error::other)
+    }
+}
+
+impl Drop for Hibernation {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// --- Memory estimate ---
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemoryUsage {
+    // Editor buffer, rope mirror, cached copy and search index
+    pub text: usize,
+    // Undo / redo snapshots
+    pub history: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.text + self.history
+    }
+}
+
+pub fn format_size(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    let text = if bytes < KB {
+        format!("{bytes} o")
+    } else if bytes < KB * KB {
+        format!("{:.1} Ko", bytes / KB)
+    } else if bytes < KB * KB * KB {
+        format!("{:.1} Mo", bytes / (KB * KB))
+    } else {
+        format!("{:.2} Go", bytes / (KB * KB * KB))
+    };
+    text.replace('.', ",")
+}
+
+// Background tab that may be hibernated
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate {
+    pub index: usize,
+    pub usage: usize,
+    pub last_active: u64,
+}
+
+// Least recently used candidates to hibernate until `total` fits under `limit`
+pub fn pick(mut candidates: Vec<Candidate>, total: usize, limit: usize) -> Vec<usize> {
+    candidates.sort_by_key(|c| c.last_active);
+    let mut total = total;
+    let mut picked = Vec::new();
+    for candidate in candidates {
+        if total <= limit {
+            break;
+        }
+        total = total.saturating_sub(candidate.usage);
+        picked.push(candidate.index);
+    }
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(index: usize, usage: usize, last_active: u64) -> Candidate {
+        Candidate {
+            index,
+            usage,
+            last_active,
+        }
+    }
+
+    #[test]
+    fn pick_takes_least_recently_used_until_under_limit() {
+        let candidates = vec![candidate(0, 300, 5), candidate(1, 300, 1), candidate(2, 300, 3)];
+        assert_eq!(pick(candidates.clone(), 1000, 2000), Vec::<usize>::new());
+        assert_eq!(pick(candidates.clone(), 1000, 700), vec![1]);
+        assert_eq!(pick(candidates, 1000, 100), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn sizes_are_formatted_in_french() {
+        assert_eq!(format_size(512), "512 o");
+        assert_eq!(format_size(1536), "1,5 Ko");
+        assert_eq!(format_size(3 * 1024 * 1024), "3,0 Mo");
+    }
+
+    #[test]
+    fn hibernation_file_is_removed_on_drop() {
+        let view = ViewState {
+            line: 1,
+            column: 2,
+            scroll: 0.0,
+        };
+        let tab = HibernatedTab {
+            text: "contenu".into(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+        };
+        let hibernation = Hibernation::write(u64::MAX, view, &tab).unwrap();
+        let path = hibernation.path.clone();
+        assert_eq!(hibernation.read().unwrap().text, "contenu");
+        drop(hibernation);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use ureq::{Agent, Proxy};
+
+use crate::secrets;
+
+// --- HTTP client shared by the network features ---
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+pub const USER_AGENT: &str = concat!("notepad/", env!("CARGO_PKG_VERSION"));
+const INVALID_CREDENTIALS: &str = "identifiants du proxy invalides (ASCII sans @ / ? #)";
+#[cfg(windows)]
+const INTERNET_SETTINGS: &str = r"Software\Microsoft\Windows\CurrentVersion\Internet Settings";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProxyMode {
+    // HTTPS_PROXY and the like, then the Internet settings on Windows
+    // (ProxyEnable, ProxyServer and ProxyOverride)
+    #[default]
+    System,
+    Direct,
+    // The address typed in the settings
+    Manual,
+}
+
+impl ProxyMode {
+    pub const ALL: [ProxyMode; 3] = [ProxyMode::System, ProxyMode::Direct, ProxyMode::Manual];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::System => "Système",
+            Self::Direct => "Aucun",
+            Self::Manual => "Manuel",
+        }
+    }
+}
+
+// Saved with the preferences; the password goes to the system keychain
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    // "proxy.local:3128", "http://proxy:8080" or "socks5://proxy:1080"
+    pub address: String,
+    // Empty when the proxy needs no authentication
+    pub user: String,
+}
+
+impl ProxySettings {
+    // Keychain entry holding the password of this user on this proxy
+    pub fn password_name(&self) -> String {
+        secrets::name("proxy", &format!("{}@{}", self.user, self.address.trim()))
+    }
+}
+
+// Blocking: the proxy password is read from the keychain
+pub fn agent(settings: &ProxySettings) -> Result<Agent, String> {
+    let proxy = match settings.mode {
+        ProxyMode::System => system_proxy(),
+        ProxyMode::Direct => None,
+        ProxyMode::Manual => {
+            let password = if settings.user.is_empty() {
+                None
+            } else {
+                secrets::load(&settings.password_name())?
+            };
+            Some(manual_proxy(settings, password.as_deref())?)
+        }
+    };
+    Ok(Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .proxy(proxy)
+        .build()
+        .into())
+}
+
+// ureq's win-system-proxy feature reads the Internet settings after the
+// environment, but only when one proxy serves every protocol
+fn system_proxy() -> Option<Proxy> {
+    Proxy::try_from_env().or_else(internet_settings_proxy)
+}
+
+#[cfg(windows)]
+fn internet_settings_proxy() -> Option<Proxy> {
+    use winreg::enums::{HKEY_CURRENT_USER, KEY_READ};
+
+    let settings = winreg::RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey_with_flags(INTERNET_SETTINGS, KEY_READ)
+        .ok()?;
+    if settings.get_value::<u32, _>("ProxyEnable").ok()? != 1 {
+        return None;
+    }
+    let server: String = settings.get_value("ProxyServer").ok()?;
+    let bypass: String = settings.get_value("ProxyOverride").unwrap_or_default();
+    windows_proxy(&server, &bypass)
+}
+
+#[cfg(not(windows))]
+fn internet_settings_proxy() -> Option<Proxy> {
+    None
+}
+
+// `server` is "host:port" for every protocol or "http=host:port;https=host:port";
+// requests are HTTPS, so their entry wins. `bypass` lists the hosts reached
+// directly, separated by ";"; "<local>" (names without a dot) has no equivalent
+#[cfg(any(windows, test))]
+fn windows_proxy(server: &str, bypass: &str) -> Option<Proxy> {
+    let entry = |scheme: &str| {
+        server
+            .split(';')
+            .find_map(|entry| entry.trim().strip_prefix(scheme)?.strip_prefix('='))
+    };
+    let address = if server.contains('=') {
+        entry("https").or_else(|| entry("http"))?
+    } else {
+        server.trim()
+    };
+    let proxy = Proxy::new(address).ok()?;
+    let builder = Proxy::builder(proxy.protocol()).host(proxy.host()).port(proxy.port());
+    bypass
+        .split(';')
+        .map(str::trim)
+        .filter(|host| !host.is_empty() && *host != "<local>")
+        .fold(builder, |builder, host| builder.no_proxy(host))
+        .build()
+        .ok()
+}
+
+fn manual_proxy(settings: &ProxySettings, password: Option<&str>) -> Result<Proxy, String> {
+    let address = settings.address.trim();
+    if address.is_empty() {
+        return Err("adresse du proxy manquante".to_string());
+    }
+    let proxy = Proxy::new(address).map_err(|_| format!("adresse de proxy invalide : {address}"))?;
+    if settings.user.is_empty() {
+        return Ok(proxy);
+    }
+    // Credentials are sent as typed inside the address, so they must be ASCII
+    // without the characters ending its user part
+    let usable = |text: &str| text.is_ascii() && !text.contains(['@', '/', '?', '#']);
+    if !usable(&settings.user) || settings.user.contains(':') || !password.is_none_or(usable) {
+        return Err(INVALID_CREDENTIALS.to_string());
+    }
+    let mut builder = Proxy::builder(proxy.protocol())
+        .host(proxy.host())
+        .port(proxy.port())
+        .username(&settings.user);
+    if let Some(password) = password {
+        builder = builder.password(password);
+    }
+    builder.build().map_err(|_| INVALID_CREDENTIALS.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manual(address: &str, user: &str) -> ProxySettings {
+        ProxySettings {
+            mode: ProxyMode::Manual,
+            address: address.to_string(),
+            user: user.to_string(),
+        }
+    }
+
+    #[test]
+    fn manual_proxy_carries_the_credentials() {
+        let proxy = manual_proxy(&manual(" proxy.local:3128 ", ""), None).unwrap();
+        assert_eq!((proxy.host(), proxy.port()), ("proxy.local", 3128));
+        assert_eq!(proxy.username(), None);
+
+        let proxy = manual_proxy(&manual("http://proxy.local:8080", "lea"), Some("s3cret")).unwrap();
+        assert_eq!(proxy.username(), Some("lea"));
+        assert_eq!(proxy.password(), Some("s3cret"));
+        assert_eq!(proxy.port(), 8080);
+
+        assert!(manual_proxy(&manual("", ""), None).is_err());
+        assert!(manual_proxy(&manual("proxy:3128", "lea"), Some("a@b")).is_err());
+        assert!(manual_proxy(&manual("proxy:3128", "léa"), None).is_err());
+        assert_eq!(manual("proxy:3128", "lea").password_name(), "proxy:lea@proxy:3128");
+    }
+
+    #[test]
+    fn windows_proxy_server_per_protocol() {
+        let proxy = windows_proxy("proxy.local:3128", "").unwrap();
+        assert_eq!((proxy.host(), proxy.port()), ("proxy.local", 3128));
+
+        let proxy = windows_proxy("ftp=ftp.local:21;http=web.local:80;https=secure.local:8443", "").unwrap();
+        assert_eq!((proxy.host(), proxy.port()), ("secure.local", 8443));
+        let proxy = windows_proxy("http=web.local:80", "").unwrap();
+        assert_eq!(proxy.host(), "web.local");
+        assert!(windows_proxy("socks=socks.local:1080", "").is_none());
+
+        let proxy = windows_proxy("proxy.local:3128", "*.intra.fr; <local>;updates.example.org").unwrap();
+        let uri = |text: &str| text.parse::<ureq::http::Uri>().unwrap();
+        assert!(proxy.is_no_proxy(&uri("https://wiki.intra.fr/")));
+        assert!(proxy.is_no_proxy(&uri("https://updates.example.org/")));
+        assert!(!proxy.is_no_proxy(&uri("https://github.com/")));
+    }
+}
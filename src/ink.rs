@@ -0,0 +1,204 @@
+use std::path::{Path, PathBuf};
+
+use iced::mouse;
+use iced::touch;
+use iced::widget::canvas::{self, Event, Frame, Path as Line, Stroke as Pen};
+use iced::{Color, Point, Rectangle, Renderer, Theme, Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{InkMsg, Message};
+
+// --- Handwritten annotations drawn over the text ---
+
+const PEN_WIDTH: f32 = 2.0;
+// Pointer moves closer than this (in pixels) add no point to the stroke
+const MIN_POINT_DISTANCE: f32 = 2.0;
+
+// Points in text units: character columns from the left of the text and lines
+// from the top of the document, so annotations follow scrolling and zoom
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Stroke {
+    pub points: Vec<[f32; 2]>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Sidecar {
+    strokes: Vec<Stroke>,
+}
+
+// Hidden file beside the document: "notes.txt" is annotated in ".notes.txt.ink"
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.ink"))
+}
+
+// Annotations of the file, none when it has no sidecar or an unreadable one
+pub fn load(path: &Path) -> Vec<Stroke> {
+    let sidecar = sidecar_path(path);
+    let Ok(json) = std::fs::read_to_string(&sidecar) else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Sidecar>(&json) {
+        Ok(sidecar) => sidecar.strokes,
+        Err(e) => {
+            tracing::warn!("Annotations illisibles dans {} : {e}", sidecar.display());
+            Vec::new()
+        }
+    }
+}
+
+// The sidecar is removed once the last stroke is erased
+pub fn save(path: &Path, strokes: &[Stroke]) -> std::io::Result<()> {
+    let sidecar = sidecar_path(path);
+    if strokes.is_empty() {
+        return match std::fs::remove_file(&sidecar) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let json = serde_json::to_string(&Sidecar { strokes: strokes.to_vec() }).map_err(std::io::Error::other)?;
+    std::fs::write(sidecar, json)
+}
+
+// Canvas laid over an editor pane. Only the pane with focus takes the pointer;
+// the other one just shows its annotations.
+pub struct Layer<'a> {
+    pub strokes: &'a [Stroke],
+    pub drawing: Option<&'a Stroke>,
+    pub interactive: bool,
+    pub scroll: f32,
+    // Pixels per column and per line, and the editor's inner padding
+    pub column_width: f32,
+    pub line_height: f32,
+    pub padding: f32,
+    pub color: Color,
+}
+
+#[derive(Default)]
+pub struct LayerState {
+    last: Option<Point>,
+}
+
+impl Layer<'_> {
+    fn to_text(&self, at: Point) -> [f32; 2] {
+        [
+            (at.x - self.padding) / self.column_width,
+            (at.y - self.padding) / self.line_height + self.scroll,
+        ]
+    }
+
+    fn to_screen(&self, [column, line]: [f32; 2]) -> Point {
+        Point::new(
+            self.padding + column * self.column_width,
+            self.padding + (line - self.scroll) * self.line_height,
+        )
+    }
+}
+
+impl canvas::Program<Message> for Layer<'_> {
+    type State = LayerState;
+
+    fn update(
+        &self,
+        state: &mut LayerState,
+        event: &Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<Message>> {
+        if !self.interactive {
+            return None;
+        }
+        let inside = |at: Point| bounds.contains(at).then(|| Point::new(at.x - bounds.x, at.y - bounds.y));
+        // A stylus reaches the window as touches or as the mouse, depending on the system
+        let message = match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let at = cursor.position_in(bounds)?;
+                state.last = Some(at);
+                InkMsg::Begin(self.to_text(at))
+            }
+            Event::Touch(touch::Event::FingerPressed { position, .. }) => {
+                let at = inside(*position)?;
+                state.last = Some(at);
+                InkMsg::Begin(self.to_text(at))
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position })
+            | Event::Touch(touch::Event::FingerMoved { position, .. }) => {
+                let last = state.last?;
+                let at = inside(*position)?;
+                if at.distance(last) < MIN_POINT_DISTANCE {
+                    return None;
+                }
+                state.last = Some(at);
+                InkMsg::Extend(self.to_text(at))
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+            | Event::Touch(touch::Event::FingerLifted { .. } | touch::Event::FingerLost { .. }) => {
+                state.last.take()?;
+                InkMsg::End
+            }
+            _ => return None,
+        };
+        Some(canvas::Action::publish(Message::Ink(message)).and_capture())
+    }
+
+    fn mouse_interaction(&self, _state: &LayerState, bounds: Rectangle, cursor: mouse::Cursor) -> mouse::Interaction {
+        if self.interactive && cursor.is_over(bounds) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &LayerState,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let pen = Pen::default().with_color(self.color).with_width(PEN_WIDTH);
+        for stroke in self.strokes.iter().chain(self.drawing) {
+            let line = Line::new(|builder| {
+                let mut points = stroke.points.iter().map(|p| self.to_screen(*p));
+                if let Some(first) = points.next() {
+                    builder.move_to(first);
+                    // A dot is drawn as a tiny segment so it shows
+                    if stroke.points.len() == 1 {
+                        builder.line_to(first + Vector::new(0.5, 0.0));
+                    }
+                    for point in points {
+                        builder.line_to(point);
+                    }
+                }
+            });
+            frame.stroke(&line, pen);
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotations_live_beside_the_file() {
+        let dir = std::env::temp_dir().join(format!("notepad_ink_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("notes.txt");
+        assert_eq!(sidecar_path(&file), dir.join(".notes.txt.ink"));
+        assert!(load(&file).is_empty());
+
+        let strokes = vec![Stroke { points: vec![[1.0, 0.5], [4.5, 2.0]] }];
+        save(&file, &strokes).unwrap();
+        assert_eq!(load(&file), strokes);
+        save(&file, &[]).unwrap();
+        assert!(!sidecar_path(&file).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
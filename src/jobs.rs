@@ -0,0 +1,390 @@
+use iced::futures::channel::{mpsc, oneshot};
+use iced::futures::Stream;
+use iced::task::Handle;
+use iced::Task;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Instant;
+
+// Size of the chunks used by the reading / writing workers
+const IO_CHUNK_SIZE: usize = 1024 * 1024;
+
+pub type JobId = u64;
+
+// --- Running job ---
+
+pub struct Job {
+    pub id: JobId,
+    pub label: String,
+    pub progress: f32,
+    pub cancellable: bool,
+    pub started: Instant,
+    handle: Handle,
+}
+
+// --- Registry of the background tasks currently running ---
+
+#[derive(Default)]
+pub struct Jobs {
+    next_id: JobId,
+    running: Vec<Job>,
+}
+
+impl Jobs {
+    // Registers a job and makes its task abortable; `build` receives the new job id
+    pub fn spawn<T: Send + 'static>(
+        &mut self,
+        label: impl Into<String>,
+        cancellable: bool,
+        build: impl FnOnce(JobId) -> Task<T>,
+    ) -> (JobId, Task<T>) {
+        self.next_id += 1;
+        let id = self.next_id;
+        let (task, handle) = build(id).abortable();
+        self.running.push(Job {
+            id,
+            label: label.into(),
+            progress: 0.0,
+            cancellable,
+            started: Instant::now(),
+            handle,
+        });
+        (id, task)
+    }
+
+    pub fn set_progress(&mut self, id: JobId, progress: f32) {
+        if let Some(job) = self.running.iter_mut().find(|j| j.id == id) {
+            job.progress = progress.clamp(0.0, 1.0);
+        }
+    }
+
+    // Returns false when the job was unknown (already finished or cancelled)
+    pub fn finish(&mut self, id: JobId) -> bool {
+        let before = self.running.len();
+        self.running.retain(|j| j.id != id);
+        self.running.len() != before
+    }
+
+    pub fn cancel(&mut self, id: JobId) -> bool {
+        match self.running.iter().position(|j| j.id == id && j.cancellable) {
+            Some(index) => {
+                self.running.remove(index).handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.running.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.running.len()
+    }
+
+    // Average progress of all running jobs, None when idle
+    pub fn overall_progress(&self) -> Option<f32> {
+        if self.running.is_empty() {
+            return None;
+        }
+        let total: f32 = self.running.iter().map(|j| j.progress).sum();
+        Some(total / self.running.len() as f32)
+    }
+}
+
+// --- Worker threads ---
+
+#[derive(Debug, Clone)]
+pub enum JobEvent<T> {
+    Progress(f32),
+    Done(T),
+}
+
+// Runs `work` on its own thread. The callback given to `work` reports progress
+// and returns false once the job was cancelled, so the worker can stop early.
+pub fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce(&dyn Fn(f32) -> bool) -> T + Send + 'static,
+) -> impl Stream<Item = JobEvent<T>> {
+    let (sender, receiver) = mpsc::unbounded();
+    std::thread::spawn(move || {
+        let last_percent = std::cell::Cell::new(u32::MAX);
+        let report = |progress: f32| {
+            // Only forward whole-percent changes to avoid flooding the UI
+            let percent = (progress.clamp(0.0, 1.0) * 100.0) as u32;
+            if percent == last_percent.get() {
+                return !sender.is_closed();
+            }
+            last_percent.set(percent);
+            sender.unbounded_send(JobEvent::Progress(progress)).is_ok()
+        };
+        let output = work(&report);
+        let _ = sender.unbounded_send(JobEvent::Done(output));
+    });
+    receiver
+}
+
+// Runs `work` on its own thread; resolves to None if the thread panicked
+pub fn spawn_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> impl std::future::Future<Output = Option<T>> {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+    async move { receiver.await.ok() }
+}
+
+// Reads a whole file chunk by chunk. Ok(None) means the read was cancelled.
+pub fn read_with_progress(
+    path: &Path,
+    report: &dyn Fn(f32) -> bool,
+) -> std::io::Result<Option<Vec<u8>>> {
+    let mut file = std::fs::File::open(path)?;
+    let total = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut bytes = Vec::with_capacity(total as usize);
+    let mut chunk = vec![0; IO_CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..read]);
+        if !report(fraction(bytes.len(), total as usize)) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(bytes))
+}
+
+pub fn write_with_progress(
+    path: &Path,
+    bytes: &[u8],
+    report: &dyn Fn(f32) -> bool,
+) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    let mut written = 0;
+    for chunk in bytes.chunks(IO_CHUNK_SIZE) {
+        file.write_all(chunk)?;
+        written += chunk.len();
+        report(fraction(written, bytes.len()));
+    }
+    file.sync_all()
+}
+
+fn fraction(done: usize, total: usize) -> f32 {
+    if total == 0 {
+        1.0
+    } else {
+        (done as f32 / total as f32).min(1.0)
+    }
+}
+
+// --- Taskbar progress ---
+
+// Mirrors the overall job progress on the window's taskbar button (Windows only)
+pub fn taskbar_progress<T: Send + 'static>(progress: Option<f32>) -> Task<T> {
+    iced::window::latest().then(move |id| match id {
+        Some(id) => iced::window::run(id, move |window| {
+            if let Some(hwnd) = native_window(window) {
+                taskbar::set_progress(hwnd, progress);
+            }
+        })
+        .discard(),
+        None => Task::none(),
+    })
+}
+
+pub fn native_window(window: &dyn iced::window::Window) -> Option<isize> {
+    use iced::window::raw_window_handle::RawWindowHandle;
+    match window.window_handle().ok()?.as_raw() {
+        RawWindowHandle::Win32(handle) => Some(handle.hwnd.get()),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod taskbar {
+    use std::ffi::c_void;
+    use std::ptr::null_mut;
+
+    #[repr(C)]
+    struct Guid(u32, u16, u16, [u8; 8]);
+
+    const CLSID_TASKBAR_LIST: Guid = Guid(
+        0x56fd_f344,
+        0xfd6d,
+        0x11d0,
+        [0x95, 0x8a, 0x00, 0x60, 0x97, 0xc9, 0xa0, 0x90],
+    );
+    const IID_ITASKBAR_LIST3: Guid = Guid(
+        0xea1a_fb91,
+        0x9e28,
+        0x4b86,
+        [0x90, 0xe9, 0x9e, 0x9f, 0x8a, 0x5e, 0xef, 0xaf],
+    );
+    const CLSCTX_INPROC_SERVER: u32 = 0x1;
+    const COINIT_APARTMENTTHREADED: u32 = 0x2;
+    const TBPF_NOPROGRESS: u32 = 0x0;
+    const TBPF_NORMAL: u32 = 0x2;
+
+    type Unused = usize;
+
+    // ITaskbarList3 vtable, in declaration order
+    #[repr(C)]
+    struct TaskbarListVtbl {
+        _query_interface: Unused,
+        _add_ref: Unused,
+        release: unsafe extern "system" fn(*mut c_void) -> u32,
+        hr_init: unsafe extern "system" fn(*mut c_void) -> i32,
+        _add_tab: Unused,
+        _delete_tab: Unused,
+        _activate_tab: Unused,
+        _set_active_alt: Unused,
+        _mark_fullscreen_window: Unused,
+        set_progress_value: unsafe extern "system" fn(*mut c_void, isize, u64, u64) -> i32,
+        set_progress_state: unsafe extern "system" fn(*mut c_void, isize, u32) -> i32,
+    }
+
+    #[link(name = "ole32")]
+    extern "system" {
+        fn CoInitializeEx(reserved: *mut c_void, co_init: u32) -> i32;
+        fn CoCreateInstance(
+            clsid: *const Guid,
+            outer: *mut c_void,
+            context: u32,
+            iid: *const Guid,
+            out: *mut *mut c_void,
+        ) -> i32;
+    }
+
+    pub fn set_progress(hwnd: isize, progress: Option<f32>) {
+        unsafe {
+            // Already initialized by the windowing library; the call is then a no-op
+            CoInitializeEx(null_mut(), COINIT_APARTMENTTHREADED);
+            let mut list: *mut c_void = null_mut();
+            let hr = CoCreateInstance(
+                &CLSID_TASKBAR_LIST,
+                null_mut(),
+                CLSCTX_INPROC_SERVER,
+                &IID_ITASKBAR_LIST3,
+                &mut list,
+            );
+            if hr < 0 || list.is_null() {
+                return;
+            }
+            let vtbl = &**(list as *mut *const TaskbarListVtbl);
+            if (vtbl.hr_init)(list) >= 0 {
+                match progress {
+                    Some(p) => {
+                        (vtbl.set_progress_state)(list, hwnd, TBPF_NORMAL);
+                        (vtbl.set_progress_value)(list, hwnd, (p * 1000.0) as u64, 1000);
+                    }
+                    None => {
+                        (vtbl.set_progress_state)(list, hwnd, TBPF_NOPROGRESS);
+                    }
+                }
+            }
+            (vtbl.release)(list);
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod taskbar {
+    pub fn set_progress(_hwnd: isize, _progress: Option<f32>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_running(jobs: &Jobs, id: JobId) -> bool {
+        jobs.iter().any(|j| j.id == id)
+    }
+
+    fn spawn_idle(jobs: &mut Jobs, label: &str, cancellable: bool) -> JobId {
+        jobs.spawn(label, cancellable, |_| Task::<()>::none()).0
+    }
+
+    #[test]
+    fn spawn_assigns_increasing_ids() {
+        let mut jobs = Jobs::default();
+        let a = spawn_idle(&mut jobs, "a", true);
+        let b = spawn_idle(&mut jobs, "b", true);
+        assert!(b > a);
+        assert!(is_running(&jobs, a) && is_running(&jobs, b));
+    }
+
+    #[test]
+    fn overall_progress_is_average() {
+        let mut jobs = Jobs::default();
+        assert_eq!(jobs.overall_progress(), None);
+        let a = spawn_idle(&mut jobs, "a", true);
+        let b = spawn_idle(&mut jobs, "b", true);
+        jobs.set_progress(a, 1.0);
+        jobs.set_progress(b, 0.5);
+        assert_eq!(jobs.overall_progress(), Some(0.75));
+    }
+
+    #[test]
+    fn progress_is_clamped() {
+        let mut jobs = Jobs::default();
+        let a = spawn_idle(&mut jobs, "a", true);
+        jobs.set_progress(a, 3.0);
+        assert_eq!(jobs.overall_progress(), Some(1.0));
+    }
+
+    #[test]
+    fn finish_twice_reports_stale() {
+        let mut jobs = Jobs::default();
+        let a = spawn_idle(&mut jobs, "a", true);
+        assert!(jobs.finish(a));
+        assert!(!jobs.finish(a));
+        assert_eq!(jobs.len(), 0);
+    }
+
+    #[test]
+    fn cancel_respects_cancellable_flag() {
+        let mut jobs = Jobs::default();
+        let save = spawn_idle(&mut jobs, "save", false);
+        let load = spawn_idle(&mut jobs, "load", true);
+        assert!(!jobs.cancel(save));
+        assert!(jobs.cancel(load));
+        assert!(is_running(&jobs, save));
+        assert!(!is_running(&jobs, load));
+    }
+
+    #[test]
+    fn read_and_write_with_progress_round_trip() {
+        let path = std::env::temp_dir().join(format!("notepad_jobs_{}.bin", std::process::id()));
+        let data: Vec<u8> = (0..IO_CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect();
+        let reports = std::cell::RefCell::new(Vec::new());
+        let report = |p: f32| {
+            reports.borrow_mut().push(p);
+            true
+        };
+        write_with_progress(&path, &data, &report).unwrap();
+        assert_eq!(reports.borrow().last(), Some(&1.0));
+        reports.borrow_mut().clear();
+        let read = read_with_progress(&path, &report).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(read.as_deref(), Some(&data[..]));
+        assert_eq!(reports.borrow().len(), 3);
+    }
+
+    #[test]
+    fn read_stops_when_cancelled() {
+        let path = std::env::temp_dir().join(format!("notepad_jobs_c_{}.bin", std::process::id()));
+        std::fs::write(&path, vec![0u8; IO_CHUNK_SIZE * 2]).unwrap();
+        let read = read_with_progress(&path, &|_| false).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(read.is_none());
+    }
+
+    #[test]
+    fn fraction_handles_empty_total() {
+        assert_eq!(fraction(0, 0), 1.0);
+        assert_eq!(fraction(5, 10), 0.5);
+    }
+}
@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::markdown;
+
+// --- Document language ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    PlainText,
+    Markdown,
+    Log,
+    Json,
+    Toml,
+    Yaml,
+    Csv,
+    Rust,
+    Python,
+    Shell,
+    Xml,
+}
+
+impl Language {
+    pub const ALL: [Language; 11] = [
+        Language::PlainText,
+        Language::Markdown,
+        Language::Log,
+        Language::Json,
+        Language::Toml,
+        Language::Yaml,
+        Language::Csv,
+        Language::Rust,
+        Language::Python,
+        Language::Shell,
+        Language::Xml,
+    ];
+
+    // Text written for people rather than code or data
+    pub fn is_prose(self) -> bool {
+        matches!(self, Language::PlainText | Language::Markdown)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::PlainText => "Texte brut",
+            Self::Markdown => "Markdown",
+            Self::Log => "Journal",
+            Self::Json => "JSON",
+            Self::Toml => "TOML",
+            Self::Yaml => "YAML",
+            Self::Csv => "CSV",
+            Self::Rust => "Rust",
+            Self::Python => "Python",
+            Self::Shell => "Shell",
+            Self::Xml => "XML",
+        }
+    }
+
+    // The first one is appended to file names saved without extension
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::PlainText => &["txt"],
+            Self::Markdown => &["md", "markdown"],
+            Self::Log => &["log"],
+            Self::Json => &["json"],
+            Self::Toml => &["toml"],
+            Self::Yaml => &["yaml", "yml"],
+            Self::Csv => &["csv"],
+            Self::Rust => &["rs"],
+            Self::Python => &["py", "pyw"],
+            Self::Shell => &["sh", "bash", "zsh"],
+            Self::Xml => &["xml", "xsd", "svg"],
+        }
+    }
+
+    // Name of the Save As filter
+    pub fn filter_name(self) -> &'static str {
+        match self {
+            Self::PlainText => "Fichiers texte",
+            Self::Markdown => "Markdown",
+            Self::Log => "Journaux",
+            Self::Json => "JSON",
+            Self::Toml => "TOML",
+            Self::Yaml => "YAML",
+            Self::Csv => "CSV",
+            Self::Rust => "Rust",
+            Self::Python => "Python",
+            Self::Shell => "Scripts shell",
+            Self::Xml => "XML",
+        }
+    }
+
+    pub fn from_path(path: Option<&Path>) -> Self {
+        if markdown::is_markdown(path) {
+            return Self::Markdown;
+        }
+        let Some(ext) = path.and_then(Path::extension).and_then(|e| e.to_str()) else {
+            return Self::PlainText;
+        };
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.extensions().iter().any(|e| ext.eq_ignore_ascii_case(e)))
+            .unwrap_or(Self::PlainText)
+    }
+}
+
+// --- Content sniffing for files without extension ---
+
+// Lines looked at to recognize the content
+const SNIFF_LINES: usize = 30;
+
+// Language guessed from the first lines of a file without extension
+// ("deploy", "README", ".bashrc"); None when the file has one or nothing matches
+pub fn sniff(path: &Path, text: &str) -> Option<Language> {
+    if path.extension().is_some() {
+        return None;
+    }
+    let text = text.trim_start_matches('\u{feff}');
+    let mut lines = text.lines().take(SNIFF_LINES).skip_while(|l| l.trim().is_empty());
+    let first = lines.next()?.trim();
+    if let Some(command) = first.strip_prefix("#!") {
+        return interpreter(command);
+    }
+    if first.starts_with("<?xml") {
+        return Some(Language::Xml);
+    }
+    if looks_like_json(text.trim_start()) {
+        return Some(Language::Json);
+    }
+    looks_like_markdown(text).then_some(Language::Markdown)
+}
+
+// "#!/usr/bin/env -S python3 -u" -> python; version suffixes are ignored
+fn interpreter(command: &str) -> Option<Language> {
+    let mut words = command.split_whitespace();
+    let mut program = words.next()?.rsplit(['/', '\\']).next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-'))?;
+    }
+    match program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "python" | "pypy" => Some(Language::Python),
+        "sh" | "bash" | "dash" | "zsh" | "ksh" | "ash" => Some(Language::Shell),
+        _ => None,
+    }
+}
+
+// An object or array opening: "[section]" of an INI file does not qualify
+fn looks_like_json(text: &str) -> bool {
+    let mut chars = text.chars();
+    let open = chars.next();
+    let next = chars.find(|c| !c.is_whitespace());
+    match (open, next) {
+        (Some('{'), Some(next)) => matches!(next, '"' | '}'),
+        (Some('['), Some(next)) => matches!(next, '{' | '[' | '"' | ']' | '-' | '0'..='9'),
+        _ => false,
+    }
+}
+
+// A heading alone could be a "# comment": it takes a setext heading, or an ATX
+// heading along with a list, a link or a code fence
+fn looks_like_markdown(text: &str) -> bool {
+    let lines: Vec<&str> = text.lines().take(SNIFF_LINES).collect();
+    let setext = lines.windows(2).any(|pair| {
+        let underline = pair[1].trim_end();
+        !pair[0].trim().is_empty()
+            && underline.len() >= 3
+            && (underline.bytes().all(|b| b == b'=') || underline.bytes().all(|b| b == b'-'))
+    });
+    let heading = lines.iter().any(|l| {
+        let hashes = l.bytes().take_while(|&b| b == b'#').count();
+        (1..=6).contains(&hashes) && l[hashes..].starts_with(' ')
+    });
+    let other = lines.iter().any(|l| {
+        l.starts_with("```") || l.starts_with("- ") || l.starts_with("* ") || l.contains("](")
+    });
+    setext || (heading && other)
+}
+
+// Save As filters, the document's language first so the dialog starts on it
+pub fn save_filters(current: Language) -> Vec<Language> {
+    let mut filters = vec![current];
+    filters.extend(Language::ALL.into_iter().filter(|&lang| lang != current));
+    filters
+}
+
+// "notes" -> "notes.md" for a Markdown document; names with an extension and
+// dot files such as ".env" are kept as typed
+pub fn with_default_extension(path: PathBuf, language: Language) -> PathBuf {
+    let dot_file = path
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+    if path.extension().is_some() || dot_file {
+        return path;
+    }
+    path.with_extension(language.extensions()[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_follows_the_extension() {
+        assert_eq!(Language::from_path(Some(Path::new("a.MD"))), Language::Markdown);
+        assert_eq!(Language::from_path(Some(Path::new("main.rs"))), Language::Rust);
+        assert_eq!(Language::from_path(Some(Path::new("script.pyw"))), Language::Python);
+        assert_eq!(Language::from_path(Some(Path::new("Makefile"))), Language::PlainText);
+        assert_eq!(Language::from_path(None), Language::PlainText);
+    }
+
+    #[test]
+    fn content_decides_without_extension() {
+        let sniff = |name: &str, text: &str| sniff(Path::new(name), text);
+        assert_eq!(sniff("deploy", "#!/usr/bin/env -S python3 -u\nprint(1)"), Some(Language::Python));
+        assert_eq!(sniff("build", "\n#!/bin/bash\nset -e"), Some(Language::Shell));
+        assert_eq!(sniff("run", "#!/usr/bin/perl"), None);
+        assert_eq!(sniff("feed", "\u{feff}<?xml version=\"1.0\"?>\n<rss/>"), Some(Language::Xml));
+        assert_eq!(sniff("data", "  {\n  \"a\": 1\n}"), Some(Language::Json));
+        assert_eq!(sniff("list", "[1, 2]"), Some(Language::Json));
+        assert_eq!(sniff("config", "[section]\nkey=1"), None);
+        assert_eq!(sniff("README", "# Projet\n\n- installer\n- lancer"), Some(Language::Markdown));
+        assert_eq!(sniff("NOTES", "Titre\n=====\ntexte"), Some(Language::Markdown));
+        assert_eq!(sniff(".bashrc", "# réglages\nexport A=1"), None);
+        assert_eq!(sniff("script.py", "#!/bin/sh"), None);
+    }
+
+    #[test]
+    fn missing_extension_is_appended() {
+        let md = with_default_extension(PathBuf::from("/notes/idées"), Language::Markdown);
+        assert_eq!(md, PathBuf::from("/notes/idées.md"));
+        let txt = with_default_extension(PathBuf::from("/notes/liste"), Language::PlainText);
+        assert_eq!(txt, PathBuf::from("/notes/liste.txt"));
+        let kept = with_default_extension(PathBuf::from("/notes/data.csv"), Language::Json);
+        assert_eq!(kept, PathBuf::from("/notes/data.csv"));
+        let env = with_default_extension(PathBuf::from("/app/.env"), Language::PlainText);
+        assert_eq!(env, PathBuf::from("/app/.env"));
+    }
+
+    #[test]
+    fn current_language_is_the_first_filter() {
+        let filters = save_filters(Language::Json);
+        assert_eq!(filters[0], Language::Json);
+        assert_eq!(filters.len(), Language::ALL.len());
+    }
+}
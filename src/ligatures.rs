@@ -0,0 +1,36 @@
+// --- Programming ligatures ---
+
+// The editor text is shaped with the font's default OpenType features, which
+// iced gives no way to change, so ligatures are turned off by drawing with
+// the sibling family built without them
+const WITHOUT_LIGATURES: &[(&str, &str)] = &[
+    ("Cascadia Code", "Cascadia Mono"),
+    ("Fira Code", "Fira Mono"),
+    ("JetBrains Mono", "JetBrains Mono NL"),
+];
+
+// Whether the setting changes anything for this family
+pub fn has_ligatures(family: &str) -> bool {
+    WITHOUT_LIGATURES.iter().any(|(with, _)| *with == family)
+}
+
+// Family drawing `family` without its ligatures, None when it has none
+pub fn without(family: &str) -> Option<&'static str> {
+    WITHOUT_LIGATURES
+        .iter()
+        .find(|(with, _)| *with == family)
+        .map(|(_, without)| *without)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ligature_fonts_have_a_plain_sibling() {
+        assert!(has_ligatures("Cascadia Code"));
+        assert_eq!(without("Cascadia Code"), Some("Cascadia Mono"));
+        assert!(!has_ligatures("Consolas"));
+        assert_eq!(without("Consolas"), None);
+    }
+}
@@ -0,0 +1,127 @@
+use std::io::Read;
+
+use crate::http::{self, ProxySettings};
+
+// --- Pasted URLs as Markdown links ---
+
+// Start of the page read for its <title>
+const TITLE_BYTES: u64 = 64 * 1024;
+
+// The web address `text` is made of, surrounding blanks aside
+pub fn url(text: &str) -> Option<&str> {
+    let text = text.trim();
+    let rest = text.strip_prefix("https://").or_else(|| text.strip_prefix("http://"))?;
+    (!rest.is_empty() && !text.chars().any(char::is_whitespace)).then_some(text)
+}
+
+// [label](url), the label's brackets escaped and the URL in angle brackets
+// when a parenthesis would end it early
+pub fn markdown_link(label: &str, url: &str) -> String {
+    let label = label.replace('[', "\\[").replace(']', "\\]");
+    if url.contains(['(', ')']) {
+        format!("[{label}](<{url}>)")
+    } else {
+        format!("[{label}]({url})")
+    }
+}
+
+// Blocking: run it off the UI thread
+pub fn fetch_title(proxy: &ProxySettings, url: &str) -> Result<String, String> {
+    let mut response = http::agent(proxy)?
+        .get(url)
+        .header("User-Agent", http::USER_AGENT)
+        .header("Accept", "text/html")
+        .call()
+        .map_err(|e| e.to_string())?;
+    let mut html = Vec::new();
+    response
+        .body_mut()
+        .as_reader()
+        .take(TITLE_BYTES)
+        .read_to_end(&mut html)
+        .map_err(|e| e.to_string())?;
+    page_title(&String::from_utf8_lossy(&html)).ok_or_else(|| "page sans titre".to_string())
+}
+
+// Text of the <title> element, entities decoded and blanks collapsed
+pub fn page_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let tag = lower.find("<title")?;
+    let start = tag + lower[tag..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = decode_entities(&html[start..end]);
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => {
+                    let code = entity.strip_prefix('#')?;
+                    let value = match code.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => code.parse().ok(),
+                    };
+                    value.and_then(char::from_u32)
+                }
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_web_addresses_are_urls() {
+        assert_eq!(url(" https://example.org/a?b=c \n"), Some("https://example.org/a?b=c"));
+        assert_eq!(url("http://localhost:8080"), Some("http://localhost:8080"));
+        assert_eq!(url("https://"), None);
+        assert_eq!(url("voir https://example.org"), None);
+        assert_eq!(url("ftp://example.org"), None);
+    }
+
+    #[test]
+    fn links_escape_brackets_and_parentheses() {
+        assert_eq!(markdown_link("la doc", "https://docs.rs"), "[la doc](https://docs.rs)");
+        assert_eq!(
+            markdown_link("[1] Rust", "https://fr.wikipedia.org/wiki/Rust_(langage)"),
+            "[\\[1\\] Rust](<https://fr.wikipedia.org/wiki/Rust_(langage)>)"
+        );
+    }
+
+    #[test]
+    fn title_is_read_from_the_page() {
+        let html = "<html><HEAD><Title lang=\"fr\">\n  Rust &amp; Cie &#8212; l&#x27;accueil\n</TITLE></head>";
+        assert_eq!(page_title(html).as_deref(), Some("Rust & Cie — l'accueil"));
+        assert_eq!(page_title("<title>  </title>"), None);
+        assert_eq!(page_title("<p>AT&T &unknown; 5 & 6</p><title>AT&T &bogus;</title>"), Some("AT&T &bogus;".to_string()));
+    }
+}
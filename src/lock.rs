@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// --- Advisory lock files, so two instances don't edit the same document ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockOwner {
+    pub user: String,
+    pub host: String,
+    pub pid: u32,
+}
+
+impl LockOwner {
+    pub fn current() -> Self {
+        let env = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|k| std::env::var(k).ok().filter(|v| !v.is_empty()))
+                .unwrap_or_else(|| "?".to_string())
+        };
+        Self {
+            user: env(&["USERNAME", "USER"]),
+            host: env(&["COMPUTERNAME", "HOSTNAME"]),
+            pid: std::process::id(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}@{}", self.user, self.host)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockState {
+    Held,
+    // Another instance holds the lock: the tab is read-only
+    ReadOnly(LockOwner),
+    // The lock file could not be written (read-only folder…)
+    Unavailable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocLock {
+    pub path: PathBuf,
+    pub state: LockState,
+}
+
+// `.~lock.name#` next to the document, as office suites do
+pub fn lock_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".~lock.{name}#"))
+}
+
+fn read_owner(lock: &Path) -> Option<LockOwner> {
+    std::fs::read_to_string(lock)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+fn owner_json() -> String {
+    serde_json::to_string(&LockOwner::current()).unwrap_or_default()
+}
+
+pub fn acquire(path: &Path) -> io::Result<LockState> {
+    let lock = lock_path(path);
+    match OpenOptions::new().write(true).create_new(true).open(&lock) {
+        Ok(mut file) => {
+            file.write_all(owner_json().as_bytes())?;
+            Ok(LockState::Held)
+        }
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => match read_owner(&lock) {
+            Some(owner) if owner == LockOwner::current() => Ok(LockState::Held),
+            Some(owner) => Ok(LockState::ReadOnly(owner)),
+            // Unreadable lock file: take it over
+            None => steal(path).map(|_| LockState::Held),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+pub fn steal(path: &Path) -> io::Result<()> {
+    std::fs::write(lock_path(path), owner_json())
+}
+
+// Only removes the lock file if this instance still owns it
+pub fn release(path: &Path) {
+    let lock = lock_path(path);
+    if read_owner(&lock) == Some(LockOwner::current()) {
+        if let Err(e) = std::fs::remove_file(&lock) {
+            tracing::warn!("suppression du verrou {} impossible : {e}", lock.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_doc(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("notepad_lock_{name}_{}.txt", std::process::id()))
+    }
+
+    fn other_owner() -> LockOwner {
+        LockOwner {
+            user: "quelqu'un".to_string(),
+            host: "ailleurs".to_string(),
+            pid: 1,
+        }
+    }
+
+    #[test]
+    fn lock_path_is_hidden_sibling() {
+        let lock = lock_path(Path::new("/docs/notes.txt"));
+        assert_eq!(lock, PathBuf::from("/docs/.~lock.notes.txt#"));
+    }
+
+    #[test]
+    fn acquire_then_release() {
+        let doc = temp_doc("own");
+        assert_eq!(acquire(&doc).unwrap(), LockState::Held);
+        assert!(lock_path(&doc).exists());
+        // Same instance: acquiring again succeeds
+        assert_eq!(acquire(&doc).unwrap(), LockState::Held);
+        release(&doc);
+        assert!(!lock_path(&doc).exists());
+    }
+
+    #[test]
+    fn foreign_lock_is_read_only_until_stolen() {
+        let doc = temp_doc("foreign");
+        let lock = lock_path(&doc);
+        std::fs::write(&lock, serde_json::to_string(&other_owner()).unwrap()).unwrap();
+        assert_eq!(acquire(&doc).unwrap(), LockState::ReadOnly(other_owner()));
+        // Releasing someone else's lock leaves it alone
+        release(&doc);
+        assert!(lock.exists());
+        steal(&doc).unwrap();
+        assert_eq!(acquire(&doc).unwrap(), LockState::Held);
+        release(&doc);
+        assert!(!lock.exists());
+    }
+
+    #[test]
+    fn unreadable_lock_is_taken_over() {
+        let doc = temp_doc("garbage");
+        std::fs::write(lock_path(&doc), "not json").unwrap();
+        assert_eq!(acquire(&doc).unwrap(), LockState::Held);
+        release(&doc);
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+use tracing::Level;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::prelude::*;
+
+use crate::preferences;
+
+// Daily log files kept in the logs folder
+const MAX_LOG_FILES: usize = 7;
+
+const LOG_PREFIX: &str = "notepad";
+const LOG_SUFFIX: &str = "log";
+
+pub fn dir() -> PathBuf {
+    preferences::dir().join("logs")
+}
+
+// `--verbose` on the command line logs debug events too
+pub fn verbose_requested(args: impl IntoIterator<Item = String>) -> bool {
+    args.into_iter().any(|a| a == "--verbose" || a == "-v")
+}
+
+// Starts writing to the rotating log files; the app runs without logs if
+// the folder cannot be created
+pub fn init(verbose: bool) {
+    let appender = match RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_PREFIX)
+        .filename_suffix(LOG_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir())
+    {
+        Ok(appender) => appender,
+        Err(_) => return,
+    };
+    let level = if verbose { Level::DEBUG } else { Level::INFO };
+    // Only warnings from the GUI toolkit and its dependencies
+    let filter = Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), level)
+        .with_default(Level::WARN);
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(appender)
+        .with_ansi(false);
+    let _ = tracing_subscriber::registry()
+        .with(layer.with_filter(filter))
+        .try_init();
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), verbose, "démarrage");
+}
+
+// Most recently written log file
+pub fn latest_log() -> Option<PathBuf> {
+    latest_in(&dir())
+}
+
+fn latest_in(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            name.starts_with(LOG_PREFIX) && name.ends_with(LOG_SUFFIX)
+        })
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .max()
+        .map(|(_, path)| path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbose_flag_is_recognized() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(verbose_requested(args(&["notepad", "--verbose"])));
+        assert!(verbose_requested(args(&["notepad", "-v"])));
+        assert!(!verbose_requested(args(&["notepad", "notes.txt"])));
+    }
+
+    #[test]
+    fn latest_log_is_the_newest_file() {
+        let dir = std::env::temp_dir().join(format!("notepad-logs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notepad.2026-01-01.log"), "ancien").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(dir.join("notepad.2026-01-02.log"), "récent").unwrap();
+        std::fs::write(dir.join("autre.txt"), "").unwrap();
+        assert_eq!(latest_in(&dir), Some(dir.join("notepad.2026-01-02.log")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
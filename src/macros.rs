@@ -0,0 +1,74 @@
+use iced::widget::text_editor;
+
+use crate::app::{EditMsg, Message, SearchMsg};
+use crate::shortcuts::Action;
+
+// --- Keyboard macros ---
+
+// Highest repeat count accepted by the replay prompt
+pub const MAX_REPEAT: usize = 9999;
+
+// Messages a macro keeps: typing, caret moves and selections, and the edit
+// and search commands working from the caret. Clicks are left out since they
+// land on screen positions, and so is anything opening a dialog.
+pub fn is_recorded(message: &Message) -> bool {
+    match message {
+        Message::EditorAction(action) => matches!(
+            action,
+            text_editor::Action::Edit(_)
+                | text_editor::Action::Move(_)
+                | text_editor::Action::Select(_)
+                | text_editor::Action::SelectWord
+                | text_editor::Action::SelectLine
+                | text_editor::Action::SelectAll
+        ),
+        Message::Edit(msg) => matches!(
+            msg,
+            EditMsg::Copy
+                | EditMsg::Cut
+                | EditMsg::Paste
+                | EditMsg::PastePlain
+                | EditMsg::SelectAll
+                | EditMsg::Undo
+                | EditMsg::Redo
+                | EditMsg::InsertDateTime
+                | EditMsg::InsertChar(_)
+                | EditMsg::InsertTab
+                | EditMsg::MoveParagraphUp
+                | EditMsg::MoveParagraphDown
+                | EditMsg::TrimTrailingWhitespace
+                | EditMsg::TabsToSpaces
+                | EditMsg::SpacesToTabs
+                | EditMsg::JoinLines
+                | EditMsg::ToggleComment
+        ),
+        Message::Search(msg) => matches!(msg, SearchMsg::FindNext | SearchMsg::FindPrevious | SearchMsg::ReplaceOne),
+        _ => false,
+    }
+}
+
+// Shortcuts a macro keeps, the ones running a recorded command; they are
+// recorded where they run, since the global ones never go through a message
+pub fn is_recorded_shortcut(action: Action) -> bool {
+    matches!(
+        action,
+        Action::Undo
+            | Action::Redo
+            | Action::Cut
+            | Action::Copy
+            | Action::Paste
+            | Action::PastePlain
+            | Action::SelectAll
+            | Action::InsertDateTime
+            | Action::JoinLines
+            | Action::ToggleComment
+            | Action::MoveParagraphUp
+            | Action::MoveParagraphDown
+            | Action::FindNext
+            | Action::FindPrevious
+            | Action::PreviousParagraph
+            | Action::NextParagraph
+            | Action::PreviousHeading
+            | Action::NextHeading
+    )
+}
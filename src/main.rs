@@ -1,12 +1,68 @@
 #![windows_subsystem = "windows"]
 
+mod abbreviations;
 mod app;
+mod archive;
+mod buffer;
+mod changes;
+mod charmap;
+mod color;
+mod comments;
+mod completion;
+mod crash;
+mod datetime;
+mod dictionary;
+mod dpi;
+mod editorconfig;
+mod export;
+mod filter;
+mod folder;
+#[cfg(test)]
+mod harness;
+mod hibernate;
+mod http;
+mod ink;
+mod jobs;
+mod language;
+mod ligatures;
+mod links;
+mod lock;
+mod macros;
+mod logging;
+mod markdown;
+mod marks;
+mod merge;
+mod metadata;
+mod navigation;
+mod notes;
+mod opacity;
+mod paragraphs;
+mod paste;
+mod paths;
 mod preferences;
+mod print;
+mod qr;
+mod reflow;
+mod scripts;
+mod search_index;
+mod secrets;
+mod shortcuts;
+mod snippets;
+mod spelling;
+mod syntax;
+mod tabs;
+mod templates;
+mod terminal;
+mod toast;
 mod ui;
 mod update;
+mod updates;
+mod watch;
+mod wiki;
 
 use app::Notepad;
 use preferences::UserPreferences;
+use std::path::PathBuf;
 
 pub const DEFAULT_WINDOW_WIDTH: f32 = 800.0;
 pub const DEFAULT_WINDOW_HEIGHT: f32 = 600.0;
@@ -26,13 +82,30 @@ pub const FONT_FAMILIES: &[&str] = &[
     "Times New Roman",
 ];
 
+// Fixed-width families of FONT_FAMILIES: the gutter only follows word wrap
+// with these, where rows can be counted in character cells
+pub const MONOSPACE_FAMILIES: &[&str] = &["Consolas", "Courier New", "Cascadia Code", "Lucida Console"];
+
 fn main() -> iced::Result {
+    let launched = std::time::Instant::now();
+    logging::init(logging::verbose_requested(std::env::args()));
+    crash::install();
     let prefs = UserPreferences::load();
-    iced::application(Notepad::new, Notepad::update, Notepad::view)
+    let size = iced::Size::new(prefs.window_width, prefs.window_height);
+    // Arguments other than flags are files; "~" and variables quoted away
+    // from the shell are expanded here
+    let files: Vec<PathBuf> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .map(|arg| paths::expand(&arg))
+        .collect();
+    let boot = move || Notepad::new(prefs.clone(), launched, files.clone());
+    iced::application(boot, Notepad::update, Notepad::view)
         .title(Notepad::title)
         .theme(Notepad::theme)
+        .scale_factor(Notepad::scale_factor)
         .subscription(Notepad::subscription)
-        .window_size(iced::Size::new(prefs.window_width, prefs.window_height))
+        .window_size(size)
         .exit_on_close_request(false)
         .run()
 }
@@ -0,0 +1,521 @@
+use std::ops::Range;
+use std::path::Path;
+
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd"];
+
+pub fn is_markdown(path: Option<&Path>) -> bool {
+    path.and_then(Path::extension)
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| MARKDOWN_EXTENSIONS.iter().any(|m| e.eq_ignore_ascii_case(m)))
+}
+
+// --- Outline ---
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub line: usize,
+    pub level: u8,
+    pub title: String,
+}
+
+// ATX heading ("## Title ##"): up to three spaces, 1 to 6 '#', then a space or the end
+fn parse_heading(line: &str) -> Option<(u8, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let hashes = trimmed.bytes().take_while(|&b| b == b'#').count();
+    if !(1..=6).contains(&hashes) {
+        return None;
+    }
+    let rest = &trimmed[hashes..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let title = rest.trim();
+    // Optional closing sequence, only when preceded by a space
+    let without_closing = title.trim_end_matches('#');
+    let title = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        without_closing.trim_end()
+    } else {
+        title
+    };
+    Some((hashes as u8, title))
+}
+
+// Headings of the document, ignoring lines inside fenced code blocks
+pub fn headings<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<Heading> {
+    let mut result = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    for (line, text) in lines.enumerate() {
+        let trimmed = text.trim_start();
+        let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        if let Some(c) = marker {
+            let run = trimmed.chars().take_while(|&x| x == c).count();
+            if run >= 3 {
+                match fence {
+                    None => fence = Some((c, run)),
+                    Some((open, len)) if open == c && run >= len => fence = None,
+                    Some(_) => {}
+                }
+                continue;
+            }
+        }
+        if fence.is_some() {
+            continue;
+        }
+        if let Some((level, title)) = parse_heading(text) {
+            result.push(Heading {
+                line,
+                level,
+                title: title.to_string(),
+            });
+        }
+    }
+    result
+}
+
+// Indices of the headings enclosing `line`, outermost first
+pub fn heading_path(headings: &[Heading], line: usize) -> Vec<usize> {
+    let Some(current) = headings.iter().rposition(|h| h.line <= line) else {
+        return Vec::new();
+    };
+    let mut path = vec![current];
+    let mut level = headings[current].level;
+    for i in (0..current).rev() {
+        if headings[i].level < level {
+            level = headings[i].level;
+            path.push(i);
+        }
+    }
+    path.reverse();
+    path
+}
+
+pub fn next_heading(headings: &[Heading], line: usize) -> Option<&Heading> {
+    headings.iter().find(|h| h.line > line)
+}
+
+pub fn previous_heading(headings: &[Heading], line: usize) -> Option<&Heading> {
+    headings.iter().rev().find(|h| h.line < line)
+}
+
+// --- Tables ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Default,
+    Left,
+    Center,
+    Right,
+}
+
+// Minimum width of a column, so the separator keeps three dashes
+const MIN_COLUMN_WIDTH: usize = 3;
+
+pub fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+// Lines of the table containing `line`, given a test telling table rows apart
+pub fn table_range(line: usize, line_count: usize, is_row: impl Fn(usize) -> bool) -> Option<Range<usize>> {
+    if line >= line_count || !is_row(line) {
+        return None;
+    }
+    let mut start = line;
+    while start > 0 && is_row(start - 1) {
+        start -= 1;
+    }
+    let mut end = line + 1;
+    while end < line_count && is_row(end) {
+        end += 1;
+    }
+    Some(start..end)
+}
+
+// Byte offsets of the unescaped pipes of a row
+fn pipe_positions(line: &str) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '|' if !escaped => positions.push(i),
+            _ => escaped = false,
+        }
+        if c != '\\' {
+            escaped = false;
+        }
+    }
+    positions
+}
+
+fn split_cells(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let pipes = pipe_positions(trimmed);
+    let mut bounds = vec![0];
+    bounds.extend(pipes.iter().flat_map(|&p| [p, p + 1]));
+    bounds.push(trimmed.len());
+    let mut cells: Vec<String> = bounds
+        .chunks(2)
+        .map(|pair| trimmed[pair[0]..pair[1]].trim().to_string())
+        .collect();
+    // Outer pipes leave an empty first (and usually last) cell
+    if pipes.first() == Some(&0) {
+        cells.remove(0);
+    }
+    if pipes.last().is_some_and(|&p| p + 1 == trimmed.len()) && pipes.len() > 1 {
+        cells.pop();
+    }
+    cells
+}
+
+fn parse_align(cell: &str) -> Option<Align> {
+    let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+    if dashes.is_empty() || !dashes.bytes().all(|b| b == b'-') {
+        return None;
+    }
+    Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+        (true, true) => Align::Center,
+        (true, false) => Align::Left,
+        (false, true) => Align::Right,
+        (false, false) => Align::Default,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    indent: String,
+    // Cells of every row except the separator
+    pub rows: Vec<Vec<String>>,
+    pub aligns: Vec<Align>,
+    // Whether row 0 is a header followed by a separator line
+    pub has_header: bool,
+}
+
+impl Table {
+    pub fn parse<S: AsRef<str>>(lines: &[S]) -> Self {
+        let indent: String = lines
+            .first()
+            .map(|l| l.as_ref().chars().take_while(|c| c.is_whitespace()).collect())
+            .unwrap_or_default();
+        let mut rows = Vec::new();
+        let mut aligns = Vec::new();
+        let mut has_header = false;
+        for (i, line) in lines.iter().enumerate() {
+            let cells = split_cells(line.as_ref());
+            if i == 1 {
+                let parsed: Option<Vec<Align>> = cells.iter().map(|c| parse_align(c)).collect();
+                if let Some(parsed) = parsed {
+                    aligns = parsed;
+                    has_header = true;
+                    continue;
+                }
+            }
+            rows.push(cells);
+        }
+        let columns = rows.iter().map(Vec::len).chain([aligns.len(), 1]).max().unwrap_or(1);
+        for row in &mut rows {
+            row.resize(columns, String::new());
+        }
+        aligns.resize(columns, Align::Default);
+        Self {
+            indent,
+            rows,
+            aligns,
+            has_header,
+        }
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.aligns.len()
+    }
+
+    // Line index (within the table) of a row, accounting for the separator
+    pub fn line_of_row(&self, row: usize) -> usize {
+        if self.has_header && row > 0 {
+            row + 1
+        } else {
+            row
+        }
+    }
+
+    // Row shown on a table line; the separator maps to the header
+    pub fn row_of_line(&self, line: usize) -> usize {
+        if self.has_header && line > 0 {
+            line - 1
+        } else {
+            line
+        }
+    }
+
+    pub fn insert_row(&mut self, at: usize) {
+        let at = at.clamp(usize::from(self.has_header), self.rows.len());
+        self.rows.insert(at, vec![String::new(); self.column_count()]);
+    }
+
+    // The header row and the last remaining row are kept
+    pub fn remove_row(&mut self, row: usize) -> bool {
+        if (self.has_header && row == 0) || row >= self.rows.len() || self.rows.len() == 1 {
+            return false;
+        }
+        self.rows.remove(row);
+        true
+    }
+
+    pub fn insert_column(&mut self, at: usize) {
+        let at = at.min(self.column_count());
+        for row in &mut self.rows {
+            row.insert(at, String::new());
+        }
+        self.aligns.insert(at, Align::Default);
+    }
+
+    pub fn remove_column(&mut self, column: usize) -> bool {
+        if self.column_count() <= 1 || column >= self.column_count() {
+            return false;
+        }
+        for row in &mut self.rows {
+            row.remove(column);
+        }
+        self.aligns.remove(column);
+        true
+    }
+
+    // Rows with padded cells and aligned pipes
+    pub fn render(&self) -> Vec<String> {
+        let widths: Vec<usize> = (0..self.column_count())
+            .map(|c| {
+                self.rows
+                    .iter()
+                    .map(|r| r[c].chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .max(MIN_COLUMN_WIDTH)
+            })
+            .collect();
+        let render_row = |cells: Vec<String>| format!("{}| {} |", self.indent, cells.join(" | "));
+        let mut lines = Vec::with_capacity(self.rows.len() + 1);
+        for (i, row) in self.rows.iter().enumerate() {
+            let cells = row
+                .iter()
+                .zip(&widths)
+                .zip(&self.aligns)
+                .map(|((cell, &width), &align)| {
+                    let pad = width - cell.chars().count();
+                    let (left, right) = match align {
+                        Align::Right => (pad, 0),
+                        Align::Center => (pad / 2, pad - pad / 2),
+                        _ => (0, pad),
+                    };
+                    format!("{}{}{}", " ".repeat(left), cell, " ".repeat(right))
+                })
+                .collect();
+            lines.push(render_row(cells));
+            if i == 0 && self.has_header {
+                let separator = widths
+                    .iter()
+                    .zip(&self.aligns)
+                    .map(|(&width, &align)| match align {
+                        Align::Default => "-".repeat(width),
+                        Align::Left => format!(":{}", "-".repeat(width - 1)),
+                        Align::Center => format!(":{}:", "-".repeat(width - 2)),
+                        Align::Right => format!("{}:", "-".repeat(width - 1)),
+                    })
+                    .collect();
+                lines.push(render_row(separator));
+            }
+        }
+        lines
+    }
+}
+
+// Cell under a byte column of a row
+pub fn column_at(line: &str, column: usize) -> usize {
+    let pipes = pipe_positions(line);
+    let before = pipes.iter().take_while(|&&p| p < column).count();
+    let leading = usize::from(is_table_row(line));
+    before.saturating_sub(leading)
+}
+
+// Byte column where the text of a cell starts in a rendered row
+pub fn cell_start(line: &str, column: usize) -> usize {
+    let pipes = pipe_positions(line);
+    pipes.get(column).map_or(line.len(), |&p| (p + 2).min(line.len()))
+}
+
+pub fn table_skeleton(columns: usize, rows: usize) -> Vec<String> {
+    let mut table = Table {
+        indent: String::new(),
+        rows: vec![(1..=columns).map(|c| format!("Colonne {c}")).collect()],
+        aligns: vec![Align::Default; columns],
+        has_header: true,
+    };
+    for _ in 0..rows {
+        table.insert_row(table.rows.len());
+    }
+    table.render()
+}
+
+// Aligns every table of a Markdown document, keeping its line endings
+pub fn align_tables(text: &str) -> String {
+    let segments: Vec<&str> = text.split_inclusive('\n').collect();
+    let body = |s: &str| s.trim_end_matches(['\r', '\n']).len();
+    let mut out = String::with_capacity(text.len());
+    let mut fence = false;
+    let mut i = 0;
+    while i < segments.len() {
+        let line = &segments[i][..body(segments[i])];
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = !fence;
+        }
+        if fence || !is_table_row(line) {
+            out.push_str(segments[i]);
+            i += 1;
+            continue;
+        }
+        let end = (i..segments.len())
+            .find(|&j| !is_table_row(&segments[j][..body(segments[j])]))
+            .unwrap_or(segments.len());
+        let lines: Vec<&str> = segments[i..end].iter().map(|s| &s[..body(s)]).collect();
+        for (segment, rendered) in segments[i..end].iter().zip(Table::parse(&lines).render()) {
+            out.push_str(&rendered);
+            out.push_str(&segment[body(segment)..]);
+        }
+        i = end;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "# Notes\nintro\n## Setup\n### Linux\n```\n# not a heading\n```\n## Usage\n#tag\n# Annexe #\n";
+
+    fn doc_headings() -> Vec<Heading> {
+        headings(DOC.lines())
+    }
+
+    #[test]
+    fn detects_markdown_extensions() {
+        assert!(is_markdown(Some(Path::new("notes.MD"))));
+        assert!(is_markdown(Some(Path::new("a/b.markdown"))));
+        assert!(!is_markdown(Some(Path::new("notes.txt"))));
+        assert!(!is_markdown(None));
+    }
+
+    #[test]
+    fn parses_atx_headings() {
+        let titles: Vec<_> = doc_headings()
+            .into_iter()
+            .map(|h| (h.line, h.level, h.title))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                (0, 1, "Notes".to_string()),
+                (2, 2, "Setup".to_string()),
+                (3, 3, "Linux".to_string()),
+                (7, 2, "Usage".to_string()),
+                (9, 1, "Annexe".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn closing_hashes_need_a_space() {
+        assert_eq!(parse_heading("## C#"), Some((2, "C#")));
+        assert_eq!(parse_heading("##"), Some((2, "")));
+        assert_eq!(parse_heading("    # code"), None);
+        assert_eq!(parse_heading("####### seven"), None);
+    }
+
+    #[test]
+    fn path_lists_enclosing_headings() {
+        let h = doc_headings();
+        assert!(heading_path(&h, 0).len() == 1);
+        let titles = |line| -> Vec<&str> {
+            heading_path(&h, line).into_iter().map(|i| h[i].title.as_str()).collect()
+        };
+        assert_eq!(titles(5), vec!["Notes", "Setup", "Linux"]);
+        assert_eq!(titles(8), vec!["Notes", "Usage"]);
+        assert_eq!(titles(9), vec!["Annexe"]);
+        assert!(heading_path(&headings("text\n# A".lines()), 0).is_empty());
+    }
+
+    #[test]
+    fn next_and_previous_skip_current_line() {
+        let h = doc_headings();
+        assert_eq!(next_heading(&h, 2).map(|h| h.line), Some(3));
+        assert_eq!(previous_heading(&h, 2).map(|h| h.line), Some(0));
+        assert_eq!(previous_heading(&h, 0), None);
+        assert_eq!(next_heading(&h, 9), None);
+    }
+
+    #[test]
+    fn table_is_aligned_with_header_alignment() {
+        let table = Table::parse(&["| Nom | Qté |", "|:-|--:|", "| pomme | 3 |", "| kiwi |12|"]);
+        assert_eq!(
+            table.render(),
+            vec!["| Nom   | Qté |", "| :---- | --: |", "| pomme |   3 |", "| kiwi  |  12 |"]
+        );
+    }
+
+    #[test]
+    fn table_pads_short_rows_and_keeps_escaped_pipes() {
+        let table = Table::parse(&["| a | b \\| c |", "| d |"]);
+        assert!(!table.has_header);
+        assert_eq!(table.render(), vec!["| a   | b \\| c |", "| d   |        |"]);
+    }
+
+    #[test]
+    fn table_rows_and_columns_can_be_edited() {
+        let mut table = Table::parse(&["| a | b |", "| --- | --- |", "| 1 | 2 |"]);
+        table.insert_column(1);
+        table.insert_row(2);
+        assert_eq!(table.rows, vec![vec!["a", "", "b"], vec!["1", "", "2"], vec!["", "", ""]]);
+        assert!(!table.remove_row(0));
+        assert!(table.remove_row(1));
+        assert!(table.remove_column(0));
+        assert_eq!(table.rows, vec![vec!["", "b"], vec!["", ""]]);
+        assert_eq!(table.line_of_row(1), 2);
+        assert_eq!(table.row_of_line(1), 0);
+    }
+
+    #[test]
+    fn columns_are_found_from_cursor() {
+        let row = "| ab | cd |";
+        assert_eq!(column_at(row, 0), 0);
+        assert_eq!(column_at(row, 3), 0);
+        assert_eq!(column_at(row, 6), 1);
+        assert_eq!(cell_start(row, 1), 7);
+        assert_eq!(cell_start(row, 5), row.len());
+    }
+
+    #[test]
+    fn table_range_stops_at_other_lines() {
+        let lines = ["text", "| a |", "| b |", "", "| c |"];
+        let is_row = |i: usize| is_table_row(lines[i]);
+        assert_eq!(table_range(2, lines.len(), is_row), Some(1..3));
+        assert_eq!(table_range(0, lines.len(), is_row), None);
+    }
+
+    #[test]
+    fn skeleton_has_header_and_empty_rows() {
+        assert_eq!(
+            table_skeleton(2, 1),
+            vec!["| Colonne 1 | Colonne 2 |", "| --------- | --------- |", "|           |           |"]
+        );
+    }
+
+    #[test]
+    fn align_tables_keeps_endings_and_code() {
+        let text = "# T\r\n|a|b|\r\n|-|-|\r\n```\n|x|\n```\n";
+        assert_eq!(
+            align_tables(text),
+            "# T\r\n| a   | b   |\r\n| --- | --- |\r\n```\n|x|\n```\n"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use iced::advanced::text::highlighter::Highlighter;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::spelling::Speller;
+
+// --- Editor marks ---
+
+// What the editor highlighter colors. The text editor cannot draw wavy
+// underlines, so the misspelled words are colored too.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mark {
+    SyntaxError,
+    Misspelled,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MarkSettings {
+    // Line and column of the syntax error
+    pub error: Option<(usize, usize)>,
+    // Dictionary the words are checked against; None when the document is not checked
+    pub speller: Option<Arc<Speller>>,
+}
+
+// The dictionary is compared by identity: a new one is loaded each time
+impl PartialEq for MarkSettings {
+    fn eq(&self, other: &Self) -> bool {
+        self.error == other.error
+            && match (&self.speller, &other.speller) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+// Bytes of `line` marked for an error at `column`: the word it starts, or the
+// last character when the error is past the end of the line
+fn mark_range(line: &str, column: usize) -> Range<usize> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let Some(start) = line.char_indices().nth(column).map(|(i, _)| i) else {
+        return line.char_indices().last().map_or(0..0, |(i, c)| i..i + c.len_utf8());
+    };
+    let word = line[start..]
+        .char_indices()
+        .find(|(i, c)| *i > 0 && (c.is_whitespace() || ",:;{}[]=".contains(*c)))
+        .map_or(line.len() - start, |(i, _)| i);
+    start..start + word.max(1)
+}
+
+// Editor highlighter that only colors the text at the syntax error and the
+// unknown words
+pub struct EditorMarks {
+    settings: MarkSettings,
+    current_line: usize,
+}
+
+impl Highlighter for EditorMarks {
+    type Settings = MarkSettings;
+    type Highlight = Mark;
+    type Iterator<'a> = std::vec::IntoIter<(Range<usize>, Mark)>;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Self {
+            settings: settings.clone(),
+            current_line: 0,
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        self.settings = new_settings.clone();
+        self.current_line = 0;
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.current_line = self.current_line.min(line);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let index = self.current_line;
+        self.current_line += 1;
+        let mut marks = Vec::new();
+        if let Some((error_line, column)) = self.settings.error {
+            if error_line == index {
+                let range = mark_range(line, column);
+                if !range.is_empty() {
+                    marks.push((range, Mark::SyntaxError));
+                }
+            }
+        }
+        if let Some(speller) = &self.settings.speller {
+            let misspelled = speller.misspelled(line.trim_end_matches(['\r', '\n']));
+            marks.extend(
+                misspelled
+                    .into_iter()
+                    .filter(|r| marks.iter().all(|(m, _)| r.end <= m.start || m.end <= r.start))
+                    .map(|r| (r, Mark::Misspelled))
+                    .collect::<Vec<_>>(),
+            );
+            marks.sort_by_key(|(r, _)| r.start);
+        }
+        marks.into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.current_line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mark_covers_the_word_at_the_error() {
+        assert_eq!(mark_range("  \"b\": 2", 2), 2..5);
+        assert_eq!(mark_range("nom = valeur", 6), 6..12);
+        assert_eq!(mark_range("{ \"a\": 1", 40), 7..8);
+        assert_eq!(mark_range("", 0), 0..0);
+    }
+}
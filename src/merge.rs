@@ -0,0 +1,175 @@
+use similar::{DiffOp, TextDiff};
+
+// --- Three-way view of a buffer edited locally while its file changed on disk ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkChoice {
+    Local,
+    Disk,
+    // Local lines first, then the disk ones
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    pub local: Vec<String>,
+    pub disk: Vec<String>,
+    pub choice: HunkChoice,
+}
+
+impl Hunk {
+    pub fn merged(&self) -> Vec<&str> {
+        let local = self.local.iter().map(String::as_str);
+        let disk = self.disk.iter().map(String::as_str);
+        match self.choice {
+            HunkChoice::Local => local.collect(),
+            HunkChoice::Disk => disk.collect(),
+            HunkChoice::Both => local.chain(disk).collect(),
+        }
+    }
+}
+
+// Lines keep their line endings so the merged text is rebuilt byte for byte
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Same(Vec<String>),
+    Conflict(Hunk),
+}
+
+#[derive(Debug, Clone)]
+pub struct Merge {
+    pub segments: Vec<Segment>,
+}
+
+impl Merge {
+    pub fn new(local: &str, disk: &str) -> Self {
+        let diff = TextDiff::from_lines(local, disk);
+        let local_lines = diff.old_slices();
+        let disk_lines = diff.new_slices();
+        let mut segments: Vec<Segment> = Vec::new();
+        for op in diff.ops() {
+            match *op {
+                DiffOp::Equal { old_index, len, .. } => {
+                    let lines = local_lines[old_index..old_index + len]
+                        .iter()
+                        .map(|l| l.to_string())
+                        .collect();
+                    segments.push(Segment::Same(lines));
+                }
+                _ => {
+                    let local = local_lines[op.old_range()]
+                        .iter()
+                        .map(|l| l.to_string());
+                    let disk = disk_lines[op.new_range()].iter().map(|l| l.to_string());
+                    // Adjacent delete + insert ops form a single hunk
+                    if let Some(Segment::Conflict(hunk)) = segments.last_mut() {
+                        hunk.local.extend(local);
+                        hunk.disk.extend(disk);
+                    } else {
+                        segments.push(Segment::Conflict(Hunk {
+                            local: local.collect(),
+                            disk: disk.collect(),
+                            choice: HunkChoice::Local,
+                        }));
+                    }
+                }
+            }
+        }
+        Self { segments }
+    }
+
+    pub fn hunks(&self) -> impl Iterator<Item = &Hunk> {
+        self.segments.iter().filter_map(|s| match s {
+            Segment::Conflict(hunk) => Some(hunk),
+            Segment::Same(_) => None,
+        })
+    }
+
+    pub fn hunk_count(&self) -> usize {
+        self.hunks().count()
+    }
+
+    // `index` counts hunks only, not unchanged segments
+    pub fn choose(&mut self, index: usize, choice: HunkChoice) {
+        let hunk = self
+            .segments
+            .iter_mut()
+            .filter_map(|s| match s {
+                Segment::Conflict(hunk) => Some(hunk),
+                Segment::Same(_) => None,
+            })
+            .nth(index);
+        if let Some(hunk) = hunk {
+            hunk.choice = choice;
+        }
+    }
+
+    pub fn choose_all(&mut self, choice: HunkChoice) {
+        for segment in &mut self.segments {
+            if let Segment::Conflict(hunk) = segment {
+                hunk.choice = choice;
+            }
+        }
+    }
+
+    pub fn merged(&self) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Same(lines) => lines.iter().for_each(|l| out.push_str(l)),
+                Segment::Conflict(hunk) => hunk.merged().into_iter().for_each(|l| out.push_str(l)),
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCAL: &str = "a\nb local\nc\nd\ne\n";
+    const DISK: &str = "a\nb\nc\nd\ne disk\nf\n";
+
+    #[test]
+    fn hunks_group_changed_lines() {
+        let merge = Merge::new(LOCAL, DISK);
+        let hunks: Vec<&Hunk> = merge.hunks().collect();
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].local, vec!["b local\n"]);
+        assert_eq!(hunks[0].disk, vec!["b\n"]);
+        assert_eq!(hunks[1].local, vec!["e\n"]);
+        assert_eq!(hunks[1].disk, vec!["e disk\n", "f\n"]);
+    }
+
+    #[test]
+    fn default_keeps_local_text() {
+        let merge = Merge::new(LOCAL, DISK);
+        assert_eq!(merge.merged(), LOCAL);
+    }
+
+    #[test]
+    fn choose_all_disk_gives_disk_text() {
+        let mut merge = Merge::new(LOCAL, DISK);
+        merge.choose_all(HunkChoice::Disk);
+        assert_eq!(merge.merged(), DISK);
+    }
+
+    #[test]
+    fn per_hunk_choices() {
+        let mut merge = Merge::new(LOCAL, DISK);
+        merge.choose(1, HunkChoice::Disk);
+        assert_eq!(merge.merged(), "a\nb local\nc\nd\ne disk\nf\n");
+        merge.choose(0, HunkChoice::Both);
+        assert_eq!(merge.merged(), "a\nb local\nb\nc\nd\ne disk\nf\n");
+        merge.choose(5, HunkChoice::Disk);
+        assert_eq!(merge.hunk_count(), 2);
+    }
+
+    #[test]
+    fn identical_texts_have_no_hunks() {
+        let merge = Merge::new("same\n", "same\n");
+        assert_eq!(merge.hunk_count(), 0);
+        assert_eq!(merge.merged(), "same\n");
+    }
+}
@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::language::Language;
+use crate::navigation::Bookmarks;
+use crate::paths;
+use crate::preferences;
+
+// --- Per-file metadata kept outside the files ---
+
+// Least recently used files are forgotten beyond this count
+const MAX_FILE_METADATA: usize = 1000;
+
+// Where the cursor was when the file was closed (column in characters)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Caret {
+    pub line: usize,
+    pub column: usize,
+    pub scroll: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct FileMetadata {
+    // As opened, for pruning and for reading the file by hand
+    pub path: PathBuf,
+    pub caret: Option<Caret>,
+    // Chosen from the status bar instead of the detected one
+    pub language: Option<Language>,
+    // Line numbers
+    pub bookmarks: Vec<usize>,
+    // Folded line ranges, first and last line
+    pub folds: Vec<(usize, usize)>,
+    // Seconds since the epoch of the last change, to forget the oldest
+    pub touched: u64,
+}
+
+impl FileMetadata {
+    fn is_empty(&self) -> bool {
+        self.caret.is_none() && self.language.is_none() && self.bookmarks.is_empty() && self.folds.is_empty()
+    }
+}
+
+// metadata.json, keyed by a hash of the canonical path so a file reached through
+// a link or another spelling shares its entry
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct MetadataStore {
+    entries: HashMap<String, FileMetadata>,
+}
+
+// Files written by earlier versions, imported once
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct LegacyPositions {
+    entries: Vec<LegacyPosition>,
+}
+
+#[derive(Deserialize)]
+struct LegacyPosition {
+    path: PathBuf,
+    line: usize,
+    column: usize,
+    scroll: f32,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct LegacyLanguages {
+    entries: Vec<LegacyLanguage>,
+}
+
+#[derive(Deserialize)]
+struct LegacyLanguage {
+    path: PathBuf,
+    language: Language,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+// FNV-1a: stable across Rust versions, unlike the standard hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+// Shares are not resolved, so a server out of reach cannot stall an open or a
+// close; Windows paths ignore case like the file system
+fn key(path: &Path) -> String {
+    let path = paths::normalize(path);
+    let canonical = if paths::is_unc(&path) {
+        path
+    } else {
+        std::fs::canonicalize(&path).map_or(path, |p| paths::normalize(&p))
+    };
+    let mut text = canonical.to_string_lossy().into_owned();
+    if cfg!(target_os = "windows") {
+        text = text.to_lowercase();
+    }
+    format!("{:016x}", fnv1a(text.as_bytes()))
+}
+
+impl MetadataStore {
+    pub fn path() -> PathBuf {
+        preferences::dir().join("metadata.json")
+    }
+
+    // Blocking: reads the store, or builds it from positions.json and
+    // languages.json the first time
+    pub fn load() -> Self {
+        let path = Self::path();
+        if path.exists() {
+            return preferences::load_json(&path);
+        }
+        let mut store = Self::default();
+        let positions: LegacyPositions = preferences::load_json(&preferences::dir().join("positions.json"));
+        for p in positions.entries.into_iter().rev() {
+            let caret = Caret {
+                line: p.line,
+                column: p.column,
+                scroll: p.scroll,
+            };
+            store.update(&p.path, |meta| meta.caret = Some(caret));
+        }
+        let languages: LegacyLanguages = preferences::load_json(&preferences::dir().join("languages.json"));
+        for l in languages.entries.into_iter().rev() {
+            store.update(&l.path, |meta| meta.language = Some(l.language));
+        }
+        store
+    }
+
+    pub fn save(&self) {
+        preferences::save_json(&Self::path(), self);
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&FileMetadata> {
+        self.entries.get(&key(path))
+    }
+
+    pub fn caret(&self, path: &Path) -> Option<Caret> {
+        self.get(path).and_then(|meta| meta.caret)
+    }
+
+    pub fn language(&self, path: &Path) -> Option<Language> {
+        self.get(path).and_then(|meta| meta.language)
+    }
+
+    pub fn bookmarks(&self, path: &Path) -> Bookmarks {
+        Bookmarks::from_lines(self.get(path).map(|meta| meta.bookmarks.clone()).unwrap_or_default())
+    }
+
+    // Changes the entry of `path`, created if needed; an entry left with
+    // nothing in it is dropped
+    pub fn update(&mut self, path: &Path, change: impl FnOnce(&mut FileMetadata)) {
+        let key = key(path);
+        let meta = self.entries.entry(key.clone()).or_default();
+        change(meta);
+        meta.path = paths::normalize(path);
+        meta.touched = now().max(meta.touched);
+        if meta.is_empty() {
+            self.entries.remove(&key);
+        }
+        if self.entries.len() > MAX_FILE_METADATA {
+            let oldest = self.entries.iter().min_by_key(|(_, m)| m.touched).map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    // Blocking: forgets the files deleted from disk. Files on shares are kept,
+    // a server out of reach does not mean they are gone. Returns how many
+    // entries were dropped.
+    pub fn prune(&mut self) -> usize {
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, meta| paths::is_network(&meta.path) || meta.path.exists());
+        before - self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_kept_per_file() {
+        let mut store = MetadataStore::default();
+        let caret = Caret {
+            line: 9,
+            column: 2,
+            scroll: 4.0,
+        };
+        store.update(Path::new("/notes/journal"), |m| m.language = Some(Language::Markdown));
+        store.update(Path::new("/notes/journal"), |m| m.caret = Some(caret));
+        store.update(Path::new("/src/build"), |m| m.bookmarks = vec![3, 12]);
+        assert_eq!(store.language(Path::new("/notes/journal")), Some(Language::Markdown));
+        assert_eq!(store.caret(Path::new("/notes/journal")), Some(caret));
+        assert_eq!(store.get(Path::new("/src/build")).unwrap().bookmarks, [3, 12]);
+
+        store.update(Path::new("/notes/journal"), |m| m.language = None);
+        assert_eq!(store.language(Path::new("/notes/journal")), None);
+        store.update(Path::new("/notes/journal"), |m| m.caret = None);
+        assert!(store.get(Path::new("/notes/journal")).is_none());
+    }
+
+    #[test]
+    fn store_is_bounded() {
+        let mut store = MetadataStore::default();
+        for i in 0..MAX_FILE_METADATA + 3 {
+            store.update(Path::new(&format!("/f{i}.txt")), |m| m.bookmarks = vec![i]);
+        }
+        assert_eq!(store.entries.len(), MAX_FILE_METADATA);
+        let json = serde_json::to_string(&store).unwrap();
+        let restored: MetadataStore = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.entries, store.entries);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_share_an_entry_and_deleted_files_are_pruned() {
+        let dir = std::env::temp_dir().join(format!("notepad_metadata_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let real = dir.join("real.txt");
+        let link = dir.join("link.txt");
+        std::fs::write(&real, "x").unwrap();
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut store = MetadataStore::default();
+        store.update(&link, |m| m.language = Some(Language::Json));
+        assert_eq!(store.language(&real), Some(Language::Json));
+        store.update(&dir.join("gone.txt"), |m| m.bookmarks = vec![1]);
+        assert_eq!(store.prune(), 1);
+        assert_eq!(store.language(&real), Some(Language::Json));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
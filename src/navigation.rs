@@ -0,0 +1,323 @@
+// Cursor positions visited before jumps (go to line, find, tab switches), across tabs
+pub const MAX_NAV_HISTORY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NavPoint {
+    pub doc: u64,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Default)]
+pub struct NavHistory {
+    back: Vec<NavPoint>,
+    forward: Vec<NavPoint>,
+}
+
+impl NavHistory {
+    // Remembers where a jump started; a new jump discards the forward entries
+    pub fn record(&mut self, from: NavPoint) {
+        if self.back.last() != Some(&from) {
+            self.back.push(from);
+            if self.back.len() > MAX_NAV_HISTORY {
+                self.back.remove(0);
+            }
+        }
+        self.forward.clear();
+    }
+
+    // Entries pointing to closed documents are skipped
+    pub fn back(&mut self, current: NavPoint, exists: impl Fn(u64) -> bool) -> Option<NavPoint> {
+        let target = Self::pop_valid(&mut self.back, current, exists)?;
+        self.forward.push(current);
+        Some(target)
+    }
+
+    pub fn forward(
+        &mut self,
+        current: NavPoint,
+        exists: impl Fn(u64) -> bool,
+    ) -> Option<NavPoint> {
+        let target = Self::pop_valid(&mut self.forward, current, exists)?;
+        self.back.push(current);
+        Some(target)
+    }
+
+    fn pop_valid(
+        stack: &mut Vec<NavPoint>,
+        current: NavPoint,
+        exists: impl Fn(u64) -> bool,
+    ) -> Option<NavPoint> {
+        while let Some(point) = stack.pop() {
+            if point != current && exists(point.doc) {
+                return Some(point);
+            }
+        }
+        None
+    }
+}
+
+// --- Recently edited places of a document ---
+
+pub const MAX_EDIT_SPOTS: usize = 50;
+// Edits this close to an earlier one replace it instead of adding a spot
+const SPOT_MERGE_LINES: usize = 3;
+
+// Cursor positions after the edits, kept apart from the undo history so
+// undoing keeps them. Lines follow the later insertions and deletions.
+#[derive(Debug, Default, Clone)]
+pub struct EditSpots {
+    // (line, column), oldest edit first
+    spots: Vec<(usize, usize)>,
+    // Spot reached by the last "Dernière modification", until the next edit
+    walk: Option<usize>,
+}
+
+impl EditSpots {
+    // An edit starting at line `from` that added `delta` lines (removed when
+    // negative) and left the cursor at `at`
+    pub fn record(&mut self, from: usize, delta: isize, at: (usize, usize)) {
+        if delta != 0 {
+            for spot in &mut self.spots {
+                if spot.0 > from {
+                    spot.0 = spot.0.saturating_add_signed(delta).max(from);
+                }
+            }
+        }
+        self.spots.retain(|spot| spot.0.abs_diff(at.0) > SPOT_MERGE_LINES);
+        self.spots.push(at);
+        if self.spots.len() > MAX_EDIT_SPOTS {
+            self.spots.remove(0);
+        }
+        self.walk = None;
+    }
+
+    // Steps back through the spots, newest first, then around again
+    pub fn last(&mut self) -> Option<(usize, usize)> {
+        let index = match self.walk {
+            Some(i) if i > 0 && i <= self.spots.len() => i - 1,
+            _ => self.spots.len().checked_sub(1)?,
+        };
+        self.walk = Some(index);
+        Some(self.spots[index])
+    }
+
+    // Nearest spot below / above `line`, in document order
+    pub fn next_after(&self, line: usize) -> Option<(usize, usize)> {
+        self.spots.iter().copied().filter(|s| s.0 > line).min()
+    }
+
+    pub fn previous_before(&self, line: usize) -> Option<(usize, usize)> {
+        self.spots.iter().copied().filter(|s| s.0 < line).max()
+    }
+}
+
+// --- Bookmarked lines ---
+
+// Lines marked with Ctrl+F2, in document order. Like the edit spots they
+// follow the lines inserted and deleted above them, undo included.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Bookmarks {
+    lines: Vec<usize>,
+}
+
+impl Bookmarks {
+    pub fn from_lines(mut lines: Vec<usize>) -> Self {
+        lines.sort_unstable();
+        lines.dedup();
+        Self { lines }
+    }
+
+    pub fn lines(&self) -> &[usize] {
+        &self.lines
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        self.lines.binary_search(&line).is_ok()
+    }
+
+    pub fn toggle(&mut self, line: usize) {
+        match self.lines.binary_search(&line) {
+            Ok(i) => {
+                self.lines.remove(i);
+            }
+            Err(i) => self.lines.insert(i, line),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    // An edit starting at line `from` added `delta` lines (removed when
+    // negative); bookmarks on deleted lines move up to the edit
+    pub fn shift(&mut self, from: usize, delta: isize) {
+        if delta == 0 {
+            return;
+        }
+        for line in &mut self.lines {
+            if *line > from {
+                *line = line.saturating_add_signed(delta).max(from);
+            }
+        }
+        self.lines.dedup();
+    }
+
+    // Next bookmark below `line`, around to the first one
+    pub fn next_after(&self, line: usize) -> Option<usize> {
+        self.lines.iter().copied().find(|&l| l > line).or(self.lines.first().copied())
+    }
+
+    pub fn previous_before(&self, line: usize) -> Option<usize> {
+        self.lines.iter().copied().rev().find(|&l| l < line).or(self.lines.last().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(doc: u64, line: usize) -> NavPoint {
+        NavPoint {
+            doc,
+            line,
+            column: 0,
+        }
+    }
+
+    #[test]
+    fn back_then_forward() {
+        let mut history = NavHistory::default();
+        history.record(point(1, 0));
+        history.record(point(1, 10));
+        assert_eq!(history.back(point(1, 20), |_| true), Some(point(1, 10)));
+        assert_eq!(history.back(point(1, 10), |_| true), Some(point(1, 0)));
+        assert_eq!(history.back(point(1, 0), |_| true), None);
+        assert_eq!(history.forward(point(1, 0), |_| true), Some(point(1, 10)));
+        assert_eq!(history.forward(point(1, 10), |_| true), Some(point(1, 20)));
+        assert_eq!(history.forward(point(1, 20), |_| true), None);
+    }
+
+    #[test]
+    fn record_clears_forward() {
+        let mut history = NavHistory::default();
+        history.record(point(1, 0));
+        history.back(point(1, 5), |_| true);
+        history.record(point(1, 0));
+        assert_eq!(history.forward(point(1, 7), |_| true), None);
+    }
+
+    #[test]
+    fn record_skips_duplicates() {
+        let mut history = NavHistory::default();
+        history.record(point(1, 3));
+        history.record(point(1, 3));
+        assert_eq!(history.back(point(1, 9), |_| true), Some(point(1, 3)));
+        assert_eq!(history.back(point(1, 3), |_| true), None);
+    }
+
+    #[test]
+    fn closed_documents_are_skipped() {
+        let mut history = NavHistory::default();
+        history.record(point(1, 0));
+        history.record(point(2, 4));
+        assert_eq!(history.back(point(1, 8), |doc| doc != 2), Some(point(1, 0)));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = NavHistory::default();
+        for line in 0..MAX_NAV_HISTORY + 5 {
+            history.record(point(1, line));
+        }
+        let mut steps = 0;
+        let mut current = point(1, usize::MAX);
+        while let Some(p) = history.back(current, |_| true) {
+            current = p;
+            steps += 1;
+        }
+        assert_eq!(steps, MAX_NAV_HISTORY);
+        assert_eq!(current, point(1, 5));
+    }
+
+    // ============================
+    // Edit spots
+    // ============================
+
+    #[test]
+    fn last_edit_walks_back_then_around() {
+        let mut spots = EditSpots::default();
+        spots.record(2, 0, (2, 4));
+        spots.record(20, 0, (20, 1));
+        spots.record(40, 0, (40, 0));
+        assert_eq!(spots.last(), Some((40, 0)));
+        assert_eq!(spots.last(), Some((20, 1)));
+        assert_eq!(spots.last(), Some((2, 4)));
+        assert_eq!(spots.last(), Some((40, 0)));
+        // A new edit starts again from the newest
+        spots.record(60, 0, (60, 0));
+        assert_eq!(spots.last(), Some((60, 0)));
+    }
+
+    #[test]
+    fn nearby_edits_are_one_spot() {
+        let mut spots = EditSpots::default();
+        spots.record(10, 0, (10, 0));
+        spots.record(12, 0, (12, 5));
+        assert_eq!(spots.last(), Some((12, 5)));
+        assert_eq!(spots.last(), Some((12, 5)));
+    }
+
+    #[test]
+    fn spots_follow_inserted_and_removed_lines() {
+        let mut spots = EditSpots::default();
+        spots.record(30, 0, (30, 0));
+        spots.record(5, 3, (5, 0));
+        assert_eq!(spots.next_after(5), Some((33, 0)));
+        // The lines of the spot were deleted: it moves up to the deletion
+        spots.record(20, -40, (20, 0));
+        assert_eq!(spots.next_after(5), Some((20, 0)));
+        assert_eq!(spots.previous_before(20), Some((5, 0)));
+    }
+
+    #[test]
+    fn next_and_previous_edits_in_document_order() {
+        let mut spots = EditSpots::default();
+        spots.record(50, 0, (50, 0));
+        spots.record(10, 0, (10, 0));
+        spots.record(30, 0, (30, 0));
+        assert_eq!(spots.next_after(10), Some((30, 0)));
+        assert_eq!(spots.previous_before(30), Some((10, 0)));
+        assert_eq!(spots.previous_before(10), None);
+        assert_eq!(spots.next_after(50), None);
+    }
+
+    #[test]
+    fn bookmarks_toggle_and_wrap_around() {
+        let mut marks = Bookmarks::from_lines(vec![40, 10, 10]);
+        assert_eq!(marks.lines(), [10, 40]);
+        marks.toggle(25);
+        marks.toggle(40);
+        assert_eq!(marks.lines(), [10, 25]);
+        assert_eq!(marks.next_after(10), Some(25));
+        assert_eq!(marks.next_after(25), Some(10));
+        assert_eq!(marks.previous_before(10), Some(25));
+        assert_eq!(marks.previous_before(12), Some(10));
+        assert_eq!(Bookmarks::default().next_after(0), None);
+    }
+
+    #[test]
+    fn bookmarks_follow_inserted_and_removed_lines() {
+        let mut marks = Bookmarks::from_lines(vec![2, 8, 12]);
+        marks.shift(5, 3);
+        assert_eq!(marks.lines(), [2, 11, 15]);
+        // Both deleted lines end up on the edit, once
+        marks.shift(3, -20);
+        assert_eq!(marks.lines(), [2, 3]);
+        assert!(marks.contains(3) && !marks.contains(11));
+    }
+}
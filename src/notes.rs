@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+use crate::preferences;
+
+// Recently opened files remembered besides the tagged and favorite ones
+const MAX_RECENT_NOTES: usize = 200;
+
+// Bytes of a file read by the full-text search
+const MAX_SEARCH_BYTES: u64 = 4 * 1024 * 1024;
+
+const SNIPPET_CHARS: usize = 80;
+
+// --- Note metadata ---
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct NoteMeta {
+    pub path: PathBuf,
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    // Seconds since the Unix epoch
+    pub last_opened: u64,
+}
+
+impl NoteMeta {
+    fn is_kept(&self) -> bool {
+        self.favorite || !self.tags.is_empty()
+    }
+}
+
+// Tags, favorites and recently opened files, most recent first
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct NoteIndex {
+    entries: Vec<NoteMeta>,
+}
+
+impl NoteIndex {
+    pub fn path() -> PathBuf {
+        preferences::dir().join("notes.json")
+    }
+
+    pub fn load() -> Self {
+        preferences::load_json(&Self::path())
+    }
+
+    pub fn save(&self) {
+        preferences::save_json(&Self::path(), self);
+    }
+
+    pub fn entries(&self) -> &[NoteMeta] {
+        &self.entries
+    }
+
+    // \\?\UNC\srv\share\a.md and \\srv\share\a.md are the same note
+    pub fn get(&self, path: &Path) -> Option<&NoteMeta> {
+        let path = paths::normalize(path);
+        self.entries.iter().find(|e| e.path == path)
+    }
+
+    // Records that the file was opened or saved
+    pub fn touch(&mut self, path: &Path, now: u64) {
+        let mut meta = self.take(path);
+        meta.last_opened = now;
+        self.entries.insert(0, meta);
+        self.prune();
+    }
+
+    pub fn set_tags(&mut self, path: &Path, tags: Vec<String>, favorite: bool) {
+        let mut meta = self.take(path);
+        meta.tags = tags;
+        meta.favorite = favorite;
+        self.entries.insert(0, meta);
+        self.prune();
+    }
+
+    // Every tag in use, sorted
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.entries.iter().flat_map(|e| e.tags.clone()).collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    fn take(&mut self, path: &Path) -> NoteMeta {
+        let path = paths::normalize(path);
+        match self.entries.iter().position(|e| e.path == path) {
+            Some(i) => self.entries.remove(i),
+            None => NoteMeta {
+                path,
+                ..NoteMeta::default()
+            },
+        }
+    }
+
+    // Drops the oldest plain entries; tagged and favorite files are kept
+    fn prune(&mut self) {
+        let mut plain = 0;
+        self.entries.retain(|e| {
+            if e.is_kept() {
+                return true;
+            }
+            plain += 1;
+            plain <= MAX_RECENT_NOTES
+        });
+    }
+}
+
+// "projet, #idée  todo" -> ["projet", "idée", "todo"]
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(|c: char| c == ',' || c.is_whitespace()) {
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+// --- Search ---
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteHit {
+    pub path: PathBuf,
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    // Line holding the first searched word
+    pub snippet: Option<String>,
+}
+
+// `#tag` words filter by tag, the others must all appear in the file name or text
+fn parse_query(query: &str) -> (Vec<String>, Vec<String>) {
+    let (tags, words): (Vec<&str>, Vec<&str>) =
+        query.split_whitespace().partition(|w| w.starts_with('#'));
+    let tags = tags
+        .iter()
+        .map(|t| t.trim_start_matches('#').to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    (tags, words.iter().map(|w| w.to_lowercase()).collect())
+}
+
+// Blocking: reads the files when the query has words. Favorites come first,
+// then the most recently opened.
+pub fn search(entries: Vec<NoteMeta>, query: &str) -> Vec<NoteHit> {
+    let (tags, words) = parse_query(query);
+    let mut hits: Vec<NoteHit> = entries
+        .into_iter()
+        .filter(|meta| tags.iter().all(|t| meta.tags.contains(t)))
+        .filter_map(|meta| {
+            let snippet = if words.is_empty() {
+                // An unreachable share is listed rather than forgotten
+                if !paths::is_unc(&meta.path) && !meta.path.exists() {
+                    return None;
+                }
+                None
+            } else {
+                let text = read_text(&meta.path)?;
+                let name = meta
+                    .path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                let lower = text.to_lowercase();
+                if !words.iter().all(|w| lower.contains(w) || name.contains(w)) {
+                    return None;
+                }
+                snippet(&text, &words)
+            };
+            Some(NoteHit {
+                path: meta.path,
+                tags: meta.tags,
+                favorite: meta.favorite,
+                snippet,
+            })
+        })
+        .collect();
+    hits.sort_by_key(|hit| !hit.favorite);
+    hits
+}
+
+fn read_text(path: &Path) -> Option<String> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(MAX_SEARCH_BYTES)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn snippet(text: &str, words: &[String]) -> Option<String> {
+    let line = text
+        .lines()
+        .find(|line| words.iter().any(|w| line.to_lowercase().contains(w)))?
+        .trim();
+    let mut snippet: String = line.chars().take(SNIPPET_CHARS).collect();
+    if line.chars().count() > SNIPPET_CHARS {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_notes(files: &[(&str, &str)]) -> (PathBuf, NoteIndex) {
+        let dir = std::env::temp_dir().join(format!(
+            "notepad-notes-{}-{}",
+            std::process::id(),
+            files.len()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut index = NoteIndex::default();
+        for (i, (name, text)) in files.iter().enumerate() {
+            let path = dir.join(name);
+            std::fs::write(&path, text).unwrap();
+            index.touch(&path, i as u64);
+        }
+        (dir, index)
+    }
+
+    #[test]
+    fn tags_are_parsed_and_deduplicated() {
+        assert_eq!(parse_tags("Projet, #idée  todo,projet"), vec!["projet", "idée", "todo"]);
+        assert!(parse_tags(" , # ").is_empty());
+    }
+
+    #[test]
+    fn touch_keeps_tagged_files_and_prunes_old_ones() {
+        let mut index = NoteIndex::default();
+        index.set_tags(Path::new("/tagged.md"), vec!["a".into()], false);
+        for i in 0..MAX_RECENT_NOTES + 5 {
+            index.touch(Path::new(&format!("/{i}.txt")), i as u64);
+        }
+        assert_eq!(index.entries().len(), MAX_RECENT_NOTES + 1);
+        assert!(index.get(Path::new("/tagged.md")).is_some());
+        assert!(index.get(Path::new("/0.txt")).is_none());
+        assert_eq!(index.entries()[0].path, Path::new(&format!("/{}.txt", MAX_RECENT_NOTES + 4)));
+    }
+
+    #[test]
+    fn search_filters_by_tag_and_text() {
+        let (dir, mut index) = temp_notes(&[
+            ("courses.md", "pain\nlait et œufs\n"),
+            ("projet.md", "# Projet\nAcheter du LAIT pour la démo\n"),
+            ("vide.md", ""),
+        ]);
+        index.set_tags(&dir.join("projet.md"), vec!["travail".into()], false);
+        index.set_tags(&dir.join("vide.md"), vec!["travail".into()], true);
+
+        let hits = search(index.entries().to_vec(), "#travail");
+        let names: Vec<_> = hits.iter().map(|h| h.path.file_name().unwrap()).collect();
+        assert_eq!(names, ["vide.md", "projet.md"]);
+
+        let hits = search(index.entries().to_vec(), "lait");
+        assert_eq!(hits.len(), 2);
+        let projet = hits.iter().find(|h| h.path.ends_with("projet.md")).unwrap();
+        assert_eq!(projet.snippet.as_deref(), Some("Acheter du LAIT pour la démo"));
+
+        let hits = search(index.entries().to_vec(), "#travail lait");
+        assert_eq!(hits.len(), 1);
+        assert!(search(index.entries().to_vec(), "courses").iter().any(|h| h.path.ends_with("courses.md")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
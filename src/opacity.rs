@@ -0,0 +1,58 @@
+use iced::Task;
+
+use crate::jobs;
+
+// --- Window opacity ---
+
+// Below this the text behind the window gets harder to read than the document
+pub const MIN_WINDOW_OPACITY: u8 = 70;
+
+// Only Windows exposes a per-window alpha the application can set
+pub const SUPPORTED: bool = cfg!(target_os = "windows");
+
+// Applies the opacity, in percent, to the main window
+pub fn apply<T: Send + 'static>(percent: u8) -> Task<T> {
+    let percent = percent.clamp(MIN_WINDOW_OPACITY, 100);
+    iced::window::latest().then(move |id| match id {
+        Some(id) => iced::window::run(id, move |window| {
+            if let Some(hwnd) = jobs::native_window(window) {
+                layered::set_alpha(hwnd, percent);
+            }
+        })
+        .discard(),
+        None => Task::none(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+mod layered {
+    const GWL_EXSTYLE: i32 = -20;
+    const WS_EX_LAYERED: isize = 0x0008_0000;
+    const LWA_ALPHA: u32 = 0x2;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetWindowLongPtrW(hwnd: isize, index: i32) -> isize;
+        fn SetWindowLongPtrW(hwnd: isize, index: i32, value: isize) -> isize;
+        fn SetLayeredWindowAttributes(hwnd: isize, key: u32, alpha: u8, flags: u32) -> i32;
+    }
+
+    // An opaque window leaves the layered mode so it is composed as before
+    pub fn set_alpha(hwnd: isize, percent: u8) {
+        unsafe {
+            let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+            if percent >= 100 {
+                SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style & !WS_EX_LAYERED);
+                return;
+            }
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED);
+            let alpha = (u32::from(percent) * 255 / 100) as u8;
+            SetLayeredWindowAttributes(hwnd, 0, alpha, LWA_ALPHA);
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod layered {
+    pub fn set_alpha(_hwnd: isize, _percent: u8) {}
+}
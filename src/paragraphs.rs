@@ -0,0 +1,113 @@
+use std::ops::Range;
+
+// --- Paragraphs: blocks of lines separated by blank lines ---
+
+// Lines are read through `blank(line)`, true for an empty or whitespace-only
+// line, so large documents are not copied to walk a few paragraphs
+
+// Start of the next paragraph, the last line when there is none below
+pub fn next_start(count: usize, blank: impl Fn(usize) -> bool, line: usize) -> usize {
+    let last = count.saturating_sub(1);
+    let mut i = line;
+    while i < last && !blank(i) {
+        i += 1;
+    }
+    while i < last && blank(i) {
+        i += 1;
+    }
+    if i == line { last } else { i }
+}
+
+// Start of the current paragraph when the cursor is inside it, else of the
+// one above; the first line when there is none
+pub fn previous_start(count: usize, blank: impl Fn(usize) -> bool, line: usize) -> usize {
+    let mut i = line.min(count.saturating_sub(1));
+    if i > 0 && !blank(i) && !blank(i - 1) {
+        return start(&blank, i);
+    }
+    while i > 0 {
+        i -= 1;
+        if !blank(i) {
+            return start(&blank, i);
+        }
+    }
+    0
+}
+
+// Lines of the paragraph under `line`, None on a blank line
+pub fn bounds(count: usize, blank: impl Fn(usize) -> bool, line: usize) -> Option<Range<usize>> {
+    if line >= count || blank(line) {
+        return None;
+    }
+    let mut end = line + 1;
+    while end < count && !blank(end) {
+        end += 1;
+    }
+    Some(start(&blank, line)..end)
+}
+
+// The paragraph under `line` and the one above it, upper one first
+pub fn with_previous(count: usize, blank: impl Fn(usize) -> bool, line: usize) -> Option<(Range<usize>, Range<usize>)> {
+    let current = bounds(count, &blank, line)?;
+    let mut i = current.start;
+    while i > 0 && blank(i - 1) {
+        i -= 1;
+    }
+    let above = bounds(count, &blank, i.checked_sub(1)?)?;
+    Some((above, current))
+}
+
+// The paragraph under `line` and the one below it, upper one first
+pub fn with_next(count: usize, blank: impl Fn(usize) -> bool, line: usize) -> Option<(Range<usize>, Range<usize>)> {
+    let current = bounds(count, &blank, line)?;
+    let mut i = current.end;
+    while i < count && blank(i) {
+        i += 1;
+    }
+    let below = bounds(count, &blank, i)?;
+    Some((current, below))
+}
+
+fn start(blank: impl Fn(usize) -> bool, line: usize) -> usize {
+    let mut i = line;
+    while i > 0 && !blank(i - 1) {
+        i -= 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &[&str] = &["a", "b", "", "  ", "c", "d", "e", "", "f"];
+
+    fn blank(line: usize) -> bool {
+        TEXT[line].trim().is_empty()
+    }
+
+    #[test]
+    fn cursor_jumps_from_paragraph_to_paragraph() {
+        let count = TEXT.len();
+        assert_eq!(next_start(count, blank, 0), 4);
+        assert_eq!(next_start(count, blank, 2), 4);
+        assert_eq!(next_start(count, blank, 5), 8);
+        assert_eq!(next_start(count, blank, 8), 8);
+        assert_eq!(previous_start(count, blank, 6), 4);
+        assert_eq!(previous_start(count, blank, 4), 0);
+        assert_eq!(previous_start(count, blank, 3), 0);
+        assert_eq!(previous_start(count, blank, 8), 4);
+        assert_eq!(previous_start(count, blank, 0), 0);
+    }
+
+    #[test]
+    fn neighbouring_paragraphs() {
+        let count = TEXT.len();
+        assert_eq!(bounds(count, blank, 5), Some(4..7));
+        assert_eq!(bounds(count, blank, 3), None);
+        assert_eq!(with_previous(count, blank, 5), Some((0..2, 4..7)));
+        assert_eq!(with_previous(count, blank, 1), None);
+        assert_eq!(with_next(count, blank, 1), Some((0..2, 4..7)));
+        assert_eq!(with_next(count, blank, 8), None);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::app::LineEnding;
+
+// --- Paste ---
+
+// `text` with its line breaks (CRLF, LF or CR, even mixed) written as
+// `ending`, so a paste matches the document it lands in
+pub fn normalize_line_endings(text: &str, ending: LineEnding) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                out.push_str(ending.as_str());
+            }
+            '\n' => out.push_str(ending.as_str()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// "Coller sans formatage": the clipboard text without byte order marks and
+// control characters, line breaks and tabs aside
+pub fn plain(text: &str) -> String {
+    text.chars()
+        .filter(|&c| c != '\u{FEFF}' && (!c.is_control() || matches!(c, '\n' | '\r' | '\t')))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_endings_follow_the_document() {
+        let mixed = "a\r\nb\nc\rd\r\n";
+        assert_eq!(normalize_line_endings(mixed, LineEnding::Lf), "a\nb\nc\nd\n");
+        assert_eq!(normalize_line_endings(mixed, LineEnding::CrLf), "a\r\nb\r\nc\r\nd\r\n");
+        assert_eq!(normalize_line_endings(mixed, LineEnding::Cr), "a\rb\rc\rd\r");
+        assert_eq!(normalize_line_endings("\r\n\r\n", LineEnding::Lf), "\n\n");
+    }
+
+    #[test]
+    fn plain_drops_bom_and_control_characters() {
+        assert_eq!(plain("\u{FEFF}titre\u{0}\u{7}\tété\r\nfin\u{1b}[0m"), "titre\tété\r\nfin[0m");
+    }
+}
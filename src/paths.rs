@@ -0,0 +1,271 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+// --- Symbolic links and network shares ---
+
+// Linux file system types reached over the network
+#[cfg(target_os = "linux")]
+const NETWORK_FILE_SYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "ncpfs", "afs", "9p", "davfs", "fuse.sshfs", "fuse.rclone",
+];
+
+// Canonical Windows paths carry a \\?\ prefix: \\?\UNC\srv\share\a.txt is
+// shown and stored as \\srv\share\a.txt, \\?\C:\a.txt as C:\a.txt
+pub fn normalize(path: &Path) -> PathBuf {
+    let text = path.to_string_lossy();
+    if let Some(rest) = text.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{rest}"));
+    }
+    if let Some(rest) = text.strip_prefix(r"\\?\") {
+        return PathBuf::from(rest);
+    }
+    path.to_path_buf()
+}
+
+// \\server\share\... (or //server/share/...)
+pub fn is_unc(path: &Path) -> bool {
+    let text = normalize(path).to_string_lossy().replace('/', "\\");
+    text.starts_with(r"\\") && !text.starts_with(r"\\.\") && text.len() > 2
+}
+
+// Real file behind a symbolic link, None for a plain file
+pub fn symlink_target(path: &Path) -> Option<PathBuf> {
+    let meta = std::fs::symlink_metadata(path).ok()?;
+    if !meta.file_type().is_symlink() {
+        return None;
+    }
+    std::fs::canonicalize(path).ok().map(|p| normalize(&p))
+}
+
+// File written by saves: the target of a link, so replacing the file in a
+// safe save never turns the link into a plain file
+pub fn write_target(path: &Path) -> PathBuf {
+    symlink_target(path).unwrap_or_else(|| path.to_path_buf())
+}
+
+// Same file reached through another spelling: a link, "..", or another case
+// on Windows. Shares are only compared by name, so a server out of reach
+// cannot stall the check.
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return true;
+    }
+    if is_unc(&a) || is_unc(&b) {
+        return false;
+    }
+    match (std::fs::canonicalize(&a), std::fs::canonicalize(&b)) {
+        (Ok(x), Ok(y)) => x == y,
+        _ => false,
+    }
+}
+
+pub fn is_network(path: &Path) -> bool {
+    is_unc(path) || on_network_drive(path)
+}
+
+#[cfg(target_os = "linux")]
+fn on_network_drive(path: &Path) -> bool {
+    let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") else {
+        return false;
+    };
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    mount_type(&mounts, &path).is_some_and(|fs| NETWORK_FILE_SYSTEMS.contains(&fs))
+}
+
+// File system type of the deepest mount point holding `path`
+#[cfg(target_os = "linux")]
+fn mount_type<'a>(mounts: &'a str, path: &Path) -> Option<&'a str> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (_, point, fs) = (fields.next()?, fields.next()?, fields.next()?);
+            // Spaces in mount points are escaped as \040
+            let point = point.replace("\\040", " ");
+            path.starts_with(&point).then_some((point.len(), fs))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, fs)| fs)
+}
+
+// Mapped network drives (Z:\...)
+#[cfg(target_os = "windows")]
+fn on_network_drive(path: &Path) -> bool {
+    const DRIVE_REMOTE: u32 = 4;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDriveTypeW(root: *const u16) -> u32;
+    }
+
+    let text = normalize(path).to_string_lossy().into_owned();
+    let bytes = text.as_bytes();
+    if bytes.len() < 2 || !bytes[0].is_ascii_alphabetic() || bytes[1] != b':' {
+        return false;
+    }
+    let root: Vec<u16> = format!("{}:\\", bytes[0] as char).encode_utf16().chain([0]).collect();
+    // SAFETY: `root` is a NUL-terminated UTF-16 string that outlives the call
+    unsafe { GetDriveTypeW(root.as_ptr()) == DRIVE_REMOTE }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn on_network_drive(_path: &Path) -> bool {
+    false
+}
+
+// --- Expansion of ~ and environment variables ---
+
+fn home_dir() -> Option<String> {
+    std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()
+        .filter(|home| !home.is_empty())
+}
+
+// "~/todo.md", "$HOME/todo.md", "${HOME}/todo.md" and "%APPDATA%\notes.txt";
+// unknown variables are kept as typed
+pub fn expand(input: &str) -> PathBuf {
+    expand_with(input, |name| std::env::var(name).ok(), home_dir())
+}
+
+fn expand_with(input: &str, var: impl Fn(&str) -> Option<String>, home: Option<String>) -> PathBuf {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    if let (Some(home), Some(after)) = (&home, input.strip_prefix('~')) {
+        if after.is_empty() || after.starts_with(['/', '\\']) {
+            out.push_str(home);
+            rest = after;
+        }
+    }
+    while let Some(i) = rest.find(['$', '%']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        let (name, len) = if let Some(braced) = tail.strip_prefix("${") {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 3),
+                None => ("", 1),
+            }
+        } else if let Some(percent) = tail.strip_prefix('%') {
+            match percent.find('%') {
+                Some(end) => (&percent[..end], end + 2),
+                None => ("", 1),
+            }
+        } else {
+            let end = tail[1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .map_or(tail.len() - 1, |e| e);
+            (&tail[1..1 + end], end + 1)
+        };
+        match var(name).filter(|_| !name.is_empty()) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&tail[..len]),
+        }
+        rest = &tail[len..];
+    }
+    out.push_str(rest);
+    PathBuf::from(out)
+}
+
+// --- Safe save ---
+
+fn temp_sibling(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!(".{name}.notepad-tmp"))
+}
+
+// Writes next to the file then renames over it, so a dropped connection never
+// leaves a truncated file; the permissions of the original are kept
+pub fn replace_via_temp(path: &Path, write: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    let target = write_target(path);
+    let temp = temp_sibling(&target);
+    let result = write(&temp)
+        .and_then(|_| match std::fs::metadata(&target) {
+            Ok(meta) => std::fs::set_permissions(&temp, meta.permissions()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        })
+        .and_then(|_| std::fs::rename(&temp, &target));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbatim_prefixes_are_removed() {
+        assert_eq!(normalize(Path::new(r"\\?\UNC\srv\docs\a.txt")), PathBuf::from(r"\\srv\docs\a.txt"));
+        assert_eq!(normalize(Path::new(r"\\?\C:\notes\a.txt")), PathBuf::from(r"C:\notes\a.txt"));
+        assert_eq!(normalize(Path::new("/home/a.txt")), PathBuf::from("/home/a.txt"));
+    }
+
+    #[test]
+    fn unc_paths_are_detected() {
+        assert!(is_unc(Path::new(r"\\srv\docs\a.txt")));
+        assert!(is_unc(Path::new("//srv/docs/a.txt")));
+        assert!(is_unc(Path::new(r"\\?\UNC\srv\docs\a.txt")));
+        assert!(!is_unc(Path::new(r"\\?\C:\a.txt")));
+        assert!(!is_unc(Path::new(r"\\.\pipe\x")));
+        assert!(!is_unc(Path::new("/srv/docs/a.txt")));
+        assert!(is_network(Path::new(r"\\srv\docs\a.txt")));
+    }
+
+    #[test]
+    fn home_and_variables_are_expanded() {
+        let var = |name: &str| match name {
+            "HOME" => Some("/home/léa".to_string()),
+            "APPDATA" => Some(r"C:\Users\léa\AppData\Roaming".to_string()),
+            _ => None,
+        };
+        let home = Some("/home/léa".to_string());
+        let expand = |input: &str| expand_with(input, var, home.clone());
+        assert_eq!(expand("~/todo.md"), PathBuf::from("/home/léa/todo.md"));
+        assert_eq!(expand("$HOME/todo.md"), PathBuf::from("/home/léa/todo.md"));
+        assert_eq!(expand("${HOME}/a/${HOME}"), PathBuf::from("/home/léa/a//home/léa"));
+        assert_eq!(
+            expand(r"%APPDATA%\notes.txt"),
+            PathBuf::from(r"C:\Users\léa\AppData\Roaming\notes.txt")
+        );
+        assert_eq!(expand("~léa/a.txt"), PathBuf::from("~léa/a.txt"));
+        assert_eq!(expand("$INCONNU/100%/a$"), PathBuf::from("$INCONNU/100%/a$"));
+        assert_eq!(expand("${HOME"), PathBuf::from("${HOME"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn deepest_mount_point_wins() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\nsrv:/export /mnt/nas nfs4 rw 0 0\n//srv/My\\040Docs /mnt/my\\040docs cifs rw 0 0\n";
+        assert_eq!(mount_type(mounts, Path::new("/mnt/nas/a.txt")), Some("nfs4"));
+        assert_eq!(mount_type(mounts, Path::new("/mnt/my docs/a.txt")), Some("cifs"));
+        assert_eq!(mount_type(mounts, Path::new("/mnt/nasty.txt")), Some("ext4"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn safe_save_replaces_the_link_target() {
+        let dir = std::env::temp_dir().join(format!("notepad_paths_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let real = dir.join("real.txt");
+        let link = dir.join("link.txt");
+        std::fs::write(&real, "avant").unwrap();
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        assert_eq!(symlink_target(&link), Some(std::fs::canonicalize(&real).unwrap()));
+        assert_eq!(symlink_target(&real), None);
+        replace_via_temp(&link, |temp| std::fs::write(temp, "après")).unwrap();
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(&real).unwrap(), "après");
+        assert!(replace_via_temp(&link, |_| Err(io::Error::other("réseau coupé"))).is_err());
+        assert_eq!(std::fs::read_to_string(&real).unwrap(), "après");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        assert!(same_file(&link, &real));
+        assert!(same_file(&dir.join(".").join("real.txt"), &real));
+        assert!(!same_file(&dir.join("autre.txt"), &real));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,27 +1,177 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
+use crate::abbreviations::Abbreviation;
+use crate::app::DEFAULT_INDENT_SIZE;
+use crate::datetime;
+use crate::http::ProxySettings;
+use crate::paths;
 use crate::{DEFAULT_FONT_SIZE, DEFAULT_WINDOW_HEIGHT, DEFAULT_WINDOW_WIDTH};
 
-fn dir() -> PathBuf {
+pub fn dir() -> PathBuf {
     std::env::current_exe()
         .ok()
         .and_then(|p| p.parent().map(|d| d.to_path_buf()))
         .unwrap_or_else(|| PathBuf::from("."))
 }
 
+// A missing file gives the defaults; an unreadable or invalid one is logged
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            if e.kind() != io::ErrorKind::NotFound {
+                tracing::warn!("lecture de {} impossible : {e}", path.display());
+            }
+            return T::default();
+        }
+    };
+    serde_json::from_str(&text).unwrap_or_else(|e| {
+        tracing::warn!("{} ignoré, contenu invalide : {e}", path.display());
+        T::default()
+    })
+}
+
+// Written to a temporary file flushed to disk then renamed over the old one,
+// so a crash mid-write leaves the previous file instead of a truncated one
+pub fn save_json<T: Serialize>(path: &Path, value: &T) {
+    let result = serde_json::to_string_pretty(value)
+        .map_err(io::Error::other)
+        .and_then(|json| {
+            paths::replace_via_temp(path, |temp| {
+                let mut file = std::fs::File::create(temp)?;
+                file.write_all(json.as_bytes())?;
+                file.sync_all()
+            })
+        });
+    if let Err(e) = result {
+        tracing::warn!("écriture de {} impossible : {e}", path.display());
+    }
+}
+
 // --- User preferences ---
 
-#[derive(Serialize, Deserialize)]
+// What Escape does once no menu or dialog is left to close
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EscapeOrder {
+    // Side panels and the find / go to bars, the selection is kept
+    #[default]
+    PanelsOnly,
+    // The selection is dropped before anything else is closed
+    SelectionFirst,
+    // The selection is dropped once the panels and bars are closed
+    PanelsFirst,
+}
+
+impl EscapeOrder {
+    pub const ALL: [EscapeOrder; 3] = [Self::PanelsOnly, Self::SelectionFirst, Self::PanelsFirst];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::PanelsOnly => "Panneaux",
+            Self::SelectionFirst => "Sélection d'abord",
+            Self::PanelsFirst => "Panneaux d'abord",
+        }
+    }
+
+    // Precedence shown under the setting
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::PanelsOnly => {
+                "Menus et fenêtres, puis instantanés, barres de recherche et tâches ; la sélection reste"
+            }
+            Self::SelectionFirst => {
+                "Menus et fenêtres, puis la sélection, puis instantanés, barres de recherche et tâches"
+            }
+            Self::PanelsFirst => {
+                "Menus et fenêtres, puis instantanés, barres de recherche et tâches, puis la sélection"
+            }
+        }
+    }
+}
+
+// What pasting a URL in a Markdown document does
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UrlPaste {
+    // Pasted as it is, like any text
+    Off,
+    // Over a selection, makes it a link: [selection](url)
+    #[default]
+    Selection,
+    // The same, and without a selection the page's title is fetched to make
+    // [title](url)
+    Title,
+}
+
+impl UrlPaste {
+    pub const ALL: [UrlPaste; 3] = [Self::Off, Self::Selection, Self::Title];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "Non",
+            Self::Selection => "Sur la sélection",
+            Self::Title => "Et titre de la page",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct UserPreferences {
     pub font_size: f32,
     pub font_family: String,
     pub dark_mode: bool,
     pub word_wrap: bool,
+    // Font ligatures in prose (plain text, Markdown) and in code documents
+    pub prose_ligatures: bool,
+    pub code_ligatures: bool,
     pub window_width: f32,
     pub window_height: f32,
     pub restore_session: bool,
+    pub show_toolbar: bool,
+    pub mru_tab_switching: bool,
+    // The find bar closes as soon as typing resumes in the editor
+    pub auto_hide_find: bool,
+    pub escape_order: EscapeOrder,
+    // A click in the editor closes the find, replace and go to bars
+    pub click_closes_bars: bool,
+    pub remember_cursor_position: bool,
+    pub lock_documents: bool,
+    pub hibernate_tabs: bool,
+    // Folder where [[wiki links]] are resolved; the document's folder when unset
+    pub notes_folder: Option<PathBuf>,
+    // Files on network shares: written through a temporary file, autosaved less often
+    pub network_safe_save: bool,
+    pub network_autosave_minutes: u64,
+    // Saves to a share slower than this many milliseconds are queued in the
+    // background from then on; 0 turns it off
+    pub slow_save_ms: u64,
+    // Modified documents with a file are saved when the window loses focus
+    pub save_on_focus_loss: bool,
+    // Fewer timers and no background indexing, for laptops on battery
+    pub low_power: bool,
+    // Spaces and tabs ending the lines are removed on save, unless EditorConfig says otherwise
+    pub trim_trailing_whitespace: bool,
+    // Indentation of new documents: columns per tab stop, and whether the Tab
+    // key inserts that many spaces instead of a tab
+    pub tab_width: usize,
+    pub insert_spaces: bool,
+    // Percent, 100 for an opaque window
+    pub window_opacity: u8,
+    // Percent on top of the system's display scaling
+    pub ui_scale: u16,
+    // Used by every network feature
+    pub proxy: ProxySettings,
+    pub abbreviations: Vec<Abbreviation>,
+    // Hunspell dictionary of the spell checker ("fr_FR"), off when unset
+    pub spell_dictionary: Option<String>,
+    // Characters last inserted from the character picker, most recent first
+    pub recent_characters: String,
+    // strftime-like format of the F5 timestamp
+    pub date_format: String,
+    pub url_paste: UrlPaste,
 }
 
 impl Default for UserPreferences {
@@ -31,9 +181,36 @@ impl Default for UserPreferences {
             font_family: crate::DEFAULT_FONT_FAMILY.to_string(),
             dark_mode: false,
             word_wrap: true,
+            prose_ligatures: true,
+            code_ligatures: true,
             window_width: DEFAULT_WINDOW_WIDTH,
             window_height: DEFAULT_WINDOW_HEIGHT,
             restore_session: true,
+            show_toolbar: false,
+            mru_tab_switching: false,
+            auto_hide_find: false,
+            escape_order: EscapeOrder::PanelsOnly,
+            click_closes_bars: true,
+            remember_cursor_position: false,
+            lock_documents: false,
+            hibernate_tabs: false,
+            notes_folder: None,
+            network_safe_save: true,
+            network_autosave_minutes: 5,
+            slow_save_ms: 1000,
+            save_on_focus_loss: false,
+            low_power: false,
+            trim_trailing_whitespace: false,
+            tab_width: DEFAULT_INDENT_SIZE,
+            insert_spaces: false,
+            window_opacity: 100,
+            ui_scale: 100,
+            proxy: ProxySettings::default(),
+            abbreviations: Vec::new(),
+            spell_dictionary: None,
+            recent_characters: String::new(),
+            date_format: datetime::DEFAULT_FORMAT.to_string(),
+            url_paste: UrlPaste::Selection,
         }
     }
 }
@@ -44,16 +221,11 @@ impl UserPreferences {
     }
 
     pub fn load() -> Self {
-        std::fs::read_to_string(Self::path())
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
+        load_json(&Self::path())
     }
 
     pub fn save(&self) {
-        if let Ok(json) = serde_json::to_string_pretty(self) {
-            let _ = std::fs::write(Self::path(), json);
-        }
+        save_json(&Self::path(), self);
     }
 }
 
@@ -64,6 +236,13 @@ pub struct SessionTab {
     pub file_path: Option<PathBuf>,
     pub unsaved_content: Option<String>,
     pub is_modified: bool,
+    // View state (column in characters)
+    #[serde(default)]
+    pub cursor_line: usize,
+    #[serde(default)]
+    pub cursor_column: usize,
+    #[serde(default)]
+    pub scroll_offset: f32,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
@@ -78,26 +257,120 @@ impl SessionData {
     }
 
     pub fn load() -> Self {
-        std::fs::read_to_string(Self::path())
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
+        load_json(&Self::path())
     }
 
     pub fn save(&self) {
-        if let Ok(json) = serde_json::to_string_pretty(self) {
-            let _ = std::fs::write(Self::path(), json);
-        }
+        save_json(&Self::path(), self);
     }
 
     pub fn clear() {
-        let _ = std::fs::remove_file(Self::path());
+        match std::fs::remove_file(Self::path()) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                tracing::warn!("suppression de la session impossible : {e}")
+            }
+            _ => {}
+        }
+    }
+}
+
+// --- Named sessions ---
+
+// Tab set saved under a name ("Travail", "Blog") with its window size
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NamedSession {
+    pub name: String,
+    pub session: SessionData,
+    pub window_width: f32,
+    pub window_height: f32,
+}
+
+// Sorted by name; `current` is the one the open tabs belong to
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct NamedSessions {
+    pub entries: Vec<NamedSession>,
+    pub current: Option<String>,
+}
+
+impl NamedSessions {
+    pub fn path() -> PathBuf {
+        dir().join("sessions.json")
+    }
+
+    pub fn load() -> Self {
+        load_json(&Self::path())
+    }
+
+    pub fn save(&self) {
+        save_json(&Self::path(), self);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&NamedSession> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    // Replaces the session of the same name
+    pub fn set(&mut self, session: NamedSession) {
+        self.entries.retain(|e| e.name != session.name);
+        let at = self
+            .entries
+            .partition_point(|e| e.name.to_lowercase() < session.name.to_lowercase());
+        self.entries.insert(at, session);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.entries.retain(|e| e.name != name);
+        if self.current.as_deref() == Some(name) {
+            self.current = None;
+        }
+    }
+}
+
+// --- Recent files ---
+
+// Entries of the "Fichiers récents" menu
+const MAX_RECENT_FILES: usize = 10;
+
+// Files opened or saved, most recent first
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct RecentFiles {
+    pub entries: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    pub fn path() -> PathBuf {
+        dir().join("recent_files.json")
+    }
+
+    pub fn load() -> Self {
+        load_json(&Self::path())
+    }
+
+    pub fn save(&self) {
+        save_json(&Self::path(), self);
+    }
+
+    // Moves the file to the top of the list
+    pub fn push(&mut self, path: &Path) {
+        let path = paths::normalize(path);
+        self.entries.retain(|e| *e != path);
+        self.entries.insert(0, path);
+        self.entries.truncate(MAX_RECENT_FILES);
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        let path = paths::normalize(path);
+        self.entries.retain(|e| *e != path);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::ProxyMode;
+    use crate::language::Language;
     use crate::{DEFAULT_FONT_SIZE, DEFAULT_WINDOW_HEIGHT, DEFAULT_WINDOW_WIDTH};
 
     #[test]
@@ -109,6 +382,28 @@ mod tests {
         assert_eq!(prefs.window_width, DEFAULT_WINDOW_WIDTH);
         assert_eq!(prefs.window_height, DEFAULT_WINDOW_HEIGHT);
         assert!(prefs.restore_session);
+        assert!(!prefs.show_toolbar);
+        assert!(!prefs.mru_tab_switching);
+        assert!(!prefs.auto_hide_find);
+        assert_eq!(prefs.escape_order, EscapeOrder::PanelsOnly);
+        assert!(prefs.click_closes_bars);
+        assert!(!prefs.remember_cursor_position);
+        assert!(!prefs.lock_documents);
+        assert!(!prefs.hibernate_tabs);
+        assert!(prefs.notes_folder.is_none());
+        assert!(prefs.network_safe_save);
+        assert_eq!(prefs.network_autosave_minutes, 5);
+        assert_eq!(prefs.slow_save_ms, 1000);
+        assert!(!prefs.save_on_focus_loss);
+        assert!(!prefs.low_power);
+        assert!(!prefs.trim_trailing_whitespace);
+        assert_eq!(prefs.tab_width, 4);
+        assert!(!prefs.insert_spaces);
+        assert_eq!(prefs.window_opacity, 100);
+        assert_eq!(prefs.ui_scale, 100);
+        assert!(prefs.recent_characters.is_empty());
+        assert_eq!(prefs.date_format, "%H:%M %d/%m/%Y");
+        assert_eq!(prefs.url_paste, UrlPaste::Selection);
     }
 
     #[test]
@@ -118,18 +413,80 @@ mod tests {
             font_family: "Courier New".to_string(),
             dark_mode: true,
             word_wrap: false,
+            prose_ligatures: false,
+            code_ligatures: true,
             window_width: 1024.0,
             window_height: 768.0,
             restore_session: false,
+            show_toolbar: true,
+            mru_tab_switching: true,
+            auto_hide_find: true,
+            escape_order: EscapeOrder::SelectionFirst,
+            click_closes_bars: false,
+            remember_cursor_position: true,
+            lock_documents: true,
+            hibernate_tabs: true,
+            notes_folder: Some(PathBuf::from("/notes")),
+            network_safe_save: false,
+            network_autosave_minutes: 15,
+            slow_save_ms: 0,
+            save_on_focus_loss: true,
+            low_power: true,
+            trim_trailing_whitespace: true,
+            tab_width: 2,
+            insert_spaces: true,
+            window_opacity: 80,
+            ui_scale: 150,
+            proxy: ProxySettings {
+                mode: ProxyMode::Manual,
+                address: "proxy.local:3128".to_string(),
+                user: "léa".to_string(),
+            },
+            abbreviations: vec![Abbreviation {
+                abbreviation: "@@".to_string(),
+                expansion: "lea@example.org".to_string(),
+                language: Some(Language::Markdown),
+            }],
+            spell_dictionary: Some("fr_FR".to_string()),
+            recent_characters: "€→±".to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+            url_paste: UrlPaste::Title,
         };
         let json = serde_json::to_string(&prefs).unwrap();
         let restored: UserPreferences = serde_json::from_str(&json).unwrap();
         assert_eq!(restored.font_size, 18.0);
         assert!(restored.dark_mode);
         assert!(!restored.word_wrap);
+        assert!(!restored.prose_ligatures && restored.code_ligatures);
         assert_eq!(restored.window_width, 1024.0);
         assert_eq!(restored.window_height, 768.0);
         assert!(!restored.restore_session);
+        assert!(restored.show_toolbar);
+        assert!(restored.mru_tab_switching);
+        assert!(restored.auto_hide_find);
+        assert_eq!(restored.escape_order, EscapeOrder::SelectionFirst);
+        assert!(!restored.click_closes_bars);
+        assert!(restored.remember_cursor_position);
+        assert!(restored.lock_documents);
+        assert!(restored.hibernate_tabs);
+        assert_eq!(restored.notes_folder, Some(PathBuf::from("/notes")));
+        assert!(!restored.network_safe_save);
+        assert_eq!(restored.network_autosave_minutes, 15);
+        assert_eq!(restored.slow_save_ms, 0);
+        assert!(restored.save_on_focus_loss);
+        assert!(restored.low_power);
+        assert!(restored.trim_trailing_whitespace);
+        assert_eq!(restored.tab_width, 2);
+        assert!(restored.insert_spaces);
+        assert_eq!(restored.window_opacity, 80);
+        assert_eq!(restored.ui_scale, 150);
+        assert_eq!(restored.proxy.mode, ProxyMode::Manual);
+        assert_eq!(restored.proxy.user, "léa");
+        assert_eq!(restored.abbreviations, prefs.abbreviations);
+        assert_eq!(restored.spell_dictionary.as_deref(), Some("fr_FR"));
+        assert_eq!(restored.recent_characters, "€→±");
+        assert_eq!(restored.date_format, "%Y-%m-%d");
+        assert_eq!(restored.url_paste, UrlPaste::Title);
     }
 
     #[test]
@@ -154,11 +511,17 @@ mod tests {
                     file_path: Some(PathBuf::from("/tmp/test.txt")),
                     unsaved_content: None,
                     is_modified: false,
+                    cursor_line: 12,
+                    cursor_column: 3,
+                    scroll_offset: 5.0,
                 },
                 SessionTab {
                     file_path: None,
                     unsaved_content: Some("hello world".to_string()),
                     is_modified: true,
+                    cursor_line: 0,
+                    cursor_column: 0,
+                    scroll_offset: 0.0,
                 },
             ],
             active_tab: 1,
@@ -172,6 +535,9 @@ mod tests {
         );
         assert!(restored.tabs[0].unsaved_content.is_none());
         assert!(!restored.tabs[0].is_modified);
+        assert_eq!(restored.tabs[0].cursor_line, 12);
+        assert_eq!(restored.tabs[0].cursor_column, 3);
+        assert_eq!(restored.tabs[0].scroll_offset, 5.0);
         assert!(restored.tabs[1].file_path.is_none());
         assert_eq!(
             restored.tabs[1].unsaved_content.as_deref(),
@@ -181,10 +547,75 @@ mod tests {
         assert_eq!(restored.active_tab, 1);
     }
 
+    #[test]
+    fn session_tab_without_view_state() {
+        // Sessions saved before cursor/scroll were stored
+        let json = r#"{"file_path":null,"unsaved_content":"x","is_modified":true}"#;
+        let tab: SessionTab = serde_json::from_str(json).unwrap();
+        assert_eq!(tab.cursor_line, 0);
+        assert_eq!(tab.scroll_offset, 0.0);
+    }
+
     #[test]
     fn session_data_default_empty() {
         let session = SessionData::default();
         assert!(session.tabs.is_empty());
         assert_eq!(session.active_tab, 0);
     }
+
+    #[test]
+    fn json_is_replaced_whole() {
+        let dir = std::env::temp_dir().join(format!("notepad_prefs_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("session.json");
+        std::fs::write(&path, "{\"tabs\": [{\"file_path\": \"/ancien.txt\"}]}").unwrap();
+        let session = SessionData {
+            tabs: Vec::new(),
+            active_tab: 0,
+        };
+        save_json(&path, &session);
+        let saved: SessionData = load_json(&path);
+        assert!(saved.tabs.is_empty());
+        // Only the file itself is left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn named_sessions_are_sorted_and_replaced() {
+        let named = |name: &str, width: f32| NamedSession {
+            name: name.to_string(),
+            session: SessionData::default(),
+            window_width: width,
+            window_height: 600.0,
+        };
+        let mut sessions = NamedSessions::default();
+        sessions.set(named("Travail", 800.0));
+        sessions.set(named("blog", 800.0));
+        sessions.set(named("Travail", 1200.0));
+        let names: Vec<&str> = sessions.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["blog", "Travail"]);
+        assert_eq!(sessions.get("Travail").unwrap().window_width, 1200.0);
+        sessions.current = Some("blog".to_string());
+        sessions.remove("blog");
+        assert_eq!(sessions.current, None);
+        assert!(sessions.get("blog").is_none());
+    }
+
+    #[test]
+    fn recent_files_are_bounded_and_moved_up() {
+        let mut recent = RecentFiles::default();
+        for i in 0..MAX_RECENT_FILES + 2 {
+            recent.push(Path::new(&format!("/notes/{i}.txt")));
+        }
+        assert_eq!(recent.entries.len(), MAX_RECENT_FILES);
+        recent.push(Path::new("/notes/5.txt"));
+        recent.push(Path::new(r"\\?\UNC\srv\docs\a.txt"));
+        recent.push(Path::new(r"\\srv\docs\a.txt"));
+        assert_eq!(recent.entries[0], PathBuf::from(r"\\srv\docs\a.txt"));
+        assert_eq!(recent.entries[1], PathBuf::from("/notes/5.txt"));
+        assert_eq!(recent.entries.len(), MAX_RECENT_FILES);
+        recent.remove(Path::new("/notes/5.txt"));
+        assert!(!recent.entries.contains(&PathBuf::from("/notes/5.txt")));
+    }
 }
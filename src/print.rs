@@ -0,0 +1,505 @@
+use pdf_writer::types::{LineCapStyle, LineJoinStyle};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use std::path::Path;
+
+use crate::ink::Stroke;
+use crate::tabs;
+
+// --- Page layout shared by the print preview (A4 portrait, sizes in points) ---
+
+pub const PAGE_WIDTH_PT: f32 = 595.0;
+pub const PAGE_HEIGHT_PT: f32 = 842.0;
+pub const MARGIN_CHOICES_MM: [f32; 4] = [10.0, 15.0, 20.0, 25.0];
+// Approximate monospace advance and line height, relative to the font size
+const CHAR_WIDTH_RATIO: f32 = 0.6;
+pub const LINE_HEIGHT_RATIO: f32 = 1.2;
+// "Fit to width" never shrinks text below this size
+const MIN_FIT_FONT_SIZE: f32 = 4.0;
+// Annotations print in red, a little thinner than on screen
+const INK_COLOR: [f32; 3] = [0.8, 0.1, 0.1];
+const INK_WIDTH: f32 = 1.2;
+
+pub fn mm_to_pt(mm: f32) -> f32 {
+    mm * 72.0 / 25.4
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageSetup {
+    pub margin_mm: f32,
+    pub font_size: f32,
+    pub header_footer: bool,
+    pub wrap: bool,
+    // Shrinks the font so the longest line fits, for this print only
+    pub fit_to_width: bool,
+    // Columns between tab stops
+    pub tab_width: usize,
+}
+
+impl Default for PageSetup {
+    fn default() -> Self {
+        Self {
+            margin_mm: 20.0,
+            font_size: 10.0,
+            header_footer: true,
+            wrap: true,
+            fit_to_width: false,
+            tab_width: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub header: Option<String>,
+    pub lines: Vec<String>,
+    pub footer: Option<String>,
+    // Handwritten strokes, as [column, row] points from the first line of the page
+    pub ink: Vec<Vec<[f32; 2]>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pagination {
+    // Font size actually used, after "fit to width"
+    pub font_size: f32,
+    pub pages: Vec<Page>,
+}
+
+impl PageSetup {
+    fn printable_width(&self) -> f32 {
+        PAGE_WIDTH_PT - 2.0 * mm_to_pt(self.margin_mm)
+    }
+
+    fn printable_height(&self) -> f32 {
+        PAGE_HEIGHT_PT - 2.0 * mm_to_pt(self.margin_mm)
+    }
+}
+
+// Splits at the last space before `width` when there is one, otherwise mid-word
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.len() <= width {
+        return vec![line.to_string()];
+    }
+    let mut rows = Vec::new();
+    let mut start = 0;
+    while chars.len() - start > width {
+        let end = start + width;
+        let split = chars[start..end]
+            .iter()
+            .rposition(|c| *c == ' ')
+            .map_or(end, |i| start + i + 1);
+        rows.push(chars[start..split].iter().collect::<String>().trim_end().to_string());
+        start = split;
+    }
+    rows.push(chars[start..].iter().collect());
+    rows
+}
+
+pub fn paginate(text: &str, title: &str, setup: &PageSetup) -> Pagination {
+    paginate_annotated(text, title, setup, &[])
+}
+
+// Same layout, with the document's annotations carried over to the pages
+// under the lines they were drawn on
+pub fn paginate_annotated(text: &str, title: &str, setup: &PageSetup, ink: &[Stroke]) -> Pagination {
+    let lines: Vec<String> = text.lines().map(|line| tabs::expand(line, setup.tab_width)).collect();
+    let width_pt = setup.printable_width();
+
+    let mut font_size = setup.font_size;
+    if setup.fit_to_width {
+        let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        if longest > 0 {
+            let fitting = width_pt / (longest as f32 * CHAR_WIDTH_RATIO);
+            font_size = fitting.clamp(MIN_FIT_FONT_SIZE, setup.font_size);
+        }
+    }
+
+    let line_height = font_size * LINE_HEIGHT_RATIO;
+    let columns = ((width_pt / (font_size * CHAR_WIDTH_RATIO)) as usize).max(1);
+    // Header and footer each take a line plus a blank separator line
+    let reserved = if setup.header_footer { 4.0 * line_height } else { 0.0 };
+    let rows_per_page = (((setup.printable_height() - reserved) / line_height) as usize).max(1);
+
+    // First row of each line, to place the annotations
+    let mut first_rows = Vec::with_capacity(lines.len());
+    let mut rows: Vec<String> = Vec::new();
+    for line in &lines {
+        first_rows.push(rows.len());
+        if setup.wrap {
+            rows.extend(wrap_line(line, columns));
+        } else {
+            // Unwrapped lines are clipped at the right margin
+            rows.push(line.chars().take(columns).collect());
+        }
+    }
+
+    let mut chunks: Vec<Vec<String>> = rows.chunks(rows_per_page).map(<[String]>::to_vec).collect();
+    if chunks.is_empty() {
+        chunks.push(Vec::new());
+    }
+    let total = chunks.len();
+    let mut pages: Vec<Page> = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, lines)| Page {
+            header: setup.header_footer.then(|| title.to_string()),
+            lines,
+            footer: setup
+                .header_footer
+                .then(|| format!("Page {} / {}", i + 1, total)),
+            ink: Vec::new(),
+        })
+        .collect();
+
+    // Lines are placed on their first row; a stroke crossing a page break is
+    // cut in two
+    let row_of = |line: f32| {
+        let index = line.max(0.0) as usize;
+        let start = first_rows.get(index).copied().unwrap_or_else(|| rows.len() + index - lines.len());
+        start as f32 + line.max(0.0).fract()
+    };
+    for stroke in ink {
+        let mut current: Option<(usize, Vec<[f32; 2]>)> = None;
+        for &[column, line] in &stroke.points {
+            let row = row_of(line);
+            let page = (row as usize / rows_per_page).min(total - 1);
+            let point = [column, row - (page * rows_per_page) as f32];
+            match &mut current {
+                Some((p, points)) if *p == page => points.push(point),
+                _ => {
+                    if let Some((p, points)) = current.take() {
+                        pages[p].ink.push(points);
+                    }
+                    current = Some((page, vec![point]));
+                }
+            }
+        }
+        if let Some((p, points)) = current {
+            pages[p].ink.push(points);
+        }
+    }
+    Pagination { font_size, pages }
+}
+
+// Strokes of a page, `top` being the top of its first text row
+pub fn draw_ink(content: &mut Content, ink: &[Vec<[f32; 2]>], left: f32, top: f32, size: f32) {
+    if ink.is_empty() {
+        return;
+    }
+    let line_height = size * LINE_HEIGHT_RATIO;
+    let at = |[column, row]: [f32; 2]| (left + column * size * CHAR_WIDTH_RATIO, top - row * line_height);
+    content.save_state();
+    content.set_stroke_rgb(INK_COLOR[0], INK_COLOR[1], INK_COLOR[2]);
+    content.set_line_width(INK_WIDTH);
+    content.set_line_cap(LineCapStyle::RoundCap);
+    content.set_line_join(LineJoinStyle::RoundJoin);
+    for points in ink {
+        let Some(&first) = points.first() else { continue };
+        let (x, y) = at(first);
+        content.move_to(x, y);
+        if points.len() == 1 {
+            content.line_to(x + 0.5, y);
+        }
+        for &point in &points[1..] {
+            let (x, y) = at(point);
+            content.line_to(x, y);
+        }
+        content.stroke();
+    }
+    content.restore_state();
+}
+
+// --- Output to the printer ---
+
+// Standard PDF fonts use WinAnsi; other characters print as '?'
+pub fn win_ansi(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut buf = [0; 4];
+    for c in text.chars() {
+        let (encoded, _, unmappable) = encoding_rs::WINDOWS_1252.encode(c.encode_utf8(&mut buf));
+        if unmappable {
+            bytes.push(b'?');
+        } else {
+            bytes.extend_from_slice(&encoded);
+        }
+    }
+    bytes
+}
+
+// Pages laid out by `paginate`, as the preview shows them. Each document
+// follows the previous one from a new page, with its own font size.
+pub fn pdf(documents: &[Pagination], setup: &PageSetup) -> Vec<u8> {
+    let catalog_id = Ref::new(1);
+    let tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let first = 4;
+    let pages: Vec<(&Page, f32)> = documents
+        .iter()
+        .flat_map(|d| d.pages.iter().map(|page| (page, d.font_size)))
+        .collect();
+    let page_ids: Vec<Ref> = (0..pages.len())
+        .map(|i| Ref::new(first + 2 * i as i32))
+        .collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(tree_id);
+    pdf.pages(tree_id)
+        .kids(page_ids.iter().copied())
+        .count(page_ids.len() as i32);
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Courier"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    let margin = mm_to_pt(setup.margin_mm);
+    for ((page, size), id) in pages.into_iter().zip(&page_ids) {
+        let line_height = size * LINE_HEIGHT_RATIO;
+        let top = PAGE_HEIGHT_PT - margin - size;
+        let content_id = Ref::new(id.get() + 1);
+        let mut content = Content::new();
+        content.begin_text();
+        content.set_font(Name(b"F1"), size);
+        content.next_line(margin, top);
+        if let Some(header) = &page.header {
+            content.show(Str(&win_ansi(header)));
+            content.next_line(0.0, -2.0 * line_height);
+        }
+        for line in &page.lines {
+            content.show(Str(&win_ansi(line)));
+            content.next_line(0.0, -line_height);
+        }
+        content.end_text();
+        let body_top = top + size - if page.header.is_some() { 2.0 * line_height } else { 0.0 };
+        draw_ink(&mut content, &page.ink, margin, body_top, size);
+        if let Some(footer) = &page.footer {
+            let width = footer.chars().count() as f32 * size * CHAR_WIDTH_RATIO;
+            content.begin_text();
+            content.set_font(Name(b"F1"), size);
+            content.next_line((PAGE_WIDTH_PT - width) / 2.0, margin);
+            content.show(Str(&win_ansi(footer)));
+            content.end_text();
+        }
+
+        let mut pdf_page = pdf.page(*id);
+        pdf_page
+            .parent(tree_id)
+            .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH_PT, PAGE_HEIGHT_PT))
+            .contents(content_id);
+        pdf_page.resources().fonts().pair(Name(b"F1"), font_id);
+        pdf_page.finish();
+        pdf.stream(content_id, &content.finish());
+    }
+    pdf.finish()
+}
+
+// Blocking: lays the pages out as a PDF and hands it to the system spooler
+// as a single job
+pub fn print(documents: &[Pagination], setup: &PageSetup, title: &str) -> Result<(), String> {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let file = std::env::temp_dir().join(format!("notepad-impression-{stamp}.pdf"));
+    std::fs::write(&file, pdf(documents, setup)).map_err(|e| format!("{} : {e}", file.display()))?;
+    let result = send_to_printer(&file, title);
+    // The Windows handler reads the file after the call returns, so it stays
+    // in the temporary folder there
+    if cfg!(not(target_os = "windows")) || result.is_err() {
+        let _ = std::fs::remove_file(&file);
+    }
+    result
+}
+
+// CUPS copies the file into its queue before `lp` returns
+#[cfg(not(target_os = "windows"))]
+fn send_to_printer(file: &Path, title: &str) -> Result<(), String> {
+    let output = std::process::Command::new("lp")
+        .arg("-t")
+        .arg(title)
+        .arg("--")
+        .arg(file)
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => "commande lp introuvable, CUPS est-il installé ?".to_string(),
+            _ => format!("impossible de lancer lp : {e}"),
+        })?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Err(if message.is_empty() {
+        format!("lp a échoué ({})", output.status)
+    } else {
+        message
+    })
+}
+
+// The "print" verb of the application registered for PDF files sends it to
+// the default printer
+#[cfg(target_os = "windows")]
+fn send_to_printer(file: &Path, _title: &str) -> Result<(), String> {
+    const SW_HIDE: i32 = 0;
+    const SE_ERR_NOASSOC: isize = 31;
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn ShellExecuteW(
+            hwnd: *mut std::ffi::c_void,
+            operation: *const u16,
+            file: *const u16,
+            parameters: *const u16,
+            directory: *const u16,
+            show: i32,
+        ) -> isize;
+    }
+
+    let wide = |text: &str| -> Vec<u16> { text.encode_utf16().chain([0]).collect() };
+    let operation = wide("print");
+    let file = wide(&file.to_string_lossy());
+    // SAFETY: both strings are NUL-terminated UTF-16 that outlive the call,
+    // the other pointers may be null
+    let code = unsafe {
+        ShellExecuteW(
+            std::ptr::null_mut(),
+            operation.as_ptr(),
+            file.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            SW_HIDE,
+        )
+    };
+    match code {
+        c if c > 32 => Ok(()),
+        SE_ERR_NOASSOC => Err("aucune application ne sait imprimer les PDF".to_string()),
+        c => Err(format!("envoi à l'imprimante impossible (code {c})")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> PageSetup {
+        PageSetup::default()
+    }
+
+    #[test]
+    fn empty_document_has_one_page() {
+        let result = paginate("", "vide.txt", &setup());
+        assert_eq!(result.pages.len(), 1);
+        assert_eq!(result.pages[0].footer.as_deref(), Some("Page 1 / 1"));
+        assert_eq!(result.pages[0].header.as_deref(), Some("vide.txt"));
+    }
+
+    #[test]
+    fn long_documents_span_pages() {
+        let text = "ligne\n".repeat(200);
+        let result = paginate(&text, "doc", &setup());
+        let total: usize = result.pages.iter().map(|p| p.lines.len()).sum();
+        assert_eq!(total, 200);
+        assert!(result.pages.len() > 1);
+        let last = result.pages.last().unwrap();
+        assert_eq!(
+            last.footer.clone().unwrap(),
+            format!("Page {0} / {0}", result.pages.len())
+        );
+    }
+
+    #[test]
+    fn larger_margins_mean_more_pages() {
+        let text = "ligne\n".repeat(500);
+        let narrow = paginate(&text, "doc", &PageSetup { margin_mm: 10.0, ..setup() });
+        let wide = paginate(&text, "doc", &PageSetup { margin_mm: 25.0, ..setup() });
+        assert!(wide.pages.len() > narrow.pages.len());
+    }
+
+    #[test]
+    fn header_footer_take_room() {
+        let text = "ligne\n".repeat(60);
+        let with = paginate(&text, "doc", &setup());
+        let without = paginate(&text, "doc", &PageSetup { header_footer: false, ..setup() });
+        assert!(with.pages[0].lines.len() < without.pages[0].lines.len());
+        assert!(without.pages[0].header.is_none());
+    }
+
+    #[test]
+    fn wrap_breaks_at_spaces() {
+        assert_eq!(wrap_line("aaa bbb ccc", 8), vec!["aaa bbb", "ccc"]);
+        assert_eq!(wrap_line("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn without_wrap_lines_are_clipped() {
+        let line = "x".repeat(500);
+        let result = paginate(&line, "doc", &PageSetup { wrap: false, ..setup() });
+        assert_eq!(result.pages[0].lines.len(), 1);
+        assert!(result.pages[0].lines[0].len() < 500);
+    }
+
+    #[test]
+    fn fit_to_width_shrinks_font() {
+        let line = "x".repeat(200);
+        let result = paginate(&line, "doc", &PageSetup { fit_to_width: true, ..setup() });
+        assert!(result.font_size < setup().font_size);
+        assert_eq!(result.pages[0].lines, vec![line]);
+        // Short lines keep the chosen size
+        let short = paginate("court", "doc", &PageSetup { fit_to_width: true, ..setup() });
+        assert_eq!(short.font_size, setup().font_size);
+    }
+
+    #[test]
+    fn tabs_expand_to_stops() {
+        let result = paginate("a\tb", "doc", &setup());
+        assert_eq!(result.pages[0].lines, vec!["a   b"]);
+        let result = paginate("a\tb", "doc", &PageSetup { tab_width: 8, ..setup() });
+        assert_eq!(result.pages[0].lines, vec!["a       b"]);
+    }
+
+    #[test]
+    fn pdf_has_a_page_per_page() {
+        let text = "ligne é\n".repeat(200);
+        let pagination = paginate(&text, "doc", &setup());
+        let bytes = pdf(std::slice::from_ref(&pagination), &setup());
+        assert!(bytes.starts_with(b"%PDF-"));
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains(&format!("/Count {}", pagination.pages.len())));
+        assert!(text.contains(&format!("Page {0} / {0}", pagination.pages.len())));
+        assert_eq!(win_ansi("é→"), vec![0xE9, b'?']);
+    }
+
+    #[test]
+    fn documents_start_on_their_own_pages() {
+        let first = paginate("court", "a.txt", &setup());
+        let second = paginate(&"ligne\n".repeat(200), "b.txt", &setup());
+        let bytes = pdf(&[first, second.clone()], &setup());
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains(&format!("/Count {}", 1 + second.pages.len())));
+        assert!(text.contains("(a.txt)") && text.contains("(b.txt)"));
+        assert!(text.contains("Page 1 / 1"));
+    }
+
+    #[test]
+    fn annotations_follow_their_lines_across_pages() {
+        let text = "ligne\n".repeat(200);
+        let rows = paginate(&text, "doc", &setup()).pages[0].lines.len();
+        let ink = [
+            Stroke { points: vec![[1.0, 2.5], [3.0, 2.5]] },
+            // Drawn over the page break
+            Stroke { points: vec![[0.0, rows as f32 - 0.5], [0.0, rows as f32 + 1.0]] },
+        ];
+        let pagination = paginate_annotated(&text, "doc", &setup(), &ink);
+        assert_eq!(pagination.pages[0].ink, vec![vec![[1.0, 2.5], [3.0, 2.5]], vec![[0.0, rows as f32 - 0.5]]]);
+        assert_eq!(pagination.pages[1].ink, vec![vec![[0.0, 1.0]]]);
+        let bytes = pdf(std::slice::from_ref(&pagination), &setup());
+        assert!(String::from_utf8_lossy(&bytes).contains(" RG"));
+    }
+
+    #[test]
+    fn annotations_move_down_with_wrapped_lines() {
+        let setup = PageSetup { wrap: true, ..setup() };
+        let text = format!("{}\ncourt", "mot ".repeat(100));
+        let ink = [Stroke { points: vec![[2.0, 1.0]] }];
+        let pagination = paginate_annotated(&text, "doc", &setup, &ink);
+        let first_line_rows = pagination.pages[0].lines.len() - 1;
+        assert!(first_line_rows > 1);
+        assert_eq!(pagination.pages[0].ink, vec![vec![[2.0, first_line_rows as f32]]]);
+    }
+}
@@ -0,0 +1,143 @@
+use iced::mouse;
+use iced::widget::canvas;
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
+use qrcode::{Color as Module, EcLevel, QrCode};
+
+// --- QR code of the selection, to carry text over to a phone ---
+
+// Blank modules around the code, as the standard asks for readers to find it
+const QUIET_ZONE: usize = 4;
+// Pixels per module of the saved and copied image
+pub const IMAGE_SCALE: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct Qr {
+    // Modules per side, quiet zone excluded
+    pub width: usize,
+    dark: Vec<bool>,
+}
+
+// Medium error correction: still readable from a slightly blurry screen while
+// leaving room for a few hundred characters
+pub fn encode(text: &str) -> Result<Qr, String> {
+    let code = QrCode::with_error_correction_level(text.as_bytes(), EcLevel::M)
+        .map_err(|_| "texte trop long pour un code QR".to_string())?;
+    Ok(Qr {
+        width: code.width(),
+        dark: code.to_colors().into_iter().map(|m| m == Module::Dark).collect(),
+    })
+}
+
+// "http://..." or "https://..." word containing the character `column` of `line`
+pub fn url_at(line: &str, column: usize) -> Option<&str> {
+    let at = line.char_indices().nth(column).map_or(line.len(), |(i, _)| i);
+    let start = line[..at]
+        .rfind(char::is_whitespace)
+        .map_or(0, |i| i + 1);
+    let end = line[start..].find(char::is_whitespace).map_or(line.len(), |i| start + i);
+    let word = line[start..end].trim_end_matches(['.', ',', ';', ')', '>', '"', '\'']);
+    let word = word.trim_start_matches(['(', '<', '"', '\'']);
+    (word.starts_with("http://") || word.starts_with("https://")).then_some(word)
+}
+
+impl Qr {
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+
+    // Side of the image in modules, quiet zone included
+    pub fn side(&self) -> usize {
+        self.width + 2 * QUIET_ZONE
+    }
+
+    // Black on white RGBA pixels, `scale` pixels per module
+    pub fn rgba(&self, scale: usize) -> (usize, Vec<u8>) {
+        let pixels = self.side() * scale;
+        let mut bytes = Vec::with_capacity(pixels * pixels * 4);
+        for py in 0..pixels {
+            for px in 0..pixels {
+                let (x, y) = (px / scale, py / scale);
+                let inside = (QUIET_ZONE..QUIET_ZONE + self.width).contains(&x)
+                    && (QUIET_ZONE..QUIET_ZONE + self.width).contains(&y);
+                let dark = inside && self.is_dark(x - QUIET_ZONE, y - QUIET_ZONE);
+                let value = if dark { 0 } else { 255 };
+                bytes.extend_from_slice(&[value, value, value, 255]);
+            }
+        }
+        (pixels, bytes)
+    }
+
+    pub fn png(&self, scale: usize) -> Result<Vec<u8>, String> {
+        let (pixels, rgba) = self.rgba(scale);
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, pixels as u32, pixels as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(&rgba).map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+        Ok(out)
+    }
+}
+
+// Drawn black on white whatever the theme, so phones can read it
+impl<Message> canvas::Program<Message> for Qr {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::WHITE);
+        let module = bounds.width.min(bounds.height) / self.side() as f32;
+        for y in 0..self.width {
+            for x in 0..self.width {
+                if self.is_dark(x, y) {
+                    let at = Point::new((x + QUIET_ZONE) as f32 * module, (y + QUIET_ZONE) as f32 * module);
+                    frame.fill_rectangle(at, Size::new(module, module), Color::BLACK);
+                }
+            }
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_becomes_a_square_code() {
+        let qr = encode("https://example.org").unwrap();
+        assert_eq!(qr.width, 25);
+        // Finder pattern in the top-left corner
+        assert!(qr.is_dark(0, 0) && qr.is_dark(6, 6) && !qr.is_dark(1, 1));
+        assert!(encode(&"x".repeat(5000)).is_err());
+    }
+
+    #[test]
+    fn image_has_a_white_quiet_zone() {
+        let qr = encode("WIFI:T:WPA;S:maison;P:secret;;").unwrap();
+        let (pixels, rgba) = qr.rgba(2);
+        assert_eq!(pixels, qr.side() * 2);
+        assert_eq!(rgba.len(), pixels * pixels * 4);
+        assert_eq!(&rgba[..4], &[255, 255, 255, 255]);
+        let corner = (QUIET_ZONE * 2 * pixels + QUIET_ZONE * 2) * 4;
+        assert_eq!(&rgba[corner..corner + 4], &[0, 0, 0, 255]);
+        assert!(qr.png(2).unwrap().starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn url_under_the_cursor() {
+        let line = "voir (https://example.org/page). merci";
+        assert_eq!(url_at(line, 10), Some("https://example.org/page"));
+        assert_eq!(url_at(line, 2), None);
+        assert_eq!(url_at("ftp://x", 3), None);
+        assert_eq!(url_at("é https://a.fr", 4), Some("https://a.fr"));
+    }
+}
@@ -0,0 +1,63 @@
+// --- Joining and hard-wrapping lines ---
+
+// Width proposed by the wrap prompt until another one is used
+pub const DEFAULT_WRAP_WIDTH: usize = 80;
+
+// The lines as one, separated by a single space: the indentation of the first
+// line is kept, the blank lines are dropped
+pub fn join(lines: &[&str]) -> String {
+    let indent = lines
+        .first()
+        .map_or("", |line| &line[..line.len() - line.trim_start().len()]);
+    let words: Vec<&str> = lines.iter().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    format!("{indent}{}", words.join(" "))
+}
+
+// Each line broken between words so none is longer than `width` characters;
+// continuation lines take the indentation of the line they come from, and a
+// word longer than the width gets a line of its own
+pub fn hard_wrap(lines: &[&str], width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut out = Vec::new();
+    for line in lines {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let indent_width = indent.chars().count();
+        let mut current = String::new();
+        let mut current_width = 0;
+        for word in line.split_whitespace() {
+            let word_width = word.chars().count();
+            if current_width > 0 && current_width + 1 + word_width > width.saturating_sub(indent_width) {
+                out.push(format!("{indent}{current}"));
+                current.clear();
+                current_width = 0;
+            }
+            if current_width > 0 {
+                current.push(' ');
+                current_width += 1;
+            }
+            current.push_str(word);
+            current_width += word_width;
+        }
+        out.push(if current.is_empty() { String::new() } else { format!("{indent}{current}") });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_join_with_one_space() {
+        assert_eq!(join(&["  un ", "", "\tdeux", "trois  "]), "  un deux trois");
+        assert_eq!(join(&["seule"]), "seule");
+    }
+
+    #[test]
+    fn long_lines_break_between_words() {
+        let wrapped = hard_wrap(&["le chat dort sur le tapis", "", "  très long mot"], 10);
+        assert_eq!(wrapped, ["le chat", "dort sur", "le tapis", "", "  très", "  long mot"]);
+        // Words wider than the column stay whole
+        assert_eq!(hard_wrap(&["anticonstitutionnellement ok"], 8), ["anticonstitutionnellement", "ok"]);
+    }
+}
@@ -0,0 +1,174 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rhai::{Dynamic, Engine, EvalAltResult, Scope};
+
+use crate::preferences;
+
+// --- Rhai scripts transforming the selection or the document ---
+
+// How often a running script is checked for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Written to the scripts folder the first time it is opened
+const EXAMPLES: &[(&str, &str)] = &[
+    (
+        "Majuscules.rhai",
+        "// Met le texte en majuscules\ntext.to_upper()\n",
+    ),
+    (
+        "Trier les lignes.rhai",
+        "// Trie les lignes par ordre alphabétique\n\
+         let lines = text.split(\"\\n\");\n\
+         lines.sort();\n\
+         let result = \"\";\n\
+         for (line, i) in lines {\n    \
+             if i > 0 { result += \"\\n\"; }\n    \
+             result += line;\n\
+         }\n\
+         result\n",
+    ),
+    (
+        "Numéroter les lignes.rhai",
+        "// Ajoute le numéro de chaque ligne devant elle\n\
+         let result = \"\";\n\
+         for (line, i) in text.split(\"\\n\") {\n    \
+             if i > 0 { result += \"\\n\"; }\n    \
+             result += `${i + 1}. ${line}`;\n\
+         }\n\
+         result\n",
+    ),
+];
+
+// Scripts installed next to the executable. Each one receives the selection,
+// or the whole document without one, in `text` and returns the replacement;
+// a script returning nothing gives back `text` as it left it.
+pub fn folder() -> PathBuf {
+    preferences::dir().join("scripts")
+}
+
+// *.rhai files of the folder, by name. A missing folder is created with the
+// examples so there is something to start from.
+pub fn list(folder: &Path) -> Vec<PathBuf> {
+    if !folder.exists() {
+        install_examples(folder);
+    }
+    let mut scripts: Vec<PathBuf> = std::fs::read_dir(folder)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    scripts.retain(|p| p.is_file() && p.extension().is_some_and(|e| e.eq_ignore_ascii_case("rhai")));
+    scripts.sort();
+    scripts
+}
+
+fn install_examples(folder: &Path) {
+    if let Err(e) = std::fs::create_dir_all(folder) {
+        tracing::warn!("Impossible de créer {} : {e}", folder.display());
+        return;
+    }
+    for (name, source) in EXAMPLES {
+        if let Err(e) = std::fs::write(folder.join(name), source) {
+            tracing::warn!("Impossible d'écrire l'exemple {name} : {e}");
+        }
+    }
+}
+
+// Shown in the picker: the file name without .rhai
+pub fn name(path: &Path) -> String {
+    path.file_stem()
+        .map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+// Blocking: runs the script file on `input`. `keep_going` is polled while it
+// runs; false stops the script at its next operation.
+pub fn run(path: &Path, input: &str, keep_going: &dyn Fn() -> bool) -> Result<String, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("{} : {e}", name(path)))?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let (stop, input) = (stop.clone(), input.to_owned());
+        std::thread::spawn(move || eval(&source, input, stop))
+    };
+    while !worker.is_finished() {
+        if !keep_going() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    worker.join().map_err(|_| "le script s'est arrêté brutalement".to_string())?
+}
+
+fn eval(source: &str, input: String, stop: Arc<AtomicBool>) -> Result<String, String> {
+    let mut engine = Engine::new();
+    engine.on_progress(move |_| stop.load(Ordering::Relaxed).then(|| Dynamic::from("interrompu")));
+    let mut scope = Scope::new();
+    scope.push("text", input);
+    let value = engine
+        .eval_with_scope::<Dynamic>(&mut scope, source)
+        .map_err(|e| match *e {
+            EvalAltResult::ErrorTerminated(..) => "script interrompu".to_string(),
+            e => e.to_string(),
+        })?;
+    if value.is_unit() {
+        return scope
+            .get_value::<String>("text")
+            .ok_or_else(|| "la variable text n'est plus du texte".to_string());
+    }
+    value
+        .into_string()
+        .map_err(|kind| format!("le script doit renvoyer du texte, pas {kind}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(name: &str) -> &'static str {
+        EXAMPLES.iter().find(|(n, _)| *n == name).expect("example").1
+    }
+
+    fn eval_source(source: &str, input: &str) -> Result<String, String> {
+        eval(source, input.to_string(), Arc::new(AtomicBool::new(false)))
+    }
+
+    #[test]
+    fn examples_transform_the_text() {
+        assert_eq!(eval_source(example("Majuscules.rhai"), "café"), Ok("CAFÉ".to_string()));
+        assert_eq!(
+            eval_source(example("Trier les lignes.rhai"), "poire\npomme\nabricot"),
+            Ok("abricot\npoire\npomme".to_string())
+        );
+        assert_eq!(
+            eval_source(example("Numéroter les lignes.rhai"), "a\nb"),
+            Ok("1. a\n2. b".to_string())
+        );
+    }
+
+    #[test]
+    fn text_is_kept_when_nothing_is_returned() {
+        assert_eq!(eval_source("text += \"!\";", "fin"), Ok("fin!".to_string()));
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        assert!(eval_source("42", "x").unwrap_err().contains("texte"));
+        assert!(eval_source("let = ;", "x").is_err());
+    }
+
+    #[test]
+    fn stopped_scripts_are_interrupted() {
+        let result = eval("loop {}", String::new(), Arc::new(AtomicBool::new(true)));
+        assert_eq!(result, Err("script interrompu".to_string()));
+    }
+
+    #[test]
+    fn missing_folder_gets_the_examples() {
+        let folder = std::env::temp_dir().join(format!("notepad-scripts-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        let scripts = list(&folder);
+        assert_eq!(scripts.len(), EXAMPLES.len());
+        assert_eq!(name(&scripts[0]), "Majuscules");
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+}
@@ -0,0 +1,229 @@
+use ropey::Rope;
+
+// Documents from this size on get a search index
+pub const SEARCH_INDEX_MIN_BYTES: usize = 4 * 1024 * 1024;
+
+// Target size of an indexed chunk; chunks always hold whole lines
+const CHUNK_BYTES: usize = 64 * 1024;
+
+// Bits of the per-chunk trigram filter
+const FILTER_WORDS: usize = 1024;
+const FILTER_MASK: u32 = (FILTER_WORDS * 64 - 1) as u32;
+
+// Characters that match an ASCII letter in case-insensitive mode
+// (Kelvin sign, long s), which the ASCII-folded trigrams would miss
+const ASCII_FOLDING: [char; 2] = ['\u{212A}', '\u{17F}'];
+
+// A run of whole lines and a Bloom filter of the byte trigrams it contains
+// (ASCII-lowercased), to skip the chunks that cannot hold a match
+#[derive(Debug, Clone)]
+struct Chunk {
+    lines: usize,
+    bytes: usize,
+    filter: Box<[u64; FILTER_WORDS]>,
+    folds_to_ascii: bool,
+}
+
+impl Chunk {
+    fn new(text: &str, lines: usize) -> Self {
+        let mut filter = Box::new([0u64; FILTER_WORDS]);
+        for window in text.as_bytes().windows(3) {
+            for bit in trigram_bits(window) {
+                filter[(bit / 64) as usize] |= 1 << (bit % 64);
+            }
+        }
+        Self {
+            lines,
+            bytes: text.len(),
+            filter,
+            folds_to_ascii: text.contains(ASCII_FOLDING),
+        }
+    }
+
+    fn may_contain(&self, trigrams: &[[u8; 3]]) -> bool {
+        trigrams.iter().all(|trigram| {
+            trigram_bits(trigram)
+                .into_iter()
+                .all(|bit| self.filter[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+        })
+    }
+}
+
+fn trigram_bits(bytes: &[u8]) -> [u32; 2] {
+    let key = u32::from_le_bytes([
+        bytes[0].to_ascii_lowercase(),
+        bytes[1].to_ascii_lowercase(),
+        bytes[2].to_ascii_lowercase(),
+        0,
+    ]);
+    [
+        (key.wrapping_mul(0x9E37_79B1) >> 15) & FILTER_MASK,
+        (key.wrapping_mul(0x85EB_CA77) >> 15) & FILTER_MASK,
+    ]
+}
+
+// Byte range of the document that may hold a match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    chunks: Vec<Chunk>,
+}
+
+impl SearchIndex {
+    pub fn build(rope: &Rope) -> Self {
+        Self {
+            chunks: build_chunks(rope, 0, rope.len_lines()),
+        }
+    }
+
+    // Lines `start..=old_end` were replaced by lines `start..=new_end` of `rope`
+    pub fn update(&mut self, rope: &Rope, start: usize, old_end: usize, new_end: usize) {
+        let mut first_line = 0;
+        let mut first = None;
+        let mut last = self.chunks.len();
+        let mut line = 0;
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            if first.is_none() && start < line + chunk.lines {
+                first = Some(i);
+                first_line = line;
+            }
+            if old_end < line + chunk.lines {
+                last = i;
+                break;
+            }
+            line += chunk.lines;
+        }
+        let (Some(first), true) = (first, last < self.chunks.len()) else {
+            *self = Self::build(rope);
+            return;
+        };
+        let old_lines: usize = self.chunks[first..=last].iter().map(|c| c.lines).sum();
+        let new_lines = (old_lines + new_end + 1).saturating_sub(old_end + 1);
+        let rebuilt = build_chunks(rope, first_line, first_line + new_lines);
+        self.chunks.splice(first..=last, rebuilt);
+        if self.chunks.iter().map(|c| c.lines).sum::<usize>() != rope.len_lines() {
+            *self = Self::build(rope);
+        }
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.chunks.len() * (std::mem::size_of::<Chunk>() + FILTER_WORDS * 8)
+    }
+
+    // Chunks that may contain `needle`, in document order. None when the index
+    // cannot answer: a match could span several lines.
+    pub fn candidates(&self, needle: &str, case_sensitive: bool) -> Option<Vec<Span>> {
+        if needle.contains(['\n', '\r']) {
+            return None;
+        }
+        // Non-ASCII letters fold to other bytes in case-insensitive mode
+        let trigrams: Vec<[u8; 3]> = if case_sensitive || needle.is_ascii() {
+            needle
+                .as_bytes()
+                .windows(3)
+                .map(|w| [w[0], w[1], w[2]])
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut spans: Vec<Span> = Vec::new();
+        let mut offset = 0;
+        for chunk in &self.chunks {
+            let keep = (!case_sensitive && chunk.folds_to_ascii) || chunk.may_contain(&trigrams);
+            if keep {
+                match spans.last_mut() {
+                    Some(span) if span.end == offset => span.end += chunk.bytes,
+                    _ => spans.push(Span {
+                        start: offset,
+                        end: offset + chunk.bytes,
+                    }),
+                }
+            }
+            offset += chunk.bytes;
+        }
+        Some(spans)
+    }
+}
+
+fn build_chunks(rope: &Rope, start: usize, end: usize) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut text = String::new();
+    let mut lines = 0;
+    for index in start..end.min(rope.len_lines()) {
+        for piece in rope.line(index).chunks() {
+            text.push_str(piece);
+        }
+        lines += 1;
+        if text.len() >= CHUNK_BYTES {
+            chunks.push(Chunk::new(&text, lines));
+            text.clear();
+            lines = 0;
+        }
+    }
+    if lines > 0 {
+        chunks.push(Chunk::new(&text, lines));
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(lines: usize) -> String {
+        (0..lines).map(|i| format!("{i:06} INFO request served\n")).collect()
+    }
+
+    #[test]
+    fn candidates_skip_chunks_without_the_needle() {
+        let mut text = log(20_000);
+        text.push_str("000001 ERROR disk full\n");
+        let index = SearchIndex::build(&Rope::from_str(&text));
+        assert!(index.chunks.len() > 5);
+        let spans = index.candidates("ERROR", true).unwrap();
+        assert_eq!(spans.len(), 1);
+        assert!(text[spans[0].start..spans[0].end].contains("ERROR"));
+        let spans = index.candidates("error", false).unwrap();
+        assert!(text[spans[0].start..spans[0].end].contains("ERROR"));
+        assert!(index.candidates("a\nb", true).is_none());
+    }
+
+    #[test]
+    fn short_needles_keep_every_chunk() {
+        let text = log(10_000);
+        let index = SearchIndex::build(&Rope::from_str(&text));
+        let spans = index.candidates("ab", true).unwrap();
+        assert_eq!(spans, vec![Span { start: 0, end: text.len() }]);
+    }
+
+    #[test]
+    fn case_insensitive_keeps_folding_characters() {
+        let text = format!("{}\u{212A}ELVIN\n{}", log(5_000), log(5_000));
+        let index = SearchIndex::build(&Rope::from_str(&text));
+        let spans = index.candidates("kelvin", false).unwrap();
+        assert!(spans.iter().any(|s| text[s.start..s.end].contains('\u{212A}')));
+    }
+
+    #[test]
+    fn update_follows_edits() {
+        let mut rope = Rope::from_str(&log(20_000));
+        let mut index = SearchIndex::build(&rope);
+        // Replace line 10 with two lines, one holding the needle
+        let from = rope.line_to_char(10);
+        let to = rope.line_to_char(11);
+        rope.remove(from..to);
+        rope.insert(from, "first\nneedle here\n");
+        index.update(&rope, 9, 11, 12);
+        let text = rope.to_string();
+        let spans = index.candidates("needle", true).unwrap();
+        assert_eq!(spans.len(), 1);
+        assert!(text[spans[0].start..spans[0].end].contains("needle here"));
+        let total: usize = index.chunks.iter().map(|c| c.bytes).sum();
+        assert_eq!(total, text.len());
+    }
+}
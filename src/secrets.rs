@@ -0,0 +1,72 @@
+use keyring::Entry;
+
+// --- Secrets kept in the OS keychain ---
+
+// Credential Manager on Windows, Secret Service on Linux, Keychain on macOS:
+// passwords and tokens never go through the JSON preferences in clear text.
+// All calls are blocking (the keychain may ask the user to unlock it) and
+// belong in a background job.
+
+// Service name the entries are filed under in the keychain
+const SERVICE: &str = "notepad";
+
+// Name of a secret: the feature that owns it and what it is for, e.g.
+// ("sftp", "léa@nas") or ("paste", "token")
+pub fn name(feature: &str, account: &str) -> String {
+    format!("{feature}:{account}")
+}
+
+fn entry(name: &str) -> Result<Entry, String> {
+    if name.trim().is_empty() {
+        return Err("nom de secret vide".to_string());
+    }
+    Entry::new(SERVICE, name).map_err(describe)
+}
+
+// Never includes the secret itself, so errors can be logged as is
+fn describe(error: keyring::Error) -> String {
+    match error {
+        keyring::Error::NoStorageAccess(e) | keyring::Error::PlatformFailure(e) => {
+            format!("trousseau du système indisponible : {e}")
+        }
+        keyring::Error::NoEntry => "secret introuvable".to_string(),
+        e => format!("trousseau du système : {e}"),
+    }
+}
+
+// None when nothing was stored under that name
+pub fn load(name: &str) -> Result<Option<String>, String> {
+    match entry(name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(describe(e)),
+    }
+}
+
+// Replaces the secret; an empty one removes it
+pub fn store(name: &str, secret: &str) -> Result<(), String> {
+    if secret.is_empty() {
+        return forget(name);
+    }
+    entry(name)?.set_password(secret).map_err(describe)
+}
+
+// Forgetting a secret that was never stored is not an error
+pub fn forget(name: &str) -> Result<(), String> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(describe(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_say_who_owns_the_secret() {
+        assert_eq!(name("sftp", "léa@nas"), "sftp:léa@nas");
+        assert_eq!(load(" ").unwrap_err(), "nom de secret vide");
+        assert_eq!(describe(keyring::Error::NoEntry), "secret introuvable");
+    }
+}
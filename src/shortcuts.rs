@@ -0,0 +1,631 @@
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::preferences;
+
+// --- Action registry ---
+
+// Every keyboard shortcut of the app: the key handlers look bindings up in the
+// keymap and the menus and the F1 cheat sheet display them from it
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    NewTab,
+    Open,
+    Save,
+    SaveAs,
+    CloseTab,
+    Print,
+    NextTab,
+    PreviousTab,
+    Undo,
+    Redo,
+    Cut,
+    Copy,
+    Paste,
+    PastePlain,
+    SelectAll,
+    InsertDateTime,
+    JoinLines,
+    ToggleComment,
+    MoveParagraphUp,
+    MoveParagraphDown,
+    ToggleMacroRecording,
+    PlayMacro,
+    Find,
+    Replace,
+    GoTo,
+    SearchNotes,
+    FindNext,
+    FindPrevious,
+    NavigateBack,
+    NavigateForward,
+    LastEdit,
+    NextEdit,
+    PreviousEdit,
+    ToggleBookmark,
+    NextBookmark,
+    PreviousBookmark,
+    PreviousParagraph,
+    NextParagraph,
+    PreviousHeading,
+    NextHeading,
+    ToggleWordWrap,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    ShowShortcuts,
+    ClosePanel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    File,
+    Edit,
+    Search,
+    View,
+    Help,
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [
+        Category::File,
+        Category::Edit,
+        Category::Search,
+        Category::View,
+        Category::Help,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::File => "Fichier",
+            Category::Edit => "Edition",
+            Category::Search => "Recherche",
+            Category::View => "Affichage",
+            Category::Help => "Aide",
+        }
+    }
+}
+
+// Where the key press is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    // Window events, wherever the focus is
+    Global,
+    // Editor key binding, while the editor has focus
+    Editor,
+    // Built into the text editor widget
+    Native,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyName {
+    Named(Named),
+    // Lowercase
+    Char(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shortcut {
+    pub action: Action,
+    pub label: &'static str,
+    pub category: Category,
+    pub modifiers: Modifiers,
+    pub key: KeyName,
+    pub scope: Scope,
+}
+
+const NONE: Modifiers = Modifiers::empty();
+const CTRL: Modifiers = Modifiers::CTRL;
+const ALT: Modifiers = Modifiers::ALT;
+const SHIFT: Modifiers = Modifiers::SHIFT;
+const CTRL_SHIFT: Modifiers = Modifiers::CTRL.union(Modifiers::SHIFT);
+const ALT_SHIFT: Modifiers = Modifiers::ALT.union(Modifiers::SHIFT);
+
+const fn shortcut(
+    action: Action,
+    label: &'static str,
+    category: Category,
+    modifiers: Modifiers,
+    key: KeyName,
+    scope: Scope,
+) -> Shortcut {
+    Shortcut {
+        action,
+        label,
+        category,
+        modifiers,
+        key,
+        scope,
+    }
+}
+
+use Action as A;
+use Category as C;
+use KeyName::{Char, Named as N};
+use Scope::{Editor, Global, Native};
+
+// Default bindings, before the changes of keybindings.json
+pub const SHORTCUTS: &[Shortcut] = &[
+    shortcut(A::NewTab, "Nouvel onglet", C::File, CTRL, Char('n'), Global),
+    shortcut(A::Open, "Ouvrir", C::File, CTRL, Char('o'), Global),
+    shortcut(A::Save, "Enregistrer", C::File, CTRL, Char('s'), Global),
+    shortcut(A::SaveAs, "Enregistrer sous", C::File, CTRL_SHIFT, Char('s'), Global),
+    shortcut(A::CloseTab, "Fermer l'onglet", C::File, CTRL, Char('w'), Global),
+    shortcut(A::Print, "Imprimer", C::File, CTRL, Char('p'), Global),
+    shortcut(A::NextTab, "Onglet suivant", C::File, CTRL, N(Named::Tab), Global),
+    shortcut(A::PreviousTab, "Onglet précédent", C::File, CTRL_SHIFT, N(Named::Tab), Global),
+    shortcut(A::Undo, "Annuler", C::Edit, CTRL, Char('z'), Global),
+    shortcut(A::Redo, "Rétablir", C::Edit, CTRL, Char('y'), Global),
+    shortcut(A::Cut, "Couper", C::Edit, CTRL, Char('x'), Native),
+    shortcut(A::Copy, "Copier", C::Edit, CTRL, Char('c'), Native),
+    shortcut(A::Paste, "Coller", C::Edit, CTRL, Char('v'), Native),
+    shortcut(A::PastePlain, "Coller sans formatage", C::Edit, CTRL_SHIFT, Char('v'), Editor),
+    shortcut(A::SelectAll, "Tout sélectionner", C::Edit, CTRL, Char('a'), Native),
+    shortcut(A::InsertDateTime, "Insérer la date et l'heure", C::Edit, NONE, N(Named::F5), Global),
+    shortcut(A::JoinLines, "Joindre les lignes", C::Edit, CTRL, Char('j'), Global),
+    shortcut(A::ToggleComment, "Commenter / décommenter", C::Edit, CTRL, Char('/'), Global),
+    shortcut(A::MoveParagraphUp, "Monter le paragraphe", C::Edit, ALT_SHIFT, N(Named::ArrowUp), Editor),
+    shortcut(A::MoveParagraphDown, "Descendre le paragraphe", C::Edit, ALT_SHIFT, N(Named::ArrowDown), Editor),
+    shortcut(A::ToggleMacroRecording, "Démarrer / arrêter l'enregistrement", C::Edit, CTRL_SHIFT, Char('r'), Global),
+    shortcut(A::PlayMacro, "Rejouer la macro", C::Edit, CTRL_SHIFT, Char('p'), Global),
+    shortcut(A::Find, "Rechercher", C::Search, CTRL, Char('f'), Global),
+    shortcut(A::Replace, "Remplacer", C::Search, CTRL, Char('h'), Global),
+    shortcut(A::GoTo, "Atteindre la ligne", C::Search, CTRL, Char('g'), Global),
+    shortcut(A::SearchNotes, "Rechercher dans les notes", C::Search, CTRL_SHIFT, Char('f'), Global),
+    shortcut(A::FindNext, "Occurrence suivante", C::Search, NONE, N(Named::F3), Global),
+    shortcut(A::FindPrevious, "Occurrence précédente", C::Search, SHIFT, N(Named::F3), Global),
+    shortcut(A::NavigateBack, "Position précédente", C::Search, ALT, N(Named::ArrowLeft), Editor),
+    shortcut(A::NavigateForward, "Position suivante", C::Search, ALT, N(Named::ArrowRight), Editor),
+    shortcut(A::LastEdit, "Dernière modification", C::Search, CTRL_SHIFT, N(Named::Backspace), Editor),
+    shortcut(A::NextEdit, "Modification suivante", C::Search, ALT, N(Named::PageDown), Editor),
+    shortcut(A::PreviousEdit, "Modification précédente", C::Search, ALT, N(Named::PageUp), Editor),
+    shortcut(A::ToggleBookmark, "Basculer le signet", C::Search, CTRL, N(Named::F2), Global),
+    shortcut(A::NextBookmark, "Signet suivant", C::Search, NONE, N(Named::F2), Global),
+    shortcut(A::PreviousBookmark, "Signet précédent", C::Search, SHIFT, N(Named::F2), Global),
+    shortcut(A::PreviousParagraph, "Paragraphe précédent", C::Search, CTRL, N(Named::ArrowUp), Editor),
+    shortcut(A::NextParagraph, "Paragraphe suivant", C::Search, CTRL, N(Named::ArrowDown), Editor),
+    shortcut(A::PreviousHeading, "Titre Markdown précédent", C::Search, CTRL_SHIFT, N(Named::ArrowUp), Editor),
+    shortcut(A::NextHeading, "Titre Markdown suivant", C::Search, CTRL_SHIFT, N(Named::ArrowDown), Editor),
+    shortcut(A::ToggleWordWrap, "Retour à la ligne", C::View, ALT, Char('z'), Global),
+    shortcut(A::ZoomIn, "Zoom +", C::View, CTRL, Char('='), Global),
+    shortcut(A::ZoomIn, "Zoom +", C::View, CTRL, Char('+'), Global),
+    shortcut(A::ZoomOut, "Zoom -", C::View, CTRL, Char('-'), Global),
+    shortcut(A::ZoomReset, "Zoom réinitialiser", C::View, CTRL, Char('0'), Global),
+    shortcut(A::ShowShortcuts, "Raccourcis clavier", C::Help, NONE, N(Named::F1), Global),
+    shortcut(A::ClosePanel, "Fermer le panneau ou la fenêtre", C::Help, NONE, N(Named::Escape), Global),
+];
+
+impl Shortcut {
+    pub fn keys(&self) -> String {
+        let mut parts: Vec<&str> = Vec::new();
+        if self.modifiers.control() {
+            parts.push("Ctrl");
+        }
+        if self.modifiers.alt() {
+            parts.push("Alt");
+        }
+        if self.modifiers.shift() {
+            parts.push("Shift");
+        }
+        let key = match self.key {
+            KeyName::Char(c) => c.to_uppercase().to_string(),
+            KeyName::Named(named) => named_label(named).to_string(),
+        };
+        let mut keys = parts.join("+");
+        if !keys.is_empty() {
+            keys.push('+');
+        }
+        keys.push_str(&key);
+        keys
+    }
+
+    fn key_matches(&self, key: &Key) -> bool {
+        match (self.key, key.as_ref()) {
+            (KeyName::Named(a), Key::Named(b)) => a == b,
+            (KeyName::Char(a), Key::Character(b)) => b.to_lowercase().chars().eq([a]),
+            _ => false,
+        }
+    }
+
+    // Symbols may need Shift depending on the keyboard layout, so Shift is
+    // not compared for them
+    fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+        self.key_matches(key) && significant(self.key, self.modifiers) == significant(self.key, modifiers)
+    }
+
+    // Two bindings answering the same key press
+    fn same_keys(&self, other: &Shortcut) -> bool {
+        self.key == other.key
+            && significant(self.key, self.modifiers) == significant(other.key, other.modifiers)
+    }
+}
+
+fn significant(key: KeyName, modifiers: Modifiers) -> Modifiers {
+    let modifiers = modifiers & (CTRL | ALT | SHIFT);
+    match key {
+        KeyName::Char(c) if !c.is_alphabetic() => modifiers - SHIFT,
+        _ => modifiers,
+    }
+}
+
+// Keys that can be bound by name, with the label shown and written in
+// keybindings.json
+const NAMED_KEYS: &[(Named, &str)] = &[
+    (Named::Tab, "Tab"),
+    (Named::Escape, "Échap"),
+    (Named::Enter, "Entrée"),
+    (Named::Space, "Espace"),
+    (Named::Backspace, "Retour arrière"),
+    (Named::Delete, "Suppr"),
+    (Named::Insert, "Inser"),
+    (Named::Home, "Début"),
+    (Named::End, "Fin"),
+    (Named::PageUp, "Page préc."),
+    (Named::PageDown, "Page suiv."),
+    (Named::ArrowLeft, "←"),
+    (Named::ArrowRight, "→"),
+    (Named::ArrowUp, "↑"),
+    (Named::ArrowDown, "↓"),
+    (Named::F1, "F1"),
+    (Named::F2, "F2"),
+    (Named::F3, "F3"),
+    (Named::F4, "F4"),
+    (Named::F5, "F5"),
+    (Named::F6, "F6"),
+    (Named::F7, "F7"),
+    (Named::F8, "F8"),
+    (Named::F9, "F9"),
+    (Named::F10, "F10"),
+    (Named::F11, "F11"),
+    (Named::F12, "F12"),
+];
+
+fn named_label(named: Named) -> &'static str {
+    NAMED_KEYS
+        .iter()
+        .find(|(n, _)| *n == named)
+        .map_or("?", |(_, label)| label)
+}
+
+// Key and modifiers of a key press, None for a lone modifier or a key that
+// cannot be bound
+pub fn chord(key: &Key, modifiers: Modifiers) -> Option<(Modifiers, KeyName)> {
+    let key = match key.as_ref() {
+        Key::Named(named) => NAMED_KEYS.iter().find(|(n, _)| *n == named)?.0.into(),
+        Key::Character(c) => {
+            let mut chars = c.chars().flat_map(char::to_lowercase);
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyName::Char(c),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some((modifiers & (CTRL | ALT | SHIFT), key))
+}
+
+impl From<Named> for KeyName {
+    fn from(named: Named) -> Self {
+        KeyName::Named(named)
+    }
+}
+
+// "Ctrl+Shift+S", "Alt+←", "Ctrl++", as displayed by `Shortcut::keys`
+fn parse_keys(keys: &str) -> Option<(Modifiers, KeyName)> {
+    let keys = keys.trim();
+    let (modifiers, key) = if keys == "+" {
+        ("", "+")
+    } else if let Some(modifiers) = keys.strip_suffix("++") {
+        (modifiers, "+")
+    } else {
+        keys.rsplit_once('+').unwrap_or(("", keys))
+    };
+    let mut parsed = NONE;
+    for modifier in modifiers.split('+').map(str::trim).filter(|m| !m.is_empty()) {
+        parsed |= match modifier.to_lowercase().as_str() {
+            "ctrl" => CTRL,
+            "alt" => ALT,
+            "shift" => SHIFT,
+            _ => return None,
+        };
+    }
+    let key = key.trim();
+    let named = NAMED_KEYS.iter().find(|(_, label)| label.eq_ignore_ascii_case(key));
+    let key = match named {
+        Some((named, _)) => KeyName::Named(*named),
+        None => {
+            let mut chars = key.chars().flat_map(char::to_lowercase);
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyName::Char(c),
+                _ => return None,
+            }
+        }
+    };
+    Some((parsed, key))
+}
+
+// --- Keymap ---
+
+// Actions typed into the text editor, whose keys are fixed by the widget
+pub fn is_native(action: Action) -> bool {
+    SHORTCUTS.iter().any(|s| s.action == action && s.scope == Native)
+}
+
+// Actions in the order of the default table
+pub fn actions() -> Vec<Action> {
+    let mut actions: Vec<Action> = Vec::new();
+    for s in SHORTCUTS {
+        if !actions.contains(&s.action) {
+            actions.push(s.action);
+        }
+    }
+    actions
+}
+
+fn defaults(action: Action) -> impl Iterator<Item = &'static Shortcut> {
+    SHORTCUTS.iter().filter(move |s| s.action == action)
+}
+
+pub fn label(action: Action) -> &'static str {
+    defaults(action).next().map_or("", |s| s.label)
+}
+
+pub fn category(action: Action) -> Category {
+    defaults(action).next().map_or(Category::Help, |s| s.category)
+}
+
+// Changes to the default bindings, by action: an empty list unbinds it
+type Overrides = BTreeMap<Action, Vec<String>>;
+
+// Bindings in use: the defaults with the changes of keybindings.json
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<Shortcut>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: SHORTCUTS.to_vec(),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn path() -> PathBuf {
+        preferences::dir().join("keybindings.json")
+    }
+
+    pub fn load() -> Self {
+        Self::from_overrides(&preferences::load_json(&Self::path()))
+    }
+
+    pub fn save(&self) {
+        preferences::save_json(&Self::path(), &self.overrides());
+    }
+
+    fn from_overrides(overrides: &Overrides) -> Self {
+        let mut keymap = Self::default();
+        for (&action, keys) in overrides {
+            if is_native(action) {
+                tracing::warn!("keybindings.json : {action:?} ne peut pas être modifié");
+                continue;
+            }
+            let chords = keys.iter().filter_map(|k| {
+                let chord = parse_keys(k);
+                if chord.is_none() {
+                    tracing::warn!("keybindings.json : touches inconnues « {k} » pour {action:?}");
+                }
+                chord
+            });
+            keymap.replace(action, chords.collect());
+        }
+        keymap
+    }
+
+    fn overrides(&self) -> Overrides {
+        actions()
+            .into_iter()
+            .filter(|&action| !self.is_default(action))
+            .map(|action| (action, self.bindings(action).map(Shortcut::keys).collect()))
+            .collect()
+    }
+
+    fn bindings(&self, action: Action) -> impl Iterator<Item = &Shortcut> {
+        self.bindings.iter().filter(move |s| s.action == action)
+    }
+
+    fn replace(&mut self, action: Action, chords: Vec<(Modifiers, KeyName)>) {
+        let Some(default) = defaults(action).next().copied() else {
+            return;
+        };
+        let at = self
+            .bindings
+            .iter()
+            .position(|s| s.action == action)
+            .unwrap_or(self.bindings.len());
+        self.bindings.retain(|s| s.action != action);
+        let at = at.min(self.bindings.len());
+        let new = chords.into_iter().map(|(modifiers, key)| Shortcut {
+            modifiers,
+            key,
+            ..default
+        });
+        self.bindings.splice(at..at, new);
+    }
+
+    // The action answers this key press only
+    pub fn rebind(&mut self, action: Action, modifiers: Modifiers, key: KeyName) {
+        self.replace(action, vec![(modifiers, key)]);
+    }
+
+    pub fn reset(&mut self, action: Action) {
+        self.replace(action, defaults(action).map(|s| (s.modifiers, s.key)).collect());
+    }
+
+    pub fn is_default(&self, action: Action) -> bool {
+        self.bindings(action)
+            .map(|s| (s.modifiers, s.key))
+            .eq(defaults(action).map(|s| (s.modifiers, s.key)))
+    }
+
+    // Other actions sharing a key press with `action`: the scopes overlap
+    // while the editor has focus, so they all count
+    pub fn conflicts(&self, action: Action) -> Vec<Action> {
+        let mut conflicts: Vec<Action> = Vec::new();
+        for binding in self.bindings(action) {
+            for other in self.bindings.iter().filter(|o| o.action != action && o.same_keys(binding)) {
+                if !conflicts.contains(&other.action) {
+                    conflicts.push(other.action);
+                }
+            }
+        }
+        conflicts
+    }
+
+    // Action bound to a key press in `scope`. Function keys and Escape bound
+    // without modifiers also answer while modifiers are held (Escape closes the
+    // Ctrl+Tab switcher with Ctrl still down), unless a modified binding matches.
+    pub fn lookup(&self, scope: Scope, key: &Key, modifiers: Modifiers) -> Option<Action> {
+        let in_scope = || self.bindings.iter().filter(move |s| s.scope == scope);
+        in_scope()
+            .find(|s| s.matches(key, modifiers))
+            .or_else(|| {
+                in_scope().find(|s| {
+                    s.modifiers.is_empty() && matches!(s.key, KeyName::Named(_)) && s.key_matches(key)
+                })
+            })
+            .map(|s| s.action)
+    }
+
+    // Keys shown next to a menu item (the first binding of the action)
+    pub fn keys(&self, action: Action) -> String {
+        self.bindings(action).next().map(Shortcut::keys).unwrap_or_default()
+    }
+
+    // All the bindings of an action, for the settings and the cheat sheet
+    pub fn all_keys(&self, action: Action) -> String {
+        self.bindings(action).map(Shortcut::keys).collect::<Vec<_>>().join(" / ")
+    }
+
+    // Cheat sheet entries matching `query` (label or keys), by category; an
+    // action bound to several keys is listed once with all of them
+    pub fn cheat_sheet(&self, query: &str) -> Vec<(Category, Vec<(&'static str, String)>)> {
+        let query = query.trim().to_lowercase();
+        let mut groups = Vec::new();
+        for category in Category::ALL {
+            let entries: Vec<(&'static str, String)> = actions()
+                .into_iter()
+                .filter_map(|action| defaults(action).next())
+                .filter(|s| s.category == category)
+                .map(|s| (s.label, self.all_keys(s.action)))
+                .filter(|(label, keys)| {
+                    !keys.is_empty()
+                        && (query.is_empty()
+                            || label.to_lowercase().contains(&query)
+                            || keys.to_lowercase().contains(&query))
+                })
+                .collect();
+            if !entries.is_empty() {
+                groups.push((category, entries));
+            }
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_displayed_with_modifiers() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.keys(Action::SaveAs), "Ctrl+Shift+S");
+        assert_eq!(keymap.keys(Action::FindPrevious), "Shift+F3");
+        assert_eq!(keymap.keys(Action::NavigateBack), "Alt+←");
+        assert_eq!(keymap.keys(Action::ShowShortcuts), "F1");
+    }
+
+    #[test]
+    fn lookup_respects_modifiers_and_scope() {
+        let keymap = Keymap::default();
+        let key = Key::Character("s".into());
+        assert_eq!(keymap.lookup(Scope::Global, &key, CTRL), Some(Action::Save));
+        assert_eq!(keymap.lookup(Scope::Global, &key, CTRL_SHIFT), Some(Action::SaveAs));
+        assert_eq!(keymap.lookup(Scope::Global, &key, NONE), None);
+        // Ctrl++ is typed with Shift on most layouts
+        assert_eq!(keymap.lookup(Scope::Global, &Key::Character("+".into()), CTRL_SHIFT), Some(Action::ZoomIn));
+        let escape = Key::Named(Named::Escape);
+        assert_eq!(keymap.lookup(Scope::Global, &escape, CTRL), Some(Action::ClosePanel));
+        let f3 = Key::Named(Named::F3);
+        assert_eq!(keymap.lookup(Scope::Global, &f3, SHIFT), Some(Action::FindPrevious));
+        assert_eq!(keymap.lookup(Scope::Global, &f3, CTRL), Some(Action::FindNext));
+        let left = Key::Named(Named::ArrowLeft);
+        assert_eq!(keymap.lookup(Scope::Global, &left, ALT), None);
+        assert_eq!(keymap.lookup(Scope::Editor, &left, ALT), Some(Action::NavigateBack));
+    }
+
+    #[test]
+    fn cheat_sheet_filters_and_merges_bindings() {
+        let keymap = Keymap::default();
+        let all = keymap.cheat_sheet("");
+        assert_eq!(all.len(), Category::ALL.len());
+        let zoom = keymap.cheat_sheet("zoom +");
+        assert_eq!(zoom, vec![(Category::View, vec![("Zoom +", "Ctrl+= / Ctrl++".to_string())])]);
+        let by_keys = keymap.cheat_sheet("ctrl+shift");
+        assert!(by_keys.iter().flat_map(|(_, e)| e).any(|(label, _)| *label == "Enregistrer sous"));
+        assert!(keymap.cheat_sheet("introuvable").is_empty());
+    }
+
+    #[test]
+    fn displayed_keys_are_parsed_back() {
+        for s in SHORTCUTS {
+            assert_eq!(parse_keys(&s.keys()), Some((s.modifiers, s.key)), "{}", s.keys());
+        }
+        assert_eq!(parse_keys("ctrl + alt + t"), Some((CTRL | ALT, KeyName::Char('t'))));
+        assert_eq!(parse_keys("Hyper+T"), None);
+        assert_eq!(parse_keys("Ctrl+Entrée+X"), None);
+    }
+
+    #[test]
+    fn overrides_rebind_and_unbind_actions() {
+        let overrides = Overrides::from([
+            (Action::Print, vec!["Ctrl+Shift+P".to_string()]),
+            (Action::ToggleWordWrap, Vec::new()),
+            (Action::Copy, vec!["F9".to_string()]),
+            (Action::GoTo, vec!["Ctrl+Alt+Inconnue".to_string(), "Ctrl+L".to_string()]),
+        ]);
+        let keymap = Keymap::from_overrides(&overrides);
+        let p = Key::Character("P".into());
+        assert_eq!(keymap.lookup(Scope::Global, &p, CTRL_SHIFT), Some(Action::Print));
+        assert_eq!(keymap.lookup(Scope::Global, &p, CTRL), None);
+        assert_eq!(keymap.keys(Action::ToggleWordWrap), "");
+        assert_eq!(keymap.keys(Action::GoTo), "Ctrl+L");
+        // The text editor's own keys stay as they are
+        assert_eq!(keymap.keys(Action::Copy), "Ctrl+C");
+        assert_eq!(keymap.overrides().len(), 3);
+        assert_eq!(Keymap::from_overrides(&keymap.overrides()), keymap);
+    }
+
+    #[test]
+    fn conflicts_are_detected() {
+        let mut keymap = Keymap::default();
+        for action in actions() {
+            assert!(keymap.conflicts(action).is_empty(), "{action:?}");
+        }
+        keymap.rebind(Action::Print, CTRL, KeyName::Char('s'));
+        assert_eq!(keymap.conflicts(Action::Print), [Action::Save]);
+        assert_eq!(keymap.conflicts(Action::Save), [Action::Print]);
+        keymap.reset(Action::Print);
+        assert!(keymap.is_default(Action::Print));
+        assert!(keymap.conflicts(Action::Save).is_empty());
+        // Shift does not count for symbols
+        keymap.rebind(Action::GoTo, CTRL_SHIFT, KeyName::Char('='));
+        assert_eq!(keymap.conflicts(Action::GoTo), [Action::ZoomIn]);
+    }
+}
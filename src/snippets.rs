@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::language::Language;
+use crate::preferences;
+
+// --- Snippets ---
+
+// Marks where the caret goes once the snippet is inserted
+pub const CURSOR: &str = "$CURSOR";
+
+// Trigger word expanded by Tab: "fn" by a function skeleton, "addr" by an
+// address block. Kept in snippets.json so it can be shared or edited by hand.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Snippet {
+    pub trigger: String,
+    pub body: String,
+    // Only in documents of this language; every document when unset
+    pub language: Option<Language>,
+}
+
+pub fn path() -> PathBuf {
+    preferences::dir().join("snippets.json")
+}
+
+pub fn load() -> Vec<Snippet> {
+    preferences::load_json(&path())
+}
+
+pub fn save(snippets: &[Snippet]) {
+    preferences::save_json(&path(), &snippets);
+}
+
+// Snippet whose trigger ends `before` (the text left of the cursor) in a
+// document of `language`. The trigger must start a word, and the snippets of
+// the language win over the ones of every document.
+pub fn find<'a>(list: &'a [Snippet], language: Language, before: &str) -> Option<&'a Snippet> {
+    list.iter()
+        .filter(|s| !s.trigger.is_empty() && s.language.is_none_or(|l| l == language))
+        .filter(|s| {
+            before.strip_suffix(s.trigger.as_str()).is_some_and(|rest| {
+                rest.chars().next_back().is_none_or(|c| !c.is_alphanumeric())
+            })
+        })
+        .max_by_key(|s| (s.language.is_some(), s.trigger.len()))
+}
+
+impl Snippet {
+    // Text inserted and the offset in characters of the caret within it: the
+    // body, typed on one line in the settings with \n for the line breaks,
+    // its lines after the first indented like the line it is expanded on
+    pub fn expand(&self, indent: &str) -> (String, usize) {
+        let body = self.body.replace("\\n", "\n").replace('\n', &format!("\n{indent}"));
+        match body.find(CURSOR) {
+            Some(at) => {
+                let text = body.replacen(CURSOR, "", 1);
+                let offset = body[..at].chars().count();
+                (text, offset)
+            }
+            None => {
+                let offset = body.chars().count();
+                (body, offset)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(trigger: &str, body: &str, language: Option<Language>) -> Snippet {
+        Snippet {
+            trigger: trigger.to_string(),
+            body: body.to_string(),
+            language,
+        }
+    }
+
+    #[test]
+    fn trigger_must_start_a_word() {
+        let list = [
+            snippet("addr", "1 rue de la Paix", None),
+            snippet("fn", "fn $CURSOR() {}", Some(Language::Rust)),
+        ];
+        let found = |language, before| find(&list, language, before).map(|s| s.trigger.as_str());
+        assert_eq!(found(Language::PlainText, "écrire à addr"), Some("addr"));
+        assert_eq!(found(Language::Rust, "    fn"), Some("fn"));
+        assert_eq!(found(Language::Python, "fn"), None);
+        assert_eq!(found(Language::PlainText, "maddr"), None);
+    }
+
+    #[test]
+    fn body_is_indented_and_placeholder_removed() {
+        let (text, offset) = snippet("if", "if $CURSOR {\\n    \\n}", None).expand("  ");
+        assert_eq!(text, "if  {\n      \n  }");
+        assert_eq!(offset, 3);
+        let (text, offset) = snippet("é", "été\nfin", None).expand("");
+        assert_eq!(text, "été\nfin");
+        assert_eq!(offset, 7);
+    }
+}
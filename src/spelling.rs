@@ -0,0 +1,520 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::language::Language;
+
+// --- Spell checking ---
+
+// Hunspell dictionaries, as shipped with LibreOffice (fr_FR.aff + fr_FR.dic),
+// installed in the dictionaries folder beside the word lists of the
+// "Définition" popup. Only the common part of the format is read: prefixes
+// and suffixes with their conditions, flag aliases, REP and TRY for the
+// suggestions. Compounding and morphology are ignored.
+
+const MAX_SUGGESTIONS: usize = 6;
+
+// Prose only: code and configuration files are full of identifiers
+pub fn is_checked(language: Language) -> bool {
+    language.is_prose()
+}
+
+// Names of the installed dictionaries ("fr_FR"): the .dic files with their .aff
+pub fn available(folder: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(folder)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect::<Vec<PathBuf>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("dic")))
+        .filter(|p| p.with_extension("aff").exists())
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+type Flag = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlagMode {
+    // One character per flag (the default, and FLAG UTF-8)
+    Char,
+    // Two characters per flag
+    Long,
+    // Comma-separated numbers
+    Numeric,
+}
+
+fn parse_flags(text: &str, mode: FlagMode) -> Vec<Flag> {
+    match mode {
+        FlagMode::Char => text.chars().map(|c| c as Flag).collect(),
+        FlagMode::Long => {
+            let chars: Vec<char> = text.chars().collect();
+            chars
+                .chunks(2)
+                .map(|pair| pair.iter().fold(0, |flag, &c| (flag << 16) | c as Flag))
+                .collect()
+        }
+        FlagMode::Numeric => text.split(',').filter_map(|n| n.trim().parse().ok()).collect(),
+    }
+}
+
+// One position of an affix condition
+#[derive(Debug, Clone, PartialEq)]
+enum CharClass {
+    Any,
+    Is(char),
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Any => true,
+            CharClass::Is(expected) => c == *expected,
+            CharClass::OneOf(set) => set.contains(&c),
+            CharClass::NoneOf(set) => !set.contains(&c),
+        }
+    }
+}
+
+// "[^aeiou]y" and the like; "." for any stem
+fn parse_condition(text: &str) -> Vec<CharClass> {
+    let mut classes = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        classes.push(match c {
+            '.' => CharClass::Any,
+            '[' => {
+                let mut set: Vec<char> = chars.by_ref().take_while(|&c| c != ']').collect();
+                if set.first() == Some(&'^') {
+                    set.remove(0);
+                    CharClass::NoneOf(set)
+                } else {
+                    CharClass::OneOf(set)
+                }
+            }
+            c => CharClass::Is(c),
+        });
+    }
+    classes
+}
+
+#[derive(Debug, Clone)]
+struct Affix {
+    flag: Flag,
+    // Can be combined with an affix of the other kind
+    cross: bool,
+    strip: String,
+    condition: Vec<CharClass>,
+}
+
+impl Affix {
+    fn suffix_applies(&self, stem: &str) -> bool {
+        let mut chars = stem.chars().rev();
+        self.condition.iter().rev().all(|class| chars.next().is_some_and(|c| class.matches(c)))
+    }
+
+    fn prefix_applies(&self, stem: &str) -> bool {
+        let mut chars = stem.chars();
+        self.condition.iter().all(|class| chars.next().is_some_and(|c| class.matches(c)))
+    }
+}
+
+// A loaded dictionary
+pub struct Speller {
+    pub name: String,
+    // Stems with their affix flags
+    words: HashMap<String, Vec<Flag>>,
+    // Affix rules by the text they add
+    prefixes: HashMap<String, Vec<Affix>>,
+    suffixes: HashMap<String, Vec<Affix>>,
+    forbidden: Option<Flag>,
+    need_affix: Option<Flag>,
+    // Letters tried by the suggestions, most frequent first
+    try_chars: Vec<char>,
+    // Common mistakes: (typed, meant)
+    replacements: Vec<(String, String)>,
+}
+
+// The word lists are too big to be printed in the logs
+impl std::fmt::Debug for Speller {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Speller")
+            .field("name", &self.name)
+            .field("words", &self.words.len())
+            .finish()
+    }
+}
+
+// Blocking: reads `name`.aff and `name`.dic from `folder`
+pub fn load(folder: &Path, name: &str) -> Result<Speller, String> {
+    let read = |extension: &str| {
+        let path = folder.join(format!("{name}.{extension}"));
+        std::fs::read(&path).map_err(|e| format!("{} : {e}", path.display()))
+    };
+    let (aff, dic) = (read("aff")?, read("dic")?);
+    // The encoding of both files is given by the SET line of the .aff
+    let label = aff
+        .split(|&b| b == b'\n')
+        .find_map(|line| line.trim_ascii().strip_prefix(b"SET"))
+        .map(<[u8]>::trim_ascii)
+        .unwrap_or(b"ISO8859-1");
+    let encoding = encoding_rs::Encoding::for_label(label).unwrap_or(encoding_rs::WINDOWS_1252);
+    let (aff, _, _) = encoding.decode(&aff);
+    let (dic, _, _) = encoding.decode(&dic);
+    Ok(Speller::parse(name, &aff, &dic))
+}
+
+impl Speller {
+    fn parse(name: &str, aff: &str, dic: &str) -> Self {
+        let mut speller = Speller {
+            name: name.to_string(),
+            words: HashMap::new(),
+            prefixes: HashMap::new(),
+            suffixes: HashMap::new(),
+            forbidden: None,
+            need_affix: None,
+            try_chars: Vec::new(),
+            replacements: Vec::new(),
+        };
+        let mut mode = FlagMode::Char;
+        let mut aliases: Vec<Vec<Flag>> = Vec::new();
+        let mut cross: HashMap<(bool, Flag), bool> = HashMap::new();
+        for line in aff.lines() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                ["FLAG", kind, ..] => {
+                    mode = match *kind {
+                        "long" => FlagMode::Long,
+                        "num" => FlagMode::Numeric,
+                        _ => FlagMode::Char,
+                    }
+                }
+                ["TRY", letters, ..] => speller.try_chars = letters.chars().collect(),
+                ["FORBIDDENWORD", flag, ..] => speller.forbidden = parse_flags(flag, mode).first().copied(),
+                ["NEEDAFFIX", flag, ..] => speller.need_affix = parse_flags(flag, mode).first().copied(),
+                ["AF", flags, ..] if flags.parse::<usize>().is_err() => aliases.push(parse_flags(flags, mode)),
+                ["REP", from, to, ..] => speller.replacements.push((from.replace('_', " "), to.replace('_', " "))),
+                [kind @ ("PFX" | "SFX"), flag, product, count]
+                    if matches!(*product, "Y" | "N") && count.parse::<usize>().is_ok() =>
+                {
+                    if let Some(&flag) = parse_flags(flag, mode).first() {
+                        cross.insert((*kind == "PFX", flag), *product == "Y");
+                    }
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] => {
+                    let Some(&flag) = parse_flags(flag, mode).first() else {
+                        continue;
+                    };
+                    let is_prefix = *kind == "PFX";
+                    let zero = |text: &str| if text == "0" { String::new() } else { text.to_string() };
+                    // Flags after the added text allow a second affix, which is not supported
+                    let add = zero(add.split('/').next().unwrap_or_default());
+                    let affix = Affix {
+                        flag,
+                        cross: cross.get(&(is_prefix, flag)).copied().unwrap_or(false),
+                        strip: zero(strip),
+                        condition: parse_condition(rest.first().copied().unwrap_or(".")),
+                    };
+                    let rules = if is_prefix { &mut speller.prefixes } else { &mut speller.suffixes };
+                    rules.entry(add).or_default().push(affix);
+                }
+                _ => {}
+            }
+        }
+        let mut lines = dic.lines();
+        // The first line gives the number of entries
+        if let Some(first) = lines.next().filter(|l| l.trim().parse::<usize>().is_err()) {
+            speller.add_entry(first, mode, &aliases);
+        }
+        for line in lines {
+            speller.add_entry(line, mode, &aliases);
+        }
+        speller
+    }
+
+    // "word/FLAGS" followed by optional morphological fields
+    fn add_entry(&mut self, line: &str, mode: FlagMode, aliases: &[Vec<Flag>]) {
+        let Some(entry) = line.split_whitespace().next() else {
+            return;
+        };
+        let (word, flags) = match entry.split_once('/') {
+            Some((word, flags)) if !aliases.is_empty() => {
+                // With aliases the flags are the 1-based number of an AF line
+                let alias = flags.parse::<usize>().ok().and_then(|n| aliases.get(n.checked_sub(1)?));
+                (word, alias.cloned().unwrap_or_default())
+            }
+            Some((word, flags)) => (word, parse_flags(flags, mode)),
+            None => (entry, Vec::new()),
+        };
+        if !word.is_empty() {
+            self.words.entry(word.to_string()).or_default().extend(flags);
+        }
+    }
+
+    fn has_stem(&self, stem: &str, needed: &[Flag]) -> bool {
+        self.words.get(stem).is_some_and(|flags| {
+            !self.forbidden.is_some_and(|f| flags.contains(&f)) && needed.iter().all(|f| flags.contains(f))
+        })
+    }
+
+    // The word as written: a stem, or a stem with a prefix, a suffix or both
+    fn is_valid(&self, word: &str) -> bool {
+        if word.is_empty() {
+            return false;
+        }
+        if let Some(flags) = self.words.get(word) {
+            let flagged = |flag: Option<Flag>| flag.is_some_and(|f| flags.contains(&f));
+            if flagged(self.forbidden) {
+                return false;
+            }
+            if !flagged(self.need_affix) {
+                return true;
+            }
+        }
+        if self.has_suffix(word, None) {
+            return true;
+        }
+        let boundaries = word.char_indices().map(|(i, _)| i).skip(1).chain([word.len()]);
+        for i in boundaries {
+            let Some(rules) = self.prefixes.get(&word[..i]) else {
+                continue;
+            };
+            for rule in rules {
+                let stem = format!("{}{}", rule.strip, &word[i..]);
+                if stem.is_empty() || !rule.prefix_applies(&stem) {
+                    continue;
+                }
+                if self.has_stem(&stem, &[rule.flag]) || (rule.cross && self.has_suffix(&stem, Some(rule.flag))) {
+                    return true;
+                }
+            }
+        }
+        // Rules adding nothing to the front
+        self.prefixes.get("").is_some_and(|rules| {
+            rules.iter().any(|rule| {
+                let stem = format!("{}{word}", rule.strip);
+                rule.prefix_applies(&stem) && self.has_stem(&stem, &[rule.flag])
+            })
+        })
+    }
+
+    // A stem with one of its suffixes; `prefix` is the flag of the prefix
+    // already removed, which the stem must also carry
+    fn has_suffix(&self, word: &str, prefix: Option<Flag>) -> bool {
+        let boundaries = word.char_indices().map(|(i, _)| i).chain([word.len()]);
+        for i in boundaries {
+            let Some(rules) = self.suffixes.get(&word[i..]) else {
+                continue;
+            };
+            for rule in rules.iter().filter(|r| prefix.is_none() || r.cross) {
+                let stem = format!("{}{}", &word[..i], rule.strip);
+                if stem.is_empty() || !rule.suffix_applies(&stem) {
+                    continue;
+                }
+                let needed: Vec<Flag> = [Some(rule.flag), prefix].into_iter().flatten().collect();
+                if self.has_stem(&stem, &needed) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // Also accepted with a capital at the start of a sentence, or in capitals
+    pub fn check(&self, word: &str) -> bool {
+        let word = word.replace('’', "'");
+        if self.is_valid(&word) {
+            return true;
+        }
+        let mut chars = word.chars();
+        let Some(first) = chars.next() else {
+            return true;
+        };
+        if first.is_uppercase() {
+            let lowered: String = first.to_lowercase().chain(chars).collect();
+            if self.is_valid(&lowered) {
+                return true;
+            }
+            if word.chars().all(|c| !c.is_lowercase()) {
+                let lower = word.to_lowercase();
+                if self.is_valid(&lower) || self.is_valid(&capitalize(&lower)) {
+                    return true;
+                }
+            }
+        }
+        // "porte-monnaie" may only be known as two words
+        word.contains('-') && word.split('-').all(|part| !part.is_empty() && self.check(part))
+    }
+
+    // Byte ranges of the unknown words of a line
+    pub fn misspelled(&self, line: &str) -> Vec<Range<usize>> {
+        words(line).into_iter().filter(|range| !self.check(&line[range.clone()])).collect()
+    }
+
+    // Blocking: known words one edit away, the common mistakes first
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let letters: Vec<char> = if self.try_chars.is_empty() {
+            ('a'..='z').collect()
+        } else {
+            self.try_chars.clone()
+        };
+        let mut candidates: Vec<String> = Vec::new();
+        for (from, to) in &self.replacements {
+            for (at, _) in word.match_indices(from.as_str()) {
+                candidates.push(format!("{}{to}{}", &word[..at], &word[at + from.len()..]));
+            }
+        }
+        let join = |chars: &[char]| chars.iter().collect::<String>();
+        for i in 0..chars.len() {
+            for &letter in &letters {
+                if letter != chars[i] {
+                    let mut edited = chars.clone();
+                    edited[i] = letter;
+                    candidates.push(join(&edited));
+                }
+            }
+        }
+        for i in 1..chars.len() {
+            let mut edited = chars.clone();
+            edited.swap(i - 1, i);
+            candidates.push(join(&edited));
+        }
+        for i in 0..chars.len() {
+            let mut edited = chars.clone();
+            edited.remove(i);
+            candidates.push(join(&edited));
+        }
+        for i in 0..=chars.len() {
+            for &letter in &letters {
+                let mut edited = chars.clone();
+                edited.insert(i, letter);
+                candidates.push(join(&edited));
+            }
+        }
+        for i in 1..chars.len() {
+            candidates.push(format!("{} {}", join(&chars[..i]), join(&chars[i..])));
+        }
+
+        let mut suggestions: Vec<String> = Vec::new();
+        for candidate in candidates {
+            if suggestions.len() == MAX_SUGGESTIONS {
+                break;
+            }
+            if candidate != word
+                && !suggestions.contains(&candidate)
+                && candidate.split(' ').all(|part| !part.is_empty() && self.check(part))
+            {
+                suggestions.push(candidate);
+            }
+        }
+        suggestions
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+// Byte ranges of the words of a line: letters with their inner apostrophes
+// and hyphens. Addresses, paths and words with digits are skipped.
+pub fn words(line: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    for chunk in line.split_inclusive(char::is_whitespace) {
+        let start = offset;
+        offset += chunk.len();
+        let chars: Vec<(usize, char)> = chunk.char_indices().collect();
+        // "www.exemple.fr" is an address
+        let dotted = chars.windows(3).any(|w| w[1].1 == '.' && w[0].1.is_alphanumeric() && w[2].1.is_alphanumeric());
+        if dotted || chunk.contains(['@', '/', '\\', '_']) {
+            continue;
+        }
+        let is_word = |i: usize| {
+            chars.get(i).is_some_and(|(_, c)| c.is_alphanumeric())
+                || (chars.get(i).is_some_and(|(_, c)| matches!(c, '\'' | '’' | '-'))
+                    && i > 0
+                    && chars[i - 1].1.is_alphanumeric()
+                    && chars.get(i + 1).is_some_and(|(_, c)| c.is_alphanumeric()))
+        };
+        let mut i = 0;
+        while i < chars.len() {
+            if !is_word(i) {
+                i += 1;
+                continue;
+            }
+            let first = i;
+            while i < chars.len() && is_word(i) {
+                i += 1;
+            }
+            let end = chars.get(i).map_or(chunk.len(), |(at, _)| *at);
+            // "3e" is a number
+            if !chunk[chars[first].0..end].chars().any(|c| c.is_ascii_digit()) {
+                ranges.push(start + chars[first].0..start + end);
+            }
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8\nTRY esairtnolcdugmphbyfvkwzj\nFORBIDDENWORD !\n\
+        REP 1\nREP f ph\n\
+        SFX S Y 2\nSFX S 0 s [^sxz]\nSFX S al aux al\n\
+        PFX R Y 1\nPFX R 0 re .\n";
+    const DIC: &str = "5\nmaison/S\ncheval/S\nfaire/R\nphoto/S\nchevals/!\n";
+
+    fn speller() -> Speller {
+        Speller::parse("fr_TEST", AFF, DIC)
+    }
+
+    #[test]
+    fn affixes_build_the_known_words() {
+        let speller = speller();
+        for word in ["maison", "maisons", "chevaux", "refaire", "Maison", "MAISONS", "photos"] {
+            assert!(speller.check(word), "{word}");
+        }
+        for word in ["maisonss", "chevals", "remaison", "faires", "mAIson"] {
+            assert!(!speller.check(word), "{word}");
+        }
+    }
+
+    #[test]
+    fn long_and_aliased_flags_are_read() {
+        let aff = "FLAG long\nSFX Aa Y 1\nSFX Aa 0 s .\n";
+        assert!(Speller::parse("x", aff, "1\nmot/AaBb\n").check("mots"));
+        let aff = "AF 1\nAF S\nSFX S Y 1\nSFX S 0 s .\n";
+        let speller = Speller::parse("x", aff, "1\nmot/1\n");
+        assert!(speller.check("mots"));
+    }
+
+    #[test]
+    fn suggestions_are_one_edit_away() {
+        let speller = speller();
+        assert_eq!(speller.suggest("foto"), ["photo"]);
+        assert_eq!(speller.suggest("masion").first().map(String::as_str), Some("maison"));
+        assert!(speller.suggest("maisonphoto").contains(&"maison photo".to_string()));
+        assert!(speller.suggest("zzzz").is_empty());
+    }
+
+    #[test]
+    fn words_of_a_line() {
+        let line = "Une maisn, www.exemple.fr et lea@exemple.org l’homme 3e porte-monnaie";
+        let found: Vec<&str> = words(line).into_iter().map(|r| &line[r]).collect();
+        assert_eq!(found, ["Une", "maisn", "et", "l’homme", "porte-monnaie"]);
+        let speller = speller();
+        let line = "Les maisons et les chevals";
+        let wrong: Vec<&str> = speller.misspelled(line).into_iter().map(|r| &line[r]).collect();
+        assert_eq!(wrong, ["Les", "et", "les", "chevals"]);
+    }
+}
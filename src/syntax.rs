@@ -0,0 +1,98 @@
+use crate::language::Language;
+
+// --- Syntax errors of configuration files ---
+
+// Quiet time after the last keystroke before the document is parsed again
+pub const SYNTAX_CHECK_IDLE_MS: u64 = 600;
+
+// First error found by the parser; line and column are 0-based, the column
+// counted in characters
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+pub fn is_checked(language: Language) -> bool {
+    matches!(language, Language::Json | Language::Toml | Language::Yaml)
+}
+
+// Blocking: None when the text parses (or the language is not checked)
+pub fn check(language: Language, text: &str) -> Option<SyntaxError> {
+    match language {
+        Language::Json => check_json(text),
+        Language::Toml => check_toml(text),
+        Language::Yaml => check_yaml(text),
+        _ => None,
+    }
+}
+
+fn check_json(text: &str) -> Option<SyntaxError> {
+    let error = serde_json::from_str::<serde::de::IgnoredAny>(text).err()?;
+    let line = error.line().saturating_sub(1);
+    // serde_json counts the column in bytes, 0 when the error is at a line break
+    let column = text
+        .lines()
+        .nth(line)
+        .map_or(0, |l| char_column(l, error.column().saturating_sub(1)));
+    let message = error.to_string();
+    let message = message.rfind(" at line ").map_or(message.as_str(), |end| &message[..end]);
+    Some(SyntaxError {
+        line,
+        column,
+        message: message.to_string(),
+    })
+}
+
+fn check_toml(text: &str) -> Option<SyntaxError> {
+    let error = text.parse::<toml::Table>().err()?;
+    let offset = error.span().map_or(0, |span| span.start).min(text.len());
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Some(SyntaxError {
+        line: before.matches('\n').count(),
+        column: before[line_start..].chars().count(),
+        message: error.message().trim().to_string(),
+    })
+}
+
+fn check_yaml(text: &str) -> Option<SyntaxError> {
+    let error = yaml_rust2::YamlLoader::load_from_str(text).err()?;
+    Some(SyntaxError {
+        line: error.marker().line().saturating_sub(1),
+        column: error.marker().col(),
+        message: error.info().to_string(),
+    })
+}
+
+fn char_column(line: &str, byte: usize) -> usize {
+    line.char_indices().take_while(|(i, _)| *i < byte).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_errors_point_at_the_token() {
+        assert_eq!(check(Language::Json, "{\"a\": [1, 2]}"), None);
+        let error = check(Language::Json, "{\n  \"é\": 1\n  \"b\": 2\n}").unwrap();
+        assert_eq!((error.line, error.column), (2, 2));
+        assert_eq!(error.message, "expected `,` or `}`");
+    }
+
+    #[test]
+    fn toml_and_yaml_errors_are_located() {
+        assert_eq!(check(Language::Toml, "[paquet]\nnom = \"x\"\n"), None);
+        let error = check(Language::Toml, "[paquet]\nnom = \"x\"\nnom = \"y\"\n").unwrap();
+        assert_eq!(error.line, 2);
+        assert!(!error.message.is_empty());
+
+        assert_eq!(check(Language::Yaml, "a:\n  - 1\n  - 2\n"), None);
+        let error = check(Language::Yaml, "a: [1, 2\nb: 3\n").unwrap();
+        assert!(error.line >= 1);
+        assert!(!error.message.is_empty());
+        assert_eq!(check(Language::Markdown, "{"), None);
+    }
+}
@@ -0,0 +1,54 @@
+// --- Tabs and spaces ---
+
+// Tabs replaced by the spaces reaching the next stop, every `width` columns
+pub fn expand(line: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut out = String::with_capacity(line.len());
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = width - column % width;
+            out.extend(std::iter::repeat_n(' ', spaces));
+            column += spaces;
+        } else {
+            out.push(c);
+            column += 1;
+        }
+    }
+    out
+}
+
+// Indentation rewritten with a tab per `width` columns, the spaces short of a
+// stop kept after them; tabs and spaces further in the line are left alone
+pub fn unexpand_indent(line: &str, width: usize) -> String {
+    let width = width.max(1);
+    let body = line.trim_start_matches([' ', '\t']);
+    let indent = expand(&line[..line.len() - body.len()], width).len();
+    let mut out = "\t".repeat(indent / width);
+    out.extend(std::iter::repeat_n(' ', indent % width));
+    out.push_str(body);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabs_reach_the_next_stop() {
+        assert_eq!(expand("a\tb", 4), "a   b");
+        assert_eq!(expand("\tb", 4), "    b");
+        assert_eq!(expand("abcd\tx\t", 4), "abcd    x   ");
+        assert_eq!(expand("\t", 8), " ".repeat(8));
+    }
+
+    #[test]
+    fn indentation_becomes_tabs() {
+        assert_eq!(unexpand_indent("        x = 1", 4), "\t\tx = 1");
+        assert_eq!(unexpand_indent("  \t  y", 4), "\t  y");
+        assert_eq!(unexpand_indent("      z", 4), "\t  z");
+        // Alignment inside the line stays as it is
+        assert_eq!(unexpand_indent("    a    b", 4), "\ta    b");
+        assert_eq!(unexpand_indent("", 4), "");
+    }
+}
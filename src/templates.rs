@@ -0,0 +1,129 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+use crate::preferences;
+
+// --- Document templates ---
+
+// Files of the templates folder, each instantiated into a new tab. A template
+// may hold variables like {{date}} or {{title}}, asked for on instantiation.
+pub fn dir() -> PathBuf {
+    preferences::dir().join("templates")
+}
+
+// File names of the templates in `dir` ("Compte rendu.md"), sorted
+pub fn list(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .filter_map(|e| e.file_name().into_string().ok())
+        .collect();
+    names.sort_by_key(|name| name.to_lowercase());
+    names
+}
+
+// Name typed by the user, refused when it would leave the folder or is not a
+// valid file name on Windows
+pub fn is_valid_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.chars().any(|c| c.is_control() || r#"<>:"/\|?*"#.contains(c))
+}
+
+// Writes `text` as `name`.`extension`, replacing a template of that name
+pub fn save(dir: &Path, name: &str, extension: &str, text: &str) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.{extension}", name.trim()));
+    paths::replace_via_temp(&path, |temp| std::fs::write(temp, text))?;
+    Ok(path)
+}
+
+// Names of the {{variables}} of `text`, once each, in order of appearance
+pub fn variables(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("}}") else { break };
+        let name = rest[..end].trim();
+        if is_variable_name(name) && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = &rest[end + 2..];
+    }
+    names
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+// `text` with its variables replaced by their values; unknown ones are left
+// as they are
+pub fn fill(text: &str, values: &[(String, String)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let name = after[..end].trim();
+            values.iter().find(|(n, _)| n == name).map(|(_, v)| (v, end))
+        });
+        match value {
+            Some((value, end)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables_are_listed_once_in_order() {
+        let text = "# {{title}}\n{{ date }} — {{title}}\n{{pas une variable}} {{auteur}";
+        assert_eq!(variables(text), ["title", "date"]);
+    }
+
+    #[test]
+    fn fill_replaces_known_variables() {
+        let values = [("title".to_string(), "Réunion".to_string()), ("date".to_string(), "17/10/2026".to_string())];
+        assert_eq!(fill("# {{title}} ({{ date }})\n{{autre}} {{", &values), "# Réunion (17/10/2026)\n{{autre}} {{");
+    }
+
+    #[test]
+    fn names_cannot_leave_the_folder() {
+        assert!(is_valid_name("Compte rendu"));
+        assert!(!is_valid_name("  "));
+        assert!(!is_valid_name("../notes"));
+        assert!(!is_valid_name("a/b"));
+        assert!(!is_valid_name(".cache"));
+    }
+
+    #[test]
+    fn saved_templates_are_listed() {
+        let dir = std::env::temp_dir().join(format!("notepad_templates_{}", std::process::id()));
+        save(&dir, "Lettre", "txt", "Madame, Monsieur,").unwrap();
+        save(&dir, " compte rendu ", "md", "# {{title}}").unwrap();
+        let names = list(&dir);
+        let text = std::fs::read_to_string(dir.join("compte rendu.md")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(names, ["compte rendu.md", "Lettre.txt"]);
+        assert_eq!(text, "# {{title}}");
+    }
+}
@@ -0,0 +1,269 @@
+use iced::futures::channel::mpsc;
+use iced::futures::Stream;
+use regex::Regex;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::LazyLock;
+
+// Lines kept in the panel; older output is dropped
+pub const MAX_TERMINAL_LINES: usize = 2000;
+
+// Color / cursor escape sequences, which the panel cannot render
+static ANSI_ESCAPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b(\[[0-9;?]*[ -/]*[@-~]|\][^\x07]*\x07|[@-Z\\-_])").unwrap());
+
+// --- Shell process ---
+
+#[derive(Debug, Clone)]
+pub enum ShellEvent {
+    Output(String),
+    // Both output pipes were closed: the shell exited
+    Closed,
+}
+
+pub struct Shell {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl Shell {
+    // Starts the system shell in `cwd`; the stream carries its stdout and stderr
+    pub fn spawn(cwd: &Path) -> io::Result<(Self, impl Stream<Item = ShellEvent>)> {
+        let mut command = shell_command();
+        command
+            .current_dir(cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command.spawn()?;
+        let stdin = child.stdin.take();
+        let (sender, receiver) = mpsc::unbounded();
+        let readers: Vec<Box<dyn Read + Send>> = [
+            child.stdout.take().map(|r| Box::new(r) as Box<dyn Read + Send>),
+            child.stderr.take().map(|r| Box::new(r) as Box<dyn Read + Send>),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let open_pipes = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(readers.len()));
+        for mut reader in readers {
+            let sender = sender.clone();
+            let open_pipes = open_pipes.clone();
+            std::thread::spawn(move || {
+                let mut chunk = [0u8; 4096];
+                let mut carry = Vec::new();
+                while let Ok(read) = reader.read(&mut chunk) {
+                    if read == 0 {
+                        break;
+                    }
+                    let text = decode_chunk(&mut carry, &chunk[..read]);
+                    if !text.is_empty() && sender.unbounded_send(ShellEvent::Output(text)).is_err() {
+                        return;
+                    }
+                }
+                if open_pipes.fetch_sub(1, std::sync::atomic::Ordering::SeqCst) == 1 {
+                    let _ = sender.unbounded_send(ShellEvent::Closed);
+                }
+            });
+        }
+        Ok((Self { child, stdin }, receiver))
+    }
+
+    pub fn send(&mut self, line: &str) -> io::Result<()> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "entrée fermée"))?;
+        stdin.write_all(line.as_bytes())?;
+        stdin.write_all(b"\n")?;
+        stdin.flush()
+    }
+
+    pub fn exit_code(&mut self) -> Option<i32> {
+        self.child.try_wait().ok().flatten().and_then(|status| status.code())
+    }
+}
+
+impl Drop for Shell {
+    fn drop(&mut self) {
+        self.stdin = None;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn shell_command() -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    // "-Command -" makes PowerShell read the commands from its standard input
+    let mut command = Command::new("powershell.exe");
+    command
+        .args(["-NoLogo", "-NoProfile", "-Command", "-"])
+        .creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell_command() -> Command {
+    Command::new(std::env::var_os("SHELL").unwrap_or_else(|| "/bin/sh".into()))
+}
+
+pub fn shell_name() -> String {
+    let program = shell_command();
+    Path::new(program.get_program())
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// Decodes what can be decoded, keeping a truncated UTF-8 sequence for the next chunk
+fn decode_chunk(carry: &mut Vec<u8>, bytes: &[u8]) -> String {
+    carry.extend_from_slice(bytes);
+    let valid = match std::str::from_utf8(carry) {
+        Ok(_) => carry.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => carry.len(),
+    };
+    let text = String::from_utf8_lossy(&carry[..valid]).into_owned();
+    carry.drain(..valid);
+    text
+}
+
+// --- Output buffer ---
+
+#[derive(Debug, Default)]
+pub struct TerminalOutput {
+    lines: VecDeque<String>,
+    // Last line, not terminated yet
+    partial: String,
+}
+
+impl TerminalOutput {
+    pub fn push(&mut self, chunk: &str) {
+        let cleaned = ANSI_ESCAPE.replace_all(chunk, "");
+        let mut rest: &str = &cleaned;
+        while let Some(end) = rest.find('\n') {
+            let line = rest[..end].strip_suffix('\r').unwrap_or(&rest[..end]);
+            self.partial.push_str(line);
+            let line = std::mem::take(&mut self.partial);
+            self.push_complete(line);
+            rest = &rest[end + 1..];
+        }
+        self.partial.push_str(rest);
+    }
+
+    pub fn push_line(&mut self, line: impl Into<String>) {
+        if !self.partial.is_empty() {
+            let partial = std::mem::take(&mut self.partial);
+            self.push_complete(partial);
+        }
+        self.push_complete(line.into());
+    }
+
+    fn push_complete(&mut self, line: String) {
+        // A bare carriage return rewrites the line (progress indicators)
+        let line = match line.rfind('\r') {
+            Some(pos) => line[pos + 1..].to_string(),
+            None => line,
+        };
+        self.lines.push_back(line);
+        while self.lines.len() > MAX_TERMINAL_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.partial.clear();
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.push_str(&self.partial);
+        text
+    }
+}
+
+// --- Panel state ---
+
+pub struct Terminal {
+    pub id: u64,
+    pub cwd: PathBuf,
+    pub output: TerminalOutput,
+    pub input: String,
+    // None once the shell exited or could not be started
+    pub shell: Option<Shell>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_splits_lines_across_chunks() {
+        let mut out = TerminalOutput::default();
+        out.push("hel");
+        out.push("lo\r\nwor");
+        assert_eq!(out.text(), "hello\nwor");
+        out.push("ld\n");
+        assert_eq!(out.text(), "hello\nworld\n");
+    }
+
+    #[test]
+    fn output_strips_escapes_and_carriage_returns() {
+        let mut out = TerminalOutput::default();
+        out.push("\x1b[32mok\x1b[0m\n10%\r50%\r100%\n");
+        assert_eq!(out.text(), "ok\n100%\n");
+    }
+
+    #[test]
+    fn push_line_terminates_partial_output() {
+        let mut out = TerminalOutput::default();
+        out.push("$ ");
+        out.push_line("> ls");
+        assert_eq!(out.text(), "$ \n> ls\n");
+    }
+
+    #[test]
+    fn output_is_capped() {
+        let mut out = TerminalOutput::default();
+        for i in 0..MAX_TERMINAL_LINES + 5 {
+            out.push_line(i.to_string());
+        }
+        assert!(out.text().starts_with("5\n"));
+    }
+
+    #[test]
+    fn decode_keeps_truncated_utf8() {
+        let mut carry = Vec::new();
+        let bytes = "é".as_bytes();
+        assert_eq!(decode_chunk(&mut carry, &bytes[..1]), "");
+        assert_eq!(decode_chunk(&mut carry, &bytes[1..]), "é");
+        assert!(carry.is_empty());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn shell_runs_commands_in_cwd() {
+        use iced::futures::executor::block_on_stream;
+        let dir = std::env::temp_dir();
+        let (mut shell, events) = Shell::spawn(&dir).unwrap();
+        shell.send("pwd; exit").unwrap();
+        let mut output = String::new();
+        for event in block_on_stream(events) {
+            match event {
+                ShellEvent::Output(text) => output.push_str(&text),
+                ShellEvent::Closed => break,
+            }
+        }
+        let expected = dir.canonicalize().unwrap();
+        assert_eq!(Path::new(output.trim()).canonicalize().unwrap(), expected);
+    }
+}
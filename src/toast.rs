@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+// Oldest notifications are dropped beyond this count
+const MAX_TOASTS: usize = 5;
+const TOAST_LIFETIME: Duration = Duration::from_secs(4);
+const ERROR_TOAST_LIFETIME: Duration = Duration::from_secs(8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn lifetime(self) -> Duration {
+        match self {
+            Severity::Warning | Severity::Error => ERROR_TOAST_LIFETIME,
+            Severity::Info | Severity::Success => TOAST_LIFETIME,
+        }
+    }
+}
+
+pub struct Toast {
+    pub id: u64,
+    pub severity: Severity,
+    pub message: String,
+    pub created: Instant,
+}
+
+// --- Non-blocking notifications, shown bottom-right and dismissed automatically ---
+
+#[derive(Default)]
+pub struct Toasts {
+    next_id: u64,
+    items: Vec<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>) -> u64 {
+        self.next_id += 1;
+        self.items.push(Toast {
+            id: self.next_id,
+            severity,
+            message: message.into(),
+            created: Instant::now(),
+        });
+        if self.items.len() > MAX_TOASTS {
+            self.items.remove(0);
+        }
+        self.next_id
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.items.retain(|t| t.id != id);
+    }
+
+    pub fn expire(&mut self, now: Instant) {
+        self.items
+            .retain(|t| now.duration_since(t.created) < t.severity.lifetime());
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Toast> {
+        self.items.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_keeps_most_recent() {
+        let mut toasts = Toasts::default();
+        for i in 0..MAX_TOASTS + 2 {
+            toasts.push(Severity::Info, format!("t{i}"));
+        }
+        let messages: Vec<&str> = toasts.iter().map(|t| t.message.as_str()).collect();
+        assert_eq!(messages.len(), MAX_TOASTS);
+        assert_eq!(messages[0], "t2");
+    }
+
+    #[test]
+    fn dismiss_removes_only_that_toast() {
+        let mut toasts = Toasts::default();
+        let a = toasts.push(Severity::Info, "a");
+        toasts.push(Severity::Error, "b");
+        toasts.dismiss(a);
+        let messages: Vec<&str> = toasts.iter().map(|t| t.message.as_str()).collect();
+        assert_eq!(messages, vec!["b"]);
+    }
+
+    #[test]
+    fn errors_outlive_infos() {
+        let mut toasts = Toasts::default();
+        toasts.push(Severity::Success, "saved");
+        toasts.push(Severity::Error, "failed");
+        toasts.expire(Instant::now() + TOAST_LIFETIME);
+        let messages: Vec<&str> = toasts.iter().map(|t| t.message.as_str()).collect();
+        assert_eq!(messages, vec!["failed"]);
+        toasts.expire(Instant::now() + ERROR_TOAST_LIFETIME);
+        assert!(toasts.is_empty());
+    }
+}
@@ -1,13 +1,44 @@
 use iced::widget::{
-    button, container, mouse_area, row, text, text_editor, text_input, Column, Row, Space, Stack,
+    button, canvas, container, mouse_area, pick_list, progress_bar, row, scrollable, slider, text, text_editor, text_input, tooltip, Column,
+    Row, Space, Stack,
 };
+use iced::advanced::text::highlighter;
+use iced::keyboard::key::Named;
+use iced::keyboard::Key;
 use iced::{Element, Font, Length, Padding, Theme};
+use unicode_width::UnicodeWidthChar;
 
 use crate::app::{
-    find_input_id, goto_input_id, replace_input_id, EditMsg, FileMsg, FormatMsg, Menu, MenuMsg,
-    Message, Notepad, SearchMsg, SettingsMsg, ViewMsg, MENU_BAR_HEIGHT, MENU_ITEM_WIDTH,
-    TAB_BAR_HEIGHT,
+    editor_id, split_editor_id, Document, Pane, filter_command_input_id, wrap_width_input_id, macro_count_input_id, char_search_input_id, find_input_id, folder_filter_input_id, FolderMsg, session_name_input_id, SessionMsg, template_name_input_id, template_value_input_id, TemplateMsg, goto_input_id, note_search_input_id, replace_input_id, tags_input_id, shortcut_input_id, snapshot_name_input_id, terminal_input_id, ColorMsg, CompletionMsg, EditMsg, FileMsg, FormatMsg, HelpMsg, IndentStyle, LineEnding,
+    InkMsg, JobMsg, Menu, MenuMsg, MergeMsg, Message, NotesMsg, PrintMsg, SnapshotMsg, ToastMsg, Notepad, SearchMsg, SettingsMsg, TableMsg, TerminalMsg, ViewMsg, INDENT_SIZE_CHOICES,
+    BREADCRUMB_HEIGHT, MENU_BAR_HEIGHT, MENU_ITEM_WIDTH, TAB_BADGE_FONT_SIZE, TAB_BAR_HEIGHT, TAB_FONT_SIZE, TERMINAL_PANEL_HEIGHT,
+    TOOLBAR_HEIGHT, NETWORK_AUTOSAVE_CHOICES, SLOW_SAVE_CHOICES_MS, reopen_encodings,
 };
+use crate::changes::{self, LineChange};
+use crate::charmap;
+use crate::color;
+use crate::datetime::{self, LocalTime};
+use crate::dictionary;
+use crate::dpi;
+use crate::export::ExportFormat;
+use crate::folder::{self, MAX_FOLDER_FILES};
+use crate::hibernate::{format_size, MemoryUsage};
+use crate::http::ProxyMode;
+use crate::ink;
+use crate::language::Language;
+use crate::ligatures;
+use crate::lock::LockState;
+use crate::opacity::{self, MIN_WINDOW_OPACITY};
+use crate::preferences::{EscapeOrder, UrlPaste};
+use crate::markdown;
+use crate::marks::{EditorMarks, Mark, MarkSettings};
+use crate::merge::{HunkChoice, Segment};
+use crate::scripts;
+use crate::print::{mm_to_pt, MARGIN_CHOICES_MM, PAGE_HEIGHT_PT, PAGE_WIDTH_PT};
+use crate::shortcuts::{self, Action, Scope};
+use crate::spelling;
+use crate::toast::Severity;
+use crate::updates::UpdateCheck;
 use crate::DEFAULT_FONT_SIZE;
 
 const MENU_LABELS: &[(Menu, &str)] = &[
@@ -16,9 +47,20 @@ const MENU_LABELS: &[(Menu, &str)] = &[
     (Menu::Search, "Recherche"),
     (Menu::View, "Affichage"),
     (Menu::Format, "Format"),
+    (Menu::Window, "Fenêtre"),
+    (Menu::Help, "Aide"),
 ];
 
 const MENU_FONT_SIZE: f32 = 12.0;
+// Position of "Fichiers récents" in the File menu, where its submenu unfolds
+const RECENT_FILES_MENU_ROW: usize = 4;
+const REOPEN_ENCODING_MENU_ROW: usize = 5;
+const BOOKMARKS_MENU_ROW: usize = 4;
+const DATE_FORMAT_MENU_ROW: usize = 8;
+// Side of the dot marking a bookmarked line in the gutter
+const BOOKMARK_DOT_SIZE: f32 = 6.0;
+// Side of the QR code shown on screen
+const QR_CODE_SIZE: f32 = 260.0;
 const MENU_H_PADDING: f32 = 12.0;
 
 fn menu_left_offset(menu: Menu) -> f32 {
@@ -54,6 +96,106 @@ fn menu_item_widget<'a>(
         .into()
 }
 
+// "…ments/notes" for a folder too long for a menu item
+fn elide_start(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+    let tail: String = text.chars().skip(count - max_chars + 1).collect();
+    format!("…{tail}")
+}
+
+// Rows the editor wraps `line` into at `columns` character cells, counted up
+// to `max_rows` so a huge line costs no more than the screen. Like the
+// editor, it breaks at Unicode line break opportunities, lets blanks hang
+// past the edge and leaves a word wider than the editor unbroken; tabs go to
+// the next multiple of 8 cells and wide characters take two
+fn wrapped_rows(line: &str, columns: usize, max_rows: usize) -> usize {
+    if columns == 0 || (line.len() <= columns && !line.contains('\t')) {
+        return 1;
+    }
+    let mut x = 0;
+    let mut advance = |c: char| {
+        let width = match c {
+            '\t' => EDITOR_TAB_CELLS - x % EDITOR_TAB_CELLS,
+            c => c.width().unwrap_or(0),
+        };
+        x += width;
+        width
+    };
+    let mut rows = 1;
+    // Cells taken on the current row
+    let mut used = 0;
+    let mut start = 0;
+    for (end, _) in unicode_linebreak::linebreaks(line) {
+        let segment = &line[start..end];
+        start = end;
+        // The word, then each blank after it, as (cells, blank)
+        let word = segment.trim_end_matches(char::is_whitespace);
+        let word_width: usize = word.chars().map(&mut advance).sum();
+        let pieces = (!word.is_empty())
+            .then_some((word_width, false))
+            .into_iter()
+            .chain(segment[word.len()..].chars().map(|c| (advance(c), true)));
+        for (width, blank) in pieces {
+            if used + width <= columns || (blank && used <= columns) {
+                used += width;
+                continue;
+            }
+            if used > 0 {
+                rows += 1;
+                if rows >= max_rows {
+                    return max_rows;
+                }
+            }
+            used = if blank { 0 } else { width };
+        }
+    }
+    rows
+}
+
+// Cells between tab stops, as the editor lays tabs out
+const EDITOR_TAB_CELLS: usize = 8;
+
+// Advance of one character cell of a monospace font at `size`
+fn cell_width(font: Font, size: f32) -> f32 {
+    use iced::advanced::text::Paragraph as _;
+    const SAMPLE: &str = "0000000000";
+    let paragraph = iced::advanced::graphics::text::Paragraph::with_text(iced::advanced::Text {
+        content: SAMPLE,
+        bounds: iced::Size::INFINITE,
+        size: size.into(),
+        line_height: text::LineHeight::default(),
+        font,
+        align_x: text::Alignment::Default,
+        align_y: iced::alignment::Vertical::Top,
+        shaping: text::Shaping::Advanced,
+        wrapping: text::Wrapping::None,
+    });
+    paragraph.min_width() / SAMPLE.len() as f32
+}
+
+fn toolbar_button<'a>(
+    icon: &'a str,
+    tip: &'a str,
+    msg: Message,
+    bg_weak: iced::Color,
+    bg_strong: iced::Color,
+) -> Element<'a, Message> {
+    tooltip(
+        button(text(icon).size(14))
+            .on_press(msg)
+            .padding([3, 8])
+            .style(button::text),
+        container(text(tip).size(11))
+            .padding([3, 6])
+            .style(popup_style(bg_weak, bg_strong)),
+        tooltip::Position::Bottom,
+    )
+    .into()
+}
+
 fn bar_style(
     bg_weak: iced::Color,
     bg_strong: iced::Color,
@@ -104,6 +246,7 @@ fn menu_popup_size(item_count: usize) -> (f32, f32) {
     (width, height)
 }
 
+// Keeps a popup inside the window, its corner on a device pixel at `scale`
 fn clamp_popup_position(
     mut x: f32,
     mut y: f32,
@@ -111,6 +254,7 @@ fn clamp_popup_position(
     popup_h: f32,
     window_w: f32,
     window_h: f32,
+    scale: f32,
 ) -> (f32, f32) {
     if x + popup_w > window_w {
         x = (window_w - popup_w).max(0.0);
@@ -118,7 +262,7 @@ fn clamp_popup_position(
     if y + popup_h > window_h {
         y = (window_h - popup_h).max(0.0);
     }
-    (x, y)
+    (dpi::snap(x, scale), dpi::snap(y, scale))
 }
 
 fn overlay_at<'a>(
@@ -140,7 +284,382 @@ fn overlay_at<'a>(
         .into()
 }
 
+// Width (or height) of the line between the panes of a split
+const SPLIT_DIVIDER: f32 = 1.0;
+
+// Halves of the editor area for the two panes of a split
+fn split_areas(area: iced::Rectangle, vertical: bool) -> (iced::Rectangle, iced::Rectangle) {
+    let mut first = area;
+    let mut second = area;
+    if vertical {
+        first.width = (area.width - SPLIT_DIVIDER) / 2.0;
+        second.width = first.width;
+        second.x = area.x + first.width + SPLIT_DIVIDER;
+    } else {
+        first.height = (area.height - SPLIT_DIVIDER) / 2.0;
+        second.height = first.height;
+        second.y = area.y + first.height + SPLIT_DIVIDER;
+    }
+    (first, second)
+}
+
 impl Notepad {
+    // Gutter, editor and scrollbar of `doc` in `area` (window coordinates). The
+    // focused pane edits the active tab; the other one only takes focus when
+    // used. Returns the gutter width, for the popups placed beside the text.
+    fn editor_pane<'a>(
+        &'a self,
+        doc: &'a Document,
+        pane: Pane,
+        focused: bool,
+        area: iced::Rectangle,
+        editor_font: Font,
+    ) -> (Element<'a, Message>, f32) {
+        let theme = self.theme();
+        let palette = theme.extended_palette();
+        let bg_weak = palette.background.weak.color;
+        let bg_strong = palette.background.strong.color;
+        let bg_base = palette.background.base.color;
+        let bg_text = palette.background.base.text;
+        let primary_weak = palette.primary.weak.color;
+        let is_markdown = doc.is_markdown();
+        let ligatures = if doc.language().is_prose() {
+            self.prose_ligatures
+        } else {
+            self.code_ligatures
+        };
+        let editor_font = match ligatures::without(&self.font_family) {
+            Some(family) if !ligatures => Font::with_name(family),
+            _ => editor_font,
+        };
+        // The focused pane of a split is outlined
+        let border_color = if focused && self.split.is_some() {
+            palette.primary.base.color
+        } else {
+            bg_strong
+        };
+
+        let total_lines = doc.content.line_count();
+        let digits = total_lines.max(1).to_string().len().max(3);
+        // Widths derived from the font size land on whole device pixels, so
+        // the gutter and scrollbar edges stay sharp under fractional scaling
+        let scale = self.pixel_scale();
+        let mut gutter_width = dpi::snap(digits as f32 * self.font_size * 0.6 + 20.0, scale);
+        let scrollbar_width = dpi::snap(12.0, scale);
+        let thumb_width = dpi::snap(8.0, scale);
+        let line_number_color = iced::Color { a: 0.45, ..bg_text };
+
+        let line_height = self.font_size * 1.3;
+        let visible_lines = (area.height / line_height) as usize + 2;
+        let scroll_line = doc.scroll_offset as usize;
+        let visible_end = (scroll_line + visible_lines).min(total_lines);
+
+        // First color literal of each visible line, shown as a clickable swatch
+        let swatches: Vec<Option<color::ColorLiteral>> = (scroll_line..visible_end)
+            .map(|i| {
+                doc.content
+                    .line(i)
+                    .and_then(|l| color::swatch(&l.text))
+            })
+            .collect();
+        let has_swatches = swatches.iter().any(Option::is_some);
+        const SWATCH_COLUMN_WIDTH: f32 = 16.0;
+        // Always there, so the text does not move on the first edit
+        const CHANGE_BAR_WIDTH: f32 = 3.0;
+        let number_width = gutter_width;
+        gutter_width += CHANGE_BAR_WIDTH;
+        if has_swatches {
+            gutter_width += SWATCH_COLUMN_WIDTH;
+        }
+
+        // With word wrap, a logical line can take several visual rows: its number sits
+        // on the first one and the following ones get a continuation marker. Rows
+        // are only counted in character cells, so proportional fonts keep one
+        // number per line
+        let monospace = crate::MONOSPACE_FAMILIES.contains(&self.font_family.as_str());
+        let wrap_columns = if self.word_wrap && monospace {
+            let text_width = area.width - gutter_width - scrollbar_width - 20.0;
+            ((text_width / cell_width(editor_font, self.font_size)).max(1.0)) as usize
+        } else {
+            usize::MAX
+        };
+        let mut gutter_rows: Vec<(usize, bool)> = Vec::new();
+        for i in scroll_line..visible_end {
+            let rows = doc
+                .content
+                .line(i)
+                .map_or(1, |l| wrapped_rows(&l.text, wrap_columns, visible_lines));
+            gutter_rows.push((i, false));
+            gutter_rows.extend(std::iter::repeat_n((i, true), rows - 1));
+            if gutter_rows.len() >= visible_lines {
+                break;
+            }
+        }
+        gutter_rows.truncate(visible_lines);
+
+        let mut line_nums = Column::new();
+        for (index, continuation) in gutter_rows {
+            let i = index + 1;
+            let swatch = swatches[index - scroll_line].as_ref().filter(|_| !continuation);
+            let label = if continuation { "↪".to_string() } else { i.to_string() };
+            let number = container(
+                text(label)
+                    .font(editor_font)
+                    .size(self.font_size)
+                    .color(line_number_color),
+            )
+            .width(number_width)
+            .align_x(iced::Alignment::End)
+            .padding(Padding {
+                top: 0.0,
+                right: 8.0,
+                bottom: 0.0,
+                left: 4.0,
+            });
+            // Bookmarked lines get a dot before their number
+            let number: Element<'_, Message> = if !continuation && doc.bookmarks.contains(index) {
+                let dot_color = palette.primary.base.color;
+                let dot = container(Space::new().width(BOOKMARK_DOT_SIZE).height(BOOKMARK_DOT_SIZE)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(dot_color)),
+                        border: iced::Border {
+                            radius: (BOOKMARK_DOT_SIZE / 2.0).into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                );
+                Stack::new()
+                    .push(number)
+                    .push(container(dot).height(line_height).center_y(line_height))
+                    .into()
+            } else {
+                number.into()
+            };
+            // Lines changed since the last save
+            let bar_color = changes::at(&doc.line_changes, index).map(|change| match change {
+                LineChange::Added => palette.success.base.color,
+                LineChange::Modified => palette.primary.base.color,
+                LineChange::Removed => palette.danger.base.color,
+            });
+            let number = Row::new()
+                .push(
+                    container(Space::new().width(CHANGE_BAR_WIDTH).height(line_height)).style(
+                        move |_: &Theme| container::Style {
+                            background: bar_color.map(iced::Background::Color),
+                            ..Default::default()
+                        },
+                    ),
+                )
+                .push(number);
+            if !has_swatches {
+                line_nums = line_nums.push(number);
+                continue;
+            }
+            let mut gutter_row = Row::new().align_y(iced::Alignment::Center);
+            if let Some(literal) = swatch {
+                let fill = literal.color.to_iced();
+                // The picker edits the active document, so only its pane opens it
+                gutter_row = gutter_row.push(
+                    container(
+                        button(Space::new().width(10).height(10))
+                            .on_press_maybe(
+                                focused.then_some(Message::Color(ColorMsg::Open(i - 1, literal.start))),
+                            )
+                            .padding(0)
+                            .style(move |_: &Theme, _| button::Style {
+                                background: Some(iced::Background::Color(fill)),
+                                border: iced::Border {
+                                    color: bg_strong,
+                                    width: 1.0,
+                                    radius: 2.0.into(),
+                                },
+                                ..Default::default()
+                            }),
+                    )
+                    .center_x(SWATCH_COLUMN_WIDTH),
+                );
+            } else {
+                gutter_row = gutter_row.push(Space::new().width(SWATCH_COLUMN_WIDTH));
+            }
+            line_nums = line_nums.push(gutter_row.push(number));
+        }
+
+        let gutter_container = container(
+            container(line_nums).padding(Padding {
+                top: 10.0,
+                right: 0.0,
+                bottom: 10.0,
+                left: 0.0,
+            }),
+        )
+        .style(bar_style(bg_weak, bg_strong))
+        .height(Length::Fill)
+        .clip(true);
+
+        let completing = self.path_completion.is_some();
+        let keymap = &self.keymap;
+        let editor = text_editor(&doc.content)
+            .id(if focused { editor_id() } else { split_editor_id() })
+            .on_action(move |action| {
+                if focused {
+                    Message::EditorAction(action)
+                } else {
+                    Message::View(ViewMsg::PaneAction(pane, action))
+                }
+            })
+            .key_binding(move |key_press| {
+                // The path completion popup takes the arrows, Enter, Tab and Escape
+                if completing && key_press.modifiers.is_empty() {
+                    let msg = match key_press.key {
+                        Key::Named(Named::ArrowDown) => Some(CompletionMsg::Next),
+                        Key::Named(Named::ArrowUp) => Some(CompletionMsg::Previous),
+                        Key::Named(Named::Enter | Named::Tab) => Some(CompletionMsg::Accept),
+                        Key::Named(Named::Escape) => Some(CompletionMsg::Dismiss),
+                        _ => None,
+                    };
+                    if let Some(msg) = msg {
+                        return Some(text_editor::Binding::Custom(Message::Completion(msg)));
+                    }
+                }
+                // Editor shortcuts (jump history, paragraphs, Markdown headings) instead of cursor moves
+                match keymap.lookup(Scope::Editor, &key_press.key, key_press.modifiers) {
+                    Some(Action::PreviousHeading | Action::NextHeading) if !is_markdown => {}
+                    Some(action) => {
+                        return Some(text_editor::Binding::Custom(Message::Shortcut(action)));
+                    }
+                    None => {}
+                }
+                // Tab inserts the document's indent unit (tab or spaces)
+                if key_press.key == Key::Named(Named::Tab)
+                    && key_press.modifiers.is_empty()
+                    && matches!(key_press.status, text_editor::Status::Focused { .. })
+                {
+                    return Some(text_editor::Binding::Custom(Message::Edit(EditMsg::InsertTab)));
+                }
+                text_editor::Binding::from_key_press(key_press)
+            })
+            .padding(10)
+            .font(editor_font)
+            .size(self.font_size)
+            .wrapping(if self.word_wrap {
+                text::Wrapping::Word
+            } else {
+                text::Wrapping::None
+            })
+            .height(Length::Fill)
+            .style(move |_theme, _status| text_editor::Style {
+                background: iced::Background::Color(bg_base),
+                border: iced::Border {
+                    color: border_color,
+                    width: 1.0,
+                    radius: 0.0.into(),
+                },
+                placeholder: iced::Color {
+                    a: 0.4,
+                    ..bg_text
+                },
+                value: bg_text,
+                selection: primary_weak,
+            })
+            .highlight_with::<EditorMarks>(
+                MarkSettings {
+                    error: doc.current_syntax_error().map(|e| (e.line, e.column)),
+                    speller: self.speller.clone().filter(|_| spelling::is_checked(doc.language())),
+                },
+                |mark, theme: &Theme| highlighter::Format {
+                    color: Some(match mark {
+                        Mark::SyntaxError => theme.extended_palette().danger.base.color,
+                        Mark::Misspelled => theme.extended_palette().danger.strong.color,
+                    }),
+                    font: None,
+                },
+            );
+        let editor_area =
+            mouse_area(editor).on_right_press(Message::Menu(MenuMsg::ShowContext));
+        // Annotations over the text; while they are shown the pointer draws
+        let editor_area: Element<'a, Message> = if self.show_ink {
+            let layer = ink::Layer {
+                strokes: &doc.ink,
+                drawing: doc.ink_stroke.as_ref(),
+                interactive: focused,
+                scroll: doc.scroll_offset,
+                column_width: self.font_size * 0.6,
+                line_height,
+                padding: 10.0,
+                color: palette.danger.base.color,
+            };
+            Stack::new()
+                .push(editor_area)
+                .push(canvas(layer).width(Length::Fill).height(Length::Fill))
+                .into()
+        } else {
+            editor_area.into()
+        };
+
+        // --- Custom scrollbar ---
+        let visible_lines_f = (area.height / line_height).max(1.0);
+        let thumb_ratio = (visible_lines_f / total_lines.max(1) as f32).min(1.0);
+        let scroll_ratio = if total_lines <= 1 {
+            0.0
+        } else {
+            doc.scroll_offset / (total_lines.saturating_sub(1) as f32)
+        };
+
+        let track_color = iced::Color { a: 0.15, ..bg_text };
+        let thumb_color = iced::Color { a: 0.4, ..bg_text };
+
+        // Calculate mouse_position ratio for click handling
+        let mouse_y = self.mouse_position.y;
+        let click_ratio = ((mouse_y - area.y) / area.height).clamp(0.0, 1.0);
+
+        let thumb_height_pct = (thumb_ratio * 100.0).max(5.0);
+        let thumb_top_pct = scroll_ratio * (100.0 - thumb_height_pct);
+
+        let mut scrollbar_track = mouse_area(
+            container(
+                Column::new()
+                    .push(Space::new().height(Length::FillPortion(
+                        (thumb_top_pct * 100.0) as u16,
+                    )))
+                    .push(
+                        container(Space::new().width(thumb_width).height(Length::FillPortion(
+                            (thumb_height_pct * 100.0) as u16,
+                        )))
+                        .style(move |_: &Theme| container::Style {
+                            background: Some(iced::Background::Color(thumb_color)),
+                            border: iced::Border {
+                                color: thumb_color,
+                                width: 0.0,
+                                radius: (thumb_width / 2.0).into(),
+                            },
+                            ..Default::default()
+                        }),
+                    )
+                    .push(Space::new().height(Length::FillPortion(
+                        ((100.0 - thumb_top_pct - thumb_height_pct) * 100.0).max(0.0) as u16,
+                    )))
+                    .height(Length::Fill),
+            )
+            .style(move |_: &Theme| container::Style {
+                background: Some(iced::Background::Color(track_color)),
+                ..Default::default()
+            })
+            .width(scrollbar_width)
+            .height(Length::Fill),
+        );
+        if focused {
+            scrollbar_track = scrollbar_track.on_press(Message::ScrollbarClick(click_ratio));
+        }
+
+        let editor_row = Row::new()
+            .push(gutter_container)
+            .push(editor_area)
+            .push(scrollbar_track)
+            .height(Length::Fill);
+        (editor_row.into(), gutter_width)
+    }
+
     pub fn view(&self) -> Element<'_, Message> {
         let theme = self.theme();
         let palette = theme.extended_palette();
@@ -149,7 +668,6 @@ impl Notepad {
         let bg_strong = palette.background.strong.color;
         let bg_base = palette.background.base.color;
         let bg_text = palette.background.base.text;
-        let primary_weak = palette.primary.weak.color;
         let shortcut_color = iced::Color { a: 0.5, ..bg_text };
 
         let doc = self.active_doc();
@@ -181,17 +699,65 @@ impl Notepad {
             .height(MENU_BAR_HEIGHT);
         layout = layout.push(menu_bar);
 
+        // --- Toolbar ---
+        if self.show_toolbar {
+            let separator = || container(text("|").size(12).color(shortcut_color)).padding([0, 4]);
+            let tool = |icon, tip, msg| toolbar_button(icon, tip, msg, bg_weak, bg_strong);
+            let toolbar_row = row![
+                tool("+", "Nouvel onglet (Ctrl+N)", Message::File(FileMsg::NewTab)),
+                tool("↥", "Ouvrir (Ctrl+O)", Message::File(FileMsg::Open)),
+                tool("↓", "Enregistrer (Ctrl+S)", Message::File(FileMsg::Save)),
+                separator(),
+                tool("↶", "Annuler (Ctrl+Z)", Message::Edit(EditMsg::Undo)),
+                tool("↷", "Rétablir (Ctrl+Y)", Message::Edit(EditMsg::Redo)),
+                separator(),
+                tool("✂", "Couper (Ctrl+X)", Message::Edit(EditMsg::Cut)),
+                tool("⧉", "Copier (Ctrl+C)", Message::Edit(EditMsg::Copy)),
+                tool("⎘", "Coller (Ctrl+V)", Message::Edit(EditMsg::Paste)),
+                separator(),
+                tool("⌕", "Rechercher (Ctrl+F)", Message::Search(SearchMsg::OpenFind)),
+                separator(),
+                tool("⊕", "Zoom + (Ctrl+=)", Message::View(ViewMsg::ZoomIn)),
+                tool("⊖", "Zoom - (Ctrl+-)", Message::View(ViewMsg::ZoomOut)),
+            ]
+            .spacing(2)
+            .padding([0, 4])
+            .align_y(iced::Alignment::Center);
+            let toolbar = container(toolbar_row)
+                .style(bar_style(bg_weak, bg_strong))
+                .width(Length::Fill)
+                .height(TOOLBAR_HEIGHT)
+                .align_y(iced::Alignment::Center);
+            layout = layout.push(toolbar);
+        }
+
         // --- Tab bar ---
+        let tabs_overflow = self.tabs_overflow();
+        let tab_start = if tabs_overflow {
+            self.tab_scroll.min(self.tabs.len() - 1)
+        } else {
+            0
+        };
         let mut tab_row = Row::new().spacing(0);
-        for (i, tab_doc) in self.tabs.iter().enumerate() {
+        for (i, tab_doc) in self.tabs.iter().enumerate().skip(tab_start) {
             let is_active_tab = i == self.active_tab;
             let label = tab_doc.title_label();
 
-            // Tab button with close X
-            let tab_content = Row::new()
-                .push(text(label).size(11))
+            // Tab button with archive badge and close X
+            let mut tab_content = Row::new().push(text(label).size(TAB_FONT_SIZE));
+            if let Some(archive) = &tab_doc.archive {
+                tab_content = tab_content.push(
+                    container(
+                        text(archive.badge())
+                            .size(TAB_BADGE_FONT_SIZE)
+                            .color(palette.primary.base.color),
+                    )
+                    .padding([0, 2]),
+                );
+            }
+            let tab_content = tab_content
                 .push(
-                    button(text("×").size(11))
+                    button(text("×").size(TAB_FONT_SIZE))
                         .on_press(Message::File(FileMsg::CloseTab(i)))
                         .padding(Padding {
                             top: 0.0,
@@ -221,20 +787,35 @@ impl Notepad {
             tab_row = tab_row.push(tab_btn);
         }
 
-        // "+" button for new tab
-        tab_row = tab_row.push(
-            button(text("+").size(12))
-                .on_press(Message::File(FileMsg::NewTab))
+        let strip_button = |label: &'static str, msg: Message| {
+            button(text(label).size(12))
+                .on_press(msg)
                 .padding(Padding {
                     top: 6.0,
                     bottom: 6.0,
                     left: 8.0,
                     right: 8.0,
                 })
-                .style(button::text),
-        );
+                .style(button::text)
+        };
+
+        // "+" button for new tab (pinned to the right when tabs overflow)
+        let new_tab_btn = strip_button("+", Message::File(FileMsg::NewTab));
+        let mut tab_bar_row = Row::new().spacing(0);
+        if tabs_overflow {
+            tab_bar_row = tab_bar_row
+                .push(strip_button("‹", Message::File(FileMsg::ScrollTabs(-1))))
+                .push(container(tab_row).width(Length::Fill).clip(true))
+                .push(strip_button("›", Message::File(FileMsg::ScrollTabs(1))))
+                .push(new_tab_btn);
+        } else {
+            tab_bar_row = tab_bar_row
+                .push(tab_row.push(new_tab_btn))
+                .push(Space::new().width(Length::Fill));
+        }
+        tab_bar_row = tab_bar_row.push(strip_button("▼", Message::Menu(MenuMsg::ToggleTabList)));
 
-        let tab_bar = container(tab_row)
+        let tab_bar = container(tab_bar_row)
             .style(bar_style(bg_weak, bg_strong))
             .width(Length::Fill)
             .height(TAB_BAR_HEIGHT);
@@ -242,10 +823,22 @@ impl Notepad {
 
         // --- External modification banner ---
         if doc.externally_modified {
+            let mut banner_row = Row::new()
+                .push(text("Ce fichier a été modifié par un autre programme.").size(12))
+                .push(Space::new().width(Length::Fill));
+            // Local edits would be lost by a reload: offer to merge them
+            if doc.is_modified {
+                banner_row = banner_row
+                    .push(
+                        button(text("Fusionner…").size(11))
+                            .on_press(Message::Merge(MergeMsg::Open(self.active_tab)))
+                            .style(button::primary)
+                            .padding(Padding::from([3, 12])),
+                    )
+                    .push(Space::new().width(6));
+            }
             let banner = container(
-                Row::new()
-                    .push(text("Ce fichier a été modifié par un autre programme.").size(12))
-                    .push(Space::new().width(Length::Fill))
+                banner_row
                     .push(
                         button(text("Recharger").size(11))
                             .on_press(Message::File(FileMsg::ReloadFile(self.active_tab)))
@@ -281,6 +874,16 @@ impl Notepad {
             } else {
                 button::secondary
             };
+            let extended_style = if self.extended_search {
+                button::primary
+            } else {
+                button::secondary
+            };
+            let in_selection_style = if self.find_in_selection.is_some() {
+                button::primary
+            } else {
+                button::secondary
+            };
             let mut find_row = row![
                 text("Rechercher:").size(12),
                 text_input("Rechercher...", &self.find_query)
@@ -289,25 +892,58 @@ impl Notepad {
                     .on_submit(Message::Search(SearchMsg::FindNext))
                     .size(12)
                     .width(200),
-                button(text("Aa").size(11))
-                    .on_press(Message::Search(SearchMsg::ToggleCaseSensitive))
-                    .padding(4)
-                    .style(case_style),
-                button(text(".*").size(11))
-                    .on_press(Message::Search(SearchMsg::ToggleRegex))
-                    .padding(4)
-                    .style(regex_style),
-                button(text("Suivant").size(11))
-                    .on_press(Message::Search(SearchMsg::FindNext))
-                    .padding(4)
-                    .style(button::secondary),
-                button(text("Précédent").size(11))
-                    .on_press(Message::Search(SearchMsg::FindPrevious))
-                    .padding(4)
-                    .style(button::secondary),
             ]
             .spacing(6)
             .align_y(iced::Alignment::Center);
+            if let Some(count) = self.find_matches {
+                let color = if count.total == 0 {
+                    palette.danger.base.color
+                } else {
+                    shortcut_color
+                };
+                find_row = find_row.push(text(count.label()).size(11).color(color));
+            }
+            find_row = find_row
+                .push(
+                    button(text("Aa").size(11))
+                        .on_press(Message::Search(SearchMsg::ToggleCaseSensitive))
+                        .padding(4)
+                        .style(case_style),
+                )
+                .push(
+                    button(text(".*").size(11))
+                        .on_press(Message::Search(SearchMsg::ToggleRegex))
+                        .padding(4)
+                        .style(regex_style),
+                )
+                .push(tooltip(
+                    button(text("\\n").size(11))
+                        .on_press(Message::Search(SearchMsg::ToggleExtended))
+                        .padding(4)
+                        .style(extended_style),
+                    container(text("Étendu (\\n, \\r, \\t, \\0, \\xNN)").size(11))
+                        .padding([3, 6])
+                        .style(popup_style(bg_weak, bg_strong)),
+                    tooltip::Position::Bottom,
+                ))
+                .push(
+                    button(text("Dans la sélection").size(11))
+                        .on_press(Message::Search(SearchMsg::ToggleInSelection))
+                        .padding(4)
+                        .style(in_selection_style),
+                )
+                .push(
+                    button(text("Suivant").size(11))
+                        .on_press(Message::Search(SearchMsg::FindNext))
+                        .padding(4)
+                        .style(button::secondary),
+                )
+                .push(
+                    button(text("Précédent").size(11))
+                        .on_press(Message::Search(SearchMsg::FindPrevious))
+                        .padding(4)
+                        .style(button::secondary),
+                );
 
             if self.show_replace {
                 find_row = find_row
@@ -377,151 +1013,159 @@ impl Notepad {
             layout = layout.push(goto_bar);
         }
 
-        // --- Editor with line numbers ---
-        let total_lines = doc.content.line_count();
-        let digits = total_lines.max(1).to_string().len().max(3);
-        let gutter_width = digits as f32 * self.font_size * 0.6 + 20.0;
-        let line_number_color = iced::Color { a: 0.45, ..bg_text };
+        // --- Markdown breadcrumb: headings enclosing the cursor ---
+        let is_markdown = doc.is_markdown();
+        let show_breadcrumb = is_markdown && !doc.cached_headings.is_empty();
+        if show_breadcrumb {
+            let headings = &doc.cached_headings;
+            let cursor_line = doc.content.cursor().position.line;
+            let mut crumbs = Row::new().spacing(2).align_y(iced::Alignment::Center).push(
+                button(text("Plan ▾").size(11))
+                    .on_press(Message::View(ViewMsg::ToggleOutline))
+                    .padding([2, 6])
+                    .style(if self.show_outline { button::primary } else { button::text }),
+            );
+            for index in markdown::heading_path(headings, cursor_line) {
+                let heading = &headings[index];
+                crumbs = crumbs
+                    .push(text("›").size(11).color(shortcut_color))
+                    .push(
+                        button(text(heading.title.clone()).size(11))
+                            .on_press(Message::Search(SearchMsg::GoToHeading(heading.line)))
+                            .padding([2, 6])
+                            .style(button::text),
+                    );
+            }
+            layout = layout.push(
+                container(crumbs.padding([0, 6]))
+                    .height(BREADCRUMB_HEIGHT)
+                    .align_y(iced::Alignment::Center)
+                    .style(bar_style(bg_weak, bg_strong))
+                    .width(Length::Fill)
+                    .clip(true),
+            );
+        }
 
+        // --- Editor with line numbers, in two panes when split ---
         let font_name: &'static str =
             Box::leak(self.font_family.clone().into_boxed_str());
         let editor_font = Font::with_name(font_name);
 
         let line_height = self.font_size * 1.3;
-        let visible_lines =
-            ((self.window_height - MENU_BAR_HEIGHT - TAB_BAR_HEIGHT) / line_height) as usize + 2;
-        let scroll_line = doc.scroll_offset as usize;
-        let visible_end = (scroll_line + visible_lines).min(total_lines);
+        let toolbar_height = if self.show_toolbar { TOOLBAR_HEIGHT } else { 0.0 };
+        let terminal_height = if self.show_terminal { TERMINAL_PANEL_HEIGHT } else { 0.0 };
+        let editor_height = self.window_height - MENU_BAR_HEIGHT - toolbar_height - TAB_BAR_HEIGHT
+            - terminal_height
+            - 30.0; // approx status bar
+        let bars_height = {
+            let mut h = MENU_BAR_HEIGHT + toolbar_height + TAB_BAR_HEIGHT;
+            if doc.externally_modified { h += 30.0; }
+            if self.show_find { h += 36.0; }
+            if self.show_goto { h += 36.0; }
+            if show_breadcrumb { h += BREADCRUMB_HEIGHT; }
+            h
+        };
+        let editor_area = iced::Rectangle::new(
+            iced::Point::new(0.0, bars_height),
+            iced::Size::new(self.window_width, editor_height),
+        );
+        // Top-left corner of the focused pane, for the popups placed at the cursor
+        let mut pane_origin = iced::Point::new(0.0, bars_height);
+        let gutter_width;
+        match &self.split {
+            None => {
+                let (pane, width) = self.editor_pane(doc, Pane::First, true, editor_area, editor_font);
+                gutter_width = width;
+                layout = layout.push(pane);
+            }
+            Some(split) => {
+                let other = self.tabs.iter().find(|d| d.id == split.other).unwrap_or(doc);
+                let (first_area, second_area) = split_areas(editor_area, split.vertical);
+                let (first_doc, second_doc) = match split.focus {
+                    Pane::First => (doc, other),
+                    Pane::Second => (other, doc),
+                };
+                let focused_first = split.focus == Pane::First;
+                let (first, first_gutter) =
+                    self.editor_pane(first_doc, Pane::First, focused_first, first_area, editor_font);
+                let (second, second_gutter) =
+                    self.editor_pane(second_doc, Pane::Second, !focused_first, second_area, editor_font);
+                let focused_area = if focused_first { first_area } else { second_area };
+                pane_origin = focused_area.position();
+                gutter_width = if focused_first { first_gutter } else { second_gutter };
+                let divider = container(Space::new().width(Length::Fill).height(Length::Fill))
+                    .style(move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(bg_strong)),
+                        ..Default::default()
+                    });
+                let panes: Element<'_, Message> = if split.vertical {
+                    Row::new()
+                        .push(container(first).width(Length::Fill))
+                        .push(divider.width(SPLIT_DIVIDER))
+                        .push(container(second).width(Length::Fill))
+                        .height(Length::Fill)
+                        .into()
+                } else {
+                    Column::new()
+                        .push(container(first).height(Length::Fill))
+                        .push(divider.height(SPLIT_DIVIDER))
+                        .push(container(second).height(Length::Fill))
+                        .height(Length::Fill)
+                        .into()
+                };
+                layout = layout.push(panes);
+            }
+        }
 
-        let mut line_nums = Column::new();
-        for i in (scroll_line + 1)..=visible_end {
-            line_nums = line_nums.push(
-                container(
-                    text(i.to_string())
-                        .font(editor_font)
-                        .size(self.font_size)
-                        .color(line_number_color),
-                )
-                .width(gutter_width)
-                .align_x(iced::Alignment::End)
-                .padding(Padding {
-                    top: 0.0,
-                    right: 8.0,
-                    bottom: 0.0,
-                    left: 4.0,
-                }),
-            );
-        }
-
-        let gutter_container = container(
-            container(line_nums).padding(Padding {
-                top: 10.0,
-                right: 0.0,
-                bottom: 10.0,
-                left: 0.0,
-            }),
-        )
-        .style(bar_style(bg_weak, bg_strong))
-        .height(Length::Fill)
-        .clip(true);
-
-        let editor = text_editor(&doc.content)
-            .on_action(Message::EditorAction)
-            .padding(10)
-            .font(editor_font)
-            .size(self.font_size)
-            .wrapping(if self.word_wrap {
-                text::Wrapping::Word
-            } else {
-                text::Wrapping::None
-            })
-            .height(Length::Fill)
-            .style(move |_theme, _status| text_editor::Style {
-                background: iced::Background::Color(bg_base),
-                border: iced::Border {
-                    color: bg_strong,
-                    width: 1.0,
-                    radius: 0.0.into(),
-                },
-                placeholder: iced::Color {
-                    a: 0.4,
-                    ..bg_text
-                },
-                value: bg_text,
-                selection: primary_weak,
-            });
-        let editor_area =
-            mouse_area(editor).on_right_press(Message::Menu(MenuMsg::ShowContext));
-
-        // --- Custom scrollbar ---
-        let total_lines = doc.content.line_count();
-        let editor_height = self.window_height - MENU_BAR_HEIGHT - TAB_BAR_HEIGHT - 30.0; // approx status bar
-        let visible_lines_f =
-            (editor_height / (self.font_size * 1.3)).max(1.0);
-        let thumb_ratio = (visible_lines_f / total_lines.max(1) as f32).min(1.0);
-        let scroll_ratio = if total_lines <= 1 {
-            0.0
-        } else {
-            doc.scroll_offset / (total_lines.saturating_sub(1) as f32)
-        };
-
-        let track_color = iced::Color { a: 0.15, ..bg_text };
-        let thumb_color = iced::Color { a: 0.4, ..bg_text };
-
-        // Calculate mouse_position ratio for click handling
-        let bars_height = {
-            let mut h = MENU_BAR_HEIGHT + TAB_BAR_HEIGHT;
-            if doc.externally_modified { h += 30.0; }
-            if self.show_find { h += 36.0; }
-            if self.show_goto { h += 36.0; }
-            h
-        };
-        let mouse_y = self.mouse_position.y;
-        let click_ratio = ((mouse_y - bars_height) / editor_height).clamp(0.0, 1.0);
-
-        let thumb_height_pct = (thumb_ratio * 100.0).max(5.0);
-        let thumb_top_pct = scroll_ratio * (100.0 - thumb_height_pct);
-
-        let scrollbar_track = mouse_area(
-            container(
-                Column::new()
-                    .push(Space::new().height(Length::FillPortion(
-                        (thumb_top_pct * 100.0) as u16,
-                    )))
-                    .push(
-                        container(Space::new().width(8).height(Length::FillPortion(
-                            (thumb_height_pct * 100.0) as u16,
-                        )))
-                        .style(move |_: &Theme| container::Style {
-                            background: Some(iced::Background::Color(thumb_color)),
-                            border: iced::Border {
-                                color: thumb_color,
-                                width: 0.0,
-                                radius: 4.0.into(),
-                            },
-                            ..Default::default()
-                        }),
-                    )
-                    .push(Space::new().height(Length::FillPortion(
-                        ((100.0 - thumb_top_pct - thumb_height_pct) * 100.0).max(0.0) as u16,
-                    )))
-                    .height(Length::Fill),
+        // --- Terminal panel ---
+        if let Some(term) = self.terminal.as_ref().filter(|_| self.show_terminal) {
+            let header = row![
+                text("Terminal").size(12),
+                text(term.cwd.display().to_string()).size(11).color(shortcut_color),
+                Space::new().width(Length::Fill),
+                button(text("Effacer").size(11))
+                    .on_press(Message::Terminal(TerminalMsg::Clear))
+                    .padding([2, 8])
+                    .style(button::text),
+                button(text("Redémarrer").size(11))
+                    .on_press(Message::Terminal(TerminalMsg::Restart))
+                    .padding([2, 8])
+                    .style(button::text),
+                button(text("✕").size(11))
+                    .on_press(Message::Terminal(TerminalMsg::TogglePanel))
+                    .padding([0, 4])
+                    .style(button::text),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center);
+            let output = scrollable(
+                text(term.output.text())
+                    .font(editor_font)
+                    .size(12)
+                    .width(Length::Fill),
             )
-            .style(move |_: &Theme| container::Style {
-                background: Some(iced::Background::Color(track_color)),
-                ..Default::default()
-            })
-            .width(12)
-            .height(Length::Fill),
-        )
-        .on_press(Message::ScrollbarClick(click_ratio));
-
-        let editor_row = Row::new()
-            .push(gutter_container)
-            .push(editor_area)
-            .push(scrollbar_track)
+            .anchor_bottom()
             .height(Length::Fill);
-        layout = layout.push(editor_row);
+            let placeholder = if term.shell.is_some() {
+                "Commande"
+            } else {
+                "Terminal arrêté"
+            };
+            let input = text_input(placeholder, &term.input)
+                .id(terminal_input_id())
+                .on_input(|s| Message::Terminal(TerminalMsg::InputChanged(s)))
+                .on_submit(Message::Terminal(TerminalMsg::Submit))
+                .font(editor_font)
+                .size(12)
+                .padding([4, 6]);
+            layout = layout.push(
+                container(Column::new().push(header).push(output).push(input).spacing(6))
+                    .padding([6, 10])
+                    .height(TERMINAL_PANEL_HEIGHT)
+                    .width(Length::Fill)
+                    .style(bar_style(bg_weak, bg_strong)),
+            );
+        }
 
         // --- Status bar ---
         let cursor_pos = doc.content.cursor().position;
@@ -539,6 +1183,12 @@ impl Notepad {
             format!("Ln {}, Col {}", line + 1, col + 1)
         };
 
+        let encoding_label = if doc.bom {
+            format!("{} BOM", doc.encoding.name())
+        } else {
+            doc.encoding.name().to_string()
+        };
+
         let mut status_row = row![
             text(cursor_text).size(11),
         ]
@@ -551,6 +1201,109 @@ impl Notepad {
                 .push(text(msg.clone()).size(11).color(palette.success.base.color));
         }
 
+        // Low power mode; a click turns it off
+        if self.low_power {
+            status_row = status_row
+                .push(container(text("|").size(11)).padding([0, 8]))
+                .push(
+                    button(text("Économie d'énergie").size(11))
+                        .on_press(Message::Settings(SettingsMsg::SetLowPower(false)))
+                        .padding(0)
+                        .style(button::text),
+                );
+        }
+
+        // Macro being recorded; a click stops it
+        if let Some(steps) = &self.macro_recording {
+            status_row = status_row
+                .push(container(text("|").size(11)).padding([0, 8]))
+                .push(
+                    button(
+                        text(format!("● Macro ({})", steps.len()))
+                            .size(11)
+                            .color(palette.danger.base.color),
+                    )
+                    .on_press(Message::Edit(EditMsg::ToggleMacroRecording))
+                    .padding(0)
+                    .style(button::text),
+                );
+        }
+
+        // Document lock, with a way to take over another instance's lock
+        if let Some(doc_lock) = &doc.lock {
+            match &doc_lock.state {
+                LockState::Held => {
+                    status_row = status_row
+                        .push(container(text("|").size(11)).padding([0, 8]))
+                        .push(text("Verrouillé").size(11));
+                }
+                LockState::ReadOnly(owner) => {
+                    status_row = status_row
+                        .push(container(text("|").size(11)).padding([0, 8]))
+                        .push(
+                            text(format!("Lecture seule — verrouillé par {}", owner.label()))
+                                .size(11)
+                                .color(palette.danger.base.color),
+                        )
+                        .push(
+                            button(text("Voler le verrou").size(11))
+                                .on_press(Message::File(FileMsg::StealLock(self.active_tab)))
+                                .padding([0, 8])
+                                .style(button::text),
+                        );
+                }
+                LockState::Unavailable => {}
+            }
+        }
+
+        // First parse error of a JSON / TOML / YAML document; a click goes to it
+        if let Some(error) = doc.current_syntax_error() {
+            status_row = status_row
+                .push(container(text("|").size(11)).padding([0, 8]))
+                .push(
+                    button(
+                        text(format!(
+                            "Erreur {} ligne {}, col {} : {}",
+                            doc.language().label(),
+                            error.line + 1,
+                            error.column + 1,
+                            error.message
+                        ))
+                        .size(11)
+                        .color(palette.danger.base.color),
+                    )
+                    .on_press(Message::Edit(EditMsg::GoToSyntaxError))
+                    .padding(0)
+                    .style(button::text),
+                );
+        }
+
+        // Most recent background job, with its progress and cancel button
+        if let Some(job) = self.jobs.iter().last() {
+            let more = self.jobs.len() - 1;
+            let label = if more > 0 {
+                format!("{} — {} % (+{more})", job.label, (job.progress * 100.0) as u32)
+            } else {
+                format!("{} — {} %", job.label, (job.progress * 100.0) as u32)
+            };
+            status_row = status_row
+                .push(container(text("|").size(11)).padding([0, 8]))
+                .push(
+                    button(text(label).size(11))
+                        .on_press(Message::Job(JobMsg::TogglePanel))
+                        .padding(0)
+                        .style(button::text),
+                );
+            if job.cancellable {
+                status_row = status_row.push(
+                    button(text("Annuler").size(11))
+                        .on_press(Message::Job(JobMsg::Cancel(job.id)))
+                        .padding([0, 8])
+                        .style(button::text),
+                );
+            }
+        }
+
         status_row = status_row
             .push(Space::new().width(Length::Fill))
             .push(text(format!("{} mots", word_count)).size(11))
@@ -561,9 +1314,23 @@ impl Notepad {
             .push(container(text("|").size(11)).padding([0, 8]))
             .push(text(format!("Zoom: {}%", zoom_pct)).size(11))
             .push(container(text("|").size(11)).padding([0, 8]))
+            .push(
+                button(text(doc.indent_label()).size(11))
+                    .on_press(Message::Menu(MenuMsg::ShowIndent))
+                    .padding(0)
+                    .style(button::text),
+            )
+            .push(container(text("|").size(11)).padding([0, 8]))
+            .push(
+                button(text(doc.language().label()).size(11))
+                    .on_press(Message::Menu(MenuMsg::ShowLanguage))
+                    .padding(0)
+                    .style(button::text),
+            )
+            .push(container(text("|").size(11)).padding([0, 8]))
             .push(text(doc.line_ending.label()).size(11))
             .push(container(text("|").size(11)).padding([0, 8]))
-            .push(text(doc.encoding.name()).size(11));
+            .push(text(encoding_label).size(11));
 
         let status_bar = container(status_row)
             .style(bar_style(bg_weak, bg_strong))
@@ -573,7 +1340,13 @@ impl Notepad {
         // --- Stack overlays ---
         let mut layers = Stack::new().push(layout);
 
-        if self.active_menu.is_some() || self.show_context_menu {
+        if self.active_menu.is_some()
+            || self.show_context_menu
+            || self.show_indent_menu
+            || self.show_language_menu
+            || self.show_tab_list
+            || self.show_outline
+        {
             layers = layers.push(
                 mouse_area(Space::new().width(Length::Fill).height(Length::Fill))
                     .on_press(Message::Menu(MenuMsg::CloseAll)),
@@ -586,31 +1359,115 @@ impl Notepad {
                 Menu::File => vec![
                     menu_item_widget(
                         "Nouvel onglet",
-                        "Ctrl+N",
+                        &self.keymap.keys(Action::NewTab),
                         Message::File(FileMsg::NewTab),
                         shortcut_color,
                     ),
+                    menu_item_widget(
+                        "Dupliquer l'onglet",
+                        "",
+                        Message::File(FileMsg::DuplicateTab),
+                        shortcut_color,
+                    ),
                     menu_item_widget(
                         "Ouvrir...",
-                        "Ctrl+O",
+                        &self.keymap.keys(Action::Open),
                         Message::File(FileMsg::Open),
                         shortcut_color,
                     ),
+                    menu_item_widget(
+                        "Ouvrir un dossier...",
+                        "",
+                        Message::Folder(FolderMsg::Choose),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Fichiers récents",
+                        if self.show_recent_files { "◂" } else { "▸" },
+                        Message::File(FileMsg::ToggleRecentFiles),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Rouvrir avec l'encodage",
+                        if self.show_reopen_encodings { "◂" } else { "▸" },
+                        Message::File(FileMsg::ToggleReopenEncodings),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Sessions...",
+                        "",
+                        Message::Session(SessionMsg::Show),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Nouveau depuis un modèle...",
+                        "",
+                        Message::Template(TemplateMsg::Show),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Enregistrer comme modèle...",
+                        "",
+                        Message::Template(TemplateMsg::Show),
+                        shortcut_color,
+                    ),
                     menu_item_widget(
                         "Enregistrer",
-                        "Ctrl+S",
+                        &self.keymap.keys(Action::Save),
                         Message::File(FileMsg::Save),
                         shortcut_color,
                     ),
                     menu_item_widget(
                         "Enregistrer sous...",
-                        "Ctrl+Shift+S",
+                        &self.keymap.keys(Action::SaveAs),
                         Message::File(FileMsg::SaveAs),
                         shortcut_color,
                     ),
+                    menu_item_widget(
+                        "Aperçu avant impression...",
+                        "",
+                        Message::Print(PrintMsg::OpenPreview),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Imprimer...",
+                        &self.keymap.keys(Action::Print),
+                        Message::File(FileMsg::Print),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Imprimer la sélection",
+                        "",
+                        Message::Print(PrintMsg::PrintSelection),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Imprimer plusieurs onglets...",
+                        "",
+                        Message::Print(PrintMsg::OpenQueue),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Exporter les notes en HTML...",
+                        "",
+                        Message::Notes(NotesMsg::Export(ExportFormat::Site)),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Exporter les notes en PDF...",
+                        "",
+                        Message::Notes(NotesMsg::Export(ExportFormat::Pdf)),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Propriétés...",
+                        "",
+                        Message::File(FileMsg::ToggleProperties),
+                        shortcut_color,
+                    ),
                     menu_item_widget(
                         "Fermer l'onglet",
-                        "Ctrl+W",
+                        &self.keymap.keys(Action::CloseTab),
                         Message::File(FileMsg::CloseTab(self.active_tab)),
                         shortcut_color,
                     ),
@@ -618,120 +1475,395 @@ impl Notepad {
                 Menu::Edit => vec![
                     menu_item_widget(
                         "Annuler",
-                        "Ctrl+Z",
+                        &self.keymap.keys(Action::Undo),
                         Message::Edit(EditMsg::Undo),
                         shortcut_color,
                     ),
                     menu_item_widget(
                         "Rétablir",
-                        "Ctrl+Y",
+                        &self.keymap.keys(Action::Redo),
                         Message::Edit(EditMsg::Redo),
                         shortcut_color,
                     ),
                     menu_item_widget(
                         "Couper",
-                        "Ctrl+X",
+                        &self.keymap.keys(Action::Cut),
                         Message::Edit(EditMsg::Cut),
                         shortcut_color,
                     ),
                     menu_item_widget(
                         "Copier",
-                        "Ctrl+C",
+                        &self.keymap.keys(Action::Copy),
                         Message::Edit(EditMsg::Copy),
                         shortcut_color,
                     ),
                     menu_item_widget(
                         "Coller",
-                        "Ctrl+V",
+                        &self.keymap.keys(Action::Paste),
                         Message::Edit(EditMsg::Paste),
                         shortcut_color,
                     ),
+                    menu_item_widget(
+                        "Coller sans formatage",
+                        &self.keymap.keys(Action::PastePlain),
+                        Message::Edit(EditMsg::PastePlain),
+                        shortcut_color,
+                    ),
                     menu_item_widget(
                         "Tout sélectionner",
-                        "Ctrl+A",
+                        &self.keymap.keys(Action::SelectAll),
                         Message::Edit(EditMsg::SelectAll),
                         shortcut_color,
                     ),
                     menu_item_widget(
                         "Date/Heure",
-                        "F5",
+                        &self.keymap.keys(Action::InsertDateTime),
                         Message::Edit(EditMsg::InsertDateTime),
                         shortcut_color,
                     ),
-                ],
-                Menu::Search => vec![
                     menu_item_widget(
-                        "Rechercher...",
-                        "Ctrl+F",
-                        Message::Search(SearchMsg::OpenFind),
+                        "Format de la date",
+                        if self.show_date_formats { "◂" } else { "▸" },
+                        Message::Edit(EditMsg::ToggleDateFormats),
                         shortcut_color,
                     ),
                     menu_item_widget(
-                        "Remplacer...",
-                        "Ctrl+H",
-                        Message::Search(SearchMsg::OpenReplace),
+                        "Insérer un caractère...",
+                        "",
+                        Message::Edit(EditMsg::ShowCharPicker),
                         shortcut_color,
                     ),
                     menu_item_widget(
-                        "Aller à la ligne...",
-                        "Ctrl+G",
-                        Message::Search(SearchMsg::OpenGoTo),
+                        "Monter le paragraphe",
+                        &self.keymap.keys(Action::MoveParagraphUp),
+                        Message::Edit(EditMsg::MoveParagraphUp),
                         shortcut_color,
                     ),
-                ],
-                Menu::View => {
-                    let theme_label = if self.dark_mode {
-                        "Mode clair"
-                    } else {
-                        "Mode sombre"
-                    };
-                    let wrap_label = if self.word_wrap {
-                        "Désactiver le retour à la ligne"
-                    } else {
-                        "Retour à la ligne"
-                    };
-                    vec![
-                        menu_item_widget(
-                            theme_label,
-                            "",
-                            Message::View(ViewMsg::ToggleDarkMode),
-                            shortcut_color,
-                        ),
-                        menu_item_widget(
-                            wrap_label,
-                            "Alt+Z",
-                            Message::View(ViewMsg::ToggleWordWrap),
-                            shortcut_color,
-                        ),
-                        menu_item_widget(
-                            "Zoom +",
-                            "Ctrl+=",
-                            Message::View(ViewMsg::ZoomIn),
-                            shortcut_color,
-                        ),
-                        menu_item_widget(
-                            "Zoom -",
-                            "Ctrl+-",
-                            Message::View(ViewMsg::ZoomOut),
-                            shortcut_color,
-                        ),
-                        menu_item_widget(
-                            "Zoom réinitialiser",
-                            "Ctrl+0",
-                            Message::View(ViewMsg::ZoomReset),
-                            shortcut_color,
-                        ),
-                        menu_item_widget(
-                            "Paramètres",
-                            "",
-                            Message::Settings(SettingsMsg::Open),
-                            shortcut_color,
-                        ),
-                    ]
-                }
-                Menu::Format => crate::FONT_FAMILIES
-                    .iter()
-                    .map(|&family| {
+                    menu_item_widget(
+                        "Descendre le paragraphe",
+                        &self.keymap.keys(Action::MoveParagraphDown),
+                        Message::Edit(EditMsg::MoveParagraphDown),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Joindre les lignes",
+                        &self.keymap.keys(Action::JoinLines),
+                        Message::Edit(EditMsg::JoinLines),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Commenter / décommenter",
+                        &self.keymap.keys(Action::ToggleComment),
+                        Message::Edit(EditMsg::ToggleComment),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Scinder à la largeur...",
+                        "",
+                        Message::Edit(EditMsg::HardWrap),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Supprimer les espaces de fin",
+                        "",
+                        Message::Edit(EditMsg::TrimTrailingWhitespace),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Tabulations en espaces",
+                        "",
+                        Message::Edit(EditMsg::TabsToSpaces),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Espaces en tabulations",
+                        "",
+                        Message::Edit(EditMsg::SpacesToTabs),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        if self.macro_recording.is_some() {
+                            "Arrêter l'enregistrement"
+                        } else {
+                            "Démarrer l'enregistrement"
+                        },
+                        &self.keymap.keys(Action::ToggleMacroRecording),
+                        Message::Edit(EditMsg::ToggleMacroRecording),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Rejouer la macro",
+                        &self.keymap.keys(Action::PlayMacro),
+                        Message::Edit(EditMsg::PlayMacro),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Rejouer la macro plusieurs fois...",
+                        "",
+                        Message::Edit(EditMsg::PlayMacroTimes),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Insérer un tableau",
+                        "",
+                        Message::Table(TableMsg::Insert),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Créer un instantané",
+                        "",
+                        Message::Snapshot(SnapshotMsg::Create),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Étiquettes du document...",
+                        "",
+                        Message::Notes(NotesMsg::EditTags),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Ajouter la sélection à un fichier...",
+                        "",
+                        Message::Edit(EditMsg::AppendSelection),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Filtrer via une commande...",
+                        "",
+                        Message::Edit(EditMsg::FilterSelection),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Exécuter un script...",
+                        "",
+                        Message::Edit(EditMsg::ShowScripts),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Code QR...",
+                        "",
+                        Message::Edit(EditMsg::ShowQrCode),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Définition",
+                        "",
+                        Message::Edit(EditMsg::Define),
+                        shortcut_color,
+                    ),
+                ],
+                Menu::Search => vec![
+                    menu_item_widget(
+                        "Rechercher...",
+                        &self.keymap.keys(Action::Find),
+                        Message::Search(SearchMsg::OpenFind),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Remplacer...",
+                        &self.keymap.keys(Action::Replace),
+                        Message::Search(SearchMsg::OpenReplace),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Aller à la ligne...",
+                        &self.keymap.keys(Action::GoTo),
+                        Message::Search(SearchMsg::OpenGoTo),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Rechercher dans les notes...",
+                        &self.keymap.keys(Action::SearchNotes),
+                        Message::Notes(NotesMsg::OpenSearch),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Signets",
+                        if self.show_bookmarks_menu { "◂" } else { "▸" },
+                        Message::Search(SearchMsg::ToggleBookmarksMenu),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Position précédente",
+                        &self.keymap.keys(Action::NavigateBack),
+                        Message::Search(SearchMsg::NavigateBack),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Position suivante",
+                        &self.keymap.keys(Action::NavigateForward),
+                        Message::Search(SearchMsg::NavigateForward),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Dernière modification",
+                        &self.keymap.keys(Action::LastEdit),
+                        Message::Search(SearchMsg::LastEdit),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Modification précédente",
+                        &self.keymap.keys(Action::PreviousEdit),
+                        Message::Search(SearchMsg::PreviousEdit),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Modification suivante",
+                        &self.keymap.keys(Action::NextEdit),
+                        Message::Search(SearchMsg::NextEdit),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Paragraphe précédent",
+                        &self.keymap.keys(Action::PreviousParagraph),
+                        Message::Search(SearchMsg::PreviousParagraph),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Paragraphe suivant",
+                        &self.keymap.keys(Action::NextParagraph),
+                        Message::Search(SearchMsg::NextParagraph),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Titre précédent",
+                        &self.keymap.keys(Action::PreviousHeading),
+                        Message::Search(SearchMsg::PreviousHeading),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Titre suivant",
+                        &self.keymap.keys(Action::NextHeading),
+                        Message::Search(SearchMsg::NextHeading),
+                        shortcut_color,
+                    ),
+                ],
+                Menu::View => {
+                    let theme_label = if self.dark_mode {
+                        "Mode clair"
+                    } else {
+                        "Mode sombre"
+                    };
+                    let wrap_label = if self.word_wrap {
+                        "Désactiver le retour à la ligne"
+                    } else {
+                        "Retour à la ligne"
+                    };
+                    let mut items = vec![
+                        menu_item_widget(
+                            theme_label,
+                            "",
+                            Message::View(ViewMsg::ToggleDarkMode),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            wrap_label,
+                            &self.keymap.keys(Action::ToggleWordWrap),
+                            Message::View(ViewMsg::ToggleWordWrap),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Diviser verticalement",
+                            "",
+                            Message::View(ViewMsg::SplitVertical),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Diviser horizontalement",
+                            "",
+                            Message::View(ViewMsg::SplitHorizontal),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Zoom +",
+                            &self.keymap.keys(Action::ZoomIn),
+                            Message::View(ViewMsg::ZoomIn),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Zoom -",
+                            &self.keymap.keys(Action::ZoomOut),
+                            Message::View(ViewMsg::ZoomOut),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Zoom réinitialiser",
+                            &self.keymap.keys(Action::ZoomReset),
+                            Message::View(ViewMsg::ZoomReset),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Tâches en arrière-plan",
+                            "",
+                            Message::Job(JobMsg::TogglePanel),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Terminal",
+                            "",
+                            Message::Terminal(TerminalMsg::TogglePanel),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Instantanés",
+                            "",
+                            Message::Snapshot(SnapshotMsg::TogglePanel),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Utilisation de la mémoire",
+                            "",
+                            Message::View(ViewMsg::ToggleMemory),
+                            shortcut_color,
+                        ),
+                        menu_item_widget(
+                            "Paramètres",
+                            "",
+                            Message::Settings(SettingsMsg::Open),
+                            shortcut_color,
+                        ),
+                    ];
+                    if self.split.is_some() {
+                        items.insert(
+                            4,
+                            menu_item_widget(
+                                "Fermer la division",
+                                "",
+                                Message::View(ViewMsg::CloseSplit),
+                                shortcut_color,
+                            ),
+                        );
+                    }
+                    let mut ink_items = vec![menu_item_widget(
+                        if self.show_ink {
+                            "Masquer les annotations"
+                        } else {
+                            "Annotations manuscrites"
+                        },
+                        "",
+                        Message::Ink(InkMsg::Toggle),
+                        shortcut_color,
+                    )];
+                    if self.show_ink {
+                        ink_items.push(menu_item_widget(
+                            "Annuler le dernier trait",
+                            "",
+                            Message::Ink(InkMsg::UndoStroke),
+                            shortcut_color,
+                        ));
+                        ink_items.push(menu_item_widget(
+                            "Effacer les annotations",
+                            "",
+                            Message::Ink(InkMsg::Clear),
+                            shortcut_color,
+                        ));
+                    }
+                    items.splice(2..2, ink_items);
+                    items
+                }
+                Menu::Format => crate::FONT_FAMILIES
+                    .iter()
+                    .map(|&family| {
                         let label = if family == self.font_family {
                             format!("• {}", family)
                         } else {
@@ -745,6 +1877,56 @@ impl Notepad {
                         )
                     })
                     .collect(),
+                Menu::Window => {
+                    let mut items = vec![menu_item_widget(
+                        "Fermer les onglets sans modification",
+                        "",
+                        Message::File(FileMsg::CloseUnmodifiedTabs),
+                        shortcut_color,
+                    )];
+                    items.extend(self.name_order().into_iter().map(|i| {
+                        let tab_doc = &self.tabs[i];
+                        let marker = if i == self.active_tab { "•" } else { " " };
+                        let folder = tab_doc
+                            .file_path
+                            .as_ref()
+                            .and_then(|p| p.parent())
+                            .map_or(String::new(), |p| elide_start(&p.to_string_lossy(), 24));
+                        menu_item_widget(
+                            &format!("{marker} {}", tab_doc.title_label()),
+                            &folder,
+                            Message::File(FileMsg::SwitchTab(i)),
+                            shortcut_color,
+                        )
+                    }));
+                    items
+                }
+                Menu::Help => vec![
+                    menu_item_widget(
+                        "Raccourcis clavier",
+                        &self.keymap.keys(Action::ShowShortcuts),
+                        Message::Help(HelpMsg::ToggleShortcuts),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Afficher les journaux",
+                        "",
+                        Message::Help(HelpMsg::ShowLogs),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Vérifier les mises à jour",
+                        "",
+                        Message::Help(HelpMsg::CheckUpdates),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "À propos de Notepad",
+                        "",
+                        Message::Help(HelpMsg::ToggleAbout),
+                        shortcut_color,
+                    ),
+                ],
             };
 
             let item_count = items.len();
@@ -764,62 +1946,3472 @@ impl Notepad {
                 popup_h,
                 self.window_width,
                 self.window_height,
+                self.pixel_scale(),
             );
             layers = layers.push(overlay_at(dropdown, top_offset, left_offset));
+
+            // Recent files, unfolded beside their File menu item
+            if menu == Menu::File && self.show_recent_files {
+                let mut recent: Vec<Element<'_, Message>> = self
+                    .recent_files
+                    .entries
+                    .iter()
+                    .map(|path| {
+                        let name = path
+                            .file_name()
+                            .map_or(path.display().to_string(), |n| n.to_string_lossy().into_owned());
+                        let folder = path
+                            .parent()
+                            .map_or(String::new(), |p| elide_start(&p.to_string_lossy(), 24));
+                        menu_item_widget(
+                            &name,
+                            &folder,
+                            Message::File(FileMsg::OpenRecent(path.clone())),
+                            shortcut_color,
+                        )
+                    })
+                    .collect();
+                if recent.is_empty() {
+                    recent.push(
+                        container(text("Aucun fichier récent").size(12).color(shortcut_color))
+                            .padding([4, 8])
+                            .width(MENU_ITEM_WIDTH)
+                            .into(),
+                    );
+                } else {
+                    recent.push(menu_item_widget(
+                        "Vider la liste",
+                        "",
+                        Message::File(FileMsg::ClearRecentFiles),
+                        shortcut_color,
+                    ));
+                }
+                let (sub_w, sub_h) = menu_popup_size(recent.len());
+                let item_top = top_offset
+                    + RECENT_FILES_MENU_ROW as f32 * (MENU_ITEM_HEIGHT + MENU_ITEM_SPACING);
+                let (sub_left, sub_top) = clamp_popup_position(
+                    left_offset + popup_w,
+                    item_top,
+                    sub_w,
+                    sub_h,
+                    self.window_width,
+                    self.window_height,
+                    self.pixel_scale(),
+                );
+                let submenu = container(
+                    Column::with_children(recent)
+                        .spacing(MENU_ITEM_SPACING)
+                        .padding(MENU_CONTAINER_PADDING),
+                )
+                .style(popup_style(bg_weak, bg_strong));
+                layers = layers.push(overlay_at(submenu, sub_top, sub_left));
+            }
+
+            // F5 formats, the current one marked
+            if menu == Menu::Edit && self.show_date_formats {
+                let formats: Vec<Element<'_, Message>> = datetime::PRESETS
+                    .into_iter()
+                    .map(|(label, format)| {
+                        menu_item_widget(
+                            label,
+                            if format == self.date_format { "✓" } else { "" },
+                            Message::Edit(EditMsg::ChooseDateFormat(format)),
+                            shortcut_color,
+                        )
+                    })
+                    .collect();
+                let (sub_w, sub_h) = menu_popup_size(formats.len());
+                let item_top = top_offset
+                    + DATE_FORMAT_MENU_ROW as f32 * (MENU_ITEM_HEIGHT + MENU_ITEM_SPACING);
+                let (sub_left, sub_top) = clamp_popup_position(
+                    left_offset + popup_w,
+                    item_top,
+                    sub_w,
+                    sub_h,
+                    self.window_width,
+                    self.window_height,
+                    self.pixel_scale(),
+                );
+                let submenu = container(
+                    Column::with_children(formats)
+                        .spacing(MENU_ITEM_SPACING)
+                        .padding(MENU_CONTAINER_PADDING),
+                )
+                .style(popup_style(bg_weak, bg_strong));
+                layers = layers.push(overlay_at(submenu, sub_top, sub_left));
+            }
+
+            // Encodings to decode the file with again, the current one marked
+            if menu == Menu::File && self.show_reopen_encodings {
+                let current = self.active_doc().encoding;
+                let encodings: Vec<Element<'_, Message>> = reopen_encodings()
+                    .into_iter()
+                    .map(|(label, encoding)| {
+                        menu_item_widget(
+                            label,
+                            if encoding == current { "✓" } else { "" },
+                            Message::File(FileMsg::ReopenWithEncoding(encoding)),
+                            shortcut_color,
+                        )
+                    })
+                    .collect();
+                let (sub_w, sub_h) = menu_popup_size(encodings.len());
+                let item_top = top_offset
+                    + REOPEN_ENCODING_MENU_ROW as f32 * (MENU_ITEM_HEIGHT + MENU_ITEM_SPACING);
+                let (sub_left, sub_top) = clamp_popup_position(
+                    left_offset + popup_w,
+                    item_top,
+                    sub_w,
+                    sub_h,
+                    self.window_width,
+                    self.window_height,
+                    self.pixel_scale(),
+                );
+                let submenu = container(
+                    Column::with_children(encodings)
+                        .spacing(MENU_ITEM_SPACING)
+                        .padding(MENU_CONTAINER_PADDING),
+                )
+                .style(popup_style(bg_weak, bg_strong));
+                layers = layers.push(overlay_at(submenu, sub_top, sub_left));
+            }
+
+            // Bookmarks of the document, each shown with the start of its line
+            if menu == Menu::Search && self.show_bookmarks_menu {
+                let doc = self.active_doc();
+                let mut items: Vec<Element<'_, Message>> = vec![
+                    menu_item_widget(
+                        "Basculer le signet",
+                        &self.keymap.keys(Action::ToggleBookmark),
+                        Message::Search(SearchMsg::ToggleBookmark),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Signet suivant",
+                        &self.keymap.keys(Action::NextBookmark),
+                        Message::Search(SearchMsg::NextBookmark),
+                        shortcut_color,
+                    ),
+                    menu_item_widget(
+                        "Signet précédent",
+                        &self.keymap.keys(Action::PreviousBookmark),
+                        Message::Search(SearchMsg::PreviousBookmark),
+                        shortcut_color,
+                    ),
+                ];
+                if doc.bookmarks.is_empty() {
+                    items.push(
+                        container(text("Aucun signet").size(12).color(shortcut_color))
+                            .padding([4, 8])
+                            .width(MENU_ITEM_WIDTH)
+                            .into(),
+                    );
+                } else {
+                    for &line in doc.bookmarks.lines() {
+                        let preview: String = doc.mirror.line(line).trim().chars().take(24).collect();
+                        items.push(menu_item_widget(
+                            &preview,
+                            &format!("Ligne {}", line + 1),
+                            Message::Search(SearchMsg::GoToBookmark(line)),
+                            shortcut_color,
+                        ));
+                    }
+                    items.push(menu_item_widget(
+                        "Effacer les signets",
+                        "",
+                        Message::Search(SearchMsg::ClearBookmarks),
+                        shortcut_color,
+                    ));
+                }
+                let (sub_w, sub_h) = menu_popup_size(items.len());
+                let item_top = top_offset
+                    + BOOKMARKS_MENU_ROW as f32 * (MENU_ITEM_HEIGHT + MENU_ITEM_SPACING);
+                let (sub_left, sub_top) = clamp_popup_position(
+                    left_offset + popup_w,
+                    item_top,
+                    sub_w,
+                    sub_h,
+                    self.window_width,
+                    self.window_height,
+                    self.pixel_scale(),
+                );
+                let submenu = container(
+                    Column::with_children(items)
+                        .spacing(MENU_ITEM_SPACING)
+                        .padding(MENU_CONTAINER_PADDING),
+                )
+                .style(popup_style(bg_weak, bg_strong));
+                layers = layers.push(overlay_at(submenu, sub_top, sub_left));
+            }
         }
 
         // Context menu overlay
         if self.show_context_menu {
-            let ctx_items: Vec<Element<'_, Message>> = vec![
+            // Replacements of the misspelled word under the cursor come first
+            let mut ctx_items: Vec<Element<'_, Message>> = Vec::new();
+            if let Some(found) = &self.spell_suggestions {
+                if found.suggestions.is_empty() {
+                    ctx_items.push(
+                        button(text("Aucune suggestion").size(12).color(shortcut_color))
+                            .style(button::text)
+                            .padding([4, 8])
+                            .width(MENU_ITEM_WIDTH)
+                            .into(),
+                    );
+                }
+                for suggestion in &found.suggestions {
+                    ctx_items.push(menu_item_widget(
+                        suggestion,
+                        "",
+                        Message::Edit(EditMsg::ReplaceMisspelled(suggestion.clone())),
+                        shortcut_color,
+                    ));
+                }
+            }
+            ctx_items.extend([
                 menu_item_widget(
                     "Couper",
-                    "Ctrl+X",
+                    &self.keymap.keys(Action::Cut),
                     Message::Edit(EditMsg::Cut),
                     shortcut_color,
                 ),
                 menu_item_widget(
                     "Copier",
-                    "Ctrl+C",
+                    &self.keymap.keys(Action::Copy),
                     Message::Edit(EditMsg::Copy),
                     shortcut_color,
                 ),
                 menu_item_widget(
                     "Coller",
-                    "Ctrl+V",
+                    &self.keymap.keys(Action::Paste),
                     Message::Edit(EditMsg::Paste),
                     shortcut_color,
                 ),
+                menu_item_widget(
+                    "Coller sans formatage",
+                    &self.keymap.keys(Action::PastePlain),
+                    Message::Edit(EditMsg::PastePlain),
+                    shortcut_color,
+                ),
                 menu_item_widget(
                     "Tout sélectionner",
-                    "Ctrl+A",
+                    &self.keymap.keys(Action::SelectAll),
                     Message::Edit(EditMsg::SelectAll),
                     shortcut_color,
                 ),
-            ];
+            ]);
+            // Markdown table under the cursor
+            let in_table = is_markdown
+                && doc
+                    .content
+                    .line(doc.content.cursor().position.line)
+                    .is_some_and(|l| markdown::is_table_row(&l.text));
+            if in_table {
+                for (label, msg) in [
+                    ("Insérer une ligne", TableMsg::InsertRow),
+                    ("Supprimer la ligne", TableMsg::RemoveRow),
+                    ("Insérer une colonne", TableMsg::InsertColumn),
+                    ("Supprimer la colonne", TableMsg::RemoveColumn),
+                ] {
+                    ctx_items.push(menu_item_widget(label, "", Message::Table(msg), shortcut_color));
+                }
+            }
+
+            let ctx_count = ctx_items.len();
+            let ctx_menu = container(
+                Column::with_children(ctx_items)
+                    .spacing(MENU_ITEM_SPACING)
+                    .padding(MENU_CONTAINER_PADDING),
+            )
+            .style(popup_style(bg_weak, bg_strong));
+
+            let (popup_w, popup_h) = menu_popup_size(ctx_count);
+            let (ctx_x, ctx_y) = clamp_popup_position(
+                self.context_menu_position.x,
+                self.context_menu_position.y,
+                popup_w,
+                popup_h,
+                self.window_width,
+                self.window_height,
+                self.pixel_scale(),
+            );
+            layers = layers.push(overlay_at(ctx_menu, ctx_y, ctx_x));
+        }
+
+        // Path completion popup, below the cursor
+        if let Some(popup) = &self.path_completion {
+            const ROW_HEIGHT: f32 = 24.0;
+            const MAX_ROWS: usize = 8;
+            const POPUP_WIDTH: f32 = 280.0;
+            let items: Vec<Element<'_, Message>> = popup
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    let label = if entry.is_dir {
+                        format!("{}{}", entry.name, popup.separator)
+                    } else {
+                        entry.name.clone()
+                    };
+                    button(text(label).font(editor_font).size(12))
+                        .on_press(Message::Completion(CompletionMsg::Pick(i)))
+                        .style(if i == popup.selected { button::primary } else { button::text })
+                        .width(Length::Fill)
+                        .height(ROW_HEIGHT)
+                        .padding(Padding::from([3, 8]))
+                        .into()
+                })
+                .collect();
+            let rows = popup.entries.len().min(MAX_ROWS);
+            let popup_h = rows as f32 * ROW_HEIGHT + 8.0;
+            let list = container(
+                scrollable(Column::with_children(items)).height(rows as f32 * ROW_HEIGHT),
+            )
+            .padding(4)
+            .width(POPUP_WIDTH)
+            .style(popup_style(bg_weak, bg_strong));
+
+            // Approximate cursor location, from the same metrics as the gutter
+            let cursor = doc.content.cursor().position;
+            let column_chars = doc
+                .content
+                .line(cursor.line)
+                .map_or(0, |l| l.text.get(..cursor.column).map_or(0, |t| t.chars().count()));
+            let x = pane_origin.x + gutter_width + 10.0
+                + column_chars.saturating_sub(popup.prefix_len) as f32 * self.font_size * 0.6;
+            let y = pane_origin.y + 10.0 + (cursor.line as f32 - doc.scroll_offset + 1.0) * line_height;
+            let (x, y) = clamp_popup_position(
+                x,
+                y,
+                POPUP_WIDTH,
+                popup_h,
+                self.window_width,
+                self.window_height,
+                self.pixel_scale(),
+            );
+            layers = layers.push(overlay_at(list, y, x));
+        }
+
+        // Color picker for a clicked gutter swatch
+        if let Some(picker) = &self.color_picker {
+            let swatch = |c: iced::Color| {
+                container(Space::new().width(Length::Fill).height(28)).style(move |_: &Theme| {
+                    container::Style {
+                        background: Some(iced::Background::Color(c)),
+                        border: iced::Border {
+                            color: bg_strong,
+                            width: 1.0,
+                            radius: 3.0.into(),
+                        },
+                        ..Default::default()
+                    }
+                })
+            };
+            let channel_row = |label: &'static str, index: usize, value: u8| {
+                Row::new()
+                    .push(text(label).size(12).width(16))
+                    .push(
+                        slider(0..=255u8, value, move |v| {
+                            Message::Color(ColorMsg::SetChannel(index, v))
+                        })
+                        .width(Length::Fill),
+                    )
+                    .push(text(value.to_string()).size(12).width(30))
+                    .spacing(8)
+                    .align_y(iced::Alignment::Center)
+            };
+            let content = Column::new()
+                .push(text("Couleur").size(16))
+                .push(
+                    Row::new()
+                        .push(swatch(picker.literal.color.to_iced()))
+                        .push(swatch(picker.color.to_iced()))
+                        .spacing(6),
+                )
+                .push(channel_row("R", 0, picker.color.r))
+                .push(channel_row("V", 1, picker.color.g))
+                .push(channel_row("B", 2, picker.color.b))
+                .push(
+                    text_input("#rrggbb", &picker.hex_input)
+                        .on_input(|s| Message::Color(ColorMsg::HexChanged(s)))
+                        .on_submit(Message::Color(ColorMsg::Apply))
+                        .font(editor_font)
+                        .size(12),
+                )
+                .push(
+                    Row::new()
+                        .push(Space::new().width(Length::Fill))
+                        .push(
+                            button(text("Annuler").size(13))
+                                .on_press(Message::Color(ColorMsg::Cancel))
+                                .style(button::secondary)
+                                .padding(Padding::from([4, 16])),
+                        )
+                        .push(
+                            button(text("Appliquer").size(13))
+                                .on_press(Message::Color(ColorMsg::Apply))
+                                .style(button::primary)
+                                .padding(Padding::from([4, 16])),
+                        )
+                        .spacing(8),
+                )
+                .spacing(10)
+                .width(260);
+            const PICKER_WIDTH: f32 = 290.0;
+            const PICKER_HEIGHT: f32 = 270.0;
+            let y = pane_origin.y
+                + 10.0
+                + (picker.line as f32 - doc.scroll_offset + 1.0) * line_height;
+            let (x, y) = clamp_popup_position(
+                pane_origin.x + gutter_width,
+                y,
+                PICKER_WIDTH,
+                PICKER_HEIGHT,
+                self.window_width,
+                self.window_height,
+                self.pixel_scale(),
+            );
+            let popup = container(content)
+                .padding(14)
+                .style(popup_style(bg_weak, bg_strong));
+            layers = layers.push(overlay_at(popup, y, x));
+        }
+
+        // Markdown outline (opened from the breadcrumb), folded to the chosen heading level
+        if self.show_outline && show_breadcrumb {
+            let mut levels = Row::new().spacing(2).push(text("Niveaux").size(11)).push(Space::new().width(6));
+            for level in 1..=6u8 {
+                levels = levels.push(
+                    button(text(level.to_string()).size(11))
+                        .on_press(Message::View(ViewMsg::SetOutlineLevel(level)))
+                        .padding([1, 6])
+                        .style(if level == self.outline_level {
+                            button::primary
+                        } else {
+                            button::secondary
+                        }),
+                );
+            }
+            let current = markdown::heading_path(&doc.cached_headings, doc.content.cursor().position.line)
+                .last()
+                .map(|&i| doc.cached_headings[i].line);
+            let mut entries = Column::new().spacing(MENU_ITEM_SPACING);
+            for heading in doc.cached_headings.iter().filter(|h| h.level <= self.outline_level) {
+                let label = if Some(heading.line) == current {
+                    format!("• {}", heading.title)
+                } else {
+                    format!("  {}", heading.title)
+                };
+                entries = entries.push(
+                    container(menu_item_widget(
+                        &label,
+                        "",
+                        Message::Search(SearchMsg::GoToHeading(heading.line)),
+                        shortcut_color,
+                    ))
+                    .padding(Padding {
+                        left: (heading.level - 1) as f32 * 12.0,
+                        ..Padding::ZERO
+                    }),
+                );
+            }
+            let max_height = (self.window_height - bars_height - 40.0).max(MENU_ITEM_HEIGHT);
+            let outline = container(
+                Column::new()
+                    .push(levels.align_y(iced::Alignment::Center))
+                    .push(scrollable(entries).height(Length::Shrink))
+                    .spacing(6)
+                    .padding(MENU_CONTAINER_PADDING),
+            )
+            .max_height(max_height)
+            .width(300)
+            .style(popup_style(bg_weak, bg_strong));
+            layers = layers.push(overlay_at(outline, bars_height, 4.0));
+        }
+
+        // Open tabs list overlay (opened from the "▼" button)
+        if self.show_tab_list {
+            let list_items: Vec<Element<'_, Message>> = self
+                .tabs
+                .iter()
+                .enumerate()
+                .map(|(i, tab_doc)| {
+                    let label = if i == self.active_tab {
+                        format!("• {}", tab_doc.title_label())
+                    } else {
+                        format!("  {}", tab_doc.title_label())
+                    };
+                    menu_item_widget(&label, "", Message::File(FileMsg::SwitchTab(i)), shortcut_color)
+                })
+                .collect();
+
+            let top = MENU_BAR_HEIGHT + toolbar_height + TAB_BAR_HEIGHT;
+            let (popup_w, popup_h) = menu_popup_size(list_items.len());
+            let list_height = popup_h.min((self.window_height - top).max(MENU_ITEM_HEIGHT));
+            let tab_list = container(
+                scrollable(
+                    Column::with_children(list_items)
+                        .spacing(MENU_ITEM_SPACING)
+                        .padding(MENU_CONTAINER_PADDING),
+                )
+                .height(list_height),
+            )
+            .style(popup_style(bg_weak, bg_strong));
+            let left = (self.window_width - popup_w).max(0.0);
+            layers = layers.push(overlay_at(tab_list, top, left));
+        }
+
+        // Indentation menu overlay (opened from the status bar)
+        if self.show_indent_menu {
+            let mark = |active: bool, label: String| {
+                if active {
+                    format!("• {label}")
+                } else {
+                    format!("  {label}")
+                }
+            };
+            let mut indent_items: Vec<Element<'_, Message>> = vec![menu_item_widget(
+                &mark(doc.indent_style == IndentStyle::Tab, "Tabulations".to_string()),
+                "",
+                Message::Format(FormatMsg::SetIndent(IndentStyle::Tab, doc.indent_size)),
+                shortcut_color,
+            )];
+            for &size in INDENT_SIZE_CHOICES {
+                indent_items.push(menu_item_widget(
+                    &mark(
+                        doc.indent_style == IndentStyle::Space && doc.indent_size == size,
+                        format!("Espaces: {size}"),
+                    ),
+                    "",
+                    Message::Format(FormatMsg::SetIndent(IndentStyle::Space, size)),
+                    shortcut_color,
+                ));
+            }
+
+            let (popup_w, popup_h) = menu_popup_size(indent_items.len());
+            let indent_menu = container(
+                Column::with_children(indent_items)
+                    .spacing(MENU_ITEM_SPACING)
+                    .padding(MENU_CONTAINER_PADDING),
+            )
+            .style(popup_style(bg_weak, bg_strong));
+            let (menu_x, menu_y) = clamp_popup_position(
+                self.context_menu_position.x,
+                self.context_menu_position.y - popup_h,
+                popup_w,
+                popup_h,
+                self.window_width,
+                self.window_height,
+                self.pixel_scale(),
+            );
+            layers = layers.push(overlay_at(indent_menu, menu_y.max(0.0), menu_x));
+        }
+
+        // Language menu overlay (opened from the status bar)
+        if self.show_language_menu {
+            let current = doc.language();
+            let language_items: Vec<Element<'_, Message>> = Language::ALL
+                .into_iter()
+                .map(|language| {
+                    let mark = if language == current { "•" } else { " " };
+                    menu_item_widget(
+                        &format!("{mark} {}", language.label()),
+                        "",
+                        Message::Format(FormatMsg::ChooseLanguage(language)),
+                        shortcut_color,
+                    )
+                })
+                .collect();
+
+            let (popup_w, popup_h) = menu_popup_size(language_items.len());
+            let language_menu = container(
+                Column::with_children(language_items)
+                    .spacing(MENU_ITEM_SPACING)
+                    .padding(MENU_CONTAINER_PADDING),
+            )
+            .style(popup_style(bg_weak, bg_strong));
+            let (menu_x, menu_y) = clamp_popup_position(
+                self.context_menu_position.x,
+                self.context_menu_position.y - popup_h,
+                popup_w,
+                popup_h,
+                self.window_width,
+                self.window_height,
+                self.pixel_scale(),
+            );
+            layers = layers.push(overlay_at(language_menu, menu_y.max(0.0), menu_x));
+        }
+
+        // Bottom-right corner: toasts stacked above the background jobs panel
+        let mut corner = Column::new().spacing(6).align_x(iced::Alignment::End);
+        for toast in self.toasts.iter() {
+            let accent = match toast.severity {
+                Severity::Info => palette.primary.base.color,
+                Severity::Success => palette.success.base.color,
+                Severity::Warning => palette.warning.base.color,
+                Severity::Error => palette.danger.base.color,
+            };
+            let toast_row = row![
+                container(Space::new().width(4).height(Length::Fill)).style(move |_: &Theme| {
+                    container::Style {
+                        background: Some(iced::Background::Color(accent)),
+                        ..Default::default()
+                    }
+                }),
+                container(text(toast.message.clone()).size(12))
+                    .padding([6, 8])
+                    .width(Length::Fill),
+                button(text("✕").size(11))
+                    .on_press(Message::Toast(ToastMsg::Dismiss(toast.id)))
+                    .padding([6, 8])
+                    .style(button::text),
+            ]
+            .height(Length::Shrink);
+            corner = corner.push(
+                container(toast_row)
+                    .width(300)
+                    .clip(true)
+                    .style(popup_style(bg_weak, bg_strong)),
+            );
+        }
+
+        // Named snapshots panel, under the tab bar on the right
+        if self.show_snapshots {
+            let mut rows = Column::new().spacing(8).padding(10).width(320);
+            rows = rows.push(
+                row![
+                    text("Instantanés").size(13),
+                    Space::new().width(Length::Fill),
+                    button(text("✕").size(11))
+                        .on_press(Message::Snapshot(SnapshotMsg::TogglePanel))
+                        .padding([0, 4])
+                        .style(button::text),
+                ]
+                .align_y(iced::Alignment::Center),
+            );
+            rows = rows.push(
+                row![
+                    text_input("Nom de l'instantané", &self.snapshot_name)
+                        .id(snapshot_name_input_id())
+                        .on_input(|name| Message::Snapshot(SnapshotMsg::NameChanged(name)))
+                        .on_submit(Message::Snapshot(SnapshotMsg::Save))
+                        .size(12)
+                        .padding(4),
+                    button(text("Créer").size(11))
+                        .on_press(Message::Snapshot(SnapshotMsg::Save))
+                        .padding([3, 8])
+                        .style(button::primary),
+                ]
+                .spacing(6)
+                .align_y(iced::Alignment::Center),
+            );
+            if doc.snapshots.is_empty() {
+                rows = rows.push(
+                    text("Aucun instantané pour ce document").size(11).color(shortcut_color),
+                );
+            }
+            let mut list = Column::new().spacing(6);
+            for (i, snapshot) in doc.snapshots.iter().enumerate().rev() {
+                list = list.push(
+                    row![
+                        Column::new()
+                            .push(text(snapshot.name.clone()).size(12))
+                            .push(
+                                text(format!("{} — {} lignes", snapshot.taken, snapshot.lines))
+                                .size(10)
+                                .color(shortcut_color),
+                            )
+                            .width(Length::Fill),
+                        button(text("Restaurer").size(11))
+                            .on_press(Message::Snapshot(SnapshotMsg::Restore(i)))
+                            .padding([2, 8])
+                            .style(button::secondary),
+                        button(text("✕").size(11))
+                            .on_press(Message::Snapshot(SnapshotMsg::Delete(i)))
+                            .padding([2, 6])
+                            .style(button::text),
+                    ]
+                    .spacing(6)
+                    .align_y(iced::Alignment::Center),
+                );
+            }
+            rows = rows.push(scrollable(list).height(Length::Shrink));
+            let top = MENU_BAR_HEIGHT
+                + if self.show_toolbar { TOOLBAR_HEIGHT } else { 0.0 }
+                + TAB_BAR_HEIGHT
+                + 4.0;
+            layers = layers.push(
+                container(
+                    container(rows)
+                        .max_height(400)
+                        .style(popup_style(bg_weak, bg_strong)),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(Padding {
+                    top,
+                    right: 8.0,
+                    bottom: 0.0,
+                    left: 0.0,
+                })
+                .align_x(iced::Alignment::End),
+            );
+        }
+
+        // Background jobs panel
+        if self.show_jobs {
+            let mut rows = Column::new().spacing(8).padding(10).width(300);
+            rows = rows.push(
+                row![
+                    text("Tâches en arrière-plan").size(13),
+                    Space::new().width(Length::Fill),
+                    button(text("✕").size(11))
+                        .on_press(Message::Job(JobMsg::TogglePanel))
+                        .padding([0, 4])
+                        .style(button::text),
+                ]
+                .align_y(iced::Alignment::Center),
+            );
+            if self.jobs.len() == 0 {
+                rows = rows.push(text("Aucune tâche en cours").size(11).color(shortcut_color));
+            }
+            for job in self.jobs.iter() {
+                let cancel: Element<'_, Message> = if job.cancellable {
+                    button(text("Annuler").size(11))
+                        .on_press(Message::Job(JobMsg::Cancel(job.id)))
+                        .padding([2, 8])
+                        .style(button::secondary)
+                        .into()
+                } else {
+                    Space::new().into()
+                };
+                rows = rows.push(
+                    Column::new()
+                        .spacing(4)
+                        .push(
+                            row![
+                                text(job.label.clone()).size(12),
+                                Space::new().width(Length::Fill),
+                                cancel,
+                            ]
+                            .align_y(iced::Alignment::Center),
+                        )
+                        .push(progress_bar(0.0..=1.0, job.progress).girth(6))
+                        .push(
+                            text(format!(
+                                "{} % — {} s",
+                                (job.progress * 100.0) as u32,
+                                job.started.elapsed().as_secs()
+                            ))
+                            .size(10)
+                            .color(shortcut_color),
+                        ),
+                );
+            }
+            corner = corner.push(container(rows).style(popup_style(bg_weak, bg_strong)));
+        }
+        if self.show_jobs || !self.toasts.is_empty() {
+            layers = layers.push(
+                container(corner)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .padding(Padding {
+                        top: 0.0,
+                        right: 8.0,
+                        bottom: 32.0,
+                        left: 0.0,
+                    })
+                    .align_x(iced::Alignment::End)
+                    .align_y(iced::Alignment::End),
+            );
+        }
+
+        // Ctrl+Tab switcher overlay (MRU mode, visible while Ctrl is held)
+        if let Some((order, selected)) = &self.tab_switcher {
+            let entries: Vec<Element<'_, Message>> = order
+                .iter()
+                .enumerate()
+                .filter_map(|(pos, &i)| {
+                    let tab_doc = self.tabs.get(i)?;
+                    Some(
+                        container(text(tab_doc.title_label()).size(12))
+                            .padding([4, 12])
+                            .width(Length::Fill)
+                            .style(if pos == *selected {
+                                container::primary
+                            } else {
+                                container::transparent
+                            })
+                            .into(),
+                    )
+                })
+                .collect();
+            let switcher = container(
+                Column::with_children(entries)
+                    .spacing(MENU_ITEM_SPACING)
+                    .padding(MENU_CONTAINER_PADDING)
+                    .width(MENU_ITEM_WIDTH + 80.0),
+            )
+            .style(popup_style(bg_weak, bg_strong));
+            layers = layers.push(
+                container(switcher)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Settings modal ---
+        if self.show_settings {
+            // Semi-transparent backdrop
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Settings(SettingsMsg::Close));
+            layers = layers.push(backdrop);
+
+            // Modal content
+            let title_row = Row::new()
+                .push(text("Paramètres").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Settings(SettingsMsg::Close))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Theme toggle
+            let theme_btn_label = if self.dark_mode { "Sombre" } else { "Clair" };
+            let theme_row = Row::new()
+                .push(text("Thème").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text(theme_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetDarkMode(!self.dark_mode)))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Font size
+            let font_row = Row::new()
+                .push(text("Taille de police").size(14).width(Length::FillPortion(1)))
+                .push(
+                    Row::new()
+                        .push(
+                            button(text("-").size(13))
+                                .on_press(Message::Settings(SettingsMsg::SetFontSize(
+                                    self.font_size - crate::ZOOM_STEP,
+                                )))
+                                .style(button::secondary)
+                                .padding(Padding::from([4, 10])),
+                        )
+                        .push(
+                            container(text(format!("{}", self.font_size as u32)).size(13))
+                                .padding(Padding::from([4, 12])),
+                        )
+                        .push(
+                            button(text("+").size(13))
+                                .on_press(Message::Settings(SettingsMsg::SetFontSize(
+                                    self.font_size + crate::ZOOM_STEP,
+                                )))
+                                .style(button::secondary)
+                                .padding(Padding::from([4, 10])),
+                        )
+                        .spacing(4)
+                        .align_y(iced::Alignment::Center),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Word wrap toggle
+            let wrap_btn_label = if self.word_wrap { "Activé" } else { "Désactivé" };
+            let wrap_row = Row::new()
+                .push(
+                    text("Retour à la ligne")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(wrap_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetWordWrap(!self.word_wrap)))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Ligatures, for prose and for code
+            let ligature_button = |label: &'static str, on: bool, msg: fn(bool) -> SettingsMsg| {
+                Row::new()
+                    .push(text(label).size(14).width(Length::FillPortion(1)))
+                    .push(
+                        button(text(if on { "Activées" } else { "Désactivées" }).size(13))
+                            .on_press(Message::Settings(msg(!on)))
+                            .style(button::secondary)
+                            .padding(Padding::from([4, 16])),
+                    )
+                    .align_y(iced::Alignment::Center)
+                    .width(Length::Fill)
+            };
+            let mut ligatures_column = Column::new()
+                .push(ligature_button(
+                    "Ligatures (texte, Markdown)",
+                    self.prose_ligatures,
+                    SettingsMsg::SetProseLigatures,
+                ))
+                .push(Space::new().height(12))
+                .push(ligature_button(
+                    "Ligatures (code)",
+                    self.code_ligatures,
+                    SettingsMsg::SetCodeLigatures,
+                ));
+            if !ligatures::has_ligatures(&self.font_family) {
+                ligatures_column = ligatures_column.push(
+                    text(format!("La police {} n'a pas de ligatures", self.font_family))
+                        .size(11)
+                        .color(shortcut_color),
+                );
+            }
+
+            // Session restore toggle
+            let session_btn_label = if self.restore_session {
+                "Activé"
+            } else {
+                "Désactivé"
+            };
+            let session_row = Row::new()
+                .push(
+                    text("Restaurer la session")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(session_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetRestoreSession(
+                            !self.restore_session,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Toolbar toggle
+            let toolbar_btn_label = if self.show_toolbar { "Affichée" } else { "Masquée" };
+            let toolbar_row = Row::new()
+                .push(
+                    text("Barre d'outils")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(toolbar_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetShowToolbar(
+                            !self.show_toolbar,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Ctrl+Tab order
+            let mru_btn_label = if self.mru_tab_switching {
+                "Récents"
+            } else {
+                "Position"
+            };
+            let mru_row = Row::new()
+                .push(
+                    text("Ordre de Ctrl+Tab")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(mru_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetMruTabSwitching(
+                            !self.mru_tab_switching,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Find bar closing by itself once typing resumes
+            let auto_hide_find_btn_label = if self.auto_hide_find {
+                "En tapant"
+            } else {
+                "Manuellement"
+            };
+            let auto_hide_find_row = Row::new()
+                .push(
+                    text("Fermeture de la barre de recherche")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(auto_hide_find_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetAutoHideFind(
+                            !self.auto_hide_find,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Escape past the menus and dialogs, and clicks into the text
+            let mut escape_orders = Row::new().spacing(4);
+            for order in EscapeOrder::ALL {
+                escape_orders = escape_orders.push(
+                    button(text(order.label()).size(12))
+                        .on_press(Message::Settings(SettingsMsg::SetEscapeOrder(order)))
+                        .style(if self.escape_order == order { button::primary } else { button::secondary })
+                        .padding(Padding::from([4, 10])),
+                );
+            }
+            let click_btn_label = if self.click_closes_bars { "Les ferme" } else { "Les laisse" };
+            let escape_column = Column::new()
+                .push(
+                    Row::new()
+                        .push(text("Touche Échap").size(14).width(Length::FillPortion(1)))
+                        .push(escape_orders)
+                        .align_y(iced::Alignment::Center)
+                        .width(Length::Fill),
+                )
+                .push(text(self.escape_order.description()).size(11).color(shortcut_color))
+                .push(Space::new().height(12))
+                .push(
+                    Row::new()
+                        .push(
+                            text("Clic dans l'éditeur et barres de recherche")
+                                .size(14)
+                                .width(Length::FillPortion(1)),
+                        )
+                        .push(
+                            button(text(click_btn_label).size(13))
+                                .on_press(Message::Settings(SettingsMsg::SetClickClosesBars(
+                                    !self.click_closes_bars,
+                                )))
+                                .style(button::secondary)
+                                .padding(Padding::from([4, 16])),
+                        )
+                        .align_y(iced::Alignment::Center)
+                        .width(Length::Fill),
+                );
+
+            // Cursor placement when opening a file
+            let cursor_btn_label = if self.remember_cursor_position {
+                "Dernière position"
+            } else {
+                "Début du fichier"
+            };
+            let cursor_row = Row::new()
+                .push(
+                    text("Curseur à l'ouverture")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(cursor_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetRememberCursorPosition(
+                            !self.remember_cursor_position,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Advisory locking of opened files
+            let lock_btn_label = if self.lock_documents { "Activé" } else { "Désactivé" };
+            let lock_row = Row::new()
+                .push(
+                    text("Verrouiller les fichiers ouverts")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(lock_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetLockDocuments(
+                            !self.lock_documents,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Unloading of background tabs under memory pressure
+            let hibernate_btn_label = if self.hibernate_tabs { "Activé" } else { "Désactivé" };
+            let hibernate_row = Row::new()
+                .push(
+                    text("Hiberner les onglets inactifs")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(hibernate_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetHibernateTabs(
+                            !self.hibernate_tabs,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Folder where [[wiki links]] open and create notes
+            let notes_folder_label = self
+                .notes_folder
+                .as_ref()
+                .and_then(|f| f.file_name())
+                .map_or("Dossier du document".to_string(), |n| n.to_string_lossy().into_owned());
+            let mut notes_folder_row = Row::new()
+                .push(text("Dossier des notes").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text(notes_folder_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::ChooseNotesFolder))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .spacing(4)
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+            if self.notes_folder.is_some() {
+                notes_folder_row = notes_folder_row.push(
+                    button(text("✕").size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetNotesFolder(None)))
+                        .style(button::text),
+                );
+            }
+
+            // EditorConfig's trim_trailing_whitespace wins over this one
+            let trim_btn_label = if self.trim_trailing_whitespace { "Supprimés" } else { "Conservés" };
+            let trim_row = Row::new()
+                .push(
+                    text("Espaces de fin de ligne à l'enregistrement")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(trim_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetTrimTrailingWhitespace(
+                            !self.trim_trailing_whitespace,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Indentation of new documents; a file keeps the one detected in it
+            let next_tab_width = INDENT_SIZE_CHOICES
+                .iter()
+                .copied()
+                .find(|&w| w > self.tab_width)
+                .unwrap_or(INDENT_SIZE_CHOICES[0]);
+            let tab_width_row = Row::new()
+                .push(text("Largeur des tabulations").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text(self.tab_width.to_string()).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetTabWidth(next_tab_width)))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+            let insert_spaces_btn_label = if self.insert_spaces { "Des espaces" } else { "Une tabulation" };
+            let insert_spaces_row = Row::new()
+                .push(text("La touche Tab insère").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text(insert_spaces_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetInsertSpaces(!self.insert_spaces)))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // For those who switch to a terminal to build
+            let focus_save_btn_label = if self.save_on_focus_loss { "Activé" } else { "Désactivé" };
+            let focus_save_row = Row::new()
+                .push(
+                    text("Enregistrer en quittant la fenêtre")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(focus_save_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetSaveOnFocusLoss(
+                            !self.save_on_focus_loss,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let low_power_btn_label = if self.low_power { "Activé" } else { "Désactivé" };
+            let low_power_row = Row::new()
+                .push(text("Économie d'énergie").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text(low_power_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetLowPower(!self.low_power)))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Files on network shares are written through a temporary file
+            let safe_save_btn_label = if self.network_safe_save { "Activé" } else { "Désactivé" };
+            let safe_save_row = Row::new()
+                .push(
+                    text("Partages réseau : enregistrement sécurisé")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(safe_save_btn_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetNetworkSafeSave(
+                            !self.network_safe_save,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let next_interval = NETWORK_AUTOSAVE_CHOICES
+                .iter()
+                .copied()
+                .find(|&m| m > self.network_autosave_minutes)
+                .unwrap_or(NETWORK_AUTOSAVE_CHOICES[0]);
+            let network_autosave_row = Row::new()
+                .push(
+                    text("Enregistrement auto. sur le réseau")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(format!("{} min", self.network_autosave_minutes)).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetNetworkAutosaveMinutes(
+                            next_interval,
+                        )))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Saves to a share slower than this are queued on a worker from then on
+            let next_slow_save = SLOW_SAVE_CHOICES_MS
+                .iter()
+                .copied()
+                .find(|&ms| ms > self.slow_save_ms)
+                .unwrap_or(SLOW_SAVE_CHOICES_MS[0]);
+            let slow_save_label = match self.slow_save_ms {
+                0 => "Désactivé".to_string(),
+                ms if ms % 1000 == 0 => format!("Au-delà de {} s", ms / 1000),
+                ms => format!("Au-delà de {},{} s", ms / 1000, ms % 1000 / 100),
+            };
+            let slow_save_row = Row::new()
+                .push(
+                    text("Partages lents : file d'enregistrement")
+                        .size(14)
+                        .width(Length::FillPortion(1)),
+                )
+                .push(
+                    button(text(slow_save_label).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetSlowSaveMs(next_slow_save)))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // strftime-like, previewed as it is typed
+            let date_format_row = Row::new()
+                .push(text("Format de Date/Heure (F5)").size(14).width(Length::FillPortion(1)))
+                .push(
+                    text_input(datetime::DEFAULT_FORMAT, &self.date_format)
+                        .on_input(|v| Message::Settings(SettingsMsg::DateFormatChanged(v)))
+                        .size(13)
+                        .width(150),
+                )
+                .push(
+                    text(LocalTime::now().format(&self.date_format))
+                        .size(12)
+                        .color(shortcut_color)
+                        .width(170),
+                )
+                .spacing(8)
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Markdown links from pasted URLs
+            let mut url_paste_modes = Row::new().spacing(4);
+            for mode in UrlPaste::ALL {
+                url_paste_modes = url_paste_modes.push(
+                    button(text(mode.label()).size(12))
+                        .on_press(Message::Settings(SettingsMsg::SetUrlPaste(mode)))
+                        .style(if self.url_paste == mode { button::primary } else { button::secondary })
+                        .padding(Padding::from([4, 10])),
+                );
+            }
+            let url_paste_column = Column::new()
+                .push(
+                    Row::new()
+                        .push(text("URL collée en lien Markdown").size(14).width(Length::FillPortion(1)))
+                        .push(url_paste_modes)
+                        .align_y(iced::Alignment::Center)
+                        .width(Length::Fill),
+                )
+                .push(
+                    text("Une URL collée sur une sélection donne [sélection](url), et sans sélection [titre](url) une fois la page lue")
+                        .size(11)
+                        .color(shortcut_color),
+                );
+
+            // See-through window over reference material
+            let mut opacity_row = Row::new()
+                .push(text("Opacité de la fenêtre").size(14).width(Length::FillPortion(1)))
+                .push(
+                    slider(MIN_WINDOW_OPACITY..=100u8, self.window_opacity, |v| {
+                        Message::Settings(SettingsMsg::SetWindowOpacity(v))
+                    })
+                    .on_release(Message::Settings(SettingsMsg::SaveWindowOpacity))
+                    .width(110),
+                )
+                .push(text(format!("{} %", self.window_opacity)).size(13).width(44))
+                .spacing(8)
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+            if !opacity::SUPPORTED {
+                opacity_row = opacity_row.push(
+                    tooltip(
+                        text("ⓘ").size(13),
+                        container(text("Non pris en charge sur ce système").size(11))
+                            .padding([3, 6])
+                            .style(popup_style(bg_weak, bg_strong)),
+                        tooltip::Position::Left,
+                    ),
+                );
+            }
+
+            // On top of the system's display scaling, for monitors it gets wrong
+            let next_scale = dpi::UI_SCALE_CHOICES
+                .iter()
+                .copied()
+                .find(|&p| p > self.ui_scale)
+                .unwrap_or(dpi::UI_SCALE_CHOICES[0]);
+            let ui_scale_row = Row::new()
+                .push(text("Échelle de l'interface").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text(format!("{} %", self.ui_scale)).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetUiScale(next_scale)))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Proxy of the network features; the password goes to the keychain
+            let mut proxy_modes = Row::new().spacing(4);
+            for mode in ProxyMode::ALL {
+                proxy_modes = proxy_modes.push(
+                    button(text(mode.label()).size(12))
+                        .on_press(Message::Settings(SettingsMsg::SetProxyMode(mode)))
+                        .style(if self.proxy.mode == mode { button::primary } else { button::secondary })
+                        .padding(Padding::from([4, 10])),
+                );
+            }
+            let mut proxy_column = Column::new().push(
+                Row::new()
+                    .push(text("Proxy").size(14).width(Length::FillPortion(1)))
+                    .push(proxy_modes)
+                    .align_y(iced::Alignment::Center)
+                    .width(Length::Fill),
+            );
+            if self.proxy.mode == ProxyMode::Manual {
+                let password_row = Row::new()
+                    .push(
+                        text_input("Mot de passe", &self.proxy_password)
+                            .on_input(|v| Message::Settings(SettingsMsg::ProxyPasswordChanged(v)))
+                            .on_submit(Message::Settings(SettingsMsg::SaveProxyPassword))
+                            .secure(true)
+                            .size(13)
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        button(text("Enregistrer").size(12))
+                            .on_press_maybe(
+                                (!self.proxy.user.is_empty())
+                                    .then_some(Message::Settings(SettingsMsg::SaveProxyPassword)),
+                            )
+                            .style(button::secondary)
+                            .padding(Padding::from([4, 10])),
+                    )
+                    .spacing(4)
+                    .align_y(iced::Alignment::Center);
+                proxy_column = proxy_column
+                    .push(Space::new().height(6))
+                    .push(
+                        text_input("Adresse (proxy.local:3128)", &self.proxy.address)
+                            .on_input(|v| Message::Settings(SettingsMsg::ProxyAddressChanged(v)))
+                            .size(13),
+                    )
+                    .push(Space::new().height(4))
+                    .push(
+                        text_input("Utilisateur (facultatif)", &self.proxy.user)
+                            .on_input(|v| Message::Settings(SettingsMsg::ProxyUserChanged(v)))
+                            .size(13),
+                    )
+                    .push(Space::new().height(4))
+                    .push(password_row);
+            }
+
+            // Shortcuts are edited in their own dialog
+            let keybindings_row = Row::new()
+                .push(text("Raccourcis clavier").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text("Personnaliser...").size(13))
+                        .on_press(Message::Settings(SettingsMsg::ToggleKeybindings))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Spell checking: off, or one of the installed Hunspell dictionaries
+            let spell_choices: Vec<Option<String>> =
+                std::iter::once(None).chain(self.spell_dictionaries.iter().cloned().map(Some)).collect();
+            let next_dictionary = spell_choices
+                .iter()
+                .position(|d| *d == self.spell_dictionary)
+                .and_then(|i| spell_choices.get(i + 1))
+                .cloned()
+                .flatten();
+            let mut spelling_row = Row::new()
+                .push(text("Orthographe").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text(self.spell_dictionary.as_deref().unwrap_or("Désactivée")).size(13))
+                        .on_press(Message::Settings(SettingsMsg::SetSpellDictionary(next_dictionary)))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .spacing(4)
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+            if self.spell_dictionaries.is_empty() {
+                spelling_row = spelling_row.push(tooltip(
+                    text("ⓘ").size(13),
+                    container(
+                        text(format!(
+                            "Copiez un dictionnaire Hunspell (.aff et .dic) dans {}",
+                            dictionary::folder().display()
+                        ))
+                        .size(11),
+                    )
+                    .padding([3, 6])
+                    .style(popup_style(bg_weak, bg_strong)),
+                    tooltip::Position::Left,
+                ));
+            }
+
+            let abbreviations_row = Row::new()
+                .push(text("Abréviations").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text(format!("Modifier ({})...", self.abbreviations.len())).size(13))
+                        .on_press(Message::Settings(SettingsMsg::ToggleAbbreviations))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let snippets_row = Row::new()
+                .push(text("Extraits (Tab)").size(14).width(Length::FillPortion(1)))
+                .push(
+                    button(text(format!("Modifier ({})...", self.snippets.len())).size(13))
+                        .on_press(Message::Settings(SettingsMsg::ToggleSnippets))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(16))
+                    .push(theme_row)
+                    .push(Space::new().height(12))
+                    .push(font_row)
+                    .push(Space::new().height(12))
+                    .push(wrap_row)
+                    .push(Space::new().height(12))
+                    .push(ligatures_column)
+                    .push(Space::new().height(12))
+                    .push(session_row)
+                    .push(Space::new().height(12))
+                    .push(toolbar_row)
+                    .push(Space::new().height(12))
+                    .push(mru_row)
+                    .push(Space::new().height(12))
+                    .push(auto_hide_find_row)
+                    .push(Space::new().height(12))
+                    .push(escape_column)
+                    .push(Space::new().height(12))
+                    .push(cursor_row)
+                    .push(Space::new().height(12))
+                    .push(lock_row)
+                    .push(Space::new().height(12))
+                    .push(hibernate_row)
+                    .push(Space::new().height(12))
+                    .push(notes_folder_row)
+                    .push(Space::new().height(12))
+                    .push(trim_row)
+                    .push(Space::new().height(12))
+                    .push(tab_width_row)
+                    .push(Space::new().height(12))
+                    .push(insert_spaces_row)
+                    .push(Space::new().height(12))
+                    .push(focus_save_row)
+                    .push(Space::new().height(12))
+                    .push(low_power_row)
+                    .push(Space::new().height(12))
+                    .push(safe_save_row)
+                    .push(Space::new().height(12))
+                    .push(network_autosave_row)
+                    .push(Space::new().height(12))
+                    .push(slow_save_row)
+                    .push(Space::new().height(12))
+                    .push(date_format_row)
+                    .push(Space::new().height(12))
+                    .push(url_paste_column)
+                    .push(Space::new().height(12))
+                    .push(opacity_row)
+                    .push(Space::new().height(12))
+                    .push(ui_scale_row)
+                    .push(Space::new().height(12))
+                    .push(keybindings_row)
+                    .push(Space::new().height(12))
+                    .push(abbreviations_row)
+                    .push(Space::new().height(12))
+                    .push(snippets_row)
+                    .push(Space::new().height(12))
+                    .push(spelling_row)
+                    .push(Space::new().height(12))
+                    .push(proxy_column)
+                    .width(350),
+            )
+            .padding(24)
+            .style(popup_style(bg_weak, bg_strong));
+
+            let centered = container(modal_content)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill);
+
+            layers = layers.push(centered);
+        }
+
+        // --- Keybindings editor, over the settings ---
+        if self.show_settings && self.show_keybindings {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Settings(SettingsMsg::ToggleKeybindings));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Raccourcis clavier").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Settings(SettingsMsg::ToggleKeybindings))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let hint = if self.rebinding.is_some() {
+                "Appuyez sur les nouvelles touches (Échap pour annuler)"
+            } else {
+                "Enregistrés dans keybindings.json"
+            };
+
+            let actions = shortcuts::actions();
+            let mut list = Column::new().spacing(4);
+            for category in shortcuts::Category::ALL {
+                list = list.push(Space::new().height(6)).push(
+                    text(category.label())
+                        .size(14)
+                        .font(Font {
+                            weight: iced::font::Weight::Bold,
+                            ..Font::default()
+                        }),
+                );
+                for &action in actions.iter().filter(|&&a| shortcuts::category(a) == category) {
+                    let conflicts = self.keymap.conflicts(action);
+                    let keys = if self.rebinding == Some(action) {
+                        "…".to_string()
+                    } else {
+                        self.keymap.all_keys(action)
+                    };
+                    let keys_text = text(if keys.is_empty() { "—".to_string() } else { keys }).size(12);
+                    let keys_text = if conflicts.is_empty() {
+                        keys_text.color(shortcut_color)
+                    } else {
+                        keys_text.color(iced::Color::from_rgb(0.85, 0.25, 0.25))
+                    };
+                    let mut row = Row::new()
+                        .push(text(shortcuts::label(action)).size(13).width(Length::Fill))
+                        .push(keys_text)
+                        .spacing(8)
+                        .align_y(iced::Alignment::Center);
+                    if !conflicts.is_empty() {
+                        let labels: Vec<&str> = conflicts.into_iter().map(shortcuts::label).collect();
+                        row = row.push(tooltip(
+                            text("⚠").size(13),
+                            container(text(format!("Aussi utilisé par : {}", labels.join(", "))).size(11))
+                                .padding([3, 6])
+                                .style(popup_style(bg_weak, bg_strong)),
+                            tooltip::Position::Left,
+                        ));
+                    }
+                    if shortcuts::is_native(action) {
+                        row = row.push(
+                            tooltip(
+                                text("ⓘ").size(13),
+                                container(text("Fixé par la zone de texte").size(11))
+                                    .padding([3, 6])
+                                    .style(popup_style(bg_weak, bg_strong)),
+                                tooltip::Position::Left,
+                            ),
+                        );
+                    } else {
+                        row = row
+                            .push(
+                                button(text("Modifier").size(12))
+                                    .on_press(Message::Settings(SettingsMsg::Rebind(action)))
+                                    .style(if self.rebinding == Some(action) {
+                                        button::primary
+                                    } else {
+                                        button::secondary
+                                    })
+                                    .padding(Padding::from([2, 8])),
+                            )
+                            .push(
+                                button(text("↺").size(12))
+                                    .on_press_maybe(
+                                        (!self.keymap.is_default(action))
+                                            .then_some(Message::Settings(SettingsMsg::ResetBinding(action))),
+                                    )
+                                    .style(button::text)
+                                    .padding(Padding::from([2, 6])),
+                            );
+                    }
+                    list = list.push(row);
+                }
+            }
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(4))
+                    .push(text(hint).size(12).color(shortcut_color))
+                    .push(Space::new().height(6))
+                    .push(scrollable(list).height(Length::Shrink))
+                    .push(Space::new().height(12))
+                    .push(
+                        button(text("Tout réinitialiser").size(12))
+                            .on_press(Message::Settings(SettingsMsg::ResetAllBindings))
+                            .style(button::secondary)
+                            .padding(Padding::from([4, 12])),
+                    )
+                    .width(460),
+            )
+            .padding(24)
+            .max_height(600)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Abbreviations editor, over the settings ---
+        if self.show_settings && self.show_abbreviations {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Settings(SettingsMsg::ToggleAbbreviations));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Abréviations").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Settings(SettingsMsg::ToggleAbbreviations))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let mut list = Column::new().spacing(6);
+            if self.abbreviations.is_empty() {
+                list = list.push(text("Aucune abréviation").size(13));
+            }
+            for (index, entry) in self.abbreviations.iter().enumerate() {
+                // The language button goes through every document, then each language
+                let next_language = match entry.language {
+                    None => Some(Language::ALL[0]),
+                    Some(language) => Language::ALL
+                        .iter()
+                        .position(|&l| l == language)
+                        .and_then(|i| Language::ALL.get(i + 1).copied()),
+                };
+                list = list.push(
+                    Row::new()
+                        .push(
+                            text_input("Abréviation", &entry.abbreviation)
+                                .on_input(move |v| {
+                                    Message::Settings(SettingsMsg::AbbreviationChanged(index, v))
+                                })
+                                .size(13)
+                                .width(90),
+                        )
+                        .push(
+                            text_input("Texte (\\n pour un saut de ligne)", &entry.expansion)
+                                .on_input(move |v| Message::Settings(SettingsMsg::ExpansionChanged(index, v)))
+                                .size(13)
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            button(text(entry.language.map_or("Tous", Language::label)).size(12))
+                                .on_press(Message::Settings(SettingsMsg::SetAbbreviationLanguage(
+                                    index,
+                                    next_language,
+                                )))
+                                .style(button::secondary)
+                                .padding(Padding::from([4, 8]))
+                                .width(90),
+                        )
+                        .push(
+                            button(text("✕").size(13))
+                                .on_press(Message::Settings(SettingsMsg::RemoveAbbreviation(index)))
+                                .style(button::text),
+                        )
+                        .spacing(6)
+                        .align_y(iced::Alignment::Center),
+                );
+            }
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(4))
+                    .push(
+                        text("Remplacées dès qu'un espace, un saut de ligne ou une ponctuation les suit")
+                            .size(12)
+                            .color(shortcut_color),
+                    )
+                    .push(Space::new().height(10))
+                    .push(scrollable(list).height(Length::Shrink))
+                    .push(Space::new().height(12))
+                    .push(
+                        button(text("Ajouter").size(12))
+                            .on_press(Message::Settings(SettingsMsg::AddAbbreviation))
+                            .style(button::secondary)
+                            .padding(Padding::from([4, 12])),
+                    )
+                    .width(520),
+            )
+            .padding(24)
+            .max_height(560)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Snippets editor, over the settings ---
+        if self.show_settings && self.show_snippets {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Settings(SettingsMsg::ToggleSnippets));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Extraits").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Settings(SettingsMsg::ToggleSnippets))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let mut list = Column::new().spacing(6);
+            if self.snippets.is_empty() {
+                list = list.push(text("Aucun extrait").size(13));
+            }
+            for (index, entry) in self.snippets.iter().enumerate() {
+                let next_language = match entry.language {
+                    None => Some(Language::ALL[0]),
+                    Some(language) => Language::ALL
+                        .iter()
+                        .position(|&l| l == language)
+                        .and_then(|i| Language::ALL.get(i + 1).copied()),
+                };
+                list = list.push(
+                    Row::new()
+                        .push(
+                            text_input("Déclencheur", &entry.trigger)
+                                .on_input(move |v| {
+                                    Message::Settings(SettingsMsg::SnippetTriggerChanged(index, v))
+                                })
+                                .size(13)
+                                .width(90),
+                        )
+                        .push(
+                            text_input("Texte (\\n, $CURSOR pour le curseur)", &entry.body)
+                                .on_input(move |v| Message::Settings(SettingsMsg::SnippetBodyChanged(index, v)))
+                                .size(13)
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            button(text(entry.language.map_or("Tous", Language::label)).size(12))
+                                .on_press(Message::Settings(SettingsMsg::SetSnippetLanguage(
+                                    index,
+                                    next_language,
+                                )))
+                                .style(button::secondary)
+                                .padding(Padding::from([4, 8]))
+                                .width(90),
+                        )
+                        .push(
+                            button(text("✕").size(13))
+                                .on_press(Message::Settings(SettingsMsg::RemoveSnippet(index)))
+                                .style(button::text),
+                        )
+                        .spacing(6)
+                        .align_y(iced::Alignment::Center),
+                );
+            }
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(4))
+                    .push(
+                        text("Développés par Tab juste après leur déclencheur, le curseur placé sur $CURSOR")
+                            .size(12)
+                            .color(shortcut_color),
+                    )
+                    .push(Space::new().height(10))
+                    .push(scrollable(list).height(Length::Shrink))
+                    .push(Space::new().height(12))
+                    .push(
+                        button(text("Ajouter").size(12))
+                            .on_press(Message::Settings(SettingsMsg::AddSnippet))
+                            .style(button::secondary)
+                            .padding(Padding::from([4, 12])),
+                    )
+                    .width(520),
+            )
+            .padding(24)
+            .max_height(560)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Memory usage modal ---
+        if self.show_memory {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::View(ViewMsg::ToggleMemory));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Utilisation de la mémoire").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::View(ViewMsg::ToggleMemory))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let usage_row = |name: String, state: &str, usage: MemoryUsage, size: u32| {
+                Row::new()
+                    .push(text(name).size(size).width(Length::FillPortion(3)))
+                    .push(text(state.to_string()).size(size).width(Length::FillPortion(2)))
+                    .push(text(format_size(usage.text)).size(size).width(Length::FillPortion(2)))
+                    .push(text(format_size(usage.history)).size(size).width(Length::FillPortion(2)))
+                    .push(text(format_size(usage.total())).size(size).width(Length::FillPortion(2)))
+                    .spacing(8)
+                    .align_y(iced::Alignment::Center)
+                    .width(Length::Fill)
+            };
+            let header = Row::new()
+                .push(text("Onglet").size(12).width(Length::FillPortion(3)))
+                .push(text("État").size(12).width(Length::FillPortion(2)))
+                .push(text("Texte").size(12).width(Length::FillPortion(2)))
+                .push(text("Historique").size(12).width(Length::FillPortion(2)))
+                .push(text("Total").size(12).width(Length::FillPortion(2)))
+                .spacing(8)
+                .width(Length::Fill);
+
+            let mut rows = Column::new().spacing(6);
+            let mut total = MemoryUsage::default();
+            for doc in &self.tabs {
+                let usage = doc.memory_usage();
+                total.text += usage.text;
+                total.history += usage.history;
+                let state = if doc.hibernation.is_some() {
+                    "Hiberné"
+                } else {
+                    "Chargé"
+                };
+                rows = rows.push(usage_row(doc.title_label(), state, usage, 13));
+            }
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(16))
+                    .push(header)
+                    .push(Space::new().height(6))
+                    .push(scrollable(rows).height(Length::Shrink))
+                    .push(Space::new().height(10))
+                    .push(usage_row("Total".to_string(), "", total, 14))
+                    .push(Space::new().height(16))
+                    .push(
+                        button(text("Hiberner les onglets en arrière-plan").size(13))
+                            .on_press(Message::View(ViewMsg::HibernateBackgroundTabs))
+                            .style(button::secondary)
+                            .padding(Padding::from([4, 16])),
+                    )
+                    .width(560),
+            )
+            .padding(24)
+            .max_height(480)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Keyboard shortcut cheat sheet ---
+        if let Some(query) = &self.shortcut_sheet {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Help(HelpMsg::ToggleShortcuts));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Raccourcis clavier").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Help(HelpMsg::ToggleShortcuts))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let search = text_input("Rechercher un raccourci…", query)
+                .id(shortcut_input_id())
+                .on_input(|q| Message::Help(HelpMsg::ShortcutQueryChanged(q)))
+                .size(13)
+                .padding(6);
+
+            let groups = self.keymap.cheat_sheet(query);
+            let mut list = Column::new().spacing(4);
+            if groups.is_empty() {
+                list = list.push(text("Aucun raccourci trouvé").size(13));
+            }
+            for (category, entries) in groups {
+                list = list.push(Space::new().height(6)).push(
+                    text(category.label())
+                        .size(14)
+                        .font(Font {
+                            weight: iced::font::Weight::Bold,
+                            ..Font::default()
+                        }),
+                );
+                for (label, keys) in entries {
+                    list = list.push(
+                        Row::new()
+                            .push(text(label).size(13).width(Length::Fill))
+                            .push(text(keys).size(12).color(shortcut_color))
+                            .spacing(12),
+                    );
+                }
+            }
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(12))
+                    .push(search)
+                    .push(Space::new().height(6))
+                    .push(scrollable(list).height(Length::Shrink))
+                    .width(420),
+            )
+            .padding(24)
+            .max_height(560)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- File properties modal ---
+        if let Some(props) = &self.properties {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::File(FileMsg::ToggleProperties));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Propriétés").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::File(FileMsg::ToggleProperties))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let property_row = |label: &str, value: String| {
+                Row::new()
+                    .push(text(label.to_string()).size(13).width(Length::FillPortion(1)))
+                    .push(text(value).size(13).width(Length::FillPortion(3)))
+                    .spacing(8)
+                    .width(Length::Fill)
+            };
+            let mut rows = Column::new()
+                .spacing(6)
+                .push(property_row("Nom", props.name.clone()));
+            match &props.path {
+                Some(path) => {
+                    rows = rows.push(property_row("Chemin", path.display().to_string()));
+                    if let Some(target) = &props.target {
+                        rows = rows.push(property_row(
+                            "Lien symbolique vers",
+                            target.display().to_string(),
+                        ));
+                    }
+                    let location = if props.network { "Partage réseau" } else { "Disque local" };
+                    rows = rows.push(property_row("Emplacement", location.to_string()));
+                    if let Some(size) = props.size {
+                        rows = rows.push(property_row("Taille", format_size(size as usize)));
+                    }
+                    if let Some(modified) = &props.modified {
+                        rows = rows.push(property_row("Modifié", modified.clone()));
+                    }
+                }
+                None => rows = rows.push(property_row("Chemin", "Document non enregistré".to_string())),
+            }
+            rows = rows
+                .push(property_row("Encodage", props.encoding.to_string()))
+                .push(property_row("Fin de ligne", props.line_ending.to_string()));
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(16))
+                    .push(rows)
+                    .width(480),
+            )
+            .padding(24)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Filter command prompt ---
+        if let Some(command) = &self.filter_prompt {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Edit(EditMsg::CloseFilter));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Filtrer via une commande").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Edit(EditMsg::CloseFilter))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let buttons = Row::new()
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("Filtrer").size(13))
+                        .on_press(Message::Edit(EditMsg::RunFilter))
+                        .padding(Padding::from([4, 16])),
+                )
+                .width(Length::Fill);
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(12))
+                    .push(
+                        text_input("sort, jq ., fmt -w 72", command)
+                            .id(filter_command_input_id())
+                            .on_input(|command| Message::Edit(EditMsg::FilterCommandChanged(command)))
+                            .on_submit(Message::Edit(EditMsg::RunFilter))
+                            .font(iced::Font::MONOSPACE)
+                            .size(13)
+                            .padding(6),
+                    )
+                    .push(Space::new().height(4))
+                    .push(
+                        text("La sélection est envoyée à la commande, puis remplacée par ce qu'elle affiche")
+                            .size(11),
+                    )
+                    .push(Space::new().height(16))
+                    .push(buttons)
+                    .width(440),
+            )
+            .padding(24)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Hard wrap column prompt ---
+        if let Some(width) = &self.wrap_prompt {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Edit(EditMsg::CloseHardWrap));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Scinder à la largeur").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Edit(EditMsg::CloseHardWrap))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let width_row = Row::new()
+                .push(
+                    text_input("80", width)
+                        .id(wrap_width_input_id())
+                        .on_input(|width| Message::Edit(EditMsg::HardWrapWidthChanged(width)))
+                        .on_submit(Message::Edit(EditMsg::RunHardWrap))
+                        .size(13)
+                        .padding(6)
+                        .width(80),
+                )
+                .push(text("caractères").size(13))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("Scinder").size(13))
+                        .on_press(Message::Edit(EditMsg::RunHardWrap))
+                        .padding(Padding::from([4, 16])),
+                )
+                .spacing(8)
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(12))
+                    .push(width_row)
+                    .push(Space::new().height(4))
+                    .push(
+                        text("Les lignes sélectionnées, ou celle du curseur, sont coupées entre les mots")
+                            .size(11),
+                    )
+                    .width(360),
+            )
+            .padding(24)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Macro repeat count prompt ---
+        if let Some(count) = &self.macro_prompt {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Edit(EditMsg::CloseMacroPrompt));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Rejouer la macro").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Edit(EditMsg::CloseMacroPrompt))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let count_row = Row::new()
+                .push(
+                    text_input("1", count)
+                        .id(macro_count_input_id())
+                        .on_input(|count| Message::Edit(EditMsg::MacroCountChanged(count)))
+                        .on_submit(Message::Edit(EditMsg::RunMacroTimes))
+                        .size(13)
+                        .padding(6)
+                        .width(80),
+                )
+                .push(text("fois").size(13))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("Rejouer").size(13))
+                        .on_press(Message::Edit(EditMsg::RunMacroTimes))
+                        .padding(Padding::from([4, 16])),
+                )
+                .spacing(8)
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(12))
+                    .push(count_row)
+                    .push(Space::new().height(4))
+                    .push(
+                        text("S'arrête plus tôt quand le curseur atteint la fin du document ou que la macro ne change plus rien")
+                            .size(11),
+                    )
+                    .width(360),
+            )
+            .padding(24)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Definition popup ---
+        if let Some(popup) = &self.definition {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Edit(EditMsg::CloseDefinition));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text(popup.word.clone()).size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Edit(EditMsg::CloseDefinition))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let dim_color = iced::Color { a: 0.6, ..bg_text };
+            let mut body = Column::new().spacing(6);
+            match &popup.result {
+                None => body = body.push(text("Recherche...").size(13).color(dim_color)),
+                Some(Err(e)) => body = body.push(text(e.clone()).size(13).color(dim_color)),
+                Some(Ok(entries)) if entries.is_empty() => {
+                    body = body.push(text("Mot introuvable dans les dictionnaires installés").size(13).color(dim_color));
+                }
+                Some(Ok(entries)) => {
+                    for entry in entries {
+                        body = body.push(text(entry.source.clone()).size(11).color(dim_color));
+                        for (i, definition) in entry.definitions.iter().enumerate() {
+                            body = body.push(text(format!("{}. {definition}", i + 1)).size(13));
+                        }
+                        // Synonyms are looked up in turn when clicked
+                        for (kind, words) in &entry.synonyms {
+                            let mut row = Row::new().spacing(4).align_y(iced::Alignment::Center);
+                            if let Some(kind) = kind {
+                                row = row.push(text(format!("{kind} :")).size(12).color(dim_color));
+                            }
+                            for word in words {
+                                row = row.push(
+                                    button(text(word.clone()).size(12))
+                                        .on_press(Message::Edit(EditMsg::DefineWord(word.clone())))
+                                        .style(button::text)
+                                        .padding(Padding::from([1, 4])),
+                                );
+                            }
+                            body = body.push(row.wrap());
+                        }
+                    }
+                }
+            }
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(12))
+                    .push(scrollable(body).height(Length::Shrink))
+                    .width(440),
+            )
+            .max_height(460)
+            .padding(24)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Document tags modal ---
+        if let Some(editor) = &self.tag_editor {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Notes(NotesMsg::CloseTags));
+            layers = layers.push(backdrop);
+
+            let name = editor
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let title_row = Row::new()
+                .push(text(format!("Étiquettes de {name}")).size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Notes(NotesMsg::CloseTags))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let favorite_label = if editor.favorite {
+                "★ Favori"
+            } else {
+                "☆ Ajouter aux favoris"
+            };
+            let buttons = Row::new()
+                .push(
+                    button(text(favorite_label).size(13))
+                        .on_press(Message::Notes(NotesMsg::ToggleFavorite))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("Enregistrer").size(13))
+                        .on_press(Message::Notes(NotesMsg::SaveTags))
+                        .padding(Padding::from([4, 16])),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(12))
+                    .push(
+                        text_input("projet, idée, à faire", &editor.input)
+                            .id(tags_input_id())
+                            .on_input(|input| Message::Notes(NotesMsg::TagsInputChanged(input)))
+                            .on_submit(Message::Notes(NotesMsg::SaveTags))
+                            .size(13)
+                            .padding(6),
+                    )
+                    .push(Space::new().height(4))
+                    .push(text("Séparez les étiquettes par des virgules ou des espaces").size(11))
+                    .push(Space::new().height(16))
+                    .push(buttons)
+                    .width(400),
+            )
+            .padding(24)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Note search modal ---
+        if let Some(search) = &self.note_search {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Notes(NotesMsg::CloseSearch));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Rechercher dans les notes").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Notes(NotesMsg::CloseSearch))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let input = text_input("Texte ou #étiquette…", &search.query)
+                .id(note_search_input_id())
+                .on_input(|q| Message::Notes(NotesMsg::QueryChanged(q)))
+                .size(13)
+                .padding(6);
+
+            // Clicking a tag filters on it
+            let mut tag_row = Row::new().spacing(4);
+            for tag in self.notes.all_tags() {
+                tag_row = tag_row.push(
+                    button(text(format!("#{tag}")).size(11))
+                        .on_press(Message::Notes(NotesMsg::QueryChanged(format!("#{tag}"))))
+                        .style(button::secondary)
+                        .padding([2, 6]),
+                );
+            }
+
+            let mut list = Column::new().spacing(2);
+            if search.results.is_empty() {
+                let status = if search.searching {
+                    "Recherche…"
+                } else {
+                    "Aucune note trouvée"
+                };
+                list = list.push(text(status).size(13));
+            }
+            for hit in &search.results {
+                let name = hit
+                    .path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let star = if hit.favorite { "★ " } else { "" };
+                let tags: Vec<String> = hit.tags.iter().map(|t| format!("#{t}")).collect();
+                let mut entry = Column::new()
+                    .push(
+                        Row::new()
+                            .push(text(format!("{star}{name}")).size(13).width(Length::Fill))
+                            .push(text(tags.join(" ")).size(11).color(shortcut_color))
+                            .spacing(8),
+                    )
+                    .push(
+                        text(hit.path.display().to_string())
+                            .size(10)
+                            .color(shortcut_color),
+                    );
+                if let Some(snippet) = &hit.snippet {
+                    entry = entry.push(text(snippet.clone()).size(11));
+                }
+                list = list.push(
+                    button(entry)
+                        .on_press(Message::Notes(NotesMsg::Open(hit.path.clone())))
+                        .style(button::text)
+                        .padding([4, 6])
+                        .width(Length::Fill),
+                );
+            }
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(12))
+                    .push(input)
+                    .push(Space::new().height(6))
+                    .push(scrollable(tag_row).direction(scrollable::Direction::Horizontal(
+                        scrollable::Scrollbar::new().width(4).scroller_width(4),
+                    )))
+                    .push(Space::new().height(6))
+                    .push(scrollable(list).height(Length::Shrink))
+                    .width(520),
+            )
+            .padding(24)
+            .max_height(560)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Script picker modal ---
+        if let Some(picker) = &self.script_picker {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Edit(EditMsg::CloseScripts));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Exécuter un script").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Edit(EditMsg::CloseScripts))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let target = if self.active_doc().content.selection().is_some() {
+                "Le script transforme la sélection"
+            } else {
+                "Le script transforme tout le document"
+            };
+            let mut list = Column::new().spacing(2);
+            match &picker.scripts {
+                None => list = list.push(text("Lecture des scripts…").size(13)),
+                Some(found) if found.is_empty() => {
+                    list = list.push(text("Aucun script (.rhai) dans le dossier").size(13));
+                }
+                Some(found) => {
+                    for path in found {
+                        list = list.push(
+                            button(text(scripts::name(path)).size(13))
+                                .on_press(Message::Edit(EditMsg::RunScript(path.clone())))
+                                .style(button::text)
+                                .padding([4, 6])
+                                .width(Length::Fill),
+                        );
+                    }
+                }
+            }
+
+            let column = Column::new()
+                .push(title_row)
+                .push(
+                    text(scripts::folder().display().to_string())
+                        .size(11)
+                        .color(shortcut_color),
+                )
+                .push(Space::new().height(12))
+                .push(text(target).size(13))
+                .push(Space::new().height(6))
+                .push(scrollable(list).height(Length::Shrink));
+
+            let modal_content = container(column.width(420))
+                .padding(24)
+                .max_height(480)
+                .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Character picker modal ---
+        if let Some(picker) = &self.char_picker {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Edit(EditMsg::CloseCharPicker));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Insérer un caractère").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Edit(EditMsg::CloseCharPicker))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            // Enter inserts the character typed or designated by its code
+            let designated = charmap::designated_char(&picker.query);
+            let search = text_input("Bloc, caractère ou code (flèches, €, U+2192)", &picker.query)
+                .id(char_search_input_id())
+                .on_input(|query| Message::Edit(EditMsg::CharQueryChanged(query)))
+                .on_submit_maybe(designated.map(|c| Message::Edit(EditMsg::InsertChar(c))))
+                .size(13)
+                .padding(6);
+
+            let char_button = |c: char| {
+                button(text(c.to_string()).size(18).center())
+                    .on_press(Message::Edit(EditMsg::InsertChar(c)))
+                    .style(button::text)
+                    .padding(2)
+                    .width(34)
+            };
+
+            let mut recent = Row::new().spacing(2).align_y(iced::Alignment::Center);
+            recent = recent.push(text("Récents").size(12).color(shortcut_color).width(64));
+            if self.recent_characters.is_empty() {
+                recent = recent.push(text("aucun").size(12).color(shortcut_color));
+            }
+            for c in self.recent_characters.chars() {
+                recent = recent.push(char_button(c));
+            }
+
+            let mut blocks = Column::new().spacing(2);
+            for index in charmap::search(&picker.query) {
+                let name = charmap::BLOCKS[index].name;
+                let label = if index == picker.block { format!("▸ {name}") } else { name.to_string() };
+                blocks = blocks.push(
+                    button(text(label).size(13))
+                        .on_press(Message::Edit(EditMsg::ChooseCharBlock(index)))
+                        .style(button::text)
+                        .padding([3, 6])
+                        .width(Length::Fill),
+                );
+            }
+
+            const PER_ROW: usize = 9;
+            let chars: Vec<char> = charmap::BLOCKS[picker.block].chars().collect();
+            let mut grid = Column::new().spacing(2);
+            for row_chars in chars.chunks(PER_ROW) {
+                grid = grid.push(row_chars.iter().fold(Row::new().spacing(2), |row, &c| row.push(char_button(c))));
+            }
+
+            let footer = match designated {
+                Some(c) => format!("Entrée insère {c} ({})", charmap::code_point(c)),
+                None => format!(
+                    "{} : {} à {}",
+                    charmap::BLOCKS[picker.block].name,
+                    charmap::code_point(chars.first().copied().unwrap_or_default()),
+                    charmap::code_point(chars.last().copied().unwrap_or_default()),
+                ),
+            };
+
+            let column = Column::new()
+                .push(title_row)
+                .push(Space::new().height(8))
+                .push(search)
+                .push(Space::new().height(8))
+                .push(recent)
+                .push(Space::new().height(8))
+                .push(
+                    Row::new()
+                        .push(scrollable(blocks).width(200).height(320))
+                        .push(scrollable(grid).width(Length::Fill).height(320))
+                        .spacing(12),
+                )
+                .push(Space::new().height(6))
+                .push(text(footer).size(11).color(shortcut_color));
+
+            let modal_content = container(column.width(600))
+                .padding(24)
+                .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Print queue modal ---
+        if let Some(queue) = &self.print_queue {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Print(PrintMsg::CloseQueue));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Imprimer plusieurs onglets").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Print(PrintMsg::CloseQueue))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let mut list = Column::new().spacing(2);
+            for doc in &self.tabs {
+                let mark = if queue.docs.contains(&doc.id) { "☑" } else { "☐" };
+                list = list.push(
+                    button(text(format!("{mark} {}", doc.title_label())).size(13))
+                        .on_press(Message::Print(PrintMsg::ToggleQueued(doc.id)))
+                        .style(button::text)
+                        .padding([4, 6])
+                        .width(Length::Fill),
+                );
+            }
+
+            let buttons = Row::new()
+                .push(text(format!("{} onglet(s)", queue.docs.len())).size(12).color(shortcut_color))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("Imprimer").size(13))
+                        .on_press_maybe((!queue.docs.is_empty()).then_some(Message::Print(PrintMsg::PrintQueued)))
+                        .style(button::primary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .push(
+                    button(text("Annuler").size(13))
+                        .on_press(Message::Print(PrintMsg::CloseQueue))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .spacing(10)
+                .align_y(iced::Alignment::Center);
+
+            let column = Column::new()
+                .push(title_row)
+                .push(Space::new().height(6))
+                .push(text("Chaque onglet commence sur une nouvelle page, sous son nom").size(12))
+                .push(Space::new().height(12))
+                .push(scrollable(list).height(Length::Shrink))
+                .push(Space::new().height(12))
+                .push(buttons);
+
+            let modal_content = container(column.width(420))
+                .padding(24)
+                .max_height(480)
+                .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Save As encoding and line ending modal ---
+        if let Some(dialog) = &self.save_as {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::File(FileMsg::CancelSaveAs));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Enregistrer sous").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::File(FileMsg::CancelSaveAs))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let encoding_row = Row::new()
+                .push(text("Encodage").size(13))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    pick_list(dialog.options.as_slice(), Some(dialog.choice), |choice| {
+                        Message::File(FileMsg::SetSaveEncoding(choice))
+                    })
+                    .text_size(13)
+                    .width(200),
+                )
+                .align_y(iced::Alignment::Center);
+
+            let line_ending_row = Row::new()
+                .push(text("Fins de ligne").size(13))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    pick_list(LineEnding::ALL, Some(dialog.line_ending), |eol| {
+                        Message::File(FileMsg::SetSaveLineEnding(eol))
+                    })
+                    .text_size(13)
+                    .width(200),
+                )
+                .align_y(iced::Alignment::Center);
+
+            let mut column = Column::new()
+                .push(title_row)
+                .push(text(dialog.path.display().to_string()).size(11).color(shortcut_color))
+                .push(Space::new().height(12))
+                .push(encoding_row)
+                .push(Space::new().height(8))
+                .push(line_ending_row);
+            if !dialog.unmappable.is_empty() {
+                let chars: String = dialog.unmappable.iter().map(|c| format!(" {c}")).collect();
+                column = column.push(Space::new().height(8)).push(
+                    text(format!(
+                        "⚠ Caractères absents de {} :{chars} — ils seront enregistrés sous la forme &#…;",
+                        dialog.choice
+                    ))
+                    .size(12)
+                    .color(palette.warning.base.color),
+                );
+            }
+            let buttons = Row::new()
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("Enregistrer").size(13))
+                        .on_press(Message::File(FileMsg::ConfirmSaveAs))
+                        .style(button::primary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .push(
+                    button(text("Annuler").size(13))
+                        .on_press(Message::File(FileMsg::CancelSaveAs))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .spacing(10);
+            column = column.push(Space::new().height(16)).push(buttons);
+
+            let modal_content = container(column.width(420))
+                .padding(24)
+                .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- QR code modal ---
+        if let Some(popup) = &self.qr_popup {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Edit(EditMsg::CloseQrCode));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Code QR").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Edit(EditMsg::CloseQrCode))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let preview: String = popup.text.lines().next().unwrap_or_default().chars().take(60).collect();
+            let buttons = Row::new()
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("Copier").size(13))
+                        .on_press(Message::Edit(EditMsg::CopyQrCode))
+                        .style(button::primary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .push(
+                    button(text("Enregistrer...").size(13))
+                        .on_press(Message::Edit(EditMsg::SaveQrCode))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .push(
+                    button(text("Fermer").size(13))
+                        .on_press(Message::Edit(EditMsg::CloseQrCode))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .spacing(10);
+
+            let column = Column::new()
+                .push(title_row)
+                .push(text(preview).size(11).color(shortcut_color))
+                .push(Space::new().height(12))
+                .push(
+                    container(canvas(&popup.code).width(QR_CODE_SIZE).height(QR_CODE_SIZE))
+                        .center_x(Length::Fill),
+                )
+                .push(Space::new().height(16))
+                .push(buttons);
+
+            let modal_content = container(column.width(360))
+                .padding(24)
+                .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Folder picker modal ---
+        if let Some(picker) = &self.folder_picker {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Folder(FolderMsg::Close));
+            layers = layers.push(backdrop);
+
+            let name = picker
+                .folder
+                .file_name()
+                .map_or(picker.folder.display().to_string(), |n| n.to_string_lossy().into_owned());
+            let title_row = Row::new()
+                .push(text(format!("Dossier {name}")).size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Folder(FolderMsg::Close))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let input = text_input("Filtrer les fichiers…", &picker.query)
+                .id(folder_filter_input_id())
+                .on_input(|q| Message::Folder(FolderMsg::QueryChanged(q)))
+                .on_submit(Message::Folder(FolderMsg::OpenFirst))
+                .size(13)
+                .padding(6);
+
+            let mut list = Column::new().spacing(2);
+            let mut footer = None;
+            match &picker.files {
+                None => list = list.push(text("Lecture du dossier…").size(13)),
+                Some(files) => {
+                    let mut shown = 0;
+                    for rel in folder::filter(files, &picker.query) {
+                        list = list.push(
+                            button(text(rel.display().to_string()).size(13))
+                                .on_press(Message::Folder(FolderMsg::Open(rel.clone())))
+                                .style(button::text)
+                                .padding([4, 6])
+                                .width(Length::Fill),
+                        );
+                        shown += 1;
+                    }
+                    if shown == 0 {
+                        list = list.push(text("Aucun fichier texte").size(13));
+                    }
+                    if files.len() >= MAX_FOLDER_FILES {
+                        footer = Some(format!("Seuls les {MAX_FOLDER_FILES} premiers fichiers sont listés"));
+                    }
+                }
+            }
+
+            let mut column = Column::new()
+                .push(title_row)
+                .push(
+                    text(picker.folder.display().to_string())
+                        .size(11)
+                        .color(shortcut_color),
+                )
+                .push(Space::new().height(12))
+                .push(input)
+                .push(Space::new().height(6))
+                .push(scrollable(list).height(Length::Shrink));
+            if let Some(footer) = footer {
+                column = column
+                    .push(Space::new().height(6))
+                    .push(text(footer).size(11).color(shortcut_color));
+            }
+
+            let modal_content = container(column.width(520))
+                .padding(24)
+                .max_height(560)
+                .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Named sessions modal ---
+        if let Some(name_input) = &self.session_manager {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Session(SessionMsg::Close));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text("Sessions").size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Session(SessionMsg::Close))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let name_row = Row::new()
+                .push(
+                    text_input("Nom de la session (Travail, Blog…)", name_input)
+                        .id(session_name_input_id())
+                        .on_input(|name| Message::Session(SessionMsg::NameChanged(name)))
+                        .on_submit(Message::Session(SessionMsg::Save))
+                        .size(13)
+                        .padding(6),
+                )
+                .push(
+                    button(text("Enregistrer les onglets").size(13))
+                        .on_press_maybe(
+                            (!name_input.trim().is_empty()).then_some(Message::Session(SessionMsg::Save)),
+                        )
+                        .padding([6, 12]),
+                )
+                .spacing(8)
+                .align_y(iced::Alignment::Center);
+
+            let current = self.named_sessions.current.as_deref();
+            let mut list = Column::new().spacing(2);
+            for session in &self.named_sessions.entries {
+                let marker = if current == Some(session.name.as_str()) { "•" } else { " " };
+                let tab_count = session.session.tabs.len();
+                let label = format!(
+                    "{marker} {} ({tab_count} onglet{})",
+                    session.name,
+                    if tab_count > 1 { "s" } else { "" }
+                );
+                list = list.push(
+                    Row::new()
+                        .push(
+                            button(text(label).size(13))
+                                .on_press(Message::Session(SessionMsg::Open(session.name.clone())))
+                                .style(button::text)
+                                .padding([4, 6])
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            button(text("✕").size(12))
+                                .on_press(Message::Session(SessionMsg::Delete(session.name.clone())))
+                                .style(button::text)
+                                .padding([4, 6]),
+                        )
+                        .align_y(iced::Alignment::Center),
+                );
+            }
+            if self.named_sessions.entries.is_empty() {
+                list = list.push(text("Aucune session enregistrée").size(13).color(shortcut_color));
+            }
+
+            let column = Column::new()
+                .push(title_row)
+                .push(
+                    text("Chaque session garde ses onglets, l'onglet actif et la taille de la fenêtre")
+                        .size(11)
+                        .color(shortcut_color),
+                )
+                .push(Space::new().height(12))
+                .push(name_row)
+                .push(Space::new().height(12))
+                .push(scrollable(list).height(Length::Shrink));
+
+            let modal_content = container(column.width(460))
+                .padding(24)
+                .max_height(520)
+                .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Templates modal ---
+        if let Some(manager) = &self.template_manager {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Template(TemplateMsg::Close));
+            layers = layers.push(backdrop);
+
+            let title = match &manager.filling {
+                Some(fill) => format!("Nouveau depuis « {} »", fill.file_name),
+                None => "Modèles".to_string(),
+            };
+            let title_row = Row::new()
+                .push(text(title).size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Template(TemplateMsg::Close))
+                        .style(button::text),
+                )
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill);
+
+            let column = match &manager.filling {
+                // Variables of the chosen template, then the new tab
+                Some(fill) => {
+                    let mut fields = Column::new().spacing(8);
+                    for (index, (name, value)) in fill.values.iter().enumerate() {
+                        let mut input = text_input("", value)
+                            .on_input(move |value| Message::Template(TemplateMsg::ValueChanged(index, value)))
+                            .on_submit(Message::Template(TemplateMsg::Create))
+                            .size(13)
+                            .padding(6);
+                        if index == 0 {
+                            input = input.id(template_value_input_id());
+                        }
+                        fields = fields.push(
+                            Row::new()
+                                .push(text(name.as_str()).size(13).width(Length::FillPortion(1)))
+                                .push(container(input).width(Length::FillPortion(2)))
+                                .spacing(8)
+                                .align_y(iced::Alignment::Center),
+                        );
+                    }
+                    Column::new()
+                        .push(title_row)
+                        .push(
+                            text("Valeurs remplaçant les {{variables}} du modèle")
+                                .size(11)
+                                .color(shortcut_color),
+                        )
+                        .push(Space::new().height(12))
+                        .push(fields)
+                        .push(Space::new().height(12))
+                        .push(
+                            Row::new().push(Space::new().width(Length::Fill)).push(
+                                button(text("Créer").size(13))
+                                    .on_press(Message::Template(TemplateMsg::Create))
+                                    .padding([6, 12]),
+                            ),
+                        )
+                }
+                // Saving the document, or picking a template
+                None => {
+                    let name_row = Row::new()
+                        .push(
+                            text_input("Nom du modèle (Lettre, Compte rendu…)", &manager.name)
+                                .id(template_name_input_id())
+                                .on_input(|name| Message::Template(TemplateMsg::NameChanged(name)))
+                                .on_submit(Message::Template(TemplateMsg::Save))
+                                .size(13)
+                                .padding(6),
+                        )
+                        .push(
+                            button(text("Enregistrer le document").size(13))
+                                .on_press_maybe(
+                                    (!manager.name.trim().is_empty())
+                                        .then_some(Message::Template(TemplateMsg::Save)),
+                                )
+                                .padding([6, 12]),
+                        )
+                        .spacing(8)
+                        .align_y(iced::Alignment::Center);
 
-            let ctx_count = ctx_items.len();
-            let ctx_menu = container(
-                Column::with_children(ctx_items)
-                    .spacing(MENU_ITEM_SPACING)
-                    .padding(MENU_CONTAINER_PADDING),
-            )
-            .style(popup_style(bg_weak, bg_strong));
+                    let mut list = Column::new().spacing(2);
+                    for file_name in &manager.templates {
+                        list = list.push(
+                            Row::new()
+                                .push(
+                                    button(text(file_name.as_str()).size(13))
+                                        .on_press(Message::Template(TemplateMsg::Use(file_name.clone())))
+                                        .style(button::text)
+                                        .padding([4, 6])
+                                        .width(Length::Fill),
+                                )
+                                .push(
+                                    button(text("✕").size(12))
+                                        .on_press(Message::Template(TemplateMsg::Delete(file_name.clone())))
+                                        .style(button::text)
+                                        .padding([4, 6]),
+                                )
+                                .align_y(iced::Alignment::Center),
+                        );
+                    }
+                    if manager.templates.is_empty() {
+                        list = list.push(text("Aucun modèle enregistré").size(13).color(shortcut_color));
+                    }
 
-            let (popup_w, popup_h) = menu_popup_size(ctx_count);
-            let (ctx_x, ctx_y) = clamp_popup_position(
-                self.context_menu_position.x,
-                self.context_menu_position.y,
-                popup_w,
-                popup_h,
-                self.window_width,
-                self.window_height,
+                    Column::new()
+                        .push(title_row)
+                        .push(
+                            text("{{date}}, {{title}} ou toute autre {{variable}} du document est demandée à chaque nouveau document")
+                                .size(11)
+                                .color(shortcut_color),
+                        )
+                        .push(Space::new().height(12))
+                        .push(name_row)
+                        .push(Space::new().height(12))
+                        .push(scrollable(list).height(Length::Shrink))
+                }
+            };
+
+            let modal_content = container(column.width(460))
+                .padding(24)
+                .max_height(520)
+                .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
             );
-            layers = layers.push(overlay_at(ctx_menu, ctx_y, ctx_x));
         }
 
-        // --- Settings modal ---
-        if self.show_settings {
-            // Semi-transparent backdrop
+        // --- About modal ---
+        if self.show_about {
             let backdrop = mouse_area(
                 container(Space::new().width(Length::Fill).height(Length::Fill)).style(
                     move |_: &Theme| container::Style {
@@ -831,124 +5423,384 @@ impl Notepad {
                     },
                 ),
             )
-            .on_press(Message::Settings(SettingsMsg::Close));
+            .on_press(Message::Help(HelpMsg::ToggleAbout));
             layers = layers.push(backdrop);
 
-            // Modal content
             let title_row = Row::new()
-                .push(text("Paramètres").size(18))
+                .push(text("À propos de Notepad").size(18))
                 .push(Space::new().width(Length::Fill))
                 .push(
                     button(text("✕").size(14))
-                        .on_press(Message::Settings(SettingsMsg::Close))
+                        .on_press(Message::Help(HelpMsg::ToggleAbout))
                         .style(button::text),
                 )
                 .align_y(iced::Alignment::Center)
                 .width(Length::Fill);
 
-            // Theme toggle
-            let theme_btn_label = if self.dark_mode { "Sombre" } else { "Clair" };
-            let theme_row = Row::new()
-                .push(text("Thème").size(14).width(Length::FillPortion(1)))
+            let update_status: Element<'_, Message> = match &self.update_check {
+                UpdateCheck::Idle => Space::new().into(),
+                UpdateCheck::Checking => text("Recherche de mises à jour…").size(13).into(),
+                UpdateCheck::UpToDate => text("Notepad est à jour.").size(13).into(),
+                UpdateCheck::Available(release) => Row::new()
+                    .push(text(format!("Version {} disponible", release.version)).size(13))
+                    .push(Space::new().width(Length::Fill))
+                    .push(
+                        button(text("Télécharger").size(13))
+                            .on_press(Message::Help(HelpMsg::OpenUrl(release.url.clone())))
+                            .style(button::primary)
+                            .padding(Padding::from([4, 16])),
+                    )
+                    .align_y(iced::Alignment::Center)
+                    .width(Length::Fill)
+                    .into(),
+                UpdateCheck::Failed(e) => text(format!("Vérification impossible : {e}")).size(13).into(),
+            };
+
+            let mut check_btn = button(text("Vérifier les mises à jour").size(13))
+                .style(button::secondary)
+                .padding(Padding::from([4, 16]));
+            if self.update_check != UpdateCheck::Checking {
+                check_btn = check_btn.on_press(Message::Help(HelpMsg::CheckUpdates));
+            }
+            let buttons = Row::new()
+                .push(check_btn)
+                .push(Space::new().width(Length::Fill))
                 .push(
-                    button(text(theme_btn_label).size(13))
-                        .on_press(Message::Settings(SettingsMsg::SetDarkMode(!self.dark_mode)))
+                    button(text("Page du projet").size(13))
+                        .on_press(Message::Help(HelpMsg::OpenUrl(
+                            env!("CARGO_PKG_REPOSITORY").to_string(),
+                        )))
                         .style(button::secondary)
                         .padding(Padding::from([4, 16])),
                 )
-                .align_y(iced::Alignment::Center)
                 .width(Length::Fill);
 
-            // Font size
-            let font_row = Row::new()
-                .push(text("Taille de police").size(14).width(Length::FillPortion(1)))
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(16))
+                    .push(text(format!("Version {}", env!("CARGO_PKG_VERSION"))).size(14))
+                    .push(text(format!("Compilé le {}", env!("BUILD_DATE"))).size(13))
+                    .push(text(format!("Licence {}", env!("CARGO_PKG_LICENSE"))).size(13))
+                    .push(Space::new().height(12))
+                    .push(text("Développé par Olivier6431 et les contributeurs du projet.").size(13))
+                    .push(text("Interface réalisée avec iced ; texte géré par ropey.").size(13))
+                    .push(Space::new().height(16))
+                    .push(update_status)
+                    .push(Space::new().height(12))
+                    .push(buttons)
+                    .spacing(4)
+                    .width(400),
+            )
+            .padding(24)
+            .style(popup_style(bg_weak, bg_strong));
+
+            layers = layers.push(
+                container(modal_content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill),
+            );
+        }
+
+        // --- Print preview modal ---
+        if let Some(preview) = &self.print_preview {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Print(PrintMsg::ClosePreview));
+            layers = layers.push(backdrop);
+
+            let setup = preview.setup;
+            let total = preview.pagination.pages.len();
+            let toggle_style = |on: bool| if on { button::primary } else { button::secondary };
+
+            let title_row = Row::new()
+                .push(text("Aperçu avant impression").size(18))
+                .push(Space::new().width(Length::Fill))
                 .push(
-                    Row::new()
-                        .push(
-                            button(text("-").size(13))
-                                .on_press(Message::Settings(SettingsMsg::SetFontSize(
-                                    self.font_size - crate::ZOOM_STEP,
-                                )))
-                                .style(button::secondary)
-                                .padding(Padding::from([4, 10])),
-                        )
-                        .push(
-                            container(text(format!("{}", self.font_size as u32)).size(13))
-                                .padding(Padding::from([4, 12])),
-                        )
-                        .push(
-                            button(text("+").size(13))
-                                .on_press(Message::Settings(SettingsMsg::SetFontSize(
-                                    self.font_size + crate::ZOOM_STEP,
-                                )))
-                                .style(button::secondary)
-                                .padding(Padding::from([4, 10])),
-                        )
-                        .spacing(4)
-                        .align_y(iced::Alignment::Center),
+                    button(text("✕").size(14))
+                        .on_press(Message::Print(PrintMsg::ClosePreview))
+                        .style(button::text),
                 )
                 .align_y(iced::Alignment::Center)
                 .width(Length::Fill);
 
-            // Word wrap toggle
-            let wrap_btn_label = if self.word_wrap { "Activé" } else { "Désactivé" };
-            let wrap_row = Row::new()
+            let mut margin_row = Row::new()
+                .push(text("Marges").size(13))
+                .spacing(6)
+                .align_y(iced::Alignment::Center);
+            for mm in MARGIN_CHOICES_MM {
+                margin_row = margin_row.push(
+                    button(text(format!("{mm} mm")).size(12))
+                        .on_press(Message::Print(PrintMsg::SetMargin(mm)))
+                        .style(toggle_style(setup.margin_mm == mm))
+                        .padding(Padding::from([3, 10])),
+                );
+            }
+            let options_row = Row::new()
+                .push(margin_row)
+                .push(Space::new().width(Length::Fill))
                 .push(
-                    text("Retour à la ligne")
-                        .size(14)
-                        .width(Length::FillPortion(1)),
+                    button(text("En-tête et pied de page").size(12))
+                        .on_press(Message::Print(PrintMsg::ToggleHeaderFooter))
+                        .style(toggle_style(setup.header_footer))
+                        .padding(Padding::from([3, 10])),
                 )
                 .push(
-                    button(text(wrap_btn_label).size(13))
-                        .on_press(Message::Settings(SettingsMsg::SetWordWrap(!self.word_wrap)))
+                    button(text("Ajuster à la largeur").size(12))
+                        .on_press(Message::Print(PrintMsg::ToggleFitToWidth))
+                        .style(toggle_style(setup.fit_to_width))
+                        .padding(Padding::from([3, 10])),
+                )
+                .spacing(6)
+                .align_y(iced::Alignment::Center);
+
+            // The page, scaled down from points
+            const PREVIEW_SCALE: f32 = 0.7;
+            let ink = iced::Color::from_rgb(0.1, 0.1, 0.1);
+            let body_size = preview.pagination.font_size * PREVIEW_SCALE;
+            let margin = mm_to_pt(setup.margin_mm) * PREVIEW_SCALE;
+            let page = &preview.pagination.pages[preview.page];
+            let mut page_column = Column::new().width(Length::Fill).height(Length::Fill);
+            if let Some(header) = &page.header {
+                page_column = page_column
+                    .push(text(header.clone()).font(editor_font).size(body_size).color(ink))
+                    .push(Space::new().height(body_size * 1.2));
+            }
+            page_column = page_column.push(
+                text(page.lines.join("\n"))
+                    .font(editor_font)
+                    .size(body_size)
+                    .line_height(iced::widget::text::LineHeight::Relative(1.2))
+                    .color(ink)
+                    .height(Length::Fill),
+            );
+            if let Some(footer) = &page.footer {
+                page_column = page_column.push(
+                    container(text(footer.clone()).font(editor_font).size(body_size).color(ink))
+                        .center_x(Length::Fill),
+                );
+            }
+            let sheet = container(page_column)
+                .width(PAGE_WIDTH_PT * PREVIEW_SCALE)
+                .height(PAGE_HEIGHT_PT * PREVIEW_SCALE)
+                .padding(margin)
+                .clip(true)
+                .style(move |_: &Theme| container::Style {
+                    background: Some(iced::Background::Color(iced::Color::WHITE)),
+                    border: iced::Border {
+                        color: bg_strong,
+                        width: 1.0,
+                        radius: 0.0.into(),
+                    },
+                    ..Default::default()
+                });
+
+            let nav_row = Row::new()
+                .push(
+                    button(text("‹").size(14))
+                        .on_press_maybe((preview.page > 0).then_some(Message::Print(PrintMsg::PreviousPage)))
+                        .style(button::secondary)
+                        .padding(Padding::from([2, 12])),
+                )
+                .push(text(format!("Page {} / {}", preview.page + 1, total)).size(13))
+                .push(
+                    button(text("›").size(14))
+                        .on_press_maybe(
+                            (preview.page + 1 < total).then_some(Message::Print(PrintMsg::NextPage)),
+                        )
+                        .style(button::secondary)
+                        .padding(Padding::from([2, 12])),
+                )
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("Imprimer").size(13))
+                        .on_press(Message::File(FileMsg::Print))
+                        .style(button::primary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .push(
+                    button(text("Fermer").size(13))
+                        .on_press(Message::Print(PrintMsg::ClosePreview))
                         .style(button::secondary)
                         .padding(Padding::from([4, 16])),
                 )
+                .spacing(10)
+                .align_y(iced::Alignment::Center);
+
+            let modal_content = container(
+                Column::new()
+                    .push(title_row)
+                    .push(Space::new().height(12))
+                    .push(options_row)
+                    .push(Space::new().height(12))
+                    .push(scrollable(container(sheet).center_x(Length::Fill)).height(Length::Fill))
+                    .push(Space::new().height(12))
+                    .push(nav_row),
+            )
+            .padding(24)
+            .width(PAGE_WIDTH_PT * PREVIEW_SCALE + 200.0)
+            .style(popup_style(bg_weak, bg_strong));
+
+            let centered = container(modal_content)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(40)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill);
+
+            layers = layers.push(centered);
+        }
+
+        // --- Merge modal: disk version, local version, merged result ---
+        if let Some(session) = &self.merge {
+            let backdrop = mouse_area(
+                container(Space::new().width(Length::Fill).height(Length::Fill)).style(
+                    move |_: &Theme| container::Style {
+                        background: Some(iced::Background::Color(iced::Color {
+                            a: 0.5,
+                            ..iced::Color::BLACK
+                        })),
+                        ..Default::default()
+                    },
+                ),
+            )
+            .on_press(Message::Merge(MergeMsg::Cancel));
+            layers = layers.push(backdrop);
+
+            let title_row = Row::new()
+                .push(text(format!("Fusionner : {}", session.name)).size(18))
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("✕").size(14))
+                        .on_press(Message::Merge(MergeMsg::Cancel))
+                        .style(button::text),
+                )
                 .align_y(iced::Alignment::Center)
                 .width(Length::Fill);
 
-            // Session restore toggle
-            let session_btn_label = if self.restore_session {
-                "Activé"
-            } else {
-                "Désactivé"
-            };
-            let session_row = Row::new()
+            let header = Row::new()
+                .push(text("Version sur disque").size(13).width(Length::FillPortion(1)))
+                .push(text("Version locale").size(13).width(Length::FillPortion(1)))
+                .push(text("Résultat").size(13).width(Length::FillPortion(1)))
+                .spacing(8);
+
+            let dim_color = iced::Color { a: 0.5, ..bg_text };
+            let disk_bg = iced::Color { a: 0.25, ..palette.danger.weak.color };
+            let local_bg = iced::Color { a: 0.25, ..palette.success.weak.color };
+            let mut segments = Column::new().spacing(4);
+            let mut hunk_index = 0;
+            for segment in &session.merge.segments {
+                match segment {
+                    Segment::Same(lines) => {
+                        let shown = merge_context(lines);
+                        let cell = || {
+                            merge_cell(&shown, editor_font, self.font_size, None)
+                                .color(dim_color)
+                                .width(Length::FillPortion(1))
+                        };
+                        segments = segments.push(
+                            Row::new().push(cell()).push(cell()).push(cell()).spacing(8),
+                        );
+                    }
+                    Segment::Conflict(hunk) => {
+                        let index = hunk_index;
+                        hunk_index += 1;
+                        let choice_button = |label: &'static str, choice: HunkChoice| {
+                            button(text(label).size(11))
+                                .on_press(Message::Merge(MergeMsg::Choose(index, choice)))
+                                .style(if hunk.choice == choice {
+                                    button::primary
+                                } else {
+                                    button::secondary
+                                })
+                                .padding(Padding::from([2, 10]))
+                        };
+                        let side = |lines: Vec<&str>, bg, label, choice| {
+                            Column::new()
+                                .push(
+                                    container(merge_cell(&lines, editor_font, self.font_size, Some("(vide)")))
+                                        .style(move |_: &Theme| container::Style {
+                                            background: Some(iced::Background::Color(bg)),
+                                            ..Default::default()
+                                        })
+                                        .width(Length::Fill),
+                                )
+                                .push(choice_button(label, choice))
+                                .spacing(4)
+                                .width(Length::FillPortion(1))
+                        };
+                        let disk_lines = hunk.disk.iter().map(String::as_str).collect();
+                        let local_lines = hunk.local.iter().map(String::as_str).collect();
+                        segments = segments.push(
+                            Row::new()
+                                .push(side(disk_lines, disk_bg, "Prendre", HunkChoice::Disk))
+                                .push(side(local_lines, local_bg, "Garder", HunkChoice::Local))
+                                .push(side(hunk.merged(), bg_base, "Les deux", HunkChoice::Both))
+                                .spacing(8),
+                        );
+                    }
+                }
+            }
+
+            let footer = Row::new()
                 .push(
-                    text("Restaurer la session")
-                        .size(14)
-                        .width(Length::FillPortion(1)),
+                    button(text("Tout prendre du disque").size(13))
+                        .on_press(Message::Merge(MergeMsg::ChooseAll(HunkChoice::Disk)))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
                 )
                 .push(
-                    button(text(session_btn_label).size(13))
-                        .on_press(Message::Settings(SettingsMsg::SetRestoreSession(
-                            !self.restore_session,
-                        )))
+                    button(text("Tout garder local").size(13))
+                        .on_press(Message::Merge(MergeMsg::ChooseAll(HunkChoice::Local)))
                         .style(button::secondary)
                         .padding(Padding::from([4, 16])),
                 )
-                .align_y(iced::Alignment::Center)
-                .width(Length::Fill);
+                .push(Space::new().width(Length::Fill))
+                .push(
+                    button(text("Annuler").size(13))
+                        .on_press(Message::Merge(MergeMsg::Cancel))
+                        .style(button::secondary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .push(
+                    button(text("Appliquer").size(13))
+                        .on_press(Message::Merge(MergeMsg::Apply))
+                        .style(button::primary)
+                        .padding(Padding::from([4, 16])),
+                )
+                .spacing(8)
+                .align_y(iced::Alignment::Center);
 
             let modal_content = container(
                 Column::new()
                     .push(title_row)
-                    .push(Space::new().height(16))
-                    .push(theme_row)
-                    .push(Space::new().height(12))
-                    .push(font_row)
                     .push(Space::new().height(12))
-                    .push(wrap_row)
+                    .push(header)
+                    .push(Space::new().height(6))
+                    .push(scrollable(segments).height(Length::Fill))
                     .push(Space::new().height(12))
-                    .push(session_row)
-                    .width(350),
+                    .push(footer),
             )
             .padding(24)
+            .width(Length::FillPortion(9))
+            .height(Length::FillPortion(9))
             .style(popup_style(bg_weak, bg_strong));
 
             let centered = container(modal_content)
                 .width(Length::Fill)
                 .height(Length::Fill)
+                .padding(40)
                 .center_x(Length::Fill)
                 .center_y(Length::Fill);
 
@@ -959,6 +5811,37 @@ impl Notepad {
     }
 }
 
+// Long unchanged runs in the merge view only keep a few lines around the hunks
+const MERGE_CONTEXT_LINES: usize = 2;
+
+fn merge_context(lines: &[String]) -> Vec<&str> {
+    if lines.len() <= MERGE_CONTEXT_LINES * 2 + 1 {
+        return lines.iter().map(String::as_str).collect();
+    }
+    let mut shown: Vec<&str> = lines[..MERGE_CONTEXT_LINES].iter().map(String::as_str).collect();
+    shown.push("…");
+    shown.extend(lines[lines.len() - MERGE_CONTEXT_LINES..].iter().map(String::as_str));
+    shown
+}
+
+fn merge_cell<'a>(
+    lines: &[&str],
+    font: Font,
+    size: f32,
+    empty: Option<&str>,
+) -> iced::widget::Text<'a> {
+    let body = lines
+        .iter()
+        .map(|l| l.trim_end_matches(['\r', '\n']))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let body = match empty {
+        Some(placeholder) if lines.is_empty() => placeholder.to_string(),
+        _ => body,
+    };
+    text(body).font(font).size(size * 0.85)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1024,35 +5907,98 @@ mod tests {
 
     #[test]
     fn clamp_within_bounds_unchanged() {
-        let (x, y) = clamp_popup_position(10.0, 20.0, 100.0, 50.0, 800.0, 600.0);
+        let (x, y) = clamp_popup_position(10.0, 20.0, 100.0, 50.0, 800.0, 600.0, 1.0);
         assert_eq!((x, y), (10.0, 20.0));
     }
 
     #[test]
     fn clamp_overflow_right() {
-        let (x, y) = clamp_popup_position(750.0, 20.0, 100.0, 50.0, 800.0, 600.0);
+        let (x, y) = clamp_popup_position(750.0, 20.0, 100.0, 50.0, 800.0, 600.0, 1.0);
         assert_eq!(x, 700.0);
         assert_eq!(y, 20.0);
     }
 
     #[test]
     fn clamp_overflow_bottom() {
-        let (x, y) = clamp_popup_position(10.0, 580.0, 100.0, 50.0, 800.0, 600.0);
+        let (x, y) = clamp_popup_position(10.0, 580.0, 100.0, 50.0, 800.0, 600.0, 1.0);
         assert_eq!(x, 10.0);
         assert_eq!(y, 550.0);
     }
 
     #[test]
     fn clamp_overflow_both() {
-        let (x, y) = clamp_popup_position(750.0, 580.0, 100.0, 50.0, 800.0, 600.0);
+        let (x, y) = clamp_popup_position(750.0, 580.0, 100.0, 50.0, 800.0, 600.0, 1.0);
         assert_eq!(x, 700.0);
         assert_eq!(y, 550.0);
     }
 
     #[test]
     fn clamp_window_smaller_than_popup() {
-        let (x, y) = clamp_popup_position(10.0, 10.0, 200.0, 200.0, 100.0, 100.0);
+        let (x, y) = clamp_popup_position(10.0, 10.0, 200.0, 200.0, 100.0, 100.0, 1.0);
         assert_eq!(x, 0.0);
         assert_eq!(y, 0.0);
     }
+
+    // ============================
+    // elide_start
+    // ============================
+
+    #[test]
+    fn long_folders_keep_their_end() {
+        assert_eq!(elide_start("/home/léa", 24), "/home/léa");
+        assert_eq!(elide_start("/home/léa/Documents/notes", 12), "…ments/notes");
+    }
+
+    // ============================
+    // split_areas
+    // ============================
+
+    #[test]
+    fn split_areas_share_the_editor() {
+        let area = iced::Rectangle::new(iced::Point::new(0.0, 50.0), iced::Size::new(801.0, 401.0));
+        let (left, right) = split_areas(area, true);
+        assert_eq!((left.x, left.width, left.height), (0.0, 400.0, 401.0));
+        assert_eq!((right.x, right.y, right.width), (401.0, 50.0, 400.0));
+        let (top, bottom) = split_areas(area, false);
+        assert_eq!((top.y, top.height, top.width), (50.0, 200.0, 801.0));
+        assert_eq!((bottom.x, bottom.y, bottom.height), (0.0, 251.0, 200.0));
+    }
+
+    // ============================
+    // wrapped_rows
+    // ============================
+
+    #[test]
+    fn short_and_empty_lines_take_one_row() {
+        assert_eq!(wrapped_rows("", 10, usize::MAX), 1);
+        assert_eq!(wrapped_rows("bonjour", 10, usize::MAX), 1);
+        assert_eq!(wrapped_rows("a very long line", usize::MAX, usize::MAX), 1);
+    }
+
+    #[test]
+    fn words_move_to_the_next_row() {
+        assert_eq!(wrapped_rows("un deux trois", 8, usize::MAX), 2);
+        assert_eq!(wrapped_rows("un deux trois quatre", 8, usize::MAX), 3);
+        // A trailing space does not force a wrap on its own
+        assert_eq!(wrapped_rows("abcd efgh ", 9, usize::MAX), 1);
+    }
+
+    #[test]
+    fn long_words_overflow_on_their_own_row() {
+        assert_eq!(wrapped_rows(&"x".repeat(25), 10, usize::MAX), 1);
+        assert_eq!(wrapped_rows(&format!("ab {} cd", "x".repeat(12)), 10, usize::MAX), 3);
+        // Rows past the screen are not counted
+        assert_eq!(wrapped_rows(&"x ".repeat(5_000_000), 10, 40), 40);
+    }
+
+    #[test]
+    fn breaks_tabs_and_wide_characters_follow_the_editor() {
+        // A hyphen is a break opportunity too
+        assert_eq!(wrapped_rows("avant-après", 8, usize::MAX), 2);
+        // Tabs reach the next stop of 8 cells
+        assert_eq!(wrapped_rows("\tab\tcd", 18, usize::MAX), 1);
+        assert_eq!(wrapped_rows("\tab\tcd", 17, usize::MAX), 2);
+        // Ideographs take two cells and can break between any two of them
+        assert_eq!(wrapped_rows("漢字漢字漢字", 8, usize::MAX), 2);
+    }
 }
@@ -1,101 +1,297 @@
-use iced::keyboard::key::Named;
-use iced::keyboard::{self, Key, Modifiers};
+use iced::keyboard;
 use iced::widget::{operation, text_editor};
 use iced::{Event, Task};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
+use crate::abbreviations::{self, Abbreviation};
 use crate::app::{
-    find_input_id, goto_input_id, Document, EditMsg, FileMsg, FormatMsg, LineEnding, MenuMsg,
-    Message, Notepad, SearchMsg, SettingsMsg, TextSnapshot, ViewMsg, FILE_SIZE_LIMIT_MB, FILE_SIZE_WARN_MB,
-    LARGE_FILE_UNDO_HISTORY, MAX_UNDO_HISTORY, UNDO_BATCH_TIMEOUT_MS,
+    editor_id, find_input_id, goto_input_id, Document, EditMsg, FileMsg, FormatMsg, IndentStyle, JobMsg, LineEnding,
+    LoadedFile, MatchCount, MenuMsg, Message, Notepad, SearchMsg, SettingsMsg, TextSnapshot, ToastMsg, ViewMsg, ViewState, MergeMsg, MergeSession, PrintMsg, PrintPreview, PrintQueue, SaveAsDialog, SaveEncoding, QrPopup, InkMsg, unmappable_chars, UNMAPPABLE_SHOWN, CompletionMsg, PathCompletion, ColorMsg, ColorPicker,
+    TableMsg, TerminalMsg, terminal_input_id, shortcut_input_id, StartupData, HelpMsg, NamedSnapshot, SnapshotMsg,
+    snapshot_name_input_id, filter_command_input_id, wrap_width_input_id, macro_count_input_id, char_search_input_id, CharPicker, ScriptPicker, Pane, Split, DefinitionPopup, SpellSuggestions, NotesMsg, FolderMsg, FolderPicker, folder_filter_input_id, SessionMsg, session_name_input_id, TemplateMsg, TemplateManager, TemplateFill, template_name_input_id, template_value_input_id, NoteSearch, TagEditor, tags_input_id, note_search_input_id, FileProperties,
+    BACKGROUND_IO_THRESHOLD_BYTES, FILE_SIZE_LIMIT_MB, FILE_SIZE_WARN_MB, LARGE_FILE_UNDO_HISTORY, MAX_UNDO_HISTORY, MENU_BAR_HEIGHT, TAB_BAR_HEIGHT, TOOLBAR_HEIGHT,
+    UNDO_BATCH_TIMEOUT_MS,
 };
-use crate::preferences::{SessionData, SessionTab, UserPreferences};
+use crate::archive;
+use crate::charmap;
+use crate::color::{self, Rgb};
+use crate::comments;
+use crate::completion;
+use crate::crash::{self, DirtyBuffer};
+use crate::datetime::{self, LocalTime};
+use crate::dictionary;
+use crate::dpi;
+use crate::editorconfig::DocSettings;
+use crate::export::{self, ExportFormat};
+use crate::filter;
+use crate::folder;
+use crate::hibernate::{self, Candidate, HIBERNATE_ABOVE_BYTES};
+use crate::jobs::{self, JobEvent, JobId};
+use crate::ink;
+use crate::language::{self, Language};
+use crate::links;
+use crate::lock::{self, DocLock, LockState};
+use crate::macros;
+use crate::logging;
+use crate::markdown::{self, Table};
+use crate::merge::{HunkChoice, Merge};
+use crate::metadata::{Caret, MetadataStore};
+use crate::notes::{self, NoteIndex};
+use crate::opacity::{self, MIN_WINDOW_OPACITY};
+use crate::paragraphs;
+use crate::paste;
+use crate::paths;
+use crate::preferences::{
+    EscapeOrder, NamedSession, NamedSessions, RecentFiles, SessionData, SessionTab, UrlPaste, UserPreferences,
+};
+use crate::print::{self, PageSetup};
+use crate::qr;
+use crate::reflow;
+use crate::scripts;
+use crate::search_index::{SearchIndex, Span, SEARCH_INDEX_MIN_BYTES};
+use crate::secrets;
+use crate::shortcuts::{self, Action, KeyName, Keymap, Scope};
+use crate::snippets::{self, Snippet};
+use crate::spelling;
+use crate::syntax::{self, SYNTAX_CHECK_IDLE_MS};
+use crate::tabs;
+use crate::templates;
+use crate::terminal::{self, Shell, ShellEvent, Terminal, TerminalOutput};
+use crate::toast::Severity;
+use crate::updates::{self, UpdateCheck};
+use crate::wiki;
 use crate::{DEFAULT_FONT_SIZE, MAX_FONT_SIZE, MIN_FONT_SIZE, ZOOM_STEP};
 
 fn format_local_datetime(unix_secs: u64) -> String {
-    // UTC offset for local time — use platform-specific API
-    #[cfg(target_os = "windows")]
-    fn utc_offset_secs() -> i64 {
-        #[repr(C)]
-        struct TimeZoneInformation {
-            bias: i32,
-            _rest: [u8; 168],
-        }
-        extern "system" {
-            fn GetTimeZoneInformation(lp_time_zone_information: *mut TimeZoneInformation) -> u32;
-        }
-        let mut tzi = TimeZoneInformation {
-            bias: 0,
-            _rest: [0; 168],
-        };
-        unsafe {
-            GetTimeZoneInformation(&mut tzi);
-        }
-        // Bias is in minutes, west-positive → negate for east-positive
-        -(tzi.bias as i64) * 60
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    fn utc_offset_secs() -> i64 {
-        0 // Fallback to UTC on non-Windows
-    }
-
-    let local_secs = unix_secs as i64 + utc_offset_secs();
-
-    // Days since epoch → date
-    let mut days = local_secs.div_euclid(86400);
-    let day_secs = local_secs.rem_euclid(86400);
-    let hours = day_secs / 3600;
-    let minutes = (day_secs % 3600) / 60;
-
-    // Civil date from days since 1970-01-01 (Algorithm from Howard Hinnant)
-    days += 719_468;
-    let era = days.div_euclid(146_097);
-    let doe = days.rem_euclid(146_097);
-    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
-    let y = yoe + era * 400;
-    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
-    let mp = (5 * doy + 2) / 153;
-    let d = doy - (153 * mp + 2) / 5 + 1;
-    let m = if mp < 10 { mp + 3 } else { mp - 9 };
-    let y = if m <= 2 { y + 1 } else { y };
-
-    format!("{:02}:{:02} {:02}/{:02}/{:04}", hours, minutes, d, m, y)
+    LocalTime::from_unix(unix_secs).format(datetime::DEFAULT_FORMAT)
+}
+
+// Offered for the {{date}} of a template
+fn today() -> String {
+    LocalTime::now().format("%d/%m/%Y")
+}
+
+// Title, text and annotations of a document sent to the printer
+type PrintedDocument = (String, Arc<str>, Vec<ink::Stroke>);
+
+// Header of the printed pages
+fn print_title(doc: &Document) -> String {
+    doc.file_path
+        .as_deref()
+        .map_or_else(|| "Sans titre".to_string(), file_display_name)
+}
+
+fn file_display_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("fichier")
+        .to_string()
+}
+
+// A safe write goes through a temporary file so the original survives a failure
+fn write_file(path: &Path, bytes: &[u8], safe: bool) -> std::io::Result<()> {
+    if safe {
+        paths::replace_via_temp(path, |temp| std::fs::write(temp, bytes))
+    } else {
+        std::fs::write(path, bytes)
+    }
+}
+
+// Appends "--- stamp ---" and the text to the file, a blank line after the
+// previous entry; the file is created when missing
+fn append_entry(path: &Path, text: &str, stamp: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    let existing = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut entry = String::new();
+    if existing > 0 {
+        entry.push('\n');
+    }
+    entry.push_str(&format!("--- {stamp} ---\n{text}"));
+    if !text.ends_with('\n') {
+        entry.push('\n');
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(entry.as_bytes())
+}
+
+// Interprets \n, \r, \t, \0, \\ and \xNN; unknown sequences are kept as typed
+fn expand_escapes(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek().copied() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('0') => out.push('\0'),
+            Some('\\') => out.push('\\'),
+            Some('x') => {
+                let hex: String = chars.clone().skip(1).take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) if hex.len() == 2 => {
+                        out.push(char::from(byte));
+                        chars.next();
+                        chars.next();
+                    }
+                    _ => {
+                        out.push('\\');
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                out.push('\\');
+                continue;
+            }
+        }
+        chars.next();
+    }
+    out
 }
 
-fn byte_pos_to_line_col(text: &str, byte_pos: usize) -> (usize, usize) {
-    let before = &text[..byte_pos];
-    let line = before.matches('\n').count();
-    let line_start = before.rfind('\n').map(|p| p + 1).unwrap_or(0);
-    let col = text[line_start..byte_pos].chars().count();
-    (line, col)
+// Messages sent by timers, watchers and background jobs rather than the
+// user; they do not count as activity for the idle delay
+fn is_background(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::File(
+            FileMsg::AutoSave
+                | FileMsg::AutoSaveNetwork
+                | FileMsg::AutoSaveSession
+                | FileMsg::CheckExternalChanges
+                | FileMsg::ChangedOnDisk(_)
+                | FileMsg::Loaded(..)
+                | FileMsg::Saved(..)
+                | FileMsg::Printed(..)
+        ) | Message::Edit(
+            EditMsg::CheckSyntax
+                | EditMsg::RefreshStats
+                | EditMsg::SyntaxChecked(..)
+                | EditMsg::Filtered(..)
+                | EditMsg::ScriptsListed(_)
+                | EditMsg::ScriptRan(..)
+                | EditMsg::Defined(..)
+                | EditMsg::LinkTitleFetched(..)
+        ) | Message::Settings(SettingsMsg::SpellDictionaryLoaded(..) | SettingsMsg::ProxyPasswordSaved(_))
+            | Message::Search(SearchMsg::Indexed(..))
+            | Message::Help(HelpMsg::UpdateChecked(_))
+            | Message::Notes(NotesMsg::Results(..) | NotesMsg::Exported(..))
+            | Message::Folder(FolderMsg::Listed(..))
+            | Message::Session(SessionMsg::Loaded(..))
+            | Message::Terminal(TerminalMsg::Output(..) | TerminalMsg::Closed(_))
+            | Message::Job(_)
+            | Message::Toast(ToastMsg::Tick)
+            | Message::StartupLoaded(_)
+            | Message::EventOccurred(Event::Window(
+                iced::window::Event::Unfocused
+                    | iced::window::Event::Moved(_)
+                    | iced::window::Event::RedrawRequested(_)
+            ))
+    )
 }
 
 impl Notepad {
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        if !is_background(&message) {
+            self.last_activity = Instant::now();
+        }
+        if let Some(steps) = &mut self.macro_recording {
+            if macros::is_recorded(&message) {
+                steps.push(message.clone());
+            }
+        }
         // Auto-close menus on most actions
         match &message {
             Message::Menu(MenuMsg::Hover(_))
             | Message::Menu(MenuMsg::Toggle(_))
             | Message::Menu(MenuMsg::ShowContext)
+            | Message::Menu(MenuMsg::ShowIndent)
+            | Message::Menu(MenuMsg::ShowLanguage)
+            | Message::Menu(MenuMsg::ToggleTabList)
+            | Message::View(ViewMsg::ToggleOutline)
+            | Message::View(ViewMsg::SetOutlineLevel(_))
+            | Message::File(FileMsg::ScrollTabs(_))
             | Message::Menu(MenuMsg::CloseAll)
             | Message::EventOccurred(_)
             | Message::Search(SearchMsg::FindQueryChanged(_))
             | Message::Search(SearchMsg::ReplaceQueryChanged(_))
             | Message::Search(SearchMsg::GoToInputChanged(_))
             | Message::File(FileMsg::AutoSave)
+            | Message::File(FileMsg::AutoSaveSession)
             | Message::File(FileMsg::CheckExternalChanges)
+            | Message::File(FileMsg::ChangedOnDisk(_))
+            | Message::File(FileMsg::Loaded(..))
+            | Message::File(FileMsg::Saved(..))
+            | Message::File(FileMsg::Printed(..))
+            | Message::Job(JobMsg::Progress(..))
+            | Message::Toast(_)
+            | Message::StartupLoaded(_)
+            | Message::Search(SearchMsg::Indexed(..))
+            | Message::Help(HelpMsg::UpdateChecked(_))
+            | Message::Help(HelpMsg::ShortcutQueryChanged(_))
+            | Message::Snapshot(SnapshotMsg::NameChanged(_))
+            | Message::Notes(NotesMsg::TagsInputChanged(_))
+            | Message::Notes(NotesMsg::QueryChanged(_))
+            | Message::Notes(NotesMsg::Results(..))
+            | Message::Notes(NotesMsg::Exported(..))
+            | Message::Folder(FolderMsg::Listed(..))
+            | Message::Folder(FolderMsg::QueryChanged(_))
+            | Message::Session(SessionMsg::NameChanged(_))
+            | Message::Template(TemplateMsg::NameChanged(_))
+            | Message::Template(TemplateMsg::ValueChanged(..))
+            | Message::File(FileMsg::ToggleRecentFiles)
+            | Message::File(FileMsg::ToggleReopenEncodings)
+            | Message::Search(SearchMsg::ToggleBookmarksMenu)
+            | Message::Session(SessionMsg::Loaded(..))
+            | Message::Edit(EditMsg::FilterCommandChanged(_))
+            | Message::Edit(EditMsg::HardWrapWidthChanged(_))
+            | Message::Edit(EditMsg::MacroCountChanged(_))
+            | Message::Edit(EditMsg::CharQueryChanged(_))
+            | Message::Edit(EditMsg::ToggleDateFormats)
+            | Message::Edit(EditMsg::Filtered(..))
+            | Message::Edit(EditMsg::ScriptsListed(_))
+            | Message::Edit(EditMsg::ScriptRan(..))
+            | Message::Edit(EditMsg::Defined(..))
+            | Message::Edit(EditMsg::LinkTitleFetched(..))
+            | Message::Edit(EditMsg::CheckSyntax)
+            | Message::Edit(EditMsg::RefreshStats)
+            | Message::Edit(EditMsg::SyntaxChecked(..))
+            | Message::Terminal(TerminalMsg::Output(..))
+            | Message::Terminal(TerminalMsg::Closed(_))
+            | Message::View(ViewMsg::RestoreView)
             | Message::Settings(_)
             | Message::ScrollbarClick(_) => {}
             _ => {
                 self.active_menu = None;
                 self.show_context_menu = false;
+                self.show_indent_menu = false;
+                self.show_language_menu = false;
+                self.show_tab_list = false;
+                self.show_outline = false;
             }
         }
 
-        match message {
+        let prev_active = self.active_tab;
+        let prev_len = self.tabs.len();
+        let prev_progress = self.job_percent();
+        let prev_point = self.active_doc().nav_point();
+        let loaded = matches!(message, Message::File(FileMsg::Loaded(..)));
+        let mut task = match message {
+            Message::EditorAction(text_editor::Action::Edit(text_editor::Edit::Paste(text))) => {
+                // Ctrl+V: the clipboard's line breaks become the document's
+                let text = paste::normalize_line_endings(&text, self.active_doc().line_ending);
+                let pasted = self.pasted_link(&text).unwrap_or_else(|| text.clone());
+                let task = self.handle_editor_action(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(
+                    pasted,
+                ))));
+                Task::batch([task, self.fetch_link_title(&text)])
+            }
             Message::EditorAction(action) => self.handle_editor_action(action),
             Message::EventOccurred(event) => self.handle_event(event),
             Message::File(msg) => self.handle_file(msg),
@@ -105,6 +301,23 @@ impl Notepad {
             Message::Settings(msg) => self.handle_settings(msg),
             Message::Format(msg) => self.handle_format(msg),
             Message::Menu(msg) => self.handle_menu(msg),
+            Message::Job(msg) => self.handle_job(msg),
+            Message::Toast(msg) => self.handle_toast(msg),
+            Message::Merge(msg) => self.handle_merge(msg),
+            Message::Print(msg) => self.handle_print(msg),
+            Message::Ink(msg) => self.handle_ink(msg),
+            Message::Completion(msg) => self.handle_completion(msg),
+            Message::Color(msg) => self.handle_color(msg),
+            Message::Table(msg) => self.handle_table(msg),
+            Message::Terminal(msg) => self.handle_terminal(msg),
+            Message::Help(msg) => self.handle_help(msg),
+            Message::Snapshot(msg) => self.handle_snapshot(msg),
+            Message::Notes(msg) => self.handle_notes(msg),
+            Message::Folder(msg) => self.handle_folder(msg),
+            Message::Session(msg) => self.handle_session(msg),
+            Message::Template(msg) => self.handle_template(msg),
+            Message::Shortcut(action) => self.run_shortcut(action),
+            Message::StartupLoaded(data) => self.apply_startup(*data),
             Message::ScrollbarClick(ratio) => {
                 let doc = self.active_doc_mut();
                 let max_offset = doc.content.line_count().saturating_sub(1) as f32;
@@ -116,1652 +329,8857 @@ impl Notepad {
                 });
                 Task::none()
             }
+        };
+        if self.active_tab != prev_active || self.tabs.len() != prev_len {
+            self.activation_counter += 1;
+            let stamp = self.activation_counter;
+            self.active_doc_mut().last_active = stamp;
+            self.reveal_active_tab();
+            self.path_completion = None;
         }
+        // The searched range belongs to the document it was selected in
+        if self.active_doc().id != prev_point.doc {
+            self.find_in_selection = None;
+            self.find_origin = None;
+        }
+        if self.active_tab != prev_active || self.tabs.len() != prev_len || loaded {
+            self.relieve_memory_pressure();
+        }
+        // Opening or closing a tab is written at once, edits with the timer
+        if self.tabs.len() != prev_len || loaded {
+            self.autosave_session();
+        }
+        // Switching tabs is a jump too, unless the history itself made it
+        let jumped = std::mem::take(&mut self.nav_jumping);
+        if !jumped
+            && self.active_doc().id != prev_point.doc
+            && self.tabs.iter().any(|d| d.id == prev_point.doc)
+        {
+            self.nav_history.record(prev_point);
+        }
+        self.sync_locks();
+        self.track_unsaved();
+        // Deferred so the editor is laid out before scrolling
+        let doc = self.active_doc();
+        if doc.pending_view.is_some() && doc.job.is_none() {
+            task = Task::batch([task, Task::done(Message::View(ViewMsg::RestoreView))]);
+        }
+        if let Some(index) = self.index_task() {
+            task = Task::batch([task, index]);
+        }
+        if self.job_percent() != prev_progress {
+            let progress = self.jobs.overall_progress();
+            return Task::batch([task, jobs::taskbar_progress(progress)]);
+        }
+        task
     }
 
-    // --- Editor action ---
+    // --- Document locking ---
 
-    fn handle_editor_action(&mut self, action: text_editor::Action) -> Task<Message> {
-        // Ctrl+wheel → zoom instead of scroll
-        if self.ctrl_pressed {
-            if let text_editor::Action::Scroll { lines } = &action {
-                return if *lines < 0 {
-                    self.handle_view(ViewMsg::ZoomIn)
-                } else {
-                    self.handle_view(ViewMsg::ZoomOut)
-                };
+    // Takes the lock of every tab whose file changed since the last update (open, Save As…)
+    fn sync_locks(&mut self) {
+        if !self.lock_documents {
+            return;
+        }
+        for i in 0..self.tabs.len() {
+            let doc = &self.tabs[i];
+            let locked_path = doc.lock.as_ref().map(|l| &l.path);
+            if doc.job.is_some() || locked_path == doc.file_path.as_ref() {
+                continue;
+            }
+            if let Some(old) = self.tabs[i].lock.take() {
+                self.release_lock(old);
+            }
+            let Some(path) = self.tabs[i].file_path.clone() else {
+                continue;
+            };
+            let state = lock::acquire(&path).unwrap_or(LockState::Unavailable);
+            if let LockState::ReadOnly(owner) = &state {
+                let msg = format!(
+                    "{} est verrouillé par {} : ouvert en lecture seule",
+                    file_display_name(&path),
+                    owner.label()
+                );
+                self.notify(Severity::Warning, msg);
             }
+            self.tabs[i].lock = Some(DocLock { path, state });
         }
+    }
 
-        let is_edit = matches!(&action, text_editor::Action::Edit(_));
-        let scroll_delta = if let text_editor::Action::Scroll { lines } = &action {
-            Some(*lines)
-        } else {
-            None
+    // Other tabs of this instance may share the lock on the same file
+    fn release_lock(&self, doc_lock: DocLock) {
+        let shared = self.tabs.iter().any(|d| {
+            matches!(&d.lock, Some(l) if l.path == doc_lock.path && l.state == LockState::Held)
+        });
+        if doc_lock.state == LockState::Held && !shared {
+            lock::release(&doc_lock.path);
+        }
+    }
+
+    fn release_locks(&mut self) {
+        for i in 0..self.tabs.len() {
+            if let Some(doc_lock) = self.tabs[i].lock.take() {
+                self.release_lock(doc_lock);
+            }
+        }
+    }
+
+    // --- Hibernation ---
+
+    // Tabs in no pane whose editor can be unloaded: only saved files, so
+    // that autosave, the session and the close prompt never depend on the
+    // hibernation file
+    fn hibernation_candidates(&self) -> Vec<Candidate> {
+        let other_pane = self.split.as_ref().map(|split| split.other);
+        self.tabs
+            .iter()
+            .enumerate()
+            .filter(|(i, d)| {
+                *i != self.active_tab
+                    && other_pane != Some(d.id)
+                    && d.hibernation.is_none()
+                    && d.job.is_none()
+                    && d.save_in_flight.is_none()
+                    && d.file_path.is_some()
+                    && !d.is_modified
+                    && d.mirror.len_bytes() > 0
+            })
+            .map(|(index, d)| Candidate {
+                index,
+                usage: d.memory_usage().total(),
+                last_active: d.last_active,
+            })
+            .collect()
+    }
+
+    fn hibernate_at(&mut self, indices: Vec<usize>) -> usize {
+        let mut count = 0;
+        for i in indices {
+            match self.tabs[i].hibernate() {
+                Ok(()) => count += 1,
+                Err(e) => {
+                    let msg = format!(
+                        "Impossible d'hiberner « {} » : {e}",
+                        self.tabs[i].title_label()
+                    );
+                    self.notify(Severity::Error, msg);
+                }
+            }
+        }
+        count
+    }
+
+    // Hibernates the least recently used tabs while the estimate is over the limit
+    fn relieve_memory_pressure(&mut self) {
+        if !self.hibernate_tabs {
+            return;
+        }
+        let total = self.tabs.iter().map(|d| d.memory_usage().total()).sum();
+        let picked = hibernate::pick(self.hibernation_candidates(), total, HIBERNATE_ABOVE_BYTES);
+        self.hibernate_at(picked);
+    }
+
+    // --- Crash recovery ---
+
+    // Hands the unsaved tabs to the panic hook
+    fn track_unsaved(&self) {
+        let buffers = self
+            .tabs
+            .iter()
+            .filter(|d| d.is_modified)
+            .map(|d| DirtyBuffer {
+                name: d
+                    .file_path
+                    .as_ref()
+                    .and_then(|p| p.file_name())
+                    .map_or_else(|| "Sans titre".to_string(), |n| n.to_string_lossy().into_owned()),
+                // The rope lacks the LF CR bytes of an inexact mirror
+                text: if d.mirror.is_exact() {
+                    d.mirror.rope()
+                } else {
+                    ropey::Rope::from_str(&d.text())
+                },
+            })
+            .collect();
+        crash::track(buffers);
+    }
+
+    // --- Pasted links ---
+
+    // Markdown documents: a URL pasted over a one-line selection links it,
+    // [selection](url); None when the text goes in as it is
+    fn pasted_link(&self, text: &str) -> Option<String> {
+        let doc = self.active_doc();
+        if self.url_paste == UrlPaste::Off || doc.language() != Language::Markdown {
+            return None;
+        }
+        let url = links::url(text)?;
+        let selected = doc.content.selection().filter(|s| !s.contains(['\n', '\r']))?;
+        Some(links::markdown_link(&selected, url))
+    }
+
+    // Right after a URL was pasted alone, asks for the page's title to turn
+    // it into [title](url)
+    fn fetch_link_title(&self, pasted: &str) -> Task<Message> {
+        let doc = self.active_doc();
+        if self.url_paste != UrlPaste::Title || doc.language() != Language::Markdown {
+            return Task::none();
+        }
+        let Some(url) = links::url(pasted).filter(|url| *url == pasted).map(str::to_string) else {
+            return Task::none();
         };
-        if is_edit {
-            self.save_snapshot_if_needed();
+        if !self.text_before_cursor().ends_with(&url) {
+            return Task::none();
+        }
+        let position = doc.content.cursor().position;
+        let start = doc.mirror.position_to_byte(position.line, position.column) - url.len();
+        let doc_id = doc.id;
+        let proxy = self.proxy.clone();
+        let target = url.clone();
+        Task::future(jobs::spawn_blocking(move || links::fetch_title(&proxy, &target))).map(move |title| {
+            let title = title.unwrap_or_else(|| Err("recherche interrompue".to_string()));
+            Message::Edit(EditMsg::LinkTitleFetched(doc_id, start, url.clone(), title))
+        })
+    }
+
+    // --- Path completion ---
+
+    // Text of the cursor line up to the cursor
+    fn text_before_cursor(&self) -> String {
+        let content = &self.active_doc().content;
+        let position = content.cursor().position;
+        content.line(position.line).map_or_else(String::new, |l| {
+            let end = position.column.min(l.text.len());
+            l.text.get(..end).unwrap_or_default().to_string()
+        })
+    }
+
+    // --- Spell checking ---
+
+    // Reads the chosen dictionary in the background
+    pub fn load_speller(&self) -> Task<Message> {
+        let Some(name) = self.spell_dictionary.clone() else {
+            return Task::none();
+        };
+        let folder = dictionary::folder();
+        let loading = name.clone();
+        Task::future(jobs::spawn_blocking(move || spelling::load(&folder, &loading).map(Arc::new))).map(
+            move |result| {
+                let result = result.unwrap_or_else(|| Err("lecture interrompue".to_string()));
+                Message::Settings(SettingsMsg::SpellDictionaryLoaded(name.clone(), result))
+            },
+        )
+    }
+
+    // The unknown word under the cursor, with its suggestions
+    fn misspelled_at_cursor(&self) -> Option<SpellSuggestions> {
+        let speller = self.speller.as_ref()?;
+        let doc = self.active_doc();
+        if !spelling::is_checked(doc.language()) || doc.content.selection().is_some() {
+            return None;
+        }
+        let position = doc.content.cursor().position;
+        let line = doc.content.line(position.line)?;
+        let range = spelling::words(&line.text)
+            .into_iter()
+            .find(|r| r.start <= position.column && position.column <= r.end)?;
+        let word = &line.text[range.clone()];
+        (!speller.check(word)).then(|| SpellSuggestions {
+            line: position.line,
+            suggestions: speller.suggest(word),
+            range,
+        })
+    }
+
+    // Replaces the abbreviation left of the cursor by its expansion
+    fn expand_abbreviation(&mut self) {
+        if self.abbreviations.is_empty() || self.active_doc().content.selection().is_some() {
+            return;
         }
+        let before = self.text_before_cursor();
+        let language = self.active_doc().language();
+        let Some(found) = abbreviations::find(&self.abbreviations, language, &before) else {
+            return;
+        };
+        let (length, expansion) = (found.abbreviation.chars().count(), found.text());
         let doc = self.active_doc_mut();
-        doc.content.perform(action);
-        if is_edit {
-            doc.is_modified = true;
-            doc.status_message = None;
+        for _ in 0..length {
+            doc.content
+                .perform(text_editor::Action::Select(text_editor::Motion::Left));
+        }
+        doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+            Arc::new(expansion),
+        )));
+    }
+
+    // Replaces the snippet trigger left of the cursor by the snippet, the
+    // caret left at its $CURSOR; false when there is nothing to expand
+    fn expand_snippet(&mut self) -> bool {
+        if self.snippets.is_empty() || self.active_doc().content.selection().is_some() {
+            return false;
+        }
+        let before = self.text_before_cursor();
+        let language = self.active_doc().language();
+        let Some(found) = snippets::find(&self.snippets, language, &before) else {
+            return false;
+        };
+        let indent = &before[..before.len() - before.trim_start().len()];
+        let (text, offset) = found.expand(indent);
+        let length = found.trigger.chars().count();
+        let line = self.active_doc().content.cursor().position.line;
+        let column = before.chars().count() - length;
+        let doc = self.active_doc_mut();
+        for _ in 0..length {
+            doc.content
+                .perform(text_editor::Action::Select(text_editor::Motion::Left));
+        }
+        let _ = self.handle_editor_action(text_editor::Action::Edit(text_editor::Edit::Paste(
+            Arc::new(text.clone()),
+        )));
+        // The caret ends up after the snippet; it goes back to the placeholder
+        let inserted: String = text.chars().take(offset).collect();
+        let (line, column) = match inserted.rsplit_once('\n') {
+            Some((above, last)) => (line + above.matches('\n').count() + 1, last.chars().count()),
+            None => (line, column + offset),
+        };
+        self.active_doc_mut().move_cursor(line, column);
+        true
+    }
+
+    fn refresh_stale_stats(&mut self) {
+        for doc in self.tabs.iter_mut().filter(|d| d.stats_stale()) {
             doc.update_stats_cache();
         }
-        if let Some(delta) = scroll_delta {
-            let doc = self.active_doc_mut();
-            let max_offset = doc.content.line_count().saturating_sub(1) as f32;
-            doc.scroll_offset = (doc.scroll_offset + delta as f32).clamp(0.0, max_offset);
+    }
+
+    // Replays the last macro, stopping early once a pass changes neither the
+    // text nor the caret, or brings the caret to the end of the document
+    fn play_macro(&mut self, times: usize) -> Task<Message> {
+        if self.macro_recording.is_some() {
+            self.notify(Severity::Info, "Arrêtez l'enregistrement avant de rejouer la macro");
+            return Task::none();
         }
-        Task::none()
+        if self.last_macro.is_empty() {
+            self.notify(Severity::Info, "Aucune macro enregistrée");
+            return Task::none();
+        }
+        // The typing of the replay merges into this step, so one undo takes it back
+        self.save_snapshot();
+        let steps = self.last_macro.clone();
+        let mut tasks = Vec::new();
+        let state = |n: &Self| (n.active_doc().mirror.version(), n.active_doc().content.cursor());
+        let at_end = |n: &Self| {
+            let content = &n.active_doc().content;
+            let position = content.cursor().position;
+            let last = content.line_count().saturating_sub(1);
+            position.line == last && content.line(last).is_none_or(|l| position.column >= l.text.len())
+        };
+        for _ in 0..times {
+            let (before, started_at_end) = (state(self), at_end(self));
+            for step in &steps {
+                tasks.push(self.update(step.clone()));
+            }
+            if state(self) == before || (!started_at_end && at_end(self)) {
+                break;
+            }
+        }
+        Task::batch(tasks)
     }
 
-    // --- File operations ---
+    fn refresh_path_completion(&mut self) {
+        let before = self.text_before_cursor();
+        let Some(token) = completion::path_token(&before) else {
+            self.path_completion = self.wiki_completion(&before);
+            return;
+        };
+        let base = self
+            .active_doc()
+            .file_path
+            .as_ref()
+            .and_then(|p| p.parent().map(Path::to_path_buf))
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        let entries = completion::complete(&base, token);
+        let (_, prefix) = completion::split_token(token);
+        self.path_completion = (!entries.is_empty()).then(|| PathCompletion {
+            prefix_len: prefix.chars().count(),
+            separator: completion::separator(token),
+            suffix: "",
+            entries,
+            selected: 0,
+        });
+    }
 
-    fn confirm_discard(
-        description: &'static str,
-        on_confirm: impl Fn(bool) -> Message + Send + 'static,
-    ) -> Task<Message> {
-        Task::perform(
-            async move {
-                matches!(
-                    rfd::AsyncMessageDialog::new()
-                        .set_title("Notepad")
-                        .set_description(description)
-                        .set_buttons(rfd::MessageButtons::OkCancel)
-                        .set_level(rfd::MessageLevel::Warning)
-                        .show()
-                        .await,
-                    rfd::MessageDialogResult::Ok
-                )
-            },
-            on_confirm,
-        )
+    // Note names completing the [[link]] typed in a Markdown document
+    fn wiki_completion(&self, before: &str) -> Option<PathCompletion> {
+        if !self.active_doc().is_markdown() {
+            return None;
+        }
+        let prefix = wiki::open_link(before)?;
+        let entries = wiki::complete(&self.notes_folder()?, prefix);
+        (!entries.is_empty()).then(|| PathCompletion {
+            prefix_len: prefix.chars().count(),
+            separator: '/',
+            suffix: "]]",
+            entries,
+            selected: 0,
+        })
     }
 
-    fn handle_file(&mut self, msg: FileMsg) -> Task<Message> {
+    // Where wiki links of the active document are resolved
+    fn notes_folder(&self) -> Option<PathBuf> {
+        self.notes_folder.clone().or_else(|| {
+            let path = self.active_doc().file_path.as_ref()?;
+            path.parent().map(Path::to_path_buf)
+        })
+    }
+
+    // Ctrl+Click on a [[link]] opens the note, creating it when missing
+    fn follow_wiki_link(&mut self) -> Task<Message> {
+        let doc = self.active_doc();
+        if !doc.is_markdown() {
+            return Task::none();
+        }
+        let position = doc.content.cursor().position;
+        let target = doc
+            .content
+            .line(position.line)
+            .and_then(|line| wiki::link_at(&line.text, position.column).map(str::to_string));
+        let Some(target) = target else {
+            return Task::none();
+        };
+        let Some(folder) = self.notes_folder() else {
+            self.notify(
+                Severity::Warning,
+                "Choisissez un dossier de notes dans les paramètres pour suivre ce lien",
+            );
+            return Task::none();
+        };
+        let path = wiki::resolve(&folder, &target);
+        if !path.exists() {
+            let created = std::fs::create_dir_all(&folder)
+                .and_then(|_| std::fs::write(&path, format!("# {target}\n")));
+            if let Err(e) = created {
+                self.notify(Severity::Error, format!("Impossible de créer la note {target} : {e}"));
+                return Task::none();
+            }
+            self.notify(Severity::Info, format!("Note créée : {}", file_display_name(&path)));
+        }
+        self.open_dropped_file(path)
+    }
+
+    fn handle_completion(&mut self, msg: CompletionMsg) -> Task<Message> {
+        let Some(popup) = &mut self.path_completion else {
+            return Task::none();
+        };
+        let count = popup.entries.len();
         match msg {
-            FileMsg::NewTab => {
-                self.tabs.push(Document::default());
-                self.active_tab = self.tabs.len() - 1;
-                Task::none()
+            CompletionMsg::Next => popup.selected = (popup.selected + 1) % count,
+            CompletionMsg::Previous => popup.selected = (popup.selected + count - 1) % count,
+            CompletionMsg::Dismiss => self.path_completion = None,
+            CompletionMsg::Accept => {
+                let index = popup.selected;
+                self.accept_path_completion(index);
             }
-            FileMsg::CloseTab(index) => {
-                if index >= self.tabs.len() {
-                    return Task::none();
-                }
-                if self.tabs[index].is_modified {
-                    Self::confirm_discard(
-                        "Le document a été modifié. Voulez-vous fermer sans enregistrer ?",
-                        move |confirmed| {
-                            Message::File(FileMsg::ConfirmCloseTabResult(confirmed, index))
-                        },
-                    )
-                } else {
-                    self.remove_tab(index);
-                    Task::none()
-                }
+            CompletionMsg::Pick(index) => {
+                // Clicking the popup took the focus away from the editor
+                self.accept_path_completion(index);
+                return operation::focus(editor_id());
             }
-            FileMsg::ConfirmCloseTabResult(confirmed, index) => {
-                if confirmed {
-                    self.remove_tab(index);
-                }
-                Task::none()
+        }
+        Task::none()
+    }
+
+    // Replaces the typed component; a completed directory opens its own listing
+    fn accept_path_completion(&mut self, index: usize) {
+        let Some(popup) = self.path_completion.take() else {
+            return;
+        };
+        let Some(entry) = popup.entries.get(index) else {
+            return;
+        };
+        if self.active_doc().is_read_only() {
+            return;
+        }
+        let mut insert = entry.name.clone();
+        if entry.is_dir {
+            insert.push(popup.separator);
+        } else {
+            insert.push_str(popup.suffix);
+        }
+        self.save_snapshot();
+        let doc = self.active_doc_mut();
+        for _ in 0..popup.prefix_len {
+            doc.content
+                .perform(text_editor::Action::Select(text_editor::Motion::Left));
+        }
+        doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+            Arc::new(insert),
+        )));
+        doc.is_modified = true;
+        doc.update_stats_cache();
+        if entry.is_dir {
+            self.refresh_path_completion();
+        }
+    }
+
+    // --- Color picker ---
+
+    fn handle_color(&mut self, msg: ColorMsg) -> Task<Message> {
+        match msg {
+            ColorMsg::Open(line, start) => {
+                let doc = self.active_doc();
+                let literal = doc
+                    .content
+                    .line(line)
+                    .and_then(|l| color::find_colors(&l.text).into_iter().find(|c| c.start == start));
+                self.color_picker = literal.map(|literal| ColorPicker {
+                    doc: doc.id,
+                    line,
+                    literal,
+                    color: literal.color,
+                    hex_input: literal.color.to_hex(),
+                });
             }
-            FileMsg::SwitchTab(index) => {
-                if index < self.tabs.len() {
-                    self.active_tab = index;
-                    self.find_cursor = 0;
+            ColorMsg::SetChannel(channel, value) => {
+                if let Some(picker) = &mut self.color_picker {
+                    match channel {
+                        0 => picker.color.r = value,
+                        1 => picker.color.g = value,
+                        _ => picker.color.b = value,
+                    }
+                    picker.hex_input = picker.color.to_hex();
                 }
-                Task::none()
             }
-            FileMsg::Save => {
-                if let Some(path) = self.active_doc().file_path.clone() {
-                    self.save_to_file(path);
-                    Task::none()
-                } else {
-                    self.save_as()
+            ColorMsg::HexChanged(input) => {
+                if let Some(picker) = &mut self.color_picker {
+                    if let Some(color) = Rgb::parse_hex(&input) {
+                        picker.color = color;
+                    }
+                    picker.hex_input = input;
                 }
             }
-            FileMsg::SaveAs => self.save_as(),
-            FileMsg::Open => {
-                // Open in a new tab (like Windows Notepad)
-                self.open_file()
-            }
-            FileMsg::SaveFileSelected(path) => {
-                if let Some(path) = path {
-                    self.save_to_file(path);
+            ColorMsg::Apply => {
+                if let Some(picker) = self.color_picker.take() {
+                    self.apply_color(picker);
                 }
-                Task::none()
             }
-            FileMsg::OpenFileSelected(path) => {
-                if let Some(path) = path {
-                    return self.open_dropped_file(path);
-                }
-                Task::none()
+            ColorMsg::Cancel => {
+                self.color_picker = None;
             }
-            FileMsg::CloseRequested(id) => {
-                self.save_session();
-                let any_modified = self.tabs.iter().any(|doc| doc.is_modified);
-                if any_modified {
-                    Self::confirm_discard(
-                        "Des documents ont été modifiés. Voulez-vous quitter sans enregistrer ?",
-                        move |confirmed| {
-                            Message::File(FileMsg::ConfirmCloseResult(confirmed, id))
-                        },
-                    )
-                } else {
-                    iced::window::close(id)
-                }
-            }
-            FileMsg::ConfirmCloseResult(confirmed, id) => {
-                if confirmed {
-                    self.save_session();
-                    iced::window::close(id)
-                } else {
-                    Task::none()
-                }
-            }
-            FileMsg::AutoSave => {
-                for doc in &mut self.tabs {
-                    if doc.is_modified {
-                        if let Some(path) = doc.file_path.clone() {
-                            if std::fs::write(&path, doc.encode_content()).is_ok() {
-                                doc.is_modified = false;
-                                doc.last_file_modified = std::fs::metadata(&path)
-                                    .ok()
-                                    .and_then(|m| m.modified().ok());
-                                let name = path
-                                    .file_name()
-                                    .and_then(|n| n.to_str())
-                                    .unwrap_or("fichier")
-                                    .to_string();
-                                doc.status_message = Some(format!("Enregistré : {name}"));
-                            }
-                        }
-                    }
-                }
-                Task::none()
-            }
-            FileMsg::CheckExternalChanges => {
-                for i in 0..self.tabs.len() {
-                    let doc = &self.tabs[i];
-                    if doc.externally_modified {
-                        continue;
-                    }
-                    let (path, last_known) = match (&doc.file_path, doc.last_file_modified) {
-                        (Some(p), Some(t)) => (p.clone(), t),
-                        _ => continue,
-                    };
+        }
+        Task::none()
+    }
 
-                    let current_modified = match std::fs::metadata(&path)
-                        .and_then(|m| m.modified())
-                    {
-                        Ok(t) => t,
-                        Err(_) => {
-                            let name = path
-                                .file_name()
-                                .and_then(|n| n.to_str())
-                                .unwrap_or("fichier")
-                                .to_string();
-                            self.tabs[i].status_message =
-                                Some(format!("Fichier supprimé : {name}"));
-                            self.tabs[i].last_file_modified = None;
-                            continue;
-                        }
-                    };
+    // Rewrites the literal as one undoable edit, if the line still holds it
+    fn apply_color(&mut self, picker: ColorPicker) {
+        let Some(index) = self.tabs.iter().position(|d| d.id == picker.doc) else {
+            return;
+        };
+        let unchanged = self.tabs[index]
+            .content
+            .line(picker.line)
+            .is_some_and(|l| color::find_colors(&l.text).contains(&picker.literal));
+        if !unchanged {
+            self.notify(Severity::Warning, "La couleur a été modifiée entre-temps");
+            return;
+        }
+        if self.tabs[index].is_read_only() || picker.color == picker.literal.color {
+            return;
+        }
+        self.active_tab = index;
+        self.save_snapshot();
+        let doc = self.active_doc_mut();
+        doc.content.move_to(text_editor::Cursor {
+            position: text_editor::Position {
+                line: picker.line,
+                column: picker.literal.end,
+            },
+            selection: Some(text_editor::Position {
+                line: picker.line,
+                column: picker.literal.start,
+            }),
+        });
+        let replacement = picker.literal.format(picker.color);
+        doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(replacement))));
+        doc.is_modified = true;
+        doc.update_stats_cache();
+    }
 
-                    if current_modified > last_known {
-                        self.tabs[i].externally_modified = true;
-                    }
-                }
-                Task::none()
+    // --- Markdown tables ---
+
+    fn handle_table(&mut self, msg: TableMsg) -> Task<Message> {
+        if self.active_doc().is_read_only() {
+            return Task::none();
+        }
+        if let TableMsg::Insert = msg {
+            self.insert_table();
+            return Task::none();
+        }
+        let Some((range, mut table, row, column)) = self.current_table() else {
+            return Task::none();
+        };
+        let (row, column) = match msg {
+            TableMsg::InsertRow => {
+                table.insert_row(row + 1);
+                (row + 1, column)
             }
-            FileMsg::ReloadFile(idx) => {
-                if let Some(path) = self.tabs.get(idx).and_then(|d| d.file_path.clone()) {
-                    self.active_tab = idx;
-                    self.tabs[idx].externally_modified = false;
-                    self.load_from_file(path);
-                }
-                Task::none()
+            TableMsg::RemoveRow if table.remove_row(row) => (row.min(table.rows.len() - 1), column),
+            TableMsg::InsertColumn => {
+                table.insert_column(column + 1);
+                (row, column + 1)
             }
-            FileMsg::IgnoreExternalChange(idx) => {
-                if let Some(doc) = self.tabs.get_mut(idx) {
-                    doc.externally_modified = false;
-                    if let Some(path) = &doc.file_path {
-                        doc.last_file_modified =
-                            std::fs::metadata(path).ok().and_then(|m| m.modified().ok());
-                    }
-                }
-                Task::none()
+            TableMsg::RemoveColumn if table.remove_column(column) => {
+                (row, column.min(table.column_count() - 1))
             }
-        }
+            _ => return Task::none(),
+        };
+        self.replace_table(range, &table, row, column);
+        Task::none()
     }
 
-    fn remove_tab(&mut self, index: usize) {
-        if self.tabs.len() <= 1 {
-            // Last tab: replace with empty document
-            self.tabs[0] = Document::default();
-            self.active_tab = 0;
-        } else {
-            self.tabs.remove(index);
-            if self.active_tab >= self.tabs.len() {
-                self.active_tab = self.tabs.len() - 1;
-            } else if self.active_tab > index {
-                self.active_tab -= 1;
-            }
+    // Table under the cursor of a Markdown document, with the row and column of the cursor
+    fn current_table(&self) -> Option<(std::ops::Range<usize>, Table, usize, usize)> {
+        let doc = self.active_doc();
+        if !doc.is_markdown() {
+            return None;
         }
+        let content = &doc.content;
+        let position = content.cursor().position;
+        let range = markdown::table_range(position.line, content.line_count(), |i| {
+            content.line(i).is_some_and(|l| markdown::is_table_row(&l.text))
+        })?;
+        let lines: Vec<String> = range
+            .clone()
+            .filter_map(|i| content.line(i).map(|l| l.text.to_string()))
+            .collect();
+        let column = markdown::column_at(&lines[position.line - range.start], position.column);
+        let table = Table::parse(&lines);
+        let row = table.row_of_line(position.line - range.start);
+        let column = column.min(table.column_count() - 1);
+        Some((range, table, row, column))
     }
 
-    fn open_dropped_file(&mut self, path: PathBuf) -> Task<Message> {
+    // Rewrites the table lines as one undoable edit and puts the cursor in the given cell
+    fn replace_table(&mut self, range: std::ops::Range<usize>, table: &Table, row: usize, column: usize) {
+        let rendered = table.render();
+        self.save_snapshot();
+        let doc = self.active_doc_mut();
+        let last = range.end - 1;
+        let last_len = doc.content.line(last).map_or(0, |l| l.text.len());
+        doc.content.move_to(text_editor::Cursor {
+            position: text_editor::Position { line: last, column: last_len },
+            selection: Some(text_editor::Position { line: range.start, column: 0 }),
+        });
+        let joined = rendered.join(doc.line_ending.as_str());
+        doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(joined))));
+        let line = table.line_of_row(row);
+        doc.content.move_to(text_editor::Cursor {
+            position: text_editor::Position {
+                line: range.start + line,
+                column: markdown::cell_start(&rendered[line], column),
+            },
+            selection: None,
+        });
+        doc.is_modified = true;
+        doc.update_stats_cache();
+    }
+
+    // Puts the `lower` paragraph before the `upper` one, the blank lines between
+    // them staying in place; the cursor follows the paragraph it was in
+    fn swap_paragraphs(&mut self, upper: std::ops::Range<usize>, lower: std::ops::Range<usize>, cursor_in_upper: bool) {
         let doc = self.active_doc();
-        let reuse = !doc.is_modified
-            && doc.file_path.is_none()
-            && doc.content.text().trim().is_empty();
-        if !reuse {
-            self.tabs.push(Document::default());
-            self.active_tab = self.tabs.len() - 1;
-        }
-        self.load_from_file(path);
-        Task::none()
+        let cursor = doc.content.cursor().position;
+        let lines = |range: std::ops::Range<usize>| range.map(|i| doc.mirror.line(i)).collect::<Vec<_>>();
+        let (above, gap, below) = (lines(upper.clone()), lines(upper.end..lower.start), lines(lower.clone()));
+        self.replace_lines(upper.start..lower.end, &[below, gap, above].concat());
+        let line = if cursor_in_upper {
+            cursor.line + lower.end - upper.end
+        } else {
+            cursor.line - (lower.start - upper.start)
+        };
+        self.active_doc_mut().content.move_to(text_editor::Cursor {
+            position: text_editor::Position { line, column: cursor.column },
+            selection: None,
+        });
     }
 
-    // --- Edit operations ---
+    // Whole lines touched by the selection, the cursor line without one. A
+    // selection ending at the start of a line leaves that line out.
+    fn selected_lines(&self) -> std::ops::Range<usize> {
+        let cursor = self.active_doc().content.cursor();
+        let anchor = cursor.selection.unwrap_or(cursor.position);
+        let (start, end) = if (anchor.line, anchor.column) <= (cursor.position.line, cursor.position.column) {
+            (anchor, cursor.position)
+        } else {
+            (cursor.position, anchor)
+        };
+        let last = if end.line > start.line && end.column == 0 { end.line - 1 } else { end.line };
+        start.line..last + 1
+    }
 
-    fn handle_edit(&mut self, msg: EditMsg) -> Task<Message> {
+    // Replaces whole lines as one undo step, the cursor left after the new text
+    fn replace_lines(&mut self, range: std::ops::Range<usize>, lines: &[String]) {
+        self.save_snapshot();
+        let doc = self.active_doc_mut();
+        let text = lines.join(doc.line_ending.as_str());
+        let last_len = doc.content.line(range.end - 1).map_or(0, |l| l.text.len());
+        doc.content.move_to(text_editor::Cursor {
+            position: text_editor::Position { line: range.end - 1, column: last_len },
+            selection: Some(text_editor::Position { line: range.start, column: 0 }),
+        });
+        doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(text))));
+        doc.is_modified = true;
+        doc.update_stats_cache();
+    }
+
+    // Inserts a 3×2 table skeleton on its own lines below the cursor
+    fn insert_table(&mut self) {
+        self.save_snapshot();
+        let doc = self.active_doc_mut();
+        let eol = doc.line_ending.as_str();
+        let line = doc.content.cursor().position.line;
+        let line_len = doc.content.line(line).map_or(0, |l| l.text.len());
+        let prefix = if line_len > 0 { eol } else { "" };
+        doc.content.move_to(text_editor::Cursor {
+            position: text_editor::Position { line, column: line_len },
+            selection: None,
+        });
+        let skeleton = markdown::table_skeleton(3, 2);
+        let text = format!("{}{}", prefix, skeleton.join(eol));
+        doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(text))));
+        let header = line + usize::from(line_len > 0);
+        doc.content.move_to(text_editor::Cursor {
+            position: text_editor::Position {
+                line: header,
+                column: markdown::cell_start(&skeleton[0], 0),
+            },
+            selection: None,
+        });
+        doc.is_modified = true;
+        doc.update_stats_cache();
+    }
+
+    // --- Named snapshots ---
+
+    fn handle_snapshot(&mut self, msg: SnapshotMsg) -> Task<Message> {
         match msg {
-            EditMsg::Copy => {
-                let doc = &self.tabs[self.active_tab];
-                if let Some(clipboard) = &mut self.clipboard {
-                    if let Some(selected) = doc.content.selection() {
-                        if let Err(e) = clipboard.set_text(selected) {
-                            rfd::MessageDialog::new()
-                                .set_title("Erreur")
-                                .set_description(format!(
-                                    "Impossible de copier dans le presse-papiers :\n{e}"
-                                ))
-                                .set_level(rfd::MessageLevel::Error)
-                                .set_buttons(rfd::MessageButtons::Ok)
-                                .show();
-                        }
-                    }
-                }
-                Task::none()
-            }
-            EditMsg::Cut => {
-                let selected = self.tabs[self.active_tab].content.selection();
-                if let Some(clipboard) = &mut self.clipboard {
-                    if let Some(selected) = selected {
-                        if let Err(e) = clipboard.set_text(selected) {
-                            rfd::MessageDialog::new()
-                                .set_title("Erreur")
-                                .set_description(format!(
-                                    "Impossible de copier dans le presse-papiers :\n{e}"
-                                ))
-                                .set_level(rfd::MessageLevel::Error)
-                                .set_buttons(rfd::MessageButtons::Ok)
-                                .show();
-                        } else {
-                            self.save_snapshot();
-                            let doc = self.active_doc_mut();
-                            doc.content.perform(text_editor::Action::Edit(
-                                text_editor::Edit::Backspace,
-                            ));
-                            doc.is_modified = true;
-                            doc.update_stats_cache();
-                        }
-                    }
-                }
-                Task::none()
+            SnapshotMsg::Create => {
+                self.show_snapshots = true;
+                self.snapshot_name = self.default_snapshot_name();
+                return operation::focus(snapshot_name_input_id());
             }
-            EditMsg::Paste => {
-                if let Some(clipboard) = &mut self.clipboard {
-                    match clipboard.get_text() {
-                        Ok(clip_text) => {
-                            self.save_snapshot();
-                            let doc = self.active_doc_mut();
-                            doc.content.perform(text_editor::Action::Edit(
-                                text_editor::Edit::Paste(Arc::new(clip_text)),
-                            ));
-                            doc.is_modified = true;
-                            doc.update_stats_cache();
-                        }
-                        Err(e) => {
-                            rfd::MessageDialog::new()
-                                .set_title("Erreur")
-                                .set_description(format!(
-                                    "Impossible de lire le presse-papiers :\n{e}"
-                                ))
-                                .set_level(rfd::MessageLevel::Error)
-                                .set_buttons(rfd::MessageButtons::Ok)
-                                .show();
-                        }
-                    }
+            SnapshotMsg::TogglePanel => {
+                self.show_snapshots = !self.show_snapshots;
+                if self.show_snapshots && self.snapshot_name.is_empty() {
+                    self.snapshot_name = self.default_snapshot_name();
                 }
-                Task::none()
-            }
-            EditMsg::SelectAll => {
-                let doc = self.active_doc_mut();
-                doc.content
-                    .perform(text_editor::Action::Move(text_editor::Motion::DocumentStart));
-                doc.content
-                    .perform(text_editor::Action::Select(text_editor::Motion::DocumentEnd));
-                Task::none()
-            }
-            EditMsg::Undo => {
-                self.undo();
-                Task::none()
             }
-            EditMsg::Redo => {
-                self.redo();
-                Task::none()
+            SnapshotMsg::NameChanged(name) => {
+                self.snapshot_name = name;
             }
-            EditMsg::InsertDateTime => {
-                let now = std::time::SystemTime::now();
-                let secs = now
+            SnapshotMsg::Save => {
+                let name = match self.snapshot_name.trim() {
+                    "" => self.default_snapshot_name(),
+                    name => name.to_string(),
+                };
+                let secs = std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap_or_default()
                     .as_secs();
-                // Convert to local time using platform API
-                let datetime_str = format_local_datetime(secs);
+                let doc = self.active_doc_mut();
+                let position = doc.content.cursor().position;
+                doc.snapshots.push(NamedSnapshot {
+                    name: name.clone(),
+                    taken: format_local_datetime(secs),
+                    text: doc.text(),
+                    lines: doc.mirror.line_count(),
+                    cursor_line: position.line,
+                    cursor_col: position.column,
+                });
+                self.snapshot_name = self.default_snapshot_name();
+                self.notify(Severity::Success, format!("Instantané « {name} » créé"));
+            }
+            SnapshotMsg::Restore(index) => {
+                let Some(snapshot) = self.active_doc().snapshots.get(index) else {
+                    return Task::none();
+                };
+                let (name, text) = (snapshot.name.clone(), snapshot.text.clone());
+                let (line, col) = (snapshot.cursor_line, snapshot.cursor_col);
+                if *text == *self.active_doc().text() {
+                    return Task::none();
+                }
+                // Undoable like any other edit
                 self.save_snapshot();
                 let doc = self.active_doc_mut();
-                doc.content.perform(text_editor::Action::Edit(
-                    text_editor::Edit::Paste(Arc::new(datetime_str)),
-                ));
+                doc.set_text(&text);
                 doc.is_modified = true;
                 doc.update_stats_cache();
-                Task::none()
+                self.navigate_to(line, col);
+                self.notify(Severity::Info, format!("Instantané « {name} » restauré"));
+            }
+            SnapshotMsg::Delete(index) => {
+                let doc = self.active_doc_mut();
+                if index < doc.snapshots.len() {
+                    doc.snapshots.remove(index);
+                }
             }
         }
+        Task::none()
     }
 
-    // --- Search operations ---
+    // --- Tags and note search ---
 
-    fn handle_search(&mut self, msg: SearchMsg) -> Task<Message> {
+    fn handle_notes(&mut self, msg: NotesMsg) -> Task<Message> {
         match msg {
-            SearchMsg::OpenFind => {
-                self.show_find = true;
-                self.show_replace = false;
-                self.show_goto = false;
-                operation::focus(find_input_id())
+            NotesMsg::EditTags => {
+                let Some(path) = self.active_doc().file_path.clone() else {
+                    self.notify(
+                        Severity::Warning,
+                        "Enregistrez le document pour lui ajouter des étiquettes",
+                    );
+                    return Task::none();
+                };
+                let meta = self.notes.get(&path);
+                self.tag_editor = Some(TagEditor {
+                    input: meta.map(|m| m.tags.join(", ")).unwrap_or_default(),
+                    favorite: meta.is_some_and(|m| m.favorite),
+                    path,
+                });
+                return operation::focus(tags_input_id());
             }
-            SearchMsg::OpenReplace => {
-                self.show_find = true;
-                self.show_replace = true;
-                self.show_goto = false;
-                operation::focus(find_input_id())
+            NotesMsg::TagsInputChanged(input) => {
+                if let Some(editor) = &mut self.tag_editor {
+                    editor.input = input;
+                }
             }
-            SearchMsg::CloseFind => {
-                self.show_find = false;
-                self.show_replace = false;
-                Task::none()
+            NotesMsg::ToggleFavorite => {
+                if let Some(editor) = &mut self.tag_editor {
+                    editor.favorite = !editor.favorite;
+                }
             }
-            SearchMsg::FindQueryChanged(query) => {
-                self.find_query = query;
-                self.find_cursor = 0;
-                Task::none()
+            NotesMsg::SaveTags => {
+                if let Some(editor) = self.tag_editor.take() {
+                    let tags = notes::parse_tags(&editor.input);
+                    self.notes.set_tags(&editor.path, tags, editor.favorite);
+                    self.notes.save();
+                }
+                return operation::focus(editor_id());
             }
-            SearchMsg::ReplaceQueryChanged(query) => {
-                self.replace_query = query;
-                Task::none()
+            NotesMsg::CloseTags => {
+                self.tag_editor = None;
+                return operation::focus(editor_id());
             }
-            SearchMsg::FindNext => {
-                self.find_next();
-                Task::none()
+            NotesMsg::OpenSearch => {
+                if self.note_search.is_none() {
+                    self.note_search = Some(NoteSearch::default());
+                }
+                return Task::batch([operation::focus(note_search_input_id()), self.search_notes()]);
             }
-            SearchMsg::FindPrevious => {
-                self.find_previous();
-                Task::none()
+            NotesMsg::CloseSearch => {
+                self.note_search = None;
+                return operation::focus(editor_id());
             }
-            SearchMsg::ReplaceOne => {
-                self.replace_one();
-                Task::none()
+            NotesMsg::QueryChanged(query) => {
+                if let Some(search) = &mut self.note_search {
+                    search.query = query;
+                }
+                return self.search_notes();
             }
-            SearchMsg::ReplaceAll => {
-                self.replace_all();
-                Task::none()
+            NotesMsg::Results(generation, results) => {
+                if let Some(search) = &mut self.note_search {
+                    if search.generation == generation {
+                        search.results = results;
+                        search.searching = false;
+                    }
+                }
             }
-            SearchMsg::OpenGoTo => {
-                self.show_goto = true;
-                self.show_find = false;
-                self.show_replace = false;
-                self.goto_input.clear();
-                operation::focus(goto_input_id())
+            NotesMsg::Open(path) => {
+                self.note_search = None;
+                return self.open_dropped_file(path);
             }
-            SearchMsg::CloseGoTo => {
-                self.show_goto = false;
-                Task::none()
+            NotesMsg::Export(format) => {
+                if self.notes_folder.is_none() {
+                    self.notify(
+                        Severity::Warning,
+                        "Choisissez un dossier de notes dans les paramètres pour l'exporter",
+                    );
+                    return Task::none();
+                }
+                return Task::future(async move {
+                    let dialog = rfd::AsyncFileDialog::new();
+                    let handle = match format {
+                        ExportFormat::Site => {
+                            dialog.set_title("Exporter les notes en site HTML").pick_folder().await
+                        }
+                        ExportFormat::Pdf => {
+                            dialog
+                                .set_title("Exporter les notes en PDF")
+                                .add_filter("PDF", &["pdf"])
+                                .set_file_name("notes.pdf")
+                                .save_file()
+                                .await
+                        }
+                    };
+                    handle.map(|handle| handle.path().to_path_buf())
+                })
+                .and_then(move |target| Task::done(Message::Notes(NotesMsg::ExportTo(format, target))));
             }
-            SearchMsg::GoToInputChanged(value) => {
-                self.goto_input = value;
-                Task::none()
-            }
-            SearchMsg::GoToLineSubmit => {
-                let line_count = self.active_doc().content.line_count();
-                match self.goto_input.parse::<usize>() {
-                    Ok(n) if n >= 1 && n <= line_count => {
-                        self.navigate_to(n - 1, 0);
-                        self.show_goto = false;
-                        self.active_doc_mut().status_message = None;
-                        return Task::none();
-                    }
-                    Ok(_) => {
-                        self.active_doc_mut().status_message = Some(format!(
-                            "Numéro de ligne invalide (1–{})",
-                            line_count
-                        ));
-                    }
-                    Err(_) => {
-                        self.active_doc_mut().status_message =
-                            Some("Entrez un numéro de ligne valide".to_string());
-                    }
+            NotesMsg::ExportTo(format, target) => return self.export_notes(format, target),
+            NotesMsg::Exported(id, target, result) => {
+                self.jobs.finish(id);
+                match result {
+                    Ok(count) => self.notify(
+                        Severity::Success,
+                        format!("{count} note(s) exportée(s) vers {}", target.display()),
+                    ),
+                    Err(e) => self.notify(Severity::Error, format!("Exportation impossible : {e}")),
                 }
-                Task::none()
-            }
-            SearchMsg::ToggleCaseSensitive => {
-                self.case_sensitive = !self.case_sensitive;
-                self.find_cursor = 0;
-                Task::none()
-            }
-            SearchMsg::ToggleRegex => {
-                self.use_regex = !self.use_regex;
-                self.find_cursor = 0;
-                Task::none()
             }
         }
+        Task::none()
     }
 
-    // --- View operations ---
+    // Switches to the tab of the file, opening it when needed
+    fn tab_with_file(&self, path: &Path) -> Option<usize> {
+        self.tabs
+            .iter()
+            .position(|d| d.file_path.as_deref().is_some_and(|p| paths::same_file(p, path)))
+    }
 
-    fn handle_view(&mut self, msg: ViewMsg) -> Task<Message> {
+    fn export_notes(&mut self, format: ExportFormat, target: PathBuf) -> Task<Message> {
+        let Some(folder) = self.notes_folder.clone() else {
+            return Task::none();
+        };
+        let label = format!("Exportation des notes en {}", format.label());
+        let (_, task) = self.jobs.spawn(label, true, move |id| {
+            let worker_target = target.clone();
+            let worker = jobs::run_blocking(move |report| {
+                export::export(format, &folder, &worker_target, report)
+            });
+            Task::run(worker, move |event| match event {
+                JobEvent::Progress(p) => Message::Job(JobMsg::Progress(id, p)),
+                JobEvent::Done(result) => Message::Notes(NotesMsg::Exported(id, target.clone(), result)),
+            })
+        });
+        task
+    }
+
+    // Reads the files off the UI thread; a newer query makes these results stale
+    fn search_notes(&mut self) -> Task<Message> {
+        let entries = self.notes.entries().to_vec();
+        let Some(search) = &mut self.note_search else {
+            return Task::none();
+        };
+        search.generation += 1;
+        search.searching = true;
+        let (generation, query) = (search.generation, search.query.clone());
+        Task::future(jobs::spawn_blocking(move || notes::search(entries, &query))).map(move |results| {
+            Message::Notes(NotesMsg::Results(generation, results.unwrap_or_default()))
+        })
+    }
+
+    // Remembers the file among the recent notes and in File → Fichiers récents
+    fn record_note(&mut self, path: &Path) {
+        self.recent_files.push(path);
+        self.recent_files.save();
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.notes.touch(path, secs);
+        self.notes.save();
+    }
+
+    fn default_snapshot_name(&self) -> String {
+        format!("Instantané {}", self.active_doc().snapshots.len() + 1)
+    }
+
+    // --- Folder picker ---
+
+    fn handle_folder(&mut self, msg: FolderMsg) -> Task<Message> {
         match msg {
-            ViewMsg::ZoomIn => {
-                self.font_size = (self.font_size + ZOOM_STEP).min(MAX_FONT_SIZE);
-                self.save_preferences();
+            FolderMsg::Choose => {
+                return Task::future(async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Ouvrir un dossier")
+                        .pick_folder()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                })
+                .and_then(|folder| Task::done(Message::Folder(FolderMsg::Show(folder))));
             }
-            ViewMsg::ZoomOut => {
-                self.font_size = (self.font_size - ZOOM_STEP).max(MIN_FONT_SIZE);
-                self.save_preferences();
+            FolderMsg::Show(folder) => {
+                let folder = paths::normalize(&folder);
+                self.folder_picker = Some(FolderPicker {
+                    folder: folder.clone(),
+                    query: String::new(),
+                    files: None,
+                });
+                let worker_folder = folder.clone();
+                let listing = Task::future(jobs::spawn_blocking(move || folder::text_files(&worker_folder)))
+                    .map(move |files| Message::Folder(FolderMsg::Listed(folder.clone(), files.unwrap_or_default())));
+                return Task::batch([operation::focus(folder_filter_input_id()), listing]);
             }
-            ViewMsg::ZoomReset => {
-                self.font_size = DEFAULT_FONT_SIZE;
-                self.save_preferences();
+            FolderMsg::Listed(folder, files) => {
+                // Dropped when another folder was opened meanwhile
+                if let Some(picker) = self.folder_picker.as_mut().filter(|p| p.folder == folder) {
+                    picker.files = Some(files);
+                }
             }
-            ViewMsg::ToggleDarkMode => {
-                self.dark_mode = !self.dark_mode;
-                self.save_preferences();
+            FolderMsg::QueryChanged(query) => {
+                if let Some(picker) = &mut self.folder_picker {
+                    picker.query = query;
+                }
             }
-            ViewMsg::ToggleWordWrap => {
-                self.word_wrap = !self.word_wrap;
-                self.save_preferences();
+            FolderMsg::Open(rel) => {
+                if let Some(picker) = self.folder_picker.take() {
+                    return self.open_dropped_file(picker.folder.join(rel));
+                }
+            }
+            FolderMsg::OpenFirst => {
+                let first = self.folder_picker.as_ref().and_then(|picker| {
+                    folder::filter(picker.files.as_deref()?, &picker.query).next().cloned()
+                });
+                if let Some(rel) = first {
+                    return self.handle_folder(FolderMsg::Open(rel));
+                }
+            }
+            FolderMsg::Close => {
+                self.folder_picker = None;
+                return operation::focus(editor_id());
             }
         }
         Task::none()
     }
 
-    // --- Settings ---
+    // --- Help ---
 
-    fn handle_settings(&mut self, msg: SettingsMsg) -> Task<Message> {
+    fn handle_help(&mut self, msg: HelpMsg) -> Task<Message> {
         match msg {
-            SettingsMsg::Open => {
-                self.show_settings = true;
-            }
-            SettingsMsg::Close => {
-                self.show_settings = false;
+            HelpMsg::ShowLogs => match logging::latest_log() {
+                Some(path) => self.handle_file(FileMsg::OpenFileSelected(Some(path))),
+                None => {
+                    let msg = format!("Aucun journal dans {}", logging::dir().display());
+                    self.notify(Severity::Info, msg);
+                    Task::none()
+                }
+            },
+            HelpMsg::ToggleShortcuts => {
+                if self.shortcut_sheet.take().is_some() {
+                    return operation::focus(editor_id());
+                }
+                self.shortcut_sheet = Some(String::new());
+                operation::focus(shortcut_input_id())
             }
-            SettingsMsg::SetDarkMode(v) => {
-                self.dark_mode = v;
-                self.save_preferences();
+            HelpMsg::ShortcutQueryChanged(query) => {
+                self.shortcut_sheet = Some(query);
+                Task::none()
             }
-            SettingsMsg::SetFontSize(v) => {
-                self.font_size = v.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
-                self.save_preferences();
+            HelpMsg::ToggleAbout => {
+                self.show_about = !self.show_about;
+                Task::none()
             }
-            SettingsMsg::SetWordWrap(v) => {
-                self.word_wrap = v;
-                self.save_preferences();
+            HelpMsg::CheckUpdates => {
+                self.show_about = true;
+                if self.update_check == UpdateCheck::Checking {
+                    return Task::none();
+                }
+                self.update_check = UpdateCheck::Checking;
+                let proxy = self.proxy.clone();
+                Task::future(jobs::spawn_blocking(move || updates::latest_release(&proxy))).map(|result| {
+                    let result = result.unwrap_or_else(|| Err("vérification interrompue".to_string()));
+                    Message::Help(HelpMsg::UpdateChecked(result))
+                })
             }
-            SettingsMsg::SetRestoreSession(v) => {
-                self.restore_session = v;
-                self.save_preferences();
-                if !v {
-                    SessionData::clear();
+            HelpMsg::UpdateChecked(result) => {
+                if let Err(e) = &result {
+                    tracing::warn!("vérification des mises à jour impossible : {e}");
                 }
+                self.update_check = UpdateCheck::from_result(result);
+                Task::none()
             }
-        }
-        Task::none()
-    }
-
-    // --- Format operations ---
-
-    fn handle_format(&mut self, msg: FormatMsg) -> Task<Message> {
-        match msg {
-            FormatMsg::SetFontFamily(name) => {
-                self.font_family = name;
-                self.save_preferences();
+            HelpMsg::OpenUrl(url) => {
+                if let Err(e) = updates::open_url(&url) {
+                    self.notify(Severity::Error, format!("Impossible d'ouvrir {url} : {e}"));
+                }
+                Task::none()
             }
         }
-        Task::none()
     }
 
-    // --- Menu operations ---
+    // --- Terminal panel ---
 
-    fn handle_menu(&mut self, msg: MenuMsg) -> Task<Message> {
+    fn handle_terminal(&mut self, msg: TerminalMsg) -> Task<Message> {
         match msg {
-            MenuMsg::Toggle(menu) => {
-                if self.active_menu == Some(menu) {
-                    self.active_menu = None;
+            TerminalMsg::TogglePanel => {
+                self.show_terminal = !self.show_terminal;
+                if !self.show_terminal {
+                    return Task::none();
+                }
+                let task = if self.terminal.is_none() {
+                    self.start_terminal()
                 } else {
-                    self.active_menu = Some(menu);
+                    Task::none()
+                };
+                return Task::batch([task, operation::focus(terminal_input_id())]);
+            }
+            TerminalMsg::InputChanged(input) => {
+                if let Some(term) = &mut self.terminal {
+                    term.input = input;
                 }
-                self.show_context_menu = false;
             }
-            MenuMsg::Hover(menu) => {
-                if self.active_menu.is_some() {
-                    self.active_menu = Some(menu);
+            TerminalMsg::Submit => {
+                if let Some(term) = &mut self.terminal {
+                    let command = std::mem::take(&mut term.input);
+                    term.output.push_line(format!("> {}", command));
+                    match &mut term.shell {
+                        Some(shell) => {
+                            if let Err(e) = shell.send(&command) {
+                                term.output.push_line(format!("Erreur : {}", e));
+                            }
+                        }
+                        None => term
+                            .output
+                            .push_line("Le terminal est arrêté, cliquez sur « Redémarrer »"),
+                    }
                 }
             }
-            MenuMsg::CloseAll => {
-                self.active_menu = None;
-                self.show_context_menu = false;
+            TerminalMsg::Output(id, chunk) => {
+                if let Some(term) = self.terminal.as_mut().filter(|t| t.id == id) {
+                    term.output.push(&chunk);
+                }
             }
-            MenuMsg::ShowContext => {
-                self.show_context_menu = true;
-                self.context_menu_position = self.mouse_position;
-                self.active_menu = None;
+            TerminalMsg::Closed(id) => {
+                if let Some(term) = self.terminal.as_mut().filter(|t| t.id == id) {
+                    let code = term.shell.take().and_then(|mut shell| shell.exit_code());
+                    term.output.push_line(match code {
+                        Some(code) => format!("[Processus terminé (code {})]", code),
+                        None => "[Processus terminé]".to_string(),
+                    });
+                }
+            }
+            TerminalMsg::Clear => {
+                if let Some(term) = &mut self.terminal {
+                    term.output.clear();
+                }
+            }
+            TerminalMsg::Restart => {
+                self.terminal = None;
+                return Task::batch([self.start_terminal(), operation::focus(terminal_input_id())]);
             }
         }
         Task::none()
     }
 
-    // --- Event handling ---
-
-    fn handle_event(&mut self, event: Event) -> Task<Message> {
-        if let Event::Mouse(iced::mouse::Event::CursorMoved { position }) = &event {
-            self.mouse_position = *position;
-        }
+    // Starts the shell in the active document's folder (or the current directory)
+    fn start_terminal(&mut self) -> Task<Message> {
+        let cwd = self
+            .active_doc()
+            .file_path
+            .as_deref()
+            .and_then(Path::parent)
+            .filter(|dir| dir.is_dir())
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        self.terminal_sessions += 1;
+        let id = self.terminal_sessions;
+        let mut output = TerminalOutput::default();
+        let (shell, task) = match Shell::spawn(&cwd) {
+            Ok((shell, events)) => {
+                output.push_line(format!("{} — {}", terminal::shell_name(), cwd.display()));
+                let task = Task::run(events, move |event| match event {
+                    ShellEvent::Output(chunk) => Message::Terminal(TerminalMsg::Output(id, chunk)),
+                    ShellEvent::Closed => Message::Terminal(TerminalMsg::Closed(id)),
+                });
+                (Some(shell), task)
+            }
+            Err(e) => {
+                output.push_line(format!("Impossible de démarrer le terminal : {}", e));
+                (None, Task::none())
+            }
+        };
+        self.terminal = Some(Terminal {
+            id,
+            cwd,
+            output,
+            input: String::new(),
+            shell,
+        });
+        task
+    }
 
-        // Track modifier keys for Ctrl+wheel zoom
-        if let Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = &event {
-            self.ctrl_pressed = modifiers.control();
-        }
+    // --- Handwritten annotations ---
 
-        // Global mouse wheel scroll — works regardless of which widget the mouse is over
-        if let Event::Mouse(iced::mouse::Event::WheelScrolled { delta }) = &event {
-            let lines = match delta {
-                iced::mouse::ScrollDelta::Lines { y, .. } => *y,
-                iced::mouse::ScrollDelta::Pixels { y, .. } => *y / (self.font_size * 1.3),
-            };
-            if lines != 0.0 {
-                let int_lines = if lines > 0.0 {
-                    -(lines.ceil() as i32)
-                } else {
-                    (-lines).ceil() as i32
-                };
-                if self.ctrl_pressed {
-                    return if int_lines < 0 {
-                        self.handle_view(ViewMsg::ZoomIn)
-                    } else {
-                        self.handle_view(ViewMsg::ZoomOut)
-                    };
+    fn handle_ink(&mut self, msg: InkMsg) -> Task<Message> {
+        match msg {
+            InkMsg::Toggle => {
+                self.show_ink = !self.show_ink;
+                self.active_doc_mut().ink_stroke = None;
+            }
+            InkMsg::Begin(point) => {
+                self.active_doc_mut().ink_stroke = Some(ink::Stroke { points: vec![point] });
+            }
+            InkMsg::Extend(point) => {
+                if let Some(stroke) = &mut self.active_doc_mut().ink_stroke {
+                    stroke.points.push(point);
                 }
+            }
+            InkMsg::End => {
                 let doc = self.active_doc_mut();
-                doc.content
-                    .perform(text_editor::Action::Scroll { lines: int_lines });
-                let max_offset = doc.content.line_count().saturating_sub(1) as f32;
-                doc.scroll_offset =
-                    (doc.scroll_offset + int_lines as f32).clamp(0.0, max_offset);
-                return Task::none();
+                if let Some(stroke) = doc.ink_stroke.take() {
+                    doc.ink.push(stroke);
+                    self.save_ink();
+                }
+            }
+            InkMsg::UndoStroke => {
+                if self.active_doc_mut().ink.pop().is_some() {
+                    self.save_ink();
+                }
+            }
+            InkMsg::Clear => {
+                let doc = self.active_doc_mut();
+                if !doc.ink.is_empty() {
+                    doc.ink.clear();
+                    self.save_ink();
+                }
             }
         }
+        Task::none()
+    }
 
-        if let Event::Window(iced::window::Event::Resized(size)) = &event {
-            self.window_width = size.width;
-            self.window_height = size.height;
-            self.save_preferences();
+    // Written after every stroke; an untitled document keeps its annotations
+    // in memory until it is saved
+    fn save_ink(&mut self) {
+        let doc = self.active_doc();
+        let Some(path) = &doc.file_path else {
+            return;
+        };
+        if let Err(e) = ink::save(path, &doc.ink) {
+            self.notify(Severity::Error, format!("Impossible d'enregistrer les annotations : {e}"));
         }
+    }
 
-        if let Event::Window(iced::window::Event::FileDropped(path)) = event {
-            return self.open_dropped_file(path);
-        }
+    // --- Print preview ---
 
-        if let Event::Keyboard(keyboard::Event::KeyPressed {
-            key, modifiers, ..
-        }) = event
-        {
-            match (key.as_ref(), modifiers) {
-                (Key::Named(Named::Escape), _) => {
-                    if self.show_settings {
-                        self.show_settings = false;
-                    } else if self.active_menu.is_some() || self.show_context_menu {
-                        self.active_menu = None;
-                        self.show_context_menu = false;
-                    } else if self.show_find || self.show_goto {
-                        self.show_find = false;
-                        self.show_replace = false;
-                        self.show_goto = false;
-                    }
+    fn handle_print(&mut self, msg: PrintMsg) -> Task<Message> {
+        match msg {
+            PrintMsg::OpenPreview => {
+                let setup = self.print_setup();
+                self.paginate_preview(setup, 0);
+            }
+            PrintMsg::ClosePreview => {
+                self.print_preview = None;
+            }
+            PrintMsg::PreviousPage => {
+                if let Some(preview) = &mut self.print_preview {
+                    preview.page = preview.page.saturating_sub(1);
                 }
-                (Key::Named(Named::F3), Modifiers::SHIFT) => {
-                    return self.handle_search(SearchMsg::FindPrevious);
+            }
+            PrintMsg::NextPage => {
+                if let Some(preview) = &mut self.print_preview {
+                    let last = preview.pagination.pages.len().saturating_sub(1);
+                    preview.page = (preview.page + 1).min(last);
                 }
-                (Key::Named(Named::F3), _) => {
-                    return self.handle_search(SearchMsg::FindNext);
+            }
+            PrintMsg::SetMargin(mm) => {
+                if let Some(preview) = &self.print_preview {
+                    let setup = PageSetup { margin_mm: mm, ..preview.setup };
+                    self.page_setup.margin_mm = mm;
+                    self.paginate_preview(setup, preview.page);
                 }
-                (Key::Named(Named::F5), _) => {
-                    return self.handle_edit(EditMsg::InsertDateTime);
+            }
+            PrintMsg::ToggleHeaderFooter => {
+                if let Some(preview) = &self.print_preview {
+                    let header_footer = !preview.setup.header_footer;
+                    let setup = PageSetup { header_footer, ..preview.setup };
+                    self.page_setup.header_footer = header_footer;
+                    self.paginate_preview(setup, preview.page);
                 }
-                // Ctrl+Tab - next tab
-                (Key::Named(Named::Tab), Modifiers::CTRL) => {
-                    if !self.tabs.is_empty() {
-                        self.active_tab = (self.active_tab + 1) % self.tabs.len();
-                        self.find_cursor = 0;
-                    }
+            }
+            PrintMsg::ToggleFitToWidth => {
+                if let Some(preview) = &self.print_preview {
+                    let setup = PageSetup {
+                        fit_to_width: !preview.setup.fit_to_width,
+                        ..preview.setup
+                    };
+                    self.paginate_preview(setup, preview.page);
                 }
-                // Ctrl+Shift+Tab - previous tab
-                (Key::Named(Named::Tab), m) if m == (Modifiers::CTRL | Modifiers::SHIFT) => {
-                    if !self.tabs.is_empty() {
-                        self.active_tab = if self.active_tab == 0 {
-                            self.tabs.len() - 1
-                        } else {
-                            self.active_tab - 1
-                        };
-                        self.find_cursor = 0;
+            }
+            PrintMsg::PrintSelection => {
+                let Some(selection) = self.active_doc().content.selection() else {
+                    self.notify(Severity::Info, "Aucune sélection à imprimer".to_string());
+                    return Task::none();
+                };
+                let title = format!("{} (sélection)", self.print_title());
+                let setup = self.print_setup();
+                return self.print_documents(vec![(title, selection.into(), Vec::new())], setup);
+            }
+            PrintMsg::OpenQueue => {
+                self.print_queue = Some(PrintQueue {
+                    docs: vec![self.active_doc().id],
+                });
+            }
+            PrintMsg::ToggleQueued(id) => {
+                if let Some(queue) = &mut self.print_queue {
+                    match queue.docs.iter().position(|d| *d == id) {
+                        Some(i) => {
+                            queue.docs.remove(i);
+                        }
+                        None => queue.docs.push(id),
                     }
                 }
-                // Ctrl+Shift+S - Save As
-                (Key::Character("s"), m) if m == (Modifiers::CTRL | Modifiers::SHIFT) => {
-                    return self.handle_file(FileMsg::SaveAs);
-                }
-                // Ctrl+W - Close tab
-                (Key::Character("w"), Modifiers::CTRL) => {
-                    let idx = self.active_tab;
-                    return self.handle_file(FileMsg::CloseTab(idx));
-                }
-                (Key::Character("n"), Modifiers::CTRL) => {
-                    return self.handle_file(FileMsg::NewTab);
-                }
-                (Key::Character("s"), Modifiers::CTRL) => {
-                    return self.handle_file(FileMsg::Save);
-                }
-                (Key::Character("o"), Modifiers::CTRL) => {
-                    return self.handle_file(FileMsg::Open);
-                }
-                (Key::Character("z"), Modifiers::CTRL) => {
-                    return self.handle_edit(EditMsg::Undo);
-                }
-                (Key::Character("y"), Modifiers::CTRL) => {
-                    return self.handle_edit(EditMsg::Redo);
-                }
-                (Key::Character("f"), Modifiers::CTRL) => {
-                    return self.handle_search(SearchMsg::OpenFind);
-                }
-                (Key::Character("h"), Modifiers::CTRL) => {
-                    return self.handle_search(SearchMsg::OpenReplace);
-                }
-                (Key::Character("g"), Modifiers::CTRL) => {
-                    return self.handle_search(SearchMsg::OpenGoTo);
-                }
-                (Key::Character("="), Modifiers::CTRL) => {
-                    return self.handle_view(ViewMsg::ZoomIn);
-                }
-                (Key::Character("+"), m) if m.contains(Modifiers::CTRL) => {
-                    return self.handle_view(ViewMsg::ZoomIn);
-                }
-                (Key::Character("-"), Modifiers::CTRL) => {
-                    return self.handle_view(ViewMsg::ZoomOut);
-                }
-                (Key::Character("0"), Modifiers::CTRL) => {
-                    return self.handle_view(ViewMsg::ZoomReset);
-                }
-                (Key::Character("z"), Modifiers::ALT) => {
-                    return self.handle_view(ViewMsg::ToggleWordWrap);
+            }
+            PrintMsg::CloseQueue => {
+                self.print_queue = None;
+            }
+            PrintMsg::PrintQueued => {
+                let Some(queue) = self.print_queue.take() else {
+                    return Task::none();
+                };
+                // Printed in tab order, whatever the order they were ticked in
+                let documents: Vec<_> = self
+                    .tabs
+                    .iter()
+                    .filter(|doc| queue.docs.contains(&doc.id))
+                    .map(|doc| (print_title(doc), doc.text(), self.printed_ink(doc)))
+                    .collect();
+                if documents.is_empty() {
+                    return Task::none();
                 }
-                _ => {}
+                let setup = self.print_setup();
+                return self.print_documents(documents, setup);
             }
         }
         Task::none()
     }
 
-    // --- Preferences ---
+    // Page settings with the editor's font size, wrapping and tab width
+    fn print_setup(&self) -> PageSetup {
+        PageSetup {
+            // Editor sizes are in pixels, pages in points
+            font_size: self.font_size * 0.75,
+            wrap: self.word_wrap,
+            fit_to_width: false,
+            tab_width: self.tab_width,
+            ..self.page_setup
+        }
+    }
 
-    pub fn save_preferences(&self) {
-        UserPreferences {
-            font_size: self.font_size,
-            font_family: self.font_family.clone(),
-            dark_mode: self.dark_mode,
-            word_wrap: self.word_wrap,
-            window_width: self.window_width,
-            window_height: self.window_height,
-            restore_session: self.restore_session,
+    fn print_title(&self) -> String {
+        print_title(self.active_doc())
+    }
+
+    // Laid out like the preview when it is open, otherwise with the editor's
+    // font size
+    fn print_document(&mut self) -> Task<Message> {
+        let setup = match self.print_preview.take() {
+            Some(preview) => preview.setup,
+            None => self.print_setup(),
+        };
+        let doc = self.active_doc();
+        let document = (self.print_title(), doc.text(), self.printed_ink(doc));
+        self.print_documents(vec![document], setup)
+    }
+
+    // Annotations go on paper when they are shown
+    fn printed_ink(&self, doc: &Document) -> Vec<ink::Stroke> {
+        if self.show_ink { doc.ink.clone() } else { Vec::new() }
+    }
+
+    // One job for all the (title, text, annotations) documents, each from a
+    // new page under its own header; the spooler is reached off the UI thread
+    fn print_documents(&mut self, documents: Vec<PrintedDocument>, setup: PageSetup) -> Task<Message> {
+        let name = match documents.as_slice() {
+            [(title, _, _)] => title.clone(),
+            _ => format!("{} onglets", documents.len()),
+        };
+        self.notify(Severity::Info, format!("Impression de {name}..."));
+        let job = name.clone();
+        Task::future(jobs::spawn_blocking(move || {
+            let paginations: Vec<_> = documents
+                .iter()
+                .map(|(title, text, ink)| print::paginate_annotated(text, title, &setup, ink))
+                .collect();
+            print::print(&paginations, &setup, &job)
+        }))
+        .map(move |result| {
+            let result = result.unwrap_or_else(|| Err("impression interrompue".to_string()));
+            Message::File(FileMsg::Printed(name.clone(), result))
+        })
+    }
+
+    // Keeps the current page when possible after the layout changes
+    fn paginate_preview(&mut self, setup: PageSetup, page: usize) {
+        let title = self.print_title();
+        let doc = self.active_doc();
+        let pagination = print::paginate_annotated(&doc.text(), &title, &setup, &self.printed_ink(doc));
+        let page = page.min(pagination.pages.len().saturating_sub(1));
+        self.print_preview = Some(PrintPreview {
+            setup,
+            pagination,
+            page,
+        });
+    }
+
+    // --- Merge with the version on disk ---
+
+    fn handle_merge(&mut self, msg: MergeMsg) -> Task<Message> {
+        match msg {
+            MergeMsg::Open(idx) => {
+                let Some(doc) = self.tabs.get(idx) else {
+                    return Task::none();
+                };
+                let Some(path) = doc.file_path.clone() else {
+                    return Task::none();
+                };
+                let bytes = match std::fs::read(&path) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        self.notify(
+                            Severity::Error,
+                            format!("Impossible de lire le fichier : {e}"),
+                        );
+                        return Task::none();
+                    }
+                };
+                let disk_modified = std::fs::metadata(&path).ok().and_then(|m| m.modified().ok());
+                let disk = match Self::decode_file(&path, bytes) {
+                    Ok(loaded) => loaded.text,
+                    Err(e) => {
+                        self.notify(Severity::Error, e);
+                        return Task::none();
+                    }
+                };
+                let doc = &mut self.tabs[idx];
+                let merge = Merge::new(&doc.text(), &disk);
+                if merge.hunk_count() == 0 {
+                    doc.externally_modified = false;
+                    doc.last_file_modified = disk_modified;
+                    self.notify(Severity::Info, "Aucune différence avec la version sur disque");
+                    return Task::none();
+                }
+                self.merge = Some(MergeSession {
+                    doc: doc.id,
+                    name: file_display_name(&path),
+                    merge,
+                    disk_modified,
+                });
+            }
+            MergeMsg::Choose(hunk, choice) => {
+                if let Some(session) = &mut self.merge {
+                    session.merge.choose(hunk, choice);
+                }
+            }
+            MergeMsg::ChooseAll(choice) => {
+                if let Some(session) = &mut self.merge {
+                    session.merge.choose_all(choice);
+                }
+            }
+            MergeMsg::Apply => {
+                let Some(session) = self.merge.take() else {
+                    return Task::none();
+                };
+                let Some(index) = self.tabs.iter().position(|d| d.id == session.doc) else {
+                    return Task::none();
+                };
+                self.active_tab = index;
+                self.save_snapshot();
+                let doc = self.active_doc_mut();
+                doc.pending_view = Some(doc.view_state());
+                doc.set_text(&session.merge.merged());
+                // Taking every hunk from disk leaves nothing to save
+                doc.is_modified = session.merge.hunks().any(|h| h.choice != HunkChoice::Disk);
+                doc.externally_modified = false;
+                doc.last_file_modified = session.disk_modified;
+                doc.status_message = Some(format!("Fusion appliquée : {}", session.name));
+                doc.update_stats_cache();
+            }
+            MergeMsg::Cancel => {
+                self.merge = None;
+            }
         }
-        .save();
+        Task::none()
     }
 
-    fn save_session(&self) {
-        if !self.restore_session {
-            return;
+    fn job_percent(&self) -> Option<u32> {
+        self.jobs.overall_progress().map(|p| (p * 100.0) as u32)
+    }
+
+    // Ctrl+Tab in MRU mode: open the switcher or move its highlight
+    fn cycle_tab_switcher(&mut self, forward: bool) {
+        let (order, selected) = self
+            .tab_switcher
+            .take()
+            .unwrap_or_else(|| (self.mru_order(), 0));
+        let len = order.len();
+        let selected = if forward {
+            (selected + 1) % len
+        } else {
+            (selected + len - 1) % len
+        };
+        self.tab_switcher = Some((order, selected));
+    }
+
+    fn commit_tab_switcher(&mut self) {
+        if let Some((order, selected)) = self.tab_switcher.take() {
+            if let Some(&index) = order.get(selected).filter(|&&i| i < self.tabs.len()) {
+                self.active_tab = index;
+                self.find_cursor = 0;
+            }
         }
-        let tabs: Vec<SessionTab> = self
-            .tabs
-            .iter()
-            .map(|doc| SessionTab {
-                file_path: doc.file_path.clone(),
-                unsaved_content: if doc.file_path.is_none() || doc.is_modified {
-                    Some(doc.content.text())
+    }
+
+    fn reveal_active_tab(&mut self) {
+        self.tab_scroll = crate::app::visible_tab_start(
+            &self.tab_widths(),
+            self.tab_scroll,
+            self.active_tab,
+            self.tab_strip_width(),
+        );
+    }
+
+    // --- Editor action ---
+
+    fn handle_editor_action(&mut self, action: text_editor::Action) -> Task<Message> {
+        // Ctrl+wheel → zoom instead of scroll
+        if self.ctrl_pressed {
+            if let text_editor::Action::Scroll { lines } = &action {
+                return if *lines < 0 {
+                    self.handle_view(ViewMsg::ZoomIn)
                 } else {
-                    None
-                },
-                is_modified: doc.is_modified,
-            })
-            .collect();
-        SessionData {
-            tabs,
-            active_tab: self.active_tab,
+                    self.handle_view(ViewMsg::ZoomOut)
+                };
+            }
         }
-        .save();
+
+        let ctrl_click = self.ctrl_pressed && matches!(&action, text_editor::Action::Click(_));
+        let is_edit = matches!(&action, text_editor::Action::Edit(_));
+        // The tab is read-only while a background load / save runs or the file is locked elsewhere
+        if is_edit && self.active_doc().is_read_only() {
+            return Task::none();
+        }
+        let scroll_delta = if let text_editor::Action::Scroll { lines } = &action {
+            Some(*lines)
+        } else {
+            None
+        };
+        if is_edit {
+            self.save_snapshot_if_needed();
+            // Typing moves the text under the searched range
+            self.find_in_selection = None;
+            // Typing in the editor means the search is over; the query is
+            // kept for the next Ctrl+F
+            if self.auto_hide_find && self.show_find {
+                self.show_find = false;
+                self.show_replace = false;
+            }
+        }
+        // Clicking into the text leaves the bars above it, like clicking away
+        // from a menu
+        if self.click_closes_bars && matches!(&action, text_editor::Action::Click(_)) {
+            self.close_find_bars();
+        }
+        let terminator = match &action {
+            text_editor::Action::Edit(text_editor::Edit::Insert(c)) => abbreviations::is_terminator(*c),
+            text_editor::Action::Edit(text_editor::Edit::Enter) => true,
+            _ => false,
+        };
+        if terminator {
+            self.expand_abbreviation();
+        }
+        let low_power = self.low_power;
+        let doc = self.active_doc_mut();
+        doc.perform(action);
+        if is_edit {
+            doc.is_modified = true;
+            doc.status_message = None;
+            // In low power mode the stats catch up on a timer
+            if !low_power {
+                doc.update_stats_cache();
+            }
+            self.refresh_path_completion();
+        } else if scroll_delta.is_none() {
+            // Clicks and cursor moves close the completion popup
+            self.path_completion = None;
+        }
+        // The caret moved by hand: Escape has nowhere to take it back
+        if scroll_delta.is_none() {
+            self.find_origin = None;
+        }
+        if let Some(delta) = scroll_delta {
+            let doc = self.active_doc_mut();
+            let max_offset = doc.content.line_count().saturating_sub(1) as f32;
+            doc.scroll_offset = (doc.scroll_offset + delta as f32).clamp(0.0, max_offset);
+        }
+        if ctrl_click {
+            return self.follow_wiki_link();
+        }
+        Task::none()
+    }
+
+    // --- File operations ---
+
+    fn confirm_discard(
+        description: &'static str,
+        on_confirm: impl Fn(bool) -> Message + Send + 'static,
+    ) -> Task<Message> {
+        Self::confirm(description.to_string(), on_confirm)
+    }
+
+    fn confirm(description: String, on_confirm: impl Fn(bool) -> Message + Send + 'static) -> Task<Message> {
+        Task::perform(
+            async move {
+                matches!(
+                    rfd::AsyncMessageDialog::new()
+                        .set_title("Notepad")
+                        .set_description(description)
+                        .set_buttons(rfd::MessageButtons::OkCancel)
+                        .set_level(rfd::MessageLevel::Warning)
+                        .show()
+                        .await,
+                    rfd::MessageDialogResult::Ok
+                )
+            },
+            on_confirm,
+        )
+    }
+
+    fn handle_file(&mut self, msg: FileMsg) -> Task<Message> {
+        match msg {
+            FileMsg::NewTab => {
+                self.tabs.push(self.new_document());
+                self.active_tab = self.tabs.len() - 1;
+                Task::none()
+            }
+            FileMsg::DuplicateTab => {
+                let copy = self.active_doc().duplicate();
+                self.tabs.push(copy);
+                self.active_tab = self.tabs.len() - 1;
+                operation::focus(editor_id())
+            }
+            FileMsg::CloseTab(index) => {
+                if index >= self.tabs.len() {
+                    return Task::none();
+                }
+                if self.tabs[index].is_modified {
+                    Self::confirm_discard(
+                        "Le document a été modifié. Voulez-vous fermer sans enregistrer ?",
+                        move |confirmed| {
+                            Message::File(FileMsg::ConfirmCloseTabResult(confirmed, index))
+                        },
+                    )
+                } else {
+                    self.remove_tab(index);
+                    Task::none()
+                }
+            }
+            FileMsg::ScrollTabs(delta) => {
+                let max_start = self.tabs.len().saturating_sub(1) as i32;
+                self.tab_scroll = (self.tab_scroll as i32 + delta).clamp(0, max_start) as usize;
+                Task::none()
+            }
+            FileMsg::ConfirmCloseTabResult(confirmed, index) => {
+                if confirmed {
+                    self.remove_tab(index);
+                }
+                Task::none()
+            }
+            FileMsg::CloseUnmodifiedTabs => {
+                // Tabs still loading are left alone
+                for index in (0..self.tabs.len()).rev() {
+                    let doc = &self.tabs[index];
+                    if !doc.is_modified && doc.job.is_none() {
+                        self.remove_tab(index);
+                    }
+                }
+                Task::none()
+            }
+            FileMsg::SwitchTab(index) => {
+                if index < self.tabs.len() {
+                    self.active_tab = index;
+                    self.find_cursor = 0;
+                    if self.show_find {
+                        self.refresh_match_count(None);
+                    }
+                }
+                Task::none()
+            }
+            FileMsg::Save => {
+                if let Some(path) = self.active_doc().file_path.clone() {
+                    self.save_to_file(path)
+                } else {
+                    self.save_as()
+                }
+            }
+            FileMsg::SaveAs => self.save_as(),
+            FileMsg::Open => {
+                // Open in a new tab (like Windows Notepad)
+                self.open_file()
+            }
+            FileMsg::SaveFileSelected(path) => {
+                if let Some(path) = path {
+                    self.open_save_as(path);
+                }
+                Task::none()
+            }
+            FileMsg::SetSaveEncoding(choice) => {
+                let text = self.active_doc().text();
+                if let Some(dialog) = &mut self.save_as {
+                    dialog.choice = choice;
+                    dialog.unmappable = unmappable_chars(&text, choice.encoding(), UNMAPPABLE_SHOWN);
+                }
+                Task::none()
+            }
+            FileMsg::SetSaveLineEnding(eol) => {
+                if let Some(dialog) = &mut self.save_as {
+                    dialog.line_ending = eol;
+                }
+                Task::none()
+            }
+            FileMsg::ConfirmSaveAs => {
+                let Some(dialog) = self.save_as.take() else {
+                    return Task::none();
+                };
+                // Another line ending rewrites the lines first, as an undoable edit
+                if dialog.line_ending != self.active_doc().line_ending {
+                    if let Some(converted) = self.active_doc().text_with_line_endings(dialog.line_ending) {
+                        self.save_snapshot();
+                        self.active_doc_mut().set_text_keeping_cursor(&converted);
+                    }
+                }
+                let doc = self.active_doc_mut();
+                doc.encoding = dialog.choice.encoding();
+                doc.bom = dialog.choice.bom();
+                doc.line_ending = dialog.line_ending;
+                self.save_to_file(dialog.path)
+            }
+            FileMsg::CancelSaveAs => {
+                self.save_as = None;
+                Task::none()
+            }
+            FileMsg::OpenFileSelected(path) => {
+                if let Some(path) = path {
+                    return self.open_dropped_file(path);
+                }
+                Task::none()
+            }
+            FileMsg::CloseRequested(id) => {
+                if self.save_running() {
+                    self.notify(Severity::Warning, "Enregistrement en cours : fermeture possible une fois terminé");
+                    return Task::none();
+                }
+                self.save_session();
+                if let Some(current) = self.named_sessions.current.clone() {
+                    self.store_named_session(&current);
+                    self.named_sessions.save();
+                }
+                self.remember_positions(0..self.tabs.len());
+                let any_modified = self.tabs.iter().any(|doc| doc.is_modified);
+                if any_modified {
+                    Self::confirm_discard(
+                        "Des documents ont été modifiés. Voulez-vous quitter sans enregistrer ?",
+                        move |confirmed| {
+                            Message::File(FileMsg::ConfirmCloseResult(confirmed, id))
+                        },
+                    )
+                } else {
+                    self.release_locks();
+                    self.terminal = None;
+                    iced::window::close(id)
+                }
+            }
+            FileMsg::ConfirmCloseResult(confirmed, id) => {
+                if confirmed && self.save_running() {
+                    self.notify(Severity::Warning, "Enregistrement en cours : fermeture possible une fois terminé");
+                    Task::none()
+                } else if confirmed {
+                    self.save_session();
+                    self.release_locks();
+                    self.terminal = None;
+                    iced::window::close(id)
+                } else {
+                    Task::none()
+                }
+            }
+            FileMsg::AutoSave => self.auto_save(false),
+            FileMsg::AutoSaveNetwork => self.auto_save(true),
+            FileMsg::AutoSaveSession => {
+                self.autosave_session();
+                Task::none()
+            }
+            FileMsg::ToggleProperties => {
+                self.properties = match self.properties {
+                    Some(_) => None,
+                    None => Some(self.file_properties()),
+                };
+                Task::none()
+            }
+            FileMsg::Print => self.print_document(),
+            FileMsg::Printed(name, result) => {
+                match result {
+                    Ok(()) => self.notify(Severity::Success, format!("Envoyé à l'imprimante : {name}")),
+                    Err(e) => self.notify(Severity::Error, format!("Impossible d'imprimer {name} : {e}")),
+                }
+                Task::none()
+            }
+            FileMsg::ToggleRecentFiles => {
+                self.show_recent_files = !self.show_recent_files;
+                self.show_reopen_encodings = false;
+                Task::none()
+            }
+            FileMsg::ToggleReopenEncodings => {
+                self.show_reopen_encodings = !self.show_reopen_encodings;
+                self.show_recent_files = false;
+                Task::none()
+            }
+            FileMsg::ReopenWithEncoding(encoding) => self.reopen_with_encoding(encoding),
+            FileMsg::ConfirmReopenWithEncoding(confirmed, doc_id, encoding) => {
+                let Some(index) = self.tabs.iter().position(|d| d.id == doc_id).filter(|_| confirmed) else {
+                    return Task::none();
+                };
+                if let Some(path) = self.tabs[index].file_path.clone() {
+                    self.active_tab = index;
+                    self.decode_again(path, encoding);
+                }
+                Task::none()
+            }
+            FileMsg::OpenRecent(path) => {
+                // A file deleted since is dropped from the list; shares out of reach are kept
+                if !paths::is_network(&path) && !path.exists() {
+                    self.recent_files.remove(&path);
+                    self.recent_files.save();
+                    self.notify(
+                        Severity::Error,
+                        format!("Fichier introuvable : {}", path.display()),
+                    );
+                    return Task::none();
+                }
+                self.open_dropped_file(path)
+            }
+            FileMsg::ClearRecentFiles => {
+                self.recent_files.entries.clear();
+                self.recent_files.save();
+                Task::none()
+            }
+            FileMsg::CheckExternalChanges => {
+                for i in 0..self.tabs.len() {
+                    let doc = &self.tabs[i];
+                    // Our own write in progress would look like a change made elsewhere
+                    if doc.externally_modified || doc.job.is_some() || doc.save_in_flight.is_some() {
+                        continue;
+                    }
+                    let (path, last_known) = match (&doc.file_path, doc.last_file_modified) {
+                        (Some(p), Some(t)) => (p.clone(), t),
+                        _ => continue,
+                    };
+
+                    let current_modified = match std::fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                    {
+                        Ok(t) => t,
+                        Err(_) => {
+                            let name = path
+                                .file_name()
+                                .and_then(|n| n.to_str())
+                                .unwrap_or("fichier")
+                                .to_string();
+                            self.tabs[i].status_message =
+                                Some(format!("Fichier supprimé : {name}"));
+                            self.tabs[i].last_file_modified = None;
+                            continue;
+                        }
+                    };
+
+                    if current_modified > last_known {
+                        self.tabs[i].externally_modified = true;
+                    }
+                }
+                Task::none()
+            }
+            FileMsg::ChangedOnDisk(path) => {
+                // Other files of the folder come through too
+                if self.tabs.iter().any(|doc| doc.file_path.as_deref() == Some(path.as_path())) {
+                    return self.update(Message::File(FileMsg::CheckExternalChanges));
+                }
+                Task::none()
+            }
+            FileMsg::ReloadFile(idx) => {
+                if let Some(path) = self.tabs.get(idx).and_then(|d| d.file_path.clone()) {
+                    self.active_tab = idx;
+                    let doc = &mut self.tabs[idx];
+                    doc.externally_modified = false;
+                    doc.pending_view = Some(doc.view_state());
+                    return self.load_from_file(path);
+                }
+                Task::none()
+            }
+            FileMsg::StealLock(idx) => {
+                if let Some(doc) = self.tabs.get_mut(idx) {
+                    if let Some(doc_lock) = &mut doc.lock {
+                        match lock::steal(&doc_lock.path) {
+                            Ok(()) => doc_lock.state = LockState::Held,
+                            Err(e) => {
+                                self.notify(
+                                    Severity::Error,
+                                    format!("Impossible de prendre le verrou : {e}"),
+                                );
+                            }
+                        }
+                    }
+                }
+                Task::none()
+            }
+            FileMsg::IgnoreExternalChange(idx) => {
+                if let Some(doc) = self.tabs.get_mut(idx) {
+                    doc.externally_modified = false;
+                    if let Some(path) = &doc.file_path {
+                        doc.last_file_modified =
+                            std::fs::metadata(path).ok().and_then(|m| m.modified().ok());
+                    }
+                }
+                Task::none()
+            }
+            FileMsg::ConfirmLargeFile(confirmed, doc_id, path) => {
+                let Some(index) = self.tabs.iter().position(|d| d.id == doc_id).filter(|_| confirmed) else {
+                    return Task::none();
+                };
+                self.active_tab = index;
+                let file_size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                self.read_file(path, file_size)
+            }
+            FileMsg::Loaded(id, result) => {
+                if !self.jobs.finish(id) {
+                    return Task::none();
+                }
+                let Some(index) = self.tabs.iter().position(|d| d.job == Some(id)) else {
+                    return Task::none();
+                };
+                self.tabs[index].job = None;
+                match result {
+                    Ok(loaded) => {
+                        let path = self.tabs[index].file_path.clone().unwrap_or_default();
+                        self.apply_loaded_file(index, path, Arc::unwrap_or_clone(loaded));
+                    }
+                    Err(e) => {
+                        let doc = &mut self.tabs[index];
+                        let name = doc.file_path.take().as_deref().map(file_display_name).unwrap_or_default();
+                        doc.status_message = None;
+                        self.notify(Severity::Error, format!("Impossible d'ouvrir {name} : {e}"));
+                    }
+                }
+                Task::none()
+            }
+            FileMsg::Saved(id, result) => {
+                self.jobs.finish(id);
+                let Some(index) = self
+                    .tabs
+                    .iter()
+                    .position(|d| d.job == Some(id) || d.save_in_flight == Some(id))
+                else {
+                    return Task::none();
+                };
+                let doc = &mut self.tabs[index];
+                if doc.save_in_flight == Some(id) {
+                    doc.save_in_flight = None;
+                } else {
+                    doc.job = None;
+                }
+                // Ctrl+S pressed during the write: one more with the latest text
+                let queued = std::mem::take(&mut doc.save_queued) && result.is_ok();
+                doc.status_message = None;
+                let name = doc.file_path.as_deref().map(file_display_name).unwrap_or_default();
+                let path = doc.file_path.clone();
+                match result {
+                    Ok(()) => {
+                        doc.last_file_modified = path
+                            .as_ref()
+                            .and_then(|p| std::fs::metadata(p).ok())
+                            .and_then(|m| m.modified().ok());
+                        doc.finish_save();
+                        if let Some(path) = &path {
+                            self.record_note(path);
+                        }
+                        self.notify(Severity::Success, format!("Enregistré : {name}"));
+                    }
+                    Err(e) => {
+                        doc.pending_save = None;
+                        self.notify(
+                            Severity::Error,
+                            format!("Impossible d'enregistrer {name} : {e}"),
+                        );
+                    }
+                }
+                if queued && self.tabs[index].is_modified {
+                    return self.save_on_slow_share(index);
+                }
+                Task::none()
+            }
+        }
+    }
+
+    // --- Background jobs ---
+
+    fn handle_job(&mut self, msg: JobMsg) -> Task<Message> {
+        match msg {
+            JobMsg::Progress(id, progress) => self.jobs.set_progress(id, progress),
+            JobMsg::Cancel(id) => {
+                // Only loads are cancellable: the tab goes back to an empty document
+                if self.jobs.cancel(id) {
+                    if let Some(doc) = self.tabs.iter_mut().find(|d| d.job == Some(id)) {
+                        doc.job = None;
+                        doc.file_path = None;
+                        doc.status_message = Some("Chargement annulé".to_string());
+                    }
+                }
+            }
+            JobMsg::TogglePanel => self.show_jobs = !self.show_jobs,
+        }
+        Task::none()
+    }
+
+    // --- Clipboard ---
+
+    // Created on first use; a failure is reported and retried next time
+    fn clipboard(&mut self) -> Option<&mut arboard::Clipboard> {
+        if self.clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(e) => self.notify(Severity::Error, format!("Presse-papiers indisponible : {e}")),
+            }
+        }
+        self.clipboard.as_mut()
+    }
+
+    // --- Notifications ---
+
+    fn notify(&mut self, severity: Severity, message: impl Into<String>) {
+        let message = message.into();
+        match severity {
+            Severity::Error => tracing::error!("{message}"),
+            Severity::Warning => tracing::warn!("{message}"),
+            _ => tracing::info!("{message}"),
+        }
+        crash::log(format!("[{severity:?}] {message}"));
+        self.toasts.push(severity, message);
+    }
+
+    fn handle_toast(&mut self, msg: ToastMsg) -> Task<Message> {
+        match msg {
+            ToastMsg::Dismiss(id) => self.toasts.dismiss(id),
+            ToastMsg::Tick => self.toasts.expire(Instant::now()),
+        }
+        Task::none()
+    }
+
+    fn remove_tab(&mut self, index: usize) {
+        self.remember_positions(index..index + 1);
+        if let Some(doc_lock) = self.tabs[index].lock.take() {
+            self.release_lock(doc_lock);
+        }
+        if let Some(id) = self.tabs[index].job {
+            self.jobs.cancel(id);
+        }
+        if self.tabs.len() <= 1 {
+            // Last tab: replace with empty document
+            self.tabs[0] = self.new_document();
+            self.active_tab = 0;
+        } else {
+            self.tabs.remove(index);
+            if self.active_tab >= self.tabs.len() {
+                self.active_tab = self.tabs.len() - 1;
+            } else if self.active_tab > index {
+                self.active_tab -= 1;
+            }
+        }
+        // The other pane of a split falls back to the active document
+        let active = self.active_doc().id;
+        let tabs = &self.tabs;
+        if let Some(split) = self.split.as_mut().filter(|s| !tabs.iter().any(|d| d.id == s.other)) {
+            split.other = active;
+        }
+    }
+
+    fn open_dropped_file(&mut self, path: PathBuf) -> Task<Message> {
+        // A file already open is shown instead of being loaded in a second tab
+        if let Some(index) = self.tab_with_file(&path) {
+            self.active_tab = index;
+            return operation::focus(editor_id());
+        }
+        let doc = self.active_doc();
+        let reuse = !doc.is_modified
+            && doc.file_path.is_none()
+            && doc.text().trim().is_empty();
+        if !reuse {
+            self.tabs.push(self.new_document());
+            self.active_tab = self.tabs.len() - 1;
+        }
+        if self.remember_cursor_position {
+            if let Some(caret) = self.metadata.caret(&path) {
+                self.active_doc_mut().pending_view = Some(ViewState {
+                    line: caret.line,
+                    column: caret.column,
+                    scroll: caret.scroll,
+                });
+            }
+        }
+        self.load_from_file(path)
+    }
+
+    // Bookmarks of the active file, kept for the next time it is opened
+    fn remember_bookmarks(&mut self) {
+        let doc = self.active_doc();
+        let Some(path) = doc.file_path.clone() else {
+            return;
+        };
+        let lines = doc.bookmarks.lines().to_vec();
+        self.metadata.update(&path, |meta| meta.bookmarks = lines);
+        self.metadata.save();
+    }
+
+    // Stores where the cursor was in these tabs, for the next time the files are opened
+    fn remember_positions(&mut self, indices: std::ops::Range<usize>) {
+        if !self.remember_cursor_position {
+            return;
+        }
+        for doc in &self.tabs[indices] {
+            if let (Some(path), None) = (&doc.file_path, doc.job) {
+                let view = doc.view_state();
+                let caret = Caret {
+                    line: view.line,
+                    column: view.column,
+                    scroll: view.scroll,
+                };
+                self.metadata.update(path, |meta| meta.caret = Some(caret));
+            }
+        }
+        self.metadata.save();
+    }
+
+    // --- Edit operations ---
+
+    fn handle_edit(&mut self, msg: EditMsg) -> Task<Message> {
+        let leaves_text = matches!(
+            msg,
+            EditMsg::Copy
+                | EditMsg::SelectAll
+                | EditMsg::AppendSelection
+                | EditMsg::AppendSelectionTo(_)
+                | EditMsg::SelectionAppended(..)
+                | EditMsg::FilterCommandChanged(_)
+                | EditMsg::CloseFilter
+                | EditMsg::HardWrapWidthChanged(_)
+                | EditMsg::CloseHardWrap
+                | EditMsg::ToggleMacroRecording
+                | EditMsg::PlayMacroTimes
+                | EditMsg::MacroCountChanged(_)
+                | EditMsg::CloseMacroPrompt
+                | EditMsg::CharQueryChanged(_)
+                | EditMsg::ChooseCharBlock(_)
+                | EditMsg::CloseCharPicker
+                | EditMsg::ToggleDateFormats
+                | EditMsg::ChooseDateFormat(_)
+                | EditMsg::Filtered(..)
+                | EditMsg::CheckSyntax
+                | EditMsg::RefreshStats
+                | EditMsg::SyntaxChecked(..)
+                | EditMsg::GoToSyntaxError
+        );
+        if !leaves_text && self.active_doc().is_read_only() {
+            return Task::none();
+        }
+        match msg {
+            EditMsg::Copy => {
+                if let Some(selected) = self.active_doc().content.selection() {
+                    if let Some(Err(e)) = self.clipboard().map(|c| c.set_text(selected)) {
+                        self.notify(
+                            Severity::Error,
+                            format!("Impossible de copier dans le presse-papiers : {e}"),
+                        );
+                    }
+                }
+                Task::none()
+            }
+            EditMsg::Cut => {
+                if let Some(selected) = self.tabs[self.active_tab].content.selection() {
+                    match self.clipboard().map(|c| c.set_text(selected)) {
+                        Some(Err(e)) => {
+                            self.notify(
+                                Severity::Error,
+                                format!("Impossible de copier dans le presse-papiers : {e}"),
+                            );
+                        }
+                        Some(Ok(())) => {
+                            self.save_snapshot();
+                            let doc = self.active_doc_mut();
+                            doc.perform(text_editor::Action::Edit(
+                                text_editor::Edit::Backspace,
+                            ));
+                            doc.is_modified = true;
+                            doc.update_stats_cache();
+                        }
+                        None => {}
+                    }
+                }
+                Task::none()
+            }
+            EditMsg::Paste | EditMsg::PastePlain => {
+                let plain = matches!(msg, EditMsg::PastePlain);
+                if let Some(clipboard) = self.clipboard() {
+                    match clipboard.get_text() {
+                        Ok(clip_text) => {
+                            let clip_text = if plain { paste::plain(&clip_text) } else { clip_text };
+                            let clip_text = paste::normalize_line_endings(&clip_text, self.active_doc().line_ending);
+                            // Plain pastes leave URLs as they are
+                            let link = if plain { None } else { self.pasted_link(&clip_text) };
+                            self.save_snapshot();
+                            let doc = self.active_doc_mut();
+                            doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(
+                                link.unwrap_or_else(|| clip_text.clone()),
+                            ))));
+                            doc.is_modified = true;
+                            doc.update_stats_cache();
+                            if !plain {
+                                return self.fetch_link_title(&clip_text);
+                            }
+                        }
+                        Err(e) => {
+                            self.notify(
+                                Severity::Error,
+                                format!("Impossible de lire le presse-papiers : {e}"),
+                            );
+                        }
+                    }
+                }
+                Task::none()
+            }
+            EditMsg::SelectAll => {
+                let doc = self.active_doc_mut();
+                doc.content
+                    .perform(text_editor::Action::Move(text_editor::Motion::DocumentStart));
+                doc.content
+                    .perform(text_editor::Action::Select(text_editor::Motion::DocumentEnd));
+                Task::none()
+            }
+            EditMsg::Undo => {
+                self.undo();
+                Task::none()
+            }
+            EditMsg::Redo => {
+                self.redo();
+                Task::none()
+            }
+            EditMsg::InsertDateTime => {
+                let format = if self.date_format.trim().is_empty() {
+                    datetime::DEFAULT_FORMAT
+                } else {
+                    self.date_format.as_str()
+                };
+                let datetime_str = LocalTime::now().format(format);
+                self.save_snapshot();
+                let doc = self.active_doc_mut();
+                doc.perform(text_editor::Action::Edit(
+                    text_editor::Edit::Paste(Arc::new(datetime_str)),
+                ));
+                doc.is_modified = true;
+                doc.update_stats_cache();
+                Task::none()
+            }
+            EditMsg::AppendSelection => {
+                if self.active_doc().content.selection().is_none() {
+                    self.notify(Severity::Warning, "Sélectionnez le texte à ajouter au fichier");
+                    return Task::none();
+                }
+                let folder = self
+                    .append_target
+                    .as_ref()
+                    .and_then(|p| p.parent())
+                    .map(Path::to_path_buf);
+                Task::future(async move {
+                    let mut dialog = rfd::AsyncFileDialog::new().set_title("Ajouter la sélection à");
+                    if let Some(folder) = folder {
+                        dialog = dialog.set_directory(folder);
+                    }
+                    dialog.pick_file().await.map(|handle| handle.path().to_path_buf())
+                })
+                .and_then(|path| Task::done(Message::Edit(EditMsg::AppendSelectionTo(path))))
+            }
+            EditMsg::AppendSelectionTo(path) => {
+                let Some(selected) = self.active_doc().content.selection() else {
+                    return Task::none();
+                };
+                self.append_target = Some(path.clone());
+                let secs = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let stamp = format_local_datetime(secs);
+                let worker_path = path.clone();
+                Task::future(jobs::spawn_blocking(move || {
+                    append_entry(&worker_path, &selected, &stamp).map_err(|e| e.to_string())
+                }))
+                .map(move |result| {
+                    let result = result.unwrap_or_else(|| Err("tâche interrompue".to_string()));
+                    Message::Edit(EditMsg::SelectionAppended(path.clone(), result))
+                })
+            }
+            EditMsg::SelectionAppended(path, result) => {
+                let name = file_display_name(&path);
+                match result {
+                    Ok(()) => self.notify(Severity::Success, format!("Sélection ajoutée à {name}")),
+                    Err(e) => self.notify(Severity::Error, format!("Impossible d'ajouter à {name} : {e}")),
+                }
+                Task::none()
+            }
+            EditMsg::FilterSelection => {
+                if self.active_doc().content.selection().is_none() {
+                    self.notify(Severity::Warning, "Sélectionnez le texte à filtrer");
+                    return Task::none();
+                }
+                self.filter_prompt = Some(self.last_filter_command.clone());
+                operation::focus(filter_command_input_id())
+            }
+            EditMsg::FilterCommandChanged(command) => {
+                if let Some(prompt) = &mut self.filter_prompt {
+                    *prompt = command;
+                }
+                Task::none()
+            }
+            EditMsg::CloseFilter => {
+                self.filter_prompt = None;
+                operation::focus(editor_id())
+            }
+            EditMsg::RunFilter => {
+                let command = match &self.filter_prompt {
+                    Some(command) if !command.trim().is_empty() => command.trim().to_string(),
+                    _ => return Task::none(),
+                };
+                self.filter_prompt = None;
+                let Some(selected) = self.active_doc().content.selection() else {
+                    return Task::none();
+                };
+                self.last_filter_command = command.clone();
+                let doc_id = self.active_doc().id;
+                let label = format!("Filtre : {command}");
+                let (_, task) = self.jobs.spawn(label, true, move |id| {
+                    let input = selected.clone();
+                    let worker = jobs::run_blocking(move |report| {
+                        filter::run(&command, &input, &|| report(0.0))
+                    });
+                    Task::run(worker, move |event| match event {
+                        JobEvent::Progress(p) => Message::Job(JobMsg::Progress(id, p)),
+                        JobEvent::Done(result) => {
+                            Message::Edit(EditMsg::Filtered(id, doc_id, selected.clone(), result))
+                        }
+                    })
+                });
+                Task::batch([task, operation::focus(editor_id())])
+            }
+            EditMsg::Filtered(id, doc_id, sent, result) => {
+                // Cancelled from the jobs panel
+                if !self.jobs.finish(id) {
+                    return Task::none();
+                }
+                let output = match result {
+                    Ok(output) => output,
+                    Err(e) => {
+                        self.notify(Severity::Error, format!("Échec du filtre : {e}"));
+                        return Task::none();
+                    }
+                };
+                let Some(index) = self.tabs.iter().position(|d| d.id == doc_id) else {
+                    return Task::none();
+                };
+                let doc = &self.tabs[index];
+                if doc.is_read_only() || doc.content.selection().as_deref() != Some(sent.as_str()) {
+                    self.notify(
+                        Severity::Warning,
+                        "La sélection a changé pendant le filtre : le résultat n'a pas été appliqué",
+                    );
+                    return Task::none();
+                }
+                self.active_tab = index;
+                self.save_snapshot();
+                let doc = self.active_doc_mut();
+                doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(output))));
+                doc.is_modified = true;
+                doc.update_stats_cache();
+                Task::none()
+            }
+            EditMsg::ShowScripts => {
+                self.script_picker = Some(ScriptPicker { scripts: None });
+                let folder = scripts::folder();
+                Task::future(jobs::spawn_blocking(move || scripts::list(&folder)))
+                    .map(|scripts| Message::Edit(EditMsg::ScriptsListed(scripts.unwrap_or_default())))
+            }
+            EditMsg::ScriptsListed(scripts) => {
+                if let Some(picker) = &mut self.script_picker {
+                    picker.scripts = Some(scripts);
+                }
+                Task::none()
+            }
+            EditMsg::CloseScripts => {
+                self.script_picker = None;
+                operation::focus(editor_id())
+            }
+            EditMsg::ShowCharPicker => {
+                self.char_picker = Some(CharPicker { query: String::new(), block: 0 });
+                operation::focus(char_search_input_id())
+            }
+            EditMsg::CharQueryChanged(query) => {
+                if let Some(picker) = &mut self.char_picker {
+                    let found = charmap::search(&query);
+                    if !found.contains(&picker.block) {
+                        picker.block = found.first().copied().unwrap_or(picker.block);
+                    }
+                    picker.query = query;
+                }
+                Task::none()
+            }
+            EditMsg::ChooseCharBlock(block) => {
+                if let Some(picker) = &mut self.char_picker {
+                    picker.block = block;
+                }
+                Task::none()
+            }
+            EditMsg::InsertChar(c) => {
+                self.save_snapshot();
+                let doc = self.active_doc_mut();
+                doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(c.to_string()))));
+                doc.is_modified = true;
+                doc.update_stats_cache();
+                self.recent_characters = charmap::remember(&self.recent_characters, c);
+                self.save_preferences();
+                self.char_picker = None;
+                operation::focus(editor_id())
+            }
+            EditMsg::CloseCharPicker => {
+                self.char_picker = None;
+                operation::focus(editor_id())
+            }
+            EditMsg::ToggleDateFormats => {
+                self.show_date_formats = !self.show_date_formats;
+                Task::none()
+            }
+            EditMsg::LinkTitleFetched(doc_id, start, url, title) => {
+                let title = match title {
+                    Ok(title) => title,
+                    Err(e) => {
+                        self.notify(Severity::Warning, format!("Titre de la page introuvable : {e}"));
+                        return Task::none();
+                    }
+                };
+                // Only while the URL is still where it was pasted, in the current tab
+                let doc = self.active_doc();
+                let end = start + url.len();
+                let (line, column) = doc.mirror.byte_to_position(start);
+                if doc.id != doc_id || doc.mirror.line(line).get(column..column + url.len()) != Some(url.as_str()) {
+                    return Task::none();
+                }
+                let position = doc.content.cursor().position;
+                let cursor = doc.mirror.position_to_byte(position.line, position.column);
+                let link = links::markdown_link(&title, &url);
+                let cursor = if cursor >= end { cursor + link.len() - url.len() } else { cursor };
+                self.save_snapshot();
+                let doc = self.active_doc_mut();
+                doc.select_bytes(start, end);
+                doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(link))));
+                doc.select_bytes(cursor, cursor);
+                doc.is_modified = true;
+                doc.update_stats_cache();
+                Task::none()
+            }
+            EditMsg::ChooseDateFormat(format) => {
+                self.show_date_formats = false;
+                self.date_format = format.to_string();
+                self.save_preferences();
+                Task::none()
+            }
+            EditMsg::ShowQrCode => {
+                let doc = self.active_doc();
+                let position = doc.content.cursor().position;
+                let text = doc.content.selection().or_else(|| {
+                    let line = doc.content.line(position.line)?;
+                    qr::url_at(&line.text, position.column).map(str::to_string)
+                });
+                let Some(text) = text else {
+                    self.notify(Severity::Info, "Sélectionnez le texte à encoder ou placez le curseur sur un lien");
+                    return Task::none();
+                };
+                match qr::encode(&text) {
+                    Ok(code) => self.qr_popup = Some(QrPopup { text, code }),
+                    Err(e) => self.notify(Severity::Warning, format!("Impossible de créer le code QR : {e}")),
+                }
+                Task::none()
+            }
+            EditMsg::CloseQrCode => {
+                self.qr_popup = None;
+                operation::focus(editor_id())
+            }
+            EditMsg::CopyQrCode => {
+                let Some((pixels, rgba)) = self.qr_popup.as_ref().map(|p| p.code.rgba(qr::IMAGE_SCALE)) else {
+                    return Task::none();
+                };
+                let image = arboard::ImageData {
+                    width: pixels,
+                    height: pixels,
+                    bytes: rgba.into(),
+                };
+                if let Some(clipboard) = self.clipboard() {
+                    match clipboard.set_image(image) {
+                        Ok(()) => self.notify(Severity::Success, "Code QR copié"),
+                        Err(e) => self.notify(Severity::Error, format!("Impossible de copier le code QR : {e}")),
+                    }
+                }
+                Task::none()
+            }
+            EditMsg::SaveQrCode => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Enregistrer le code QR")
+                        .set_file_name("code-qr.png")
+                        .add_filter("Image PNG", &["png"])
+                        .save_file()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                |path| Message::Edit(EditMsg::QrCodeFileSelected(path)),
+            ),
+            EditMsg::QrCodeFileSelected(path) => {
+                let (Some(path), Some(popup)) = (path, &self.qr_popup) else {
+                    return Task::none();
+                };
+                let name = file_display_name(&path);
+                let written = popup
+                    .code
+                    .png(qr::IMAGE_SCALE)
+                    .and_then(|png| std::fs::write(&path, png).map_err(|e| e.to_string()));
+                match written {
+                    Ok(()) => self.notify(Severity::Success, format!("Code QR enregistré : {name}")),
+                    Err(e) => self.notify(Severity::Error, format!("Impossible d'enregistrer {name} : {e}")),
+                }
+                Task::none()
+            }
+            EditMsg::RunScript(path) => {
+                self.script_picker = None;
+                let doc = self.active_doc();
+                if doc.is_read_only() {
+                    self.notify(Severity::Warning, "Le document est en lecture seule");
+                    return Task::none();
+                }
+                let (doc_id, version) = (doc.id, doc.mirror.version());
+                let selected = doc.content.selection();
+                let input = selected.clone().unwrap_or_else(|| doc.text().to_string());
+                let label = format!("Script : {}", scripts::name(&path));
+                let (_, task) = self.jobs.spawn(label, true, move |id| {
+                    let worker = jobs::run_blocking(move |report| {
+                        scripts::run(&path, &input, &|| report(0.0))
+                    });
+                    Task::run(worker, move |event| match event {
+                        JobEvent::Progress(p) => Message::Job(JobMsg::Progress(id, p)),
+                        JobEvent::Done(result) => Message::Edit(EditMsg::ScriptRan(
+                            id,
+                            doc_id,
+                            version,
+                            selected.clone(),
+                            result,
+                        )),
+                    })
+                });
+                Task::batch([task, operation::focus(editor_id())])
+            }
+            EditMsg::ScriptRan(id, doc_id, version, selected, result) => {
+                // Cancelled from the jobs panel
+                if !self.jobs.finish(id) {
+                    return Task::none();
+                }
+                let output = match result {
+                    Ok(output) => output,
+                    Err(e) => {
+                        self.notify(Severity::Error, format!("Échec du script : {e}"));
+                        return Task::none();
+                    }
+                };
+                let Some(index) = self.tabs.iter().position(|d| d.id == doc_id) else {
+                    return Task::none();
+                };
+                let doc = &self.tabs[index];
+                if doc.is_read_only() || doc.mirror.version() != version || doc.content.selection() != selected {
+                    self.notify(
+                        Severity::Warning,
+                        "Le texte a changé pendant le script : le résultat n'a pas été appliqué",
+                    );
+                    return Task::none();
+                }
+                self.active_tab = index;
+                self.save_snapshot();
+                let doc = self.active_doc_mut();
+                if selected.is_none() {
+                    doc.perform(text_editor::Action::SelectAll);
+                }
+                doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(output))));
+                doc.is_modified = true;
+                doc.update_stats_cache();
+                Task::none()
+            }
+            EditMsg::RefreshStats => {
+                self.refresh_stale_stats();
+                Task::none()
+            }
+            EditMsg::CheckSyntax => {
+                let doc = self.active_doc_mut();
+                let typing = doc
+                    .last_edit_time
+                    .is_some_and(|t| t.elapsed().as_millis() < u128::from(SYNTAX_CHECK_IDLE_MS));
+                if !doc.needs_syntax_check() || typing {
+                    return Task::none();
+                }
+                let (id, version, language) = (doc.id, doc.mirror.version(), doc.language());
+                doc.syntax_checked = version;
+                let text = doc.text();
+                Task::future(jobs::spawn_blocking(move || syntax::check(language, &text))).map(
+                    move |error| Message::Edit(EditMsg::SyntaxChecked(id, version, error.flatten())),
+                )
+            }
+            EditMsg::SyntaxChecked(id, version, error) => {
+                // A result for an older text is dropped, the next check replaces it
+                if let Some(doc) = self.tabs.iter_mut().find(|d| d.id == id) {
+                    if doc.mirror.version() == version {
+                        doc.syntax_error = error;
+                    }
+                }
+                Task::none()
+            }
+            EditMsg::Define => {
+                let doc = self.active_doc();
+                let word = match doc.content.selection() {
+                    Some(selected) => Some(selected.trim().to_string()).filter(|w| !w.is_empty()),
+                    None => {
+                        let position = doc.content.cursor().position;
+                        doc.content
+                            .line(position.line)
+                            .and_then(|line| dictionary::word_at(&line.text, position.column))
+                    }
+                };
+                match word {
+                    Some(word) => self.handle_edit(EditMsg::DefineWord(word)),
+                    None => {
+                        self.notify(Severity::Warning, "Placez le curseur sur un mot ou sélectionnez-le");
+                        Task::none()
+                    }
+                }
+            }
+            EditMsg::DefineWord(word) => {
+                self.definition = Some(DefinitionPopup {
+                    word: word.clone(),
+                    result: None,
+                });
+                let folder = dictionary::folder();
+                Task::future(jobs::spawn_blocking(move || {
+                    let providers = dictionary::providers(&folder);
+                    let result = dictionary::lookup(&providers, &word);
+                    (word, result)
+                }))
+                .map(|done| {
+                    let (word, result) =
+                        done.unwrap_or_else(|| (String::new(), Err("recherche interrompue".to_string())));
+                    Message::Edit(EditMsg::Defined(word, result))
+                })
+            }
+            EditMsg::Defined(word, result) => {
+                // A later lookup replaced this one, or the popup was closed
+                if let Some(popup) = self.definition.as_mut().filter(|p| p.word == word) {
+                    popup.result = Some(result);
+                }
+                Task::none()
+            }
+            EditMsg::CloseDefinition => {
+                self.definition = None;
+                operation::focus(editor_id())
+            }
+            EditMsg::ReplaceMisspelled(replacement) => {
+                let Some(found) = self.spell_suggestions.take() else {
+                    return Task::none();
+                };
+                if self.active_doc().is_read_only() {
+                    return Task::none();
+                }
+                self.save_snapshot();
+                let doc = self.active_doc_mut();
+                doc.content.move_to(text_editor::Cursor {
+                    position: text_editor::Position { line: found.line, column: found.range.end },
+                    selection: Some(text_editor::Position { line: found.line, column: found.range.start }),
+                });
+                doc.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(replacement))));
+                doc.is_modified = true;
+                doc.update_stats_cache();
+                operation::focus(editor_id())
+            }
+            EditMsg::GoToSyntaxError => {
+                let Some(error) = self.active_doc().current_syntax_error() else {
+                    return Task::none();
+                };
+                let (line, column) = (error.line, error.column);
+                self.record_jump();
+                self.navigate_to(line, column);
+                operation::focus(editor_id())
+            }
+            EditMsg::InsertTab => {
+                // Inside a Markdown table, Tab aligns it and moves to the next cell
+                if let Some((range, mut table, row, column)) = self.current_table() {
+                    let (mut row, mut column) = (row, column + 1);
+                    if column >= table.column_count() {
+                        column = 0;
+                        row += 1;
+                    }
+                    if row >= table.rows.len() {
+                        table.insert_row(row);
+                    }
+                    self.replace_table(range, &table, row, column);
+                    return Task::none();
+                }
+                if self.expand_snippet() {
+                    return Task::none();
+                }
+                let unit = self.active_doc().indent_unit();
+                self.handle_editor_action(text_editor::Action::Edit(text_editor::Edit::Paste(
+                    Arc::new(unit),
+                )))
+            }
+            EditMsg::TrimTrailingWhitespace => {
+                if self.active_doc().is_read_only() {
+                    return Task::none();
+                }
+                let Some(trimmed) = self.active_doc().trimmed_text() else {
+                    self.notify(Severity::Info, "Aucun espace en fin de ligne");
+                    return Task::none();
+                };
+                self.save_snapshot();
+                let doc = self.active_doc_mut();
+                doc.set_text_keeping_cursor(&trimmed);
+                doc.is_modified = true;
+                doc.update_stats_cache();
+                Task::none()
+            }
+            // Whole document, one undo step; the document is then indented the new way
+            EditMsg::TabsToSpaces | EditMsg::SpacesToTabs => {
+                let to_spaces = matches!(msg, EditMsg::TabsToSpaces);
+                let doc = self.active_doc();
+                let width = doc.indent_size;
+                let line = doc.content.cursor().position.line;
+                let lines: Vec<String> = doc.mirror.lines().collect();
+                let converted: Vec<String> = lines
+                    .iter()
+                    .map(|l| if to_spaces { tabs::expand(l, width) } else { tabs::unexpand_indent(l, width) })
+                    .collect();
+                let style = if to_spaces { IndentStyle::Space } else { IndentStyle::Tab };
+                if converted != lines {
+                    self.replace_lines(0..lines.len(), &converted);
+                    self.active_doc_mut().move_cursor(line, 0);
+                } else if to_spaces {
+                    self.notify(Severity::Info, "Aucune tabulation à convertir");
+                } else {
+                    self.notify(Severity::Info, "Aucune indentation en espaces à convertir");
+                }
+                self.active_doc_mut().indent_style = style;
+                Task::none()
+            }
+            EditMsg::ToggleComment => {
+                let language = self.active_doc().language();
+                let Some(tokens) = comments::tokens(language) else {
+                    self.notify(Severity::Info, format!("Pas de commentaires en {}", language.label()));
+                    return Task::none();
+                };
+                let range = self.selected_lines();
+                let doc = self.active_doc();
+                let cursor = doc.content.cursor();
+                let lines: Vec<String> = range.clone().map(|i| doc.mirror.line(i)).collect();
+                let toggled = comments::toggle(&lines.iter().map(String::as_str).collect::<Vec<_>>(), tokens);
+                if toggled == lines {
+                    return Task::none();
+                }
+                self.replace_lines(range.clone(), &toggled);
+                // The lines stay selected, or the caret keeps its place in the text
+                let doc = self.active_doc_mut();
+                let position = match cursor.selection {
+                    Some(_) => text_editor::Position { line: range.end - 1, column: toggled[toggled.len() - 1].len() },
+                    None => {
+                        let (old, new) = (&lines[0], &toggled[0]);
+                        let mut column = (cursor.position.column + new.len()).saturating_sub(old.len()).min(new.len());
+                        while !new.is_char_boundary(column) {
+                            column -= 1;
+                        }
+                        text_editor::Position { line: range.start, column }
+                    }
+                };
+                doc.content.move_to(text_editor::Cursor {
+                    position,
+                    selection: cursor.selection.map(|_| text_editor::Position { line: range.start, column: 0 }),
+                });
+                Task::none()
+            }
+            EditMsg::JoinLines => {
+                let mut range = self.selected_lines();
+                let count = self.active_doc().content.line_count();
+                if range.len() == 1 {
+                    if range.end == count {
+                        return Task::none();
+                    }
+                    range.end += 1;
+                }
+                let doc = self.active_doc();
+                let lines: Vec<String> = range.clone().map(|i| doc.mirror.line(i)).collect();
+                let joined = reflow::join(&lines.iter().map(String::as_str).collect::<Vec<_>>());
+                self.replace_lines(range, &[joined]);
+                Task::none()
+            }
+            EditMsg::HardWrap => {
+                self.wrap_prompt = Some(self.last_wrap_width.to_string());
+                operation::focus(wrap_width_input_id())
+            }
+            EditMsg::HardWrapWidthChanged(width) => {
+                if let Some(prompt) = &mut self.wrap_prompt {
+                    *prompt = width.chars().filter(char::is_ascii_digit).collect();
+                }
+                Task::none()
+            }
+            EditMsg::CloseHardWrap => {
+                self.wrap_prompt = None;
+                operation::focus(editor_id())
+            }
+            EditMsg::RunHardWrap => {
+                let width = match self.wrap_prompt.as_deref().map(str::parse::<usize>) {
+                    Some(Ok(width)) if width > 0 => width,
+                    _ => return Task::none(),
+                };
+                self.wrap_prompt = None;
+                self.last_wrap_width = width;
+                let range = self.selected_lines();
+                let doc = self.active_doc();
+                let lines: Vec<String> = range.clone().map(|i| doc.mirror.line(i)).collect();
+                let wrapped = reflow::hard_wrap(&lines.iter().map(String::as_str).collect::<Vec<_>>(), width);
+                if wrapped != lines {
+                    self.replace_lines(range, &wrapped);
+                }
+                operation::focus(editor_id())
+            }
+            EditMsg::ToggleMacroRecording => {
+                match self.macro_recording.take() {
+                    None => {
+                        self.macro_recording = Some(Vec::new());
+                        self.notify(Severity::Info, "Enregistrement de la macro...");
+                    }
+                    Some(steps) if steps.is_empty() => {
+                        self.notify(Severity::Info, "Macro vide, la précédente est conservée");
+                    }
+                    Some(steps) => {
+                        self.notify(Severity::Info, format!("Macro enregistrée ({} actions)", steps.len()));
+                        self.last_macro = steps;
+                    }
+                }
+                Task::none()
+            }
+            EditMsg::PlayMacro => self.play_macro(1),
+            EditMsg::PlayMacroTimes => {
+                if self.last_macro.is_empty() {
+                    self.notify(Severity::Info, "Aucune macro enregistrée");
+                    return Task::none();
+                }
+                self.macro_prompt = Some(String::from("1"));
+                operation::focus(macro_count_input_id())
+            }
+            EditMsg::MacroCountChanged(count) => {
+                if let Some(prompt) = &mut self.macro_prompt {
+                    *prompt = count.chars().filter(char::is_ascii_digit).take(4).collect();
+                }
+                Task::none()
+            }
+            EditMsg::CloseMacroPrompt => {
+                self.macro_prompt = None;
+                operation::focus(editor_id())
+            }
+            EditMsg::RunMacroTimes => {
+                let times = match self.macro_prompt.as_deref().map(str::parse::<usize>) {
+                    Some(Ok(times)) if times > 0 => times.min(macros::MAX_REPEAT),
+                    _ => return Task::none(),
+                };
+                self.macro_prompt = None;
+                Task::batch([self.play_macro(times), operation::focus(editor_id())])
+            }
+            EditMsg::MoveParagraphUp | EditMsg::MoveParagraphDown => {
+                let up = matches!(msg, EditMsg::MoveParagraphUp);
+                let doc = self.active_doc();
+                if doc.is_read_only() {
+                    return Task::none();
+                }
+                let line = doc.content.cursor().position.line;
+                let count = doc.content.line_count();
+                let blank = |i: usize| doc.mirror.line(i).trim().is_empty();
+                let pair = if up {
+                    paragraphs::with_previous(count, blank, line)
+                } else {
+                    paragraphs::with_next(count, blank, line)
+                };
+                if let Some((upper, lower)) = pair {
+                    self.swap_paragraphs(upper, lower, !up);
+                }
+                Task::none()
+            }
+        }
+    }
+
+    // --- Search operations ---
+
+    // Builds the search index of a large active document off the UI thread;
+    // searches scan the text instead in low power mode
+    fn index_task(&mut self) -> Option<Task<Message>> {
+        if self.low_power {
+            return None;
+        }
+        let doc = self.active_doc_mut();
+        let id = doc.id;
+        let (version, rope) = doc.mirror.index_job(SEARCH_INDEX_MIN_BYTES)?;
+        let build = jobs::spawn_blocking(move || SearchIndex::build(&rope));
+        Some(Task::future(build).and_then(move |index| {
+            Task::done(Message::Search(SearchMsg::Indexed(id, version, Arc::new(index))))
+        }))
+    }
+
+    fn handle_search(&mut self, msg: SearchMsg) -> Task<Message> {
+        match msg {
+            SearchMsg::Indexed(id, version, index) => {
+                if let Some(doc) = self.tabs.iter_mut().find(|d| d.id == id) {
+                    let index = Arc::try_unwrap(index).unwrap_or_else(|shared| (*shared).clone());
+                    doc.mirror.set_index(version, index);
+                }
+                Task::none()
+            }
+            SearchMsg::OpenFind => {
+                self.show_find = true;
+                self.show_replace = false;
+                self.show_goto = false;
+                self.refresh_match_count(None);
+                operation::focus(find_input_id())
+            }
+            SearchMsg::OpenReplace => {
+                self.show_find = true;
+                self.show_replace = true;
+                self.show_goto = false;
+                self.refresh_match_count(None);
+                operation::focus(find_input_id())
+            }
+            SearchMsg::CloseFind => {
+                self.show_find = false;
+                self.show_replace = false;
+                self.find_in_selection = None;
+                self.find_origin = None;
+                Task::none()
+            }
+            SearchMsg::FindQueryChanged(query) => {
+                self.find_query = query;
+                self.search_as_you_type();
+                Task::none()
+            }
+            SearchMsg::ReplaceQueryChanged(query) => {
+                self.replace_query = query;
+                Task::none()
+            }
+            SearchMsg::FindNext => {
+                self.find_origin = None;
+                self.find_next();
+                Task::none()
+            }
+            SearchMsg::FindPrevious => {
+                self.find_origin = None;
+                self.find_previous();
+                Task::none()
+            }
+            SearchMsg::ReplaceOne => {
+                self.replace_one();
+                Task::none()
+            }
+            SearchMsg::ReplaceAll => {
+                self.replace_all();
+                Task::none()
+            }
+            SearchMsg::OpenGoTo => {
+                self.show_goto = true;
+                self.show_find = false;
+                self.show_replace = false;
+                self.goto_input.clear();
+                operation::focus(goto_input_id())
+            }
+            SearchMsg::CloseGoTo => {
+                self.show_goto = false;
+                Task::none()
+            }
+            SearchMsg::GoToInputChanged(value) => {
+                self.goto_input = value;
+                Task::none()
+            }
+            SearchMsg::GoToLineSubmit => {
+                let line_count = self.active_doc().content.line_count();
+                match self.goto_input.parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= line_count => {
+                        self.record_jump();
+                        self.navigate_to(n - 1, 0);
+                        self.show_goto = false;
+                        self.active_doc_mut().status_message = None;
+                        return Task::none();
+                    }
+                    Ok(_) => {
+                        self.active_doc_mut().status_message = Some(format!(
+                            "Numéro de ligne invalide (1–{})",
+                            line_count
+                        ));
+                    }
+                    Err(_) => {
+                        self.active_doc_mut().status_message =
+                            Some("Entrez un numéro de ligne valide".to_string());
+                    }
+                }
+                Task::none()
+            }
+            SearchMsg::ToggleCaseSensitive => {
+                self.case_sensitive = !self.case_sensitive;
+                self.find_cursor = 0;
+                self.refresh_match_count(None);
+                Task::none()
+            }
+            SearchMsg::ToggleRegex => {
+                self.use_regex = !self.use_regex;
+                self.find_cursor = 0;
+                self.refresh_match_count(None);
+                Task::none()
+            }
+            SearchMsg::ToggleExtended => {
+                self.extended_search = !self.extended_search;
+                self.find_cursor = 0;
+                self.refresh_match_count(None);
+                Task::none()
+            }
+            SearchMsg::ToggleInSelection => {
+                self.find_in_selection = match self.find_in_selection {
+                    Some(_) => None,
+                    None => {
+                        let range = self.selection_bytes();
+                        if range.is_none() {
+                            self.notify(Severity::Info, "Sélectionnez d'abord le texte où chercher".to_string());
+                        }
+                        range
+                    }
+                };
+                self.find_cursor = self.find_in_selection.as_ref().map_or(0, |range| range.start);
+                self.refresh_match_count(None);
+                Task::none()
+            }
+            SearchMsg::NavigateBack => {
+                self.navigate_history(false);
+                Task::none()
+            }
+            SearchMsg::NavigateForward => {
+                self.navigate_history(true);
+                Task::none()
+            }
+            SearchMsg::LastEdit | SearchMsg::NextEdit | SearchMsg::PreviousEdit => {
+                let doc = self.active_doc_mut();
+                let line = doc.content.cursor().position.line;
+                let target = match msg {
+                    SearchMsg::LastEdit => doc.edit_spots.last(),
+                    SearchMsg::NextEdit => doc.edit_spots.next_after(line),
+                    _ => doc.edit_spots.previous_before(line),
+                };
+                match target {
+                    Some((line, column)) => {
+                        self.record_jump();
+                        self.navigate_to(line, column);
+                    }
+                    None => self.notify(Severity::Info, "Aucune autre modification"),
+                }
+                Task::none()
+            }
+            SearchMsg::ToggleBookmark => {
+                let doc = self.active_doc_mut();
+                let line = doc.content.cursor().position.line;
+                doc.bookmarks.toggle(line);
+                self.remember_bookmarks();
+                Task::none()
+            }
+            SearchMsg::NextBookmark | SearchMsg::PreviousBookmark => {
+                let doc = self.active_doc();
+                let line = doc.content.cursor().position.line;
+                let target = if matches!(msg, SearchMsg::NextBookmark) {
+                    doc.bookmarks.next_after(line)
+                } else {
+                    doc.bookmarks.previous_before(line)
+                };
+                match target {
+                    Some(target) => {
+                        self.record_jump();
+                        self.navigate_to(target, 0);
+                    }
+                    None => self.notify(Severity::Info, "Aucun signet dans ce document"),
+                }
+                Task::none()
+            }
+            SearchMsg::GoToBookmark(line) => {
+                self.show_bookmarks_menu = false;
+                self.record_jump();
+                self.navigate_to(line, 0);
+                operation::focus(editor_id())
+            }
+            SearchMsg::ClearBookmarks => {
+                self.show_bookmarks_menu = false;
+                self.active_doc_mut().bookmarks.clear();
+                self.remember_bookmarks();
+                Task::none()
+            }
+            SearchMsg::ToggleBookmarksMenu => {
+                self.show_bookmarks_menu = !self.show_bookmarks_menu;
+                Task::none()
+            }
+            SearchMsg::NextHeading | SearchMsg::PreviousHeading => {
+                let doc = self.active_doc();
+                let line = doc.content.cursor().position.line;
+                let target = if matches!(msg, SearchMsg::NextHeading) {
+                    markdown::next_heading(&doc.cached_headings, line)
+                } else {
+                    markdown::previous_heading(&doc.cached_headings, line)
+                };
+                if let Some(target) = target.map(|h| h.line) {
+                    self.navigate_to(target, 0);
+                }
+                Task::none()
+            }
+            SearchMsg::NextParagraph | SearchMsg::PreviousParagraph => {
+                let doc = self.active_doc();
+                let line = doc.content.cursor().position.line;
+                let count = doc.content.line_count();
+                let blank = |i: usize| doc.mirror.line(i).trim().is_empty();
+                let target = if matches!(msg, SearchMsg::NextParagraph) {
+                    paragraphs::next_start(count, blank, line)
+                } else {
+                    paragraphs::previous_start(count, blank, line)
+                };
+                self.navigate_to(target, 0);
+                Task::none()
+            }
+            SearchMsg::GoToHeading(line) => {
+                self.show_outline = false;
+                self.record_jump();
+                self.navigate_to(line, 0);
+                operation::focus(editor_id())
+            }
+        }
+    }
+
+    // --- View operations ---
+
+    fn handle_view(&mut self, msg: ViewMsg) -> Task<Message> {
+        match msg {
+            ViewMsg::ZoomIn => {
+                self.font_size = (self.font_size + ZOOM_STEP).min(MAX_FONT_SIZE);
+                self.save_preferences();
+            }
+            ViewMsg::ZoomOut => {
+                self.font_size = (self.font_size - ZOOM_STEP).max(MIN_FONT_SIZE);
+                self.save_preferences();
+            }
+            ViewMsg::ZoomReset => {
+                self.font_size = DEFAULT_FONT_SIZE;
+                self.save_preferences();
+            }
+            ViewMsg::ToggleDarkMode => {
+                self.dark_mode = !self.dark_mode;
+                self.save_preferences();
+            }
+            ViewMsg::ToggleWordWrap => {
+                self.word_wrap = !self.word_wrap;
+                self.save_preferences();
+            }
+            ViewMsg::RestoreView => {
+                self.active_doc_mut().apply_pending_view();
+            }
+            ViewMsg::SystemScale(scale) => {
+                self.system_scale = scale;
+            }
+            ViewMsg::ToggleOutline => {
+                self.show_outline = !self.show_outline;
+                self.active_menu = None;
+                self.show_tab_list = false;
+            }
+            ViewMsg::SetOutlineLevel(level) => {
+                self.outline_level = level.clamp(1, 6);
+            }
+            ViewMsg::ToggleMemory => {
+                self.show_memory = !self.show_memory;
+            }
+            ViewMsg::HibernateBackgroundTabs => {
+                let indices = self.hibernation_candidates().iter().map(|c| c.index).collect();
+                let count = self.hibernate_at(indices);
+                if count > 0 {
+                    self.notify(Severity::Info, format!("{count} onglet(s) hiberné(s)"));
+                }
+            }
+            ViewMsg::SplitVertical | ViewMsg::SplitHorizontal => {
+                let vertical = matches!(msg, ViewMsg::SplitVertical);
+                match &mut self.split {
+                    Some(split) => split.vertical = vertical,
+                    // Two views of the active document to start with
+                    None => {
+                        self.split = Some(Split {
+                            vertical,
+                            other: self.active_doc().id,
+                            focus: Pane::First,
+                        })
+                    }
+                }
+            }
+            ViewMsg::CloseSplit => {
+                self.split = None;
+                return operation::focus(editor_id());
+            }
+            ViewMsg::PaneAction(pane, action) => {
+                // The wheel scrolls the other pane without moving the focus
+                if let text_editor::Action::Scroll { lines } = action {
+                    let other = self.split.as_ref().filter(|s| s.focus != pane).map(|s| s.other);
+                    if let Some(doc) = self.tabs.iter_mut().find(|d| Some(d.id) == other) {
+                        doc.perform(action);
+                        let max_offset = doc.content.line_count().saturating_sub(1) as f32;
+                        doc.scroll_offset = (doc.scroll_offset + lines as f32).clamp(0.0, max_offset);
+                        return Task::none();
+                    }
+                }
+                self.focus_pane(pane);
+                return self.handle_editor_action(action);
+            }
+        }
+        Task::none()
+    }
+
+    // Gives the focus to a pane of the split: its document becomes the active tab
+    fn focus_pane(&mut self, pane: Pane) {
+        let active = self.active_doc().id;
+        let Some(split) = self.split.as_mut().filter(|s| s.focus != pane) else {
+            return;
+        };
+        let other = std::mem::replace(&mut split.other, active);
+        split.focus = pane;
+        if let Some(index) = self.tabs.iter().position(|d| d.id == other) {
+            self.active_tab = index;
+        }
+    }
+
+    // --- Settings ---
+
+    fn handle_settings(&mut self, msg: SettingsMsg) -> Task<Message> {
+        match msg {
+            SettingsMsg::Open => {
+                self.show_settings = true;
+                self.spell_dictionaries = spelling::available(&dictionary::folder());
+            }
+            SettingsMsg::Close => {
+                self.show_settings = false;
+                self.show_keybindings = false;
+                self.show_abbreviations = false;
+                self.show_snippets = false;
+                self.rebinding = None;
+            }
+            SettingsMsg::SetDarkMode(v) => {
+                self.dark_mode = v;
+                self.save_preferences();
+            }
+            SettingsMsg::SetFontSize(v) => {
+                self.font_size = v.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+                self.save_preferences();
+            }
+            SettingsMsg::SetWordWrap(v) => {
+                self.word_wrap = v;
+                self.save_preferences();
+            }
+            SettingsMsg::SetProseLigatures(v) => {
+                self.prose_ligatures = v;
+                self.save_preferences();
+            }
+            SettingsMsg::SetCodeLigatures(v) => {
+                self.code_ligatures = v;
+                self.save_preferences();
+            }
+            SettingsMsg::SetRestoreSession(v) => {
+                self.restore_session = v;
+                self.save_preferences();
+                if !v {
+                    SessionData::clear();
+                }
+            }
+            SettingsMsg::SetShowToolbar(v) => {
+                self.show_toolbar = v;
+                self.save_preferences();
+            }
+            SettingsMsg::SetMruTabSwitching(v) => {
+                self.mru_tab_switching = v;
+                self.save_preferences();
+            }
+            SettingsMsg::SetAutoHideFind(v) => {
+                self.auto_hide_find = v;
+                self.save_preferences();
+            }
+            SettingsMsg::SetEscapeOrder(order) => {
+                self.escape_order = order;
+                self.save_preferences();
+            }
+            SettingsMsg::SetClickClosesBars(v) => {
+                self.click_closes_bars = v;
+                self.save_preferences();
+            }
+            SettingsMsg::SetRememberCursorPosition(v) => {
+                self.remember_cursor_position = v;
+                self.save_preferences();
+            }
+            SettingsMsg::SetLockDocuments(v) => {
+                self.lock_documents = v;
+                self.save_preferences();
+                if !v {
+                    self.release_locks();
+                }
+            }
+            SettingsMsg::SetHibernateTabs(v) => {
+                self.hibernate_tabs = v;
+                self.save_preferences();
+                self.relieve_memory_pressure();
+            }
+            SettingsMsg::ChooseNotesFolder => {
+                return Task::future(async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Dossier des notes")
+                        .pick_folder()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                })
+                .and_then(|folder| {
+                    Task::done(Message::Settings(SettingsMsg::SetNotesFolder(Some(folder))))
+                });
+            }
+            SettingsMsg::SetNotesFolder(folder) => {
+                self.notes_folder = folder;
+                self.save_preferences();
+            }
+            SettingsMsg::SetNetworkSafeSave(enabled) => {
+                self.network_safe_save = enabled;
+                self.save_preferences();
+            }
+            SettingsMsg::SetTrimTrailingWhitespace(enabled) => {
+                self.trim_trailing_whitespace = enabled;
+                self.save_preferences();
+            }
+            SettingsMsg::SetSaveOnFocusLoss(enabled) => {
+                self.save_on_focus_loss = enabled;
+                self.save_preferences();
+            }
+            SettingsMsg::SetLowPower(enabled) => {
+                self.low_power = enabled;
+                self.save_preferences();
+                if !enabled {
+                    self.refresh_stale_stats();
+                }
+            }
+            // The document being edited follows, as if picked in the status bar
+            SettingsMsg::SetTabWidth(width) => {
+                self.tab_width = width.max(1);
+                self.active_doc_mut().indent_size = self.tab_width;
+                self.save_preferences();
+            }
+            SettingsMsg::SetInsertSpaces(enabled) => {
+                self.insert_spaces = enabled;
+                self.active_doc_mut().indent_style = if enabled { IndentStyle::Space } else { IndentStyle::Tab };
+                self.save_preferences();
+            }
+            SettingsMsg::SetNetworkAutosaveMinutes(minutes) => {
+                self.network_autosave_minutes = minutes;
+                self.save_preferences();
+            }
+            SettingsMsg::SetSlowSaveMs(delay) => {
+                self.slow_save_ms = delay;
+                self.save_preferences();
+            }
+            SettingsMsg::SetWindowOpacity(percent) => {
+                self.window_opacity = percent.clamp(MIN_WINDOW_OPACITY, 100);
+                return opacity::apply(self.window_opacity);
+            }
+            SettingsMsg::SaveWindowOpacity => {
+                self.save_preferences();
+            }
+            SettingsMsg::SetUiScale(percent) => {
+                // The window keeps its size on screen, so its size in layout
+                // units changes without a resize event
+                let ratio = self.scale_factor() / dpi::ui_scale_factor(percent);
+                self.window_width *= ratio;
+                self.window_height *= ratio;
+                self.ui_scale = percent;
+                self.save_preferences();
+            }
+            SettingsMsg::SetProxyMode(mode) => {
+                self.proxy.mode = mode;
+                self.save_preferences();
+            }
+            SettingsMsg::ProxyAddressChanged(address) => {
+                self.proxy.address = address;
+                self.save_preferences();
+            }
+            SettingsMsg::ProxyUserChanged(user) => {
+                self.proxy.user = user;
+                self.save_preferences();
+            }
+            SettingsMsg::DateFormatChanged(format) => {
+                self.date_format = format;
+                self.save_preferences();
+            }
+            SettingsMsg::SetUrlPaste(mode) => {
+                self.url_paste = mode;
+                self.save_preferences();
+            }
+            SettingsMsg::ProxyPasswordChanged(password) => {
+                self.proxy_password = password;
+            }
+            SettingsMsg::SaveProxyPassword => {
+                // The keychain may ask to be unlocked, so it is reached off the UI thread
+                let name = self.proxy.password_name();
+                let password = std::mem::take(&mut self.proxy_password);
+                return Task::future(jobs::spawn_blocking(move || secrets::store(&name, &password))).map(
+                    |result| {
+                        let result = result.unwrap_or_else(|| Err("enregistrement interrompu".to_string()));
+                        Message::Settings(SettingsMsg::ProxyPasswordSaved(result))
+                    },
+                );
+            }
+            SettingsMsg::ProxyPasswordSaved(result) => match result {
+                Ok(()) => self.notify(Severity::Success, "Mot de passe du proxy enregistré dans le trousseau"),
+                Err(e) => self.notify(Severity::Error, format!("Mot de passe du proxy non enregistré : {e}")),
+            },
+            SettingsMsg::ToggleKeybindings => {
+                self.show_keybindings = !self.show_keybindings;
+                self.rebinding = None;
+            }
+            SettingsMsg::Rebind(action) => {
+                self.rebinding = Some(action);
+            }
+            SettingsMsg::ResetBinding(action) => {
+                self.rebinding = None;
+                self.keymap.reset(action);
+                self.keymap.save();
+            }
+            SettingsMsg::ResetAllBindings => {
+                self.rebinding = None;
+                self.keymap = Keymap::default();
+                self.keymap.save();
+            }
+            SettingsMsg::ToggleAbbreviations => {
+                self.show_abbreviations = !self.show_abbreviations;
+            }
+            SettingsMsg::AddAbbreviation => {
+                self.abbreviations.push(Abbreviation::default());
+                self.save_preferences();
+            }
+            SettingsMsg::RemoveAbbreviation(index) => {
+                if index < self.abbreviations.len() {
+                    self.abbreviations.remove(index);
+                    self.save_preferences();
+                }
+            }
+            SettingsMsg::AbbreviationChanged(index, abbreviation) => {
+                if let Some(entry) = self.abbreviations.get_mut(index) {
+                    // Typing a terminator would end it before it is complete
+                    entry.abbreviation = abbreviation.chars().filter(|&c| !abbreviations::is_terminator(c)).collect();
+                    self.save_preferences();
+                }
+            }
+            SettingsMsg::ExpansionChanged(index, expansion) => {
+                if let Some(entry) = self.abbreviations.get_mut(index) {
+                    entry.expansion = expansion;
+                    self.save_preferences();
+                }
+            }
+            SettingsMsg::SetSpellDictionary(name) => {
+                self.spell_dictionary = name;
+                self.speller = None;
+                self.save_preferences();
+                return self.load_speller();
+            }
+            SettingsMsg::SpellDictionaryLoaded(name, result) => {
+                // Another dictionary was chosen meanwhile
+                if self.spell_dictionary.as_ref() != Some(&name) {
+                    return Task::none();
+                }
+                match result {
+                    Ok(speller) => self.speller = Some(speller),
+                    Err(e) => self.notify(Severity::Error, format!("Dictionnaire {name} illisible : {e}")),
+                }
+            }
+            SettingsMsg::SetAbbreviationLanguage(index, language) => {
+                if let Some(entry) = self.abbreviations.get_mut(index) {
+                    entry.language = language;
+                    self.save_preferences();
+                }
+            }
+            SettingsMsg::ToggleSnippets => {
+                self.show_snippets = !self.show_snippets;
+            }
+            SettingsMsg::AddSnippet => {
+                self.snippets.push(Snippet::default());
+                snippets::save(&self.snippets);
+            }
+            SettingsMsg::RemoveSnippet(index) => {
+                if index < self.snippets.len() {
+                    self.snippets.remove(index);
+                    snippets::save(&self.snippets);
+                }
+            }
+            SettingsMsg::SnippetTriggerChanged(index, trigger) => {
+                if let Some(entry) = self.snippets.get_mut(index) {
+                    // A trigger is a single word, ended by the caret
+                    entry.trigger = trigger.chars().filter(|c| !c.is_whitespace()).collect();
+                    snippets::save(&self.snippets);
+                }
+            }
+            SettingsMsg::SnippetBodyChanged(index, body) => {
+                if let Some(entry) = self.snippets.get_mut(index) {
+                    entry.body = body;
+                    snippets::save(&self.snippets);
+                }
+            }
+            SettingsMsg::SetSnippetLanguage(index, language) => {
+                if let Some(entry) = self.snippets.get_mut(index) {
+                    entry.language = language;
+                    snippets::save(&self.snippets);
+                }
+            }
+        }
+        Task::none()
+    }
+
+    // --- Format operations ---
+
+    fn handle_format(&mut self, msg: FormatMsg) -> Task<Message> {
+        match msg {
+            FormatMsg::SetFontFamily(name) => {
+                self.font_family = name;
+                self.save_preferences();
+            }
+            FormatMsg::SetIndent(style, size) => {
+                let doc = self.active_doc_mut();
+                doc.indent_style = style;
+                doc.indent_size = size;
+            }
+            FormatMsg::ChooseLanguage(language) => {
+                let doc = self.active_doc_mut();
+                let path = doc.file_path.clone();
+                // Picking the detected language drops the override
+                doc.language_override = Some(language).filter(|&l| l != doc.detected_language());
+                // Parsed again with the new language's rules
+                doc.syntax_checked = 0;
+                doc.syntax_error = None;
+                doc.update_stats_cache();
+                let chosen = doc.language_override;
+                if let Some(path) = path {
+                    self.metadata.update(&path, |meta| meta.language = chosen);
+                    self.metadata.save();
+                }
+            }
+        }
+        Task::none()
+    }
+
+    // --- Menu operations ---
+
+    fn handle_menu(&mut self, msg: MenuMsg) -> Task<Message> {
+        match msg {
+            MenuMsg::Toggle(menu) => {
+                self.show_recent_files = false;
+                self.show_reopen_encodings = false;
+                self.show_bookmarks_menu = false;
+                self.show_date_formats = false;
+                if self.active_menu == Some(menu) {
+                    self.active_menu = None;
+                } else {
+                    self.active_menu = Some(menu);
+                }
+                self.show_context_menu = false;
+            }
+            MenuMsg::Hover(menu) => {
+                if self.active_menu.is_some() && self.active_menu != Some(menu) {
+                    self.show_recent_files = false;
+                    self.show_reopen_encodings = false;
+                    self.show_bookmarks_menu = false;
+                    self.show_date_formats = false;
+                    self.active_menu = Some(menu);
+                }
+            }
+            MenuMsg::CloseAll => {
+                self.active_menu = None;
+                self.show_context_menu = false;
+                self.show_indent_menu = false;
+                self.show_language_menu = false;
+                self.show_tab_list = false;
+                self.show_outline = false;
+            }
+            MenuMsg::ShowContext => {
+                self.spell_suggestions = self.misspelled_at_cursor();
+                self.show_context_menu = true;
+                self.show_indent_menu = false;
+                self.show_language_menu = false;
+                self.context_menu_position = self.mouse_position;
+                self.active_menu = None;
+            }
+            MenuMsg::ShowIndent => {
+                self.show_indent_menu = !self.show_indent_menu;
+                self.show_language_menu = false;
+                self.show_context_menu = false;
+                self.context_menu_position = self.mouse_position;
+                self.active_menu = None;
+            }
+            MenuMsg::ShowLanguage => {
+                self.show_language_menu = !self.show_language_menu;
+                self.show_indent_menu = false;
+                self.show_context_menu = false;
+                self.context_menu_position = self.mouse_position;
+                self.active_menu = None;
+            }
+            MenuMsg::ToggleTabList => {
+                self.show_tab_list = !self.show_tab_list;
+                self.show_context_menu = false;
+                self.show_indent_menu = false;
+                self.show_language_menu = false;
+                self.active_menu = None;
+            }
+        }
+        Task::none()
+    }
+
+    // --- Event handling ---
+
+    fn handle_event(&mut self, event: Event) -> Task<Message> {
+        if let Event::Mouse(iced::mouse::Event::CursorMoved { position }) = &event {
+            self.mouse_position = *position;
+        }
+
+        // Track modifier keys for Ctrl+wheel zoom
+        if let Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = &event {
+            self.ctrl_pressed = modifiers.control();
+            // Releasing Ctrl confirms the MRU switcher selection
+            if !self.ctrl_pressed {
+                self.commit_tab_switcher();
+            }
+        }
+
+        // Global mouse wheel scroll — works regardless of which widget the mouse is over
+        if let Event::Mouse(iced::mouse::Event::WheelScrolled { delta }) = &event {
+            let lines = match delta {
+                iced::mouse::ScrollDelta::Lines { y, .. } => *y,
+                iced::mouse::ScrollDelta::Pixels { y, .. } => *y / (self.font_size * 1.3),
+            };
+            if lines != 0.0 {
+                let int_lines = if lines > 0.0 {
+                    -(lines.ceil() as i32)
+                } else {
+                    (-lines).ceil() as i32
+                };
+                // Wheel over the tab bar scrolls the tab strip
+                let tab_bar_top = MENU_BAR_HEIGHT + if self.show_toolbar { TOOLBAR_HEIGHT } else { 0.0 };
+                let mouse_y = self.mouse_position.y;
+                if (tab_bar_top..tab_bar_top + TAB_BAR_HEIGHT).contains(&mouse_y) {
+                    return self.handle_file(FileMsg::ScrollTabs(int_lines.signum()));
+                }
+                if self.ctrl_pressed {
+                    return if int_lines < 0 {
+                        self.handle_view(ViewMsg::ZoomIn)
+                    } else {
+                        self.handle_view(ViewMsg::ZoomOut)
+                    };
+                }
+                let doc = self.active_doc_mut();
+                doc.content
+                    .perform(text_editor::Action::Scroll { lines: int_lines });
+                let max_offset = doc.content.line_count().saturating_sub(1) as f32;
+                doc.scroll_offset =
+                    (doc.scroll_offset + int_lines as f32).clamp(0.0, max_offset);
+                return Task::none();
+            }
+        }
+
+        if let Event::Window(iced::window::Event::Rescaled(scale)) = &event {
+            self.system_scale = *scale;
+        }
+
+        // Switching to another window (a terminal, to build) saves everything
+        if let Event::Window(iced::window::Event::Unfocused) = &event {
+            if self.save_on_focus_loss {
+                self.save_modified(|_| true);
+            }
+        }
+
+        if let Event::Window(iced::window::Event::Resized(size)) = &event {
+            self.window_width = size.width;
+            self.window_height = size.height;
+            self.save_preferences();
+        }
+
+        if let Event::Window(iced::window::Event::FileDropped(path)) = event {
+            if path.is_dir() {
+                return self.handle_folder(FolderMsg::Show(path));
+            }
+            return self.open_dropped_file(path);
+        }
+
+        if let Event::Keyboard(keyboard::Event::KeyPressed {
+            key, modifiers, ..
+        }) = event
+        {
+            if let Some(action) = self.rebinding {
+                self.capture_binding(action, &key, modifiers);
+                return Task::none();
+            }
+            if let Some(action) = self.keymap.lookup(Scope::Global, &key, modifiers) {
+                return self.run_shortcut(action);
+            }
+        }
+        Task::none()
+    }
+
+    // --- Keyboard shortcuts ---
+
+    // Key press typed in the keybindings editor for `action`; a lone modifier
+    // keeps waiting and Escape gives up
+    fn capture_binding(&mut self, action: Action, key: &keyboard::Key, modifiers: keyboard::Modifiers) {
+        let Some((modifiers, key)) = shortcuts::chord(key, modifiers) else {
+            return;
+        };
+        self.rebinding = None;
+        if key == KeyName::Named(keyboard::key::Named::Escape) && modifiers.is_empty() {
+            return;
+        }
+        // A plain letter or symbol would be typed into the document instead
+        if matches!(key, KeyName::Char(_)) && !modifiers.control() && !modifiers.alt() {
+            self.notify(Severity::Warning, "Un raccourci sur une lettre ou un symbole doit utiliser Ctrl ou Alt");
+            return;
+        }
+        self.keymap.rebind(action, modifiers, key);
+        self.keymap.save();
+        let conflicts = self.keymap.conflicts(action);
+        if !conflicts.is_empty() {
+            let labels: Vec<&str> = conflicts.into_iter().map(shortcuts::label).collect();
+            self.notify(
+                Severity::Warning,
+                format!("{} est aussi le raccourci de : {}", self.keymap.keys(action), labels.join(", ")),
+            );
+        }
+    }
+
+    fn run_shortcut(&mut self, action: Action) -> Task<Message> {
+        if let Some(steps) = &mut self.macro_recording {
+            if macros::is_recorded_shortcut(action) {
+                steps.push(Message::Shortcut(action));
+            }
+        }
+        match action {
+            Action::NewTab => self.handle_file(FileMsg::NewTab),
+            Action::Open => self.handle_file(FileMsg::Open),
+            Action::Save => self.handle_file(FileMsg::Save),
+            Action::SaveAs => self.handle_file(FileMsg::SaveAs),
+            Action::Print => self.handle_file(FileMsg::Print),
+            Action::CloseTab => {
+                let idx = self.active_tab;
+                self.handle_file(FileMsg::CloseTab(idx))
+            }
+            Action::NextTab => {
+                if self.mru_tab_switching {
+                    self.cycle_tab_switcher(true);
+                } else if !self.tabs.is_empty() {
+                    self.active_tab = (self.active_tab + 1) % self.tabs.len();
+                    self.find_cursor = 0;
+                }
+                Task::none()
+            }
+            Action::PreviousTab => {
+                if self.mru_tab_switching {
+                    self.cycle_tab_switcher(false);
+                } else if !self.tabs.is_empty() {
+                    self.active_tab = if self.active_tab == 0 {
+                        self.tabs.len() - 1
+                    } else {
+                        self.active_tab - 1
+                    };
+                    self.find_cursor = 0;
+                }
+                Task::none()
+            }
+            Action::Undo => self.handle_edit(EditMsg::Undo),
+            Action::Redo => self.handle_edit(EditMsg::Redo),
+            Action::Cut => self.handle_edit(EditMsg::Cut),
+            Action::Copy => self.handle_edit(EditMsg::Copy),
+            Action::Paste => self.handle_edit(EditMsg::Paste),
+            Action::PastePlain => self.handle_edit(EditMsg::PastePlain),
+            Action::SelectAll => self.handle_edit(EditMsg::SelectAll),
+            Action::InsertDateTime => self.handle_edit(EditMsg::InsertDateTime),
+            Action::JoinLines => self.handle_edit(EditMsg::JoinLines),
+            Action::ToggleComment => self.handle_edit(EditMsg::ToggleComment),
+            Action::MoveParagraphUp => self.handle_edit(EditMsg::MoveParagraphUp),
+            Action::MoveParagraphDown => self.handle_edit(EditMsg::MoveParagraphDown),
+            Action::ToggleMacroRecording => self.handle_edit(EditMsg::ToggleMacroRecording),
+            Action::PlayMacro => self.handle_edit(EditMsg::PlayMacro),
+            Action::Find => self.handle_search(SearchMsg::OpenFind),
+            Action::Replace => self.handle_search(SearchMsg::OpenReplace),
+            Action::GoTo => self.handle_search(SearchMsg::OpenGoTo),
+            Action::SearchNotes => self.handle_notes(NotesMsg::OpenSearch),
+            Action::FindNext => self.handle_search(SearchMsg::FindNext),
+            Action::FindPrevious => self.handle_search(SearchMsg::FindPrevious),
+            Action::NavigateBack => self.handle_search(SearchMsg::NavigateBack),
+            Action::NavigateForward => self.handle_search(SearchMsg::NavigateForward),
+            Action::LastEdit => self.handle_search(SearchMsg::LastEdit),
+            Action::NextEdit => self.handle_search(SearchMsg::NextEdit),
+            Action::PreviousEdit => self.handle_search(SearchMsg::PreviousEdit),
+            Action::ToggleBookmark => self.handle_search(SearchMsg::ToggleBookmark),
+            Action::NextBookmark => self.handle_search(SearchMsg::NextBookmark),
+            Action::PreviousBookmark => self.handle_search(SearchMsg::PreviousBookmark),
+            Action::PreviousParagraph => self.handle_search(SearchMsg::PreviousParagraph),
+            Action::NextParagraph => self.handle_search(SearchMsg::NextParagraph),
+            Action::PreviousHeading => self.handle_search(SearchMsg::PreviousHeading),
+            Action::NextHeading => self.handle_search(SearchMsg::NextHeading),
+            Action::ToggleWordWrap => self.handle_view(ViewMsg::ToggleWordWrap),
+            Action::ZoomIn => self.handle_view(ViewMsg::ZoomIn),
+            Action::ZoomOut => self.handle_view(ViewMsg::ZoomOut),
+            Action::ZoomReset => self.handle_view(ViewMsg::ZoomReset),
+            Action::ShowShortcuts => self.handle_help(HelpMsg::ToggleShortcuts),
+            Action::ClosePanel => {
+                self.close_panel();
+                Task::none()
+            }
+        }
+    }
+
+    // Escape closes the topmost menu or dialog; past those, the order of the
+    // panels and the selection is a setting
+    fn close_panel(&mut self) {
+        if self.close_overlay() {
+            return;
+        }
+        match self.escape_order {
+            EscapeOrder::PanelsOnly => {
+                self.close_bar();
+            }
+            EscapeOrder::SelectionFirst => {
+                if !self.clear_selection() {
+                    self.close_bar();
+                }
+            }
+            EscapeOrder::PanelsFirst => {
+                if !self.close_bar() {
+                    self.clear_selection();
+                }
+            }
+        }
+    }
+
+    // Menus, popups and dialogs, topmost first; false when none is open
+    fn close_overlay(&mut self) -> bool {
+        if self.tab_switcher.is_some() {
+            self.tab_switcher = None;
+        } else if self.shortcut_sheet.is_some() {
+            self.shortcut_sheet = None;
+        } else if self.tag_editor.is_some() {
+            self.tag_editor = None;
+        } else if self.note_search.is_some() {
+            self.note_search = None;
+        } else if self.properties.is_some() {
+            self.properties = None;
+        } else if self.folder_picker.is_some() {
+            self.folder_picker = None;
+        } else if self.session_manager.is_some() {
+            self.session_manager = None;
+        } else if self.template_manager.is_some() {
+            self.template_manager = None;
+        } else if self.filter_prompt.is_some() {
+            self.filter_prompt = None;
+        } else if self.wrap_prompt.is_some() {
+            self.wrap_prompt = None;
+        } else if self.macro_prompt.is_some() {
+            self.macro_prompt = None;
+        } else if self.char_picker.is_some() {
+            self.char_picker = None;
+        } else if self.script_picker.is_some() {
+            self.script_picker = None;
+        } else if self.definition.is_some() {
+            self.definition = None;
+        } else if self.show_keybindings {
+            self.show_keybindings = false;
+            self.rebinding = None;
+        } else if self.show_abbreviations {
+            self.show_abbreviations = false;
+        } else if self.show_snippets {
+            self.show_snippets = false;
+        } else if self.show_settings {
+            self.show_settings = false;
+        } else if self.show_memory {
+            self.show_memory = false;
+        } else if self.show_about {
+            self.show_about = false;
+        } else if self.print_preview.is_some() {
+            self.print_preview = None;
+        } else if self.print_queue.is_some() {
+            self.print_queue = None;
+        } else if self.save_as.is_some() {
+            self.save_as = None;
+        } else if self.qr_popup.is_some() {
+            self.qr_popup = None;
+        } else if self.color_picker.is_some() {
+            self.color_picker = None;
+        } else if self.active_menu.is_some()
+            || self.show_context_menu
+            || self.show_indent_menu
+            || self.show_language_menu
+            || self.show_tab_list
+            || self.show_outline
+        {
+            self.active_menu = None;
+            self.show_context_menu = false;
+            self.show_indent_menu = false;
+            self.show_language_menu = false;
+            self.show_tab_list = false;
+            self.show_outline = false;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    // Side panels and the bars above the editor, one per press
+    fn close_bar(&mut self) -> bool {
+        if self.show_snapshots {
+            self.show_snapshots = false;
+        } else if self.show_find || self.show_goto {
+            // Cancels search-as-you-type: the caret goes back where it was
+            if let Some(origin) = self.find_origin.take() {
+                let doc = self.active_doc_mut();
+                doc.select_bytes(origin, origin);
+                doc.scroll_offset = doc.byte_to_position(origin).0 as f32;
+            }
+            self.close_find_bars();
+        } else if self.show_jobs {
+            self.show_jobs = false;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn close_find_bars(&mut self) {
+        self.show_find = false;
+        self.show_replace = false;
+        self.show_goto = false;
+        self.find_origin = None;
+    }
+
+    fn clear_selection(&mut self) -> bool {
+        let doc = self.active_doc_mut();
+        if doc.content.cursor().selection.is_none() {
+            return false;
+        }
+        doc.deselect();
+        true
+    }
+
+    // --- Preferences ---
+
+    pub fn save_preferences(&self) {
+        UserPreferences {
+            font_size: self.font_size,
+            font_family: self.font_family.clone(),
+            dark_mode: self.dark_mode,
+            word_wrap: self.word_wrap,
+            prose_ligatures: self.prose_ligatures,
+            code_ligatures: self.code_ligatures,
+            window_width: self.window_width,
+            window_height: self.window_height,
+            restore_session: self.restore_session,
+            show_toolbar: self.show_toolbar,
+            mru_tab_switching: self.mru_tab_switching,
+            auto_hide_find: self.auto_hide_find,
+            escape_order: self.escape_order,
+            click_closes_bars: self.click_closes_bars,
+            remember_cursor_position: self.remember_cursor_position,
+            lock_documents: self.lock_documents,
+            hibernate_tabs: self.hibernate_tabs,
+            notes_folder: self.notes_folder.clone(),
+            network_safe_save: self.network_safe_save,
+            network_autosave_minutes: self.network_autosave_minutes,
+            slow_save_ms: self.slow_save_ms,
+            save_on_focus_loss: self.save_on_focus_loss,
+            low_power: self.low_power,
+            trim_trailing_whitespace: self.trim_trailing_whitespace,
+            tab_width: self.tab_width,
+            insert_spaces: self.insert_spaces,
+            window_opacity: self.window_opacity,
+            ui_scale: self.ui_scale,
+            proxy: self.proxy.clone(),
+            abbreviations: self.abbreviations.clone(),
+            spell_dictionary: self.spell_dictionary.clone(),
+            recent_characters: self.recent_characters.clone(),
+            date_format: self.date_format.clone(),
+            url_paste: self.url_paste,
+        }
+        .save();
+    }
+
+    // Changes with the tab list, the texts and the cursors
+    fn session_fingerprint(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::hash::DefaultHasher::new();
+        self.active_tab.hash(&mut hasher);
+        for doc in &self.tabs {
+            let view = doc.view_state();
+            (&doc.file_path, doc.is_modified, doc.mirror.version()).hash(&mut hasher);
+            (view.line, view.column).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    // Writes the session if it changed since the last write, once the startup
+    // session has been restored
+    fn autosave_session(&mut self) {
+        if self.session_saved.is_some_and(|saved| saved != self.session_fingerprint()) {
+            self.save_session();
+        }
+    }
+
+    fn save_session(&mut self) {
+        if !self.restore_session {
+            return;
+        }
+        self.session_data().save();
+        if self.session_saved.is_some() {
+            self.session_saved = Some(self.session_fingerprint());
+        }
+    }
+
+    // Open tabs with their unsaved text and view
+    fn session_data(&self) -> SessionData {
+        let tabs: Vec<SessionTab> = self
+            .tabs
+            .iter()
+            .map(|doc| {
+                let view = doc.view_state();
+                SessionTab {
+                    file_path: doc.file_path.clone(),
+                    unsaved_content: if doc.file_path.is_none() || doc.is_modified {
+                        Some(doc.text().to_string())
+                    } else {
+                        None
+                    },
+                    is_modified: doc.is_modified,
+                    cursor_line: view.line,
+                    cursor_column: view.column,
+                    scroll_offset: view.scroll,
+                }
+            })
+            .collect();
+        SessionData {
+            tabs,
+            active_tab: self.active_tab,
+        }
+    }
+
+    // --- Named sessions ---
+
+    fn store_named_session(&mut self, name: &str) {
+        let session = NamedSession {
+            name: name.to_string(),
+            session: self.session_data(),
+            window_width: self.window_width,
+            window_height: self.window_height,
+        };
+        self.named_sessions.set(session);
+    }
+
+    // Replaces the open tabs with those of the session
+    fn apply_named_session(&mut self, target: &NamedSession, files: Vec<Option<LoadedFile>>) {
+        for index in (0..self.tabs.len()).rev() {
+            self.remove_tab(index);
+        }
+        self.restore_session_data(&target.session, files);
+        self.named_sessions.current = Some(target.name.clone());
+        self.window_width = target.window_width;
+        self.window_height = target.window_height;
+    }
+
+    fn handle_session(&mut self, msg: SessionMsg) -> Task<Message> {
+        match msg {
+            SessionMsg::Show => {
+                self.session_manager = Some(self.named_sessions.current.clone().unwrap_or_default());
+                return operation::focus(session_name_input_id());
+            }
+            SessionMsg::Close => {
+                self.session_manager = None;
+                return operation::focus(editor_id());
+            }
+            SessionMsg::NameChanged(name) => {
+                if let Some(input) = &mut self.session_manager {
+                    *input = name;
+                }
+            }
+            SessionMsg::Save => {
+                let name = self.session_manager.as_deref().unwrap_or_default().trim().to_string();
+                if name.is_empty() {
+                    return Task::none();
+                }
+                self.store_named_session(&name);
+                self.named_sessions.current = Some(name.clone());
+                self.named_sessions.save();
+                self.session_manager = None;
+                self.notify(Severity::Info, format!("Session « {name} » enregistrée"));
+            }
+            SessionMsg::Open(name) => {
+                if self.named_sessions.current.as_deref() == Some(name.as_str()) {
+                    self.session_manager = None;
+                    return Task::none();
+                }
+                // The tabs of a named session are stored back into it before
+                // switching; those of an unnamed one would be lost
+                if self.named_sessions.current.is_none() && self.tabs.iter().any(|d| d.is_modified) {
+                    return Self::confirm_discard(
+                        "Des documents ont été modifiés. Les fermer sans enregistrer pour ouvrir la session ?",
+                        move |confirmed| Message::Session(SessionMsg::OpenConfirmed(confirmed, name.clone())),
+                    );
+                }
+                return self.handle_session(SessionMsg::OpenConfirmed(true, name));
+            }
+            SessionMsg::OpenConfirmed(confirmed, name) => {
+                let Some(target) = self.named_sessions.get(&name).filter(|_| confirmed) else {
+                    return Task::none();
+                };
+                let session = target.session.clone();
+                if let Some(current) = self.named_sessions.current.clone() {
+                    self.store_named_session(&current);
+                }
+                self.session_manager = None;
+                return Task::future(jobs::spawn_blocking(move || Self::read_session_files(&session))).map(
+                    move |files| Message::Session(SessionMsg::Loaded(name.clone(), files.unwrap_or_default())),
+                );
+            }
+            SessionMsg::Loaded(name, files) => {
+                let Some(target) = self.named_sessions.get(&name).cloned() else {
+                    return Task::none();
+                };
+                self.apply_named_session(&target, files);
+                self.named_sessions.save();
+                let size = iced::Size::new(target.window_width, target.window_height);
+                return iced::window::latest()
+                    .and_then(move |id| iced::window::resize(id, size));
+            }
+            SessionMsg::Delete(name) => {
+                self.named_sessions.remove(&name);
+                self.named_sessions.save();
+            }
+        }
+        Task::none()
+    }
+
+    // --- Templates ---
+
+    fn handle_template(&mut self, msg: TemplateMsg) -> Task<Message> {
+        match msg {
+            TemplateMsg::Show => {
+                self.template_manager = Some(TemplateManager {
+                    name: String::new(),
+                    templates: templates::list(&templates::dir()),
+                    filling: None,
+                });
+                return operation::focus(template_name_input_id());
+            }
+            TemplateMsg::Close => {
+                self.template_manager = None;
+                return operation::focus(editor_id());
+            }
+            TemplateMsg::NameChanged(name) => {
+                if let Some(manager) = &mut self.template_manager {
+                    manager.name = name;
+                }
+            }
+            TemplateMsg::Save => {
+                let name = self.template_manager.as_ref().map(|m| m.name.trim().to_string()).unwrap_or_default();
+                if !templates::is_valid_name(&name) {
+                    self.notify(Severity::Warning, format!("Nom de modèle invalide : « {name} »"));
+                    return Task::none();
+                }
+                let doc = self.active_doc();
+                // The extension keeps the language of the document
+                let extension = doc
+                    .file_path
+                    .as_deref()
+                    .and_then(Path::extension)
+                    .and_then(|e| e.to_str())
+                    .map_or_else(|| doc.language().extensions()[0].to_string(), str::to_string);
+                let text = doc.text();
+                match templates::save(&templates::dir(), &name, &extension, &text) {
+                    Ok(_) => {
+                        self.template_manager = None;
+                        self.notify(Severity::Info, format!("Modèle « {name} » enregistré"));
+                        return operation::focus(editor_id());
+                    }
+                    Err(e) => {
+                        self.notify(Severity::Error, format!("Impossible d'enregistrer le modèle : {e}"));
+                    }
+                }
+            }
+            TemplateMsg::Use(file_name) => {
+                let text = match std::fs::read_to_string(templates::dir().join(&file_name)) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        self.notify(Severity::Error, format!("Impossible de lire le modèle : {e}"));
+                        return Task::none();
+                    }
+                };
+                let values: Vec<(String, String)> = templates::variables(&text)
+                    .into_iter()
+                    .map(|name| {
+                        let value = if name == "date" { today() } else { String::new() };
+                        (name, value)
+                    })
+                    .collect();
+                let Some(manager) = &mut self.template_manager else {
+                    return Task::none();
+                };
+                manager.filling = Some(TemplateFill { file_name, text, values });
+                if manager.filling.as_ref().is_some_and(|f| !f.values.is_empty()) {
+                    return operation::focus(template_value_input_id());
+                }
+                return self.handle_template(TemplateMsg::Create);
+            }
+            TemplateMsg::ValueChanged(index, value) => {
+                let filling = self.template_manager.as_mut().and_then(|m| m.filling.as_mut());
+                if let Some((_, v)) = filling.and_then(|f| f.values.get_mut(index)) {
+                    *v = value;
+                }
+            }
+            TemplateMsg::Create => {
+                let Some(fill) = self.template_manager.take().and_then(|m| m.filling) else {
+                    return Task::none();
+                };
+                let text = templates::fill(&fill.text, &fill.values);
+                let mut doc = Document {
+                    is_modified: true,
+                    line_ending: LineEnding::detect(&text),
+                    language_override: Some(Language::from_path(Some(Path::new(&fill.file_name)))),
+                    ..self.new_document()
+                };
+                doc.set_text(&text);
+                doc.update_stats_cache();
+                self.tabs.push(doc);
+                self.active_tab = self.tabs.len() - 1;
+                return operation::focus(editor_id());
+            }
+            TemplateMsg::Delete(file_name) => {
+                if let Err(e) = std::fs::remove_file(templates::dir().join(&file_name)) {
+                    self.notify(Severity::Error, format!("Impossible de supprimer le modèle : {e}"));
+                }
+                if let Some(manager) = &mut self.template_manager {
+                    manager.templates = templates::list(&templates::dir());
+                }
+            }
+        }
+        Task::none()
+    }
+
+    // --- Startup ---
+
+    // Runs on a worker thread: reads the session and the files it lists
+    pub fn load_startup(restore_session: bool) -> StartupData {
+        // Entries of deleted files are dropped at each start
+        let mut metadata = MetadataStore::load();
+        if metadata.prune() > 0 || !MetadataStore::path().exists() {
+            metadata.save();
+        }
+        let session = Some(SessionData::load())
+            .filter(|s| restore_session && !s.tabs.is_empty());
+        // The session file stays: it is kept current by the autosave, so a
+        // crash right after the startup still finds the tab list
+        let files = session.as_ref().map(Self::read_session_files).unwrap_or_default();
+        StartupData {
+            session,
+            files,
+            metadata,
+            notes: NoteIndex::load(),
+            named_sessions: NamedSessions::load(),
+            recent_files: RecentFiles::load(),
+            keymap: Keymap::load(),
+            snippets: snippets::load(),
+        }
+    }
+
+    // Blocking: decoded content of each session tab with a file, by tab index
+    fn read_session_files(session: &SessionData) -> Vec<Option<LoadedFile>> {
+        let files = session.tabs.iter().map(|tab| {
+            let path = tab.file_path.as_ref()?;
+            let decoded = std::fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| Self::decode_file(path, bytes));
+            match decoded {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    tracing::warn!("onglet de session {} non restauré : {e}", path.display());
+                    None
+                }
+            }
+        });
+        files.collect()
+    }
+
+    fn apply_startup(&mut self, data: StartupData) -> Task<Message> {
+        self.metadata = data.metadata;
+        self.named_sessions = data.named_sessions;
+        self.recent_files = data.recent_files;
+        self.keymap = data.keymap;
+        self.snippets = data.snippets;
+        for doc in &mut self.tabs {
+            if let Some(path) = &doc.file_path {
+                doc.language_override = self.metadata.language(path);
+                doc.bookmarks = self.metadata.bookmarks(path);
+            }
+        }
+        // Files opened before the index was read stay on top
+        let opened = std::mem::replace(&mut self.notes, data.notes);
+        for meta in opened.entries().iter().rev() {
+            self.notes.touch(&meta.path, meta.last_opened);
+        }
+        if let Some(session) = &data.session {
+            self.restore_session_data(session, data.files);
+        }
+        self.session_saved = Some(self.session_fingerprint());
+        let elapsed = self.launched.elapsed().as_millis();
+        let doc = self.active_doc_mut();
+        if doc.status_message.is_none() {
+            doc.status_message = Some(format!("Démarré en {elapsed} ms"));
+        }
+        // Files from the command line open after the session, the last one in front
+        let mut tasks = Vec::new();
+        for path in std::mem::take(&mut self.cli_files) {
+            tasks.push(if path.is_dir() {
+                self.handle_folder(FolderMsg::Show(path))
+            } else {
+                self.open_dropped_file(path)
+            });
+        }
+        Task::batch(tasks)
+    }
+
+    pub fn apply_loaded_silent(&mut self, path: PathBuf, loaded: LoadedFile) {
+        let path = paths::normalize(&path);
+        let network = paths::is_network(&path);
+        let language = self.metadata.language(&path);
+        let bookmarks = self.metadata.bookmarks(&path);
+        let file_size_mb = loaded.size / (1024 * 1024);
+        let content_text = loaded.text;
+        let sniffed = language::sniff(&path, &content_text);
+
+        let doc = self.active_doc_mut();
+        doc.line_ending = LineEnding::detect(&content_text);
+        doc.encoding = loaded.encoding;
+        doc.bom = loaded.bom;
+        doc.detect_indent(&content_text);
+        doc.apply_settings(loaded.settings);
+        doc.archive = loaded.archive;
+        doc.set_text(&content_text);
+        doc.ink = ink::load(&path);
+        doc.last_file_modified = std::fs::metadata(&path).ok().and_then(|m| m.modified().ok());
+        doc.file_path = Some(path);
+        doc.network = network;
+        doc.language_override = language;
+        doc.bookmarks = bookmarks;
+        doc.sniffed_language = sniffed;
+        doc.is_modified = false;
+        doc.mark_saved();
+        doc.scroll_offset = 0.0;
+        doc.undo_stack.clear();
+        doc.redo_stack.clear();
+        doc.last_edit_time = None;
+
+        if file_size_mb > 10 {
+            doc.max_undo = LARGE_FILE_UNDO_HISTORY;
+        } else {
+            doc.max_undo = MAX_UNDO_HISTORY;
+        }
+
+        doc.update_stats_cache();
+    }
+
+    // --- Undo/Redo ---
+
+    fn push_snapshot(&mut self, snapshot: TextSnapshot) {
+        let doc = self.active_doc_mut();
+        doc.undo_stack.push_back(snapshot);
+        while doc.undo_stack.len() > doc.max_undo {
+            doc.undo_stack.pop_front();
+        }
+    }
+
+    fn save_snapshot(&mut self) {
+        let doc = self.active_doc_mut();
+        let pos = doc.content.cursor().position;
+            let (cursor_line, cursor_col) = (pos.line, pos.column);
+        let snapshot = TextSnapshot {
+            text: doc.text(),
+            cursor_line,
+            cursor_col,
+        };
+        self.push_snapshot(snapshot);
+        let doc = self.active_doc_mut();
+        doc.redo_stack.clear();
+        doc.last_edit_time = None;
+    }
+
+    fn save_snapshot_if_needed(&mut self) {
+        let now = Instant::now();
+        let doc = self.active_doc_mut();
+        let should_save = match doc.last_edit_time {
+            Some(last) => now.duration_since(last).as_millis() > UNDO_BATCH_TIMEOUT_MS,
+            None => true,
+        };
+        if should_save {
+            let pos = doc.content.cursor().position;
+            let (cursor_line, cursor_col) = (pos.line, pos.column);
+            let snapshot = TextSnapshot {
+                text: doc.text(),
+                cursor_line,
+                cursor_col,
+            };
+            self.push_snapshot(snapshot);
+            self.active_doc_mut().redo_stack.clear();
+        }
+        self.active_doc_mut().last_edit_time = Some(now);
+    }
+
+    fn undo(&mut self) {
+        let doc = self.active_doc_mut();
+        if let Some(snapshot) = doc.undo_stack.pop_back() {
+            let pos = doc.content.cursor().position;
+            let (cursor_line, cursor_col) = (pos.line, pos.column);
+            doc.redo_stack.push(TextSnapshot {
+                text: doc.text(),
+                cursor_line,
+                cursor_col,
+            });
+            let lines = doc.content.line_count();
+            doc.set_text(&snapshot.text);
+            doc.is_modified = true;
+            doc.update_stats_cache();
+            // The edit spots and bookmarks stay, moved by the lines the
+            // snapshot adds or removes
+            let delta = doc.content.line_count() as isize - lines as isize;
+            let from = cursor_line.min(snapshot.cursor_line);
+            doc.edit_spots.record(from, delta, (snapshot.cursor_line, snapshot.cursor_col));
+            doc.bookmarks.shift(from, delta);
+            // navigate_to needs &mut self, so we drop doc first
+            let line = snapshot.cursor_line;
+            let col = snapshot.cursor_col;
+            self.navigate_to(line, col);
+        }
+    }
+
+    fn redo(&mut self) {
+        let doc = self.active_doc_mut();
+        if let Some(snapshot) = doc.redo_stack.pop() {
+            let pos = doc.content.cursor().position;
+            let (cursor_line, cursor_col) = (pos.line, pos.column);
+            doc.undo_stack.push_back(TextSnapshot {
+                text: doc.text(),
+                cursor_line,
+                cursor_col,
+            });
+            let lines = doc.content.line_count();
+            doc.set_text(&snapshot.text);
+            doc.is_modified = true;
+            doc.update_stats_cache();
+            // The edit spots and bookmarks stay, moved by the lines the
+            // snapshot adds or removes
+            let delta = doc.content.line_count() as isize - lines as isize;
+            let from = cursor_line.min(snapshot.cursor_line);
+            doc.edit_spots.record(from, delta, (snapshot.cursor_line, snapshot.cursor_col));
+            doc.bookmarks.shift(from, delta);
+            let line = snapshot.cursor_line;
+            let col = snapshot.cursor_col;
+            self.navigate_to(line, col);
+        }
+    }
+
+
+
+    // --- File I/O ---
+
+    // Saves the modified tabs backed by a file; network shares have their own timer
+    fn auto_save(&mut self, network: bool) -> Task<Message> {
+        self.save_modified(|doc| doc.network == network);
+        Task::none()
+    }
+
+    // Writes the modified documents with a file that `wanted` picks, in place
+    fn save_modified(&mut self, wanted: impl Fn(&Document) -> bool) {
+        let safe = self.network_safe_save;
+        let trim = self.trim_trailing_whitespace;
+        let mut saved = Vec::new();
+        let mut failed = Vec::new();
+        for doc in &mut self.tabs {
+            if doc.is_modified && doc.job.is_none() && doc.save_in_flight.is_none() && wanted(doc) {
+                if let Some(path) = doc.file_path.clone() {
+                    doc.prepare_for_save(trim);
+                    let written = doc
+                        .encode_content()
+                        .and_then(|bytes| write_file(&path, &bytes, doc.network && safe));
+                    match written {
+                        Ok(()) => {
+                            doc.is_modified = false;
+                            doc.mark_saved();
+                            doc.last_file_modified = std::fs::metadata(&path)
+                                .ok()
+                                .and_then(|m| m.modified().ok());
+                            saved.push(file_display_name(&path));
+                        }
+                        Err(e) => failed.push(format!("{} ({e})", file_display_name(&path))),
+                    }
+                }
+            }
+        }
+        if !saved.is_empty() {
+            self.notify(
+                Severity::Info,
+                format!("Enregistrement automatique : {}", saved.join(", ")),
+            );
+        }
+        if !failed.is_empty() {
+            self.notify(
+                Severity::Error,
+                format!("Échec de l'enregistrement automatique : {}", failed.join(", ")),
+            );
+        }
+    }
+
+    fn file_properties(&self) -> FileProperties {
+        let doc = self.active_doc();
+        let path = doc.file_path.as_deref().map(paths::normalize);
+        let meta = path.as_deref().and_then(|p| std::fs::metadata(p).ok());
+        FileProperties {
+            name: doc.title_label(),
+            target: path.as_deref().and_then(paths::symlink_target),
+            network: doc.network || path.as_deref().is_some_and(paths::is_network),
+            size: meta.as_ref().map(|m| m.len()),
+            modified: meta
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| format_local_datetime(d.as_secs())),
+            encoding: doc.encoding.name(),
+            line_ending: doc.line_ending.label(),
+            path,
+        }
+    }
+
+    fn save_to_file(&mut self, path: PathBuf) -> Task<Message> {
+        let network_safe_save = self.network_safe_save;
+        let trim = self.trim_trailing_whitespace;
+        let slow_save_ms = self.slow_save_ms;
+        let doc = self.active_doc_mut();
+        // A share known to be slow: written on a worker, and the presses of
+        // Ctrl+S during the write coalesced into one more once it is done
+        if doc.network && slow_save_ms > 0 && doc.file_path.as_ref() == Some(&path) {
+            if doc.save_in_flight.is_some() {
+                doc.save_queued = true;
+                doc.status_message = Some("Enregistrement en cours…".to_string());
+                return Task::none();
+            }
+            if doc.slow_share && doc.job.is_none() {
+                let index = self.active_tab;
+                return self.save_on_slow_share(index);
+            }
+        }
+        if doc.job.is_some() {
+            self.notify(Severity::Warning, "Une opération est déjà en cours sur ce document");
+            return Task::none();
+        }
+        if let Some(DocLock { path: locked, state: LockState::ReadOnly(owner) }) = &doc.lock {
+            if *locked == path {
+                let msg = format!("Fichier verrouillé par {} : utilisez Enregistrer sous", owner.label());
+                self.notify(Severity::Warning, msg);
+                return Task::none();
+            }
+        }
+        if doc.file_path.as_ref() != Some(&path) {
+            // The encoding was chosen in the Save As dialog
+            let settings = DocSettings {
+                charset: None,
+                ..DocSettings::resolve(&path, doc.text().as_bytes())
+            };
+            doc.apply_settings(settings);
+            if doc.archive.as_ref().is_some_and(|a| !a.matches_path(&path)) {
+                doc.archive = None;
+            }
+            doc.network = paths::is_network(&path);
+            // Annotations follow the document to its new name
+            if !doc.ink.is_empty() {
+                if let Err(e) = ink::save(&path, &doc.ink) {
+                    tracing::warn!("Annotations non copiées vers {} : {e}", path.display());
+                }
+            }
+            // A new extension picks the language again
+            doc.language_override = None;
+            doc.sniffed_language = language::sniff(&path, &doc.text());
+        }
+        let safe = doc.network && network_safe_save;
+        doc.prepare_for_save(trim);
+        let name = file_display_name(&path);
+        let bytes = match doc.encode_content() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.notify(Severity::Error, format!("Impossible d'enregistrer {name} : {e}"));
+                return Task::none();
+            }
+        };
+        if bytes.len() as u64 >= BACKGROUND_IO_THRESHOLD_BYTES {
+            return self.save_in_background(path, bytes);
+        }
+        let started = Instant::now();
+        if let Err(e) = write_file(&path, &bytes, safe) {
+            self.notify(Severity::Error, format!("Impossible d'enregistrer {name} : {e}"));
+        } else {
+            if doc.network && slow_save_ms > 0 && started.elapsed().as_millis() >= u128::from(slow_save_ms) {
+                doc.slow_share = true;
+            }
+            doc.last_file_modified =
+                std::fs::metadata(&path).ok().and_then(|m| m.modified().ok());
+            doc.file_path = Some(path.clone());
+            doc.is_modified = false;
+            doc.mark_saved();
+            doc.status_message = None;
+            self.record_note(&path);
+            self.notify(Severity::Success, format!("Enregistré : {name}"));
+        }
+        Task::none()
+    }
+
+    // Writes the encoded bytes on a worker thread; the tab stays read-only meanwhile.
+    fn save_in_background(&mut self, path: PathBuf, bytes: Vec<u8>) -> Task<Message> {
+        let name = file_display_name(&path);
+        let (id, task) = self.spawn_save(path.clone(), bytes);
+        let doc = self.active_doc_mut();
+        doc.job = Some(id);
+        doc.file_path = Some(path);
+        doc.start_save();
+        doc.status_message = Some(format!("Enregistrement : {name}…"));
+        task
+    }
+
+    // Writes a tab of a slow share on a worker thread without locking it: the
+    // text at this point is what gets saved, and the tab stays modified if
+    // typed in before the write is done
+    fn save_on_slow_share(&mut self, index: usize) -> Task<Message> {
+        let trim = self.trim_trailing_whitespace;
+        let doc = &mut self.tabs[index];
+        let Some(path) = doc.file_path.clone() else {
+            return Task::none();
+        };
+        doc.prepare_for_save(trim);
+        let bytes = match doc.encode_content() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let name = file_display_name(&path);
+                self.notify(Severity::Error, format!("Impossible d'enregistrer {name} : {e}"));
+                return Task::none();
+            }
+        };
+        let (id, task) = self.spawn_save(path, bytes);
+        let doc = &mut self.tabs[index];
+        doc.save_in_flight = Some(id);
+        doc.start_save();
+        doc.status_message = Some("Enregistrement en cours…".to_string());
+        task
+    }
+
+    // A save written on a worker; closing meanwhile would cut it short
+    fn save_running(&self) -> bool {
+        self.tabs.iter().any(|doc| doc.pending_save.is_some())
+    }
+
+    // Saves are not cancellable, and written next to the file then renamed
+    // over it, so the file on disk is never left truncated
+    fn spawn_save(&mut self, path: PathBuf, bytes: Vec<u8>) -> (JobId, Task<Message>) {
+        let name = file_display_name(&path);
+        let worker_path = path;
+        self.jobs
+            .spawn(format!("Enregistrement de {name}"), false, move |id| {
+                let worker = jobs::run_blocking(move |report| {
+                    paths::replace_via_temp(&worker_path, |temp| {
+                        jobs::write_with_progress(temp, &bytes, report)
+                    })
+                    .map_err(|e| e.to_string())
+                });
+                Task::run(worker, move |event| match event {
+                    JobEvent::Progress(p) => Message::Job(JobMsg::Progress(id, p)),
+                    JobEvent::Done(result) => Message::File(FileMsg::Saved(id, result)),
+                })
+            })
+    }
+
+    fn load_from_file(&mut self, path: PathBuf) -> Task<Message> {
+        // --- File size guard ---
+        let file_size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let file_size_mb = file_size / (1024 * 1024);
+        let name = file_display_name(&path);
+
+        if file_size_mb > FILE_SIZE_LIMIT_MB {
+            self.notify(
+                Severity::Error,
+                format!("{name} fait {file_size_mb} Mo : la limite est de {FILE_SIZE_LIMIT_MB} Mo"),
+            );
+            return Task::none();
+        }
+
+        if file_size_mb > FILE_SIZE_WARN_MB {
+            let doc_id = self.active_doc().id;
+            return Self::confirm(
+                format!(
+                    "Ce fichier fait {file_size_mb} Mo.\n\
+                     L'ouvrir peut ralentir l'application. Continuer ?"
+                ),
+                move |confirmed| Message::File(FileMsg::ConfirmLargeFile(confirmed, doc_id, path.clone())),
+            );
+        }
+        self.read_file(path, file_size)
+    }
+
+    // Past the size guard: large files are read on a worker
+    fn read_file(&mut self, path: PathBuf, file_size: u64) -> Task<Message> {
+        if file_size >= BACKGROUND_IO_THRESHOLD_BYTES {
+            return self.load_in_background(path);
+        }
+
+        // --- Read bytes + detect encoding ---
+        let name = file_display_name(&path);
+        let loaded = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| Self::decode_file(&path, bytes));
+        match loaded {
+            Ok(loaded) => self.apply_loaded_file(self.active_tab, path, loaded),
+            Err(e) => self.notify(Severity::Error, format!("Impossible d'ouvrir {name} : {e}")),
+        }
+        Task::none()
+    }
+
+    // Reads and decodes the file on a worker thread, the tab shows it once done
+    fn load_in_background(&mut self, path: PathBuf) -> Task<Message> {
+        let name = file_display_name(&path);
+        let worker_path = path.clone();
+        let (id, task) = self
+            .jobs
+            .spawn(format!("Chargement de {name}"), true, move |id| {
+                let worker = jobs::run_blocking(move |report| {
+                    match jobs::read_with_progress(&worker_path, report) {
+                        Ok(Some(bytes)) => Self::decode_file(&worker_path, bytes).map(Arc::new),
+                        Ok(None) => Err("Chargement annulé".to_string()),
+                        Err(e) => Err(e.to_string()),
+                    }
+                });
+                Task::run(worker, move |event| match event {
+                    JobEvent::Progress(p) => Message::Job(JobMsg::Progress(id, p)),
+                    JobEvent::Done(result) => Message::File(FileMsg::Loaded(id, result)),
+                })
+            });
+        let doc = self.active_doc_mut();
+        doc.job = Some(id);
+        doc.file_path = Some(path);
+        doc.status_message = Some(format!("Chargement : {name}…"));
+        task
+    }
+
+    // Last step of Save As: the encoding and line ending start as the ones of
+    // the document, or the ones EditorConfig sets for the new location
+    fn open_save_as(&mut self, path: PathBuf) {
+        let doc = self.active_doc();
+        let text = doc.text();
+        let settings = DocSettings::resolve(&path, text.as_bytes());
+        let choice = settings.charset.unwrap_or(SaveEncoding::of(doc.encoding, doc.bom));
+        let mut options = SaveEncoding::ALL.to_vec();
+        if !options.contains(&choice) {
+            options.push(choice);
+        }
+        self.save_as = Some(SaveAsDialog {
+            path,
+            choice,
+            options,
+            line_ending: settings.end_of_line.unwrap_or(doc.line_ending),
+            unmappable: unmappable_chars(&text, choice.encoding(), UNMAPPABLE_SHOWN),
+        });
+    }
+
+    // Decodes the file of the active tab again with `encoding`, keeping the
+    // cursor and scroll; unsaved edits are lost, so they are confirmed first
+    fn reopen_with_encoding(&mut self, encoding: &'static encoding_rs::Encoding) -> Task<Message> {
+        let Some(path) = self.active_doc().file_path.clone() else {
+            self.notify(Severity::Info, "Le document n'a pas encore de fichier".to_string());
+            return Task::none();
+        };
+        if self.active_doc().is_modified {
+            let doc_id = self.active_doc().id;
+            return Self::confirm_discard(
+                "Les modifications non enregistrées seront perdues. Rouvrir avec l'encodage ?",
+                move |confirmed| Message::File(FileMsg::ConfirmReopenWithEncoding(confirmed, doc_id, encoding)),
+            );
+        }
+        self.decode_again(path, encoding);
+        Task::none()
+    }
+
+    fn decode_again(&mut self, path: PathBuf, encoding: &'static encoding_rs::Encoding) {
+        let loaded = std::fs::read(&path).map_err(|e| e.to_string()).and_then(|bytes| {
+            let (archive, bytes) = match archive::unpack(&bytes)? {
+                Some((archive, inner)) => (Some(archive), inner),
+                None => (None, bytes),
+            };
+            // The chosen encoding wins over an EditorConfig or modeline charset
+            let settings = DocSettings {
+                charset: None,
+                ..DocSettings::resolve(&path, &bytes)
+            };
+            // A BOM of the chosen encoding is dropped, any other is decoded as text
+            let bom = encoding_rs::Encoding::for_bom(&bytes).is_some_and(|(e, _)| e == encoding);
+            let (text, _) = encoding.decode_with_bom_removal(&bytes);
+            Ok(LoadedFile {
+                text: text.into_owned(),
+                encoding,
+                bom,
+                settings,
+                size: bytes.len() as u64,
+                archive,
+            })
+        });
+        match loaded {
+            Ok(loaded) => {
+                let doc = self.active_doc_mut();
+                doc.externally_modified = false;
+                doc.pending_view = Some(doc.view_state());
+                self.apply_loaded_file(self.active_tab, path, loaded);
+                self.notify(Severity::Info, format!("Rouvert en {}", encoding.name()));
+            }
+            Err(e) => {
+                let name = file_display_name(&path);
+                self.notify(Severity::Error, format!("Impossible de rouvrir {name} : {e}"));
+            }
+        }
+    }
+
+    // .gz and single-file .zip archives are unpacked before decoding
+    fn decode_file(path: &Path, bytes: Vec<u8>) -> Result<LoadedFile, String> {
+        let (archive, bytes) = match archive::unpack(&bytes)? {
+            Some((archive, inner)) => (Some(archive), inner),
+            None => (None, bytes),
+        };
+        let settings = DocSettings::resolve(path, &bytes);
+        let (text, encoding) = Self::decode_bytes(&bytes, settings.charset.map(SaveEncoding::encoding));
+        Ok(LoadedFile {
+            text,
+            encoding,
+            bom: encoding_rs::Encoding::for_bom(&bytes).is_some(),
+            settings,
+            size: bytes.len() as u64,
+            archive,
+        })
+    }
+
+    fn apply_loaded_file(&mut self, index: usize, path: PathBuf, loaded: LoadedFile) {
+        let path = paths::normalize(&path);
+        let name = file_display_name(&path);
+        let network = paths::is_network(&path);
+        let language = self.metadata.language(&path);
+        let bookmarks = self.metadata.bookmarks(&path);
+        self.record_note(&path);
+        let file_size_mb = loaded.size / (1024 * 1024);
+        let content_text = loaded.text;
+        let sniffed = language::sniff(&path, &content_text);
+
+        let doc = &mut self.tabs[index];
+        doc.line_ending = LineEnding::detect(&content_text);
+        doc.encoding = loaded.encoding;
+        doc.bom = loaded.bom;
+        doc.detect_indent(&content_text);
+        doc.apply_settings(loaded.settings);
+        doc.archive = loaded.archive;
+        doc.set_text(&content_text);
+        doc.ink = ink::load(&path);
+        doc.last_file_modified = std::fs::metadata(&path).ok().and_then(|m| m.modified().ok());
+        doc.file_path = Some(path);
+        doc.network = network;
+        doc.language_override = language;
+        doc.bookmarks = bookmarks;
+        doc.sniffed_language = sniffed;
+        doc.is_modified = false;
+        doc.mark_saved();
+        doc.scroll_offset = 0.0;
+        doc.undo_stack.clear();
+        doc.redo_stack.clear();
+        doc.last_edit_time = None;
+        doc.status_message = Some(format!("Ouvert : {name}"));
+
+        // Adaptive undo for large files
+        if file_size_mb > 10 {
+            doc.max_undo = LARGE_FILE_UNDO_HISTORY;
+        } else {
+            doc.max_undo = MAX_UNDO_HISTORY;
+        }
+
+        doc.update_stats_cache();
+
+        if network {
+            let safe = if self.network_safe_save { "activé" } else { "désactivé" };
+            self.notify(
+                Severity::Warning,
+                format!(
+                    "{name} est sur un partage réseau : enregistrement automatique toutes les {} min, enregistrement sécurisé {safe} (Paramètres)",
+                    self.network_autosave_minutes
+                ),
+            );
+        }
+    }
+
+    fn decode_bytes(
+        bytes: &[u8],
+        charset: Option<&'static encoding_rs::Encoding>,
+    ) -> (String, &'static encoding_rs::Encoding) {
+        // 1. Check BOM
+        if let Some((enc, bom_len)) = encoding_rs::Encoding::for_bom(bytes) {
+            let (text, _, _) = enc.decode(&bytes[bom_len..]);
+            return (text.into_owned(), enc);
+        }
+
+        // 2. Charset forced by EditorConfig / modeline
+        if let Some(enc) = charset {
+            let (text, _) = enc.decode_without_bom_handling(bytes);
+            return (text.into_owned(), enc);
+        }
+
+        // 3. Try UTF-8
+        let (text, encoding, had_errors) = encoding_rs::UTF_8.decode(bytes);
+        if !had_errors {
+            return (text.into_owned(), encoding);
+        }
+
+        // 4. Fallback to Windows-1252 (Latin)
+        let (text, encoding, _) = encoding_rs::WINDOWS_1252.decode(bytes);
+        (text.into_owned(), encoding)
+    }
+
+    // The dialog does not report the chosen filter: a name typed without
+    // extension gets the one of the document's language
+    fn save_as(&self) -> Task<Message> {
+        let current = self.active_doc().language();
+        Task::perform(
+            async move {
+                let dialog = language::save_filters(current)
+                    .into_iter()
+                    .fold(rfd::AsyncFileDialog::new().set_title("Enregistrer sous"), |dialog, lang| {
+                        dialog.add_filter(lang.filter_name(), lang.extensions())
+                    });
+                dialog
+                    .add_filter("Tous les fichiers", &["*"])
+                    .save_file()
+                    .await
+                    .map(|handle| language::with_default_extension(handle.path().to_path_buf(), current))
+            },
+            |path| Message::File(FileMsg::SaveFileSelected(path)),
+        )
+    }
+
+    fn open_file(&self) -> Task<Message> {
+        Task::perform(
+            async {
+                rfd::AsyncFileDialog::new()
+                    .set_title("Ouvrir un fichier")
+                    .add_filter("Fichiers texte", &["txt"])
+                    .add_filter("Tous les fichiers", &["*"])
+                    .pick_file()
+                    .await
+                    .map(|handle| handle.path().to_path_buf())
+            },
+            |path| Message::File(FileMsg::OpenFileSelected(path)),
+        )
+    }
+
+    // --- Find & Replace ---
+
+    // Jumps straight to a line and character column, whatever the distance
+    fn navigate_to(&mut self, line: usize, col: usize) {
+        let doc = self.active_doc_mut();
+        doc.move_cursor(line, col);
+        doc.scroll_offset = doc.content.cursor().position.line as f32;
+    }
+
+    fn record_jump(&mut self) {
+        let point = self.active_doc().nav_point();
+        self.nav_history.record(point);
+    }
+
+    fn navigate_history(&mut self, forward: bool) {
+        let current = self.active_doc().nav_point();
+        let ids: Vec<u64> = self.tabs.iter().map(|d| d.id).collect();
+        let exists = |doc| ids.contains(&doc);
+        let target = if forward {
+            self.nav_history.forward(current, exists)
+        } else {
+            self.nav_history.back(current, exists)
+        };
+        let Some(target) = target else {
+            return;
+        };
+        if let Some(index) = self.tabs.iter().position(|d| d.id == target.doc) {
+            if index != self.active_tab {
+                self.active_tab = index;
+                self.nav_jumping = true;
+            }
+            self.navigate_to(target.line, target.column);
+        }
+    }
+
+    fn highlight_match(&mut self, byte_pos: usize, match_len: usize) {
+        self.record_jump();
+        self.show_match(byte_pos, match_len);
+    }
+
+    fn show_match(&mut self, byte_pos: usize, match_len: usize) {
+        self.find_cursor = byte_pos + match_len;
+        let doc = self.active_doc_mut();
+        doc.select_bytes(byte_pos, byte_pos + match_len);
+        doc.scroll_offset = doc.byte_to_position(byte_pos).0 as f32;
+        self.refresh_match_count(Some(byte_pos));
+    }
+
+    // Recounts the matches of the find query; `current_start` is the highlighted one
+    fn refresh_match_count(&mut self, current_start: Option<usize>) {
+        if self.find_query.is_empty() {
+            self.find_matches = None;
+            return;
+        }
+        let Some(re) = self.build_regex() else {
+            self.find_matches = None;
+            return;
+        };
+        let mut count = MatchCount {
+            current: None,
+            total: 0,
+        };
+        let mut count_in = |start: usize, text: &str| {
+            for m in re.find_iter(text) {
+                if Some(start + m.start()) == current_start {
+                    count.current = Some(count.total);
+                }
+                count.total += 1;
+            }
+        };
+        let doc = self.active_doc();
+        if let Some(range) = self.scope() {
+            count_in(range.start, &doc.text()[range]);
+            self.find_matches = Some(count);
+            return;
+        }
+        match self.search_spans() {
+            // Only the parts of a large document that may match are copied out of the rope
+            Some(spans) => {
+                for span in spans {
+                    count_in(span.start, &doc.mirror.slice_bytes(span.start, span.end));
+                }
+            }
+            None => count_in(0, &doc.text()),
+        }
+        self.find_matches = Some(count);
+    }
+
+    // Byte range of the active document's selection, None without one
+    fn selection_bytes(&self) -> Option<std::ops::Range<usize>> {
+        let doc = self.active_doc();
+        let cursor = doc.content.cursor();
+        let at = |p: text_editor::Position| doc.position_to_byte(p.line, p.column);
+        let (anchor, end) = (at(cursor.selection?), at(cursor.position));
+        (anchor != end).then(|| anchor.min(end)..anchor.max(end))
+    }
+
+    // Range searched by "Dans la sélection", within the current text
+    fn scope(&self) -> Option<std::ops::Range<usize>> {
+        let len = self.active_doc().text().len();
+        self.find_in_selection
+            .as_ref()
+            .map(|range| range.start.min(len)..range.end.min(len))
+    }
+
+    // Parts of a large document that may hold the find query, from its search
+    // index. None when the whole text has to be searched (no index, regex,
+    // multi-line query, offsets of an inexact mirror).
+    fn search_spans(&self) -> Option<Vec<Span>> {
+        if self.use_regex || !self.active_doc().mirror.is_exact() {
+            return None;
+        }
+        let needle = if self.extended_search {
+            expand_escapes(&self.find_query)
+        } else {
+            self.find_query.clone()
+        };
+        let index = self.active_doc().mirror.search_index()?;
+        index.candidates(&needle, self.case_sensitive)
+    }
+
+    fn find_in_spans(&mut self, spans: &[Span], from: usize) -> Option<(usize, usize)> {
+        let re = self.build_regex()?;
+        let mirror = &self.active_doc().mirror;
+        spans.iter().filter(|s| s.end > from).find_map(|span| {
+            let start = from.max(span.start);
+            let text = mirror.slice_bytes(start, span.end);
+            re.find(&text).map(|m| (start + m.start(), m.len()))
+        })
+    }
+
+    fn rfind_in_spans(&mut self, spans: &[Span], until: usize) -> Option<(usize, usize)> {
+        let re = self.build_regex()?;
+        let mirror = &self.active_doc().mirror;
+        spans.iter().rev().filter(|s| s.start < until).find_map(|span| {
+            let text = mirror.slice_bytes(span.start, until.min(span.end));
+            re.find_iter(&text)
+                .last()
+                .map(|m| (span.start + m.start(), m.len()))
+        })
+    }
+
+    fn build_regex(&mut self) -> Option<regex::Regex> {
+        // Regex syntax already understands \n, \t, \xNN
+        let pattern = if self.use_regex {
+            self.find_query.clone()
+        } else if self.extended_search {
+            regex::escape(&expand_escapes(&self.find_query))
+        } else {
+            regex::escape(&self.find_query)
+        };
+        let full = if self.case_sensitive {
+            pattern
+        } else {
+            format!("(?i){pattern}")
+        };
+        match regex::Regex::new(&full) {
+            Ok(re) => {
+                self.active_doc_mut().status_message = None;
+                Some(re)
+            }
+            Err(e) => {
+                self.active_doc_mut().status_message =
+                    Some(format!("Regex invalide : {e}"));
+                None
+            }
+        }
+    }
+
+    fn find_in(&mut self, haystack: &str, from: usize) -> Option<(usize, usize)> {
+        let re = self.build_regex()?;
+        re.find(&haystack[from..])
+            .map(|m| (from + m.start(), m.len()))
+    }
+
+    fn rfind_in(&mut self, haystack: &str, until: usize) -> Option<(usize, usize)> {
+        let re = self.build_regex()?;
+        let mut last = None;
+        for m in re.find_iter(&haystack[..until]) {
+            last = Some((m.start(), m.len()));
+        }
+        last
+    }
+
+    fn find_next(&mut self) {
+        if self.find_query.is_empty() || self.active_doc().mirror.len_bytes() == 0 {
+            return;
+        }
+        match self.next_match() {
+            Some((byte_pos, mlen)) => self.highlight_match(byte_pos, mlen),
+            None => self.refresh_match_count(None),
+        }
+    }
+
+    // First match from `find_cursor`, wrapping around
+    fn next_match(&mut self) -> Option<(usize, usize)> {
+        let scope = self.scope();
+        if let Some(spans) = self.search_spans().filter(|_| scope.is_none()) {
+            let from = self.find_cursor;
+            return self
+                .find_in_spans(&spans, from)
+                .or_else(|| self.find_in_spans(&spans, 0));
+        }
+        let text = self.active_doc().text();
+        // Searched from the start of the range and wrapped within it
+        let range = scope.unwrap_or(0..text.len());
+        let haystack = &text[range.clone()];
+
+        let search_from = self.find_cursor.saturating_sub(range.start).min(haystack.len());
+        let found = if search_from < haystack.len() {
+            self.find_in(haystack, search_from)
+        } else {
+            None
+        };
+
+        found
+            .or_else(|| self.find_in(haystack, 0))
+            .map(|(byte_pos, mlen)| (range.start + byte_pos, mlen))
+    }
+
+    // Each change of the query selects its first match after the caret the
+    // search started from
+    fn search_as_you_type(&mut self) {
+        let origin = match self.find_origin {
+            Some(origin) => origin,
+            None => {
+                let origin = self.selection_bytes().map_or_else(
+                    || {
+                        let doc = self.active_doc();
+                        let at = doc.content.cursor().position;
+                        doc.position_to_byte(at.line, at.column)
+                    },
+                    |selection| selection.start,
+                );
+                // Alt+Left comes back here
+                self.record_jump();
+                self.find_origin = Some(origin);
+                origin
+            }
+        };
+        self.find_cursor = origin;
+        let found = if self.find_query.is_empty() || self.active_doc().mirror.len_bytes() == 0 {
+            None
+        } else {
+            self.next_match()
+        };
+        match found {
+            Some((byte_pos, mlen)) => self.show_match(byte_pos, mlen),
+            None => {
+                self.active_doc_mut().select_bytes(origin, origin);
+                self.refresh_match_count(None);
+            }
+        }
+    }
+
+    fn find_previous(&mut self) {
+        if self.find_query.is_empty() || self.active_doc().mirror.len_bytes() == 0 {
+            return;
+        }
+        let search_until = self.find_cursor.saturating_sub(1);
+        let scope = self.scope();
+        if let Some(spans) = self.search_spans().filter(|_| scope.is_none()) {
+            let end = self.active_doc().mirror.len_bytes();
+            let found = if search_until > 0 {
+                self.rfind_in_spans(&spans, search_until)
+            } else {
+                None
+            };
+            match found.or_else(|| self.rfind_in_spans(&spans, end)) {
+                Some((byte_pos, mlen)) => self.highlight_match(byte_pos, mlen),
+                None => self.refresh_match_count(None),
+            }
+            return;
+        }
+        let text = self.active_doc().text();
+        let range = scope.unwrap_or(0..text.len());
+        let haystack = &text[range.clone()];
+        let search_until = search_until.saturating_sub(range.start).min(haystack.len());
+
+        let found = if search_until > 0 {
+            self.rfind_in(haystack, search_until)
+        } else {
+            None
+        };
+
+        let found = found.or_else(|| self.rfind_in(haystack, haystack.len()));
+
+        if let Some((byte_pos, mlen)) = found {
+            self.highlight_match(range.start + byte_pos, mlen);
+        } else {
+            self.refresh_match_count(None);
+        }
+    }
+
+    fn replace_one(&mut self) {
+        if self.find_query.is_empty() || self.active_doc().is_read_only() {
+            return;
+        }
+        if let Some(selected) = self.active_doc().content.selection() {
+            let is_match = if let Some(re) = self.build_regex() {
+                re.is_match(&selected)
+                    && re.find(&selected).is_some_and(|m| m.len() == selected.len())
+            } else {
+                false
+            };
+            // A match left of or past the searched range is not replaced
+            let in_scope = match (self.scope(), self.selection_bytes()) {
+                (Some(range), Some(selection)) => range.start <= selection.start && selection.end <= range.end,
+                _ => true,
+            };
+            if is_match && in_scope {
+                self.save_snapshot();
+                let replacement = self.replacement();
+                if let Some(range) = &mut self.find_in_selection {
+                    range.end = (range.end + replacement.len()).saturating_sub(selected.len());
+                }
+                let doc = self.active_doc_mut();
+                doc.perform(text_editor::Action::Edit(
+                    text_editor::Edit::Paste(Arc::new(replacement)),
+                ));
+                doc.is_modified = true;
+                doc.update_stats_cache();
+            }
+        }
+        self.find_next();
+    }
+
+    fn replacement(&self) -> String {
+        if self.extended_search {
+            expand_escapes(&self.replace_query)
+        } else {
+            self.replace_query.clone()
+        }
+    }
+
+    fn replace_all(&mut self) {
+        if self.find_query.is_empty() || self.active_doc().is_read_only() {
+            return;
+        }
+        let Some(re) = self.build_regex() else {
+            return;
+        };
+        let text = self.active_doc().text();
+        let replacement = self.replacement();
+        let range = self.scope().unwrap_or(0..text.len());
+        let replaced = re.replace_all(&text[range.clone()], replacement.as_str());
+        let new_text = format!("{}{replaced}{}", &text[..range.start], &text[range.end..]);
+        if *text != new_text {
+            self.save_snapshot();
+            let end = range.start + replaced.len();
+            let scoped = self.find_in_selection.is_some();
+            if scoped {
+                self.find_in_selection = Some(range.start..end);
+            }
+            let doc = self.active_doc_mut();
+            doc.set_text(&new_text);
+            // The range stays selected to show what was searched
+            if scoped {
+                doc.select_bytes(range.start, end);
+            }
+            doc.is_modified = true;
+            doc.update_stats_cache();
+        }
+        self.refresh_match_count(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::keyboard::key::Named;
+    use iced::keyboard::{Key, Modifiers};
+    use crate::app::{Notepad, ViewState, MAX_UNDO_HISTORY};
+    use crate::buffer::TextMirror;
+    use crate::changes::{self, LineChange};
+    use crate::app::Menu;
+    use crate::language::Language;
+
+    fn notepad_with(text: &str) -> Notepad {
+        let mut n = Notepad::test_default();
+        n.active_doc_mut().set_text(text);
+        n
+    }
+
+    // ============================
+    // TextMirror::byte_to_position
+    // ============================
+
+    #[test]
+    fn byte_pos_start_of_file() {
+        assert_eq!(TextMirror::new("hello\nworld").byte_to_position(0), (0, 0));
+    }
+
+    #[test]
+    fn byte_pos_mid_first_line() {
+        assert_eq!(TextMirror::new("hello\nworld").byte_to_position(3), (0, 3));
+    }
+
+    #[test]
+    fn byte_pos_start_second_line() {
+        assert_eq!(TextMirror::new("hello\nworld").byte_to_position(6), (1, 0));
+    }
+
+    #[test]
+    fn byte_pos_mid_second_line() {
+        assert_eq!(TextMirror::new("hello\nworld").byte_to_position(9), (1, 3));
+    }
+
+    #[test]
+    fn byte_pos_end_of_text() {
+        let text = "abc\ndef";
+        assert_eq!(TextMirror::new(text).byte_to_position(text.len()), (1, 3));
+    }
+
+    #[test]
+    fn byte_pos_multibyte_chars() {
+        let text = "café\nbar";
+        assert_eq!(TextMirror::new(text).byte_to_position(6), (1, 0));
+        assert_eq!(TextMirror::new(text).byte_to_position(3), (0, 3));
+    }
+
+    #[test]
+    fn byte_pos_three_lines() {
+        let text = "aaa\nbbb\nccc";
+        assert_eq!(TextMirror::new(text).byte_to_position(8), (2, 0));
+        assert_eq!(TextMirror::new(text).byte_to_position(10), (2, 2));
+    }
+
+    // ============================
+    // build_regex
+    // ============================
+
+    #[test]
+    fn build_regex_case_sensitive_literal() {
+        let mut n = Notepad::test_default();
+        n.find_query = "Hello".to_string();
+        n.case_sensitive = true;
+        n.use_regex = false;
+        let re = n.build_regex().unwrap();
+        assert!(re.is_match("Hello"));
+        assert!(!re.is_match("hello"));
+    }
+
+    #[test]
+    fn build_regex_case_insensitive_literal() {
+        let mut n = Notepad::test_default();
+        n.find_query = "hello".to_string();
+        n.case_sensitive = false;
+        n.use_regex = false;
+        let re = n.build_regex().unwrap();
+        assert!(re.is_match("HELLO"));
+        assert!(re.is_match("Hello"));
+        assert!(re.is_match("hello"));
+    }
+
+    #[test]
+    fn build_regex_valid_pattern() {
+        let mut n = Notepad::test_default();
+        n.find_query = r"\d+".to_string();
+        n.case_sensitive = true;
+        n.use_regex = true;
+        let re = n.build_regex().unwrap();
+        assert!(re.is_match("abc123"));
+        assert!(!re.is_match("abc"));
+    }
+
+    #[test]
+    fn build_regex_invalid_pattern() {
+        let mut n = Notepad::test_default();
+        n.find_query = "[unclosed".to_string();
+        n.use_regex = true;
+        assert!(n.build_regex().is_none());
+    }
+
+    #[test]
+    fn build_regex_case_insensitive_regex() {
+        let mut n = Notepad::test_default();
+        n.find_query = "abc".to_string();
+        n.case_sensitive = false;
+        n.use_regex = true;
+        let re = n.build_regex().unwrap();
+        assert!(re.is_match("ABC"));
+    }
+
+    #[test]
+    fn build_regex_escapes_special_chars_in_literal() {
+        let mut n = Notepad::test_default();
+        n.find_query = "a.b".to_string();
+        n.case_sensitive = true;
+        n.use_regex = false;
+        let re = n.build_regex().unwrap();
+        assert!(re.is_match("a.b"));
+        assert!(!re.is_match("axb"));
+    }
+
+    // ============================
+    // find_in / rfind_in
+    // ============================
+
+    #[test]
+    fn find_in_from_start() {
+        let mut n = notepad_with("hello world hello");
+        n.find_query = "hello".to_string();
+        n.case_sensitive = true;
+        assert_eq!(n.find_in("hello world hello", 0), Some((0, 5)));
+    }
+
+    #[test]
+    fn find_in_from_offset() {
+        let mut n = notepad_with("hello world hello");
+        n.find_query = "hello".to_string();
+        n.case_sensitive = true;
+        assert_eq!(n.find_in("hello world hello", 1), Some((12, 5)));
+    }
+
+    #[test]
+    fn find_in_no_match() {
+        let mut n = notepad_with("hello world");
+        n.find_query = "xyz".to_string();
+        n.case_sensitive = true;
+        assert_eq!(n.find_in("hello world", 0), None);
+    }
+
+    #[test]
+    fn rfind_in_last_occurrence() {
+        let mut n = notepad_with("hello world hello");
+        n.find_query = "hello".to_string();
+        n.case_sensitive = true;
+        let text = "hello world hello";
+        assert_eq!(n.rfind_in(text, text.len()), Some((12, 5)));
+    }
+
+    #[test]
+    fn find_in_case_insensitive() {
+        let mut n = notepad_with("Hello World");
+        n.find_query = "hello".to_string();
+        n.case_sensitive = false;
+        assert_eq!(n.find_in("Hello World", 0), Some((0, 5)));
+    }
+
+    // ============================
+    // find_next / find_previous
+    // ============================
+
+    #[test]
+    fn find_next_empty_query_no_crash() {
+        let mut n = notepad_with("some text");
+        n.find_query = String::new();
+        n.find_next();
+    }
+
+    #[test]
+    fn find_next_empty_text_no_crash() {
+        let mut n = notepad_with("");
+        n.find_query = "abc".to_string();
+        n.find_next();
+    }
+
+    #[test]
+    fn find_previous_empty_query_no_crash() {
+        let mut n = notepad_with("some text");
+        n.find_query = String::new();
+        n.find_previous();
+    }
+
+    #[test]
+    fn find_next_wraps_around() {
+        let mut n = notepad_with("abc def abc");
+        n.find_query = "abc".to_string();
+        n.case_sensitive = true;
+        n.find_cursor = 100;
+        n.find_next();
+        assert!(n.find_cursor > 0);
+    }
+
+    // ============================
+    // replace_all
+    // ============================
+
+    #[test]
+    fn replace_all_simple() {
+        let mut n = notepad_with("hello world hello");
+        n.find_query = "hello".to_string();
+        n.replace_query = "hi".to_string();
+        n.case_sensitive = true;
+        n.replace_all();
+        assert_eq!(n.active_doc().content.text().trim_end(), "hi world hi");
+        assert!(n.active_doc().is_modified);
+    }
+
+    #[test]
+    fn replace_all_case_insensitive() {
+        let mut n = notepad_with("Hello HELLO hello");
+        n.find_query = "hello".to_string();
+        n.replace_query = "hi".to_string();
+        n.case_sensitive = false;
+        n.replace_all();
+        assert_eq!(n.active_doc().content.text().trim_end(), "hi hi hi");
+    }
+
+    #[test]
+    fn replace_all_empty_query_no_change() {
+        let mut n = notepad_with("hello world");
+        n.find_query = String::new();
+        n.replace_query = "hi".to_string();
+        n.replace_all();
+        assert!(!n.active_doc().is_modified);
+    }
+
+    #[test]
+    fn replace_all_no_match() {
+        let mut n = notepad_with("hello world");
+        n.find_query = "xyz".to_string();
+        n.replace_query = "hi".to_string();
+        n.case_sensitive = true;
+        n.replace_all();
+        assert!(!n.active_doc().is_modified);
+    }
+
+    #[test]
+    fn find_next_reports_match_index() {
+        let mut n = notepad_with("ab ab ab");
+        n.find_query = "ab".to_string();
+        n.find_next();
+        assert_eq!(n.find_matches, Some(MatchCount { current: Some(0), total: 3 }));
+        n.find_next();
+        assert_eq!(n.find_matches.unwrap().label(), "2/3");
+        n.find_previous();
+        assert_eq!(n.find_matches.unwrap().label(), "1/3");
+    }
+
+    #[test]
+    fn find_selects_the_match_after_lf_cr_breaks() {
+        // The mirror holds each LF CR as LF, so its offsets are one byte short
+        let mut n = notepad_with("un\n\rab\n\rx ab");
+        assert!(!n.active_doc().mirror.is_exact());
+        n.find_query = "ab".to_string();
+        n.find_next();
+        n.find_next();
+        let cursor = n.active_doc().content.cursor();
+        assert_eq!(cursor.selection.map(|p| (p.line, p.column)), Some((2, 2)));
+        assert_eq!((cursor.position.line, cursor.position.column), (2, 4));
+        assert_eq!(n.find_matches, Some(MatchCount { current: Some(1), total: 2 }));
+    }
+
+    #[test]
+    fn query_change_counts_matches() {
+        let mut n = notepad_with("ab ab ab");
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("ab".to_string())));
+        assert_eq!(n.find_matches, Some(MatchCount { current: Some(0), total: 3 }));
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("zz".to_string())));
+        assert_eq!(n.find_matches.unwrap().label(), "Aucun résultat");
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged(String::new())));
+        assert_eq!(n.find_matches, None);
+    }
+
+    #[test]
+    fn typing_the_query_selects_the_next_match() {
+        let mut n = notepad_with("abc abd abe");
+        n.navigate_to(0, 2);
+        let _ = n.update(Message::Search(SearchMsg::OpenFind));
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("a".to_string())));
+        assert_eq!(n.selection_bytes(), Some(4..5));
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("abe".to_string())));
+        assert_eq!(n.selection_bytes(), Some(8..11));
+        // Searched again from where the caret was, not from the last match
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("ab".to_string())));
+        assert_eq!(n.selection_bytes(), Some(4..6));
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("abz".to_string())));
+        assert_eq!(n.selection_bytes(), None);
+        assert_eq!(n.active_doc().content.cursor().position.column, 2);
+
+        // Escape puts the caret back
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("abd".to_string())));
+        press(&mut n, Key::Named(Named::Escape), Modifiers::empty());
+        assert!(!n.show_find);
+        assert_eq!(n.selection_bytes(), None);
+        assert_eq!(n.active_doc().content.cursor().position.column, 2);
+
+        // Suivant keeps the match
+        let _ = n.update(Message::Search(SearchMsg::OpenFind));
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("abd".to_string())));
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        press(&mut n, Key::Named(Named::Escape), Modifiers::empty());
+        assert!(n.active_doc().content.cursor().position.column > 2);
+    }
+
+    #[test]
+    fn find_next_without_match_reports_none() {
+        let mut n = notepad_with("hello");
+        n.find_query = "xyz".to_string();
+        n.find_next();
+        assert_eq!(n.find_matches, Some(MatchCount { current: None, total: 0 }));
+    }
+
+    #[test]
+    fn typing_in_the_editor_can_close_the_find_bar() {
+        let mut n = notepad_with("hello");
+        let type_x = |n: &mut Notepad| {
+            let edit = text_editor::Edit::Insert('x');
+            let _ = n.update(Message::EditorAction(text_editor::Action::Edit(edit)));
+        };
+        let _ = n.update(Message::Search(SearchMsg::OpenReplace));
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("hel".to_string())));
+        type_x(&mut n);
+        assert!(n.show_find);
+
+        n.auto_hide_find = true;
+        // Moving the caret is not typing
+        let _ = n.update(Message::EditorAction(text_editor::Action::Move(text_editor::Motion::Right)));
+        assert!(n.show_find);
+        type_x(&mut n);
+        assert!(!n.show_find && !n.show_replace);
+        assert_eq!(n.find_query, "hel");
+    }
+
+    #[test]
+    fn find_in_selection_stays_in_the_range() {
+        let mut n = notepad_with("cat\ncat cat\ncat");
+        let _ = n.update(Message::Search(SearchMsg::OpenReplace));
+        let _ = n.update(Message::Search(SearchMsg::FindQueryChanged("cat".to_string())));
+        assert_eq!(n.find_matches.map(|m| m.total), Some(4));
+
+        n.active_doc_mut().select_bytes(4, 11);
+        let _ = n.update(Message::Search(SearchMsg::ToggleInSelection));
+        assert_eq!(n.find_in_selection, Some(4..11));
+        assert_eq!(n.find_matches.map(|m| m.total), Some(2));
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        assert_eq!(n.active_doc().content.selection().as_deref(), Some("cat"));
+        assert_eq!(n.selection_bytes(), Some(4..7));
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        assert_eq!(n.selection_bytes(), Some(8..11));
+        // Wraps to the start of the range, not of the document
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        assert_eq!(n.selection_bytes(), Some(4..7));
+        let _ = n.update(Message::Search(SearchMsg::FindPrevious));
+        assert_eq!(n.selection_bytes(), Some(8..11));
+
+        let _ = n.update(Message::Search(SearchMsg::ReplaceQueryChanged("chien".to_string())));
+        let _ = n.update(Message::Search(SearchMsg::ReplaceOne));
+        assert_eq!(n.active_doc().content.text(), "cat\ncat chien\ncat");
+        assert_eq!(n.find_in_selection, Some(4..13));
+        let _ = n.update(Message::Search(SearchMsg::ReplaceAll));
+        assert_eq!(n.active_doc().content.text(), "cat\nchien chien\ncat");
+        assert_eq!(n.find_in_selection, Some(4..15));
+
+        let _ = n.update(Message::Search(SearchMsg::ToggleInSelection));
+        assert_eq!(n.find_in_selection, None);
+        assert_eq!(n.find_matches.map(|m| m.total), Some(2));
+    }
+
+    #[test]
+    fn find_in_selection_needs_a_selection() {
+        let mut n = notepad_with("cat");
+        let _ = n.update(Message::Search(SearchMsg::ToggleInSelection));
+        assert_eq!(n.find_in_selection, None);
+        assert_eq!(n.toasts.iter().count(), 1);
+    }
+
+    // ============================
+    // Extended (escape sequence) mode
+    // ============================
+
+    #[test]
+    fn expand_escapes_known_sequences() {
+        assert_eq!(expand_escapes(r"a\nb\tc\r\0"), "a\nb\tc\r\0");
+        assert_eq!(expand_escapes(r"\x41\x7e"), "A~");
+        assert_eq!(expand_escapes(r"back\\slash"), "back\\slash");
+    }
+
+    #[test]
+    fn expand_escapes_keeps_unknown_sequences() {
+        assert_eq!(expand_escapes(r"\q \x4 \xZZ"), r"\q \x4 \xZZ");
+        assert_eq!(expand_escapes("trailing\\"), "trailing\\");
+        assert_eq!(expand_escapes("é\\u"), "é\\u");
+    }
+
+    #[test]
+    fn extended_find_matches_newline() {
+        let mut n = notepad_with("one\ntwo");
+        n.find_query = r"e\nt".to_string();
+        n.extended_search = true;
+        n.refresh_match_count(None);
+        assert_eq!(n.find_matches.unwrap().total, 1);
+        n.extended_search = false;
+        n.refresh_match_count(None);
+        assert_eq!(n.find_matches.unwrap().total, 0);
+    }
+
+    #[test]
+    fn extended_replace_inserts_newlines() {
+        let mut n = notepad_with("a,b,c");
+        n.find_query = ",".to_string();
+        n.replace_query = r"\n".to_string();
+        n.extended_search = true;
+        n.replace_all();
+        assert_eq!(n.active_doc().content.text(), "a\nb\nc");
+    }
+
+    // ============================
+    // Navigation history
+    // ============================
+
+    fn cursor_line(n: &Notepad) -> usize {
+        n.active_doc().content.cursor().position.line
+    }
+
+    #[test]
+    fn navigate_back_after_goto_line() {
+        let mut n = notepad_with("a\nb\nc\nd\ne");
+        n.goto_input = "4".to_string();
+        let _ = n.update(Message::Search(SearchMsg::GoToLineSubmit));
+        assert_eq!(cursor_line(&n), 3);
+        let _ = n.update(Message::Search(SearchMsg::NavigateBack));
+        assert_eq!(cursor_line(&n), 0);
+        let _ = n.update(Message::Search(SearchMsg::NavigateForward));
+        assert_eq!(cursor_line(&n), 3);
+    }
+
+    #[test]
+    fn navigate_back_across_tabs() {
+        let mut n = notepad_with("first");
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        assert_eq!(n.active_tab, 1);
+        let _ = n.update(Message::Search(SearchMsg::NavigateBack));
+        assert_eq!(n.active_tab, 0);
+        let _ = n.update(Message::Search(SearchMsg::NavigateForward));
+        assert_eq!(n.active_tab, 1);
+        // The jumps themselves are not recorded as new history entries
+        let _ = n.update(Message::Search(SearchMsg::NavigateForward));
+        assert_eq!(n.active_tab, 1);
+    }
+
+    #[test]
+    fn navigate_back_skips_closed_tabs() {
+        let mut n = notepad_with("first");
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        let _ = n.update(Message::File(FileMsg::CloseTab(1)));
+        let _ = n.update(Message::Search(SearchMsg::NavigateBack));
+        assert_eq!(n.active_tab, 0);
+    }
+
+    // ============================
+    // Edit spots
+    // ============================
+
+    #[test]
+    fn edit_spots_survive_undo() {
+        let text: Vec<String> = (0..20).map(|i| format!("ligne {i}")).collect();
+        let mut n = notepad_with(&text.join("\n"));
+        for line in [15, 2] {
+            n.navigate_to(line, 0);
+            let _ = n.update(Message::EditorAction(text_editor::Action::Edit(
+                text_editor::Edit::Insert('x'),
+            )));
+        }
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        n.navigate_to(0, 0);
+        let _ = n.update(Message::Search(SearchMsg::NextEdit));
+        assert_eq!(cursor_line(&n), 2);
+        let _ = n.update(Message::Search(SearchMsg::NextEdit));
+        assert_eq!(cursor_line(&n), 15);
+        let _ = n.update(Message::Search(SearchMsg::PreviousEdit));
+        assert_eq!(cursor_line(&n), 2);
+        // The jumps can be walked back like the other ones
+        let _ = n.update(Message::Search(SearchMsg::NavigateBack));
+        assert_eq!(cursor_line(&n), 15);
+    }
+
+    // ============================
+    // Bookmarks
+    // ============================
+
+    #[test]
+    fn bookmarks_follow_edits_and_undo() {
+        let text: Vec<String> = (0..10).map(|i| format!("ligne {i}")).collect();
+        let mut n = notepad_with(&text.join("\n"));
+        for line in [6, 3] {
+            n.navigate_to(line, 0);
+            let _ = n.update(Message::Search(SearchMsg::ToggleBookmark));
+        }
+        assert_eq!(n.active_doc().bookmarks.lines(), [3, 6]);
+        let _ = n.update(Message::Search(SearchMsg::NextBookmark));
+        assert_eq!(cursor_line(&n), 6);
+        // Past the last bookmark the search starts over from the top
+        let _ = n.update(Message::Search(SearchMsg::NextBookmark));
+        assert_eq!(cursor_line(&n), 3);
+        let _ = n.update(Message::Search(SearchMsg::PreviousBookmark));
+        assert_eq!(cursor_line(&n), 6);
+
+        // A line inserted above moves them down, undoing it moves them back
+        n.navigate_to(1, 0);
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(
+            text_editor::Edit::Enter,
+        )));
+        assert_eq!(n.active_doc().bookmarks.lines(), [4, 7]);
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().bookmarks.lines(), [3, 6]);
+        let _ = n.update(Message::Edit(EditMsg::Redo));
+        assert_eq!(n.active_doc().bookmarks.lines(), [4, 7]);
+
+        n.navigate_to(4, 0);
+        let _ = n.update(Message::Search(SearchMsg::ToggleBookmark));
+        assert_eq!(n.active_doc().bookmarks.lines(), [7]);
+        let _ = n.update(Message::Search(SearchMsg::ClearBookmarks));
+        assert!(n.active_doc().bookmarks.is_empty());
+        let _ = n.update(Message::Search(SearchMsg::NextBookmark));
+        assert_eq!(n.toasts.iter().count(), 1);
+    }
+
+    // ============================
+    // Split view
+    // ============================
+
+    fn click() -> text_editor::Action {
+        text_editor::Action::Click(iced::Point::new(0.0, 0.0))
+    }
+
+    #[test]
+    fn focusing_the_other_pane_swaps_the_active_tab() {
+        let mut n = notepad_with("premier");
+        let first = n.active_doc().id;
+        let _ = n.update(Message::View(ViewMsg::SplitVertical));
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        let second = n.active_doc().id;
+        // The new tab shows in the focused pane, the first one stays beside it
+        let _ = n.update(Message::View(ViewMsg::PaneAction(Pane::Second, click())));
+        assert_eq!(n.active_doc().id, first);
+        let split = n.split.as_ref().expect("split");
+        assert_eq!((split.focus, split.other), (Pane::Second, second));
+        let _ = n.update(Message::View(ViewMsg::PaneAction(Pane::First, click())));
+        assert_eq!(n.active_doc().id, second);
+        let _ = n.update(Message::View(ViewMsg::SplitHorizontal));
+        assert!(!n.split.as_ref().unwrap().vertical);
+        let _ = n.update(Message::View(ViewMsg::CloseSplit));
+        assert!(n.split.is_none());
+        assert_eq!(n.active_doc().id, second);
+    }
+
+    #[test]
+    fn closing_the_other_document_shows_the_active_one_twice() {
+        let mut n = notepad_with("premier");
+        let _ = n.update(Message::View(ViewMsg::SplitVertical));
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        n.remove_tab(0);
+        let active = n.active_doc().id;
+        assert_eq!(n.split.as_ref().unwrap().other, active);
+    }
+
+    // ============================
+    // push_snapshot / undo / redo
+    // ============================
+
+    #[test]
+    fn push_snapshot_respects_max_history() {
+        let mut n = Notepad::test_default();
+        for i in 0..MAX_UNDO_HISTORY + 10 {
+            n.push_snapshot(TextSnapshot {
+                text: format!("text{i}").into(),
+                cursor_line: 0,
+                cursor_col: 0,
+            });
+        }
+        assert_eq!(n.active_doc().undo_stack.len(), MAX_UNDO_HISTORY);
+    }
+
+    #[test]
+    fn undo_restores_previous_text() {
+        let mut n = notepad_with("original");
+        n.save_snapshot();
+        n.active_doc_mut().set_text("modified");
+        n.undo();
+        assert_eq!(n.active_doc().content.text().trim_end(), "original");
+    }
+
+    #[test]
+    fn redo_after_undo() {
+        let mut n = notepad_with("original");
+        n.save_snapshot();
+        n.active_doc_mut().set_text("modified");
+        n.active_doc_mut().is_modified = true;
+        n.undo();
+        assert_eq!(n.active_doc().content.text().trim_end(), "original");
+        n.redo();
+        assert_eq!(n.active_doc().content.text().trim_end(), "modified");
+    }
+
+    #[test]
+    fn undo_on_empty_stack_is_noop() {
+        let mut n = notepad_with("hello");
+        n.undo();
+        assert_eq!(n.active_doc().content.text().trim_end(), "hello");
+    }
+
+    #[test]
+    fn redo_on_empty_stack_is_noop() {
+        let mut n = notepad_with("hello");
+        n.redo();
+        assert_eq!(n.active_doc().content.text().trim_end(), "hello");
+    }
+
+    // ============================
+    // Tab operations
+    // ============================
+
+    #[test]
+    fn new_tab_adds_document() {
+        let mut n = Notepad::test_default();
+        assert_eq!(n.tabs.len(), 1);
+        n.tabs.push(Document::default());
+        n.active_tab = n.tabs.len() - 1;
+        assert_eq!(n.tabs.len(), 2);
+        assert_eq!(n.active_tab, 1);
+    }
+
+    #[test]
+    fn close_tab_removes_document() {
+        let mut n = Notepad::test_default();
+        n.tabs.push(Document::default());
+        n.tabs.push(Document::default());
+        assert_eq!(n.tabs.len(), 3);
+        n.remove_tab(1);
+        assert_eq!(n.tabs.len(), 2);
+    }
+
+    #[test]
+    fn close_last_tab_creates_new_empty() {
+        let mut n = Notepad::test_default();
+        n.active_doc_mut().is_modified = false;
+        n.remove_tab(0);
+        assert_eq!(n.tabs.len(), 1);
+        assert_eq!(n.active_tab, 0);
+        assert!(!n.active_doc().is_modified);
+    }
+
+    #[test]
+    fn switch_tab_changes_active() {
+        let mut n = Notepad::test_default();
+        n.tabs.push(Document::default());
+        n.active_tab = 0;
+        n.active_tab = 1;
+        assert_eq!(n.active_tab, 1);
+    }
+
+    #[test]
+    fn scroll_tabs_clamps_to_range() {
+        let mut n = Notepad::test_default();
+        n.tabs.push(Document::default());
+        let _ = n.handle_file(FileMsg::ScrollTabs(-3));
+        assert_eq!(n.tab_scroll, 0);
+        let _ = n.handle_file(FileMsg::ScrollTabs(5));
+        assert_eq!(n.tab_scroll, 1);
+    }
+
+    #[test]
+    fn switching_tab_reveals_it_in_strip() {
+        let mut n = Notepad::test_default();
+        n.window_width = 400.0;
+        for _ in 0..20 {
+            n.tabs.push(Document::default());
+        }
+        let _ = n.update(Message::File(FileMsg::SwitchTab(15)));
+        let start = n.tab_scroll;
+        assert!(start > 0 && start <= 15);
+        let _ = n.update(Message::File(FileMsg::SwitchTab(1)));
+        assert_eq!(n.tab_scroll, 1);
+    }
+
+    #[test]
+    fn mru_switcher_returns_to_previous_tab() {
+        let mut n = Notepad::test_default();
+        n.mru_tab_switching = true;
+        n.tabs.push(Document::default());
+        n.tabs.push(Document::default());
+        let _ = n.update(Message::File(FileMsg::SwitchTab(2)));
+        let _ = n.update(Message::File(FileMsg::SwitchTab(1)));
+        n.cycle_tab_switcher(true);
+        assert_eq!(n.tab_switcher.as_ref().map(|(_, s)| *s), Some(1));
+        n.commit_tab_switcher();
+        assert_eq!(n.active_tab, 2);
+        assert!(n.tab_switcher.is_none());
+    }
+
+    #[test]
+    fn mru_switcher_cycles_backwards() {
+        let mut n = Notepad::test_default();
+        n.tabs.push(Document::default());
+        n.tabs.push(Document::default());
+        n.cycle_tab_switcher(false);
+        assert_eq!(n.tab_switcher.as_ref().map(|(_, s)| *s), Some(2));
+    }
+
+    #[test]
+    fn close_tab_adjusts_active_index() {
+        let mut n = Notepad::test_default();
+        n.tabs.push(Document::default());
+        n.tabs.push(Document::default());
+        n.active_tab = 2;
+        n.remove_tab(0);
+        assert_eq!(n.active_tab, 1); // shifted down
+    }
+
+    // ============================
+    // reset via remove_tab
+    // ============================
+
+    #[test]
+    fn remove_tab_resets_when_last() {
+        let mut n = notepad_with("some content");
+        n.active_doc_mut().file_path = Some(PathBuf::from("/tmp/test.txt"));
+        n.active_doc_mut().is_modified = true;
+        n.remove_tab(0);
+        assert!(n.active_doc().file_path.is_none());
+        assert!(!n.active_doc().is_modified);
+        assert!(n.active_doc().undo_stack.is_empty());
+    }
+
+    // ============================
+    // decode_bytes / encoding
+    // ============================
+
+    #[test]
+    fn decode_utf8_bytes() {
+        let input = "Bonjour le monde".as_bytes();
+        let (text, enc) = Notepad::decode_bytes(input, None);
+        assert_eq!(text, "Bonjour le monde");
+        assert_eq!(enc, encoding_rs::UTF_8);
+    }
+
+    #[test]
+    fn decode_utf8_with_bom() {
+        let mut input = vec![0xEF, 0xBB, 0xBF]; // UTF-8 BOM
+        input.extend_from_slice("Hello".as_bytes());
+        let (text, enc) = Notepad::decode_bytes(&input, None);
+        assert_eq!(text, "Hello");
+        assert_eq!(enc, encoding_rs::UTF_8);
+    }
+
+    #[test]
+    fn decode_latin1_fallback() {
+        // 0xE9 = 'é' in Windows-1252, but invalid in UTF-8
+        let input = vec![0x48, 0x65, 0x6C, 0x6C, 0x6F, 0xE9];
+        let (text, enc) = Notepad::decode_bytes(&input, None);
+        assert_eq!(text, "Helloé");
+        assert_eq!(enc, encoding_rs::WINDOWS_1252);
+    }
+
+    #[test]
+    fn decode_utf16le_bom() {
+        let mut input = vec![0xFF, 0xFE]; // UTF-16LE BOM
+        input.extend_from_slice(&[0x48, 0x00, 0x69, 0x00]); // "Hi" in UTF-16LE
+        let (text, enc) = Notepad::decode_bytes(&input, None);
+        assert_eq!(text, "Hi");
+        assert_eq!(enc, encoding_rs::UTF_16LE);
+    }
+
+    #[test]
+    fn decode_uses_forced_charset_without_bom() {
+        let input = vec![0x48, 0x69, 0xE9];
+        let (text, enc) = Notepad::decode_bytes(&input, Some(encoding_rs::WINDOWS_1252));
+        assert_eq!(text, "Hié");
+        assert_eq!(enc, encoding_rs::WINDOWS_1252);
+    }
+
+    #[test]
+    fn decode_bom_wins_over_forced_charset() {
+        let mut input = vec![0xEF, 0xBB, 0xBF];
+        input.extend_from_slice("é".as_bytes());
+        let (text, enc) = Notepad::decode_bytes(&input, Some(encoding_rs::WINDOWS_1252));
+        assert_eq!(text, "é");
+        assert_eq!(enc, encoding_rs::UTF_8);
+    }
+
+    // Random bytes leaning on what breaks decoders and line splitting: BOMs,
+    // CR and LF in every order, NUL, lone UTF-8 lead and continuation bytes
+    fn fuzz_bytes(seed: u64, len: usize) -> Vec<u8> {
+        const TRICKY: &[u8] = &[b'\r', b'\n', 0, 0xEF, 0xBB, 0xBF, 0xFF, 0xFE, 0xC3, 0xA9, 0x80, b' '];
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                if state.is_multiple_of(3) { TRICKY[(state >> 8) as usize % TRICKY.len()] } else { (state >> 16) as u8 }
+            })
+            .collect()
+    }
+
+    // Any bytes load: the editor and its mirror agree on the lines and byte
+    // offsets map to positions and back. NOTEPAD_FUZZ_RUNS runs longer
+    #[test]
+    fn random_bytes_decode_and_map_positions() {
+        let runs = std::env::var("NOTEPAD_FUZZ_RUNS").ok().and_then(|n| n.parse().ok()).unwrap_or(64);
+        let charsets = [None, Some(encoding_rs::UTF_16BE), Some(encoding_rs::SHIFT_JIS)];
+        let mut n = Notepad::test_default();
+        for seed in 0..runs {
+            let bytes = fuzz_bytes(seed, (seed % 200) as usize);
+            let (text, _) = Notepad::decode_bytes(&bytes, charsets[seed as usize % charsets.len()]);
+            n.active_doc_mut().set_text(&text);
+            let doc = n.active_doc();
+            assert_eq!(doc.mirror.line_count(), doc.content.line_count(), "seed {seed}");
+            assert_eq!(*doc.text(), doc.content.text(), "seed {seed}");
+            let len = doc.mirror.len_bytes();
+            for byte in [0, len / 3, len / 2, len, len + 7] {
+                let (line, column) = doc.mirror.byte_to_position(byte);
+                assert!(line < doc.content.line_count(), "seed {seed}");
+                assert_eq!(doc.mirror.position_to_byte(line, column), byte.min(len), "seed {seed}");
+            }
+            // Edits keep the two in step
+            n.navigate_to(doc.content.line_count() / 2, 0);
+            let _ = n.update(Message::EditorAction(text_editor::Action::Edit(text_editor::Edit::Enter)));
+            let _ = n.update(Message::EditorAction(text_editor::Action::Edit(text_editor::Edit::Backspace)));
+            let doc = n.active_doc();
+            assert_eq!(doc.mirror.line_count(), doc.content.line_count(), "seed {seed}");
+            assert_eq!(*doc.text(), doc.content.text(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn pathological_documents_load() {
+        // A million empty lines behind a BOM
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend(std::iter::repeat_n(b'\n', 1_000_000));
+        let (text, enc) = Notepad::decode_bytes(&bytes, None);
+        assert_eq!(enc, encoding_rs::UTF_8);
+        let mirror = TextMirror::new(&text);
+        assert_eq!(mirror.line_count(), 1_000_001);
+        assert_eq!(mirror.byte_to_position(text.len()), (1_000_000, 0));
+
+        // Classic Mac endings, NUL bytes and a stray BOM inside the text
+        let (text, _) = Notepad::decode_bytes(b"un\rdeux\0\rtrois\xEF\xBB\xBF", None);
+        let n = notepad_with(&text);
+        assert_eq!(n.active_doc().mirror.line_count(), 3);
+        assert_eq!(LineEnding::detect(&text), LineEnding::Cr);
+
+        // One long line of invalid UTF-8 falls back to a single-byte encoding
+        let bytes = vec![0xE9; 2_000_000];
+        let (text, enc) = Notepad::decode_bytes(&bytes, None);
+        assert_eq!(enc, encoding_rs::WINDOWS_1252);
+        let mirror = TextMirror::new(&text);
+        assert_eq!(mirror.line_count(), 1);
+        assert_eq!(mirror.byte_to_position(text.len()), (0, text.len()));
+    }
+
+    // ============================
+    // Indentation / save overrides
+    // ============================
+
+    #[test]
+    fn insert_tab_uses_spaces_override() {
+        let mut n = notepad_with("");
+        n.active_doc_mut().apply_settings(DocSettings {
+            indent_style: Some(crate::app::IndentStyle::Space),
+            indent_size: Some(2),
+            ..DocSettings::default()
+        });
+        let _ = n.handle_edit(EditMsg::InsertTab);
+        assert_eq!(n.active_doc().content.text(), "  ");
+        assert!(n.active_doc().is_modified);
+    }
+
+    #[test]
+    fn insert_tab_defaults_to_tab_character() {
+        let mut n = notepad_with("");
+        let _ = n.handle_edit(EditMsg::InsertTab);
+        assert_eq!(n.active_doc().content.text(), "\t");
+    }
+
+    #[test]
+    fn set_indent_changes_active_document() {
+        let mut n = Notepad::test_default();
+        let _ = n.handle_format(FormatMsg::SetIndent(crate::app::IndentStyle::Space, 8));
+        assert_eq!(n.active_doc().indent_unit(), " ".repeat(8));
+    }
+
+    #[test]
+    fn prepare_for_save_applies_overrides() {
+        let mut n = notepad_with("a  \nb");
+        n.active_doc_mut().settings = DocSettings {
+            trim_trailing_whitespace: Some(true),
+            insert_final_newline: Some(true),
+            ..DocSettings::default()
+        };
+        n.active_doc_mut().prepare_for_save(false);
+        assert_eq!(n.active_doc().content.text(), "a\nb\n");
+    }
+
+    #[test]
+    fn trailing_whitespace_is_trimmed_on_demand_or_on_save() {
+        let mut n = notepad_with("a  \nb\t\nc");
+        n.navigate_to(0, 3);
+        let _ = n.update(Message::Edit(EditMsg::TrimTrailingWhitespace));
+        let doc = n.active_doc();
+        assert_eq!(doc.content.text(), "a\nb\nc");
+        assert_eq!(doc.content.cursor().position, text_editor::Position { line: 0, column: 1 });
+        assert!(doc.is_modified);
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "a  \nb\t\nc");
+        // Nothing to remove: the history is left alone
+        let mut clean = notepad_with("a\nb");
+        let _ = clean.update(Message::Edit(EditMsg::TrimTrailingWhitespace));
+        assert!(clean.active_doc().undo_stack.is_empty());
+
+        // The preference trims on save unless the file's settings keep the spaces
+        n.active_doc_mut().prepare_for_save(true);
+        assert_eq!(n.active_doc().content.text(), "a\nb\nc");
+        let mut kept = notepad_with("a  ");
+        kept.active_doc_mut().settings.trim_trailing_whitespace = Some(false);
+        kept.active_doc_mut().prepare_for_save(true);
+        assert_eq!(kept.active_doc().content.text(), "a  ");
+    }
+
+    #[test]
+    fn tab_settings_indent_new_documents_and_convert_tabs() {
+        let mut n = notepad_with("\tif a {\n\t\tb(x,\ty);\n\t}");
+        n.navigate_to(1, 2);
+        let _ = n.update(Message::Settings(SettingsMsg::SetTabWidth(2)));
+        let _ = n.update(Message::Edit(EditMsg::TabsToSpaces));
+        let doc = n.active_doc();
+        assert_eq!(doc.content.text(), "  if a {\n    b(x,  y);\n  }");
+        assert_eq!(doc.indent_style, IndentStyle::Space);
+        assert_eq!(doc.content.cursor().position.line, 1);
+        let _ = n.update(Message::Edit(EditMsg::SpacesToTabs));
+        // Only the indentation goes back to tabs
+        assert_eq!(n.active_doc().content.text(), "\tif a {\n\t\tb(x,  y);\n\t}");
+        assert_eq!(n.active_doc().indent_unit(), "\t");
+        // One undo step each
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "\tif a {\n\t\tb(x,\ty);\n\t}");
+
+        // The Tab key of a new document follows the settings
+        let _ = n.update(Message::Settings(SettingsMsg::SetInsertSpaces(true)));
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        let _ = n.update(Message::Edit(EditMsg::InsertTab));
+        assert_eq!(n.active_doc().content.text(), "  ");
+    }
+
+    #[test]
+    fn push_snapshot_respects_adaptive_max_undo() {
+        let mut n = Notepad::test_default();
+        n.active_doc_mut().max_undo = LARGE_FILE_UNDO_HISTORY;
+        for i in 0..LARGE_FILE_UNDO_HISTORY + 10 {
+            n.push_snapshot(TextSnapshot {
+                text: format!("text{i}").into(),
+                cursor_line: 0,
+                cursor_col: 0,
+            });
+        }
+        assert_eq!(n.active_doc().undo_stack.len(), LARGE_FILE_UNDO_HISTORY);
+    }
+
+    #[test]
+    fn default_document_encoding_is_utf8() {
+        let doc = Document::default();
+        assert_eq!(doc.encoding, encoding_rs::UTF_8);
+        assert_eq!(doc.max_undo, MAX_UNDO_HISTORY);
+    }
+
+    // ============================
+    // Background jobs
+    // ============================
+
+    fn start_fake_load(n: &mut Notepad) -> crate::jobs::JobId {
+        let (id, _) = n.jobs.spawn("Chargement", true, |_| Task::<Message>::none());
+        let doc = n.active_doc_mut();
+        doc.job = Some(id);
+        doc.file_path = Some(PathBuf::from("/tmp/big.txt"));
+        id
+    }
+
+    #[test]
+    fn loaded_job_fills_its_tab() {
+        let mut n = Notepad::test_default();
+        let id = start_fake_load(&mut n);
+        let loaded = LoadedFile {
+            text: "hello\r\nworld".to_string(),
+            encoding: encoding_rs::UTF_8,
+            bom: false,
+            settings: DocSettings::default(),
+            size: 12,
+            archive: None,
+        };
+        let _ = n.update(Message::File(FileMsg::Loaded(id, Ok(Arc::new(loaded)))));
+        let doc = n.active_doc();
+        assert_eq!(doc.job, None);
+        assert_eq!(doc.content.text(), "hello\r\nworld");
+        assert_eq!(doc.line_ending, LineEnding::CrLf);
+        assert!(!doc.is_modified);
+        assert_eq!(n.jobs.len(), 0);
+    }
+
+    #[test]
+    fn cancelled_load_resets_tab() {
+        let mut n = Notepad::test_default();
+        let id = start_fake_load(&mut n);
+        let _ = n.update(Message::Job(JobMsg::Cancel(id)));
+        assert_eq!(n.active_doc().job, None);
+        assert_eq!(n.active_doc().file_path, None);
+        assert_eq!(n.jobs.len(), 0);
+    }
+
+    #[test]
+    fn edits_are_ignored_while_job_runs() {
+        let mut n = Notepad::test_default();
+        start_fake_load(&mut n);
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(
+            text_editor::Edit::Insert('x'),
+        )));
+        assert_eq!(n.active_doc().content.text(), "");
+        assert!(!n.active_doc().is_modified);
+    }
+
+    #[test]
+    fn closing_tab_cancels_its_load() {
+        let mut n = Notepad::test_default();
+        n.tabs.push(Document::default());
+        n.active_tab = 1;
+        let id = start_fake_load(&mut n);
+        let _ = n.update(Message::File(FileMsg::CloseTab(1)));
+        assert_eq!(n.tabs.len(), 1);
+        // A late result for the closed tab is dropped
+        let _ = n.update(Message::File(FileMsg::Loaded(id, Err("late".to_string()))));
+        assert_eq!(n.jobs.len(), 0);
+        assert_eq!(n.active_doc().file_path, None);
+    }
+
+    // ============================
+    // Notifications
+    // ============================
+
+    #[test]
+    fn autosave_reports_with_toast() {
+        let path = std::env::temp_dir().join(format!("notepad_autosave_{}.txt", std::process::id()));
+        let mut n = notepad_with("draft");
+        n.active_doc_mut().file_path = Some(path.clone());
+        n.active_doc_mut().is_modified = true;
+        let _ = n.update(Message::File(FileMsg::AutoSave));
+        std::fs::remove_file(&path).ok();
+        let toast = n.toasts.iter().next().expect("toast");
+        assert_eq!(toast.severity, Severity::Info);
+        assert!(toast.message.contains("notepad_autosave_"));
+        assert!(!n.active_doc().is_modified);
+        assert_eq!(n.active_doc().status_message, None);
+    }
+
+    #[test]
+    fn save_failure_shows_error_toast() {
+        let mut n = notepad_with("text");
+        n.active_doc_mut().is_modified = true;
+        let _ = n.save_to_file(PathBuf::from("/nonexistent-dir/sub/file.txt"));
+        let toast = n.toasts.iter().next().expect("toast");
+        assert_eq!(toast.severity, Severity::Error);
+        assert!(n.active_doc().is_modified);
+    }
+
+    #[test]
+    fn open_failure_shows_error_toast() {
+        let mut n = Notepad::test_default();
+        let _ = n.load_from_file(PathBuf::from("/nonexistent-dir/absent.txt"));
+        let toast = n.toasts.iter().next().expect("toast");
+        assert_eq!(toast.severity, Severity::Error);
+        assert!(toast.message.contains("absent.txt"));
+        assert!(n.active_doc().file_path.is_none());
+    }
+
+    #[test]
+    fn background_save_holds_the_close_until_written() {
+        let path = std::env::temp_dir().join(format!("notepad_background_save_{}.txt", std::process::id()));
+        let mut n = notepad_with("texte");
+        n.active_doc_mut().is_modified = true;
+        let _ = n.save_in_background(path.clone(), b"texte".to_vec());
+        let id = n.active_doc().job.expect("written on a worker");
+        assert!(n.active_doc().is_modified);
+        let _ = n.update(Message::File(FileMsg::CloseRequested(iced::window::Id::unique())));
+        assert_eq!(n.toasts.iter().next().unwrap().severity, Severity::Warning);
+
+        let _ = n.update(Message::File(FileMsg::Saved(id, Ok(()))));
+        assert!(!n.active_doc().is_modified && n.active_doc().pending_save.is_none());
+        assert!(!n.save_running());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn dismiss_toast_message() {
+        let mut n = Notepad::test_default();
+        n.notify(Severity::Success, "ok");
+        let id = n.toasts.iter().next().unwrap().id;
+        let _ = n.update(Message::Toast(ToastMsg::Dismiss(id)));
+        assert!(n.toasts.is_empty());
+    }
+
+    #[test]
+    fn job_panel_toggles() {
+        let mut n = Notepad::test_default();
+        let _ = n.update(Message::Job(JobMsg::TogglePanel));
+        assert!(n.show_jobs);
+        let _ = n.update(Message::Job(JobMsg::TogglePanel));
+        assert!(!n.show_jobs);
+    }
+
+    // ============================
+    // View state restore
+    // ============================
+
+    #[test]
+    fn reload_keeps_cursor_position() {
+        let path = std::env::temp_dir().join(format!("notepad_reload_{}.txt", std::process::id()));
+        std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+        let mut n = Notepad::test_default();
+        let _ = n.load_from_file(path.clone());
+        n.active_doc_mut().move_cursor(1, 2);
+        std::fs::write(&path, "one\ntwo!\nthree\nfour\n").unwrap();
+        let _ = n.update(Message::File(FileMsg::ReloadFile(0)));
+        let _ = n.update(Message::View(ViewMsg::RestoreView));
+        let _ = std::fs::remove_file(&path);
+        let doc = n.active_doc();
+        assert_eq!(doc.content.text(), "one\ntwo!\nthree\nfour\n");
+        assert_eq!(doc.content.cursor().position.line, 1);
+        assert_eq!(doc.content.cursor().position.column, 2);
+        assert!(doc.pending_view.is_none());
+    }
+
+    #[test]
+    fn restore_view_clamps_to_document() {
+        let mut n = notepad_with("héllo\nab");
+        n.active_doc_mut().pending_view = Some(ViewState {
+            line: 0,
+            column: 3,
+            scroll: 50.0,
+        });
+        let _ = n.update(Message::View(ViewMsg::RestoreView));
+        let doc = n.active_doc();
+        // Column is in characters: "hél" is 4 bytes
+        assert_eq!(doc.content.cursor().position.column, 4);
+        assert_eq!(doc.scroll_offset, 1.0);
+
+        n.active_doc_mut().pending_view = Some(ViewState {
+            line: 9,
+            column: 9,
+            scroll: 0.0,
+        });
+        let _ = n.update(Message::View(ViewMsg::RestoreView));
+        let position = n.active_doc().content.cursor().position;
+        assert_eq!((position.line, position.column), (1, 2));
+    }
+
+    #[test]
+    fn session_restores_view_state() {
+        let mut n = Notepad::test_default();
+        let session = SessionData {
+            tabs: vec![SessionTab {
+                file_path: None,
+                unsaved_content: Some("a\nbcd\ne".to_string()),
+                is_modified: true,
+                cursor_line: 1,
+                cursor_column: 2,
+                scroll_offset: 0.0,
+            }],
+            active_tab: 0,
+        };
+        n.restore_session_data(&session, Vec::new());
+        assert!(n.active_doc().pending_view.is_some());
+        let _ = n.update(Message::View(ViewMsg::RestoreView));
+        let position = n.active_doc().content.cursor().position;
+        assert_eq!((position.line, position.column), (1, 2));
+        assert_eq!(n.active_doc().view_state().column, 2);
+    }
+
+    #[test]
+    fn open_places_cursor_at_start() {
+        let path = std::env::temp_dir().join(format!("notepad_open_start_{}.txt", std::process::id()));
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let mut n = Notepad::test_default();
+        let _ = n.open_dropped_file(path.clone());
+        let _ = std::fs::remove_file(&path);
+        let position = n.active_doc().content.cursor().position;
+        assert_eq!((position.line, position.column), (0, 0));
+        assert!(n.active_doc().pending_view.is_none());
+    }
+
+    #[test]
+    fn open_restores_remembered_position() {
+        let path = std::env::temp_dir().join(format!("notepad_open_last_{}.txt", std::process::id()));
+        std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+        let mut n = Notepad::test_default();
+        n.remember_cursor_position = true;
+        let caret = Caret {
+            line: 2,
+            column: 1,
+            scroll: 0.0,
+        };
+        n.metadata.update(&path, |meta| meta.caret = Some(caret));
+        let _ = n.open_dropped_file(path.clone());
+        let _ = n.update(Message::View(ViewMsg::RestoreView));
+        let _ = std::fs::remove_file(&path);
+        let position = n.active_doc().content.cursor().position;
+        assert_eq!((position.line, position.column), (2, 1));
+    }
+
+    // ============================
+    // Merge with disk version
+    // ============================
+
+    fn conflicting_notepad(name: &str) -> (Notepad, PathBuf) {
+        let path = std::env::temp_dir().join(format!("notepad_merge_{name}_{}.txt", std::process::id()));
+        std::fs::write(&path, "a\nb\nc\n").unwrap();
+        let mut n = Notepad::test_default();
+        let _ = n.open_dropped_file(path.clone());
+        let doc = n.active_doc_mut();
+        doc.set_text("a local\nb\nc\n");
+        doc.is_modified = true;
+        doc.externally_modified = true;
+        std::fs::write(&path, "a\nb\nc disk\n").unwrap();
+        (n, path)
+    }
+
+    #[test]
+    fn merge_keeps_chosen_hunks() {
+        let (mut n, path) = conflicting_notepad("hunks");
+        let _ = n.update(Message::Merge(MergeMsg::Open(0)));
+        assert_eq!(n.merge.as_ref().unwrap().merge.hunk_count(), 2);
+        let _ = n.update(Message::Merge(MergeMsg::Choose(1, HunkChoice::Disk)));
+        let _ = n.update(Message::Merge(MergeMsg::Apply));
+        let _ = std::fs::remove_file(&path);
+        let doc = n.active_doc();
+        assert!(n.merge.is_none());
+        assert_eq!(doc.content.text(), "a local\nb\nc disk\n");
+        assert!(doc.is_modified);
+        assert!(!doc.externally_modified);
+        // The merge can be undone
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "a local\nb\nc\n");
+    }
+
+    #[test]
+    fn merge_all_from_disk_is_not_modified() {
+        let (mut n, path) = conflicting_notepad("disk");
+        let _ = n.update(Message::Merge(MergeMsg::Open(0)));
+        let _ = n.update(Message::Merge(MergeMsg::ChooseAll(HunkChoice::Disk)));
+        let _ = n.update(Message::Merge(MergeMsg::Apply));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(n.active_doc().content.text(), "a\nb\nc disk\n");
+        assert!(!n.active_doc().is_modified);
+    }
+
+    #[test]
+    fn merge_cancel_keeps_banner() {
+        let (mut n, path) = conflicting_notepad("cancel");
+        let _ = n.update(Message::Merge(MergeMsg::Open(0)));
+        let _ = n.update(Message::Merge(MergeMsg::Cancel));
+        let _ = std::fs::remove_file(&path);
+        assert!(n.merge.is_none());
+        assert!(n.active_doc().externally_modified);
+        assert_eq!(n.active_doc().content.text(), "a local\nb\nc\n");
+    }
+
+    // ============================
+    // File watcher
+    // ============================
+
+    #[test]
+    fn watcher_event_flags_only_the_changed_file() {
+        let path = std::env::temp_dir().join(format!("notepad_watch_{}.txt", std::process::id()));
+        std::fs::write(&path, "a\n").unwrap();
+        let mut n = Notepad::test_default();
+        let _ = n.open_dropped_file(path.clone());
+        n.active_doc_mut().last_file_modified = Some(std::time::SystemTime::UNIX_EPOCH);
+        let _ = n.update(Message::File(FileMsg::ChangedOnDisk(path.with_extension("tmp"))));
+        assert!(!n.active_doc().externally_modified);
+        let _ = n.update(Message::File(FileMsg::ChangedOnDisk(path.clone())));
+        assert!(n.active_doc().externally_modified);
+        let _ = n.update(Message::File(FileMsg::IgnoreExternalChange(0)));
+        let _ = std::fs::remove_file(&path);
+        assert!(!n.active_doc().externally_modified);
+    }
+
+    // ============================
+    // Document locking
+    // ============================
+
+    fn locking_notepad(name: &str) -> (Notepad, PathBuf) {
+        let path = std::env::temp_dir().join(format!("notepad_locked_{name}_{}.txt", std::process::id()));
+        std::fs::write(&path, "text").unwrap();
+        let mut n = Notepad::test_default();
+        n.lock_documents = true;
+        (n, path)
+    }
+
+    #[test]
+    fn open_takes_lock_and_close_releases_it() {
+        let (mut n, path) = locking_notepad("own");
+        let _ = n.update(Message::File(FileMsg::OpenFileSelected(Some(path.clone()))));
+        assert_eq!(n.active_doc().lock.as_ref().unwrap().state, LockState::Held);
+        assert!(lock::lock_path(&path).exists());
+        let _ = n.update(Message::File(FileMsg::CloseTab(0)));
+        assert!(!lock::lock_path(&path).exists());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn foreign_lock_makes_tab_read_only() {
+        let (mut n, path) = locking_notepad("foreign");
+        let owner = r#"{"user":"quelqu'un","host":"ailleurs","pid":1}"#;
+        std::fs::write(lock::lock_path(&path), owner).unwrap();
+        let _ = n.update(Message::File(FileMsg::OpenFileSelected(Some(path.clone()))));
+        assert!(n.active_doc().is_read_only());
+        let _ = n.update(Message::Edit(EditMsg::InsertTab));
+        assert_eq!(n.active_doc().content.text(), "text");
+        assert_eq!(n.toasts.iter().next().unwrap().severity, Severity::Warning);
+
+        let _ = n.update(Message::File(FileMsg::StealLock(0)));
+        assert!(!n.active_doc().is_read_only());
+        let _ = n.update(Message::Edit(EditMsg::InsertTab));
+        assert!(n.active_doc().is_modified);
+        n.release_locks();
+        assert!(!lock::lock_path(&path).exists());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn disabling_locking_releases_locks() {
+        let (mut n, path) = locking_notepad("disable");
+        let _ = n.update(Message::File(FileMsg::OpenFileSelected(Some(path.clone()))));
+        let _ = n.update(Message::Settings(SettingsMsg::SetLockDocuments(false)));
+        assert!(n.active_doc().lock.is_none());
+        assert!(!lock::lock_path(&path).exists());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    // ============================
+    // Change marks
+    // ============================
+
+    #[test]
+    fn change_marks_follow_edits_until_saved() {
+        let path = std::env::temp_dir().join(format!("notepad_marks_{}.txt", std::process::id()));
+        std::fs::write(&path, "un\ndeux\ntrois\n").unwrap();
+        let mut n = Notepad::test_default();
+        let _ = n.open_dropped_file(path.clone());
+        assert!(n.active_doc().line_changes.is_empty());
+
+        n.navigate_to(1, 0);
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(
+            text_editor::Edit::Insert('x'),
+        )));
+        assert_eq!(changes::at(&n.active_doc().line_changes, 1), Some(LineChange::Modified));
+        assert_eq!(changes::at(&n.active_doc().line_changes, 0), None);
+
+        let _ = n.save_to_file(path.clone());
+        assert!(n.active_doc().line_changes.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    // ============================
+    // Compressed files
+    // ============================
+
+    #[test]
+    fn gzip_file_is_recompressed_on_save() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("notepad_archive_{}.txt.gz", std::process::id()));
+        let plain = dir.join(format!("notepad_archive_{}.txt", std::process::id()));
+        let packed = archive::Archive::Gzip { level: 9, filename: None, mtime: 0 }
+            .pack(b"compressed text\n")
+            .unwrap();
+        std::fs::write(&path, packed).unwrap();
+
+        let mut n = Notepad::test_default();
+        let _ = n.open_dropped_file(path.clone());
+        assert_eq!(n.active_doc().content.text(), "compressed text\n");
+        assert_eq!(n.active_doc().archive.as_ref().unwrap().badge(), "gz");
+
+        n.active_doc_mut().set_text("edited\n");
+        let _ = n.save_to_file(path.clone());
+        let on_disk = std::fs::read(&path).unwrap();
+        let (_, inner) = archive::unpack(&on_disk).unwrap().unwrap();
+        assert_eq!(inner, b"edited\n");
+
+        // Save As to a plain extension drops the compression
+        let _ = n.save_to_file(plain.clone());
+        assert!(n.active_doc().archive.is_none());
+        assert_eq!(std::fs::read(&plain).unwrap(), b"edited\n");
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&plain);
+    }
+
+    #[test]
+    fn invalid_archive_is_reported() {
+        let path = std::env::temp_dir().join(format!("notepad_bad_{}.gz", std::process::id()));
+        std::fs::write(&path, [0x1f, 0x8b, 0x08, 0x00]).unwrap();
+        let result = Notepad::decode_file(&path, std::fs::read(&path).unwrap());
+        let _ = std::fs::remove_file(&path);
+        assert!(result.unwrap_err().contains("gzip"));
+    }
+
+    // ============================
+    // Print preview
+    // ============================
+
+    #[test]
+    fn print_preview_navigates_pages() {
+        let mut n = notepad_with(&"ligne\n".repeat(300));
+        let _ = n.update(Message::Print(PrintMsg::OpenPreview));
+        let total = n.print_preview.as_ref().unwrap().pagination.pages.len();
+        assert!(total > 1);
+        let _ = n.update(Message::Print(PrintMsg::PreviousPage));
+        assert_eq!(n.print_preview.as_ref().unwrap().page, 0);
+        for _ in 0..total + 2 {
+            let _ = n.update(Message::Print(PrintMsg::NextPage));
+        }
+        assert_eq!(n.print_preview.as_ref().unwrap().page, total - 1);
+        let _ = n.update(Message::Print(PrintMsg::ClosePreview));
+        assert!(n.print_preview.is_none());
+    }
+
+    #[test]
+    fn print_preview_follows_editor_and_keeps_margins() {
+        let mut n = notepad_with("texte");
+        n.word_wrap = false;
+        let _ = n.update(Message::Print(PrintMsg::OpenPreview));
+        let setup = n.print_preview.as_ref().unwrap().setup;
+        assert!(!setup.wrap);
+        assert_eq!(setup.font_size, n.font_size * 0.75);
+
+        let _ = n.update(Message::Print(PrintMsg::SetMargin(10.0)));
+        let _ = n.update(Message::Print(PrintMsg::ToggleFitToWidth));
+        assert!(n.print_preview.as_ref().unwrap().setup.fit_to_width);
+        let _ = n.update(Message::Print(PrintMsg::ClosePreview));
+
+        // Margins are remembered, "fit to width" is per print
+        let _ = n.update(Message::Print(PrintMsg::OpenPreview));
+        let setup = n.print_preview.as_ref().unwrap().setup;
+        assert_eq!(setup.margin_mm, 10.0);
+        assert!(!setup.fit_to_width);
+    }
+
+    #[test]
+    fn print_queue_starts_with_the_active_tab() {
+        let mut n = notepad_with("premier");
+        let first = n.active_doc().id;
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        let second = n.active_doc().id;
+        let _ = n.update(Message::Print(PrintMsg::OpenQueue));
+        assert_eq!(n.print_queue.as_ref().unwrap().docs, vec![second]);
+        let _ = n.update(Message::Print(PrintMsg::ToggleQueued(first)));
+        let _ = n.update(Message::Print(PrintMsg::ToggleQueued(second)));
+        assert_eq!(n.print_queue.as_ref().unwrap().docs, vec![first]);
+        let _ = n.update(Message::Print(PrintMsg::PrintQueued));
+        assert!(n.print_queue.is_none());
+    }
+
+    #[test]
+    fn printing_the_selection_needs_one() {
+        let mut n = notepad_with("texte");
+        let _ = n.update(Message::Print(PrintMsg::PrintSelection));
+        assert_eq!(n.toasts.iter().next().unwrap().severity, Severity::Info);
+    }
+
+    // ============================
+    // Path completion
+    // ============================
+
+    fn completion_notepad(name: &str) -> (Notepad, PathBuf) {
+        let root = std::env::temp_dir().join(format!("notepad_paths_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("scripts")).unwrap();
+        std::fs::write(root.join("scripts/build.sh"), "").unwrap();
+        std::fs::write(root.join("setup.cfg"), "").unwrap();
+        let mut n = notepad_with("run ");
+        n.active_doc_mut().file_path = Some(root.join("Makefile"));
+        n.active_doc_mut()
+            .content
+            .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+        (n, root)
+    }
+
+    fn type_text(n: &mut Notepad, s: &str) {
+        for c in s.chars() {
+            let edit = if c == '\n' { text_editor::Edit::Enter } else { text_editor::Edit::Insert(c) };
+            let _ = n.update(Message::EditorAction(text_editor::Action::Edit(edit)));
+        }
+    }
+
+    #[test]
+    fn typing_a_path_opens_completion() {
+        let (mut n, root) = completion_notepad("open");
+        type_text(&mut n, "./s");
+        let popup = n.path_completion.as_ref().expect("popup");
+        let names: Vec<&str> = popup.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["scripts", "setup.cfg"]);
+        assert_eq!(popup.prefix_len, 1);
+
+        let _ = n.update(Message::Completion(CompletionMsg::Previous));
+        assert_eq!(n.path_completion.as_ref().unwrap().selected, 1);
+        let _ = n.update(Message::Completion(CompletionMsg::Accept));
+        assert_eq!(n.active_doc().content.text(), "run ./setup.cfg");
+        assert!(n.path_completion.is_none());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn accepting_a_directory_lists_its_content() {
+        let (mut n, root) = completion_notepad("dir");
+        type_text(&mut n, "./sc");
+        let _ = n.update(Message::Completion(CompletionMsg::Accept));
+        assert_eq!(n.active_doc().content.text(), "run ./scripts/");
+        let popup = n.path_completion.as_ref().expect("popup");
+        assert_eq!(popup.entries[0].name, "build.sh");
+
+        // Undo restores the typed prefix
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "run ./sc");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn completion_closes_on_cursor_move_and_plain_text() {
+        let (mut n, root) = completion_notepad("close");
+        type_text(&mut n, "./");
+        assert!(n.path_completion.is_some());
+        let _ = n.update(Message::EditorAction(text_editor::Action::Move(
+            text_editor::Motion::Left,
+        )));
+        assert!(n.path_completion.is_none());
+        type_text(&mut n, "x y");
+        assert!(n.path_completion.is_none());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    // ============================
+    // Color picker
+    // ============================
+
+    #[test]
+    fn color_picker_rewrites_literal_and_undoes() {
+        let mut n = notepad_with("a #aabbcc b");
+        let _ = n.update(Message::Color(ColorMsg::Open(0, 2)));
+        assert_eq!(n.color_picker.as_ref().unwrap().hex_input, "#aabbcc");
+        let _ = n.update(Message::Color(ColorMsg::SetChannel(0, 255)));
+        assert_eq!(n.color_picker.as_ref().unwrap().hex_input, "#ffbbcc");
+        let _ = n.update(Message::Color(ColorMsg::Apply));
+        assert!(n.color_picker.is_none());
+        assert_eq!(n.active_doc().content.text(), "a #ffbbcc b");
+        assert!(n.active_doc().is_modified);
+
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "a #aabbcc b");
+    }
+
+    #[test]
+    fn color_picker_keeps_rgb_syntax() {
+        let mut n = notepad_with("color: rgb(0, 0, 0);");
+        let _ = n.update(Message::Color(ColorMsg::Open(0, 7)));
+        let _ = n.update(Message::Color(ColorMsg::HexChanged("#102030".into())));
+        let _ = n.update(Message::Color(ColorMsg::Apply));
+        assert_eq!(n.active_doc().content.text(), "color: rgb(16, 32, 48);");
+    }
+
+    #[test]
+    fn color_picker_warns_when_literal_changed() {
+        let mut n = notepad_with("#000000");
+        let _ = n.update(Message::Color(ColorMsg::Open(0, 0)));
+        let _ = n.update(Message::Color(ColorMsg::SetChannel(2, 255)));
+        n.active_doc_mut().set_text("#111111");
+        let _ = n.update(Message::Color(ColorMsg::Apply));
+        assert_eq!(n.active_doc().content.text(), "#111111");
+        let toast = n.toasts.iter().next().expect("toast");
+        assert_eq!(toast.severity, Severity::Warning);
+    }
+
+    // ============================
+    // Terminal panel
+    // ============================
+
+    #[test]
+    fn terminal_starts_in_document_folder() {
+        let dir = std::env::temp_dir();
+        let mut n = notepad_with("");
+        n.active_doc_mut().file_path = Some(dir.join("notes.txt"));
+        let _ = n.update(Message::Terminal(TerminalMsg::TogglePanel));
+        assert!(n.show_terminal);
+        let term = n.terminal.as_ref().expect("terminal");
+        assert_eq!(term.cwd, dir);
+
+        // Hiding the panel keeps the shell running
+        let _ = n.update(Message::Terminal(TerminalMsg::TogglePanel));
+        assert!(!n.show_terminal);
+        assert_eq!(n.terminal.as_ref().unwrap().id, 1);
+    }
+
+    #[test]
+    fn terminal_ignores_output_of_previous_session() {
+        let mut n = notepad_with("");
+        let _ = n.update(Message::Terminal(TerminalMsg::TogglePanel));
+        let _ = n.update(Message::Terminal(TerminalMsg::Restart));
+        let _ = n.update(Message::Terminal(TerminalMsg::Output(1, "old\n".into())));
+        let _ = n.update(Message::Terminal(TerminalMsg::Output(2, "new\n".into())));
+        let text = n.terminal.as_ref().unwrap().output.text();
+        assert!(!text.contains("old"));
+        assert!(text.ends_with("new\n"));
+    }
+
+    #[test]
+    fn terminal_reports_exit_and_clears_input() {
+        let mut n = notepad_with("");
+        let _ = n.update(Message::Terminal(TerminalMsg::TogglePanel));
+        let _ = n.update(Message::Terminal(TerminalMsg::Closed(1)));
+        assert!(n.terminal.as_ref().unwrap().shell.is_none());
+        let _ = n.update(Message::Terminal(TerminalMsg::InputChanged("ls".into())));
+        let _ = n.update(Message::Terminal(TerminalMsg::Submit));
+        let term = n.terminal.as_ref().unwrap();
+        assert!(term.input.is_empty());
+        assert!(term.output.text().contains("> ls\nLe terminal est arrêté"));
+    }
+
+    // ============================
+    // Markdown outline
+    // ============================
+
+    #[test]
+    fn paragraphs_can_be_walked_and_moved() {
+        let mut n = notepad_with("un\ndeux\n\ntrois\n\n\nquatre");
+        let _ = n.update(Message::Search(SearchMsg::NextParagraph));
+        assert_eq!(cursor_line(&n), 3);
+        let _ = n.update(Message::Search(SearchMsg::NextParagraph));
+        assert_eq!(cursor_line(&n), 6);
+        let _ = n.update(Message::Search(SearchMsg::PreviousParagraph));
+        assert_eq!(cursor_line(&n), 3);
+
+        // The cursor stays in the moved paragraph, the blank lines stay put
+        n.navigate_to(6, 2);
+        let _ = n.update(Message::Edit(EditMsg::MoveParagraphUp));
+        assert_eq!(n.active_doc().content.text(), "un\ndeux\n\nquatre\n\n\ntrois");
+        assert_eq!(n.active_doc().content.cursor().position, text_editor::Position { line: 3, column: 2 });
+        let _ = n.update(Message::Edit(EditMsg::MoveParagraphUp));
+        assert_eq!(n.active_doc().content.text(), "quatre\n\nun\ndeux\n\n\ntrois");
+        assert_eq!(cursor_line(&n), 0);
+        let _ = n.update(Message::Edit(EditMsg::MoveParagraphDown));
+        assert_eq!(n.active_doc().content.text(), "un\ndeux\n\nquatre\n\n\ntrois");
+        assert_eq!(cursor_line(&n), 3);
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "quatre\n\nun\ndeux\n\n\ntrois");
+    }
+
+    fn markdown_notepad(text: &str) -> Notepad {
+        let mut n = notepad_with(text);
+        let doc = n.active_doc_mut();
+        doc.file_path = Some(PathBuf::from("notes.md"));
+        doc.update_stats_cache();
+        n
+    }
+
+    #[test]
+    fn heading_keys_jump_between_headings() {
+        let mut n = markdown_notepad("# A\ntext\n## B\n```\n# code\n```\n## C");
+        let _ = n.update(Message::Search(SearchMsg::NextHeading));
+        assert_eq!(n.active_doc().content.cursor().position.line, 2);
+        let _ = n.update(Message::Search(SearchMsg::NextHeading));
+        assert_eq!(n.active_doc().content.cursor().position.line, 6);
+        let _ = n.update(Message::Search(SearchMsg::NextHeading));
+        assert_eq!(n.active_doc().content.cursor().position.line, 6);
+        let _ = n.update(Message::Search(SearchMsg::PreviousHeading));
+        assert_eq!(n.active_doc().content.cursor().position.line, 2);
     }
 
-    pub fn load_from_file_silent(&mut self, path: PathBuf) {
-        let bytes = match std::fs::read(&path) {
-            Ok(b) => b,
-            Err(_) => return,
-        };
+    #[test]
+    fn headings_follow_edits() {
+        let mut n = markdown_notepad("intro");
+        assert!(n.active_doc().cached_headings.is_empty());
+        n.active_doc_mut()
+            .content
+            .perform(text_editor::Action::Move(text_editor::Motion::DocumentStart));
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(
+            text_editor::Edit::Paste(Arc::new("# Titre\n".to_string())),
+        )));
+        assert_eq!(n.active_doc().cached_headings[0].title, "Titre");
+    }
 
-        let file_size_mb = bytes.len() as u64 / (1024 * 1024);
-        let (content_text, detected_encoding) = Self::decode_bytes(&bytes);
+    #[test]
+    fn outline_pick_jumps_and_closes() {
+        let mut n = markdown_notepad("# A\n## B\n### C");
+        let _ = n.update(Message::View(ViewMsg::ToggleOutline));
+        let _ = n.update(Message::View(ViewMsg::SetOutlineLevel(2)));
+        assert!(n.show_outline);
+        assert_eq!(n.outline_level, 2);
+        let _ = n.update(Message::Search(SearchMsg::GoToHeading(1)));
+        assert!(!n.show_outline);
+        assert_eq!(n.active_doc().content.cursor().position.line, 1);
+
+        // Another action closes the outline like the other popups
+        let _ = n.update(Message::View(ViewMsg::ToggleOutline));
+        let _ = n.update(Message::Search(SearchMsg::OpenGoTo));
+        assert!(!n.show_outline);
+    }
 
-        let doc = self.active_doc_mut();
-        doc.line_ending = LineEnding::detect(&content_text);
-        doc.encoding = detected_encoding;
-        let mut content = text_editor::Content::with_text(&content_text);
-        content.perform(text_editor::Action::Move(
-            text_editor::Motion::DocumentEnd,
-        ));
-        doc.content = content;
-        doc.last_file_modified = std::fs::metadata(&path).ok().and_then(|m| m.modified().ok());
-        doc.file_path = Some(path);
-        doc.is_modified = false;
-        doc.scroll_offset = 0.0;
-        doc.undo_stack.clear();
-        doc.redo_stack.clear();
-        doc.last_edit_time = None;
+    // ============================
+    // Markdown tables
+    // ============================
 
-        if file_size_mb > 10 {
-            doc.max_undo = LARGE_FILE_UNDO_HISTORY;
-        } else {
-            doc.max_undo = MAX_UNDO_HISTORY;
-        }
+    fn cursor_at(n: &mut Notepad, line: usize, column: usize) {
+        n.active_doc_mut().content.move_to(text_editor::Cursor {
+            position: text_editor::Position { line, column },
+            selection: None,
+        });
+    }
 
-        doc.update_stats_cache();
+    #[test]
+    fn tab_in_table_aligns_and_moves_to_next_cell() {
+        let mut n = markdown_notepad("|a|bb|\n|-|-|\n|x|y|");
+        cursor_at(&mut n, 0, 1);
+        let _ = n.update(Message::Edit(EditMsg::InsertTab));
+        assert_eq!(
+            n.active_doc().content.text(),
+            "| a   | bb  |\n| --- | --- |\n| x   | y   |"
+        );
+        assert_eq!(n.active_doc().content.cursor().position, text_editor::Position { line: 0, column: 8 });
+
+        // From the last cell, Tab skips the separator then adds a row at the end
+        let _ = n.update(Message::Edit(EditMsg::InsertTab));
+        assert_eq!(n.active_doc().content.cursor().position.line, 2);
+        cursor_at(&mut n, 2, 8);
+        let _ = n.update(Message::Edit(EditMsg::InsertTab));
+        assert_eq!(n.active_doc().content.line_count(), 4);
+        assert_eq!(n.active_doc().content.cursor().position, text_editor::Position { line: 3, column: 2 });
+
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.line_count(), 3);
     }
 
-    // --- Undo/Redo ---
+    #[test]
+    fn tab_outside_table_inserts_indent() {
+        let mut n = markdown_notepad("text");
+        let _ = n.update(Message::Edit(EditMsg::InsertTab));
+        assert_eq!(n.active_doc().content.text(), "\ttext");
+    }
 
-    fn push_snapshot(&mut self, snapshot: TextSnapshot) {
-        let doc = self.active_doc_mut();
-        doc.undo_stack.push_back(snapshot);
-        while doc.undo_stack.len() > doc.max_undo {
-            doc.undo_stack.pop_front();
-        }
+    #[test]
+    fn table_columns_and_rows_from_context_menu() {
+        let mut n = markdown_notepad("| a | b |\r\n| --- | --- |\r\n| 1 | 2 |\r\n");
+        n.active_doc_mut().line_ending = LineEnding::CrLf;
+        cursor_at(&mut n, 2, 2);
+        let _ = n.update(Message::Table(TableMsg::InsertColumn));
+        let _ = n.update(Message::Table(TableMsg::InsertRow));
+        assert_eq!(
+            n.active_doc().content.text(),
+            "| a   |     | b   |\r\n| --- | --- | --- |\r\n| 1   |     | 2   |\r\n|     |     |     |\r\n"
+        );
+        let _ = n.update(Message::Table(TableMsg::RemoveRow));
+        let _ = n.update(Message::Table(TableMsg::RemoveColumn));
+        assert_eq!(
+            n.active_doc().content.text(),
+            "| a   | b   |\r\n| --- | --- |\r\n| 1   | 2   |\r\n"
+        );
     }
 
-    fn save_snapshot(&mut self) {
-        let doc = self.active_doc_mut();
-        let pos = doc.content.cursor().position;
-            let (cursor_line, cursor_col) = (pos.line, pos.column);
-        let snapshot = TextSnapshot {
-            text: doc.content.text(),
-            cursor_line,
-            cursor_col,
-        };
-        self.push_snapshot(snapshot);
-        let doc = self.active_doc_mut();
-        doc.redo_stack.clear();
-        doc.last_edit_time = None;
+    #[test]
+    fn insert_table_below_current_line() {
+        let mut n = markdown_notepad("Intro");
+        cursor_at(&mut n, 0, 2);
+        let _ = n.update(Message::Table(TableMsg::Insert));
+        let doc = n.active_doc();
+        assert_eq!(doc.content.line_count(), 5);
+        assert_eq!(doc.content.line(1).unwrap().text, "| Colonne 1 | Colonne 2 | Colonne 3 |");
+        assert_eq!(doc.content.cursor().position, text_editor::Position { line: 1, column: 2 });
     }
 
-    fn save_snapshot_if_needed(&mut self) {
-        let now = Instant::now();
-        let doc = self.active_doc_mut();
-        let should_save = match doc.last_edit_time {
-            Some(last) => now.duration_since(last).as_millis() > UNDO_BATCH_TIMEOUT_MS,
-            None => true,
+    #[test]
+    fn saving_markdown_aligns_tables() {
+        let mut n = markdown_notepad("|a|b|\n|-|-|\n");
+        n.active_doc_mut().prepare_for_save(false);
+        assert_eq!(n.active_doc().content.text(), "| a   | b   |\n| --- | --- |\n");
+
+        let mut plain = notepad_with("|a|b|");
+        plain.active_doc_mut().prepare_for_save(false);
+        assert_eq!(plain.active_doc().content.text(), "|a|b|");
+    }
+
+    // ============================
+    // Startup
+    // ============================
+
+    fn startup_session(path: &Path) -> StartupData {
+        let tab = |file_path: Option<PathBuf>, content: Option<&str>| SessionTab {
+            file_path,
+            unsaved_content: content.map(str::to_string),
+            is_modified: content.is_some(),
+            cursor_line: 0,
+            cursor_column: 0,
+            scroll_offset: 0.0,
         };
-        if should_save {
-            let pos = doc.content.cursor().position;
-            let (cursor_line, cursor_col) = (pos.line, pos.column);
-            let snapshot = TextSnapshot {
-                text: doc.content.text(),
-                cursor_line,
-                cursor_col,
-            };
-            self.push_snapshot(snapshot);
-            self.active_doc_mut().redo_stack.clear();
+        let loaded = Notepad::decode_file(path, b"from disk".to_vec()).unwrap();
+        StartupData {
+            session: Some(SessionData {
+                tabs: vec![tab(Some(path.to_path_buf()), None), tab(None, Some("draft"))],
+                active_tab: 1,
+            }),
+            files: vec![Some(loaded), None],
+            metadata: MetadataStore::default(),
+            notes: NoteIndex::default(),
+            named_sessions: NamedSessions::default(),
+            recent_files: RecentFiles::default(),
+            keymap: Keymap::default(),
+            snippets: Vec::new(),
         }
-        self.active_doc_mut().last_edit_time = Some(now);
     }
 
-    fn undo(&mut self) {
-        let doc = self.active_doc_mut();
-        if let Some(snapshot) = doc.undo_stack.pop_back() {
-            let pos = doc.content.cursor().position;
-            let (cursor_line, cursor_col) = (pos.line, pos.column);
-            doc.redo_stack.push(TextSnapshot {
-                text: doc.content.text(),
-                cursor_line,
-                cursor_col,
-            });
-            doc.content = text_editor::Content::with_text(&snapshot.text);
-            doc.is_modified = true;
-            doc.update_stats_cache();
-            // navigate_to needs &mut self, so we drop doc first
-            let line = snapshot.cursor_line;
-            let col = snapshot.cursor_col;
-            self.navigate_to(line, col);
-        }
+    #[test]
+    fn startup_restores_preloaded_session() {
+        let path = std::env::temp_dir().join(format!("notepad_startup_{}.txt", std::process::id()));
+        std::fs::write(&path, "from disk").unwrap();
+        let mut n = Notepad::test_default();
+        assert!(n.clipboard.is_none());
+        let _ = n.update(Message::StartupLoaded(Box::new(startup_session(&path))));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(n.tabs.len(), 2);
+        assert_eq!(n.tabs[0].content.text(), "from disk");
+        assert_eq!(n.tabs[0].file_path.as_deref(), Some(path.as_path()));
+        assert_eq!(n.active_tab, 1);
+        assert!(n.active_doc().status_message.as_deref().unwrap().starts_with("Démarré en"));
     }
 
-    fn redo(&mut self) {
-        let doc = self.active_doc_mut();
-        if let Some(snapshot) = doc.redo_stack.pop() {
-            let pos = doc.content.cursor().position;
-            let (cursor_line, cursor_col) = (pos.line, pos.column);
-            doc.undo_stack.push_back(TextSnapshot {
-                text: doc.content.text(),
-                cursor_line,
-                cursor_col,
-            });
-            doc.content = text_editor::Content::with_text(&snapshot.text);
-            doc.is_modified = true;
-            doc.update_stats_cache();
-            let line = snapshot.cursor_line;
-            let col = snapshot.cursor_col;
-            self.navigate_to(line, col);
-        }
+    #[test]
+    fn startup_keeps_text_typed_while_loading() {
+        let path = std::env::temp_dir().join(format!("notepad_startup_k_{}.txt", std::process::id()));
+        std::fs::write(&path, "from disk").unwrap();
+        let mut n = notepad_with("");
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(
+            text_editor::Edit::Insert('x'),
+        )));
+        let _ = n.update(Message::StartupLoaded(Box::new(startup_session(&path))));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(n.tabs.len(), 3);
+        assert_eq!(n.tabs[0].content.text(), "x");
     }
 
+    #[test]
+    fn startup_without_session_keeps_empty_tab() {
+        let mut n = Notepad::test_default();
+        let _ = n.update(Message::StartupLoaded(Box::default()));
+        assert_eq!(n.tabs.len(), 1);
+    }
 
+    // ============================
+    // Rope mirror
+    // ============================
 
-    // --- File I/O ---
+    fn assert_mirror_in_sync(n: &Notepad) {
+        let doc = n.active_doc();
+        assert_eq!(*doc.text(), doc.content.text());
+    }
 
-    fn save_to_file(&mut self, path: PathBuf) {
-        let doc = self.active_doc_mut();
-        let bytes = doc.encode_content();
-        if let Err(e) = std::fs::write(&path, bytes) {
-            rfd::MessageDialog::new()
-                .set_title("Erreur")
-                .set_description(format!("Impossible d'enregistrer le fichier :\n{e}"))
-                .set_level(rfd::MessageLevel::Error)
-                .set_buttons(rfd::MessageButtons::Ok)
-                .show();
-        } else {
-            let name = path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("fichier")
-                .to_string();
-            doc.last_file_modified =
-                std::fs::metadata(&path).ok().and_then(|m| m.modified().ok());
-            doc.file_path = Some(path);
-            doc.is_modified = false;
-            doc.status_message = Some(format!("Enregistré : {name}"));
-        }
+    #[test]
+    fn mirror_follows_typing_undo_and_redo() {
+        let mut n = notepad_with("first line\nsecond");
+        type_text(&mut n, "a b");
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(
+            text_editor::Edit::Enter,
+        )));
+        type_text(&mut n, "c");
+        assert_mirror_in_sync(&n);
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_mirror_in_sync(&n);
+        let _ = n.update(Message::Edit(EditMsg::Redo));
+        assert_mirror_in_sync(&n);
     }
 
-    fn load_from_file(&mut self, path: PathBuf) {
-        // --- File size guard ---
-        let file_size_mb = std::fs::metadata(&path)
-            .map(|m| m.len() / (1024 * 1024))
-            .unwrap_or(0);
+    #[test]
+    fn mirror_follows_replace_all_and_updates_stats() {
+        let mut n = notepad_with("hello world\nhello again\n");
+        n.find_query = "hello".to_string();
+        n.replace_query = "bonjour le".to_string();
+        n.replace_all();
+        assert_mirror_in_sync(&n);
+        n.active_doc_mut().update_stats_cache();
+        assert_eq!(n.active_doc().cached_word_count, 6);
+    }
 
-        if file_size_mb > FILE_SIZE_LIMIT_MB {
-            rfd::MessageDialog::new()
-                .set_title("Fichier trop volumineux")
-                .set_description(format!(
-                    "Ce fichier fait {file_size_mb} Mo.\n\
-                     La limite est de {FILE_SIZE_LIMIT_MB} Mo."
-                ))
-                .set_level(rfd::MessageLevel::Error)
-                .set_buttons(rfd::MessageButtons::Ok)
-                .show();
-            return;
-        }
+    #[test]
+    fn undo_snapshot_shares_the_cached_text() {
+        let mut n = notepad_with("shared text");
+        let text = n.active_doc().text();
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(
+            text_editor::Edit::Delete,
+        )));
+        let snapshot = &n.active_doc().undo_stack.back().unwrap().text;
+        assert!(Arc::ptr_eq(snapshot, &text));
+        assert_mirror_in_sync(&n);
+    }
 
-        if file_size_mb > FILE_SIZE_WARN_MB {
-            let proceed = matches!(
-                rfd::MessageDialog::new()
-                    .set_title("Fichier volumineux")
-                    .set_description(format!(
-                        "Ce fichier fait {file_size_mb} Mo.\n\
-                         L'ouvrir peut ralentir l'application. Continuer ?"
-                    ))
-                    .set_level(rfd::MessageLevel::Warning)
-                    .set_buttons(rfd::MessageButtons::OkCancel)
-                    .show(),
-                rfd::MessageDialogResult::Ok
-            );
-            if !proceed {
-                return;
-            }
-        }
+    // ============================
+    // Search index
+    // ============================
 
-        // --- Read bytes + detect encoding ---
-        let bytes = match std::fs::read(&path) {
-            Ok(b) => b,
-            Err(e) => {
-                rfd::MessageDialog::new()
-                    .set_title("Erreur")
-                    .set_description(format!("Impossible d'ouvrir le fichier :\n{e}"))
-                    .set_level(rfd::MessageLevel::Error)
-                    .set_buttons(rfd::MessageButtons::Ok)
-                    .show();
-                return;
-            }
+    // A log spanning several index chunks, with ERROR on the given lines
+    fn big_log(errors: &[usize]) -> String {
+        (0..5_000)
+            .map(|line| {
+                let level = if errors.contains(&line) { "ERROR" } else { "INFO" };
+                format!("{line:07} {level} request served\n")
+            })
+            .collect()
+    }
+
+    // Indexes the document whatever its size; real ones need SEARCH_INDEX_MIN_BYTES
+    fn indexed_notepad(text: &str) -> Notepad {
+        let mut n = notepad_with(text);
+        let id = n.active_doc().id;
+        let (version, rope) = n.active_doc_mut().mirror.index_job(0).unwrap();
+        let index = Arc::new(SearchIndex::build(&rope));
+        let _ = n.update(Message::Search(SearchMsg::Indexed(id, version, index)));
+        assert!(n.active_doc().mirror.search_index().is_some());
+        n
+    }
+
+    #[test]
+    fn indexed_find_walks_matches_and_wraps() {
+        let mut n = indexed_notepad(&big_log(&[100, 4900]));
+        n.find_query = "error".to_string();
+        n.case_sensitive = false;
+        assert_eq!(n.search_spans().unwrap().len(), 2);
+        let current = |n: &Notepad| {
+            let count = n.find_matches.as_ref().unwrap();
+            (count.current, count.total)
         };
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        assert_eq!(n.active_doc().content.cursor().position.line, 100);
+        assert_eq!(n.active_doc().content.selection().as_deref(), Some("ERROR"));
+        assert_eq!(current(&n), (Some(0), 2));
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        assert_eq!(current(&n), (Some(1), 2));
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        assert_eq!(current(&n), (Some(0), 2));
+        let _ = n.update(Message::Search(SearchMsg::FindPrevious));
+        assert_eq!(current(&n), (Some(1), 2));
+    }
 
-        let (content_text, detected_encoding) = Self::decode_bytes(&bytes);
+    #[test]
+    fn indexed_find_sees_new_text() {
+        let mut n = indexed_notepad(&big_log(&[100]));
+        type_text(&mut n, "ERROR ");
+        assert!(n.active_doc().mirror.search_index().is_some());
+        n.find_query = "ERROR".to_string();
+        n.case_sensitive = true;
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        assert_eq!(n.find_matches.as_ref().unwrap().total, 2);
+    }
 
-        let name = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("fichier")
-            .to_string();
+    #[test]
+    fn stale_index_is_dropped() {
+        let mut n = notepad_with(&big_log(&[]));
+        let id = n.active_doc().id;
+        let (version, rope) = n.active_doc_mut().mirror.index_job(0).unwrap();
+        type_text(&mut n, "x");
+        let index = Arc::new(SearchIndex::build(&rope));
+        let _ = n.update(Message::Search(SearchMsg::Indexed(id, version, index)));
+        assert!(n.active_doc().mirror.search_index().is_none());
+    }
 
-        let doc = self.active_doc_mut();
-        doc.line_ending = LineEnding::detect(&content_text);
-        doc.encoding = detected_encoding;
-        let mut content = text_editor::Content::with_text(&content_text);
-        content.perform(text_editor::Action::Move(
-            text_editor::Motion::DocumentEnd,
-        ));
-        doc.content = content;
-        doc.last_file_modified = std::fs::metadata(&path).ok().and_then(|m| m.modified().ok());
-        doc.file_path = Some(path);
-        doc.is_modified = false;
-        doc.scroll_offset = 0.0;
-        doc.undo_stack.clear();
-        doc.redo_stack.clear();
-        doc.last_edit_time = None;
-        doc.status_message = Some(format!("Ouvert : {name}"));
+    // ============================
+    // Cursor jumps
+    // ============================
 
-        // Adaptive undo for large files
-        if file_size_mb > 10 {
-            doc.max_undo = LARGE_FILE_UNDO_HISTORY;
-        } else {
-            doc.max_undo = MAX_UNDO_HISTORY;
-        }
+    #[test]
+    fn goto_far_line_lands_on_it() {
+        let text = "x\n".repeat(50_000);
+        let mut n = notepad_with(&text);
+        let jump = |n: &mut Notepad, line: &str| {
+            n.goto_input = line.to_string();
+            let _ = n.update(Message::Search(SearchMsg::GoToLineSubmit));
+            n.active_doc().content.cursor()
+        };
+        let far = jump(&mut n, "50000");
+        assert_eq!(far.position, text_editor::Position { line: 49_999, column: 0 });
+        assert_eq!(far.selection, None);
+        assert_eq!(n.active_doc().scroll_offset, 49_999.0);
+        let back = jump(&mut n, "1");
+        assert_eq!(back.position, text_editor::Position { line: 0, column: 0 });
+        assert_eq!(back.selection, None);
+    }
 
-        doc.update_stats_cache();
+    #[test]
+    fn navigate_from_selection_lands_on_target() {
+        let mut n = notepad_with("zero\none\ntwo\nthree\nfour");
+        n.active_doc_mut().content.move_to(text_editor::Cursor {
+            position: text_editor::Position { line: 1, column: 3 },
+            selection: Some(text_editor::Position { line: 0, column: 0 }),
+        });
+        n.navigate_to(3, 2);
+        let cursor = n.active_doc().content.cursor();
+        assert_eq!(cursor.position, text_editor::Position { line: 3, column: 2 });
+        assert_eq!(cursor.selection, None);
     }
 
-    fn decode_bytes(bytes: &[u8]) -> (String, &'static encoding_rs::Encoding) {
-        // 1. Check BOM
-        if let Some((enc, bom_len)) = encoding_rs::Encoding::for_bom(bytes) {
-            let (text, _, _) = enc.decode(&bytes[bom_len..]);
-            return (text.into_owned(), enc);
-        }
+    #[test]
+    fn navigate_counts_columns_in_characters() {
+        let mut n = notepad_with("été au café");
+        n.navigate_to(0, 9);
+        let position = n.active_doc().content.cursor().position;
+        assert_eq!(position.column, "été au ca".len());
+        n.navigate_to(5, 100);
+        let position = n.active_doc().content.cursor().position;
+        assert_eq!((position.line, position.column), (0, "été au café".len()));
+    }
 
-        // 2. Try UTF-8
-        let (text, encoding, had_errors) = encoding_rs::UTF_8.decode(bytes);
-        if !had_errors {
-            return (text.into_owned(), encoding);
-        }
+    // ============================
+    // Range selection
+    // ============================
 
-        // 3. Fallback to Windows-1252 (Latin)
-        let (text, encoding, _) = encoding_rs::WINDOWS_1252.decode(bytes);
-        (text.into_owned(), encoding)
+    #[test]
+    fn find_selects_multi_kilobyte_match() {
+        let long = "b".repeat(20_000);
+        let mut n = notepad_with(&format!("start\nx{long}y\nend"));
+        n.find_query = "b+".to_string();
+        n.use_regex = true;
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        let selection = n.active_doc().content.selection().unwrap_or_default();
+        assert!(selection == long, "selected {} bytes", selection.len());
+        let cursor = n.active_doc().content.cursor();
+        assert_eq!(cursor.position, text_editor::Position { line: 1, column: 20_001 });
+        assert_eq!(cursor.selection, Some(text_editor::Position { line: 1, column: 1 }));
     }
 
-    fn save_as(&self) -> Task<Message> {
-        Task::perform(
-            async {
-                rfd::AsyncFileDialog::new()
-                    .set_title("Enregistrer sous")
-                    .add_filter("Fichiers texte", &["txt"])
-                    .add_filter("Tous les fichiers", &["*"])
-                    .save_file()
-                    .await
-                    .map(|handle| handle.path().to_path_buf())
-            },
-            |path| Message::File(FileMsg::SaveFileSelected(path)),
-        )
+    #[test]
+    fn find_selects_match_across_crlf_lines() {
+        let mut n = notepad_with("one begin\r\ntwo\r\nthree end four");
+        n.find_query = "(?s)begin.*end".to_string();
+        n.use_regex = true;
+        let _ = n.update(Message::Search(SearchMsg::FindNext));
+        let cursor = n.active_doc().content.cursor();
+        assert_eq!(cursor.position, text_editor::Position { line: 2, column: 9 });
+        assert_eq!(cursor.selection, Some(text_editor::Position { line: 0, column: 4 }));
+        assert_eq!(n.active_doc().scroll_offset, 0.0);
     }
 
-    fn open_file(&self) -> Task<Message> {
-        Task::perform(
-            async {
-                rfd::AsyncFileDialog::new()
-                    .set_title("Ouvrir un fichier")
-                    .add_filter("Fichiers texte", &["txt"])
-                    .add_filter("Tous les fichiers", &["*"])
-                    .pick_file()
-                    .await
-                    .map(|handle| handle.path().to_path_buf())
-            },
-            |path| Message::File(FileMsg::OpenFileSelected(path)),
-        )
+    #[test]
+    fn select_bytes_handles_multibyte_text() {
+        let mut n = notepad_with("café crème\nbrûlée");
+        let text = n.active_doc().text();
+        let start = text.find("crème").unwrap();
+        let end = text.find("lée").unwrap();
+        n.active_doc_mut().select_bytes(start, end);
+        assert_eq!(n.active_doc().content.selection().as_deref(), Some("crème\nbrû"));
+        n.active_doc_mut().select_bytes(end, end);
+        assert_eq!(n.active_doc().content.selection(), None);
     }
 
-    // --- Find & Replace ---
+    // ============================
+    // Hibernation
+    // ============================
 
-    fn navigate_to(&mut self, line: usize, col: usize) {
-        let doc = self.active_doc_mut();
-        let current_line = doc.content.cursor().position.line;
-        let last_line = doc.content.line_count().saturating_sub(1);
-        let target_line = line.min(last_line);
+    // Two tabs, the first one a saved file in the background with an undo step
+    fn background_tab() -> Notepad {
+        let mut n = notepad_with("première ligne\nseconde ligne");
+        n.active_doc_mut().file_path = Some(PathBuf::from("notes.txt"));
+        n.active_doc_mut().undo_stack.push_back(TextSnapshot {
+            text: "première ligne".into(),
+            cursor_line: 0,
+            cursor_col: 3,
+        });
+        n.active_doc_mut().move_cursor(1, 4);
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        n
+    }
 
-        let from_start = target_line;
-        let from_end = last_line - target_line;
-        let from_current = target_line.abs_diff(current_line);
+    #[test]
+    fn hibernated_tab_wakes_on_activation() {
+        let mut n = background_tab();
+        let _ = n.update(Message::View(ViewMsg::HibernateBackgroundTabs));
+        let path = n.tabs[0].hibernation.as_ref().unwrap().path.clone();
+        assert!(path.exists());
+        assert_eq!(n.tabs[0].content.text(), "");
+        assert!(n.tabs[0].undo_stack.is_empty());
+        assert_eq!(n.tabs[0].memory_usage().total(), 0);
+        // Session save and the like still see the text and position
+        assert_eq!(&*n.tabs[0].text(), "première ligne\nseconde ligne");
+        assert_eq!(n.tabs[0].view_state().line, 1);
+
+        let _ = n.update(Message::File(FileMsg::SwitchTab(0)));
+        let _ = n.update(Message::View(ViewMsg::RestoreView));
+        let doc = n.active_doc();
+        assert!(doc.hibernation.is_none());
+        assert!(!path.exists());
+        assert_eq!(doc.content.text(), "première ligne\nseconde ligne");
+        assert_eq!(&*doc.undo_stack[0].text, "première ligne");
+        assert_eq!(doc.content.cursor().position.line, 1);
+    }
 
-        let min_moves = from_start.min(from_end).min(from_current);
+    #[test]
+    fn modified_files_are_not_hibernated() {
+        let mut n = background_tab();
+        n.tabs[0].is_modified = true;
+        let _ = n.update(Message::View(ViewMsg::HibernateBackgroundTabs));
+        assert!(n.tabs[0].hibernation.is_none());
+        // The active tab is never unloaded
+        assert!(n.tabs[1].hibernation.is_none());
+    }
 
-        if min_moves == from_current {
-            if target_line > current_line {
-                for _ in 0..(target_line - current_line) {
-                    doc.content
-                        .perform(text_editor::Action::Move(text_editor::Motion::Down));
-                }
-            } else {
-                for _ in 0..(current_line - target_line) {
-                    doc.content
-                        .perform(text_editor::Action::Move(text_editor::Motion::Up));
-                }
-            }
-        } else if min_moves == from_start {
-            doc.content
-                .perform(text_editor::Action::Move(text_editor::Motion::DocumentStart));
-            for _ in 0..target_line {
-                doc.content
-                    .perform(text_editor::Action::Move(text_editor::Motion::Down));
-            }
-        } else {
-            doc.content
-                .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
-            for _ in 0..from_end {
-                doc.content
-                    .perform(text_editor::Action::Move(text_editor::Motion::Up));
-            }
-        }
+    #[test]
+    fn untitled_and_visible_tabs_are_not_hibernated() {
+        let mut n = background_tab();
+        n.tabs[0].file_path = None;
+        let _ = n.update(Message::View(ViewMsg::HibernateBackgroundTabs));
+        assert!(n.tabs[0].hibernation.is_none());
+
+        let mut n = background_tab();
+        n.split = Some(Split {
+            vertical: true,
+            other: n.tabs[0].id,
+            focus: Pane::First,
+        });
+        let _ = n.update(Message::View(ViewMsg::HibernateBackgroundTabs));
+        assert!(n.tabs[0].hibernation.is_none());
+    }
 
-        doc.content
-            .perform(text_editor::Action::Move(text_editor::Motion::Home));
-        for _ in 0..col {
-            doc.content
-                .perform(text_editor::Action::Move(text_editor::Motion::Right));
-        }
+    #[test]
+    fn hibernated_text_comes_from_the_file_while_unchanged() {
+        let dir = std::env::temp_dir().join(format!("notepad_hibernate_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("notes.txt");
+        std::fs::write(&file, "première ligne\nseconde ligne").unwrap();
+        let mut n = background_tab();
+        let modified = std::fs::metadata(&file).unwrap().modified().ok();
+        n.tabs[0].file_path = Some(file.clone());
+        n.tabs[0].last_file_modified = modified;
+        let _ = n.update(Message::View(ViewMsg::HibernateBackgroundTabs));
+        std::fs::write(&file, "modifié ailleurs").unwrap();
+        // Changed on disk: the copy in the hibernation file is used
+        n.tabs[0].last_file_modified = Some(std::time::SystemTime::UNIX_EPOCH);
+        assert_eq!(&*n.tabs[0].text(), "première ligne\nseconde ligne");
+
+        // Unchanged: read from the file, even without the copy
+        n.tabs[0].last_file_modified = std::fs::metadata(&file).unwrap().modified().ok();
+        std::fs::remove_file(&n.tabs[0].hibernation.as_ref().unwrap().path).unwrap();
+        assert_eq!(&*n.tabs[0].text(), "modifié ailleurs");
+        let _ = n.update(Message::File(FileMsg::SwitchTab(0)));
+        assert_eq!(n.active_doc().content.text(), "modifié ailleurs");
+        assert!(n.active_doc().hibernation.is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 
-        doc.scroll_offset = target_line as f32;
+    #[test]
+    fn closing_a_hibernated_tab_removes_its_file() {
+        let mut n = background_tab();
+        let _ = n.update(Message::View(ViewMsg::HibernateBackgroundTabs));
+        let path = n.tabs[0].hibernation.as_ref().unwrap().path.clone();
+        let _ = n.update(Message::File(FileMsg::CloseTab(0)));
+        assert!(!path.exists());
     }
 
-    fn select_chars(&mut self, count: usize) {
-        let doc = self.active_doc_mut();
-        for _ in 0..count {
-            doc.content
-                .perform(text_editor::Action::Select(text_editor::Motion::Right));
-        }
+    #[test]
+    fn memory_usage_counts_text_and_history() {
+        let n = background_tab();
+        let usage = n.tabs[0].memory_usage();
+        assert!(usage.text >= "première ligne\nseconde ligne".len());
+        assert_eq!(usage.history, "première ligne".len());
     }
 
-    fn highlight_match(&mut self, byte_pos: usize, match_len: usize, text: &str) {
-        self.find_cursor = byte_pos + match_len;
-        let (line, col) = byte_pos_to_line_col(text, byte_pos);
-        self.navigate_to(line, col);
-        let match_chars = text[byte_pos..byte_pos + match_len].chars().count();
-        self.select_chars(match_chars);
-    }
+    // ============================
+    // About / updates
+    // ============================
 
-    fn build_regex(&mut self) -> Option<regex::Regex> {
-        let pattern = if self.use_regex {
-            self.find_query.clone()
-        } else {
-            regex::escape(&self.find_query)
+    #[test]
+    fn update_check_reports_newer_release() {
+        let mut n = Notepad::test_default();
+        let _ = n.update(Message::Help(HelpMsg::CheckUpdates));
+        assert!(n.show_about);
+        assert_eq!(n.update_check, UpdateCheck::Checking);
+        let release = updates::Release {
+            version: "999.0.0".to_string(),
+            url: "https://example.org/release".to_string(),
         };
-        let full = if self.case_sensitive {
-            pattern
-        } else {
-            format!("(?i){pattern}")
+        let _ = n.update(Message::Help(HelpMsg::UpdateChecked(Ok(release.clone()))));
+        assert_eq!(n.update_check, UpdateCheck::Available(release));
+        let current = updates::Release {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            url: String::new(),
         };
-        match regex::Regex::new(&full) {
-            Ok(re) => {
-                self.active_doc_mut().status_message = None;
-                Some(re)
-            }
-            Err(e) => {
-                self.active_doc_mut().status_message =
-                    Some(format!("Regex invalide : {e}"));
-                None
+        let _ = n.update(Message::Help(HelpMsg::UpdateChecked(Ok(current))));
+        assert_eq!(n.update_check, UpdateCheck::UpToDate);
+    }
+
+    #[test]
+    fn escape_closes_about_dialog() {
+        let mut n = Notepad::test_default();
+        let _ = n.update(Message::Help(HelpMsg::ToggleAbout));
+        assert!(n.show_about);
+        press(&mut n, Key::Named(Named::Escape), Modifiers::empty());
+        assert!(!n.show_about);
+    }
+
+    // ============================
+    // Escape and click-away
+    // ============================
+
+    #[test]
+    fn escape_order_decides_between_bars_and_selection() {
+        let escape = |n: &mut Notepad| press(n, Key::Named(Named::Escape), Modifiers::empty());
+        let selected = |n: &Notepad| n.active_doc().content.cursor().selection.is_some();
+        for order in EscapeOrder::ALL {
+            let mut n = notepad_with("hello world");
+            n.escape_order = order;
+            let _ = n.update(Message::Search(SearchMsg::OpenFind));
+            n.active_doc_mut().select_bytes(0, 5);
+            n.show_about = true;
+            // Dialogs always go first
+            escape(&mut n);
+            assert!(!n.show_about && n.show_find && selected(&n));
+            escape(&mut n);
+            match order {
+                EscapeOrder::PanelsOnly | EscapeOrder::PanelsFirst => assert!(!n.show_find && selected(&n)),
+                EscapeOrder::SelectionFirst => assert!(n.show_find && !selected(&n)),
             }
+            escape(&mut n);
+            assert!(!n.show_find);
+            assert_eq!(selected(&n), order == EscapeOrder::PanelsOnly);
+            assert_eq!(n.active_doc().content.cursor().position.column, 5);
         }
     }
 
-    fn find_in(&mut self, haystack: &str, from: usize) -> Option<(usize, usize)> {
-        let re = self.build_regex()?;
-        re.find(&haystack[from..])
-            .map(|m| (from + m.start(), m.len()))
+    #[test]
+    fn clicking_the_text_closes_the_find_bars() {
+        let click = |n: &mut Notepad| {
+            let _ = n.update(Message::EditorAction(text_editor::Action::Click(iced::Point::new(0.0, 0.0))));
+        };
+        let mut n = notepad_with("hello");
+        let _ = n.update(Message::Search(SearchMsg::OpenReplace));
+        click(&mut n);
+        assert!(!n.show_find && !n.show_replace);
+        let _ = n.update(Message::Search(SearchMsg::OpenGoTo));
+        click(&mut n);
+        assert!(!n.show_goto);
+
+        n.click_closes_bars = false;
+        let _ = n.update(Message::Search(SearchMsg::OpenFind));
+        click(&mut n);
+        assert!(n.show_find);
     }
 
-    fn rfind_in(&mut self, haystack: &str, until: usize) -> Option<(usize, usize)> {
-        let re = self.build_regex()?;
-        let mut last = None;
-        for m in re.find_iter(&haystack[..until]) {
-            last = Some((m.start(), m.len()));
-        }
-        last
+    // ============================
+    // Keyboard shortcuts
+    // ============================
+
+    fn press(n: &mut Notepad, key: Key, modifiers: Modifiers) {
+        let _ = n.update(Message::EventOccurred(Event::Keyboard(keyboard::Event::KeyPressed {
+            key: key.clone(),
+            modified_key: key,
+            physical_key: keyboard::key::Physical::Unidentified(
+                keyboard::key::NativeCode::Unidentified,
+            ),
+            location: keyboard::Location::Standard,
+            modifiers,
+            text: None,
+            repeat: false,
+        })));
     }
 
-    fn find_next(&mut self) {
-        let text = self.active_doc().content.text();
-        if self.find_query.is_empty() || text.is_empty() {
-            return;
-        }
+    #[test]
+    fn f1_opens_cheat_sheet_and_escape_closes_it() {
+        let mut n = Notepad::test_default();
+        press(&mut n, Key::Named(Named::F1), Modifiers::empty());
+        assert_eq!(n.shortcut_sheet.as_deref(), Some(""));
+        let _ = n.update(Message::Help(HelpMsg::ShortcutQueryChanged("zoom".into())));
+        assert_eq!(n.shortcut_sheet.as_deref(), Some("zoom"));
+        press(&mut n, Key::Named(Named::Escape), Modifiers::empty());
+        assert!(n.shortcut_sheet.is_none());
+    }
 
-        let search_from = self.find_cursor.min(text.len());
-        let found = if search_from < text.len() {
-            self.find_in(&text, search_from)
-        } else {
-            None
-        };
+    #[test]
+    fn registry_bindings_run_their_action() {
+        let mut n = notepad_with("texte");
+        press(&mut n, Key::Character("n".into()), Modifiers::CTRL);
+        assert_eq!(n.tabs.len(), 2);
+        press(&mut n, Key::Named(Named::Tab), Modifiers::CTRL);
+        assert_eq!(n.active_tab, 0);
+        press(&mut n, Key::Named(Named::Tab), Modifiers::CTRL | Modifiers::SHIFT);
+        assert_eq!(n.active_tab, 1);
+        press(&mut n, Key::Character("f".into()), Modifiers::CTRL);
+        assert!(n.show_find);
+    }
 
-        let found = found.or_else(|| self.find_in(&text, 0));
+    // ============================
+    // Named snapshots
+    // ============================
 
-        if let Some((byte_pos, mlen)) = found {
-            self.highlight_match(byte_pos, mlen, &text);
-        }
+    #[test]
+    fn snapshot_restores_text_and_is_undoable() {
+        let mut n = notepad_with("brouillon");
+        let _ = n.update(Message::Snapshot(SnapshotMsg::Create));
+        assert!(n.show_snapshots);
+        assert_eq!(n.snapshot_name, "Instantané 1");
+        let _ = n.update(Message::Snapshot(SnapshotMsg::NameChanged("avant relecture".into())));
+        let _ = n.update(Message::Snapshot(SnapshotMsg::Save));
+        assert_eq!(n.active_doc().snapshots[0].name, "avant relecture");
+        assert_eq!(n.snapshot_name, "Instantané 2");
+
+        n.active_doc_mut().set_text("version relue");
+        let _ = n.update(Message::Snapshot(SnapshotMsg::Restore(0)));
+        assert_eq!(n.active_doc().content.text(), "brouillon");
+        assert!(n.active_doc().is_modified);
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "version relue");
     }
 
-    fn find_previous(&mut self) {
-        let text = self.active_doc().content.text();
-        if self.find_query.is_empty() || text.is_empty() {
-            return;
-        }
+    #[test]
+    fn snapshots_belong_to_their_tab() {
+        let mut n = notepad_with("premier");
+        let _ = n.update(Message::Snapshot(SnapshotMsg::Save));
+        assert_eq!(n.active_doc().snapshots[0].name, "Instantané 1");
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        assert!(n.active_doc().snapshots.is_empty());
+        let _ = n.update(Message::File(FileMsg::SwitchTab(0)));
+        let _ = n.update(Message::Snapshot(SnapshotMsg::Delete(0)));
+        assert!(n.active_doc().snapshots.is_empty());
+    }
 
-        let search_until = self.find_cursor.saturating_sub(1);
+    // ============================
+    // Tags and note search
+    // ============================
 
-        let found = if search_until > 0 {
-            self.rfind_in(&text, search_until)
-        } else {
-            None
+    #[test]
+    fn tags_need_a_saved_file() {
+        let mut n = notepad_with("brouillon");
+        let _ = n.update(Message::Notes(NotesMsg::EditTags));
+        assert!(n.tag_editor.is_none());
+
+        let path = PathBuf::from("/notes/projet.md");
+        n.active_doc_mut().file_path = Some(path.clone());
+        let _ = n.update(Message::Notes(NotesMsg::EditTags));
+        let _ = n.update(Message::Notes(NotesMsg::TagsInputChanged("Travail, #idée".into())));
+        let _ = n.update(Message::Notes(NotesMsg::ToggleFavorite));
+        let _ = n.update(Message::Notes(NotesMsg::SaveTags));
+        assert!(n.tag_editor.is_none());
+        let meta = n.notes.get(&path).unwrap();
+        assert_eq!(meta.tags, ["travail", "idée"]);
+        assert!(meta.favorite);
+
+        let _ = n.update(Message::Notes(NotesMsg::EditTags));
+        assert_eq!(n.tag_editor.as_ref().unwrap().input, "travail, idée");
+        press(&mut n, Key::Named(Named::Escape), Modifiers::empty());
+        assert!(n.tag_editor.is_none());
+    }
+
+    #[test]
+    fn note_search_keeps_the_latest_results_and_opens_tabs() {
+        let mut n = notepad_with("a");
+        let path = PathBuf::from("/notes/a.md");
+        n.active_doc_mut().file_path = Some(path.clone());
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        press(&mut n, Key::Character("f".into()), Modifiers::CTRL | Modifiers::SHIFT);
+        let _ = n.update(Message::Notes(NotesMsg::QueryChanged("#travail".into())));
+        let search = n.note_search.as_ref().unwrap();
+        assert!(search.searching);
+        let generation = search.generation;
+
+        let hit = notes::NoteHit {
+            path: path.clone(),
+            tags: vec!["travail".into()],
+            favorite: false,
+            snippet: None,
         };
+        let _ = n.update(Message::Notes(NotesMsg::Results(generation - 1, Vec::new())));
+        assert!(n.note_search.as_ref().unwrap().searching);
+        let _ = n.update(Message::Notes(NotesMsg::Results(generation, vec![hit])));
+        assert_eq!(n.note_search.as_ref().unwrap().results.len(), 1);
 
-        let found = found.or_else(|| self.rfind_in(&text, text.len()));
+        let _ = n.update(Message::Notes(NotesMsg::Open(path)));
+        assert!(n.note_search.is_none());
+        assert_eq!(n.tabs.len(), 2);
+        assert_eq!(n.active_tab, 0);
+    }
 
-        if let Some((byte_pos, mlen)) = found {
-            self.highlight_match(byte_pos, mlen, &text);
-        }
+    // ============================
+    // Wiki links
+    // ============================
+
+    fn wiki_notepad(name: &str, text: &str) -> (Notepad, PathBuf) {
+        let root = std::env::temp_dir().join(format!("notepad_wiki_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("Projet A.md"), "# Projet A\n").unwrap();
+        let mut n = notepad_with(text);
+        n.active_doc_mut().file_path = Some(root.join("index.md"));
+        n.notes_folder = Some(root.clone());
+        (n, root)
     }
 
-    fn replace_one(&mut self) {
-        if self.find_query.is_empty() {
-            return;
-        }
-        if let Some(selected) = self.active_doc().content.selection() {
-            let is_match = if let Some(re) = self.build_regex() {
-                re.is_match(&selected)
-                    && re.find(&selected).is_some_and(|m| m.len() == selected.len())
-            } else {
-                false
-            };
-            if is_match {
-                self.save_snapshot();
-                let replacement = self.replace_query.clone();
-                let doc = self.active_doc_mut();
-                doc.content.perform(text_editor::Action::Edit(
-                    text_editor::Edit::Paste(Arc::new(replacement)),
-                ));
-                doc.is_modified = true;
-                doc.update_stats_cache();
-            }
-        }
-        self.find_next();
+    #[test]
+    fn typing_a_wiki_link_completes_note_names() {
+        let (mut n, root) = wiki_notepad("complete", "voir ");
+        n.active_doc_mut()
+            .content
+            .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+        type_text(&mut n, "[[pro");
+        let popup = n.path_completion.as_ref().expect("popup");
+        assert_eq!(popup.entries[0].name, "Projet A");
+        let _ = n.update(Message::Completion(CompletionMsg::Accept));
+        assert_eq!(n.active_doc().content.text(), "voir [[Projet A]]");
+
+        n.active_doc_mut().file_path = Some(root.join("index.txt"));
+        type_text(&mut n, " [[pro");
+        assert!(n.path_completion.is_none());
+        let _ = std::fs::remove_dir_all(&root);
     }
 
-    fn replace_all(&mut self) {
-        if self.find_query.is_empty() {
-            return;
-        }
-        let Some(re) = self.build_regex() else {
-            return;
-        };
-        let text = self.active_doc().content.text();
-        let new_text = re
-            .replace_all(&text, self.replace_query.as_str())
-            .into_owned();
-        if text != new_text {
-            self.save_snapshot();
-            let doc = self.active_doc_mut();
-            doc.content = text_editor::Content::with_text(&new_text);
-            doc.is_modified = true;
-            doc.update_stats_cache();
-        }
+    #[test]
+    fn following_a_wiki_link_opens_or_creates_the_note() {
+        let (mut n, root) = wiki_notepad("follow", "voir [[projet a]] puis [[Suite|la suite]]");
+        n.navigate_to(0, 8);
+        let _ = n.follow_wiki_link();
+        assert_eq!(n.tabs.len(), 2);
+        assert_eq!(n.active_doc().file_path.as_deref(), Some(root.join("Projet A.md").as_path()));
+
+        let _ = n.update(Message::File(FileMsg::SwitchTab(0)));
+        n.navigate_to(0, 25);
+        let _ = n.follow_wiki_link();
+        assert_eq!(std::fs::read_to_string(root.join("Suite.md")).unwrap(), "# Suite\n");
+        assert_eq!(n.active_doc().file_path.as_deref(), Some(root.join("Suite.md").as_path()));
+
+        // Already open: the tab is reused
+        let _ = n.update(Message::File(FileMsg::SwitchTab(0)));
+        n.navigate_to(0, 8);
+        let _ = n.follow_wiki_link();
+        assert_eq!(n.tabs.len(), 3);
+        assert_eq!(n.active_tab, 1);
+        let _ = std::fs::remove_dir_all(&root);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::app::{Notepad, MAX_UNDO_HISTORY};
+    // ============================
+    // Notes export
+    // ============================
 
-    fn notepad_with(text: &str) -> Notepad {
-        let mut n = Notepad::test_default();
-        n.active_doc_mut().content = text_editor::Content::with_text(text);
-        n
+    #[test]
+    fn export_needs_a_notes_folder_and_runs_as_a_job() {
+        let mut n = notepad_with("");
+        let _ = n.update(Message::Notes(NotesMsg::Export(ExportFormat::Pdf)));
+        assert_eq!(n.toasts.iter().next().unwrap().severity, Severity::Warning);
+
+        n.notes_folder = Some(PathBuf::from("/notes"));
+        let _ = n.update(Message::Notes(NotesMsg::ExportTo(ExportFormat::Pdf, PathBuf::from("/notes.pdf"))));
+        let job = n.jobs.iter().next().expect("job");
+        assert_eq!(job.label, "Exportation des notes en PDF");
+        let id = job.id;
+        let _ = n.update(Message::Notes(NotesMsg::Exported(id, PathBuf::from("/notes.pdf"), Ok(3))));
+        assert_eq!(n.jobs.len(), 0);
+        let last = n.toasts.iter().last().unwrap();
+        assert_eq!(last.severity, Severity::Success);
+        assert!(last.message.starts_with("3 note(s) exportée(s)"));
     }
 
     // ============================
-    // byte_pos_to_line_col
+    // Symbolic links and network shares
     // ============================
 
     #[test]
-    fn byte_pos_start_of_file() {
-        assert_eq!(byte_pos_to_line_col("hello\nworld", 0), (0, 0));
+    fn network_autosave_only_saves_network_tabs() {
+        let local = std::env::temp_dir().join(format!("notepad_local_{}.txt", std::process::id()));
+        let share = std::env::temp_dir().join(format!("notepad_share_{}.txt", std::process::id()));
+        let mut n = notepad_with("local");
+        n.active_doc_mut().file_path = Some(local.clone());
+        n.active_doc_mut().is_modified = true;
+        n.tabs.push(Document::default());
+        n.tabs[1].set_text("share");
+        n.tabs[1].file_path = Some(share.clone());
+        n.tabs[1].is_modified = true;
+        n.tabs[1].network = true;
+
+        let _ = n.update(Message::File(FileMsg::AutoSaveNetwork));
+        assert!(n.tabs[0].is_modified);
+        assert!(!n.tabs[1].is_modified);
+        assert_eq!(std::fs::read_to_string(&share).unwrap(), "share");
+        // Safe save leaves no temporary file behind
+        assert!(!share.with_file_name(format!(".{}.notepad-tmp", file_display_name(&share))).exists());
+
+        let _ = n.update(Message::File(FileMsg::AutoSave));
+        assert!(!n.tabs[0].is_modified);
+        let _ = std::fs::remove_file(&local);
+        let _ = std::fs::remove_file(&share);
     }
 
     #[test]
-    fn byte_pos_mid_first_line() {
-        assert_eq!(byte_pos_to_line_col("hello\nworld", 3), (0, 3));
+    fn saves_to_a_slow_share_are_queued_into_one() {
+        let share = std::env::temp_dir().join(format!("notepad_slow_share_{}.txt", std::process::id()));
+        let mut n = notepad_with("v1");
+        let doc = n.active_doc_mut();
+        doc.file_path = Some(share.clone());
+        doc.network = true;
+        doc.slow_share = true;
+        doc.is_modified = true;
+
+        let _ = n.update(Message::File(FileMsg::Save));
+        let first = n.active_doc().save_in_flight.expect("written on a worker");
+        assert!(!n.active_doc().is_read_only());
+        n.navigate_to(0, 2);
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(text_editor::Edit::Insert('!'))));
+        let _ = n.update(Message::File(FileMsg::Save));
+        let _ = n.update(Message::File(FileMsg::Save));
+        assert_eq!(n.active_doc().save_in_flight, Some(first));
+        assert!(n.active_doc().save_queued);
+        assert_eq!(n.active_doc().status_message.as_deref(), Some("Enregistrement en cours…"));
+
+        // The presses made meanwhile end in a single write of the latest text
+        let _ = n.update(Message::File(FileMsg::Saved(first, Ok(()))));
+        let second = n.active_doc().save_in_flight.expect("queued save started");
+        assert_ne!(second, first);
+        assert!(!n.active_doc().save_queued && n.active_doc().is_modified);
+        let _ = n.update(Message::File(FileMsg::Saved(second, Ok(()))));
+        assert!(n.active_doc().save_in_flight.is_none() && !n.active_doc().is_modified);
+        assert!(n.active_doc().status_message.is_none());
+        let _ = std::fs::remove_file(&share);
     }
 
     #[test]
-    fn byte_pos_start_second_line() {
-        assert_eq!(byte_pos_to_line_col("hello\nworld", 6), (1, 0));
+    fn failed_slow_share_save_leaves_the_tab_modified() {
+        let mut n = notepad_with("v1");
+        let doc = n.active_doc_mut();
+        doc.file_path = Some(PathBuf::from("/nonexistent-dir/share.txt"));
+        doc.network = true;
+        doc.slow_share = true;
+        doc.is_modified = true;
+        doc.mark_saved();
+        let saved = doc.saved_text.clone();
+        n.navigate_to(0, 2);
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(text_editor::Edit::Insert('!'))));
+
+        let _ = n.update(Message::File(FileMsg::Save));
+        let id = n.active_doc().save_in_flight.expect("written on a worker");
+        let _ = n.update(Message::File(FileMsg::CloseRequested(iced::window::Id::unique())));
+        assert_eq!(n.toasts.iter().next().unwrap().severity, Severity::Warning);
+        let _ = n.update(Message::File(FileMsg::Saved(id, Err("réseau".to_string()))));
+        // The change marks still compare with the last text on disk
+        assert!(n.active_doc().is_modified);
+        assert_eq!(n.active_doc().saved_text, saved);
     }
 
     #[test]
-    fn byte_pos_mid_second_line() {
-        assert_eq!(byte_pos_to_line_col("hello\nworld", 9), (1, 3));
+    fn unreachable_share_keeps_unsaved_session_content() {
+        let session = SessionData {
+            tabs: vec![SessionTab {
+                file_path: Some(PathBuf::from(r"\\srv\docs\notes.txt")),
+                unsaved_content: Some("brouillon".to_string()),
+                is_modified: true,
+                cursor_line: 0,
+                cursor_column: 0,
+                scroll_offset: 0.0,
+            }],
+            active_tab: 0,
+        };
+        let mut n = Notepad::test_default();
+        n.restore_session_data(&session, vec![None]);
+        assert_eq!(n.tabs.len(), 1);
+        let doc = n.active_doc();
+        assert_eq!(doc.file_path.as_deref(), Some(Path::new(r"\\srv\docs\notes.txt")));
+        assert!(doc.network && doc.is_modified);
+        assert_eq!(doc.content.text(), "brouillon");
     }
 
+    #[cfg(unix)]
     #[test]
-    fn byte_pos_end_of_text() {
-        let text = "abc\ndef";
-        assert_eq!(byte_pos_to_line_col(text, text.len()), (1, 3));
+    fn properties_show_the_symlink_target() {
+        let dir = std::env::temp_dir().join(format!("notepad_props_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let real = dir.join("real.txt");
+        let link = dir.join("link.txt");
+        std::fs::write(&real, "12345").unwrap();
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut n = notepad_with("12345");
+        n.active_doc_mut().file_path = Some(link.clone());
+        let _ = n.update(Message::File(FileMsg::ToggleProperties));
+        let props = n.properties.as_ref().expect("properties");
+        assert_eq!(props.path.as_deref(), Some(link.as_path()));
+        assert_eq!(props.target, Some(std::fs::canonicalize(&real).unwrap()));
+        assert_eq!(props.size, Some(5));
+        assert!(props.modified.is_some());
+        press(&mut n, Key::Named(Named::Escape), Modifiers::empty());
+        assert!(n.properties.is_none());
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
+    // ============================
+    // Idle timers
+    // ============================
+
     #[test]
-    fn byte_pos_multibyte_chars() {
-        let text = "café\nbar";
-        assert_eq!(byte_pos_to_line_col(text, 6), (1, 0));
-        assert_eq!(byte_pos_to_line_col(text, 3), (0, 3));
+    fn timers_do_not_wake_an_idle_app() {
+        let mut n = notepad_with("texte");
+        n.last_activity = Instant::now() - std::time::Duration::from_secs(crate::app::IDLE_AFTER_SECS + 1);
+        assert!(n.is_idle());
+        let _ = n.update(Message::File(FileMsg::CheckExternalChanges));
+        let _ = n.update(Message::Toast(ToastMsg::Tick));
+        let _ = n.update(Message::EventOccurred(Event::Window(iced::window::Event::Unfocused)));
+        assert!(n.is_idle());
+
+        let _ = n.update(Message::EditorAction(text_editor::Action::Move(text_editor::Motion::End)));
+        assert!(!n.is_idle());
     }
 
+    // ============================
+    // Low power mode
+    // ============================
+
     #[test]
-    fn byte_pos_three_lines() {
-        let text = "aaa\nbbb\nccc";
-        assert_eq!(byte_pos_to_line_col(text, 8), (2, 0));
-        assert_eq!(byte_pos_to_line_col(text, 10), (2, 2));
+    fn low_power_batches_stats_and_skips_indexing() {
+        let mut n = notepad_with("un deux");
+        n.active_doc_mut().update_stats_cache();
+        n.navigate_to(0, 7);
+        n.low_power = true;
+        for c in " trois".chars() {
+            let _ = n.update(Message::EditorAction(text_editor::Action::Edit(text_editor::Edit::Insert(c))));
+        }
+        assert_eq!(n.active_doc().cached_word_count, 2);
+        assert!(n.active_doc().stats_stale());
+        assert!(n.index_task().is_none());
+
+        let _ = n.update(Message::Edit(EditMsg::RefreshStats));
+        assert_eq!(n.active_doc().cached_word_count, 3);
+        assert!(!n.active_doc().stats_stale());
     }
 
     // ============================
-    // build_regex
+    // Folder drops
     // ============================
 
     #[test]
-    fn build_regex_case_sensitive_literal() {
+    fn dropped_folder_lists_its_files_instead_of_opening_it() {
+        let root = std::env::temp_dir().join(format!("notepad_drop_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("plan.md"), "# Plan").unwrap();
         let mut n = Notepad::test_default();
-        n.find_query = "Hello".to_string();
-        n.case_sensitive = true;
-        n.use_regex = false;
-        let re = n.build_regex().unwrap();
-        assert!(re.is_match("Hello"));
-        assert!(!re.is_match("hello"));
+        let _ = n.update(Message::EventOccurred(Event::Window(iced::window::Event::FileDropped(
+            root.clone(),
+        ))));
+        assert_eq!(n.tabs.len(), 1);
+        assert!(n.active_doc().file_path.is_none());
+        let picker = n.folder_picker.as_ref().expect("picker");
+        assert!(picker.files.is_none());
+
+        // Listing of a folder replaced meanwhile is ignored
+        let _ = n.update(Message::Folder(FolderMsg::Listed(PathBuf::from("/autre"), vec![])));
+        assert!(n.folder_picker.as_ref().unwrap().files.is_none());
+        let files = folder::text_files(&root);
+        let _ = n.update(Message::Folder(FolderMsg::Listed(root.clone(), files)));
+        let _ = n.update(Message::Folder(FolderMsg::QueryChanged("pla".into())));
+        let _ = n.update(Message::Folder(FolderMsg::OpenFirst));
+        assert!(n.folder_picker.is_none());
+        assert_eq!(n.active_doc().file_path.as_deref(), Some(root.join("plan.md").as_path()));
+        let _ = std::fs::remove_dir_all(&root);
     }
 
+    // ============================
+    // Window opacity
+    // ============================
+
     #[test]
-    fn build_regex_case_insensitive_literal() {
+    fn window_opacity_is_clamped() {
         let mut n = Notepad::test_default();
-        n.find_query = "hello".to_string();
-        n.case_sensitive = false;
-        n.use_regex = false;
-        let re = n.build_regex().unwrap();
-        assert!(re.is_match("HELLO"));
-        assert!(re.is_match("Hello"));
-        assert!(re.is_match("hello"));
+        let _ = n.update(Message::Settings(SettingsMsg::SetWindowOpacity(85)));
+        assert_eq!(n.window_opacity, 85);
+        let _ = n.update(Message::Settings(SettingsMsg::SetWindowOpacity(20)));
+        assert_eq!(n.window_opacity, MIN_WINDOW_OPACITY);
     }
 
+    // ============================
+    // UI scale
+    // ============================
+
     #[test]
-    fn build_regex_valid_pattern() {
+    fn ui_scale_keeps_popups_inside_the_window() {
         let mut n = Notepad::test_default();
-        n.find_query = r"\d+".to_string();
-        n.case_sensitive = true;
-        n.use_regex = true;
-        let re = n.build_regex().unwrap();
-        assert!(re.is_match("abc123"));
-        assert!(!re.is_match("abc"));
+        n.window_width = 900.0;
+        n.window_height = 600.0;
+        let _ = n.update(Message::Settings(SettingsMsg::SetUiScale(150)));
+        assert_eq!(n.scale_factor(), 1.5);
+        // Same window on screen, fewer layout units in it
+        assert_eq!((n.window_width, n.window_height), (600.0, 400.0));
+        let _ = n.update(Message::EventOccurred(Event::Window(iced::window::Event::Rescaled(1.25))));
+        assert_eq!(n.pixel_scale(), 1.875);
     }
 
+    // ============================
+    // Document language
+    // ============================
+
     #[test]
-    fn build_regex_invalid_pattern() {
-        let mut n = Notepad::test_default();
-        n.find_query = "[unclosed".to_string();
-        n.use_regex = true;
-        assert!(n.build_regex().is_none());
+    fn language_menu_overrides_the_extension() {
+        let mut n = notepad_with("# Titre");
+        assert_eq!(n.active_doc().language(), Language::PlainText);
+        let _ = n.update(Message::Menu(MenuMsg::ShowLanguage));
+        assert!(n.show_language_menu);
+        let _ = n.update(Message::Format(FormatMsg::ChooseLanguage(Language::Markdown)));
+        assert!(!n.show_language_menu);
+        assert!(n.active_doc().is_markdown());
+        let _ = n.update(Message::Format(FormatMsg::ChooseLanguage(Language::PlainText)));
+        assert_eq!(n.active_doc().language_override, None);
     }
 
     #[test]
-    fn build_regex_case_insensitive_regex() {
+    fn opened_file_gets_its_remembered_language() {
+        let path = std::env::temp_dir().join(format!("notepad_lang_{}", std::process::id()));
+        std::fs::write(&path, "fn main() {}").unwrap();
         let mut n = Notepad::test_default();
-        n.find_query = "abc".to_string();
-        n.case_sensitive = false;
-        n.use_regex = true;
-        let re = n.build_regex().unwrap();
-        assert!(re.is_match("ABC"));
+        n.metadata.update(&path, |meta| meta.language = Some(Language::Rust));
+        let _ = n.open_dropped_file(path.clone());
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(n.active_doc().language(), Language::Rust);
     }
 
     #[test]
-    fn build_regex_escapes_special_chars_in_literal() {
+    fn extensionless_file_is_recognized_by_its_content() {
+        let path = std::env::temp_dir().join(format!("notepad_sniff_{}", std::process::id()));
+        std::fs::write(&path, "#!/usr/bin/env python3\nprint('ok')\n").unwrap();
         let mut n = Notepad::test_default();
-        n.find_query = "a.b".to_string();
-        n.case_sensitive = true;
-        n.use_regex = false;
-        let re = n.build_regex().unwrap();
-        assert!(re.is_match("a.b"));
-        assert!(!re.is_match("axb"));
+        let _ = n.open_dropped_file(path.clone());
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(n.active_doc().language(), Language::Python);
+        // Choosing the guessed language back leaves nothing to remember
+        let _ = n.update(Message::Format(FormatMsg::ChooseLanguage(Language::Shell)));
+        assert_eq!(n.active_doc().language(), Language::Shell);
+        n.active_doc_mut().language_override = None;
+        n.active_doc_mut().update_stats_cache();
+        assert_eq!(n.active_doc().language(), Language::Python);
     }
 
     // ============================
-    // find_in / rfind_in
+    // Duplicate tab
     // ============================
 
     #[test]
-    fn find_in_from_start() {
-        let mut n = notepad_with("hello world hello");
-        n.find_query = "hello".to_string();
-        n.case_sensitive = true;
-        assert_eq!(n.find_in("hello world hello", 0), Some((0, 5)));
+    fn duplicate_tab_copies_the_buffer_without_the_file() {
+        let mut n = notepad_with("un\ndeux\ntrois");
+        n.active_doc_mut().file_path = Some(PathBuf::from("/notes/liste.md"));
+        n.navigate_to(1, 2);
+        let _ = n.update(Message::File(FileMsg::DuplicateTab));
+        assert_eq!(n.tabs.len(), 2);
+        assert_eq!(n.active_tab, 1);
+        let copy = n.active_doc();
+        assert_eq!(copy.text().as_ref(), "un\ndeux\ntrois");
+        assert!(copy.file_path.is_none() && copy.is_modified);
+        assert_eq!(copy.language(), Language::Markdown);
+        assert!(copy.undo_stack.is_empty());
+        let view = copy.pending_view.expect("view");
+        assert_eq!((view.line, view.column), (1, 2));
+        assert_ne!(copy.id, n.tabs[0].id);
+    }
+
+    // ============================
+    // Append selection to a file
+    // ============================
+
+    #[test]
+    fn appended_entries_are_stamped_and_separated() {
+        let path = std::env::temp_dir().join(format!("notepad_append_{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        append_entry(&path, "première idée", "10:00 17/10/2026").unwrap();
+        append_entry(&path, "seconde\nidée\n", "10:05 17/10/2026").unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            written,
+            "--- 10:00 17/10/2026 ---\npremière idée\n\n--- 10:05 17/10/2026 ---\nseconde\nidée\n"
+        );
+    }
+
+    #[test]
+    fn appending_needs_a_selection() {
+        let mut n = notepad_with("texte");
+        let _ = n.update(Message::Edit(EditMsg::AppendSelection));
+        assert_eq!(n.toasts.iter().next().unwrap().severity, Severity::Warning);
+        let _ = n.update(Message::Edit(EditMsg::AppendSelectionTo(PathBuf::from("/journal.txt"))));
+        assert!(n.append_target.is_none());
+    }
+
+    // ============================
+    // Join and hard wrap
+    // ============================
+
+    #[test]
+    fn lines_join_and_wrap_as_single_edits() {
+        let mut n = notepad_with("un\n  deux\ntrois\nquatre");
+        // Without a selection the cursor line takes the next one
+        let _ = n.update(Message::Edit(EditMsg::JoinLines));
+        assert_eq!(n.active_doc().content.text(), "un deux\ntrois\nquatre");
+        n.active_doc_mut().content.move_to(text_editor::Cursor {
+            position: text_editor::Position { line: 2, column: 0 },
+            selection: Some(text_editor::Position { line: 0, column: 1 }),
+        });
+        let _ = n.update(Message::Edit(EditMsg::JoinLines));
+        assert_eq!(n.active_doc().content.text(), "un deux trois\nquatre");
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "un deux\ntrois\nquatre");
+
+        n.navigate_to(0, 0);
+        let _ = n.update(Message::Edit(EditMsg::HardWrap));
+        assert_eq!(n.wrap_prompt.as_deref(), Some("80"));
+        let _ = n.update(Message::Edit(EditMsg::HardWrapWidthChanged("4x".into())));
+        assert_eq!(n.wrap_prompt.as_deref(), Some("4"));
+        let _ = n.update(Message::Edit(EditMsg::RunHardWrap));
+        assert!(n.wrap_prompt.is_none());
+        assert_eq!(n.last_wrap_width, 4);
+        assert_eq!(n.active_doc().content.text(), "un\ndeux\ntrois\nquatre");
+    }
+
+    // ============================
+    // Comment toggling
+    // ============================
+
+    #[test]
+    fn lines_are_commented_with_the_language_tokens() {
+        let mut n = notepad_with("fn main() {\n    run();\n}");
+        n.active_doc_mut().language_override = Some(Language::Rust);
+        n.navigate_to(1, 6);
+        let _ = n.update(Message::Edit(EditMsg::ToggleComment));
+        assert_eq!(n.active_doc().content.text(), "fn main() {\n    // run();\n}");
+        // The caret stays on the same character
+        assert_eq!(n.active_doc().content.cursor().position, text_editor::Position { line: 1, column: 9 });
+        let _ = n.update(Message::Edit(EditMsg::ToggleComment));
+        assert_eq!(n.active_doc().content.text(), "fn main() {\n    run();\n}");
+
+        // A selection is commented as a block and stays selected
+        n.active_doc_mut().language_override = Some(Language::Python);
+        let _ = n.update(Message::Edit(EditMsg::SelectAll));
+        let _ = n.update(Message::Edit(EditMsg::ToggleComment));
+        assert_eq!(n.active_doc().content.text(), "# fn main() {\n#     run();\n# }");
+        assert_eq!(n.active_doc().content.selection().as_deref(), Some("# fn main() {\n#     run();\n# }"));
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "fn main() {\n    run();\n}");
+
+        // Nothing to comment with in plain text
+        n.active_doc_mut().language_override = Some(Language::PlainText);
+        let _ = n.update(Message::Edit(EditMsg::ToggleComment));
+        assert_eq!(n.active_doc().content.text(), "fn main() {\n    run();\n}");
+        assert_eq!(n.toasts.iter().count(), 1);
+    }
+
+    // ============================
+    // Filter through a command
+    // ============================
+
+    #[test]
+    fn filter_result_replaces_the_selection_as_one_edit() {
+        let mut n = notepad_with("poire\npomme\nabricot");
+        let _ = n.update(Message::Edit(EditMsg::SelectAll));
+        let _ = n.update(Message::Edit(EditMsg::FilterSelection));
+        assert_eq!(n.filter_prompt.as_deref(), Some(""));
+        let _ = n.update(Message::Edit(EditMsg::FilterCommandChanged("sort".into())));
+        let _ = n.update(Message::Edit(EditMsg::RunFilter));
+        assert!(n.filter_prompt.is_none());
+        assert_eq!(n.last_filter_command, "sort");
+        let job = n.jobs.iter().next().expect("job").id;
+
+        let doc = n.active_doc().id;
+        let sent = "poire\npomme\nabricot".to_string();
+        let sorted = Ok("abricot\npoire\npomme".to_string());
+        let _ = n.update(Message::Edit(EditMsg::Filtered(job, doc, sent, sorted)));
+        assert_eq!(n.active_doc().text().as_ref(), "abricot\npoire\npomme");
+        assert!(n.jobs.iter().next().is_none());
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().text().as_ref(), "poire\npomme\nabricot");
     }
 
     #[test]
-    fn find_in_from_offset() {
-        let mut n = notepad_with("hello world hello");
-        n.find_query = "hello".to_string();
-        n.case_sensitive = true;
-        assert_eq!(n.find_in("hello world hello", 1), Some((12, 5)));
+    fn filter_is_dropped_when_the_selection_changed() {
+        let mut n = notepad_with("b\na");
+        let _ = n.update(Message::Edit(EditMsg::SelectAll));
+        let _ = n.update(Message::Edit(EditMsg::FilterSelection));
+        let _ = n.update(Message::Edit(EditMsg::FilterCommandChanged("sort".into())));
+        let _ = n.update(Message::Edit(EditMsg::RunFilter));
+        let job = n.jobs.iter().next().unwrap().id;
+        n.navigate_to(0, 0);
+        let doc = n.active_doc().id;
+        let _ = n.update(Message::Edit(EditMsg::Filtered(job, doc, "b\na".into(), Ok("a\nb".into()))));
+        assert_eq!(n.active_doc().text().as_ref(), "b\na");
+        assert_eq!(n.toasts.iter().last().unwrap().severity, Severity::Warning);
     }
 
+    // ============================
+    // Scripts
+    // ============================
+
     #[test]
-    fn find_in_no_match() {
-        let mut n = notepad_with("hello world");
-        n.find_query = "xyz".to_string();
-        n.case_sensitive = true;
-        assert_eq!(n.find_in("hello world", 0), None);
+    fn script_without_selection_replaces_the_document() {
+        let mut n = notepad_with("poire\npomme");
+        let _ = n.update(Message::Edit(EditMsg::ShowScripts));
+        let _ = n.update(Message::Edit(EditMsg::ScriptsListed(vec![PathBuf::from("/scripts/Majuscules.rhai")])));
+        assert_eq!(n.script_picker.as_ref().and_then(|p| p.scripts.as_ref()).map(Vec::len), Some(1));
+        let _ = n.update(Message::Edit(EditMsg::RunScript(PathBuf::from("/scripts/Majuscules.rhai"))));
+        assert!(n.script_picker.is_none());
+        let job = n.jobs.iter().next().expect("job").id;
+
+        let (doc, version) = (n.active_doc().id, n.active_doc().mirror.version());
+        let upper = Ok("POIRE\nPOMME".to_string());
+        let _ = n.update(Message::Edit(EditMsg::ScriptRan(job, doc, version, None, upper)));
+        assert_eq!(n.active_doc().text().as_ref(), "POIRE\nPOMME");
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().text().as_ref(), "poire\npomme");
     }
 
     #[test]
-    fn rfind_in_last_occurrence() {
-        let mut n = notepad_with("hello world hello");
-        n.find_query = "hello".to_string();
-        n.case_sensitive = true;
-        let text = "hello world hello";
-        assert_eq!(n.rfind_in(text, text.len()), Some((12, 5)));
+    fn script_is_dropped_when_the_text_changed() {
+        let mut n = notepad_with("abc");
+        let _ = n.update(Message::Edit(EditMsg::RunScript(PathBuf::from("/scripts/Majuscules.rhai"))));
+        let job = n.jobs.iter().next().unwrap().id;
+        let (doc, version) = (n.active_doc().id, n.active_doc().mirror.version());
+        n.active_doc_mut().perform(text_editor::Action::Edit(text_editor::Edit::Insert('d')));
+        let _ = n.update(Message::Edit(EditMsg::ScriptRan(job, doc, version, None, Ok("ABC".into()))));
+        assert_eq!(n.active_doc().text().as_ref(), "dabc");
+        assert_eq!(n.toasts.iter().last().unwrap().severity, Severity::Warning);
     }
 
+    // ============================
+    // Syntax errors of configuration files
+    // ============================
+
     #[test]
-    fn find_in_case_insensitive() {
-        let mut n = notepad_with("Hello World");
-        n.find_query = "hello".to_string();
-        n.case_sensitive = false;
-        assert_eq!(n.find_in("Hello World", 0), Some((0, 5)));
+    fn syntax_error_is_shown_for_the_current_text_only() {
+        let mut n = notepad_with("{\n  \"a\": 1\n  \"b\": 2\n}");
+        assert!(!n.active_doc().needs_syntax_check());
+        n.active_doc_mut().language_override = Some(Language::Json);
+        assert!(n.active_doc().needs_syntax_check());
+        let _ = n.update(Message::Edit(EditMsg::CheckSyntax));
+        assert!(!n.active_doc().needs_syntax_check());
+
+        let doc = n.active_doc();
+        let (id, version) = (doc.id, doc.mirror.version());
+        let error = syntax::check(Language::Json, &doc.text());
+        let _ = n.update(Message::Edit(EditMsg::SyntaxChecked(id, version, error.clone())));
+        assert_eq!(n.active_doc().current_syntax_error().map(|e| e.line), Some(2));
+        let _ = n.update(Message::Edit(EditMsg::GoToSyntaxError));
+        assert_eq!(n.active_doc().content.cursor().position.line, 2);
+
+        // A result for a text edited since is dropped
+        n.active_doc_mut().syntax_error = None;
+        type_text(&mut n, ",");
+        let _ = n.update(Message::Edit(EditMsg::SyntaxChecked(id, version, error)));
+        assert!(n.active_doc().current_syntax_error().is_none());
+        assert!(n.active_doc().needs_syntax_check());
+        n.active_doc_mut().language_override = Some(Language::PlainText);
+        assert!(!n.active_doc().needs_syntax_check());
     }
 
     // ============================
-    // find_next / find_previous
+    // Window menu
     // ============================
 
     #[test]
-    fn find_next_empty_query_no_crash() {
-        let mut n = notepad_with("some text");
-        n.find_query = String::new();
-        n.find_next();
+    fn opening_an_open_file_switches_to_its_tab() {
+        let dir = std::env::temp_dir().join(format!("notepad_window_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("liste.txt");
+        std::fs::write(&path, "lait").unwrap();
+        let mut n = Notepad::test_default();
+        let _ = n.open_dropped_file(path.clone());
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        let _ = n.open_dropped_file(dir.join(".").join("liste.txt"));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(n.tabs.len(), 2);
+        assert_eq!(n.active_tab, 0);
     }
 
     #[test]
-    fn find_next_empty_text_no_crash() {
-        let mut n = notepad_with("");
-        n.find_query = "abc".to_string();
-        n.find_next();
+    fn unmodified_tabs_are_closed_and_tabs_sorted_by_name() {
+        let mut n = Notepad::test_default();
+        n.active_doc_mut().file_path = Some(PathBuf::from("/b/zèbre.txt"));
+        for (name, modified) in [("Alpha.md", true), ("beta.txt", false), ("alpha.md", false)] {
+            n.tabs.push(Document {
+                file_path: Some(PathBuf::from("/a").join(name)),
+                is_modified: modified,
+                ..Document::default()
+            });
+        }
+        assert_eq!(n.name_order(), [3, 1, 2, 0]);
+        let _ = n.update(Message::File(FileMsg::CloseUnmodifiedTabs));
+        assert_eq!(n.tabs.len(), 1);
+        assert_eq!(n.active_doc().file_path, Some(PathBuf::from("/a/Alpha.md")));
     }
 
+    // ============================
+    // Session autosave
+    // ============================
+
     #[test]
-    fn find_previous_empty_query_no_crash() {
-        let mut n = notepad_with("some text");
-        n.find_query = String::new();
-        n.find_previous();
+    fn session_is_not_written_before_startup() {
+        let mut n = Notepad::test_default();
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        let _ = n.update(Message::File(FileMsg::AutoSaveSession));
+        assert_eq!(n.session_saved, None);
     }
 
     #[test]
-    fn find_next_wraps_around() {
-        let mut n = notepad_with("abc def abc");
-        n.find_query = "abc".to_string();
-        n.case_sensitive = true;
-        n.find_cursor = 100;
-        n.find_next();
-        assert!(n.find_cursor > 0);
+    fn session_fingerprint_follows_tabs_text_and_cursor() {
+        let mut n = notepad_with("un\ndeux");
+        let start = n.session_fingerprint();
+        assert_eq!(n.session_fingerprint(), start);
+        type_text(&mut n, "x");
+        let typed = n.session_fingerprint();
+        assert_ne!(typed, start);
+        n.navigate_to(1, 0);
+        assert_ne!(n.session_fingerprint(), typed);
+        let moved = n.session_fingerprint();
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        assert_ne!(n.session_fingerprint(), moved);
     }
 
     // ============================
-    // replace_all
+    // Named sessions
     // ============================
 
     #[test]
-    fn replace_all_simple() {
-        let mut n = notepad_with("hello world hello");
-        n.find_query = "hello".to_string();
-        n.replace_query = "hi".to_string();
-        n.case_sensitive = true;
-        n.replace_all();
-        assert_eq!(n.active_doc().content.text().trim_end(), "hi world hi");
-        assert!(n.active_doc().is_modified);
+    fn switching_sessions_swaps_the_tabs() {
+        let mut n = notepad_with("brouillon");
+        n.active_doc_mut().is_modified = true;
+        n.window_width = 900.0;
+        n.store_named_session("Travail");
+        let travail = n.named_sessions.get("Travail").unwrap().clone();
+        assert_eq!(travail.session.tabs[0].unsaved_content.as_deref(), Some("brouillon"));
+        assert_eq!(travail.window_width, 900.0);
+
+        let _ = n.update(Message::File(FileMsg::NewTab));
+        type_text(&mut n, "article");
+        let blog = NamedSession {
+            name: "Blog".to_string(),
+            window_width: 1200.0,
+            ..travail.clone()
+        };
+        n.apply_named_session(&blog, vec![None]);
+        assert_eq!(n.tabs.len(), 1);
+        assert_eq!(n.active_doc().text().as_ref(), "brouillon");
+        assert_eq!(n.named_sessions.current.as_deref(), Some("Blog"));
+        assert_eq!(n.window_width, 1200.0);
+        assert!(n.title().ends_with("[Blog]"));
     }
 
+    // ============================
+    // Reopen with encoding
+    // ============================
+
     #[test]
-    fn replace_all_case_insensitive() {
-        let mut n = notepad_with("Hello HELLO hello");
-        n.find_query = "hello".to_string();
-        n.replace_query = "hi".to_string();
-        n.case_sensitive = false;
-        n.replace_all();
-        assert_eq!(n.active_doc().content.text().trim_end(), "hi hi hi");
+    fn reopening_with_an_encoding_decodes_the_file_again() {
+        let path = std::env::temp_dir().join(format!("notepad_reopen_{}.txt", std::process::id()));
+        // "café" in UTF-16 LE without a BOM reads as UTF-8 with NUL bytes
+        std::fs::write(&path, [0x63, 0x00, 0x61, 0x00, 0x66, 0x00, 0xE9, 0x00]).unwrap();
+        let mut n = Notepad::test_default();
+        let _ = n.open_dropped_file(path.clone());
+        assert_ne!(n.active_doc().content.text(), "café");
+        let _ = n.update(Message::File(FileMsg::ReopenWithEncoding(encoding_rs::UTF_16LE)));
+        let _ = std::fs::remove_file(&path);
+        let doc = n.active_doc();
+        assert_eq!(doc.content.text(), "café");
+        assert_eq!(doc.encoding, encoding_rs::UTF_16LE);
+        assert!(!doc.is_modified);
     }
 
     #[test]
-    fn replace_all_empty_query_no_change() {
-        let mut n = notepad_with("hello world");
-        n.find_query = String::new();
-        n.replace_query = "hi".to_string();
-        n.replace_all();
+    fn reopening_a_modified_tab_waits_for_the_confirmation() {
+        let path = std::env::temp_dir().join(format!("notepad_reopen_modified_{}.txt", std::process::id()));
+        std::fs::write(&path, [0x63, 0x00, 0x61, 0x00, 0x66, 0x00, 0xE9, 0x00]).unwrap();
+        let mut n = Notepad::test_default();
+        let _ = n.open_dropped_file(path.clone());
+        n.active_doc_mut().is_modified = true;
+        let id = n.active_doc().id;
+        let _ = n.update(Message::File(FileMsg::ReopenWithEncoding(encoding_rs::UTF_16LE)));
+        let _ = n.update(Message::File(FileMsg::ConfirmReopenWithEncoding(false, id, encoding_rs::UTF_16LE)));
+        assert!(n.active_doc().is_modified);
+        let _ = n.update(Message::File(FileMsg::ConfirmReopenWithEncoding(true, id, encoding_rs::UTF_16LE)));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(n.active_doc().content.text(), "café");
         assert!(!n.active_doc().is_modified);
     }
 
     #[test]
-    fn replace_all_no_match() {
-        let mut n = notepad_with("hello world");
-        n.find_query = "xyz".to_string();
-        n.replace_query = "hi".to_string();
-        n.case_sensitive = true;
-        n.replace_all();
-        assert!(!n.active_doc().is_modified);
+    fn submenus_of_the_file_menu_are_exclusive() {
+        let mut n = Notepad::test_default();
+        let _ = n.update(Message::Menu(MenuMsg::Toggle(Menu::File)));
+        let _ = n.update(Message::File(FileMsg::ToggleRecentFiles));
+        let _ = n.update(Message::File(FileMsg::ToggleReopenEncodings));
+        assert!(n.show_reopen_encodings && !n.show_recent_files);
+        assert_eq!(n.active_menu, Some(Menu::File));
     }
 
     // ============================
-    // push_snapshot / undo / redo
+    // Save As encoding
     // ============================
 
     #[test]
-    fn push_snapshot_respects_max_history() {
+    fn save_as_writes_the_chosen_encoding() {
+        let path = std::env::temp_dir().join(format!("notepad_save_as_{}.txt", std::process::id()));
+        let mut n = notepad_with("façade →");
+        let _ = n.update(Message::File(FileMsg::SaveFileSelected(Some(path.clone()))));
+        assert_eq!(n.save_as.as_ref().unwrap().choice, SaveEncoding::Utf8);
+        assert!(n.save_as.as_ref().unwrap().unmappable.is_empty());
+        let _ = n.update(Message::File(FileMsg::SetSaveEncoding(SaveEncoding::Windows1252)));
+        assert_eq!(n.save_as.as_ref().unwrap().unmappable, vec!['→']);
+        let _ = n.update(Message::File(FileMsg::SetSaveEncoding(SaveEncoding::Utf8Bom)));
+        let _ = n.update(Message::File(FileMsg::ConfirmSaveAs));
+        let on_disk = std::fs::read(&path).unwrap();
+        assert!(on_disk.starts_with(&[0xEF, 0xBB, 0xBF]));
+
+        // Opened again, the BOM is remembered for the next save
         let mut n = Notepad::test_default();
-        for i in 0..MAX_UNDO_HISTORY + 10 {
-            n.push_snapshot(TextSnapshot {
-                text: format!("text{i}"),
-                cursor_line: 0,
-                cursor_col: 0,
-            });
-        }
-        assert_eq!(n.active_doc().undo_stack.len(), MAX_UNDO_HISTORY);
+        let _ = n.open_dropped_file(path.clone());
+        let _ = std::fs::remove_file(&path);
+        assert!(n.active_doc().bom);
+        assert_eq!(n.active_doc().content.text(), "façade →");
     }
 
-    #[test]
-    fn undo_restores_previous_text() {
-        let mut n = notepad_with("original");
-        n.save_snapshot();
-        n.active_doc_mut().content = text_editor::Content::with_text("modified");
-        n.undo();
-        assert_eq!(n.active_doc().content.text().trim_end(), "original");
-    }
+    // ============================
+    // Save As line endings
+    // ============================
 
     #[test]
-    fn redo_after_undo() {
-        let mut n = notepad_with("original");
-        n.save_snapshot();
-        n.active_doc_mut().content = text_editor::Content::with_text("modified");
-        n.active_doc_mut().is_modified = true;
-        n.undo();
-        assert_eq!(n.active_doc().content.text().trim_end(), "original");
-        n.redo();
-        assert_eq!(n.active_doc().content.text().trim_end(), "modified");
+    fn save_as_converts_the_chosen_line_endings() {
+        let path = std::env::temp_dir().join(format!("notepad_save_as_eol_{}.txt", std::process::id()));
+        let mut n = notepad_with("un\ndeux\r\ntrois");
+        n.active_doc_mut().line_ending = LineEnding::CrLf;
+        let _ = n.update(Message::File(FileMsg::SaveFileSelected(Some(path.clone()))));
+        assert_eq!(n.save_as.as_ref().unwrap().line_ending, LineEnding::CrLf);
+        let _ = n.update(Message::File(FileMsg::SetSaveLineEnding(LineEnding::Lf)));
+        let _ = n.update(Message::File(FileMsg::ConfirmSaveAs));
+        let on_disk = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(on_disk.unwrap(), "un\ndeux\ntrois");
+        assert_eq!(n.active_doc().line_ending, LineEnding::Lf);
+        // The conversion can be undone
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "un\ndeux\r\ntrois");
     }
 
-    #[test]
-    fn undo_on_empty_stack_is_noop() {
-        let mut n = notepad_with("hello");
-        n.undo();
-        assert_eq!(n.active_doc().content.text().trim_end(), "hello");
-    }
+    // ============================
+    // QR code
+    // ============================
 
     #[test]
-    fn redo_on_empty_stack_is_noop() {
-        let mut n = notepad_with("hello");
-        n.redo();
-        assert_eq!(n.active_doc().content.text().trim_end(), "hello");
+    fn qr_code_of_the_selection_or_the_link() {
+        let mut n = notepad_with("rien ici\nvoir https://example.org/a");
+        let _ = n.update(Message::Edit(EditMsg::ShowQrCode));
+        assert!(n.qr_popup.is_none());
+
+        n.navigate_to(1, 8);
+        let _ = n.update(Message::Edit(EditMsg::ShowQrCode));
+        assert_eq!(n.qr_popup.as_ref().unwrap().text, "https://example.org/a");
+        let _ = n.update(Message::Edit(EditMsg::CloseQrCode));
+
+        let _ = n.update(Message::Edit(EditMsg::SelectAll));
+        let _ = n.update(Message::Edit(EditMsg::ShowQrCode));
+        assert_eq!(n.qr_popup.as_ref().unwrap().text, "rien ici\nvoir https://example.org/a");
     }
 
     // ============================
-    // Tab operations
+    // Recent files
     // ============================
 
     #[test]
-    fn new_tab_adds_document() {
+    fn recent_files_submenu_keeps_the_menu_open() {
         let mut n = Notepad::test_default();
-        assert_eq!(n.tabs.len(), 1);
-        n.tabs.push(Document::default());
-        n.active_tab = n.tabs.len() - 1;
-        assert_eq!(n.tabs.len(), 2);
-        assert_eq!(n.active_tab, 1);
+        let _ = n.update(Message::Menu(MenuMsg::Toggle(Menu::File)));
+        let _ = n.update(Message::File(FileMsg::ToggleRecentFiles));
+        assert!(n.show_recent_files);
+        assert_eq!(n.active_menu, Some(Menu::File));
+        let _ = n.update(Message::Menu(MenuMsg::Hover(Menu::Edit)));
+        assert!(!n.show_recent_files);
     }
 
     #[test]
-    fn close_tab_removes_document() {
+    fn recent_file_already_open_is_shown() {
+        let path = std::env::temp_dir().join(format!("notepad_recent_{}.txt", std::process::id()));
+        std::fs::write(&path, "x").unwrap();
         let mut n = Notepad::test_default();
+        n.active_doc_mut().file_path = Some(path.clone());
         n.tabs.push(Document::default());
-        n.tabs.push(Document::default());
-        assert_eq!(n.tabs.len(), 3);
-        n.remove_tab(1);
+        n.active_tab = 1;
+        let _ = n.update(Message::File(FileMsg::OpenRecent(path.clone())));
+        assert_eq!(n.active_tab, 0);
         assert_eq!(n.tabs.len(), 2);
+        let _ = std::fs::remove_file(&path);
     }
 
+    // ============================
+    // Printing
+    // ============================
+
+    // FileMsg::Print itself is left out: it would reach the real spooler
     #[test]
-    fn close_last_tab_creates_new_empty() {
-        let mut n = Notepad::test_default();
-        n.active_doc_mut().is_modified = false;
-        n.remove_tab(0);
-        assert_eq!(n.tabs.len(), 1);
-        assert_eq!(n.active_tab, 0);
-        assert!(!n.active_doc().is_modified);
+    fn print_failure_shows_error_toast() {
+        let mut n = notepad_with("texte");
+        let failed = Err("lp a échoué".to_string());
+        let _ = n.update(Message::File(FileMsg::Printed("Sans titre".to_string(), failed)));
+        assert!(n.toasts.iter().any(|t| t.severity == Severity::Error && t.message.contains("lp a échoué")));
     }
 
+    // ============================
+    // Definitions
+    // ============================
+
     #[test]
-    fn switch_tab_changes_active() {
-        let mut n = Notepad::test_default();
-        n.tabs.push(Document::default());
-        n.active_tab = 0;
-        n.active_tab = 1;
-        assert_eq!(n.active_tab, 1);
+    fn definition_looks_up_the_word_under_the_cursor() {
+        let mut n = notepad_with("il fait beau");
+        n.navigate_to(0, 5);
+        let _ = n.update(Message::Edit(EditMsg::Define));
+        let popup = n.definition.as_ref().expect("popup");
+        assert_eq!(popup.word, "fait");
+        assert!(popup.result.is_none());
+
+        // The answer to an earlier lookup is dropped
+        let _ = n.update(Message::Edit(EditMsg::Defined("beau".to_string(), Ok(Vec::new()))));
+        assert!(n.definition.as_ref().unwrap().result.is_none());
+        let _ = n.update(Message::Edit(EditMsg::Defined("fait".to_string(), Ok(Vec::new()))));
+        assert_eq!(n.definition.as_ref().unwrap().result, Some(Ok(Vec::new())));
+        let _ = n.update(Message::Edit(EditMsg::CloseDefinition));
+        assert!(n.definition.is_none());
     }
 
+    // ============================
+    // Customizable keybindings
+    // ============================
+
     #[test]
-    fn close_tab_adjusts_active_index() {
+    fn key_press_rebinds_the_waiting_action() {
         let mut n = Notepad::test_default();
-        n.tabs.push(Document::default());
-        n.tabs.push(Document::default());
-        n.active_tab = 2;
-        n.remove_tab(0);
-        assert_eq!(n.active_tab, 1); // shifted down
+        let _ = n.update(Message::Settings(SettingsMsg::Open));
+        let _ = n.update(Message::Settings(SettingsMsg::ToggleKeybindings));
+        let _ = n.update(Message::Settings(SettingsMsg::Rebind(Action::GoTo)));
+        // Modifiers alone keep waiting, a plain letter is refused
+        press(&mut n, Key::Named(Named::Control), Modifiers::CTRL);
+        assert_eq!(n.rebinding, Some(Action::GoTo));
+        press(&mut n, Key::Character("l".into()), Modifiers::empty());
+        assert_eq!(n.rebinding, None);
+        assert_eq!(n.keymap.keys(Action::GoTo), "Ctrl+G");
+
+        let _ = n.update(Message::Settings(SettingsMsg::Rebind(Action::GoTo)));
+        press(&mut n, Key::Character("s".into()), Modifiers::CTRL);
+        assert_eq!(n.keymap.keys(Action::GoTo), "Ctrl+S");
+        assert!(n.toasts.iter().any(|t| t.message.contains("Enregistrer")));
+        // Escape gives up without closing the dialog
+        let _ = n.update(Message::Settings(SettingsMsg::Rebind(Action::GoTo)));
+        press(&mut n, Key::Named(Named::Escape), Modifiers::empty());
+        assert!(n.show_keybindings && n.rebinding.is_none());
+
+        let _ = n.update(Message::Settings(SettingsMsg::ResetAllBindings));
+        assert!(n.keymap.is_default(Action::GoTo));
+        press(&mut n, Key::Named(Named::Escape), Modifiers::empty());
+        assert!(!n.show_keybindings && n.show_settings);
     }
 
     // ============================
-    // reset via remove_tab
+    // Handwritten annotations
     // ============================
 
     #[test]
-    fn remove_tab_resets_when_last() {
-        let mut n = notepad_with("some content");
-        n.active_doc_mut().file_path = Some(PathBuf::from("/tmp/test.txt"));
-        n.active_doc_mut().is_modified = true;
-        n.remove_tab(0);
-        assert!(n.active_doc().file_path.is_none());
+    fn strokes_are_saved_beside_the_file() {
+        let dir = std::env::temp_dir().join(format!("notepad_annotations_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("plan.txt");
+        let mut n = notepad_with("une ligne");
+        n.active_doc_mut().file_path = Some(file.clone());
+        let _ = n.update(Message::Ink(InkMsg::Toggle));
+        assert!(n.show_ink);
+        for msg in [InkMsg::Begin([0.0, 0.5]), InkMsg::Extend([3.0, 0.5]), InkMsg::End] {
+            let _ = n.update(Message::Ink(msg));
+        }
+        let _ = n.update(Message::Ink(InkMsg::Begin([1.0, 1.0])));
+        let _ = n.update(Message::Ink(InkMsg::End));
+        assert_eq!(n.active_doc().ink.len(), 2);
+        assert!(n.active_doc().ink_stroke.is_none());
+        assert_eq!(ink::load(&file), n.active_doc().ink);
+        // Annotations are not edits of the text
         assert!(!n.active_doc().is_modified);
-        assert!(n.active_doc().undo_stack.is_empty());
+
+        let _ = n.update(Message::Ink(InkMsg::UndoStroke));
+        assert_eq!(ink::load(&file).len(), 1);
+        let _ = n.update(Message::Ink(InkMsg::Clear));
+        assert!(n.active_doc().ink.is_empty());
+        assert!(!ink::sidecar_path(&file).exists());
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
     // ============================
-    // decode_bytes / encoding
+    // Abbreviation expansion
     // ============================
 
     #[test]
-    fn decode_utf8_bytes() {
-        let input = "Bonjour le monde".as_bytes();
-        let (text, enc) = Notepad::decode_bytes(input);
-        assert_eq!(text, "Bonjour le monde");
-        assert_eq!(enc, encoding_rs::UTF_8);
+    fn terminator_expands_the_abbreviation_before_it() {
+        let mut n = Notepad::test_default();
+        n.abbreviations = vec![Abbreviation {
+            abbreviation: "addr".to_string(),
+            expansion: "1 rue de la Paix\\n75000 Paris".to_string(),
+            language: None,
+        }];
+        type_text(&mut n, "maddr addr.");
+        assert_eq!(n.active_doc().content.text(), "maddr 1 rue de la Paix\n75000 Paris.");
+        type_text(&mut n, "\naddr\n");
+        assert!(n.active_doc().content.text().ends_with("Paris.\n1 rue de la Paix\n75000 Paris\n"));
+        assert!(n.active_doc().is_modified);
     }
 
+    // ============================
+    // Snippets
+    // ============================
+
     #[test]
-    fn decode_utf8_with_bom() {
-        let mut input = vec![0xEF, 0xBB, 0xBF]; // UTF-8 BOM
-        input.extend_from_slice("Hello".as_bytes());
-        let (text, enc) = Notepad::decode_bytes(&input);
-        assert_eq!(text, "Hello");
-        assert_eq!(enc, encoding_rs::UTF_8);
+    fn tab_expands_the_snippet_and_places_the_caret() {
+        let mut n = notepad_with("    si");
+        n.snippets = vec![Snippet {
+            trigger: "si".to_string(),
+            body: "if $CURSOR {\\n}".to_string(),
+            language: None,
+        }];
+        n.navigate_to(0, 6);
+        let _ = n.update(Message::Edit(EditMsg::InsertTab));
+        assert_eq!(n.active_doc().content.text(), "    if  {\n    }");
+        let position = n.active_doc().content.cursor().position;
+        assert_eq!((position.line, position.column), (0, 7));
+        assert!(n.active_doc().is_modified);
+
+        // Nothing to expand: Tab indents as usual
+        let _ = n.update(Message::Edit(EditMsg::InsertTab));
+        assert_eq!(n.active_doc().content.text(), "    if \t {\n    }");
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "    si");
     }
 
+    // ============================
+    // Spell checking
+    // ============================
+
     #[test]
-    fn decode_latin1_fallback() {
-        // 0xE9 = 'é' in Windows-1252, but invalid in UTF-8
-        let input = vec![0x48, 0x65, 0x6C, 0x6C, 0x6F, 0xE9];
-        let (text, enc) = Notepad::decode_bytes(&input);
-        assert_eq!(text, "Helloé");
-        assert_eq!(enc, encoding_rs::WINDOWS_1252);
+    fn context_menu_offers_replacements_for_the_misspelled_word() {
+        let dir = std::env::temp_dir().join(format!("notepad_spelling_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("fr_TEST.aff"), "SET UTF-8\nSFX S Y 1\nSFX S 0 s .\n").unwrap();
+        std::fs::write(dir.join("fr_TEST.dic"), "2\nune\nmaison/S\n").unwrap();
+        assert_eq!(spelling::available(&dir), ["fr_TEST"]);
+        let speller = spelling::load(&dir, "fr_TEST").unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut n = notepad_with("une masion");
+        n.speller = Some(Arc::new(speller));
+        n.navigate_to(0, 2);
+        let _ = n.update(Message::Menu(MenuMsg::ShowContext));
+        assert!(n.spell_suggestions.is_none());
+        n.navigate_to(0, 7);
+        let _ = n.update(Message::Menu(MenuMsg::ShowContext));
+        let found = n.spell_suggestions.as_ref().unwrap();
+        assert_eq!((found.range.clone(), found.suggestions.clone()), (4..10, vec!["maison".to_string()]));
+        let _ = n.update(Message::Edit(EditMsg::ReplaceMisspelled("maison".to_string())));
+        assert_eq!(n.active_doc().content.text(), "une maison");
+        assert!(n.active_doc().is_modified);
+        assert!(!n.show_context_menu);
     }
 
+    // ============================
+    // Pasted line endings
+    // ============================
+
     #[test]
-    fn decode_utf16le_bom() {
-        let mut input = vec![0xFF, 0xFE]; // UTF-16LE BOM
-        input.extend_from_slice(&[0x48, 0x00, 0x69, 0x00]); // "Hi" in UTF-16LE
-        let (text, enc) = Notepad::decode_bytes(&input);
-        assert_eq!(text, "Hi");
-        assert_eq!(enc, encoding_rs::UTF_16LE);
+    fn pasted_line_endings_follow_the_document() {
+        let mut n = notepad_with("a\r\nb\r\n");
+        n.active_doc_mut().line_ending = LineEnding::CrLf;
+        n.navigate_to(1, 1);
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(text_editor::Edit::Paste(
+            Arc::new("\nx\ny\rz".to_string()),
+        ))));
+        assert_eq!(n.active_doc().content.text(), "a\r\nb\r\nx\r\ny\r\nz\r\n");
     }
 
+    // ============================
+    // Templates
+    // ============================
+
     #[test]
-    fn push_snapshot_respects_adaptive_max_undo() {
+    fn template_variables_fill_a_new_tab() {
         let mut n = Notepad::test_default();
-        n.active_doc_mut().max_undo = LARGE_FILE_UNDO_HISTORY;
-        for i in 0..LARGE_FILE_UNDO_HISTORY + 10 {
-            n.push_snapshot(TextSnapshot {
-                text: format!("text{i}"),
-                cursor_line: 0,
-                cursor_col: 0,
-            });
-        }
-        assert_eq!(n.active_doc().undo_stack.len(), LARGE_FILE_UNDO_HISTORY);
+        let text = "# {{title}}\n\nLe {{date}}, {{title}}.\n";
+        n.template_manager = Some(TemplateManager {
+            name: String::new(),
+            templates: vec!["Compte rendu.md".to_string()],
+            filling: Some(TemplateFill {
+                file_name: "Compte rendu.md".to_string(),
+                text: text.to_string(),
+                values: vec![("title".to_string(), String::new()), ("date".to_string(), "17/10/2026".to_string())],
+            }),
+        });
+        let _ = n.update(Message::Template(TemplateMsg::ValueChanged(0, "Réunion".to_string())));
+        let _ = n.update(Message::Template(TemplateMsg::Create));
+        assert!(n.template_manager.is_none());
+        assert_eq!(n.tabs.len(), 2);
+        let doc = n.active_doc();
+        assert_eq!(doc.content.text(), "# Réunion\n\nLe 17/10/2026, Réunion.\n");
+        assert_eq!(doc.language(), Language::Markdown);
+        assert!(doc.is_modified && doc.file_path.is_none());
     }
 
+    // ============================
+    // Character picker
+    // ============================
+
     #[test]
-    fn default_document_encoding_is_utf8() {
-        let doc = Document::default();
-        assert_eq!(doc.encoding, encoding_rs::UTF_8);
-        assert_eq!(doc.max_undo, MAX_UNDO_HISTORY);
+    fn character_picker_inserts_at_the_caret_and_remembers() {
+        let mut n = notepad_with("prix : 12");
+        n.recent_characters = "→".to_string();
+        n.navigate_to(0, 9);
+        let _ = n.update(Message::Edit(EditMsg::ShowCharPicker));
+        let _ = n.update(Message::Edit(EditMsg::CharQueryChanged("euro".to_string())));
+        let block = n.char_picker.as_ref().unwrap().block;
+        assert_eq!(crate::charmap::BLOCKS[block].name, "Symboles monétaires");
+        let _ = n.update(Message::Edit(EditMsg::InsertChar('€')));
+        assert_eq!(n.active_doc().content.text(), "prix : 12€");
+        assert_eq!(n.recent_characters, "€→");
+        assert!(n.char_picker.is_none());
+        let _ = n.update(Message::Edit(EditMsg::Undo));
+        assert_eq!(n.active_doc().content.text(), "prix : 12");
+    }
+
+    // ============================
+    // Date format
+    // ============================
+
+    #[test]
+    fn date_format_presets_and_custom_format_for_f5() {
+        let mut n = notepad_with("");
+        let _ = n.update(Message::Menu(MenuMsg::Toggle(Menu::Edit)));
+        let _ = n.update(Message::Edit(EditMsg::ToggleDateFormats));
+        assert!(n.show_date_formats && n.active_menu == Some(Menu::Edit));
+        let _ = n.update(Message::Edit(EditMsg::ChooseDateFormat(datetime::PRESETS[1].1)));
+        assert_eq!(n.date_format, "%Y-%m-%dT%H:%M:%S");
+        assert!(!n.show_date_formats && n.active_menu.is_none());
+
+        let _ = n.update(Message::Settings(SettingsMsg::DateFormatChanged("[%Y] %%".to_string())));
+        let _ = n.update(Message::Edit(EditMsg::InsertDateTime));
+        assert_eq!(n.active_doc().content.text(), format!("[{}] %", LocalTime::now().year));
+    }
+
+    // ============================
+    // URL paste
+    // ============================
+
+    #[test]
+    fn pasted_url_links_the_selection_and_takes_the_page_title() {
+        let paste = |n: &mut Notepad, text: &str| {
+            let _ = n.update(Message::EditorAction(text_editor::Action::Edit(text_editor::Edit::Paste(
+                Arc::new(text.to_string()),
+            ))));
+        };
+        let mut n = markdown_notepad("Voir la doc.\n");
+        n.active_doc_mut().select_bytes(5, 11);
+        paste(&mut n, "https://docs.rs");
+        assert_eq!(n.active_doc().content.text(), "Voir [la doc](https://docs.rs).\n");
+
+        // The title comes back once more was typed after the URL
+        n.url_paste = UrlPaste::Title;
+        n.navigate_to(1, 0);
+        paste(&mut n, "https://www.rust-lang.org");
+        let _ = n.update(Message::EditorAction(text_editor::Action::Edit(text_editor::Edit::Insert('!'))));
+        let doc_id = n.active_doc().id;
+        let start = "Voir [la doc](https://docs.rs).\n".len();
+        let _ = n.update(Message::Edit(EditMsg::LinkTitleFetched(
+            doc_id,
+            start,
+            "https://www.rust-lang.org".to_string(),
+            Ok("Rust".to_string()),
+        )));
+        assert_eq!(
+            n.active_doc().content.text(),
+            "Voir [la doc](https://docs.rs).\n[Rust](https://www.rust-lang.org)!"
+        );
+        assert_eq!(n.active_doc().content.cursor().position.column, "[Rust](https://www.rust-lang.org)!".len());
+
+        // Off: pasted as it is
+        n.url_paste = UrlPaste::Off;
+        n.active_doc_mut().select_bytes(1, 4);
+        paste(&mut n, "https://example.org");
+        assert!(n.active_doc().content.text().starts_with("Vhttps://example.org [la doc]"));
     }
 }
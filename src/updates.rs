@@ -0,0 +1,121 @@
+use crate::http::{self, ProxySettings};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/Olivier6431/notepad/releases/latest";
+
+// Latest published version and its download page
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum UpdateCheck {
+    #[default]
+    Idle,
+    Checking,
+    UpToDate,
+    Available(Release),
+    Failed(String),
+}
+
+impl UpdateCheck {
+    pub fn from_result(result: Result<Release, String>) -> Self {
+        match result {
+            Ok(release) if is_newer(&release.version, env!("CARGO_PKG_VERSION")) => {
+                Self::Available(release)
+            }
+            Ok(_) => Self::UpToDate,
+            Err(e) => Self::Failed(e),
+        }
+    }
+}
+
+// Blocking: run it off the UI thread
+pub fn latest_release(proxy: &ProxySettings) -> Result<Release, String> {
+    let response = http::agent(proxy)?
+        .get(RELEASES_URL)
+        .header("User-Agent", http::USER_AGENT)
+        .header("Accept", "application/vnd.github+json")
+        .call();
+    let mut response = match response {
+        Ok(response) => response,
+        Err(ureq::Error::StatusCode(404)) => return Err("aucune version publiée".to_string()),
+        Err(e) => return Err(e.to_string()),
+    };
+    let json = response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+    parse_release(&json)
+}
+
+fn parse_release(json: &str) -> Result<Release, String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let field = |name: &str| {
+        value[name]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("réponse sans « {name} »"))
+    };
+    Ok(Release {
+        version: field("tag_name")?.trim_start_matches(['v', 'V']).to_string(),
+        url: field("html_url")?,
+    })
+}
+
+// Compares dotted version numbers, ignoring pre-release suffixes
+fn is_newer(candidate: &str, current: &str) -> bool {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .trim_start_matches(['v', 'V'])
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|n| n.parse().unwrap_or(0))
+            .collect()
+    };
+    let (mut a, mut b) = (parts(candidate), parts(current));
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a > b
+}
+
+// Opens a web page in the default browser
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    let mut command = if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    command.arg(url).spawn().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert!(is_newer("0.10.0", "0.9.3"));
+        assert!(is_newer("v1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta", "0.1.0"));
+        assert!(!is_newer("0.0.9", "0.1.0"));
+    }
+
+    #[test]
+    fn release_is_read_from_github_json() {
+        let json = r#"{"tag_name": "v0.2.0", "html_url": "https://github.com/Olivier6431/notepad/releases/tag/v0.2.0", "assets": []}"#;
+        let release = parse_release(json).unwrap();
+        assert_eq!(release.version, "0.2.0");
+        assert!(release.url.ends_with("/v0.2.0"));
+        assert!(parse_release(r#"{"message": "Not Found"}"#).is_err());
+    }
+}
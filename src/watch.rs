@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{Stream, StreamExt};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+// --- Open files changed on disk by other programs ---
+
+// Folders holding the open files. The folders are watched rather than the
+// files so a save that replaces the file (a temporary file renamed over it)
+// is still seen.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Folders(pub Vec<PathBuf>);
+
+impl Folders {
+    pub fn of<'a>(files: impl Iterator<Item = &'a Path>) -> Self {
+        let mut folders: Vec<PathBuf> = files
+            .filter_map(|f| f.parent())
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .collect();
+        folders.sort();
+        folders.dedup();
+        Self(folders)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// Paths created, written or removed in the folders. The watcher lives as long
+// as the stream: the subscription ending stops it.
+pub fn changes(folders: &Folders) -> impl Stream<Item = PathBuf> {
+    let (sender, receiver) = mpsc::unbounded();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else { return };
+        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+            for path in event.paths {
+                let _ = sender.unbounded_send(path);
+            }
+        }
+    });
+    let watcher = match watcher {
+        Ok(mut watcher) => {
+            for folder in &folders.0 {
+                if let Err(e) = watcher.watch(folder, RecursiveMode::NonRecursive) {
+                    tracing::warn!("Surveillance impossible de {} : {e}", folder.display());
+                }
+            }
+            Some(watcher)
+        }
+        Err(e) => {
+            tracing::warn!("Surveillance des fichiers indisponible : {e}");
+            None
+        }
+    };
+    receiver.map(move |path| {
+        let _ = &watcher;
+        path
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_are_listed_once() {
+        let files = [
+            PathBuf::from("/b/x.txt"),
+            PathBuf::from("/a/y.txt"),
+            PathBuf::from("/b/z.txt"),
+            PathBuf::from("relatif.txt"),
+        ];
+        let folders = Folders::of(files.iter().map(PathBuf::as_path));
+        assert_eq!(folders, Folders(vec![PathBuf::from("/a"), PathBuf::from("/b")]));
+    }
+}
@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+
+use crate::completion::PathEntry;
+use crate::markdown;
+use crate::paths;
+
+// --- [[Note Name]] links between Markdown notes ---
+
+const MAX_NOTE_COMPLETIONS: usize = 50;
+
+// Name typed after an unclosed "[[" right before the cursor
+pub fn open_link(before_cursor: &str) -> Option<&str> {
+    let start = before_cursor.rfind("[[")? + 2;
+    let name = &before_cursor[start..];
+    (!name.contains([']', '[', '|', '#'])).then_some(name)
+}
+
+// Target of the [[link]] containing the byte `column` of `line`;
+// "[[Note|label]]" and "[[Note#Section]]" both point to "Note"
+pub fn link_at(line: &str, column: usize) -> Option<&str> {
+    let mut from = 0;
+    while let Some(open) = line[from..].find("[[") {
+        let start = from + open;
+        let close = start + 2 + line[start + 2..].find("]]")?;
+        if (start..close + 2).contains(&column) {
+            let inner = &line[start + 2..close];
+            let target = inner.split(['|', '#']).next().unwrap_or_default().trim();
+            return (!target.is_empty()).then_some(target);
+        }
+        from = close + 2;
+    }
+    None
+}
+
+// Markdown files of the notes folder, by name without extension
+fn notes_in(folder: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(read) = std::fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    read.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && markdown::is_markdown(Some(p)))
+        .filter_map(|p| Some((p.file_stem()?.to_str()?.to_string(), p)))
+        .collect()
+}
+
+// Note names starting with `prefix`, sorted
+pub fn complete(folder: &Path, prefix: &str) -> Vec<PathEntry> {
+    let prefix = prefix.to_lowercase();
+    let mut names: Vec<String> = notes_in(folder)
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| name.to_lowercase().starts_with(&prefix))
+        .collect();
+    names.sort_by_key(|name| name.to_lowercase());
+    names.truncate(MAX_NOTE_COMPLETIONS);
+    names
+        .into_iter()
+        .map(|name| PathEntry { name, is_dir: false })
+        .collect()
+}
+
+// Existing note named `target` (case-insensitive), else the .md file to create;
+// "[[~/todo]]" or "[[$NOTES/todo.md]]" point outside the notes folder
+pub fn resolve(folder: &Path, target: &str) -> PathBuf {
+    if target.starts_with(['~', '$', '%']) {
+        let path = paths::expand(target);
+        if path.is_absolute() {
+            return if path.extension().is_some() { path } else { path.with_extension("md") };
+        }
+    }
+    notes_in(folder)
+        .into_iter()
+        .find(|(name, _)| name.to_lowercase() == target.to_lowercase())
+        .map(|(_, path)| path)
+        .unwrap_or_else(|| folder.join(format!("{}.md", file_name(target))))
+}
+
+fn file_name(target: &str) -> String {
+    target
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_link_being_typed() {
+        assert_eq!(open_link("voir [[Proj"), Some("Proj"));
+        assert_eq!(open_link("voir [["), Some(""));
+        assert_eq!(open_link("voir [[Projet]] et"), None);
+        assert_eq!(open_link("voir [[Projet|lab"), None);
+        assert_eq!(open_link("voir [Projet"), None);
+    }
+
+    #[test]
+    fn finds_the_link_under_the_cursor() {
+        let line = "voir [[Projet A]] et [[Idées|mes idées]] ou [[Plan#Étape 2]]";
+        assert_eq!(link_at(line, 8), Some("Projet A"));
+        assert_eq!(link_at(line, 5), Some("Projet A"));
+        assert_eq!(link_at(line, 2), None);
+        assert_eq!(link_at(line, 30), Some("Idées"));
+        assert_eq!(link_at(line, line.len() - 3), Some("Plan"));
+        assert_eq!(link_at("[[]] [[ouvert", 8), None);
+    }
+
+    #[test]
+    fn resolves_and_completes_note_names() {
+        let root = std::env::temp_dir().join(format!("notepad_wiki_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("Projet A.md"), "").unwrap();
+        std::fs::write(root.join("projet b.markdown"), "").unwrap();
+        std::fs::write(root.join("Plan.txt"), "").unwrap();
+
+        let names: Vec<String> = complete(&root, "pro").into_iter().map(|e| e.name).collect();
+        assert_eq!(names, ["Projet A", "projet b"]);
+        assert!(complete(&root, "plan").is_empty());
+        assert_eq!(resolve(&root, "projet a"), root.join("Projet A.md"));
+        assert_eq!(resolve(&root, "Nouvelle/idée"), root.join("Nouvelle_idée.md"));
+        let home = paths::expand("~");
+        assert_eq!(resolve(&root, "~/todo"), home.join("todo.md"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}